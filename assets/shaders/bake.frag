@@ -0,0 +1,33 @@
+#version 450
+
+// EXAMPLE MATERIAL OVER THE BAKED SIMULATION DATA: DARKENS THE CLOTH WHERE IT IS STRAINED AND TINTS
+// IT WHERE THE WEIGHT MAP IS PAINTED
+// CHANNELS: R STRAIN, G CONTACT, B SPEED, A WEIGHT
+
+layout(location = 0) in vec3 v_Normal;
+layout(location = 1) in vec2 v_Uv;
+
+layout(location = 0) out vec4 o_Target;
+
+layout(set = 1, binding = 1) uniform BakeMaterial_albedo {
+    vec4 albedo;
+};
+layout(set = 1, binding = 2) uniform texture2D BakeMaterial_data;
+layout(set = 1, binding = 3) uniform sampler BakeMaterial_data_sampler;
+
+// HOW DARK A FULLY STRAINED TEXEL GETS, AND THE FIXED LIGHT THE CLOTH IS SHADED WITH
+const float DARKEN = 0.8;
+const vec3 LIGHT = vec3(0.3, 0.8, 0.5);
+// COLOR OF A FULLY WEIGHTED TEXEL
+const vec3 WEIGHT_TINT = vec3(0.2, 0.4, 1.0);
+
+void main() {
+    // THE UVS RUN FROM THE FIRST TO THE LAST PARTICLE, THE TEXELS ARE CENTERED ON THEM
+    vec2 size = vec2(textureSize(sampler2D(BakeMaterial_data, BakeMaterial_data_sampler), 0));
+    vec2 uv = (v_Uv * (size - 1.0) + 0.5) / size;
+    vec4 data = texture(sampler2D(BakeMaterial_data, BakeMaterial_data_sampler), uv);
+
+    float light = 0.3 + 0.7 * max(dot(normalize(v_Normal), normalize(LIGHT)), 0.0);
+    vec3 color = mix(albedo.rgb, WEIGHT_TINT, data.a);
+    o_Target = vec4(color * light * (1.0 - DARKEN * data.r), albedo.a);
+}
@@ -0,0 +1,107 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use nalgebra::{zero, Point3, Vector3};
+
+/* THE SIMULATION LIVES IN A BINARY CRATE, SO THE MODULES UNDER TEST ARE PULLED IN BY PATH */
+#[allow(dead_code)]
+#[path = "../src/grid.rs"]
+mod grid;
+#[allow(dead_code)]
+#[path = "../src/particles.rs"]
+mod particles;
+
+use particles::{Integrator, Particles};
+
+type I = usize;
+#[cfg(not(feature = "f64"))]
+type F = f32;
+#[cfg(feature = "f64")]
+type F = f64;
+type P = Point3<F>;
+type V = Vector3<F>;
+
+/* ONE INTEGRATION PASS OVER A SIZE x SIZE GRID */
+const SIZE: I = 100;
+const DAMPING: F = 0.99;
+const DT: F = 0.1;
+
+/* THE ARRAY-OF-STRUCTURES LAYOUT THE PARTICLES USED BEFORE, AS THE BASELINE */
+#[derive(Clone, Copy)]
+struct Particle {
+    p: P,
+    old_p: P,
+    a: V,
+    m: F,
+    fixed: bool,
+}
+
+impl Particle {
+    fn add_force(&mut self, f: V) {
+        self.a += f / self.m;
+    }
+
+    fn step(&mut self, damping: F, dt: F) {
+        if !self.fixed {
+            let tmp = self.p;
+            self.p += damping * (self.p - self.old_p) + self.a * dt * dt;
+            self.old_p = tmp;
+            self.a = zero();
+        }
+    }
+}
+
+/* THE SAME STATE IN BOTH LAYOUTS: A WAVY SHEET, MOVING, PUSHED AROUND, TOP ROW PINNED */
+fn setup() -> (Vec<Particle>, Particles) {
+    let position = |(x, y): (I, I)| P::new(x as F * 0.1, (x as F * 0.3).sin(), y as F * 0.1);
+    let velocity = |i: I| V::new((i as F * 0.7).cos(), 0.01, (i as F * 0.2).sin()) * 0.05;
+    let force = |i: I| V::new(0., -9.8, (i % 7) as F * 0.1);
+
+    let mut aos: Vec<Particle> = (0..SIZE * SIZE)
+        .map(|i| {
+            let p = position((i % SIZE, i / SIZE));
+            Particle { p, old_p: p, a: zero(), m: 1., fixed: i < SIZE }
+        })
+        .collect();
+    let mut soa = Particles::from_fn(SIZE, SIZE, position);
+
+    for (i, particle) in aos.iter_mut().enumerate() {
+        particle.p += velocity(i);
+        particle.add_force(force(i));
+    }
+    for i in 0..SIZE * SIZE {
+        soa.set_fixed(i, i < SIZE);
+        soa.set_position(i, soa.data[i] + velocity(i));
+        soa.add_force(i, force(i));
+    }
+    (aos, soa)
+}
+
+/* BOTH LAYOUTS MUST PRODUCE BIT-FOR-BIT IDENTICAL POSITIONS OVER SEVERAL STEPS */
+fn check_identical() {
+    let (mut aos, mut soa) = setup();
+    for _ in 0..10 {
+        aos.iter_mut().for_each(|p| p.step(DAMPING, DT));
+        soa.integrate(Integrator::Verlet, DAMPING, DT);
+        for (i, particle) in aos.iter().enumerate() {
+            let bits = |p: &P| p.coords.map(|c| c.to_bits());
+            assert_eq!(bits(&particle.p), bits(&soa.data[i]), "particle {} diverged", i);
+            assert_eq!(bits(&particle.old_p), bits(&soa.old_position(i)), "particle {} diverged", i);
+        }
+    }
+}
+
+fn integrate(c: &mut Criterion) {
+    check_identical();
+
+    let (mut aos, mut soa) = setup();
+    let mut group = c.benchmark_group("integrate 100x100");
+    group.bench_function("array of structures", |b| {
+        b.iter(|| aos.iter_mut().for_each(|p| p.step(black_box(DAMPING), black_box(DT))))
+    });
+    group.bench_function("structure of arrays", |b| {
+        b.iter(|| soa.integrate(Integrator::Verlet, black_box(DAMPING), black_box(DT)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, integrate);
+criterion_main!(benches);
@@ -0,0 +1,226 @@
+#![allow(dead_code)]
+
+use bevy::{prelude::*, render::pipeline::PrimitiveTopology};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use nalgebra::{
+    geometry::{Isometry3, Point3, Quaternion, Translation3, UnitQuaternion},
+    Vector3,
+};
+
+/* THE SIMULATION LIVES IN A BINARY CRATE, SO cloth.rs AND THE MODULES IT USES ARE PULLED IN BY PATH */
+/* THIS ROOT STANDS IN FOR main.rs, WITH THE TYPES AND CONVERSIONS THEY IMPORT FROM IT */
+#[path = "../src/bindings.rs"]
+mod bindings;
+#[path = "../src/bvh.rs"]
+mod bvh;
+#[path = "../src/camera.rs"]
+mod camera;
+#[path = "../src/cloth.rs"]
+mod cloth;
+#[path = "../src/collider.rs"]
+mod collider;
+#[path = "../src/config.rs"]
+mod config;
+#[path = "../src/damping.rs"]
+mod damping;
+#[path = "../src/debug.rs"]
+mod debug;
+#[path = "../src/error.rs"]
+mod error;
+#[path = "../src/event.rs"]
+mod event;
+#[path = "../src/fabric.rs"]
+mod fabric;
+#[path = "../src/field.rs"]
+mod field;
+#[cfg(feature = "gpu")]
+#[path = "../src/gpu.rs"]
+mod gpu;
+#[path = "../src/grid.rs"]
+mod grid;
+#[path = "../src/hierarchy.rs"]
+mod hierarchy;
+#[path = "../src/lod.rs"]
+mod lod;
+#[path = "../src/markers.rs"]
+mod markers;
+#[path = "../src/metrics.rs"]
+mod metrics;
+#[path = "../src/noise.rs"]
+mod noise;
+#[path = "../src/particles.rs"]
+mod particles;
+#[path = "../src/pick.rs"]
+mod pick;
+#[path = "../src/pillow.rs"]
+mod pillow;
+#[path = "../src/render.rs"]
+mod render;
+#[path = "../src/slots.rs"]
+mod slots;
+#[path = "../src/spatial.rs"]
+mod spatial;
+#[path = "../src/stats.rs"]
+mod stats;
+#[path = "../src/volume.rs"]
+mod volume;
+#[path = "../src/weld.rs"]
+mod weld;
+
+use cloth::ClothSim;
+use config::{ClothConfig, LocalBoost, PinPreset, Pose, SelfCollision, SolverMode, COARSE_ITERATIONS};
+
+type I = usize;
+#[cfg(not(feature = "f64"))]
+type F = f32;
+#[cfg(feature = "f64")]
+type F = f64;
+type P = Point3<F>;
+type V = Vector3<F>;
+
+fn to_array(p: P) -> [f32; 3] {
+    [p.x as f32, p.y as f32, p.z as f32]
+}
+
+fn to_vec3(v: V) -> Vec3 {
+    Vec3::new(v.x as f32, v.y as f32, v.z as f32)
+}
+
+fn to_v(v: Vec3) -> V {
+    V::new(v.x() as F, v.y() as F, v.z() as F)
+}
+
+fn to_isometry(translation: Vec3, rotation: Quat) -> Isometry3<F> {
+    let q = Vec4::from(rotation);
+    let rotation = UnitQuaternion::from_quaternion(Quaternion::new(q.w() as F, q.x() as F, q.y() as F, q.z() as F));
+    Isometry3::from_parts(Translation3::from(to_v(translation)), rotation)
+}
+
+/* ONE STEP OF THE DEFAULT CLOTH AT SIZE x SIZE, AFTER WARM_UP STEPS SO THE SCRATCH BUFFERS HAVE GROWN */
+const SIZE: I = 40;
+const WARM_UP: I = 20;
+
+fn step(c: &mut Criterion) {
+    let mut cloth = ClothSim::new(4., 4., (SIZE, SIZE), ClothConfig::default()).expect("40x40 is a valid cloth");
+    (0..WARM_UP).for_each(|_| cloth.step(&[]));
+    c.bench_function("step 40x40", |b| b.iter(|| cloth.step(black_box(&[]))));
+}
+
+/* THE SELF-COLLISION MODES ON A SELF_COLLISION_SIZE CLOTH, WHICH THE TRIANGLE MODE HAS TO STEP AT */
+/* INTERACTIVE RATES, UNDER ABOUT 16 MS */
+const SELF_COLLISION_SIZE: I = 30;
+
+fn self_collision(c: &mut Criterion) {
+    let mut group = c.benchmark_group("self-collision 30x30");
+    for &(name, mode) in &[("points", SelfCollision::Points), ("triangles", SelfCollision::Triangles)] {
+        let config = ClothConfig { self_collision: mode, ..ClothConfig::default() };
+        let subdivisions = (SELF_COLLISION_SIZE, SELF_COLLISION_SIZE);
+        let mut cloth = ClothSim::new(3., 3., subdivisions, config).expect("30x30 is a valid cloth");
+        (0..WARM_UP).for_each(|_| cloth.step(&[]));
+        group.bench_function(name, |b| b.iter(|| cloth.step(black_box(&[]))));
+    }
+    group.finish();
+}
+
+/* AN 80x80 CLOTH HUNG BY ITS TOP CORNERS, WHICH THE HIERARCHICAL SOLVER BRINGS WITHIN HIERARCHY_STRAIN */
+/* OVER HIERARCHY_STEPS STEPS WITH FEWER FINE ITERATIONS PER STEP THAN THE FLAT ONE. THE FEWEST OF EACH */
+/* ARE FOUND BY DOUBLING AND PRINTED, THEN A STEP AT THAT COUNT IS TIMED, THE COARSE PASSES INCLUDED */
+const HIERARCHY_SIZE: I = 80;
+const HIERARCHY_EXTENT: F = 8.;
+const HIERARCHY_STEPS: I = 100;
+const HIERARCHY_STRAIN: F = 1.05;
+
+fn hierarchy(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hierarchy 80x80");
+    group.sample_size(10);
+    let max = ClothConfig::default().max_iterations;
+    for &hierarchical in &[false, true] {
+        /* EVERY STEP RUNS ALL ITS ITERATIONS SO THE COUNTS COMPARE */
+        let hung = |budget| {
+            let solver = match hierarchical {
+                true => SolverMode::Hierarchical { coarse_iters: COARSE_ITERATIONS, fine_iters: budget },
+                false => SolverMode::GaussSeidel,
+            };
+            let config = ClothConfig {
+                pose: Pose::HangingVertical,
+                pin_preset: PinPreset::TopCorners,
+                solver,
+                min_iterations: budget,
+                max_iterations: budget,
+                ..ClothConfig::default()
+            };
+            let subdivisions = (HIERARCHY_SIZE, HIERARCHY_SIZE);
+            ClothSim::new(HIERARCHY_EXTENT, HIERARCHY_EXTENT, subdivisions, config).expect("80x80 is a valid cloth")
+        };
+        let mut budget = 1;
+        let mut cloth = loop {
+            let mut cloth = hung(budget);
+            (0..HIERARCHY_STEPS).for_each(|_| cloth.step(&[]));
+            if cloth.max_strain() <= HIERARCHY_STRAIN || budget >= max {
+                break cloth;
+            }
+            budget = (budget * 2).min(max);
+        };
+        let name = if hierarchical { "hierarchical" } else { "flat" };
+        println!("{}: {} fine iterations per step, max strain {:.4}", name, budget, cloth.max_strain());
+        group.bench_function(name, |b| b.iter(|| cloth.step(black_box(&[]))));
+    }
+    group.finish();
+}
+
+/* ONE TOP CORNER OF A CLOTH HANGING BY BOTH DRAGGED OUT AND BACK: AT DRAG_ITERATIONS GLOBAL ITERATIONS, */
+/* THE SAME WITH DRAG_BOOST, AND WITH AS MANY GLOBAL ITERATIONS AS THE BOOST ADDS. THE MEAN MAX STRAIN AND */
+/* CONSTRAINTS BOOSTED PER STEP OF A CYCLE ARE PRINTED, THEN A WHOLE CYCLE IS TIMED */
+const DRAG_ITERATIONS: I = 3;
+const DRAG_BOOST: LocalBoost = LocalBoost { radius: 3, iterations: 12 };
+// HOW FAR AND OVER HOW MANY STEPS THE CORNER IS PULLED OUT AND BACK
+const DRAG_REACH: F = 4.;
+const DRAG_PERIOD: I = 60;
+
+/* ONE CYCLE OF THE DRAG FROM anchor, RETURNING THE MEAN MAX STRAIN AND CONSTRAINTS BOOSTED PER STEP */
+fn drag_cycle(cloth: &mut ClothSim, anchor: P) -> (F, I) {
+    let away = V::new(-1., 0., 1.).normalize();
+    let (mut strain, mut boosted) = (0., 0);
+    for step in 0..DRAG_PERIOD {
+        let phase = 2. * std::f64::consts::PI as F * step as F / DRAG_PERIOD as F;
+        let target = cloth.to_local(anchor + away * (DRAG_REACH * (0.5 - 0.5 * phase.cos())));
+        cloth.set_position((0, 0), target).expect("the corner is on the cloth");
+        cloth.step(&[]);
+        strain += cloth.max_strain();
+        boosted += cloth.stats.boosted;
+    }
+    (strain / DRAG_PERIOD as F, boosted / DRAG_PERIOD)
+}
+
+fn drag(c: &mut Criterion) {
+    let mut group = c.benchmark_group("drag 30x30");
+    group.sample_size(10);
+    let runs = [
+        ("global", DRAG_ITERATIONS, None),
+        ("boosted", DRAG_ITERATIONS, Some(DRAG_BOOST)),
+        ("raised", DRAG_ITERATIONS + DRAG_BOOST.iterations, None),
+    ];
+    for &(name, iterations, local_boost) in &runs {
+        /* EVERY STEP RUNS ALL ITS ITERATIONS SO THE TIMES COMPARE */
+        let config = ClothConfig {
+            pose: Pose::HangingVertical,
+            pin_preset: PinPreset::TopCorners,
+            solver: SolverMode::GaussSeidel,
+            min_iterations: iterations,
+            max_iterations: iterations,
+            kind_iterations: None,
+            local_boost,
+            ..ClothConfig::default()
+        };
+        let ((width, height), n) = (cloth::SIZE, cloth::SUBDIVISIONS);
+        let mut cloth = ClothSim::new(width, height, (n, n), config).expect("30x30 is a valid cloth");
+        let anchor = cloth.world_position((0, 0));
+        let (strain, boosted) = drag_cycle(&mut cloth, anchor);
+        println!("{}: {} iterations, mean max strain {:.4}, {} boosted per step", name, iterations, strain, boosted);
+        group.bench_function(name, |b| b.iter(|| drag_cycle(&mut cloth, anchor)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, step, self_collision, hierarchy, drag);
+criterion_main!(benches);
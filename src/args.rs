@@ -0,0 +1,472 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    cloth,
+    config::{
+        AutoDamping, ClothConfig, KillMode, PinPreset, Plasticity, Pose, Pressure, SelfCollision, SolverMode,
+        SpringDamping, PILLOW_GAP,
+    },
+    fabric::FabricPreset,
+    field::FieldExample,
+    grid::GridIdx,
+    headless, material, pillow, probe, props,
+    skin::SkinOptions,
+    strain, F, I,
+};
+
+/* HAND-ROLLED COMMAND-LINE PARSING INTO THE SCENE AND SIMULATION PARAMETERS */
+
+pub const USAGE: &str = "\
+usage: cloth_sim [options]
+
+  --size <W>x<H>              size of the cloth (default 10x12)
+  --subdivisions <N>[x<M>]    particles along each side (default 30)
+  --rotate <DEG>              roll the main cloth entity about the viewing axis, it still hangs under world gravity
+  --wet-hem                   raise the air drag of the bottom three rows of the main cloth
+  --relax <N>                 solve the constraints of the main cloth for N steps before the first frame
+  --settle <N>                let the main cloth fall onto the scene for N steps before the first frame
+  --iterations <N>            maximum constraint iterations per step
+  --dt <DT>                   time step (default 0.05)
+  --max-dt <DT>               clamp on the time step actually taken (default 0.1)
+  --time-scale <S>            step the cloths on the fraction S of the frames, 0..1 (default 1)
+  --solver <gauss-seidel|jacobi|hierarchical[:<COARSE>x<FINE>]>
+  --integrator <verlet|euler>
+  --relaxation <W>            over-relaxation of the jacobi solver (default 1.5)
+  --over-relaxation <W>       sor factor of the gauss-seidel corrections, stable up to about 1.8 (default 1)
+  --kind-iterations <S>,<H>,<F>
+                              gauss-seidel sweeps of the structural, shear and flexion springs per step
+  --local-boost <K>:<N>       N extra sweeps of the springs within K springs of grabbed, newly colliding
+                              or strongly strained particles
+  --solve-order <as-generated|alternate|shuffled>
+  --seed <N>                  seed for the initial jitter and the wind
+  --pose <curtain|tablecloth|slanted|pillow>
+  --self-collision <points|triangles|off>
+  --no-self-collision         same as --self-collision off
+  --edge-collision <PAIRS>    also keep structural edges apart in folds, testing at most PAIRS pairs per step
+  --spring-damping <K>        spring damping fraction for all constraint kinds
+  --auto-damping              lower the damping while the cloth keeps swinging with only gravity on it,
+                              and raise it back once it has settled
+  --area-stiffness <K>        enable triangle area constraints with stiffness 0..1
+  --warm-start <K>            pre-apply the fraction K of the last step's spring corrections
+  --friction <MU>             coulomb friction of all contacts, 0 for none (default 0.5)
+  --porosity <P>              fraction 0..1 of the wind passing through the cloth (default 0)
+  --air-drag <K>              drag of the air flowing along the cloth (default 0)
+  --wind-occlusion <A>:<R>:<N>
+                              every N steps cast a ray R thicknesses upwind from each face, cutting the
+                              wind on faces sheltered by another part of the cloth to A of it
+  --normals <area|angle>      weighting of the face normals smoothed into the vertex normals (default area)
+  --diagonal <uniform|alternating|shortest>
+                              diagonal splitting each grid quad into two triangles (default uniform)
+  --max-speed <E>             clamp each step's move of a particle to E rest edge lengths (default 2)
+  --max-step-correction <E>   clamp the collision pass's move of a particle to E rest edge lengths (default 1)
+  --no-clamp                  disable both safety limits above
+  --fabric <NAME>             material parameters of silk, denim, rubber-sheet or chainmail
+  --kill-plane <Y>            hold particles that fall below the world height Y on it
+  --respawn <FRACTION>        reset the cloth once more than FRACTION of it is on the kill plane
+  --retension <C>             re-tension the cloth once it has contracted by more than the fraction C
+  --stiffness <WARP>[x<WEFT>] stiffness 0..1 of the springs along the rows and down the columns
+  --pins <corners|top-row|top-corners|left-column|rail|free|custom:<X>,<Y>[;<X>,<Y>...]>
+  --uv-scale <S>              repeat the texture S times along each side
+  --texture <PATH>            cloth texture, relative to assets/
+  --orthographic              view the scene through an orthographic camera instead of a perspective one
+  --stack                     add two cloths stacked over a sphere
+  --probe-speed <S>           speed of the sphere Left Alt + P drives through the scene (default 4)
+  --panels                    add two hanging panels that J sews together
+  --pillow                    inflate the cloth into a free pillow and poke it with a sphere
+  --bounce                    drop a free flat cloth onto a floor whose restitution R toggles
+  --trampoline [--ball-mass <M>]
+                              drop a ball of mass M (default 20) onto the flat cloth held by its corners,
+                              the cloth pushes it back
+  --sails                     add a solid sail and a porous net side by side in the wind
+  --ropes                     hang three ropes next to the main cloth in the same wind
+  --soft-stairs               drop a cloth built from the stairs mesh onto the stairs
+  --banner                    hang a banner tiled from a small patch, and its mirror image next to it
+  --garment                   add a lined cape over a turning mannequin, Y takes it off and puts it back
+  --emblem-flag               add a flag whose emblem is drawn with a material of its own
+  --field <uniform|radial|vortex>
+                              add a force field of that kind around the main cloth
+  --lod                       simulate cloths far from the camera or out of view at a lower level of detail
+  --bake                      bake strain, contact and speed into a texture per cloth, E shows them
+  --skin-regions <M>x<N>      regions, each with a bone, that F8 bakes a recording to (default 4x4)
+  --no-skin-rotation          give those bones a translation only
+  --scenario <PATH>           play a scenario file next to the main cloth, or with --headless run it on
+                              its own and fail if any of its checks does
+  --no-pin-markers            start with the markers on pinned particles hidden
+  --strain-top <K>            most strained constraints shown by the overlay (default 64)
+  --strain-threshold <S>      least strain shown by the overlay (default 0.02)
+  --headless [--steps <N>]    run the solver without a window and print timings
+  --metrics <PATH>            with --headless, write per-step metrics to a csv file
+  --gpu                       solve on the gpu (experimental, needs the gpu feature)
+  --deterministic             bitwise reproducible runs for a seed on any machine, overrides --gpu
+  --async-sim                 step the main cloth on a background thread (needs the async_sim feature)
+  --no-contact-cache          rediscover every collider contact from scratch each step
+  --no-untangle               eject a cloth that starts inside a collider in one step
+  --weights <mass|drag|stiffness>:<SCALE>
+                              scale that property by up to SCALE where the weight map is painted with D
+  --plasticity                let flexion springs held past yield creep, keeping creases after impacts
+  --bench-scene <N> [--frames <N>] [--bench-json <PATH>] [--lod]
+                              step N free 20x20 cloths over a shared floor without a window,
+                              print a performance report and optionally write it as json,
+                              with --lod seen from a camera at one corner of the grid
+  --update-goldens            rewrite the golden files in assets/golden that the regression test compares with";
+
+/* PHYSICAL SIZE AND GRID RESOLUTION OF THE MAIN CLOTH */
+#[derive(Clone, Copy, Debug)]
+pub struct Dimensions {
+    pub size: (F, F),
+    pub subdivisions: GridIdx,
+    /* ROLL OF THE MAIN CLOTH ENTITY ABOUT THE VIEWING AXIS, IN DEGREES */
+    pub rotation: F,
+    /* GIVE THE BOTTOM ROWS A HIGHER DRAG COEFFICIENT, LIKE A HEAVY WET HEM */
+    pub wet_hem: bool,
+    /* STEPS OF Cloth::relax AND Cloth::settle_under_gravity TO RUN BEFORE THE FIRST FRAME */
+    pub relax: I,
+    pub settle: I,
+}
+
+impl Default for Dimensions {
+    fn default() -> Self {
+        Self {
+            size: cloth::SIZE,
+            subdivisions: (cloth::SUBDIVISIONS, cloth::SUBDIVISIONS),
+            rotation: 0.,
+            wet_hem: false,
+            relax: 0,
+            settle: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Args {
+    pub config: ClothConfig,
+    pub dimensions: Dimensions,
+    pub texture: String,
+    pub orthographic: bool,
+    pub stack: bool,
+    /* SPEED OF THE KEYBOARD-DRIVEN SPHERE */
+    pub probe_speed: F,
+    pub pillow: bool,
+    pub panels: bool,
+    pub bounce: bool,
+    /* MASS OF THE BALL DROPPED ONTO THE MAIN CLOTH, HELD FLAT BY ITS CORNERS */
+    pub trampoline: Option<F>,
+    pub sails: bool,
+    pub ropes: bool,
+    pub soft_stairs: bool,
+    pub banner: bool,
+    pub garment: bool,
+    pub emblem_flag: bool,
+    pub field: Option<FieldExample>,
+    /* PICK THE LEVEL OF DETAIL OF EVERY CLOTH FROM ITS DISTANCE TO THE CAMERA */
+    pub lod: bool,
+    /* BAKE SIMULATION DATA INTO A TEXTURE PER CLOTH FOR MATERIAL EFFECTS */
+    pub bake: bool,
+    pub pin_markers: bool,
+    /* NUMBER OF LINES AND THRESHOLD OF THE STRAIN OVERLAY */
+    pub strain_overlay: (I, F),
+    /* NUMBER OF STEPS TO RUN WITHOUT A WINDOW */
+    pub headless: Option<I>,
+    /* CSV FILE THAT A HEADLESS RUN WRITES ITS PER-STEP METRICS TO */
+    pub metrics: Option<String>,
+    /* HEADLESS RUN REWRITING THE GOLDEN FILES OF THE REGRESSION TEST */
+    pub update_goldens: bool,
+    /* SCENARIO FILE TO PLAY, OR TO RUN AS A TEST WITH --headless */
+    pub scenario: Option<String>,
+    /* HEADLESS STRESS TEST WITH THIS MANY CLOTHS, AND THE JSON FILE ITS REPORT IS WRITTEN TO */
+    pub bench_scene: Option<I>,
+    pub bench_json: Option<String>,
+    /* HOW F8 BAKES A RECORDING TO BONES */
+    pub skin: SkinOptions,
+}
+
+fn parse_value<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String>
+where
+    T::Err: Display,
+{
+    let value = value.ok_or_else(|| format!("{} requires a value", flag))?;
+    value.parse().map_err(|err| format!("invalid value '{}' for {}: {}", value, flag, err))
+}
+
+/* "AxB" OR A SINGLE "A" FOR BOTH */
+fn parse_pair<T: FromStr + Copy>(flag: &str, value: Option<String>) -> Result<(T, T), String>
+where
+    T::Err: Display,
+{
+    let value = value.ok_or_else(|| format!("{} requires a value", flag))?;
+    let parse = |s: &str| parse_value(flag, Some(s.to_string()));
+    match value.split_once('x') {
+        Some((a, b)) => Ok((parse(a)?, parse(b)?)),
+        None => parse(&value).map(|a| (a, a)),
+    }
+}
+
+pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut config = ClothConfig::default();
+    let mut dimensions = Dimensions::default();
+    let mut texture = material::TEXTURE_PATH.to_string();
+    let mut orthographic = false;
+    let (mut stack, mut pillow, mut panels, mut bounce, mut sails) = (false, false, false, false, false);
+    let (mut ropes, mut soft_stairs, mut banner, mut garment, mut emblem_flag) = (false, false, false, false, false);
+    let (mut trampoline, mut ball_mass) = (false, props::BALL_MASS);
+    let (mut headless, mut steps) = (false, headless::STEPS);
+    let mut probe_speed = probe::PROBE_SPEED;
+    let mut metrics = None;
+    let mut update_goldens = false;
+    let mut skin = SkinOptions::default();
+    let mut scenario = None;
+    let (mut bench_scene, mut bench_json) = (None, None);
+    let mut field = None;
+    let mut lod = false;
+    let mut bake = false;
+    let mut pin_markers = true;
+    let mut strain_overlay = (strain::TOP_K, strain::THRESHOLD);
+
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--size" => dimensions.size = parse_pair(&flag, args.next())?,
+            "--subdivisions" => dimensions.subdivisions = parse_pair(&flag, args.next())?,
+            "--rotate" => dimensions.rotation = parse_value(&flag, args.next())?,
+            "--wet-hem" => dimensions.wet_hem = true,
+            "--relax" => dimensions.relax = parse_value(&flag, args.next())?,
+            "--settle" => dimensions.settle = parse_value(&flag, args.next())?,
+            "--iterations" => {
+                config.max_iterations = parse_value(&flag, args.next())?;
+                config.min_iterations = config.min_iterations.min(config.max_iterations);
+            }
+            "--dt" => config.dt = parse_value(&flag, args.next())?,
+            "--time-scale" => config.time_scale = parse_value(&flag, args.next())?,
+            "--max-dt" => config.max_dt = parse_value(&flag, args.next())?,
+            "--solver" => config.solver = parse_value(&flag, args.next())?,
+            "--integrator" => config.integrator = parse_value(&flag, args.next())?,
+            "--normals" => config.normal_weighting = parse_value(&flag, args.next())?,
+            "--diagonal" => config.diagonal = parse_value(&flag, args.next())?,
+            "--solve-order" => config.solve_order = parse_value(&flag, args.next())?,
+            "--relaxation" => config.relaxation = parse_value(&flag, args.next())?,
+            "--over-relaxation" => config.over_relaxation = parse_value(&flag, args.next())?,
+            "--kind-iterations" => config.kind_iterations = Some(parse_value(&flag, args.next())?),
+            "--local-boost" => config.local_boost = Some(parse_value(&flag, args.next())?),
+            "--wind-occlusion" => config.wind_occlusion = Some(parse_value(&flag, args.next())?),
+            "--seed" => config.seed = parse_value(&flag, args.next())?,
+            "--pose" => config.pose = parse_value(&flag, args.next())?,
+            "--self-collision" => config.self_collision = parse_value(&flag, args.next())?,
+            "--no-self-collision" => config.self_collision = SelfCollision::Off,
+            "--edge-collision" => config.edge_collision = Some(parse_value(&flag, args.next())?),
+            "--spring-damping" => config.spring_damping = SpringDamping::uniform(parse_value(&flag, args.next())?),
+            "--auto-damping" => config.auto_damping = Some(AutoDamping::default()),
+            "--area-stiffness" => config.area_stiffness = Some(parse_value(&flag, args.next())?),
+            "--warm-start" => config.warm_start = parse_value(&flag, args.next())?,
+            "--friction" => config.friction = parse_value(&flag, args.next())?,
+            "--porosity" => config.porosity = parse_value(&flag, args.next())?,
+            "--air-drag" => config.air_drag = parse_value(&flag, args.next())?,
+            "--retension" => config.retension = Some(parse_value(&flag, args.next())?),
+            "--max-speed" => config.max_speed = Some(parse_value(&flag, args.next())?),
+            "--max-step-correction" => config.max_step_correction = Some(parse_value(&flag, args.next())?),
+            "--fabric" => parse_value::<FabricPreset>(&flag, args.next())?.fabric().apply(&mut config),
+            "--no-clamp" => {
+                config.max_speed = None;
+                config.max_step_correction = None;
+            }
+            "--kill-plane" => config.kill_plane = Some(parse_value(&flag, args.next())?),
+            "--respawn" => config.kill_mode = KillMode::Respawn { fraction: parse_value(&flag, args.next())? },
+            "--stiffness" => {
+                let (warp, weft) = parse_pair(&flag, args.next())?;
+                config.stiffness.warp = warp;
+                config.stiffness.weft = weft;
+            }
+            "--pins" => config.pin_preset = parse_value(&flag, args.next())?,
+            "--uv-scale" => {
+                let s: F = parse_value(&flag, args.next())?;
+                config.uv_scale = (s, s);
+            }
+            "--texture" => texture = parse_value(&flag, args.next())?,
+            "--orthographic" => orthographic = true,
+            "--stack" => stack = true,
+            "--probe-speed" => probe_speed = parse_value(&flag, args.next())?,
+            "--pillow" => pillow = true,
+            "--panels" => panels = true,
+            "--bounce" => bounce = true,
+            "--trampoline" => trampoline = true,
+            "--ball-mass" => ball_mass = parse_value(&flag, args.next())?,
+            "--sails" => sails = true,
+            "--ropes" => ropes = true,
+            "--soft-stairs" => soft_stairs = true,
+            "--banner" => banner = true,
+            "--garment" => garment = true,
+            "--emblem-flag" => emblem_flag = true,
+            "--field" => field = Some(parse_value(&flag, args.next())?),
+            "--lod" => lod = true,
+            "--bake" => bake = true,
+            "--skin-regions" => skin.regions = parse_pair(&flag, args.next())?,
+            "--no-skin-rotation" => skin.rotation = false,
+            "--no-pin-markers" => pin_markers = false,
+            "--strain-top" => strain_overlay.0 = parse_value(&flag, args.next())?,
+            "--strain-threshold" => strain_overlay.1 = parse_value(&flag, args.next())?,
+            "--headless" => headless = true,
+            "--steps" | "--frames" => steps = parse_value(&flag, args.next())?,
+            "--metrics" => metrics = Some(parse_value(&flag, args.next())?),
+            "--gpu" => config.gpu = true,
+            "--deterministic" => config.deterministic = true,
+            "--async-sim" => config.async_sim = true,
+            "--no-contact-cache" => config.contact_cache = false,
+            "--no-untangle" => config.untangle = false,
+            "--plasticity" => config.plasticity = Some(Plasticity::default()),
+            "--weights" => config.weight_binding = Some(parse_value(&flag, args.next())?),
+            "--scenario" => scenario = Some(parse_value(&flag, args.next())?),
+            "--update-goldens" => {
+                update_goldens = true;
+                headless = true;
+            }
+            "--bench-scene" => {
+                bench_scene = Some(parse_value(&flag, args.next())?);
+                headless = true;
+            }
+            "--bench-json" => bench_json = Some(parse_value(&flag, args.next())?),
+            "--help" | "-h" => return Err(String::new()),
+            _ => return Err(format!("unknown option '{}'", flag)),
+        }
+    }
+
+    let ((w, h), (sx, sy)) = (dimensions.size, dimensions.subdivisions);
+    if !(w > 0. && h > 0.) {
+        return Err(format!("--size must be positive, got {}x{}", w, h));
+    }
+    if sx < 2 || sy < 2 {
+        return Err(format!("--subdivisions must be at least 2, got {}x{}", sx, sy));
+    }
+    config.pin_preset.validate(sx, sy).map_err(|err| format!("invalid --pins: {}", err))?;
+    if !(config.dt > 0. && config.max_dt > 0.) {
+        return Err(format!("--dt and --max-dt must be positive, got {} and {}", config.dt, config.max_dt));
+    }
+    if !(0. ..=1.).contains(&config.time_scale) {
+        return Err(format!("--time-scale must be between 0 and 1, got {}", config.time_scale));
+    }
+    if config.max_iterations == 0 {
+        return Err("--iterations must be at least 1".to_string());
+    }
+    if !(probe_speed > 0.) {
+        return Err(format!("--probe-speed must be positive, got {}", probe_speed));
+    }
+    if !(config.over_relaxation > 0. && config.over_relaxation < 2.) {
+        return Err(format!("--over-relaxation must be between 0 and 2, got {}", config.over_relaxation));
+    }
+    if let Some(k) = config.kind_iterations.filter(|k| k.structural == 0) {
+        return Err(format!("--kind-iterations needs at least 1 structural sweep, got {:?}", k));
+    }
+    if let Some(b) = config.local_boost.filter(|b| b.iterations == 0) {
+        return Err(format!("--local-boost needs at least 1 iteration, got {:?}", b));
+    }
+    if let Some(o) = config.wind_occlusion {
+        if !(0. ..=1.).contains(&o.attenuation) || !(o.reach > 0.) || o.interval == 0 {
+            return Err(format!("--wind-occlusion needs 0 to 1, a positive reach and at least 1 step, got {:?}", o));
+        }
+    }
+    if !(config.relaxation > 0. && config.relaxation < 2.) {
+        return Err(format!("--relaxation must be between 0 and 2, got {}", config.relaxation));
+    }
+    if pillow {
+        config.pose = Pose::Pillow { gap: PILLOW_GAP };
+        config.pin_preset = PinPreset::Free;
+        let volume = pillow::INFLATION * w * h * w.min(h);
+        config.pressure = Some(Pressure { stiffness: pillow::PRESSURE, volume });
+    }
+    if bounce {
+        config.pose = Pose::FlatHorizontal { height: 0. };
+        config.pin_preset = PinPreset::Free;
+    }
+    if !(ball_mass > 0.) {
+        return Err(format!("--ball-mass must be positive, got {}", ball_mass));
+    }
+    if trampoline {
+        config.pose = Pose::FlatHorizontal { height: 0. };
+        config.pin_preset = PinPreset::Corners;
+    }
+    if let Some(k) = config.area_stiffness.filter(|k| !(0. ..=1.).contains(k)) {
+        return Err(format!("--area-stiffness must be between 0 and 1, got {}", k));
+    }
+    if !(0. ..=1.).contains(&config.warm_start) {
+        return Err(format!("--warm-start must be between 0 and 1, got {}", config.warm_start));
+    }
+    if config.friction < 0. {
+        return Err(format!("--friction must not be negative, got {}", config.friction));
+    }
+    if !(0. ..=1.).contains(&config.porosity) {
+        return Err(format!("--porosity must be between 0 and 1, got {}", config.porosity));
+    }
+    if config.air_drag < 0. {
+        return Err(format!("--air-drag must not be negative, got {}", config.air_drag));
+    }
+    if let Some(t) = config.retension.filter(|&t| t < 0.) {
+        return Err(format!("--retension must not be negative, got {}", t));
+    }
+    if let Some(e) = config.max_speed.filter(|&e| !(e > 0.)) {
+        return Err(format!("--max-speed must be positive, got {}", e));
+    }
+    if let Some(e) = config.max_step_correction.filter(|&e| !(e > 0.)) {
+        return Err(format!("--max-step-correction must be positive, got {}", e));
+    }
+    if let KillMode::Respawn { fraction } = config.kill_mode {
+        if config.kill_plane.is_none() {
+            return Err("--respawn requires --kill-plane".to_string());
+        }
+        if !(0. ..1.).contains(&fraction) {
+            return Err(format!("--respawn must be at least 0 and below 1, got {}", fraction));
+        }
+    }
+    let (warp, weft) = (config.stiffness.warp, config.stiffness.weft);
+    if !(0. ..=1.).contains(&warp) || !(0. ..=1.).contains(&weft) {
+        return Err(format!("--stiffness must be between 0 and 1, got {}x{}", warp, weft));
+    }
+    if let SolverMode::Hierarchical { fine_iters: 0, .. } = config.solver {
+        return Err("--solver hierarchical needs at least 1 fine iteration".to_string());
+    }
+    if skin.regions.0 == 0 || skin.regions.1 == 0 {
+        return Err(format!("--skin-regions must be at least 1, got {}x{}", skin.regions.0, skin.regions.1));
+    }
+    if bench_scene == Some(0) {
+        return Err("--bench-scene needs at least 1 cloth".to_string());
+    }
+    if bench_json.is_some() && bench_scene.is_none() {
+        return Err("--bench-json requires --bench-scene".to_string());
+    }
+    if metrics.is_some() && !headless {
+        return Err("--metrics requires --headless".to_string());
+    }
+    if config.gpu && !cfg!(feature = "gpu") {
+        return Err("--gpu requires building with --features gpu".to_string());
+    }
+    if config.async_sim && !cfg!(feature = "async_sim") {
+        return Err("--async-sim requires building with --features async_sim".to_string());
+    }
+
+    Ok(Args {
+        config,
+        dimensions,
+        texture,
+        orthographic,
+        stack,
+        probe_speed,
+        pillow,
+        panels,
+        bounce,
+        trampoline: if trampoline { Some(ball_mass) } else { None },
+        sails,
+        ropes,
+        soft_stairs,
+        banner,
+        garment,
+        emblem_flag,
+        field,
+        lod,
+        bake,
+        pin_markers,
+        strain_overlay,
+        headless: if headless { Some(steps) } else { None },
+        metrics,
+        update_goldens,
+        scenario,
+        bench_scene,
+        bench_json,
+        skin,
+    })
+}
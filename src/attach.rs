@@ -0,0 +1,86 @@
+use bevy::prelude::*;
+
+use crate::{bindings::Action, cloth::{Cloth, ClothSim}, to_v, to_vec3, P};
+
+/* MOVE ATTACHED PARTICLES ALONG WITH THE ENTITIES THEY ARE ATTACHED TO */
+/* RUNS BEFORE THE SIMULATION STEP SO THE SOLVE SEES THE NEW POSITIONS */
+pub fn follow(transforms: Query<&GlobalTransform>, mut cloths: Query<&mut Cloth>) {
+    for mut cloth in cloths.iter_mut() {
+        for i in 0..cloth.attachments().len() {
+            let a = cloth.attachments()[i];
+            if let Ok(transform) = transforms.get(a.entity) {
+                let p = cloth.to_local(P::from(to_v(transform.mul_vec3(to_vec3(a.local_offset)))));
+                if let Err(err) = cloth.set_position(a.idx, p) {
+                    warn!("failed to move an attached particle: {}", err);
+                }
+            }
+        }
+    }
+}
+
+/* INVERSE OF THE TRANSFORM APPLIED IN follow */
+fn local_offset(transform: &GlobalTransform, p: P) -> Vec3 {
+    transform.rotation.conjugate() * (to_vec3(p.coords) - transform.translation) / transform.scale
+}
+
+/* EXAMPLE: A KINEMATIC BAR SWINGING SIDE TO SIDE THAT THE TOP ROW CAN BE ATTACHED TO */
+/* F BINDS THE TOP ROW TO IT LIKE A CAPE, OR RELEASES IT TO KEEP SWINGING ON ITS OWN */
+
+const SWAY_AMPLITUDE: f32 = 2.;
+const SWAY_FREQUENCY: f32 = 0.5;
+
+pub struct Pole {
+    rest: Vec3,
+}
+
+pub fn spawn_pole(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    cloth: &ClothSim,
+) {
+    let (w, _) = cloth.subdivisions();
+    let (first, last) = (cloth.world_position((0, 0)), cloth.world_position((w - 1, 0)));
+    let center = nalgebra::center(&first, &last);
+    let rest = to_vec3(center.coords) + Vec3::new(0., 0.2, 0.);
+
+    commands
+        .spawn(PbrComponents {
+            mesh: meshes.add(Mesh::from(shape::Box::new((last - first).norm() as f32 + 1., 0.2, 0.2))),
+            material: materials.add(Color::rgb(0.4, 0.3, 0.2).into()),
+            transform: Transform::from_translation(rest),
+            ..Default::default()
+        })
+        .with(Pole { rest });
+}
+
+pub fn sway(time: Res<Time>, mut query: Query<(&Pole, &mut Transform)>) {
+    let t = time.seconds_since_startup as f32;
+    for (pole, mut transform) in query.iter_mut() {
+        let offset = SWAY_AMPLITUDE * (2. * std::f32::consts::PI * SWAY_FREQUENCY * t).sin();
+        transform.translation = pole.rest + Vec3::new(0., 0., offset);
+    }
+}
+
+pub fn toggle(
+    actions: Res<Input<Action>>,
+    poles: Query<(Entity, &Pole, &GlobalTransform)>,
+    mut cloths: Query<&mut Cloth>,
+) {
+    if !actions.just_pressed(Action::Attach) {
+        return;
+    }
+
+    for (entity, _, transform) in poles.iter() {
+        for mut cloth in cloths.iter_mut() {
+            if cloth.attachments().iter().any(|a| a.entity == entity) {
+                cloth.detach(entity);
+                continue;
+            }
+
+            /* OFFSETS ARE CAPTURED FROM THE CURRENT RELATIVE POSITIONS, IN THE FRAME OF THE POLE */
+            let offsets = cloth.particles.row(0).map(|p| to_v(local_offset(transform, cloth.to_world(*p)))).collect();
+            cloth.bind_row(0, entity, offsets);
+        }
+    }
+}
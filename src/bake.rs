@@ -0,0 +1,165 @@
+use bevy::{
+    pbr::render_graph::FORWARD_PIPELINE_HANDLE,
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        pipeline::PipelineDescriptor,
+        render_graph::{base, AssetRenderResourcesNode, RenderGraph},
+        renderer::RenderResources,
+        shader::{ShaderStage, ShaderStages},
+        texture::{FilterMode, TextureFormat},
+    },
+};
+
+use crate::{bindings::Action, cloth::Cloth, grid::GridIdx, F};
+
+/* BAKING OF SIMULATION DATA INTO A TEXTURE PER CLOTH, ONE TEXEL PER PARTICLE, FOR MATERIAL EFFECTS */
+/* THE CHANNELS ARE STRAIN, CONTACT, SPEED AND THE PAINTED WEIGHT. THE UVS OF THE CLOTH ALREADY MAP */
+/* 1:1 TO THE GRID, SO A MATERIAL SAMPLES IT LIKE ANY OTHER TEXTURE. --bake TURNS IT ON, E SHOWS THE */
+/* EXAMPLE MATERIAL */
+
+const VERTEX_SHADER: &str = include_str!("../assets/shaders/bake.vert");
+const FRAGMENT_SHADER: &str = include_str!("../assets/shaders/bake.frag");
+
+const MATERIAL_NODE: &str = "bake_material";
+
+// STRAIN AND SPEED THAT SATURATE THEIR CHANNELS
+const MAX_STRAIN: F = 0.3;
+const MAX_SPEED: F = 5.;
+
+// HOW MUCH A CHANNEL OF SOME TEXEL HAS TO CHANGE BEFORE THE TEXTURE IS UPLOADED AGAIN
+const UPLOAD_THRESHOLD: u8 = 2;
+
+const ALBEDO: Color = Color::rgb(0.85, 0.8, 0.7);
+
+#[derive(RenderResources, Default, TypeUuid)]
+#[uuid = "5b8f3a52-3c1e-4d0b-9a6f-2e7c41d9b0a3"]
+pub struct BakeMaterial {
+    pub albedo: Color,
+    pub data: Handle<Texture>,
+}
+
+pub struct Bake {
+    /* --bake, THE PIPELINE EXISTS ONCE setup HAS RUN WITH IT */
+    enabled: bool,
+    /* WHETHER THE CLOTHS ARE DRAWN WITH THE EXAMPLE MATERIAL INSTEAD OF THEIR OWN */
+    visible: bool,
+    pipeline: Option<Handle<PipelineDescriptor>>,
+}
+
+impl Bake {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled, visible: false, pipeline: None }
+    }
+}
+
+/* THE BAKED TEXTURE OF A CLOTH ENTITY, ADDED BY bake */
+pub struct SimTexture {
+    texture: Handle<Texture>,
+    size: GridIdx,
+    /* PACKED TEXELS OF THIS FRAME AND THE PER-PARTICLE STRAINS THEY COME FROM, REUSED ACROSS FRAMES */
+    bytes: Vec<u8>,
+    strains: Vec<F>,
+}
+
+fn data_texture((w, h): GridIdx) -> Texture {
+    let mut texture = Texture::new(Vec2::new(w as f32, h as f32), vec![0; w * h * 4], TextureFormat::Rgba8Unorm);
+    texture.sampler.mag_filter = FilterMode::Linear;
+    texture.sampler.min_filter = FilterMode::Linear;
+    texture
+}
+
+fn channel(value: F) -> u8 {
+    (value.max(0.).min(1.) * 255.).round() as u8
+}
+
+pub fn setup(
+    mut bake: ResMut<Bake>,
+    mut pipelines: ResMut<Assets<PipelineDescriptor>>,
+    mut shaders: ResMut<Assets<Shader>>,
+    mut render_graph: ResMut<RenderGraph>,
+) {
+    if !bake.enabled {
+        return;
+    }
+    bake.pipeline = Some(pipelines.add(PipelineDescriptor::default_config(ShaderStages {
+        vertex: shaders.add(Shader::from_glsl(ShaderStage::Vertex, VERTEX_SHADER)),
+        fragment: Some(shaders.add(Shader::from_glsl(ShaderStage::Fragment, FRAGMENT_SHADER))),
+    })));
+    render_graph.add_system_node(MATERIAL_NODE, AssetRenderResourcesNode::<BakeMaterial>::new(true));
+    render_graph.add_node_edge(MATERIAL_NODE, base::node::MAIN_PASS).unwrap();
+}
+
+pub fn toggle(actions: Res<Input<Action>>, mut bake: ResMut<Bake>) {
+    if actions.just_pressed(Action::Bake) && bake.pipeline.is_some() {
+        bake.visible = !bake.visible;
+    }
+}
+
+/* PACK THE LATEST STEP OF EVERY CLOTH INTO ITS TEXTURE, ONLY UPLOADING IT IF SOME TEXEL CHANGED NOTICEABLY */
+pub fn bake(
+    mut commands: Commands,
+    bake: Res<Bake>,
+    mut textures: ResMut<Assets<Texture>>,
+    mut materials: ResMut<Assets<BakeMaterial>>,
+    mut cloths: Query<(Entity, &Cloth, Option<&mut SimTexture>, &mut RenderPipelines)>,
+) {
+    let pipeline = match &bake.pipeline {
+        Some(pipeline) => pipeline,
+        None => return,
+    };
+    for (entity, cloth, sim, mut render_pipelines) in cloths.iter_mut() {
+        /* ONLY THE HANDLE IS SWAPPED, THE SPECIALIZATION FOR THE MESH STAYS THE SAME */
+        let wanted = if bake.visible { pipeline.clone() } else { FORWARD_PIPELINE_HANDLE.typed() };
+        if let Some(render_pipeline) = render_pipelines.pipelines.first_mut().filter(|p| p.pipeline != wanted) {
+            render_pipeline.pipeline = wanted;
+        }
+
+        let (w, h) = cloth.subdivisions();
+        let mut sim = match sim {
+            Some(sim) => sim,
+            None => {
+                let texture = textures.add(data_texture((w, h)));
+                let material = materials.add(BakeMaterial { albedo: ALBEDO, data: texture.clone() });
+                let sim = SimTexture { texture, size: (w, h), bytes: vec![], strains: vec![] };
+                commands.insert(entity, (material, sim));
+                continue;
+            }
+        };
+        if sim.size != (w, h) {
+            sim.size = (w, h);
+            if let Some(texture) = textures.get_mut(&sim.texture) {
+                *texture = data_texture((w, h));
+            }
+        }
+
+        /* TORN COPIES OF A PARTICLE SHARE ITS TEXEL, WHICH TAKES THE LARGEST OF THEIR VALUES */
+        let sim = &mut *sim;
+        cloth.grid_strains(&mut sim.strains);
+        sim.bytes.clear();
+        let texels = sim.strains.iter().zip(cloth.weights().iter());
+        let texel = |(&strain, &weight): (&F, &F)| vec![channel(strain / MAX_STRAIN), 0, 0, channel(weight)];
+        sim.bytes.extend(texels.flat_map(texel));
+        let particles = &cloth.particles;
+        for i in 0..particles.len() {
+            let texel = &mut sim.bytes[particles.origin(i) * 4..][..3];
+            if cloth.in_contact(i) {
+                texel[1] = 255;
+            }
+            texel[2] = texel[2].max(channel(particles.velocity(i).norm() / MAX_SPEED));
+        }
+
+        let changed = match textures.get(&sim.texture) {
+            Some(texture) => {
+                texture.data.len() != sim.bytes.len()
+                    || texture.data.iter().zip(&sim.bytes).any(|(&a, &b)| a.max(b) - a.min(b) > UPLOAD_THRESHOLD)
+            }
+            None => false,
+        };
+        if changed {
+            if let Some(texture) = textures.get_mut(&sim.texture) {
+                texture.data.clone_from(&sim.bytes);
+            }
+        }
+    }
+}
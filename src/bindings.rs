@@ -0,0 +1,493 @@
+use std::{collections::BTreeMap, convert::TryFrom, fmt, fs, io, str::FromStr, time::SystemTime};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{stats::SERIES, I};
+
+/* NAMED ACTIONS AND THE KEYS OR MOUSE BUTTONS THAT TRIGGER THEM. THE SYSTEMS ONLY EVER LOOK AT Input<Action>, */
+/* WHICH resolve FILLS IN EACH FRAME FROM THE RAW INPUT, SO REBINDING IS JUST AN EDIT OF assets/input.ron */
+
+pub const BINDINGS_PATH: &str = "assets/input.ron";
+
+// SECONDS BETWEEN CHECKS OF THE MODIFICATION TIME
+const POLL_INTERVAL: f32 = 0.5;
+
+// NUMBER OF FABRIC PRESETS THAT GET AN ACTION
+pub const FABRIC_SLOTS: I = 9;
+
+const CTRL: u8 = 1;
+const SHIFT: u8 = 2;
+const ALT: u8 = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+pub enum Action {
+    /* A CLICK TOGGLES THE PINS UNDER THE MOUSE, HOLDING IT ON PINS DRAGS THEM ALONG */
+    TogglePin,
+    Unpin,
+    Tear,
+    Orbit,
+    Pan,
+    /* HELD, THE SCROLL WHEEL SETS THE PICK RADIUS AND THE BRUSHES WORK IN REVERSE */
+    Modify,
+    /* HELD, PAINTS THE WEIGHT MAP AND THE SCROLL WHEEL SETS THE BRUSH STRENGTH */
+    PaintWeights,
+    PaintStiffness,
+    WindGun,
+    Attract,
+    SoftSelection,
+    CycleFalloff,
+    PickSamples,
+    TopRow,
+    ClearPins,
+    PinAll,
+    Freeze,
+    Shrink,
+    Reset,
+    Slower,
+    Faster,
+    Restitution,
+    Brace,
+    Cut,
+    Upsample,
+    NormalWeighting,
+    Finer,
+    Coarser,
+    Despawn,
+    Respawn,
+    PinMarkers,
+    StrainOverlay,
+    CycleMaterial,
+    /* 1-BASED, IN THE ORDER OF fabric::Fabrics */
+    Fabric(I),
+    Garment,
+    Stitch,
+    Attach,
+    Bake,
+    CycleDebug,
+    Textured,
+    Wireframe,
+    Points,
+    TiltUp,
+    TiltDown,
+    TiltLeft,
+    TiltRight,
+    ZeroGravity,
+    FlipGravity,
+    ResetGravity,
+    SaveLayout,
+    CycleLayout,
+    Record,
+    Replay,
+    /* THE LAST THREE ONLY DO ANYTHING WHILE A RECORDING IS REPLAYED */
+    Pause,
+    StepOnce,
+    StepBack,
+    ExportSkin,
+    Screenshot,
+    Sequence,
+    Probe,
+    ProbeForward,
+    ProbeBack,
+    ProbeLeft,
+    ProbeRight,
+    ProbeUp,
+    ProbeDown,
+    StatsGraph,
+    /* 1-BASED, IN THE ORDER OF stats::Series */
+    GraphSeries(I),
+    ExportStats,
+}
+
+impl Action {
+    /* ACTIONS THAT ONLY WORK WHILE REPLAYING AND THOSE THAT ARE OFF MEANWHILE MAY SHARE A BINDING */
+    fn replay_only(self) -> bool {
+        matches!(self, Action::Pause | Action::StepOnce | Action::StepBack)
+    }
+
+    fn off_while_replaying(self) -> bool {
+        matches!(self, Action::TiltUp | Action::TiltDown | Action::TiltLeft | Action::TiltRight)
+    }
+}
+
+const DEFAULT_BINDINGS: &[(Action, &[&str])] = &[
+    (Action::TogglePin, &["MouseLeft"]),
+    (Action::Unpin, &["MouseRight"]),
+    (Action::Tear, &["MouseMiddle"]),
+    (Action::Orbit, &["Alt+MouseLeft"]),
+    (Action::Pan, &["Alt+MouseMiddle"]),
+    (Action::Modify, &["LShift"]),
+    (Action::PaintWeights, &["D"]),
+    (Action::PaintStiffness, &["I"]),
+    (Action::WindGun, &["V"]),
+    (Action::Attract, &["A"]),
+    (Action::SoftSelection, &["X"]),
+    (Action::CycleFalloff, &["Shift+X"]),
+    (Action::PickSamples, &["S"]),
+    (Action::TopRow, &["T"]),
+    (Action::ClearPins, &["C"]),
+    (Action::PinAll, &["Shift+C"]),
+    (Action::Freeze, &["Q"]),
+    (Action::Shrink, &["W"]),
+    (Action::Reset, &["N"]),
+    (Action::Slower, &["Comma"]),
+    (Action::Faster, &["Period"]),
+    (Action::Restitution, &["R"]),
+    (Action::Brace, &["B"]),
+    (Action::Cut, &["K"]),
+    (Action::Upsample, &["U"]),
+    (Action::NormalWeighting, &["H"]),
+    (Action::Finer, &["Equals", "Add"]),
+    (Action::Coarser, &["Minus", "Subtract"]),
+    (Action::Despawn, &["Delete"]),
+    (Action::Respawn, &["Insert"]),
+    (Action::PinMarkers, &["P"]),
+    (Action::StrainOverlay, &["O"]),
+    (Action::CycleMaterial, &["M"]),
+    (Action::Fabric(1), &["Key1"]),
+    (Action::Fabric(2), &["Key2"]),
+    (Action::Fabric(3), &["Key3"]),
+    (Action::Fabric(4), &["Key4"]),
+    (Action::Fabric(5), &["Key5"]),
+    (Action::Fabric(6), &["Key6"]),
+    (Action::Fabric(7), &["Key7"]),
+    (Action::Fabric(8), &["Key8"]),
+    (Action::Fabric(9), &["Key9"]),
+    (Action::Garment, &["Y"]),
+    (Action::Stitch, &["J"]),
+    (Action::Attach, &["F"]),
+    (Action::Bake, &["E"]),
+    (Action::CycleDebug, &["Tab"]),
+    (Action::Textured, &["F1"]),
+    (Action::Wireframe, &["F2"]),
+    (Action::Points, &["F3"]),
+    (Action::TiltUp, &["Up"]),
+    (Action::TiltDown, &["Down"]),
+    (Action::TiltLeft, &["Left"]),
+    (Action::TiltRight, &["Right"]),
+    (Action::ZeroGravity, &["Z"]),
+    (Action::FlipGravity, &["G"]),
+    (Action::ResetGravity, &["Key0"]),
+    (Action::SaveLayout, &["Ctrl+S"]),
+    (Action::CycleLayout, &["Ctrl+L"]),
+    (Action::Record, &["F6"]),
+    (Action::Replay, &["F7"]),
+    (Action::Pause, &["Space"]),
+    (Action::StepOnce, &["Right"]),
+    (Action::StepBack, &["Left"]),
+    (Action::ExportSkin, &["F8"]),
+    (Action::Screenshot, &["F12"]),
+    (Action::Sequence, &["F11"]),
+    (Action::Probe, &["Alt+P"]),
+    (Action::ProbeForward, &["Alt+W"]),
+    (Action::ProbeBack, &["Alt+S"]),
+    (Action::ProbeLeft, &["Alt+A"]),
+    (Action::ProbeRight, &["Alt+D"]),
+    (Action::ProbeUp, &["Alt+E"]),
+    (Action::ProbeDown, &["Alt+Q"]),
+    (Action::StatsGraph, &["L"]),
+    (Action::GraphSeries(1), &["Shift+Key1"]),
+    (Action::GraphSeries(2), &["Shift+Key2"]),
+    (Action::GraphSeries(3), &["Shift+Key3"]),
+    (Action::GraphSeries(4), &["Shift+Key4"]),
+    (Action::ExportStats, &["Shift+L"]),
+];
+
+/* NAMES AS THEY ARE WRITTEN IN assets/input.ron, THE SAME AS THE VARIANTS */
+macro_rules! key_names {
+    ($($key:ident),* $(,)?) => {
+        const KEY_NAMES: &[(&str, KeyCode)] = &[$((stringify!($key), KeyCode::$key)),*];
+    };
+}
+
+key_names![
+    Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R,
+    S, T, U, V, W, X, Y, Z, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12, Escape, Insert, Home, Delete, End,
+    PageDown, PageUp, Left, Up, Right, Down, Back, Return, Space, Tab, Numpad0, Numpad1, Numpad2, Numpad3, Numpad4,
+    Numpad5, Numpad6, Numpad7, Numpad8, Numpad9, Add, Subtract, Multiply, Divide, Minus, Equals, Comma, Period, Slash,
+    Backslash, Semicolon, Apostrophe, Grave, LBracket, RBracket, LAlt, RAlt, LControl, RControl, LShift, RShift,
+];
+
+const MOUSE_NAMES: &[(&str, MouseButton)] =
+    &[("MouseLeft", MouseButton::Left), ("MouseRight", MouseButton::Right), ("MouseMiddle", MouseButton::Middle)];
+
+const MODIFIER_NAMES: &[(&str, u8)] = &[("Ctrl", CTRL), ("Shift", SHIFT), ("Alt", ALT)];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Button {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+/* A BUTTON AND THE MODIFIERS THAT MUST BE HELD WITH IT, WRITTEN E.G. "Ctrl+S" OR "Alt+MouseLeft" */
+/* LEFT AND RIGHT MODIFIER KEYS COUNT THE SAME, OTHER MODIFIERS MAY BE HELD AS WELL */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Binding {
+    button: Button,
+    modifiers: u8,
+}
+
+impl FromStr for Binding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        let name = parts.pop().unwrap_or_default();
+        let button = KEY_NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|&(_, key)| Button::Key(key))
+            .or_else(|| MOUSE_NAMES.iter().find(|(n, _)| *n == name).map(|&(_, button)| Button::Mouse(button)))
+            .ok_or_else(|| format!("unknown key or mouse button '{}' in '{}'", name, s))?;
+        let mut modifiers = 0;
+        for part in parts {
+            match MODIFIER_NAMES.iter().find(|(n, _)| *n == part) {
+                Some(&(_, bit)) => modifiers |= bit,
+                None => return Err(format!("unknown modifier '{}' in '{}', expected Ctrl, Shift or Alt", part, s)),
+            }
+        }
+        Ok(Self { button, modifiers })
+    }
+}
+
+impl TryFrom<String> for Binding {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &(name, bit) in MODIFIER_NAMES {
+            if self.modifiers & bit != 0 {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match self.button {
+            Button::Key(key) => match KEY_NAMES.iter().find(|&&(_, k)| k == key) {
+                Some((name, _)) => write!(f, "{}", name),
+                None => write!(f, "{:?}", key),
+            },
+            Button::Mouse(button) => match MOUSE_NAMES.iter().find(|&&(_, b)| b == button) {
+                Some((name, _)) => write!(f, "{}", name),
+                None => write!(f, "{:?}", button),
+            },
+        }
+    }
+}
+
+fn held_modifiers(keys: &Input<KeyCode>) -> u8 {
+    let held = |l, r| keys.pressed(l) || keys.pressed(r);
+    let mut modifiers = 0;
+    if held(KeyCode::LControl, KeyCode::RControl) {
+        modifiers |= CTRL;
+    }
+    if held(KeyCode::LShift, KeyCode::RShift) {
+        modifiers |= SHIFT;
+    }
+    if held(KeyCode::LAlt, KeyCode::RAlt) {
+        modifiers |= ALT;
+    }
+    modifiers
+}
+
+/* EVERY BINDING OF EVERY ACTION, AN ACTION MAY HAVE SEVERAL OR NONE */
+#[derive(Clone, Debug)]
+pub struct InputMap {
+    bindings: Vec<(Action, Binding)>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        let bindings = DEFAULT_BINDINGS
+            .iter()
+            .flat_map(|&(action, names)| {
+                names.iter().map(move |name| (action, name.parse().expect("invalid default binding")))
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl InputMap {
+    /* ACTIONS LEFT OUT OF THE FILE KEEP THEIR DEFAULT BINDINGS, AN EMPTY LIST UNBINDS ONE. THE FILE IS READ */
+    /* IN ACTION ORDER, SO THE BINDINGS AND THE CONFLICTS WARNED ABOUT COME OUT THE SAME ON EVERY RUN */
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let file: BTreeMap<Action, Vec<Binding>> = ron::de::from_str(text).map_err(|err| err.to_string())?;
+        let mut map = Self::default();
+        map.bindings.retain(|(action, _)| !file.contains_key(action));
+        for (action, bindings) in file {
+            if let Action::Fabric(n) = action {
+                if !(1..=FABRIC_SLOTS).contains(&n) {
+                    return Err(format!("Fabric({}) is not between 1 and {}", n, FABRIC_SLOTS));
+                }
+            }
+            if let Action::GraphSeries(n) = action {
+                if !(1..=SERIES).contains(&n) {
+                    return Err(format!("GraphSeries({}) is not between 1 and {}", n, SERIES));
+                }
+            }
+            map.bindings.extend(bindings.into_iter().map(|binding| (action, binding)));
+        }
+        Ok(map)
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn load_or_default() -> Self {
+        let map = Self::load(BINDINGS_PATH).unwrap_or_else(|err| {
+            if err.kind() != io::ErrorKind::NotFound {
+                warn!("failed to load {}: {}", BINDINGS_PATH, err);
+            }
+            Self::default()
+        });
+        map.warn_conflicts();
+        map
+    }
+
+    /* PAIRS OF ACTIONS THAT ONE AND THE SAME BINDING TRIGGERS TOGETHER */
+    pub fn conflicts(&self) -> Vec<(Binding, Action, Action)> {
+        let mut conflicts = Vec::new();
+        for (i, &(a, binding)) in self.bindings.iter().enumerate() {
+            for &(b, other) in &self.bindings[i + 1..] {
+                let exclusive = |x: Action, y: Action| x.replay_only() && y.off_while_replaying();
+                if a != b && binding == other && !exclusive(a, b) && !exclusive(b, a) {
+                    conflicts.push((binding, a, b));
+                }
+            }
+        }
+        conflicts
+    }
+
+    fn warn_conflicts(&self) {
+        for (binding, a, b) in self.conflicts() {
+            warn!("{} is bound to both {:?} and {:?}, both fire when it is pressed", binding, a, b);
+        }
+    }
+
+    /* EVERY ACTION WITH AN ACTIVE BINDING, EACH ONCE. A TAP SHORTER THAN A FRAME STILL COUNTS FOR THAT FRAME */
+    pub fn held(&self, keys: &Input<KeyCode>, mouse: &Input<MouseButton>) -> Vec<Action> {
+        let down = |button| match button {
+            Button::Key(key) => keys.pressed(key) || keys.just_pressed(key),
+            Button::Mouse(button) => mouse.pressed(button) || mouse.just_pressed(button),
+        };
+        let modifiers = held_modifiers(keys);
+        let mut held = Vec::new();
+        for (action, binding) in &self.bindings {
+            if !held.contains(action) && self.active(binding, modifiers, &down) {
+                held.push(*action);
+            }
+        }
+        held
+    }
+
+    /* A BINDING IS ACTIVE WHEN ITS BUTTON AND MODIFIERS ARE DOWN, UNLESS ANOTHER BINDING ON THE SAME BUTTON NEEDS */
+    /* MORE OF THE HELD MODIFIERS, SO E.G. Shift+C DOES NOT ALSO TRIGGER WHAT C ALONE IS BOUND TO */
+    fn active(&self, binding: &Binding, modifiers: u8, down: impl Fn(Button) -> bool) -> bool {
+        let subset = |a: u8, b: u8| a & b == a;
+        down(binding.button)
+            && subset(binding.modifiers, modifiers)
+            && !self.bindings.iter().any(|(_, other)| {
+                other.button == binding.button
+                    && other.modifiers != binding.modifiers
+                    && subset(binding.modifiers, other.modifiers)
+                    && subset(other.modifiers, modifiers)
+            })
+    }
+}
+
+/* TURN THE RAW KEYS AND MOUSE BUTTONS INTO ACTIONS, BEFORE ANY SYSTEM OF THE UPDATE STAGE LOOKS AT THEM */
+pub fn resolve(
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    map: Res<InputMap>,
+    mut actions: ResMut<Input<Action>>,
+) {
+    actions.update();
+    let held = map.held(&keys, &mouse);
+    for &action in &held {
+        if !actions.pressed(action) {
+            actions.press(action);
+        }
+    }
+    /* ALSO RELEASES WHAT WAS HELD WHEN ITS BINDING WENT AWAY WITH A RELOAD */
+    let released: Vec<Action> = actions.get_pressed().copied().filter(|action| !held.contains(action)).collect();
+    for action in released {
+        actions.release(action);
+    }
+}
+
+#[derive(Default)]
+pub struct Watcher {
+    since_poll: f32,
+    modified: Option<SystemTime>,
+}
+
+/* REBINDING AT RUNTIME: A SAVED assets/input.ron REPLACES THE MAP, AN INVALID ONE KEEPS THE PREVIOUS BINDINGS */
+pub fn watch(time: Res<Time>, mut watcher: Local<Watcher>, mut map: ResMut<InputMap>) {
+    watcher.since_poll += time.delta_seconds;
+    if watcher.since_poll < POLL_INTERVAL {
+        return;
+    }
+    watcher.since_poll = 0.;
+
+    let modified = match fs::metadata(BINDINGS_PATH).and_then(|m| m.modified()) {
+        Ok(modified) => modified,
+        Err(_) => return,
+    };
+    match watcher.modified.replace(modified) {
+        Some(previous) if previous != modified => {}
+        _ => return,
+    }
+
+    match InputMap::load(BINDINGS_PATH) {
+        Ok(new) => {
+            info!("reloaded {}", BINDINGS_PATH);
+            new.warn_conflicts();
+            *map = new;
+        }
+        Err(err) => error!("failed to read {}, keeping the previous bindings: {}", BINDINGS_PATH, err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn held(map: &InputMap, keys: &[KeyCode], buttons: &[MouseButton]) -> Vec<Action> {
+        let (mut key_input, mut mouse_input) = (Input::default(), Input::default());
+        keys.iter().for_each(|&key| key_input.press(key));
+        buttons.iter().for_each(|&button| mouse_input.press(button));
+        map.held(&key_input, &mouse_input)
+    }
+
+    /* THE SHIPPED BINDINGS LOAD WITHOUT CONFLICTS, Shift+C ONLY PINS EVERYTHING AND THE LEFT BUTTON ORBITS WITH */
+    /* Alt HELD WITHOUT TOGGLING PINS */
+    #[test]
+    fn shipped_bindings() {
+        let shipped = InputMap::load(BINDINGS_PATH).unwrap();
+        assert!(shipped.conflicts().is_empty(), "{:?}", shipped.conflicts());
+        let pin_all = held(&shipped, &[KeyCode::LShift, KeyCode::C], &[]);
+        assert!(pin_all.contains(&Action::PinAll) && !pin_all.contains(&Action::ClearPins), "{:?}", pin_all);
+        let orbit = held(&shipped, &[KeyCode::LAlt], &[MouseButton::Left]);
+        assert!(orbit.contains(&Action::Orbit) && !orbit.contains(&Action::TogglePin), "{:?}", orbit);
+    }
+
+    /* SWAPPING THE PIN AND TEAR BUTTONS SWAPS WHAT THE LEFT AND MIDDLE BUTTONS DO, A BUTTON BOUND TWICE AND A */
+    /* MISSPELLED ONE ARE CAUGHT */
+    #[test]
+    fn edited_bindings() {
+        let swapped = InputMap::parse(r#"{ TogglePin: ["MouseMiddle"], Tear: ["MouseLeft"] }"#).unwrap();
+        assert_eq!(held(&swapped, &[], &[MouseButton::Left]), [Action::Tear]);
+        assert_eq!(held(&swapped, &[], &[MouseButton::Middle]), [Action::TogglePin]);
+        assert_eq!(InputMap::parse(r#"{ Freeze: ["T"] }"#).unwrap().conflicts().len(), 1);
+        assert!(InputMap::parse(r#"{ Tear: ["Mouse4"] }"#).is_err());
+        assert!(InputMap::parse(r#"{ Tear: ["Hyper+T"] }"#).is_err());
+    }
+}
@@ -0,0 +1,182 @@
+use std::cmp::Ordering::Equal;
+
+use nalgebra::Isometry3;
+
+use crate::{F, I, P, V};
+
+/* BOUNDING VOLUME HIERARCHY OVER AXIS-ALIGNED BOXES */
+/* MEANT TO BE REBUILT EVERY STEP, REUSING ITS BUFFERS */
+
+// MAXIMUM NUMBER OF BOXES STORED IN A SINGLE LEAF
+const LEAF_SIZE: I = 4;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Aabb {
+    pub min: P,
+    pub max: P,
+}
+
+impl Aabb {
+    pub fn empty() -> Self {
+        Self {
+            min: P::new(F::INFINITY, F::INFINITY, F::INFINITY),
+            max: P::new(F::NEG_INFINITY, F::NEG_INFINITY, F::NEG_INFINITY),
+        }
+    }
+
+    pub fn around(p: P, r: F) -> Self {
+        Self {
+            min: P::new(p.x - r, p.y - r, p.z - r),
+            max: P::new(p.x + r, p.y + r, p.z + r),
+        }
+    }
+
+    pub fn from_points(ps: &[P]) -> Self {
+        ps.iter().fold(Self::empty(), |aabb, &p| aabb.merge(&Self::around(p, 0.)))
+    }
+
+    pub fn merge(&self, other: &Self) -> Self {
+        Self {
+            min: P::new(
+                self.min.x.min(other.min.x),
+                self.min.y.min(other.min.y),
+                self.min.z.min(other.min.z),
+            ),
+            max: P::new(
+                self.max.x.max(other.max.x),
+                self.max.y.max(other.max.y),
+                self.max.z.max(other.max.z),
+            ),
+        }
+    }
+
+    pub fn inflated(&self, r: F) -> Self {
+        Self {
+            min: self.min - V::repeat(r),
+            max: self.max + V::repeat(r),
+        }
+    }
+
+    /* BOX AROUND THE EIGHT TRANSFORMED CORNERS, LOOSER THAN THE BOX WHEN iso ROTATES */
+    pub fn transformed(&self, iso: &Isometry3<F>) -> Self {
+        let corners: Vec<_> = (0..8)
+            .map(|k| {
+                let pick = |axis: I| if k & (1 << axis) == 0 { self.min[axis] } else { self.max[axis] };
+                iso * P::new(pick(0), pick(1), pick(2))
+            })
+            .collect();
+        Self::from_points(&corners)
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        (0..3).all(|i| self.min[i] <= other.max[i] && other.min[i] <= self.max[i])
+    }
+
+    /* SLAB TEST OF THE RAY origin + t dir FOR t >= 0 */
+    pub fn hits_ray(&self, origin: P, dir: V) -> bool {
+        let (mut t_enter, mut t_exit) = (0., F::INFINITY);
+        for i in 0..3 {
+            if dir[i].abs() < F::EPSILON {
+                if origin[i] < self.min[i] || origin[i] > self.max[i] {
+                    return false;
+                }
+                continue;
+            }
+            let (t0, t1) = ((self.min[i] - origin[i]) / dir[i], (self.max[i] - origin[i]) / dir[i]);
+            t_enter = t_enter.max(t0.min(t1));
+            t_exit = t_exit.min(t0.max(t1));
+        }
+        t_enter <= t_exit
+    }
+
+    fn center(&self) -> P {
+        nalgebra::center(&self.min, &self.max)
+    }
+
+    fn longest_axis(&self) -> I {
+        let d = self.max - self.min;
+        if d.x >= d.y && d.x >= d.z {
+            0
+        } else if d.y >= d.z {
+            1
+        } else {
+            2
+        }
+    }
+}
+
+#[derive(Clone)]
+enum NodeKind {
+    Leaf { start: I, end: I },
+    Branch { left: I, right: I },
+}
+
+#[derive(Clone)]
+struct Node {
+    aabb: Aabb,
+    kind: NodeKind,
+}
+
+#[derive(Clone, Default)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    order: Vec<I>,
+}
+
+impl Bvh {
+    pub fn build(&mut self, boxes: &[Aabb]) {
+        self.nodes.clear();
+        self.order.clear();
+        self.order.extend(0..boxes.len());
+        if !boxes.is_empty() {
+            self.build_node(boxes, 0, boxes.len());
+        }
+    }
+
+    /* SPLIT AT THE MEDIAN OF THE BOX CENTERS ALONG THE LONGEST AXIS */
+    fn build_node(&mut self, boxes: &[Aabb], start: I, end: I) -> I {
+        let aabb = self.order[start..end]
+            .iter()
+            .fold(Aabb::empty(), |aabb, &i| aabb.merge(&boxes[i]));
+        let idx = self.nodes.len();
+        self.nodes.push(Node {
+            aabb,
+            kind: NodeKind::Leaf { start, end },
+        });
+
+        if end - start > LEAF_SIZE {
+            let axis = aabb.longest_axis();
+            let mid = (start + end) / 2;
+            self.order[start..end].select_nth_unstable_by(mid - start, |&a, &b| {
+                boxes[a].center()[axis]
+                    .partial_cmp(&boxes[b].center()[axis])
+                    .unwrap_or(Equal)
+            });
+            let left = self.build_node(boxes, start, mid);
+            let right = self.build_node(boxes, mid, end);
+            self.nodes[idx].kind = NodeKind::Branch { left, right };
+        }
+        idx
+    }
+
+    /* CALL f WITH THE INDEX OF EVERY BOX POSSIBLY OVERLAPPING THE QUERY BOX */
+    pub fn query(&self, aabb: &Aabb, f: &mut impl FnMut(I)) {
+        if !self.nodes.is_empty() {
+            self.query_node(0, aabb, f);
+        }
+    }
+
+    fn query_node(&self, idx: I, aabb: &Aabb, f: &mut impl FnMut(I)) {
+        let node = &self.nodes[idx];
+        if !node.aabb.intersects(aabb) {
+            return;
+        }
+        match node.kind {
+            NodeKind::Leaf { start, end } => self.order[start..end].iter().for_each(|&i| f(i)),
+            NodeKind::Branch { left, right } => {
+                self.query_node(left, aabb, f);
+                self.query_node(right, aabb, f);
+            }
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use bevy::{
+    input::mouse::{MouseMotion, MouseWheel},
+    prelude::*,
+    render::{
+        camera::{Camera, OrthographicProjection, VisibleEntities, WindowOrigin},
+        render_graph::base,
+    },
+};
+
+use crate::{bindings::Action, pick::Grab};
+
+/* ORBIT CAMERA FOR THE EXAMPLE SCENE */
+/* HOLD LEFT ALT AND LEFT-DRAG TO ROTATE, MIDDLE-DRAG TO PAN, SCROLL TO ZOOM */
+/* SCROLLING WITH THE PICK MODIFIER HELD RESIZES THE PICK RADIUS INSTEAD, ALL OF IT REBINDABLE IN assets/input.ron */
+
+const ROTATE_SPEED: f32 = 0.005;
+const PAN_SPEED: f32 = 0.002;
+const ZOOM_SPEED: f32 = 0.1;
+const MIN_DISTANCE: f32 = 1.;
+const MAX_PITCH: f32 = 1.5;
+
+// WORLD UNITS PER PIXEL OF THE ORTHOGRAPHIC CAMERA PER UNIT OF DISTANCE, WHICH SHOWS ABOUT AS MUCH AROUND
+// THE FOCUS AS THE PERSPECTIVE CAMERA DOES IN A 720 PIXEL HIGH WINDOW, AND ITS FAR PLANE IN PIXELS
+const ORTHOGRAPHIC_SCALE: f32 = 1.15e-3;
+const ORTHOGRAPHIC_FAR: f32 = 1e5;
+
+pub struct OrbitCamera {
+    pub yaw: f32,
+    pub pitch: f32,
+    pub distance: f32,
+    pub focus: Vec3,
+    /* PARALLEL PROJECTION, ZOOMED BY SCALING THE TRANSFORM SINCE ITS PROJECTION IS MEASURED IN PIXELS */
+    pub orthographic: bool,
+}
+
+impl OrbitCamera {
+    pub fn looking_at(eye: Vec3, focus: Vec3) -> Self {
+        let offset = eye - focus;
+        let distance = offset.length();
+        Self {
+            yaw: offset.x().atan2(offset.z()),
+            pitch: (offset.y() / distance).asin(),
+            distance,
+            focus,
+            orthographic: false,
+        }
+    }
+
+    fn eye(&self) -> Vec3 {
+        self.focus
+            + self.distance
+                * Vec3::new(
+                    self.pitch.cos() * self.yaw.sin(),
+                    self.pitch.sin(),
+                    self.pitch.cos() * self.yaw.cos(),
+                )
+    }
+
+    pub fn transform(&self) -> Transform {
+        let mut transform = Transform::from_translation(self.eye()).looking_at(self.focus, Vec3::unit_y());
+        if self.orthographic {
+            transform.scale = Vec3::splat(self.distance * ORTHOGRAPHIC_SCALE);
+        }
+        transform
+    }
+
+    /* SPAWN THE 3D CAMERA THIS ORBIT DRIVES, WITH THE PROJECTION IT ASKS FOR */
+    pub fn spawn(self, commands: &mut Commands) -> &mut Commands {
+        let transform = self.transform();
+        let commands = match self.orthographic {
+            true => commands.spawn((
+                Camera { name: Some(base::camera::CAMERA3D.to_string()), ..Default::default() },
+                OrthographicProjection {
+                    far: ORTHOGRAPHIC_FAR,
+                    window_origin: WindowOrigin::Center,
+                    ..Default::default()
+                },
+                VisibleEntities::default(),
+                transform,
+                GlobalTransform::default(),
+            )),
+            false => commands.spawn(Camera3dComponents { transform, ..Default::default() }),
+        };
+        #[cfg(feature = "mod_picking")]
+        commands.with(bevy_mod_picking::PickSource::default());
+        commands.with(self)
+    }
+}
+
+#[derive(Default)]
+pub struct OrbitState {
+    motion: EventReader<MouseMotion>,
+    wheel: EventReader<MouseWheel>,
+}
+
+pub fn orbit(
+    mut state: Local<OrbitState>,
+    actions: Res<Input<Action>>,
+    grab: Res<Grab>,
+    motion_events: Res<Events<MouseMotion>>,
+    wheel_events: Res<Events<MouseWheel>>,
+    mut query: Query<(&mut OrbitCamera, &mut Transform)>,
+) {
+    let delta = state
+        .motion
+        .iter(&motion_events)
+        .fold(Vec2::zero(), |acc, e| acc + e.delta);
+    let scroll: f32 = state.wheel.iter(&wheel_events).map(|e| e.y).sum();
+    let adjusting = actions.pressed(Action::Modify) || actions.pressed(Action::PaintWeights) || grab.soft_dragging();
+    let scroll = if adjusting { 0. } else { scroll };
+    let rotating = actions.pressed(Action::Orbit);
+    let panning = actions.pressed(Action::Pan);
+
+    for (mut camera, mut transform) in query.iter_mut() {
+        if rotating {
+            camera.yaw -= delta.x() * ROTATE_SPEED;
+            camera.pitch = (camera.pitch + delta.y() * ROTATE_SPEED).max(-MAX_PITCH).min(MAX_PITCH);
+        }
+        if panning {
+            /* PAN IN THE PLANE OF THE SCREEN, SCALED BY DISTANCE TO FEEL CONSISTENT */
+            let right = transform.rotation.mul_vec3(Vec3::unit_x());
+            let up = transform.rotation.mul_vec3(Vec3::unit_y());
+            let scale = camera.distance * PAN_SPEED;
+            camera.focus += (-right * delta.x() + up * delta.y()) * scale;
+        }
+        camera.distance = (camera.distance * (1. - scroll * ZOOM_SPEED)).max(MIN_DISTANCE);
+
+        *transform = camera.transform();
+    }
+}
@@ -0,0 +1,268 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, SyncSender},
+        Mutex,
+    },
+    thread,
+};
+
+use bevy::{prelude::*, render::camera::Camera};
+
+use crate::{bindings::Action, camera::OrbitCamera, cloth::Cloth, to_array, I};
+
+/* STILLS AND IMAGE SEQUENCES OF THE SIMULATION AS PNG FILES IN captures/ */
+/* F12 SAVES A STILL, F11 STARTS/STOPS SAVING EVERY FRAME INTO A NEW SEQUENCE DIRECTORY */
+/* THIS VERSION OF BEVY CANNOT READ THE SWAPCHAIN BACK, SO THE CLOTHS ARE RASTERIZED OFFLINE FROM */
+/* THE CAMERA'S VIEW; DURING REPLAY THIS CAPTURES THE RECORDED PARTICLE STATE FRAME BY FRAME */
+/* THE MAIN THREAD ONLY COPIES POSITIONS, A WORKER THREAD RASTERIZES AND ENCODES */
+
+pub const CAPTURE_DIR: &str = "captures";
+
+// FLAT COLORS OF THE BACKGROUND AND THE CLOTH, THE CLOTH IS LIT BY A LIGHT AT THE CAMERA
+const BACKGROUND: [f32; 3] = [0.1, 0.1, 0.12];
+const CLOTH_COLOR: [f32; 3] = [0.8, 0.3, 0.3];
+const AMBIENT: f32 = 0.25;
+
+// FRAMES WAITING FOR THE WORKER BEFORE capture BLOCKS, SO A SLOW DISK HOLDS THE FRAME RATE BACK INSTEAD OF
+// QUEUING COPIES OF THE CLOTHS WITHOUT BOUND. A SEQUENCE KEEPS EVERY FRAME THIS WAY
+const QUEUED_FRAMES: usize = 4;
+
+/* EVERYTHING THE WORKER NEEDS TO DRAW ONE FRAME */
+struct Frame {
+    path: PathBuf,
+    width: usize,
+    height: usize,
+    view_proj: Mat4,
+    eye: Vec3,
+    positions: Vec<[f32; 3]>,
+    faces: Vec<[I; 3]>,
+}
+
+pub struct Capture {
+    /* SENDERS ARE NOT Sync, BUT RESOURCES HAVE TO BE */
+    sender: Mutex<SyncSender<Frame>>,
+    sequence: Option<(PathBuf, I)>,
+}
+
+impl Default for Capture {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::sync_channel::<Frame>(QUEUED_FRAMES);
+        thread::spawn(move || {
+            for frame in receiver {
+                if let Err(err) = frame.write() {
+                    error!("failed to write {}: {}", frame.path.display(), err);
+                }
+            }
+        });
+        Self { sender: Mutex::new(sender), sequence: None }
+    }
+}
+
+/* FIRST PATH WITH THE GIVEN NUMBERING THAT DOES NOT EXIST YET, SO EARLIER CAPTURES ARE KEPT */
+fn next_free(name: impl Fn(I) -> String) -> PathBuf {
+    (0..).map(|n| Path::new(CAPTURE_DIR).join(name(n))).find(|p| !p.exists()).unwrap()
+}
+
+pub fn capture(
+    actions: Res<Input<Action>>,
+    windows: Res<Windows>,
+    mut capture: ResMut<Capture>,
+    cameras: Query<(&OrbitCamera, &Camera, &GlobalTransform)>,
+    cloths: Query<&Cloth>,
+) {
+    if actions.just_pressed(Action::Sequence) {
+        capture.sequence = match capture.sequence.take() {
+            Some((dir, frames)) => {
+                info!("captured {} frames to {}", frames, dir.display());
+                None
+            }
+            None => {
+                let dir = next_free(|n| format!("sequence_{:03}", n));
+                match fs::create_dir_all(&dir) {
+                    Ok(()) => Some((dir, 0)),
+                    Err(err) => {
+                        error!("failed to create {}: {}", dir.display(), err);
+                        None
+                    }
+                }
+            }
+        };
+    }
+
+    let path = match capture.sequence.as_mut() {
+        Some((dir, frames)) => {
+            *frames += 1;
+            dir.join(format!("frame_{:05}.png", *frames - 1))
+        }
+        None if actions.just_pressed(Action::Screenshot) => {
+            if let Err(err) = fs::create_dir_all(CAPTURE_DIR) {
+                error!("failed to create {}: {}", CAPTURE_DIR, err);
+                return;
+            }
+            next_free(|n| format!("still_{:04}.png", n))
+        }
+        None => return,
+    };
+
+    let (window, (_, camera, transform)) = match (windows.get_primary(), cameras.iter().next()) {
+        (Some(window), Some(camera)) if window.width() > 0 && window.height() > 0 => (window, camera),
+        _ => return,
+    };
+    let mut frame = Frame {
+        path,
+        width: window.width() as usize,
+        height: window.height() as usize,
+        view_proj: camera.projection_matrix * transform.compute_matrix().inverse(),
+        eye: transform.translation,
+        positions: vec![],
+        faces: vec![],
+    };
+    for cloth in cloths.iter() {
+        let offset = frame.positions.len();
+        frame.positions.extend(cloth.particles.iter().map(|&p| to_array(cloth.to_world(p))));
+        frame.faces.extend(cloth.faces().map(|[a, b, c]| [a + offset, b + offset, c + offset]));
+    }
+    if let Err(err) = capture.sender.lock().unwrap().send(frame) {
+        error!("capture thread is gone: {}", err);
+    }
+}
+
+impl Frame {
+    fn write(&self) -> io::Result<()> {
+        let rgb = self.rasterize();
+        write_png(&self.path, self.width, self.height, &rgb)
+    }
+
+    /* Z-BUFFERED FLAT-SHADED TRIANGLES, LIT FROM BOTH SIDES SINCE THE CLOTH HAS NO BACK FACE */
+    fn rasterize(&self) -> Vec<u8> {
+        let (w, h) = (self.width, self.height);
+        let mut color = vec![BACKGROUND; w * h];
+        let mut depth = vec![f32::INFINITY; w * h];
+
+        /* SCREEN-SPACE PIXEL COORDINATES AND DEPTH, NONE FOR POINTS BEHIND THE CAMERA */
+        let screen: Vec<Option<Vec3>> = self
+            .positions
+            .iter()
+            .map(|&p| {
+                let clip = self.view_proj * Vec4::new(p[0], p[1], p[2], 1.);
+                if clip.w() <= 0. {
+                    return None;
+                }
+                let (x, y, z) = (clip.x() / clip.w(), clip.y() / clip.w(), clip.z() / clip.w());
+                Some(Vec3::new((x * 0.5 + 0.5) * w as f32, (0.5 - y * 0.5) * h as f32, z))
+            })
+            .collect();
+
+        let world = |i: I| Vec3::from(self.positions[i]);
+        for &[a, b, c] in &self.faces {
+            let (pa, pb, pc) = match (screen[a], screen[b], screen[c]) {
+                (Some(pa), Some(pb), Some(pc)) => (pa, pb, pc),
+                _ => continue,
+            };
+            let area = edge(pa, pb, pc);
+            if area.abs() < f32::EPSILON {
+                continue;
+            }
+
+            let (wa, wb, wc) = (world(a), world(b), world(c));
+            let normal = (wb - wa).cross(wc - wa).normalize();
+            let to_eye = (self.eye - (wa + wb + wc) / 3.).normalize();
+            let light = AMBIENT + (1. - AMBIENT) * normal.dot(to_eye).abs();
+            let shade = [CLOTH_COLOR[0] * light, CLOTH_COLOR[1] * light, CLOTH_COLOR[2] * light];
+
+            let x0 = pa.x().min(pb.x()).min(pc.x()).max(0.) as usize;
+            let y0 = pa.y().min(pb.y()).min(pc.y()).max(0.) as usize;
+            let x1 = (pa.x().max(pb.x()).max(pc.x()).ceil() as usize).min(w);
+            let y1 = (pa.y().max(pb.y()).max(pc.y()).ceil() as usize).min(h);
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let p = Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.);
+                    let (l0, l1, l2) = (edge(pb, pc, p) / area, edge(pc, pa, p) / area, edge(pa, pb, p) / area);
+                    if l0 < 0. || l1 < 0. || l2 < 0. {
+                        continue;
+                    }
+                    let z = l0 * pa.z() + l1 * pb.z() + l2 * pc.z();
+                    let k = y * w + x;
+                    if z < depth[k] {
+                        depth[k] = z;
+                        color[k] = shade;
+                    }
+                }
+            }
+        }
+
+        let mut rgb = Vec::with_capacity(w * h * 3);
+        for pixel in color {
+            rgb.extend(pixel.iter().map(|c| (c.max(0.).min(1.) * 255.).round() as u8));
+        }
+        rgb
+    }
+}
+
+/* TWICE THE SIGNED AREA OF abp, IGNORING DEPTH */
+fn edge(a: Vec3, b: Vec3, p: Vec3) -> f32 {
+    (b.x() - a.x()) * (p.y() - a.y()) - (b.y() - a.y()) * (p.x() - a.x())
+}
+
+/* MINIMAL PNG ENCODER: 8-BIT RGB, UNFILTERED ROWS IN UNCOMPRESSED DEFLATE BLOCKS */
+/* LARGE FILES, BUT NO DEPENDENCY AND NO COMPRESSION COST ON THE WORKER */
+fn write_png(path: &Path, width: usize, height: usize, rgb: &[u8]) -> io::Result<()> {
+    let mut raw = Vec::with_capacity((width * 3 + 1) * height);
+    for row in rgb.chunks(width * 3) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    let mut zlib = vec![0x78, 0x01];
+    let blocks = raw.chunks(0xffff).count();
+    for (k, block) in raw.chunks(0xffff).enumerate() {
+        let len = block.len() as u16;
+        zlib.push((k + 1 == blocks) as u8);
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut header = vec![];
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut w = BufWriter::new(File::create(path)?);
+    w.write_all(b"\x89PNG\r\n\x1a\n")?;
+    write_chunk(&mut w, b"IHDR", &header)?;
+    write_chunk(&mut w, b"IDAT", &zlib)?;
+    write_chunk(&mut w, b"IEND", &[])?;
+    w.flush()
+}
+
+fn write_chunk(w: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    w.write_all(&(data.len() as u32).to_be_bytes())?;
+    w.write_all(kind)?;
+    w.write_all(data)?;
+    w.write_all(&crc32(kind.iter().chain(data)).to_be_bytes())
+}
+
+fn crc32<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
@@ -1,287 +1,4880 @@
-use std::cmp::Ordering::Equal;
+use std::{
+    cmp::Ordering::{self, Equal},
+    collections::{BTreeMap, BinaryHeap, VecDeque},
+    ops::{Deref, DerefMut},
+    time::Instant,
+};
 
+use bevy::log::warn;
 use lazysort::SortedBy;
-use nalgebra::zero;
-// use nalgebra::geometry::Isometry3;
-// use ncollide3d::{query::{closest_points, ClosestPoints}, shape::{Ball, Triangle}};
-use rand::Rng;
+use nalgebra::{geometry::Isometry3, Affine3, Matrix3, Matrix4};
+use ncollide3d::{
+    query::{closest_points, ClosestPoints},
+    shape::{Ball, Triangle},
+};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 
+#[cfg(feature = "gpu")]
+use crate::gpu::GpuSolver;
 use crate::{
+    bvh::{Aabb, Bvh},
+    collider::{self, ColliderId, LayeredCollider},
+    config::{
+        ClothConfig, KillMode, LocalBoost, NormalWeighting, PinPreset, Plasticity, Pose, Pressure, SelfCollision,
+        SolveOrder, SolverMode, Stiffness, WeightTarget, Wind, WindOcclusion,
+    },
+    damping::DampingTuner,
+    error::ClothError,
+    event::ClothEvent,
+    field::ForceField,
+    lod::{self, LodLevel},
     grid::{Grid, GridIdx},
+    hierarchy::Hierarchy,
+    noise::Noise,
+    particles::{self, Anchor, Particles},
+    pillow,
+    render::{diagonals, triangles, RenderGrid},
+    slots::{SlotId, Slots},
+    spatial::SpatialGrid,
+    stats::{Phase, Stats, Stopwatch},
+    volume,
+    weld::Welded,
     *,
 };
 
-// NUMBER OF STEPS IN ITERATIVE CONSTRAINT SOLVING
-const CONSTRAINTS_ITER: I = 10;
+// A LOOSE PIECE WHOSE PARTICLES ALL STAY BELOW THIS SPEED FOR THIS MANY STEPS FALLS ASLEEP
+const SLEEP_SPEED: F = 0.05;
+const SLEEP_STEPS: I = 60;
+
+// A PARTICLE MOVING FURTHER THAN THIS IN ONE STEP MEANS THE SOLVER HAS BLOWN UP
+const UNSTABLE_DISPLACEMENT: F = 5.;
+
+// KNOWN-GOOD STATES KEPT FOR ROLLING BACK, THE OLDEST IS RESTORED AFTER A BLOW-UP
+const RECOVERY_HISTORY: I = 10;
+
+// STEPS RUN AT THE REDUCED TIME STEP AFTER A ROLLBACK, AND THE LOWEST IT IS HALVED TO
+const RECOVERY_STEPS: I = 30;
+const MIN_RECOVERY_SCALE: F = 1. / 16.;
+
+// MOVEMENT OF A PARTICLE IN ONE STEP BELOW WHICH THE RENDER MESH IS NOT UPDATED FOR IT
+const MESH_EPSILON: F = 1e-5;
+
+// NORMAL SPEED BELOW WHICH A COLLIDER CONTACT DOES NOT BOUNCE, WHATEVER ITS RESTITUTION
+const RESTITUTION_MIN_SPEED: F = 0.5;
+
+// STEPS BETWEEN MEASUREMENTS OF THE TOTAL STRUCTURAL LENGTH
+const CONTRACTION_INTERVAL: I = 10;
+
+// LARGEST CHANGE OF THE REST LENGTH SCALE PER MEASUREMENT, AND ITS LARGEST DEVIATION FROM 1
+const RETENSION_RATE: F = 1e-3;
+const MAX_RETENSION: F = 0.05;
+
+// NUMBER OF PASSES OF THE STRAIN LIMITING FIXUP
+const STRAIN_LIMIT_ITER: I = 3;
+
+// DEFAULT SIZE AND GRID RESOLUTION OF THE CLOTH: SUBDIVISIONS x SUBDIVISIONS
+pub const SIZE: (F, F) = (10., 12.);
+pub const SUBDIVISIONS: I = 30;
+
+// TIMES THE NEAREST-PARTICLE SEARCH DOUBLES ITS RADIUS BEFORE SCANNING EVERY PARTICLE
+const NEAREST_ROUNDS: I = 8;
+
+// SQUARED DISTANCES IN GRID STEPS FROM A RAY HIT TO TWO CORNERS THIS CLOSE ARE A TIE, SO A HIT ON AN EDGE
+// GOES THE SAME WAY WHICHEVER SIDE OF IT THE RAY WAS FOUND TO HIT
+const PICK_TIE: F = 1e-4;
+
+// STEPS A PARTICLE STAYS ACTIVE FOR THE EDGE-EDGE PASS AFTER IT LAST TOUCHED ANYTHING
+const EDGE_ACTIVE_STEPS: u8 = 10;
+
+// STEPS A CACHED COLLIDER CONTACT IS KEPT AFTER ITS PARTICLE HAS LEFT THE CONTACT PLANE
+const CONTACT_HYSTERESIS: u8 = 2;
+// HOW FAR, AS A FRACTION OF HALF THE THICKNESS, A PARTICLE OR COLLIDER MAY DRIFT BEFORE A CONTACT IS DROPPED
+const CONTACT_DRIFT: F = 0.5;
+
+// UNTANGLING AN OVERLAPPING START: PENETRATION, AS A FRACTION OF THE REST EDGE, THAT COUNTS AS DEEP
+// WHEN THE THICKNESS IS SMALLER, AND HOW FAR A PARTICLE MAY MOVE IN ONE STEP WHILE UNTANGLING
+const UNTANGLE_DEPTH: F = 0.25;
+pub const UNTANGLE_PUSH: F = 0.1;
+// DAMPING WHILE UNTANGLING, AND THE STEPS AFTER WHICH IT GIVES UP ON A CLOTH THAT STAYS INSIDE
+const UNTANGLE_DAMPING: F = 0.5;
+const UNTANGLE_STEPS: I = 600;
+// MOVEMENT OF EVERY PARTICLE IN ONE STEP, AS A FRACTION OF THE REST EDGE, BELOW WHICH A CLOTH IS QUIET
+const QUIET_MOTION: F = 0.05;
 
-// ENERGY DAMPING TO APPLY TO SYSTEM WHEN PERFORMING VERLET POSITION INTEGRATION
-const DAMPING: F = 0.995;
+// LOCAL BOOST: STRAIN OF A SPRING THAT HEATS UP ITS ENDS, THE FRACTION OF ITS HEAT A PARTICLE KEEPS EACH
+// STEP AFTER BEING HEATED TO 1, AND THE HEAT ABOVE WHICH IT IS HOT, SO ABOUT 10 STEPS
+const HOT_STRAIN: F = 0.05;
+const HEAT_DECAY: F = 0.8;
+const HOT_HEAT: F = 0.1;
 
-// GRID RESOLUTION OF THE CLOTH: SUBDIVISIONS x SUBDIVISIONS
-const SUBDIVISIONS: I = 30;
+// DAMPING, AS THE FRACTION OF ITS VELOCITY A PARTICLE KEEPS EACH STEP, WHILE settle_under_gravity DRAPES THE CLOTH
+const SETTLE_DAMPING: F = 0.98;
 
-// THRESHOLD FOR COLLISION CHECKING
-const EPSILON: F = 0.3;
+// RANGE scale_stiffness_near KEEPS THE PAINTED STIFFNESS MULTIPLIER OF A CONSTRAINT IN
+pub const MIN_PAINTED_STIFFNESS: F = 0.05;
+pub const MAX_PAINTED_STIFFNESS: F = 4.;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub struct Particle {
-    pub p: P,
-    old_p: P,
-    a: V,
-    m: F,
-    fixed: bool,
+pub enum ConstraintKind {
+    Structural,
+    Shear,
+    Flexion,
+}
+
+impl ConstraintKind {
+    pub const ALL: [ConstraintKind; 3] = [ConstraintKind::Structural, ConstraintKind::Shear, ConstraintKind::Flexion];
+}
+
+/* THREAD DIRECTION OF A SPRING: WARP RUNS ALONG THE ROWS OF THE GRID, WEFT DOWN ITS COLUMNS */
+/* ONLY TELLS STRUCTURAL SPRINGS APART, ANY SPRING NOT WITHIN A ROW COUNTS AS WEFT */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Weave {
+    Warp,
+    Weft,
+}
+
+/* A DIRECTION ACROSS THE GRID: X ALONG THE ROWS, FROM COLUMN TO COLUMN, Y DOWN THE COLUMNS */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Axis {
+    X,
+    Y,
+}
+
+/* WHETHER THE TEXTURE OF A TILED CLOTH REPEATS ONCE PER TILE OR STRETCHES ONCE ACROSS ALL OF THEM */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TileUvs {
+    Repeat,
+    Stretch,
+}
+
+/* WHERE A COPY OF A CLOTH GOES IN AN ASSEMBLED ONE: THE GRID INDEX ITS FIRST PARTICLE LANDS ON, */
+/* WHETHER ITS COLUMNS AND ROWS RUN BACKWARDS, AND THE MAP OF ITS POSITIONS INTO THE NEW LOCAL SPACE */
+struct Tile {
+    offset: GridIdx,
+    flip: (bool, bool),
+    transform: Affine3<F>,
+}
+
+impl Tile {
+    fn place(&self, (x, y): GridIdx, (w, h): GridIdx) -> GridIdx {
+        let x = if self.flip.0 { w - 1 - x } else { x };
+        let y = if self.flip.1 { h - 1 - y } else { y };
+        (self.offset.0 + x, self.offset.1 + y)
+    }
 }
 
-impl Particle {
-    fn new(x: F, y: F, z: F) -> Self {
-        let p = P::new(x, y, z);
+/* STABLE HANDLE TO A CONSTRAINT, UNAFFECTED BY OTHER CONSTRAINTS BEING ADDED OR REMOVED */
+pub type ConstraintId = SlotId;
+
+/* PAINTED WEIGHT PER GRID PARTICLE BETWEEN 0 AND 1, WHICH config.weight_binding TURNS INTO A FACTOR */
+/* ON ITS MASS, DRAG OR STIFFNESS. PARTICLES SPLIT OFF A CUT TAKE THEIR ORIGIN'S */
+pub type WeightMap = Grid<F>;
+
+/* STIFFNESS MULTIPLIERS PAINTED ONTO CONSTRAINTS, KEYED BY THE GRID INDICES THEIR ENDS CAME FROM IN */
+/* ORDER, SO THEY OUTLIVE A RESET AT THE SAME RESOLUTION. CONSTRAINTS LEFT OUT ARE AT 1 */
+pub type PaintedStiffness = BTreeMap<(GridIdx, GridIdx), F>;
+
+/* END POINTS ARE FLATTENED INDICES SO THEY CAN REFER TO PARTICLES SPLIT OFF PAST THE GRID */
+struct Constraint {
+    p1: I,
+    p2: I,
+    d: F,
+    kind: ConstraintKind,
+    weave: Weave,
+    /* WORN DOWN BY weaken_near FROM 1, THE CONSTRAINT TEARS ONCE IT DROPS BELOW ZERO */
+    resistance: F,
+    /* TOTAL CORRECTION OF p1 OVER THE LAST STEP, A FRACTION OF IT IS PRE-APPLIED IN THE NEXT */
+    accumulated: V,
+    /* PERMANENT CHANGE OF d RELATIVE TO THE REST LENGTH IT WAS BUILT WITH, AND STEPS IN A ROW PAST YIELD */
+    plastic: F,
+    yielding: I,
+    /* STIFFNESS FACTOR AND SHARE OF EACH CORRECTION p1 TAKES, p2 TAKING 2 - share, FROM A WEIGHT MAP */
+    /* BOUND TO THE STIFFNESS OR THE MASS, SEE Cloth::apply_weights. BOTH ARE 1 ON A UNIFORM CLOTH */
+    scale: F,
+    share: F,
+    /* STIFFNESS FACTOR PAINTED WITH Cloth::scale_stiffness_near, ON TOP OF scale */
+    painted: F,
+}
+
+impl Constraint {
+    fn new(p1: GridIdx, p2: GridIdx, kind: ConstraintKind, particles: &Grid<P>) -> Self {
         Self {
-            p,
-            old_p: p,
-            a: zero(),
-            m: 1.,
-            fixed: false,
+            p1: particles.flatten(p1),
+            p2: particles.flatten(p2),
+            d: (particles[p1] - particles[p2]).norm(),
+            kind,
+            weave: if p1.1 == p2.1 { Weave::Warp } else { Weave::Weft },
+            resistance: 1.,
+            accumulated: V::zeros(),
+            plastic: 0.,
+            yielding: 0,
+            scale: 1.,
+            share: 1.,
+            painted: 1.,
         }
     }
 
-    fn add_force(&mut self, f: V) {
-        self.a += f / self.m;
+    /* DISPLACEMENT OF p1 TOWARDS THE REST LENGTH, p2 IS DISPLACED BY THE NEGATION */
+    fn correction(&self, particles: &Grid<P>, dt: F, stiffness: &Stiffness) -> V {
+        let p12 = particles.data[self.p2] - particles.data[self.p1];
+        let d = p12.norm();
+        (stiffness.get(self.kind, self.weave) * self.scale * self.painted).min(1.) * dt * (d - self.d) * (p12 / d)
     }
-    fn offset(&mut self, v: V) {
-        if !self.fixed {
-            self.p += v;
-        }
+
+    /* TAKE THE MEAN STIFFNESS FACTOR OF THE END POINTS, AND SHARE THE CORRECTION IN INVERSE PROPORTION */
+    /* TO THEIR MASS FACTORS SO THE HEAVIER END MOVES LESS */
+    fn weigh(&mut self, mass: &[F], stiffness: &[F]) {
+        let (m1, m2) = (mass[self.p1], mass[self.p2]);
+        self.scale = (stiffness[self.p1] + stiffness[self.p2]) / 2.;
+        self.share = 2. * m2 / (m1 + m2);
+    }
+
+    /* RELATIVE DEVIATION FROM THE REST LENGTH */
+    fn strain(&self, particles: &Grid<P>) -> F {
+        ((particles.data[self.p2] - particles.data[self.p1]).norm() - self.d).abs() / self.d
+    }
+}
+
+/* GAUSS-SEIDEL UPDATE OF A SINGLE CONSTRAINT, RETURNING THE SIZE OF ITS CORRECTION */
+/* THE CORRECTION IS ALSO ACCUMULATED ON THE CONSTRAINT FOR WARM STARTING THE NEXT STEP */
+/* omega SCALES THE CORRECTION, SUCCESSIVE OVER-RELAXATION ABOVE 1 */
+fn gauss_seidel_update(particles: &mut Particles, c: &mut Constraint, dt: F, stiffness: &Stiffness, omega: F) -> F {
+    /* BOTH ENDS OF A CONSTRAINT ARE ALWAYS IN THE SAME PIECE, SO SLEEP TOGETHER */
+    if particles.asleep(c.p1) {
+        return 0.;
+    }
+    let f_c = c.correction(particles, dt, stiffness) * omega;
+    particles.offset(c.p1, f_c * c.share);
+    particles.offset(c.p2, -f_c * (2. - c.share));
+    c.accumulated += f_c;
+    f_c.norm()
+}
+
+/* TRIANGLE OF THE CLOTH SURFACE, SHARED BY THE INDEX BUFFER, WIND, SELF-COLLISION AND AREA CONSTRAINTS */
+#[derive(Clone, Copy)]
+struct Face {
+    v: [I; 3],
+    rest_area: F,
+}
+
+impl Face {
+    fn new(v: [I; 3], particles: &Grid<P>) -> Self {
+        let [a, b, c] = v;
+        let p = &particles.data;
+        Self { v, rest_area: (p[b] - p[a]).cross(&(p[c] - p[a])).norm() / 2. }
     }
+}
 
-    fn step(&mut self) {
-        if !self.fixed {
-            let tmp = self.p;
-            /* VERLET POSITION INTEGRATION */
-            self.p += DAMPING * (self.p - self.old_p) + self.a * DT_SQ;
-            self.old_p = tmp;
-            self.a = zero();
+/* ONE PASS PULLING EVERY FACE BACK TOWARDS ITS REST AREA, RETURNING THE LARGEST CORRECTION */
+/* EACH CORNER MOVES ALONG THE AREA GRADIENT, SCALED BY ITS INVERSE MASS SO PINS STAY PUT */
+fn area_pass(particles: &mut Particles, faces: &[Face], stiffness: F) -> F {
+    let mut max_correction: F = 0.;
+    for face in faces {
+        let [a, b, c] = face.v;
+        let (pa, pb, pc) = (particles.data[a], particles.data[b], particles.data[c]);
+        let n = (pb - pa).cross(&(pc - pa));
+        let area = n.norm() / 2.;
+        if area < F::EPSILON {
+            continue;
+        }
+        let n = n / (2. * area);
+        /* GRADIENT OF THE AREA WITH RESPECT TO EACH CORNER IS HALF THE OPPOSITE EDGE TURNED INWARDS */
+        let grads = [n.cross(&(pc - pb)) / 2., n.cross(&(pa - pc)) / 2., n.cross(&(pb - pa)) / 2.];
+        let w = [particles.inv_mass(a), particles.inv_mass(b), particles.inv_mass(c)];
+        let denom: F = (0..3).map(|k| w[k] * grads[k].norm_squared()).sum();
+        if denom < F::EPSILON {
+            continue;
+        }
+        let lambda = stiffness * (face.rest_area - area) / denom;
+        for (k, &i) in face.v.iter().enumerate() {
+            let delta = grads[k] * (lambda * w[k]);
+            particles.offset(i, delta);
+            max_correction = max_correction.max(delta.norm());
         }
     }
+    max_correction
 }
 
-struct Constraint {
-    p1: GridIdx,
-    p2: GridIdx,
-    d: F,
+/* ONE PASS OF THE VOLUME CONSTRAINT OF A CLOSED SURFACE, RETURNING THE LARGEST CORRECTION */
+/* THE GRADIENT OF THE VOLUME AT EACH PARTICLE IS A THIRD OF ITS AREA-WEIGHTED NORMAL */
+/* SO ALL PARTICLES MOVE OUTWARDS, OR INWARDS WHEN OVER-INFLATED, IN PROPORTION TO THE DEFICIT */
+fn pressure_pass(particles: &mut Particles, faces: &[Face], gradients: &mut Vec<V>, pressure: Pressure) -> F {
+    let p = &particles.data;
+    let volume = volume::signed_volume(p, faces.iter().map(|f| f.v));
+    gradients.clear();
+    gradients.resize(p.len(), V::zeros());
+    for &Face { v: [a, b, c], .. } in faces {
+        gradients[a] += p[b].coords.cross(&p[c].coords) / 6.;
+        gradients[b] += p[c].coords.cross(&p[a].coords) / 6.;
+        gradients[c] += p[a].coords.cross(&p[b].coords) / 6.;
+    }
+    let denom: F = gradients.iter().enumerate().map(|(i, g)| particles.inv_mass(i) * g.norm_squared()).sum();
+    if denom < F::EPSILON {
+        return 0.;
+    }
+
+    let lambda = pressure.stiffness * (pressure.volume - volume) / denom;
+    let mut max_correction: F = 0.;
+    for (i, g) in gradients.iter().enumerate() {
+        let delta = g * (lambda * particles.inv_mass(i));
+        particles.offset(i, delta);
+        max_correction = max_correction.max(delta.norm());
+    }
+    max_correction
 }
 
-impl Constraint {
-    fn new(p1: GridIdx, p2: GridIdx, particles: &Grid<Particle>) -> Self {
-        Self {
-            p1,
-            p2,
-            d: (particles[p1].p - particles[p2].p).norm(),
+/* FORCE OF THE AIR MOVING AT relative TO A FACE WITH UNIT normal: THE PART FLOWING INTO THE FACE */
+/* PUSHES ON IT UNLESS IT PASSES THROUGH THE PORES, THE PART ALONG IT DRAGS IT ALONG */
+/* LINEAR IN area AND IN 1 - porosity, SO A FLAT PATCH CATCHES (1 - porosity) OF THE SOLID FORCE */
+fn wind_force(relative: V, normal: V, area: F, porosity: F, air_drag: F) -> V {
+    let across = relative.dot(&normal);
+    let along = relative - normal * across;
+    (normal * (across * (1. - porosity)) + along * air_drag) * area
+}
+
+/* MOLLER-TRUMBORE: DISTANCE ALONG dir, IN UNITS OF ITS LENGTH, TO A TRIANGLE HIT FROM EITHER SIDE, */
+/* AND THE BARYCENTRIC WEIGHTS OF a, b AND c AT THE HIT */
+fn ray_triangle(origin: P, dir: V, [a, b, c]: [P; 3]) -> Option<(F, [F; 3])> {
+    let (e1, e2) = (b - a, c - a);
+    let h = dir.cross(&e2);
+    let det = e1.dot(&h);
+    if det.abs() < F::EPSILON {
+        return None;
+    }
+    let s = origin - a;
+    let u = s.dot(&h) / det;
+    if u < 0. || u > 1. {
+        return None;
+    }
+    let q = s.cross(&e1);
+    let v = dir.dot(&q) / det;
+    if v < 0. || u + v > 1. {
+        return None;
+    }
+    Some((e2.dot(&q) / det, [1. - u - v, u, v])).filter(|&(t, _)| t >= 0.)
+}
+
+/* DISTANCE ALONG dir, IN UNITS OF ITS LENGTH, TO WHERE A RAY PASSES CLOSEST TO THE SEGMENT a-b, */
+/* IF IT PASSES WITHIN radius OF IT, AND HOW FAR ALONG a-b THAT IS. BOTH ENDS ARE CLAMPED ONCE, WHICH */
+/* IS CLOSE ENOUGH FOR PICKING */
+fn ray_segment(origin: P, dir: V, a: P, b: P, radius: F) -> Option<(F, F)> {
+    let (e, r) = (b - a, origin - a);
+    let (dd, de, ee) = (dir.norm_squared(), dir.dot(&e), e.norm_squared());
+    if dd <= F::EPSILON {
+        return None;
+    }
+    let denom = dd * ee - de * de;
+    /* A RAY PARALLEL TO THE SEGMENT IS MEASURED FROM ITS START */
+    let s = match denom > F::EPSILON {
+        true => (de * e.dot(&r) - ee * dir.dot(&r)) / denom,
+        false => -dir.dot(&r) / dd,
+    };
+    let u = if ee > F::EPSILON { ((origin + dir * s.max(0.) - a).dot(&e) / ee).max(0.).min(1.) } else { 0. };
+    let closest = a + e * u;
+    let s = ((closest - origin).dot(&dir) / dd).max(0.);
+    Some((s, u)).filter(|_| (origin + dir * s - closest).norm() <= radius)
+}
+
+/* PARAMETERS s AND t OF THE CLOSEST POINTS p1 + s (q1 - p1) AND p2 + t (q2 - p2) OF TWO SEGMENTS */
+/* PARALLEL SEGMENTS PICK ANY CLOSEST PAIR, A DEGENERATE SEGMENT IS TREATED AS A POINT */
+fn segment_closest(p1: P, q1: P, p2: P, q2: P) -> (F, F) {
+    let (d1, d2, r) = (q1 - p1, q2 - p2, p1 - p2);
+    let (a, e, f) = (d1.norm_squared(), d2.norm_squared(), d2.dot(&r));
+    let clamp = |x: F| x.max(0.).min(1.);
+    if a <= F::EPSILON && e <= F::EPSILON {
+        return (0., 0.);
+    }
+    if a <= F::EPSILON {
+        return (0., clamp(f / e));
+    }
+    let c = d1.dot(&r);
+    if e <= F::EPSILON {
+        return (clamp(-c / a), 0.);
+    }
+    let b = d1.dot(&d2);
+    let denom = a * e - b * b;
+    let s = if denom > F::EPSILON { clamp((b * f - c * e) / denom) } else { 0. };
+    /* CLAMPING t TO THE SECOND SEGMENT MAY MOVE THE CLOSEST POINT ON THE FIRST */
+    let t = (b * s + f) / e;
+    match t {
+        t if t < 0. => (clamp(-c / a), 0.),
+        t if t > 1. => (clamp((b - c) / a), 1.),
+        t => (s, t),
+    }
+}
+
+/* STRUCTURAL ADJACENCY WITH REST LENGTHS, FOR GEODESIC DISTANCES ALONG THE CLOTH */
+fn structural_neighbors(constraints: &Slots<Constraint>, particles: &Grid<P>) -> Vec<Vec<(I, F)>> {
+    let mut neighbors = vec![vec![]; particles.len()];
+    for c in constraints.iter().filter(|c| c.kind == ConstraintKind::Structural) {
+        let (i1, i2) = (c.p1, c.p2);
+        neighbors[i1].push((i2, c.d));
+        neighbors[i2].push((i1, c.d));
+    }
+    neighbors
+}
+
+/* MIN-HEAP ENTRY FOR THE GEODESIC DISTANCE SEARCH */
+#[derive(PartialEq)]
+struct Frontier(F, I);
+
+impl Eq for Frontier {}
+
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Frontier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.partial_cmp(&self.0).unwrap_or(Equal)
+    }
+}
+
+/* A COLLIDER CONTACT KEPT FROM THE LAST STEP, WHERE THE PARTICLE WAS LEFT AND THE PLANE IT RESTS ON */
+#[derive(Clone, Copy)]
+struct CachedContact {
+    collider: ColliderId,
+    point: P,
+    normal: V,
+    /* CONSECUTIVE STEPS THE PARTICLE HAS BEEN OFF THE PLANE */
+    separated: u8,
+}
+
+/* A CONNECTED PART OF THE CLOTH, SEPARATE FROM THE REST ONCE TORN OFF */
+#[derive(Clone, Copy, Default)]
+struct Piece {
+    still_steps: I,
+    asleep: bool,
+}
+
+/* A POINT IN THE WORLD PULLING THE PARTICLES TOWARDS IT WITH INVERSE-SQUARE FALLOFF */
+#[derive(Clone, Copy, Debug)]
+pub struct Attractor {
+    pub point: P,
+    /* FORCE AT UNIT DISTANCE */
+    pub strength: F,
+    /* NOTHING IS PULLED BEYOND radius, AND THE PULL STOPS GROWING INSIDE core INSTEAD OF BLOWING UP */
+    pub radius: F,
+    pub core: F,
+}
+
+impl Attractor {
+    pub fn force(&self, p: P) -> V {
+        let d = self.point - p;
+        let dist = d.norm();
+        if dist > self.radius || dist <= F::EPSILON {
+            return V::zeros();
         }
+        d * (self.strength / (dist * dist.max(self.core).powi(2)))
     }
 }
 
-pub struct Cloth {
-    pub particles: Grid<Particle>,
-    constraints: Vec<Constraint>,
-    pub mesh_handle: Handle<Mesh>,
+/* A PARTICLE WHOSE POSITION IS DRIVEN BY THE TRANSFORM OF ANOTHER ENTITY */
+#[derive(Clone, Copy, Debug)]
+pub struct Attachment {
+    pub entity: Entity,
+    pub idx: GridIdx,
+    pub local_offset: V,
+    was: Anchor,
 }
 
-impl Cloth {
-    pub fn new(width: F, height: F, mut meshes: ResMut<Assets<Mesh>>) -> (Self, Handle<Mesh>) {
-        let parts: Vec<Particle> = (0..SUBDIVISIONS)
-            .flat_map(|y| {
-                (0..SUBDIVISIONS).map(move |x| {
-                    Particle::new(
-                        width * (x as F / SUBDIVISIONS as F),
-                        -height * (y as F / SUBDIVISIONS as F),
-                        (0.5 * height * (y as F / SUBDIVISIONS as F))
-                            + rand::thread_rng().gen_range(20., 20.1),
-                    )
-                })
-            })
-            .collect();
-        let mut particles = Grid::new(parts, SUBDIVISIONS);
-
-        /* SET 4 CORNERS TO BE FIXED AT SIMULATION START */
-        particles[(0, 0)].fixed = true;
-        particles[(1, 0)].fixed = true;
-        particles[(SUBDIVISIONS - 2, 0)].fixed = true;
-        particles[(SUBDIVISIONS - 1, 0)].fixed = true;
-        particles[(0, SUBDIVISIONS - 1)].fixed = true;
-        particles[(1, SUBDIVISIONS - 1)].fixed = true;
-        particles[(SUBDIVISIONS - 2, SUBDIVISIONS - 1)].fixed = true;
-        particles[(SUBDIVISIONS - 1, SUBDIVISIONS - 1)].fixed = true;
+/* A NAMED PART OF THE RENDER MESH DRAWN AS A SUB-MESH OF ITS OWN, E.G. WITH ANOTHER MATERIAL */
+/* SEE assign_region. IT SHARES THE VERTICES OF THE CLOTH, SO IT DEFORMS WITH THE REST OF IT */
+/* ITS MESH ASSET IS KEPT BY THE Cloth, BY THE INDEX OF THE REGION */
+#[derive(Clone, Debug)]
+pub struct TextureRegion {
+    pub name: String,
+    /* INCLUSIVE CORNERS, THE SMALLER FIRST */
+    pub rect: (GridIdx, GridIdx),
+    /* WHETHER THE SUB-MESH NEEDS ITS INDICES AND UVS WRITTEN AGAIN, NOT JUST ITS VERTICES */
+    stale: bool,
+}
+
+/* THE SIMULATION STATE OF A CLOTH: PARTICLES, CONSTRAINTS AND EVERYTHING THE SOLVER NEEDS, WITH NO */
+/* BEVY RESOURCES INVOLVED, SO HEADLESS RUNS AND TESTS BUILD AND STEP IT ON ITS OWN. Cloth ADDS THE MESH */
+/* ASSETS IT IS DRAWN WITH */
+pub struct ClothSim {
+    width: F,
+    height: F,
+    pub particles: Particles,
+    constraints: Slots<Constraint>,
+    /* SOLVE ORDER FOR SolveOrder::Shuffled, IDS OF REMOVED CONSTRAINTS ARE DROPPED FROM IT */
+    shuffled: Vec<ConstraintId>,
+    /* IDS OF EACH ConstraintKind IN GENERATION ORDER, FOR THE PER-KIND SWEEPS OF kind_iterations */
+    by_kind: [Vec<ConstraintId>; 3],
+    triangles: Vec<Face>,
+    /* PER QUAD, WHETHER config.diagonal SPLIT IT ALONG (x, y)-(x + 1, y + 1), FIXED AT CONSTRUCTION */
+    flipped: Grid<bool>,
+    weights: WeightMap,
+    painted: PaintedStiffness,
+    /* SET ONCE cut HAS CHANGED THE TOPOLOGY AWAY FROM THE REGULAR GRID */
+    torn: bool,
+    /* WHETHER THE TRIANGLES FORM A CLOSED SURFACE, WOUND OUTWARDS, WHICH PRESSURE NEEDS */
+    closed: bool,
+    render: RenderGrid,
+    /* THE MESH A CLOTH WAS BUILT FROM BY from_mesh, WHICH IT IS DRAWN AND RESET FROM, None ON A GRID */
+    welded: Option<Welded>,
+    /* SET WHEN PARTICLES MOVE, CLEARED BY update_mesh */
+    mesh_dirty: bool,
+    /* SET WHEN THE CLOTH WAS REGENERATED WITHOUT ACCESS TO ITS MESH, SO update_mesh REFILLS IT */
+    mesh_stale: bool,
+    regions: Vec<TextureRegion>,
+    pub config: ClothConfig,
+    pub stats: Stats,
+    /* SIMULATED TIME, DRIVES THE EVOLUTION OF THE WIND GUSTS */
+    time: F,
+    steps: I,
+    /* FRAMES' WORTH OF config.time_scale NOT YET TAKEN AS A STEP, SEE tick */
+    time_owed: F,
+    /* CURRENT REST LENGTHS RELATIVE TO THE ORIGINAL ONES, CHANGED ONLY BY retension */
+    rest_scale: F,
+    /* TIME STEPS THE NEXT STEP COVERS AND THE LEVEL OF DETAIL IT RUNS AT, SET BY set_lod */
+    dt_scale: F,
+    lod: LodLevel,
+    /* UNIFORM REST LENGTH FACTOR SET BY scale_rest_lengths, ON TOP OF rest_scale */
+    rest_factor: F,
+    /* FACTOR animate_rest_scale IS EASING TOWARDS AND THE STEPS LEFT TO GET THERE */
+    rest_animation: Option<(F, I)>,
+    /* RIGID MOVES APPLIED SINCE CONSTRUCTION, RE-APPLIED WHEN THE CLOTH IS REBUILT FROM ITS POSE */
+    placement: Isometry3<F>,
+    /* FROM THE LOCAL SPACE THE PARTICLES ARE SIMULATED IN TO THE WORLD, THE ENTITY'S TRANSFORM */
+    /* SET BEFORE EACH STEP. GRAVITY, WIND, COLLIDERS AND PICKED POINTS ARE IN WORLD SPACE */
+    frame: Isometry3<F>,
+    noise: Noise,
+    attachments: Vec<Attachment>,
+    /* IN WORLD SPACE, SET BEFORE EACH STEP LIKE frame */
+    attractors: Vec<Attractor>,
+    /* IN WORLD SPACE TOO, SET BEFORE EACH STEP */
+    fields: Vec<ForceField>,
+
+    /* LONG RANGE ATTACHMENTS: NEAREST PIN AND GEODESIC REST DISTANCE TO IT PER PARTICLE */
+    /* RECOMPUTED LAZILY WHENEVER THE SET OF FIXED PARTICLES CHANGES */
+    neighbors: Vec<Vec<(I, F)>>,
+    lra: Vec<Option<(I, F)>>,
+    lra_dirty: bool,
+
+    /* COARSE LEVEL OF SolverMode::Hierarchical, BUILT FROM THE POSE EVEN WHEN UNUSED SINCE IT IS CHEAP */
+    hierarchy: Hierarchy,
+
+    /* CONNECTED PIECE OF EACH PARTICLE, ONLY RECOMPUTED WHEN CONSTRAINTS ARE REMOVED OR ADDED */
+    piece: Vec<I>,
+    pieces: Vec<Piece>,
+
+    /* EVENTS SINCE THE LAST drain_events, AND WHICH PARTICLES TOUCHED A COLLIDER IN THE LAST STEP */
+    events: Vec<ClothEvent>,
+    in_contact: Vec<bool>,
+    /* in_contact OF THE STEP BEFORE, SWAPPED WITH IT EACH STEP SO NEITHER IS REALLOCATED */
+    was_in_contact: Vec<bool>,
+    /* COLLIDER CONTACTS CARRIED OVER TO THE NEXT STEP, EMPTY WITHOUT contact_cache */
+    contact_cache: Vec<Option<CachedContact>>,
+    /* WORLD IMPULSE THE LAST STEP GAVE EACH DYNAMIC COLLIDER, BY INDEX, UNTIL drain_collider_impulses */
+    collider_impulses: Vec<V>,
+    /* PER PARTICLE, 1 WHEN GRABBED, NEWLY IN CONTACT OR NEXT TO A SPRING PAST HOT_STRAIN, DECAYING BY */
+    /* HEAT_DECAY EACH STEP. WITH local_boost THE NEIGHBORHOODS OF HOT PARTICLES GET EXTRA SWEEPS */
+    heat: Vec<F>,
+    /* SCRATCH OF boost_locally: PARTICLES WITHIN REACH OF A HOT ONE AND THE CONSTRAINTS TOUCHING THEM */
+    boost_region: Vec<bool>,
+    boosted: Vec<ConstraintId>,
+    /* STEPS SPENT UNTANGLING AN OVERLAPPING START OUT OF THE COLLIDERS, None WHEN NOT UNTANGLING */
+    untangling: Option<I>,
+    damping_tuner: DampingTuner,
+    /* WHETHER A FORCE, A DRAG OR A PIN CHANGED THE CLOTH FROM OUTSIDE SINCE THE LAST STEP, WHICH HOLDS THE TUNER */
+    disturbed: bool,
+    /* WHETHER THE LAST STEP BARELY MOVED THE CLOTH OR ITS PARTICLES WERE JUST PLACED, SO THAT A DEEP */
+    /* PENETRATION IS AN OVERLAPPING START OR A TELEPORTED COLLIDER RATHER THAN AN IMPACT */
+    quiet: bool,
+    /* PARTICLES HELD ON THE KILL PLANE, SO EACH FALL IS REPORTED ONCE */
+    killed: Vec<bool>,
+
+    /* SCRATCH BUFFERS REUSED ACROSS FRAMES TO AVOID PER-STEP ALLOCATIONS */
+    /* STRAIN AND END POINTS OF EVERY CONSTRAINT AFTER THE FINAL ITERATION OF THE LAST STEP */
+    strains: Vec<(F, I, I)>,
+    /* SUM AND COUNT OF THE COLLISION CORRECTIONS OF EACH PARTICLE WITHIN A STEP */
+    /* ALSO HOLDS THE CONSTRAINT CORRECTIONS OF A JACOBI ITERATION */
+    corrections: Vec<(V, I)>,
+    /* SUM OF THE FRICTION OF THE SELF-COLLISIONS OF EACH PARTICLE, AVERAGED LIKE THE CORRECTIONS */
+    frictions: Vec<V>,
+    /* POSITIONS RIGHT AFTER INTEGRATION, WHICH max_step_correction LIMITS THE COLLISION PASS AROUND */
+    integrated: Vec<P>,
+    /* POSITIONS AT THE START OF A STEP THAT MAY UNTANGLE, WHICH IT LIMITS THE MOVES AROUND */
+    step_start: Vec<P>,
+    volume_gradients: Vec<V>,
+    triangle_boxes: Vec<Aabb>,
+    bvh: Bvh,
+    /* FACTOR ON THE WIND OF EACH FACE FROM THE LAST UPWIND CAST OF config.wind_occlusion, 1 IN THE OPEN */
+    wind_shelter: Vec<F>,
+    /* BROAD PHASE OVER THE PARTICLES, BUILT ONCE PER STEP FROM THE POSITIONS IN integrated */
+    broad_phase: SpatialGrid,
+    /* HOW FAR ANY PARTICLE HAS MOVED SINCE THE BROAD PHASE WAS BUILT, WHICH QUERIES WIDEN BY */
+    /* None DURING A STEP, BEFORE THE FIRST ONE AND AFTER MOVES THAT ARE NOT TRACKED PER PARTICLE */
+    broad_phase_slack: Option<F>,
+    /* PARTICLES NEAR THE COLLIDER BEING TESTED */
+    candidates: Vec<I>,
+    /* STEPS LEFT UNTIL A PARTICLE STOPS BEING ACTIVE FOR THE EDGE-EDGE PASS, AND THAT PASS'S SCRATCH */
+    edge_active: Vec<u8>,
+    edge_candidates: Vec<(I, I)>,
+    edge_hits: Vec<I>,
+
+    /* POSITIONS AND OLD POSITIONS OF THE LAST STABLE STEPS, OLDEST FIRST */
+    good_states: VecDeque<(Vec<P>, Vec<P>)>,
+    /* FRACTION OF THE TIME STEP TAKEN WHILE RECOVERING, AND FOR HOW MANY MORE STEPS */
+    recovery_scale: F,
+    recovery_steps: I,
+
+    /* EXPERIMENTAL GPU SOLVER, NONE WHEN DISABLED OR WITHOUT A USABLE ADAPTER */
+    #[cfg(feature = "gpu")]
+    gpu: Option<GpuSolver>,
+}
+
+/* A CLOTH NEEDS A FINITE POSITIVE SIZE AND AT LEAST 2 ROWS, A SINGLE COLUMN MAKES IT A ROPE */
+fn check_dimensions(width: F, height: F, (w, h): GridIdx) -> Result<(), ClothError> {
+    let positive = |s: F| s.is_finite() && s > 0.;
+    match positive(width) && positive(height) && w >= 1 && h >= 2 {
+        true => Ok(()),
+        false => Err(ClothError::InvalidDimensions { size: (width, height), subdivisions: (w, h) }),
+    }
+}
+
+impl ClothSim {
+    /* PURE SIMULATION STATE, NO RENDERING RESOURCES REQUIRED */
+    pub fn new(width: F, height: F, subdivisions: GridIdx, config: ClothConfig) -> Result<Self, ClothError> {
+        check_dimensions(width, height, subdivisions)?;
+        Ok(ClothSim::build(width, height, subdivisions, config))
+    }
+
+    fn build(width: F, height: F, subdivisions: GridIdx, config: ClothConfig) -> Self {
+        let (w, h) = subdivisions;
+        /* CONSTRAINTS BELOW ARE BUILT FROM WHATEVER POSE IS CHOSEN SO REST LENGTHS MATCH IT */
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let mut jitter = || match config.jitter {
+            j if j > 0. => rng.gen_range(-j, j),
+            _ => 0.,
+        };
+        let particles = Particles::from_fn(w, h, |(x, y)| {
+            let (u, v) = (x as F / w as F, y as F / h as F);
+            let p = config.pose.position((x, y), u, v, width, height, h) + config.origin;
+            P::new(p.x + jitter(), p.y + jitter(), p.z + jitter())
+        });
+        ClothSim::build_from(width, height, particles, config, rng)
+    }
+
+    /* THE REST OF build FOR PARTICLES PLACED BY THE CALLER, WITH THE RNG THE JITTER LEFT OFF AT */
+    fn build_from(width: F, height: F, mut particles: Particles, config: ClothConfig, rng: StdRng) -> Self {
+        let (w, h) = (particles.width(), particles.height());
+        (0..particles.len()).for_each(|i| particles.set_mass(i, config.particle_mass));
+
+        if let Err(err) = config.pin_preset.validate(w, h) {
+            warn!("{}, pinning what fits", err);
+        }
+        for idx in config.pin_preset.fixed(w, h) {
+            let i = particles.flatten(idx);
+            particles.set_fixed(i, true);
+        }
+        /* THE TOP ROW SLIDES ALONG A RAIL THROUGH ITS END POINTS */
+        if config.pin_preset == PinPreset::Rail {
+            let rail = Anchor::line(particles.data[0], particles.data[w - 1] - particles.data[0]);
+            (0..w).for_each(|x| particles.set_anchor(x, rail));
+        }
 
         /* CREATE CONSTRAINTS (AKA SPRINGS IN THE MASS-SPRING SYSTEM) */
+        use ConstraintKind::*;
         let mut cs = vec![];
-        for y in 0..SUBDIVISIONS {
-            for x in 0..SUBDIVISIONS {
+        for y in 0..h {
+            for x in 0..w {
                 /* STRUCTURAL SPRINGS */
-                if x < SUBDIVISIONS - 1 {
-                    cs.push(Constraint::new((x, y), (x + 1, y), &particles));
+                if x < w - 1 {
+                    cs.push(Constraint::new((x, y), (x + 1, y), Structural, &particles));
                 }
-                if y < SUBDIVISIONS - 1 {
-                    cs.push(Constraint::new((x, y), (x, y + 1), &particles));
+                if y < h - 1 {
+                    cs.push(Constraint::new((x, y), (x, y + 1), Structural, &particles));
                 }
                 /* SHEAR SPRINGS */
-                if x < SUBDIVISIONS - 1 && y < SUBDIVISIONS - 1 {
-                    cs.push(Constraint::new((x, y), (x + 1, y + 1), &particles));
-                    cs.push(Constraint::new((x + 1, y), (x, y + 1), &particles));
+                if x < w - 1 && y < h - 1 {
+                    cs.push(Constraint::new((x, y), (x + 1, y + 1), Shear, &particles));
+                    cs.push(Constraint::new((x + 1, y), (x, y + 1), Shear, &particles));
                 }
                 /* FLEXION SPRINGS */
-                if x < SUBDIVISIONS - 2 {
-                    cs.push(Constraint::new((x, y), (x + 2, y), &particles));
-                }
-                if y < SUBDIVISIONS - 2 {
-                    cs.push(Constraint::new((x, y), (x, y + 2), &particles));
-                }
-            }
-        }
-
-        // PREPARE MESH
-        let handle = meshes.add(Mesh::new(PrimitiveTopology::TriangleList));
-        let mesh = meshes.get_mut(&handle).unwrap();
-
-        let flatten = |x, y| (y * SUBDIVISIONS + x) as u32;
-        let indices: Vec<u32> = (0..SUBDIVISIONS - 1)
-            .flat_map(|y| {
-                (0..SUBDIVISIONS - 1).flat_map(move |x| {
-                    vec![
-                        flatten(x, y),
-                        flatten(x + 1, y),
-                        flatten(x, y + 1),
-                        flatten(x, y + 1),
-                        flatten(x + 1, y),
-                        flatten(x, y),
-                        flatten(x + 1, y),
-                        flatten(x + 1, y + 1),
-                        flatten(x, y + 1),
-                        flatten(x, y + 1),
-                        flatten(x + 1, y + 1),
-                        flatten(x + 1, y),
-                    ]
-                })
-            })
-            .collect();
-        mesh.set_indices(Some(Indices::U32(indices)));
+                if x + 2 < w {
+                    cs.push(Constraint::new((x, y), (x + 2, y), Flexion, &particles));
+                }
+                if y + 2 < h {
+                    cs.push(Constraint::new((x, y), (x, y + 2), Flexion, &particles));
+                }
+            }
+        }
 
-        let normals = vec![[0., 0., -1.]; SUBDIVISIONS * SUBDIVISIONS];
-        mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals.into());
+        /* A PILLOW IS SEWN SHUT ALONG ITS OPEN EDGES */
+        let flipped = diagonals(config.diagonal, &particles);
+        let mut tris = triangles(w, h, 1, &flipped);
+        if let Pose::Pillow { .. } = config.pose {
+            let (pairs, seam_triangles) = pillow::seams(w, h);
+            cs.extend(pairs.into_iter().map(|(a, b)| Constraint::new(a, b, Structural, &particles)));
+            let flatten = |idx| particles.flatten(idx);
+            tris.extend(seam_triangles.into_iter().map(|[a, b, c]| [flatten(a), flatten(b), flatten(c)]));
+        }
+        ClothSim::finish((width, height), particles, cs, tris, flipped, config, rng)
+    }
 
-        let uv: Vec<[F; 2]> = (0..SUBDIVISIONS)
-            .flat_map(|y| {
-                (0..SUBDIVISIONS).map(move |x| {
-                    [
-                        x as F / (SUBDIVISIONS - 1) as F,
-                        y as F / (SUBDIVISIONS - 1) as F,
-                    ]
-                })
-            })
-            .collect();
-        mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uv.into());
+    /* THE REST OF build_from ONCE THE SPRINGS AND TRIANGLES ARE LAID OUT, ON A GRID OR A MESH */
+    fn finish(
+        (width, height): (F, F),
+        particles: Particles,
+        cs: Vec<Constraint>,
+        mut tris: Vec<[I; 3]>,
+        flipped: Grid<bool>,
+        config: ClothConfig,
+        mut rng: StdRng,
+    ) -> Self {
+        let (w, h) = (particles.width(), particles.height());
+
+        /* PRESSURE NEEDS THE WINDING OF A CLOSED SURFACE TO BE CONSISTENT AND POINT OUTWARDS */
+        let closed = volume::orient(&mut tris);
+        if closed && volume::signed_volume(&particles.data, tris.iter().copied()) < 0. {
+            tris.iter_mut().for_each(|t| t.swap(1, 2));
+        }
 
-        let cloth = Cloth {
+        let constraints: Slots<_> = cs.into_iter().collect();
+        let mut shuffled: Vec<_> = constraints.iter_with_ids().map(|(id, _)| id).collect();
+        shuffled.shuffle(&mut rng);
+        let mut by_kind = [vec![], vec![], vec![]];
+        constraints.iter_with_ids().for_each(|(id, c)| by_kind[c.kind as I].push(id));
+        let neighbors = structural_neighbors(&constraints, &particles);
+        let noise = Noise::new(config.seed);
+        let hierarchy = Hierarchy::new(&particles);
+
+        let mut cloth = ClothSim {
+            width,
+            height,
+            triangles: tris.into_iter().map(|v| Face::new(v, &particles)).collect(),
+            torn: false,
+            closed,
+            render: RenderGrid::new(w, h, config.render_subdivision, &flipped, &config),
+            welded: None,
+            flipped,
+            weights: Grid::filled(w, h, 0.),
+            painted: PaintedStiffness::new(),
+            mesh_dirty: true,
+            mesh_stale: false,
             particles,
-            constraints: cs,
-            mesh_handle: handle.clone(),
+            constraints,
+            shuffled,
+            by_kind,
+            regions: vec![],
+            config,
+            stats: Stats::default(),
+            time: 0.,
+            time_owed: 0.,
+            steps: 0,
+            rest_scale: 1.,
+            rest_factor: 1.,
+            dt_scale: 1.,
+            lod: lod::FULL,
+            rest_animation: None,
+            placement: Isometry3::identity(),
+            frame: Isometry3::identity(),
+            noise,
+            attachments: vec![],
+            attractors: vec![],
+            fields: vec![],
+            neighbors,
+            lra: vec![],
+            lra_dirty: true,
+            hierarchy,
+            piece: vec![],
+            pieces: vec![],
+            events: vec![],
+            in_contact: vec![],
+            was_in_contact: vec![],
+            contact_cache: vec![],
+            collider_impulses: vec![],
+            heat: vec![],
+            boost_region: vec![],
+            boosted: vec![],
+            untangling: None,
+            damping_tuner: DampingTuner::default(),
+            disturbed: false,
+            quiet: true,
+            killed: vec![],
+            strains: vec![],
+            corrections: vec![],
+            frictions: vec![],
+            integrated: vec![],
+            step_start: vec![],
+            volume_gradients: vec![],
+            triangle_boxes: vec![],
+            bvh: Bvh::default(),
+            wind_shelter: vec![],
+            broad_phase: SpatialGrid::default(),
+            broad_phase_slack: None,
+            candidates: vec![],
+            edge_active: vec![],
+            edge_candidates: vec![],
+            edge_hits: vec![],
+            good_states: VecDeque::new(),
+            recovery_scale: 1.,
+            recovery_steps: 0,
+            #[cfg(feature = "gpu")]
+            gpu: None,
         };
-        cloth.update_mesh(mesh);
+        cloth.find_pieces();
+        cloth.init_gpu();
+        if closed {
+            cloth.render = cloth.render_grid(1);
+        }
+        cloth
+    }
 
-        (cloth, handle)
+    /* A ROPE: A CHAIN OF segments + 1 PARTICLES length LONG, A CLOTH ONE PARTICLE WIDE WITH ONLY STRUCTURAL */
+    /* AND FLEXION SPRINGS. IT CATCHES THE WIND AND IS PICKED PER SEGMENT AND RENDERS AS A RIBBON, AS THICK */
+    /* AS THE config.thickness IT COLLIDES WITH. THE POSE LAYS IT OUT ALONG ITS HEIGHT */
+    pub fn new_rope(length: F, segments: I, config: ClothConfig) -> Result<Self, ClothError> {
+        let segments = segments.max(1);
+        /* THE POSES SPREAD h PARTICLES OVER (h - 1) / h OF THE HEIGHT */
+        let height = length * (segments + 1) as F / segments as F;
+        ClothSim::new(config.thickness, height, (1, segments + 1), config)
     }
 
-    pub fn add_force(&mut self, force: V) {
-        self.particles.iter_mut().for_each(|p| p.add_force(force));
+    /* A CLOTH OF A TRIANGLE MESH, PLACED AT config.origin. VERTICES AT THE SAME SPOT, E.G. DOWN A UV SEAM, ARE */
+    /* WELDED INTO ONE PARTICLE SO THE CLOTH HOLDS TOGETHER THERE, WHILE THE RENDER MESH KEEPS EVERY VERTEX */
+    /* WITH ITS OWN UVS. A MESH WITHOUT UVS IS UNWRAPPED BY weld::unwrap. EVERY EDGE IS A STRUCTURAL SPRING */
+    /* AND EVERY PAIR OF CORNERS ACROSS AN EDGE A FLEXION ONE, ALL OF THEM ALONG THE WARP */
+    /* THE PARTICLES FORM A SINGLE ROW OF THE GRID, SO THE POSE AND PIN PRESET ARE NOT USED, PIN WITH */
+    /* set_fixed. IT IS NEVER UPSAMPLED, CUT, REGENERATED AT ANOTHER RESOLUTION OR TILED */
+    pub fn from_mesh(mesh: &Mesh, config: ClothConfig) -> Result<Self, ClothError> {
+        Welded::new(mesh).map(|welded| ClothSim::build_welded(welded, config))
     }
 
-    pub fn step(&mut self) {
-        /* ITERATIVELY RESOLVE SPRING CONSTRAINTS */
-        for _ in 0..CONSTRAINTS_ITER {
-            for constraint in &self.constraints {
-                let p12 = self.particles[constraint.p2].p - self.particles[constraint.p1].p;
-                let d = p12.norm();
-                let f_c = DT * (d - constraint.d) * (p12 / d);
-                self.particles[constraint.p1].offset(f_c);
-                self.particles[constraint.p2].offset(-f_c);
-            }
-        }
-
-        /* RESOLVE EXTERNAL FORCES ON THE PARTICLES */
-        self.particles.iter_mut().for_each(Particle::step);
-
-        let mut mods = vec![];
-
-        /* EXPENSIVE POINT-FACE COLLISION CHECKING */
-        /* SHOULD UPDATE EPSILON TO BE 0.1 FOR THIS MODE */
-        /* ALSO WORKS CLOSE TO REAL-TIME WITH <20x20 GRID */
-        /* UNCOMMENT THE 2 IMPORT STATEMENTS AT THE TOP FOR THIS MODE */
-        // for (i, p) in self.particles.iter().enumerate() {
-        //     for (p1, p2, p3) in (0..SUBDIVISIONS - 1)
-        //         .flat_map(|y| {
-        //             (0..SUBDIVISIONS - 1).flat_map(|x| {
-        //                 vec![(self.particles[(x, y)].p.clone(),
-        //                       self.particles[(x + 1, y)].p.clone(),
-        //                       self.particles[(x, y + 1)].p.clone()),
-        //                      (self.particles[(x, y + 1)].p.clone(),
-        //                       self.particles[(x + 1, y)].p.clone(),
-        //                       self.particles[(x, y)].p.clone()),
-        //                 ]
-        //             }).collect::<Vec<_>>()
-        //         }) {
-        //         let t = Triangle::new(p1, p2, p3);
-        //         let res = closest_points(&Isometry3::translation(p.p.x, p.p.y, p.p.z), &Ball::new(EPSILON),
-        //                                  &Isometry3::identity(), &t, EPSILON);
-
-        //         if let ClosestPoints::WithinMargin(pa, pb) = res {
-        //             let diff = pb - pa;
-        //             let d = diff.norm();
-        //             let ratio = EPSILON / d;
-        //             let delta = diff * (1. - ratio);
-        //             mods.push((i, delta));
-        //         }
-        //     }
-        // }
-
-        /* OR */
-        /* CHEAP POINT-POINT COLLISION CHECKING */
-        /* SHOULD UPDATE EPSILON TO BE 0.3 FOR THIS MODE */
-        /* WORKS WELL IN REALTIME EVEN UP TO 50+ BY 50+ GRID */
-        for (i1, p1) in self.particles.iter().enumerate() {
-            for (i2, p2) in self.particles.iter().skip(i1).enumerate() {
-                let diff = p2.p - p1.p;
-                let d = diff.norm();
-                if p1 != p2 && d < EPSILON {
-                    let ratio = EPSILON / d;
-                    let delta = diff * (1. - ratio);
-                    mods.push((i1, delta));
-                    mods.push((i2, -delta));
-                }
+    fn build_welded(welded: Welded, mut config: ClothConfig) -> Self {
+        config.render_subdivision = 1;
+        /* THE COARSE LEVEL OF THE HIERARCHICAL SOLVER TAKES EVERY FEW ROWS AND COLUMNS OF A GRID */
+        if let SolverMode::Hierarchical { .. } = config.solver {
+            warn!("a cloth built from a mesh has no grid for the hierarchical solver, solving with gauss-seidel");
+            config.solver = SolverMode::GaussSeidel;
+        }
+        let n = welded.positions.len();
+        let mut particles = Particles::from_fn(n, 1, |(i, _)| welded.positions[i] + config.origin);
+        (0..n).for_each(|i| particles.set_mass(i, config.particle_mass));
+
+        let (structural, flexion) = welded.springs();
+        let spring = |kind| {
+            let particles = &particles;
+            move |(a, b)| Constraint::new((a, 0), (b, 0), kind, particles)
+        };
+        let cs = structural.into_iter().map(spring(ConstraintKind::Structural));
+        let cs = cs.chain(flexion.into_iter().map(spring(ConstraintKind::Flexion))).collect();
+
+        let bounds = Aabb::from_points(&particles.data);
+        let size = (bounds.max.x - bounds.min.x, bounds.max.y - bounds.min.y);
+        let rng = StdRng::seed_from_u64(config.seed);
+        let tris = welded.faces.clone();
+        let mut cloth = ClothSim::finish(size, particles, cs, tris, Grid::filled(0, 0, false), config, rng);
+        cloth.render = RenderGrid::welded(&welded.vertices, &welded.triangles, &welded.uvs, n);
+        cloth.welded = Some(welded);
+        cloth
+    }
+
+    pub fn is_rope(&self) -> bool {
+        self.particles.width() == 1
+    }
+
+    /* A COPY FLIPPED ALONG axis, SO COLUMN x BECOMES w - 1 - x FOR X, WITH THE POSITIONS REFLECTED */
+    /* THROUGH THE MIDDLE OF THE CLOTH. PINS, DRAG, WEIGHTS AND PAINTED STIFFNESS FLIP ALONG, THE UVS DO NOT */
+    pub fn mirrored(&self, axis: Axis) -> Result<ClothSim, ClothError> {
+        self.check_sheet()?;
+        let n = self.grid_step(axis).normalize();
+        let n2 = n * n.transpose() * 2.;
+        let center = self.particles.iter().fold(V::zeros(), |sum, p| sum + p.coords) / self.particles.len() as F;
+        let reflection =
+            Matrix4::new_translation(&(n * 2. * n.dot(&center))) * (Matrix3::identity() - n2).to_homogeneous();
+        let tile = Tile {
+            offset: (0, 0),
+            flip: (axis == Axis::X, axis == Axis::Y),
+            transform: Affine3::from_matrix_unchecked(reflection),
+        };
+        Ok(self.assemble(self.subdivisions(), &[tile], self.config.clone()))
+    }
+
+    /* nx BY ny COPIES SIDE BY SIDE, EACH ONE GRID STEP ON FROM THE LAST, OR WITH stitch SHARING THEIR */
+    /* BORDERS SO A 20x20 CLOTH TILED 3x1 IS 60 OR 58 PARTICLES WIDE. A SHARED BORDER TAKES THE PARTICLES */
+    /* OF THE LATER TILE. PINS, DRAG, WEIGHTS AND PAINTED STIFFNESS ARE REPEATED PER TILE */
+    pub fn tiled(&self, nx: I, ny: I, stitch: bool, uvs: TileUvs) -> Result<ClothSim, ClothError> {
+        self.check_sheet()?;
+        let (w, h) = self.subdivisions();
+        if nx == 0 || ny == 0 {
+            let size = (self.width * nx as F, self.height * ny as F);
+            return Err(ClothError::InvalidDimensions { size, subdivisions: (w * nx, h * ny) });
+        }
+        let (step_x, step_y) = (w - stitch as I, h - stitch as I);
+        let (sx, sy) = (self.grid_step(Axis::X) * step_x as F, self.grid_step(Axis::Y) * step_y as F);
+        let tiles: Vec<_> = (0..ny)
+            .flat_map(|j| (0..nx).map(move |i| (i, j)))
+            .map(|(i, j)| Tile {
+                offset: (i * step_x, j * step_y),
+                flip: (false, false),
+                transform: Affine3::from_matrix_unchecked(Matrix4::new_translation(&(sx * i as F + sy * j as F))),
+            })
+            .collect();
+        let mut config = self.config.clone();
+        if uvs == TileUvs::Repeat {
+            config.uv_scale = (config.uv_scale.0 * nx as F, config.uv_scale.1 * ny as F);
+        }
+        let size = ((nx - 1) * step_x + w, (ny - 1) * step_y + h);
+        Ok(self.assemble(size, &tiles, config))
+    }
+
+    fn check_sheet(&self) -> Result<(), ClothError> {
+        if self.welded.is_some() {
+            return Err(ClothError::NotAGrid);
+        }
+        match self.torn || self.closed || self.is_rope() {
+            true => Err(ClothError::NotASheet),
+            false => Ok(()),
+        }
+    }
+
+    /* MEAN DISTANCE BETWEEN NEIGHBORING PARTICLES ALONG axis, WHICH TILES ARE SPACED BY */
+    fn grid_step(&self, axis: Axis) -> V {
+        let (w, h) = self.subdivisions();
+        let p = &self.particles;
+        match axis {
+            Axis::X => (0..h).fold(V::zeros(), |sum, y| sum + (p[(w - 1, y)] - p[(0, y)])) / (h * (w - 1)) as F,
+            Axis::Y => (0..w).fold(V::zeros(), |sum, x| sum + (p[(x, h - 1)] - p[(x, 0)])) / (w * (h - 1)) as F,
+        }
+    }
+
+    /* A NEW CLOTH OF size PARTICLES FROM COPIES OF THIS ONE, WITH REST LENGTHS FROM THE ASSEMBLED */
+    /* POSITIONS SO THE SEAMS START WITHOUT TENSION. ITS POSE IS SCALED TO THE SAME SPACING, AND A reset */
+    /* LAYS IT OUT FROM THAT AND THE ASSEMBLED PINS. ATTACHMENTS ARE LEFT OUT */
+    fn assemble(&self, size: GridIdx, tiles: &[Tile], mut config: ClothConfig) -> ClothSim {
+        let ((w, h), (tw, th)) = (self.subdivisions(), size);
+        let mut positions = Grid::filled(tw, th, P::origin());
+        let mut anchors = Grid::filled(tw, th, Anchor::Free);
+        let mut drag = Grid::filled(tw, th, 1.);
+        let mut weights = Grid::filled(tw, th, 0.);
+        let mut painted = PaintedStiffness::new();
+        for tile in tiles {
+            for i in 0..w * h {
+                let t = positions.flatten(tile.place((i % w, i / w), (w, h)));
+                positions[t] = tile.transform * self.particles.data[i];
+                anchors[t] = self.particles.anchor(i).map(&tile.transform);
+                drag[t] = self.particles.drag(i);
+                weights[t] = self.weights.data[i];
+            }
+            for (&(a, b), &factor) in &self.painted {
+                let (a, b) = (tile.place(a, (w, h)), tile.place(b, (w, h)));
+                painted.insert((a.min(b), a.max(b)), factor);
             }
         }
 
-        /* APPLY IMPULSE RESPONSES FOR ABOVE COMPUTED COLLISION CHECKS */
-        for (idx, delta) in mods {
-            self.particles.data[idx].offset(delta);
+        let fixed = (0..tw * th).filter(|&i| anchors[i] == Anchor::Fixed);
+        config.pin_preset = PinPreset::Custom(fixed.map(|i| anchors.unflatten(i)).collect());
+        let rng = StdRng::seed_from_u64(config.seed);
+        let (width, height) = (self.width * tw as F / w as F, self.height * th as F / h as F);
+        let particles = Particles::from_fn(tw, th, |idx| positions[idx]);
+        let mut cloth = ClothSim::build_from(width, height, particles, config, rng);
+        cloth.placement = self.placement;
+        cloth.frame = self.frame;
+        for (i, &anchor) in anchors.iter().enumerate() {
+            if let Anchor::Line { .. } | Anchor::Plane { .. } = anchor {
+                cloth.pin(i, anchor);
+            }
         }
+        cloth.set_drag_with(|idx| drag[idx]);
+        cloth.weights = weights;
+        cloth.apply_weights();
+        cloth.painted = painted;
+        cloth.apply_painted();
+        cloth
     }
 
-    /* SET 8 NEAREST POINTS TO SELECTED REGION TO BE FIXED IN SPACE */
-    /* USES QUICKSORT LAZYSORING TO AVOID UNNECESSARY SORTING */
-    pub fn set_fixed(&mut self, p: P, fixed: bool) {
-        self.particles
-            .iter_mut()
-            .map(|v| ((v.p - p).norm_squared(), v))
-            .sorted_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Equal))
-            .take(8)
-            .for_each(|(_, v)| v.fixed = fixed);
+    /* FULLY POPULATED RENDER MESH FOR THE CURRENT STATE, REGISTERING IT IS UP TO THE CALLER */
+    pub fn build_mesh(&self) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        let weighting = self.config.normal_weighting;
+        self.render_grid(self.config.render_subdivision).fill(&self.particles, &mut mesh, weighting);
+        mesh
     }
 
-    pub fn update_mesh(&self, mesh: &mut Mesh) {
-        let positions = self
-            .particles
-            .iter()
-            .map(|p| [p.p.x, p.p.y, p.p.z])
-            .collect::<Vec<_>>();
-        mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions.into());
+    /* DRAW THE QUADS WITH EVERY CORNER IN THE INCLUSIVE RECTANGLE BETWEEN TWO CORNERS AS A SUB-MESH OF */
+    /* THEIR OWN, NAMED name. ASSIGNING A NAME AGAIN MOVES ITS REGION, WHERE REGIONS OVERLAP THE ONE */
+    /* ASSIGNED LAST DRAWS THE QUAD. THE SUB-MESH NEEDS AN ASSET, SEE Cloth::attach_region_mesh */
+    pub fn assign_region(&mut self, name: &str, ((x0, y0), (x1, y1)): (GridIdx, GridIdx)) -> Result<I, ClothError> {
+        for &idx in &[(x0, y0), (x1, y1)] {
+            if !self.particles.contains(idx) {
+                return Err(ClothError::IndexOutOfBounds { idx, subdivisions: self.subdivisions() });
+            }
+        }
+        let rect = ((x0.min(x1), y0.min(y1)), (x0.max(x1), y0.max(y1)));
+        let k = match self.regions.iter().position(|region| region.name == name) {
+            Some(k) => {
+                self.regions[k].rect = rect;
+                k
+            }
+            None => {
+                self.regions.push(TextureRegion { name: name.to_string(), rect, stale: true });
+                self.regions.len() - 1
+            }
+        };
+        self.regions_changed();
+        Ok(k)
+    }
+
+    pub fn regions(&self) -> &[TextureRegion] {
+        &self.regions
+    }
+
+    /* THE MAIN MESH LOSES THE TRIANGLES OF THE REGIONS, SO IT IS REFILLED TOO */
+    fn regions_changed(&mut self) {
+        let rects: Vec<_> = self.regions.iter().map(|region| region.rect).collect();
+        self.render.set_regions(&rects);
+        self.mesh_stale = true;
+        self.mesh_dirty = true;
+    }
+
+    /* REGENERATE THE CLOTH AT A NEW RESOLUTION, KEEPING ITS SIZE AND CONFIG. THE NEXT update_mesh REFILLS */
+    /* THE MESH, Cloth::rebuild DOES SO RIGHT AWAY. PENDING EVENTS ARE KEPT */
+    /* PINS ARE RE-APPLIED FROM THE PRESET SINCE INDICES DO NOT CARRY OVER */
+    /* DRAG COEFFICIENTS ARE RESAMPLED FROM THE NEAREST PARTICLE OF THE OLD GRID */
+    /* PAINTED STIFFNESS IS KEPT AT THE SAME RESOLUTION AND DROPPED AT ANOTHER */
+    /* AN INVALID RESOLUTION LEAVES THE CLOTH AS IT WAS */
+    /* A CLOTH BUILT FROM A MESH IS REBUILT FROM IT, ONLY AT ITS OWN RESOLUTION */
+    pub fn regenerate(&mut self, subdivisions_x: I, subdivisions_y: I) -> Result<(), ClothError> {
+        match self.welded {
+            Some(_) if (subdivisions_x, subdivisions_y) != self.subdivisions() => return Err(ClothError::NotAGrid),
+            Some(_) => {}
+            None => check_dimensions(self.width, self.height, (subdivisions_x, subdivisions_y))?,
+        }
+        let (placement, frame) = (self.placement, self.frame);
+        let regions = std::mem::take(&mut self.regions);
+        let events = std::mem::take(&mut self.events);
+        let old = self.subdivisions();
+        let drag: Vec<F> = (0..self.particles.grid_len()).map(|i| self.particles.drag(i)).collect();
+        let weights = self.weights.clone();
+        let painted = std::mem::take(&mut self.painted);
+        *self = match self.welded.take() {
+            Some(welded) => ClothSim::build_welded(welded, self.config.clone()),
+            None => ClothSim::build(self.width, self.height, (subdivisions_x, subdivisions_y), self.config.clone()),
+        };
+        self.frame = frame;
+        self.transform(&placement);
+        let nearest = |x: I, w: I, old_w: I| ((x * (old_w - 1)) as F / (w - 1) as F).round() as I;
+        let (w, h) = self.subdivisions();
+        let resampled = |(x, y): GridIdx| nearest(y, h, old.1) * old.0 + nearest(x, w, old.0);
+        if drag.iter().any(|&c| c != 1.) {
+            self.set_drag_with(|idx| drag[resampled(idx)]);
+        }
+        if weights.iter().any(|&weight| weight != 0.) {
+            self.weights = Grid::from_fn(w, h, |idx| weights.data[resampled(idx)]);
+            self.apply_weights();
+        }
+        /* PAINTED STIFFNESS IS KEYED BY GRID INDEX, WHICH ONLY MEANS THE SAME SPOT AT THE SAME RESOLUTION */
+        if (w, h) == old {
+            self.painted = painted;
+            self.apply_painted();
+        }
+        /* REGIONS KEEP THEIR PLACE ON THE CLOTH, THEIR CORNERS SNAPPED TO THE NEAREST PARTICLES */
+        if !regions.is_empty() {
+            let snap = |(x, y): GridIdx| (nearest(x, old.0, w), nearest(y, old.1, h));
+            self.regions = regions
+                .into_iter()
+                .map(|region| TextureRegion { rect: (snap(region.rect.0), snap(region.rect.1)), ..region })
+                .collect();
+            self.regions_changed();
+        }
+        self.events = events;
+        self.mesh_stale = true;
+        Ok(())
+    }
+
+    pub fn subdivisions(&self) -> GridIdx {
+        (self.particles.width(), self.particles.height())
+    }
+
+    /* THE REGIONS ARE WRITTEN BY THE NEXT update_meshes */
+    fn fill_mesh(&mut self, mesh: &mut Mesh) {
+        self.render.fill(&self.particles, mesh, self.config.normal_weighting);
+        self.mesh_stale = false;
+        self.regions.iter_mut().for_each(|region| region.stale = true);
+        self.mesh_dirty |= !self.regions.is_empty();
+    }
+
+    /* A TORN OR CLOSED CLOTH IS ALWAYS RENDERED FROM ITS OWN TRIANGLES, SEE RenderGrid::tear */
+    /* A ROPE HAS NONE, ITS RIBBON FOLLOWS THE GRID PARTICLES EVEN ONCE TORN. A CLOTH BUILT FROM A MESH IS */
+    /* RENDERED FROM THE VERTICES OF THE MESH */
+    fn render_grid(&self, factor: I) -> RenderGrid {
+        let (w, h) = self.subdivisions();
+        let mut render = if let Some(welded) = &self.welded {
+            RenderGrid::welded(&welded.vertices, &welded.triangles, &welded.uvs, self.particles.len())
+        } else if self.is_rope() || (!self.torn && !self.closed) {
+            RenderGrid::new(w, h, factor, &self.flipped, &self.config)
+        } else {
+            let mut render = RenderGrid::new(w, h, 1, &self.flipped, &self.config);
+            let triangles: Vec<_> = self.triangles.iter().map(|f| f.v).collect();
+            render.tear(&triangles, self.particles.origins());
+            render
+        };
+        if !self.regions.is_empty() {
+            render.set_regions(&self.regions.iter().map(|region| region.rect).collect::<Vec<_>>());
+        }
+        render
+    }
+
+    pub fn render_subdivision(&self) -> I {
+        self.render.factor()
+    }
+
+    /* TAKES EFFECT WITH THE NEXT MESH UPDATE, WHICH IS FORCED SO A RESTING CLOTH CHANGES TOO */
+    /* SWAP IN A NEW CONFIG WITHOUT RESETTING THE PARTICLES. MASSES ARE UPDATED IN PLACE, THE STIFFNESS, */
+    /* DAMPING AND STRAIN LIMITS ARE READ FROM THE CONFIG EVERY STEP ANYWAY. THE POSE, PINS, DIAGONAL */
+    /* AND THE OTHER FIELDS ONLY USED BY Cloth::new TAKE EFFECT ON THE NEXT reset */
+    pub fn apply_config(&mut self, config: ClothConfig) {
+        let reweigh = config.particle_mass != self.config.particle_mass
+            || config.weight_binding != self.config.weight_binding;
+        self.config = config;
+        if reweigh {
+            self.apply_weights();
+        }
+        /* THE CORRECTIONS OF THE LAST STEP WERE FOR THE OLD STIFFNESS */
+        self.clear_warm_start();
+        /* A RESTING CLOTH DOES NOT REBUILD ITS MESH, SO NEW NORMALS ARE PUSHED EXPLICITLY */
+        self.mesh_dirty = true;
+    }
+
+    pub fn set_normal_weighting(&mut self, weighting: NormalWeighting) {
+        self.config.normal_weighting = weighting;
+        self.mesh_dirty = true;
+    }
+
+    /* THE ENTITY'S TRANSFORM, WITHOUT SCALE, WHICH spawn_cloth REJECTS */
+    pub fn set_frame(&mut self, frame: Isometry3<F>) {
+        self.frame = frame;
+    }
+
+    pub fn set_attractors(&mut self, attractors: &[Attractor]) {
+        self.attractors.clear();
+        self.attractors.extend_from_slice(attractors);
+    }
+
+    pub fn set_force_fields(&mut self, fields: &[ForceField]) {
+        self.fields.clear();
+        self.fields.extend_from_slice(fields);
+    }
+
+    pub fn frame(&self) -> Isometry3<F> {
+        self.frame
+    }
+
+    pub fn to_world(&self, p: P) -> P {
+        self.frame * p
+    }
+
+    pub fn to_local(&self, p: P) -> P {
+        self.frame.inverse_transform_point(&p)
+    }
+
+    pub fn vector_to_world(&self, v: V) -> V {
+        self.frame * v
+    }
+
+    pub fn vector_to_local(&self, v: V) -> V {
+        self.frame.inverse_transform_vector(&v)
+    }
+
+    pub fn world_position(&self, idx: GridIdx) -> P {
+        self.to_world(self.particles[idx])
+    }
+
+    /* translate AND transform MOVE THE CLOTH WITHIN ITS LOCAL SPACE, NOT THE ENTITY */
+    pub fn translate(&mut self, v: V) {
+        self.transform(&Isometry3::translation(v.x, v.y, v.z));
+    }
+
+    /* MOVE THE WHOLE SHEET RIGIDLY, PINS INCLUDED, WITHOUT CHANGING ITS VELOCITY OR REST LENGTHS */
+    /* ATTACHED PARTICLES KEEP FOLLOWING THEIR ENTITY, ONLY THE PIN THEY RETURN TO ON DETACH MOVES */
+    pub fn transform(&mut self, iso: &Isometry3<F>) {
+        self.particles.transform(iso);
+        self.attachments.iter_mut().for_each(|a| a.was = a.was.transform(iso));
+        for (p, old_p) in &mut self.good_states {
+            p.iter_mut().chain(old_p).for_each(|q| *q = iso * *q);
+        }
+        self.placement = iso * self.placement;
+        self.broad_phase_slack = None;
+        self.mesh_dirty = true;
+    }
+
+    /* force IS IN WORLD SPACE */
+    #[cfg(test)]
+    pub fn add_force(&mut self, force: V) {
+        self.disturbed = true;
+        let force = self.vector_to_local(force);
+        (0..self.particles.len()).for_each(|i| self.particles.add_force(i, force));
+    }
+
+    /* COUNT A FRAME AT config.time_scale, RETURNING WHETHER A WHOLE STEP IS DUE. THE SCALE ACCUMULATES */
+    /* RATHER THAN SHRINKING dt, SO AT 0.5 EVERY OTHER FRAME TAKES THE SAME STEP AS AT FULL SPEED AND THE */
+    /* TRAJECTORY ONLY STRETCHES OVER TWICE THE FRAMES */
+    pub fn tick(&mut self) -> bool {
+        self.time_owed += self.config.time_scale;
+        if self.time_owed < 1. {
+            return false;
+        }
+        self.time_owed -= 1.;
+        true
+    }
+
+    /* 0 FREEZES THE CLOTH, WHICH STILL RENDERS AND CAN BE PINNED AND DRAGGED */
+    pub fn set_time_scale(&mut self, scale: F) {
+        self.config.time_scale = scale.max(0.).min(1.);
+    }
+
+    pub fn set_gravity(&mut self, gravity: V) {
+        self.config.gravity = gravity;
+    }
+
+    /* direction IS IN WORLD SPACE LIKE THE REST OF THE WIND */
+    pub fn set_wind(&mut self, direction: V, strength: F) {
+        self.config.wind.direction = direction;
+        self.config.wind.strength = strength;
+    }
+
+    /* STEPS TAKEN SINCE THE CLOTH WAS BUILT OR LAST RESET */
+    pub fn steps(&self) -> I {
+        self.steps
+    }
+
+    /* WHETHER THE CLOTH IS BEING PUSHED GENTLY OUT OF A COLLIDER IT STARTED INSIDE */
+    #[cfg(test)]
+    pub fn untangling(&self) -> bool {
+        self.untangling.is_some()
+    }
+
+    pub fn step(&mut self, colliders: &[LayeredCollider]) {
+        /* THE MESH UPDATE OF THE PREVIOUS FRAME IS RECORDED BY THE CALLER AFTER THAT STEP */
+        self.stats.end_frame();
+        self.stats.broad_phase_builds = 0;
+        self.stats.boosted = 0;
+        self.broad_phase_slack = None;
+        let start = Instant::now();
+        let mut stopwatch = Stopwatch::start();
+        self.untangling = self.untangling.filter(|_| self.config.untangle);
+        let may_untangle = self.config.untangle && (self.quiet || self.untangling.is_some());
+        if may_untangle {
+            self.step_start.clear();
+            self.step_start.extend_from_slice(self.particles.as_slice());
+        }
+
+        self.animate_rest_lengths();
+        self.damp_springs();
+        let damping = self.tuned_damping();
+        self.stats.damping = damping;
+
+        /* ON THE GPU THE ITERATIONS AND THE INTEGRATION GO IN ONE SUBMISSION, SO THE EXTERNAL */
+        /* FORCES ARE ACCUMULATED BEFORE THE SOLVE AND THERE IS NO EARLY EXIT ON CONVERGENCE */
+        let on_gpu = self.step_on_gpu();
+        self.warm_start(on_gpu);
+
+        /* ITERATIVELY RESOLVE SPRING CONSTRAINTS, STOPPING EARLY ONCE CONVERGED */
+        let (mut min_iter, mut max_iter) = (self.config.min_iterations, self.config.max_iterations);
+        if let SolverMode::Hierarchical { coarse_iters, fine_iters } = self.config.solver {
+            max_iter = fine_iters;
+            min_iter = min_iter.min(fine_iters);
+            /* THE COARSE GRID FOLLOWS THE REGULAR GRID, WHICH NO LONGER HOLDS TOGETHER ONCE TORN */
+            if !on_gpu && !self.torn {
+                self.hierarchy.solve(&mut self.particles, coarse_iters, self.rest_scale * self.rest_factor);
+            }
+        }
+        /* PER-KIND COUNTS TAKE OVER FROM max_iterations AND fine_iters, JACOBI SOLVES ALL KINDS AT ONCE */
+        if let Some(counts) = self.config.kind_iterations.filter(|_| self.config.solver != SolverMode::Jacobi) {
+            max_iter = counts.max();
+            min_iter = min_iter.min(max_iter);
+        }
+        if let Some(cap) = self.lod.iterations {
+            max_iter = max_iter.min(cap);
+            min_iter = min_iter.min(max_iter);
+        }
+        self.stats.iterations = max_iter;
+        for iter in (0..max_iter).filter(|_| !on_gpu) {
+            let mut max_correction = match self.config.solver {
+                SolverMode::GaussSeidel | SolverMode::Hierarchical { .. } => self.solve_gauss_seidel(iter),
+                SolverMode::Jacobi => self.solve_jacobi(),
+            };
+            if let Some(stiffness) = self.config.area_stiffness {
+                max_correction = max_correction.max(area_pass(&mut self.particles, &self.triangles, stiffness));
+            }
+            if let Some(pressure) = self.config.pressure.filter(|_| self.closed) {
+                let (particles, gradients) = (&mut self.particles, &mut self.volume_gradients);
+                max_correction = max_correction.max(pressure_pass(particles, &self.triangles, gradients, pressure));
+            }
+            if iter + 1 >= min_iter && max_correction < self.config.tolerance {
+                self.stats.iterations = iter + 1;
+                break;
+            }
+        }
+        if let Some(boost) = self.config.local_boost.filter(|_| !on_gpu) {
+            self.boost_locally(boost);
+        }
+
+        let particles = &self.particles;
+        self.strains.clear();
+        self.strains.extend(self.constraints.iter().map(|c| (c.strain(particles), c.p1, c.p2)));
+        if self.config.local_boost.is_some() {
+            self.cool_down();
+        }
+
+        if let Some(plasticity) = self.config.plasticity {
+            self.creep(plasticity);
+        }
+
+        if self.config.long_range_attachments {
+            self.apply_long_range_attachments();
+        }
+
+        if let Some(max_strain) = self.config.max_strain {
+            self.limit_strain(max_strain);
+        }
+        self.stats.record(Phase::Constraints, stopwatch.lap());
+
+        let dt = self.dt();
+        if !on_gpu {
+            self.apply_forces();
+            /* RESOLVE EXTERNAL FORCES ON THE PARTICLES */
+            let damping = match self.untangling {
+                Some(_) => damping.min(UNTANGLE_DAMPING),
+                None => damping,
+            };
+            self.particles.integrate(self.config.integrator, damping, dt);
+        }
+        self.time += dt;
+        let rest_edge = self.mean_rest_edge();
+        self.stats.clamped = 0;
+        if let Some(max_speed) = self.config.max_speed {
+            self.clamp_speeds(max_speed * rest_edge);
+        }
+        self.apply_cached_contacts(colliders);
+        self.integrated.clear();
+        self.integrated.extend_from_slice(self.particles.as_slice());
+        self.stats.record(Phase::Integration, stopwatch.lap());
+
+        /* CELLS AT LEAST AS LARGE AS THE SELF-COLLISION DISTANCE SO ADJACENT CELLS COVER EVERY CONTACT */
+        let cell_size = self.config.thickness.max(rest_edge).max(F::EPSILON);
+        self.broad_phase.build(self.integrated.iter().copied(), cell_size);
+        self.stats.broad_phase_builds += 1;
+        self.stats.record(Phase::BroadPhase, stopwatch.lap());
+
+        self.corrections.clear();
+        self.corrections.resize(self.particles.len(), (V::zeros(), 0));
+        self.frictions.clear();
+        self.frictions.resize(self.particles.len(), V::zeros());
+
+        let meets = collider::meets(self.config.collision_mask, self.config.layer);
+        let self_collision = if meets && self.lod.self_collision {
+            self.config.self_collision
+        } else {
+            SelfCollision::Off
+        };
+        match self_collision {
+            SelfCollision::Points => self.collide_points(),
+            SelfCollision::Triangles => self.collide_triangles(),
+            SelfCollision::Off => {}
+        }
+        self.stats.edge_pairs = 0;
+        if let Some(max_pairs) = self.config.edge_collision.filter(|_| self_collision != SelfCollision::Off) {
+            self.collide_edges(max_pairs);
+        }
+
+        /* APPLY IMPULSE RESPONSES FOR ABOVE COMPUTED COLLISION CHECKS */
+        /* AVERAGED PER PARTICLE SO MANY SIMULTANEOUS CONTACTS DO NOT STACK UP AND OVERSHOOT */
+        let max_correction = self.config.max_collision_correction;
+        for (i, &(sum, count)) in self.corrections.iter().enumerate() {
+            if count > 0 {
+                let delta = sum / count as F;
+                let norm = delta.norm();
+                self.particles.offset(i, if norm > max_correction { delta * (max_correction / norm) } else { delta });
+                self.particles.offset_displacement(i, self.frictions[i] / count as F);
+            }
+        }
+
+        /* PUSH PARTICLES OUT OF THE COLLIDERS IN THE SCENE */
+        /* ONLY THE FIRST STEP OF EACH CONTACT IS REPORTED, SO A RESTING CLOTH STAYS QUIET */
+        /* THE COLLIDERS ARE IN THE WORLD, THE QUERIES GO OUT AND THEIR ANSWERS COME BACK THROUGH frame */
+        /* CANDIDATES COME FROM THE BROAD PHASE, WIDENED BY HOW FAR A PARTICLE HAS MOVED SINCE IT WAS */
+        /* BUILT PLUS ITS SWEEP OVER THE STEP. UNBOUNDED COLLIDERS TEST EVERY PARTICLE */
+        /* A DEEP PENETRATION OF A QUIET CLOTH STARTS UNTANGLING: THE PUSHES OUT ARE CAPPED AND ADD NO */
+        /* VELOCITY AND NOTHING BOUNCES, UNTIL NO PARTICLE IS DEEPER THAN THE THICKNESS */
+        /* DEPTHS ARE MEASURED FROM thickness / 2 OUTSIDE THE COLLIDER, WHERE PARTICLES ARE KEPT */
+        /* A DYNAMIC COLLIDER TAKES THE OPPOSITE OF THE MOMENTUM ITS PUSHES GIVE THE PARTICLES, AND NEVER */
+        /* STARTS UNTANGLING OR IS CACHED, AS IT MOVES BY ITSELF. FRICTION ACTS ON THE SLIP RELATIVE TO THE */
+        /* VELOCITY OF THE COLLIDER, SO A MOVING ONE DRAGS THE PARTICLES ALONG */
+        let frame = self.frame;
+        let (deep, push) = (self.config.thickness.max(UNTANGLE_DEPTH * rest_edge), UNTANGLE_PUSH * rest_edge);
+        let mut deepest: F = 0.;
+        let margin = self.config.thickness / 2.;
+        let continuous = self.config.continuous_collision;
+        std::mem::swap(&mut self.in_contact, &mut self.was_in_contact);
+        self.in_contact.clear();
+        self.in_contact.resize(self.particles.len(), false);
+        let mut reach = (0..self.particles.len()).fold(0., |reach: F, i| reach.max(self.reach(i)));
+        let mut candidates = std::mem::take(&mut self.candidates);
+        self.collider_impulses.clear();
+        self.collider_impulses.resize(colliders.len(), V::zeros());
+        for (collider_id, layered) in colliders.iter().enumerate() {
+            if !collider::meets(self.config.collision_mask, layered.layer) {
+                continue;
+            }
+            let collider = &layered.collider;
+            let (mu, restitution) = (layered.friction.unwrap_or(self.config.friction), layered.restitution);
+            let carried = frame.inverse_transform_vector(&layered.velocity()) * dt;
+            candidates.clear();
+            match collider.bounds() {
+                Some(bounds) => {
+                    let local = bounds.transformed(&frame.inverse()).inflated(margin + reach);
+                    self.broad_phase.in_box(&local, &mut |i| candidates.push(i));
+                    candidates.sort_unstable();
+                }
+                None => candidates.extend(0..self.particles.len()),
+            }
+            for &i in &candidates {
+                if self.particles.held(i) {
+                    continue;
+                }
+                let (old_p, p) = (self.particles.old_position(i), self.particles.data[i]);
+                let hit = collider.sweep(frame * old_p, frame * p, margin).filter(|_| continuous);
+                let hit = hit.map(|(hit, n)| (frame.inverse_transform_point(&hit), frame.inverse_transform_vector(&n)));
+                let (depth, normal) = match hit {
+                    /* STOP AT THE FIRST CONTACT AND ZERO THE NORMAL COMPONENT OF THE VELOCITY */
+                    Some((hit, n)) => {
+                        let (v, depth) = (p - old_p, (p - hit).norm());
+                        self.particles.place(i, hit, v - n * v.dot(&n));
+                        if self.untangling.is_none() {
+                            self.restitute(i, n, v.dot(&n), restitution, dt);
+                        }
+                        let slip = self.particles.displacement(i) - carried;
+                        self.particles.offset_displacement(i, -particles::friction(slip, n, depth, mu));
+                        (depth, Some(n))
+                    }
+                    None => match collider.push_out(frame * p, margin).map(|d| frame.inverse_transform_vector(&d)) {
+                        Some(delta) => {
+                            let depth = delta.norm();
+                            deepest = deepest.max(depth);
+                            if may_untangle && depth > deep && self.untangling.is_none() && layered.body.is_none() {
+                                self.untangling = Some(0);
+                            }
+                            if self.untangling.is_some() {
+                                let step = if depth > push { delta * (push / depth) } else { delta };
+                                self.particles.offset(i, step);
+                                self.particles.offset_displacement(i, -step);
+                            } else {
+                                self.particles.offset(i, delta);
+                            }
+                            if depth > 0. {
+                                if self.untangling.is_none() {
+                                    let approach = (p - old_p).dot(&delta) / depth;
+                                    self.restitute(i, delta / depth, approach, restitution, dt);
+                                }
+                                let slip = self.particles.displacement(i) - carried;
+                                let f = particles::friction(slip, delta / depth, depth, mu);
+                                self.particles.offset_displacement(i, -f);
+                            }
+                            (depth, Some(delta / depth).filter(|_| depth > 0.))
+                        }
+                        None => continue,
+                    },
+                };
+                if layered.body.is_some() {
+                    let change = self.particles.displacement(i) - (p - old_p);
+                    let momentum = change / (self.particles.inv_mass(i) * dt);
+                    self.collider_impulses[collider_id] -= self.vector_to_world(momentum);
+                }
+                /* A PUSHED PARTICLE MAY NOW REACH A LATER COLLIDER */
+                reach = reach.max(self.reach(i));
+                if !self.in_contact[i] && !self.was_in_contact.get(i).copied().unwrap_or(false) {
+                    let idx = self.grid_idx(i);
+                    self.events.push(ClothEvent::CollisionContact { idx, collider: collider_id, depth });
+                    self.heat_up(i);
+                }
+                self.in_contact[i] = true;
+                if self.config.contact_cache && layered.body.is_none() {
+                    let previous = self.contact_cache[i].filter(|c| c.collider == collider_id).map(|c| c.normal);
+                    if let Some(normal) = normal.or(previous) {
+                        let (collider, point) = (collider_id, self.particles.data[i]);
+                        self.contact_cache[i] = Some(CachedContact { collider, point, normal, separated: 0 });
+                    }
+                }
+            }
+        }
+        /* A CACHED CONTACT HELD ON ITS PLANE NEEDS NO PUSH, BUT IS STILL TOUCHING */
+        for (i, contact) in self.contact_cache.iter().enumerate() {
+            if contact.map_or(false, |c| c.separated == 0) {
+                self.in_contact[i] = true;
+            }
+        }
+        if let Some(max_correction) = self.config.max_step_correction {
+            self.clamp_corrections(max_correction * rest_edge);
+        }
+        if self.untangling.is_some() {
+            self.untangle(deepest <= self.config.thickness, push);
+        }
+        self.stats.clamped_total += self.stats.clamped;
+        self.candidates = candidates;
+        let respawn = self.apply_kill_plane();
+        self.update_pieces();
+        self.check_stability();
+        let particles = &self.particles;
+        let drift = |(i, q): (I, &P)| (particles.data[i] - q).norm();
+        self.broad_phase_slack = Some(self.integrated.iter().enumerate().map(drift).fold(0., F::max));
+        let particles = &self.particles;
+        self.mesh_dirty |= (0..particles.len()).any(|i| particles.displacement(i).norm() > MESH_EPSILON);
+        self.quiet = (0..particles.len()).all(|i| particles.displacement(i).norm() < QUIET_MOTION * rest_edge);
+        self.steps += 1;
+        if self.steps % CONTRACTION_INTERVAL == 0 {
+            self.retension();
+        }
+        self.stats.record(Phase::Collision, stopwatch.lap());
+        self.stats.step_time = start.elapsed();
+        /* THE CLOTH'S OWN RESOLUTION IS ALWAYS VALID */
+        let (w, h) = self.subdivisions();
+        if respawn && self.regenerate(w, h).is_ok() {
+            self.events.push(ClothEvent::Respawned);
+        }
+    }
+
+    /* HOLD EVERY PARTICLE THAT FELL BELOW THE KILL PLANE ON IT, STRAIGHT ABOVE WHERE IT ENDED UP */
+    /* TRUE IF KillMode::Respawn WANTS THE CLOTH RESET */
+    fn apply_kill_plane(&mut self) -> bool {
+        let height = match self.config.kill_plane {
+            Some(height) => height,
+            None => return false,
+        };
+        let n = self.particles.len();
+        self.killed.resize(n, false);
+        let (mut crossed, mut down) = (0, 0);
+        for i in 0..n {
+            if self.particles.held(i) {
+                continue;
+            }
+            let p = self.to_world(self.particles.data[i]);
+            if p.y > height {
+                self.killed[i] = false;
+                continue;
+            }
+            down += 1;
+            if !self.killed[i] {
+                self.killed[i] = true;
+                crossed += 1;
+            }
+            let on_plane = self.to_local(P::new(p.x, height, p.z));
+            self.particles.place(i, on_plane, V::zeros());
+        }
+        if crossed > 0 {
+            self.events.push(ClothEvent::KillPlane { crossed });
+        }
+        match self.config.kill_mode {
+            KillMode::Clamp => false,
+            KillMode::Respawn { fraction } => down as F > fraction * n as F,
+        }
+    }
+
+    /* HOLD THE PARTICLES OF LAST STEP'S COLLIDER CONTACTS ON THEIR CONTACT PLANES BEFORE THE BROAD PHASE: */
+    /* WHAT SANK BELOW IS LIFTED BACK, THE APPROACH VELOCITY IS REMOVED AND FRICTION APPLIED, SO A RESTING */
+    /* PARTICLE NO LONGER ALTERNATES BETWEEN SINKING AND BEING PUSHED OUT. A CONTACT IS DROPPED ONCE ITS */
+    /* COLLIDER IS GONE OR HAS MOVED, OR ITS PARTICLE HAS BEEN OFF THE PLANE FOR MORE THAN CONTACT_HYSTERESIS STEPS */
+    fn apply_cached_contacts(&mut self, colliders: &[LayeredCollider]) {
+        if !self.config.contact_cache {
+            self.contact_cache.clear();
+            return;
+        }
+        self.contact_cache.resize(self.particles.len(), None);
+        let (frame, margin) = (self.frame, self.config.thickness / 2.);
+        let slack = margin * CONTACT_DRIFT;
+        for i in 0..self.contact_cache.len() {
+            let mut contact = match self.contact_cache[i].take() {
+                Some(contact) => contact,
+                None => continue,
+            };
+            let mask = self.config.collision_mask;
+            let layered = match colliders.get(contact.collider) {
+                Some(layered) if collider::meets(mask, layered.layer) && layered.body.is_none() => layered,
+                _ => continue,
+            };
+            /* THE POINT WAS LEFT margin OUT OF THE COLLIDER, SO IF NEITHER HAS MOVED IT IS slack DEEP INTO THIS */
+            match layered.collider.push_out(frame * contact.point, margin + slack) {
+                Some(delta) if delta.norm() <= 2. * slack => {
+                    if let Some(n) = delta.try_normalize(F::EPSILON) {
+                        contact.normal = frame.inverse_transform_vector(&n);
+                    }
+                }
+                _ => continue,
+            }
+            let n = contact.normal;
+            let height = (self.particles.data[i] - contact.point).dot(&n);
+            if height > slack {
+                contact.separated += 1;
+                if contact.separated <= CONTACT_HYSTERESIS {
+                    self.contact_cache[i] = Some(contact);
+                }
+                continue;
+            }
+            if !self.particles.held(i) {
+                let sink = height.min(0.);
+                self.particles.offset(i, -n * sink);
+                let approach = self.particles.displacement(i).dot(&n);
+                if approach < 0. {
+                    self.particles.offset_displacement(i, -n * approach);
+                }
+                let (slip, mu) = (self.particles.displacement(i), layered.friction.unwrap_or(self.config.friction));
+                self.particles.offset_displacement(i, -particles::friction(slip, n, -sink, mu));
+            }
+            contact.separated = 0;
+            self.contact_cache[i] = Some(contact);
+        }
+    }
+
+    /* HOW FAR FROM ITS POSITION IN THE BROAD PHASE A PARTICLE'S SWEEP OVER THIS STEP CAN REACH */
+    fn reach(&self, i: I) -> F {
+        (self.particles.data[i] - self.integrated[i]).norm() + self.particles.displacement(i).norm()
+    }
+
+    /* MEAN REST LENGTH OF THE STRUCTURAL CONSTRAINTS, WHICH THE SAFETY LIMITS ARE MEASURED IN */
+    fn mean_rest_edge(&self) -> F {
+        let (sum, count) = self
+            .constraints
+            .iter()
+            .filter(|c| c.kind == ConstraintKind::Structural)
+            .fold((0., 0), |(sum, count), c| (sum + c.d, count + 1));
+        if count > 0 {
+            sum / count as F
+        } else {
+            0.
+        }
+    }
+
+    /* SAFETY VALVE AGAINST A YANKED OR EXPLODING PARTICLE FLINGING ITS NEIGHBOURS: CLAMP THE MOVE */
+    /* OF EVERY FREE PARTICLE OVER THIS STEP, KEEPING ITS DIRECTION. FIXED PARTICLES, WHICH INCLUDES */
+    /* PINNED, DRAGGED AND ATTACHED ONES, ARE MOVED KINEMATICALLY AND ARE NEVER CLAMPED */
+    fn clamp_speeds(&mut self, max: F) {
+        for i in 0..self.particles.len() {
+            if self.particles.clamp_displacement(i, max) {
+                self.stats.clamped += 1;
+            }
+        }
+    }
+
+    /* LIMIT HOW FAR SELF-COLLISIONS AND COLLIDERS MOVED EACH PARTICLE AWAY FROM ITS INTEGRATED POSITION */
+    /* THE VELOCITY THE COLLISION PASS LEFT IS KEPT, ONLY THE POSITION IS PULLED BACK ALONG THE CORRECTION */
+    fn clamp_corrections(&mut self, max: F) {
+        for i in 0..self.particles.len() {
+            if self.particles.held(i) {
+                continue;
+            }
+            let correction = self.particles.data[i] - self.integrated[i];
+            let norm = correction.norm();
+            if norm > max {
+                let displacement = self.particles.displacement(i);
+                let p = self.integrated[i] + correction * (max / norm);
+                self.particles.place(i, p, displacement);
+                self.stats.clamped += 1;
+            }
+        }
+    }
+
+    /* LIMIT THE MOVE OF EVERY PARTICLE OVER THE STEP TO max, THE VELOCITY ALONG WITH IT, AND STOP */
+    /* UNTANGLING ONCE clear, OR WHEN IT HAS TAKEN TOO LONG */
+    fn untangle(&mut self, clear: bool, max: F) {
+        for (i, &start) in self.step_start.iter().enumerate() {
+            if self.particles.held(i) {
+                continue;
+            }
+            let moved = self.particles.data[i] - start;
+            let norm = moved.norm();
+            if norm > max {
+                self.particles.place(i, start + moved * (max / norm), moved * (max / norm));
+                self.stats.clamped += 1;
+            }
+        }
+        let steps = self.untangling.map_or(0, |steps| steps + 1);
+        if !clear && steps >= UNTANGLE_STEPS {
+            warn!("cloth still inside a collider after untangling for {} steps, giving up", steps);
+        }
+        self.untangling = Some(steps).filter(|&steps| !clear && steps < UNTANGLE_STEPS);
+    }
+
+    /* GIVE A PARTICLE THAT HIT A COLLIDER AT approach ALONG normal ITS BOUNCE BY MOVING old_p */
+    /* IT ONLY EVER ADDS OUTWARD VELOCITY, SO WITH restitution 0 THE PROJECTION ALONE DECIDES THE RESPONSE */
+    fn restitute(&mut self, i: I, normal: V, approach: F, restitution: F, dt: F) {
+        if restitution <= 0. {
+            return;
+        }
+        let target = particles::bounce(approach, restitution, RESTITUTION_MIN_SPEED * dt);
+        let lift = target - self.particles.displacement(i).dot(&normal);
+        if lift > 0. {
+            self.particles.offset_displacement(i, normal * lift);
+        }
+    }
+
+    /* MEASURE THE CONTRACTION AND NUDGE THE REST LENGTH SCALE TOWARDS CANCELLING IT */
+    /* THE SCALE MOVES BY AT MOST RETENSION_RATE PER MEASUREMENT SO THE CLOTH RELAXES OUTWARDS */
+    /* OVER MANY STEPS, AND EASES BACK TO 1 WHILE EXTERNAL FORCES STRETCH THE CLOTH OR WHEN DISABLED */
+    fn retension(&mut self) {
+        let scale = self.rest_scale;
+        let particles = &self.particles.data;
+        let (length, rest) = self
+            .constraints
+            .iter()
+            .filter(|c| c.kind == ConstraintKind::Structural)
+            .fold((0., 0.), |(length, rest), c| {
+                (length + (particles[c.p2] - particles[c.p1]).norm(), rest + c.d / scale)
+            });
+        if rest == 0. {
+            return;
+        }
+        let contraction = 1. - length / rest;
+        self.stats.contraction = contraction;
+        self.stats.rest_scale = scale;
+
+        let target = match self.config.retension {
+            Some(threshold) if contraction > threshold => 1. + contraction.min(MAX_RETENSION),
+            Some(_) if contraction >= 0. => scale,
+            _ => 1.,
+        };
+        let next = scale + (target - scale).max(-RETENSION_RATE).min(RETENSION_RATE);
+        if next != scale {
+            self.constraints.iter_mut().for_each(|c| c.d *= next / scale);
+            self.rest_scale = next;
+            self.stats.rest_scale = next;
+            #[cfg(feature = "gpu")]
+            self.gpu.iter_mut().for_each(GpuSolver::invalidate);
+        }
+    }
+
+    /* MULTIPLY THE REST LENGTH OF EVERY CONSTRAINT, E.G. BELOW 1 TO SHRINK THE CLOTH OR PRE-TENSION IT */
+    /* A JUMP IN ONE STEP CAN BLOW THE SOLVER UP, SEE animate_rest_scale FOR A SMOOTH CHANGE */
+    pub fn scale_rest_lengths(&mut self, factor: F) {
+        self.scale_rest_lengths_where(factor, |_, _, _| true);
+        self.rest_factor *= factor;
+    }
+
+    /* scale_rest_lengths ON THE CONSTRAINTS FOR WHICH filter(kind, end, end) HOLDS, THE ENDS BY GRID */
+    /* INDEX. THE COARSE LEVEL OF THE HIERARCHICAL SOLVER ONLY FOLLOWS THE UNIFORM SCALING */
+    pub fn scale_rest_lengths_where(&mut self, factor: F, filter: impl Fn(ConstraintKind, GridIdx, GridIdx) -> bool) {
+        let (particles, w) = (&self.particles, self.particles.width());
+        let idx = |i: I| {
+            let origin = particles.origin(i);
+            (origin % w, origin / w)
+        };
+        for c in self.constraints.iter_mut().filter(|c| filter(c.kind, idx(c.p1), idx(c.p2))) {
+            c.d *= factor;
+        }
+        /* THE GEODESIC DISTANCES OF THE LONG RANGE ATTACHMENTS ARE MEASURED IN REST LENGTHS TOO */
+        self.neighbors = structural_neighbors(&self.constraints, &self.particles);
+        self.lra_dirty = true;
+        #[cfg(feature = "gpu")]
+        self.gpu.iter_mut().for_each(GpuSolver::invalidate);
+    }
+
+    /* EASE THE UNIFORM REST LENGTH FACTOR TO target OVER THE NEXT over_steps STEPS, THE SAME */
+    /* FRACTION OF THE REMAINING CHANGE EACH STEP SO THE SOLVER ONLY EVER SEES A SMALL NUDGE */
+    pub fn animate_rest_scale(&mut self, target: F, over_steps: I) {
+        self.rest_animation = Some((target, over_steps.max(1)));
+    }
+
+    /* THE FACTOR THE REST LENGTHS ARE AT OR ON THEIR WAY TO */
+    pub fn rest_factor(&self) -> F {
+        self.rest_animation.map_or(self.rest_factor, |(target, _)| target)
+    }
+
+    /* CURRENT REST LENGTH OF A CONSTRAINT, INCLUDING ANY PLASTIC DEFORMATION AND REST SCALING */
+    #[cfg(test)]
+    pub fn rest_length(&self, id: ConstraintId) -> Option<F> {
+        self.constraints.get(id).map(|c| c.d)
+    }
+
+    /* PLASTICITY OF THE FLEXION SPRINGS, SEE Plasticity. THE REST LENGTH IT WAS BUILT WITH IS RECOVERED */
+    /* FROM d AND plastic, SO REST SCALING SCALES IT ALONG. A RESET BUILDS A FRESH CLOTH WITHOUT ANY */
+    fn creep(&mut self, plasticity: Plasticity) {
+        let particles = &self.particles;
+        let mut crept = false;
+        for c in self.constraints.iter_mut().filter(|c| c.kind == ConstraintKind::Flexion) {
+            let length = (particles.data[c.p2] - particles.data[c.p1]).norm();
+            if (length - c.d).abs() <= plasticity.yield_strain * c.d {
+                c.yielding = 0;
+                continue;
+            }
+            c.yielding += 1;
+            if c.yielding <= plasticity.yield_steps {
+                continue;
+            }
+            let original = c.d / (1. + c.plastic);
+            let d = c.d + (length - c.d) * plasticity.creep;
+            let max = plasticity.max_strain;
+            c.plastic = (d / original - 1.).max(-max).min(max);
+            c.d = original * (1. + c.plastic);
+            crept = true;
+        }
+        if crept {
+            #[cfg(feature = "gpu")]
+            self.gpu.iter_mut().for_each(GpuSolver::invalidate);
+        }
+    }
+
+    fn animate_rest_lengths(&mut self) {
+        let (target, steps) = match self.rest_animation {
+            Some(animation) => animation,
+            None => return,
+        };
+        let next = self.rest_factor + (target - self.rest_factor) / steps as F;
+        self.scale_rest_lengths(next / self.rest_factor);
+        self.rest_animation = if steps > 1 { Some((target, steps - 1)) } else { None };
+    }
+
+    /* THE TIME STEP ACTUALLY TAKEN: dt CLAMPED TO max_dt, AND REDUCED WHILE RECOVERING */
+    pub fn dt(&self) -> F {
+        (self.config.dt * self.dt_scale).min(self.config.max_dt) * self.recovery_scale
+    }
+
+    /* THE NEXT STEPS COVER dt_scale TIME STEPS, STILL AT MOST max_dt, AT THE GIVEN LEVEL OF DETAIL */
+    pub fn set_lod(&mut self, dt_scale: F, level: &LodLevel) {
+        self.dt_scale = dt_scale;
+        self.lod = *level;
+    }
+
+    /* A NON-FINITE POSITION OR A JUMP BEYOND UNSTABLE_DISPLACEMENT MEANS THE SOLVER HAS BLOWN UP: */
+    /* ROLL BACK TO THE OLDEST KNOWN-GOOD STATE AND CONTINUE AT HALF THE TIME STEP FOR A WHILE, */
+    /* HALVING AGAIN IF IT BLOWS UP AGAIN BEFORE THE RECOVERY IS OVER */
+    fn check_stability(&mut self) {
+        let n = self.particles.len();
+        let particles = &self.particles;
+        let unstable = (0..n).any(|i| {
+            !particles.data[i].coords.iter().all(|c| c.is_finite())
+                || particles.displacement(i).norm() > UNSTABLE_DISPLACEMENT
+        });
+
+        if !unstable {
+            /* RECYCLE THE OLDEST SNAPSHOT'S BUFFERS, STATES FROM BEFORE A TEAR NO LONGER FIT */
+            if self.good_states.front().map_or(false, |(p, _)| p.len() != n) {
+                self.good_states.clear();
+            }
+            let (mut p, mut old_p) = match self.good_states.len() {
+                RECOVERY_HISTORY => self.good_states.pop_front().unwrap(),
+                _ => (vec![], vec![]),
+            };
+            p.clear();
+            p.extend_from_slice(&self.particles.data);
+            old_p.clear();
+            old_p.extend((0..n).map(|i| self.particles.old_position(i)));
+            self.good_states.push_back((p, old_p));
+
+            if self.recovery_steps > 0 {
+                self.recovery_steps -= 1;
+                if self.recovery_steps == 0 {
+                    self.recovery_scale = 1.;
+                }
+            }
+            return;
+        }
+
+        self.stats.recoveries += 1;
+        self.mesh_dirty = true;
+        self.clear_warm_start();
+        self.recovery_scale = (self.recovery_scale * 0.5).max(MIN_RECOVERY_SCALE);
+        self.recovery_steps = RECOVERY_STEPS;
+        /* THE LATER STATES ALREADY LED UP TO THE BLOW-UP, ONLY THE OLDEST IS KEPT */
+        self.good_states.truncate(1);
+        match self.good_states.front() {
+            Some((p, old_p)) => {
+                for i in 0..n {
+                    if !self.particles.frozen(i) {
+                        self.particles.place(i, p[i], p[i] - old_p[i]);
+                    }
+                }
+            }
+            /* NOTHING TO GO BACK TO YET, THE BEST LEFT IS TO STOP EVERYTHING MOVING */
+            None => (0..n).for_each(|i| self.particles.reset_velocity(i)),
+        }
+        warn!(
+            "cloth went unstable, rolled back and continuing at {}x the time step",
+            self.recovery_scale
+        );
+    }
+
+    /* THE ORDER ONLY MATTERS HERE, JACOBI CORRECTIONS DO NOT SEE EACH OTHER */
+    /* WITH kind_iterations EACH KIND IS SWEPT ON ITS OWN UNTIL ITS COUNT RUNS OUT, IN GENERATION ORDER */
+    /* OR ALTERNATING UNDER AlternateSweeps. THERE IS NO SHUFFLED ORDER WITHIN A KIND */
+    fn solve_gauss_seidel(&mut self, iter: I) -> F {
+        let (dt, k, omega) = (self.dt(), &self.config.stiffness, self.config.over_relaxation);
+        let (particles, constraints) = (&mut self.particles, &mut self.constraints);
+        let mut max_correction: F = 0.;
+        let mut update = |c: &mut Constraint| {
+            max_correction = max_correction.max(gauss_seidel_update(particles, c, dt, k, omega));
+        };
+        if let Some(counts) = self.config.kind_iterations {
+            let reverse = self.config.solve_order == SolveOrder::AlternateSweeps && iter % 2 == 1;
+            for kind in ConstraintKind::ALL.iter().copied().filter(|&kind| iter < counts.get(kind)) {
+                let ids = &self.by_kind[kind as I];
+                let mut sweep = |id: &ConstraintId| {
+                    if let Some(c) = constraints.get_mut(*id) {
+                        update(c);
+                    }
+                };
+                match reverse {
+                    true => ids.iter().rev().for_each(&mut sweep),
+                    false => ids.iter().for_each(&mut sweep),
+                }
+            }
+            return max_correction;
+        }
+        match self.config.solve_order {
+            SolveOrder::AlternateSweeps if iter % 2 == 1 => constraints.iter_mut().rev().for_each(&mut update),
+            SolveOrder::Shuffled => {
+                for &id in &self.shuffled {
+                    if let Some(c) = constraints.get_mut(id) {
+                        update(c);
+                    }
+                }
+            }
+            _ => constraints.iter_mut().for_each(&mut update),
+        }
+        max_correction
+    }
+
+    /* EXTRA GAUSS-SEIDEL SWEEPS OVER THE CONSTRAINTS TOUCHING A PARTICLE WITHIN boost.radius STRUCTURAL */
+    /* SPRINGS OF A HOT ONE, FOUND BREADTH-FIRST, SO A GRAB OR AN IMPACT CONVERGES WITHOUT RAISING THE */
+    /* ITERATIONS OF THE WHOLE CLOTH. THE JACOBI SOLVER IS BOOSTED WITH GAUSS-SEIDEL SWEEPS AS WELL */
+    fn boost_locally(&mut self, boost: LocalBoost) {
+        let n = self.particles.len();
+        self.heat.resize(n, 0.);
+        let mut frontier: Vec<I> = (0..n).filter(|&i| self.heat[i] > HOT_HEAT).collect();
+        if frontier.is_empty() {
+            return;
+        }
+        let region = &mut self.boost_region;
+        region.clear();
+        region.resize(n, false);
+        frontier.iter().for_each(|&i| region[i] = true);
+        for _ in 0..boost.radius {
+            let mut next = vec![];
+            for &i in &frontier {
+                for &(j, _) in self.neighbors.get(i).map_or(&[][..], |n| &n[..]) {
+                    if !region[j] {
+                        region[j] = true;
+                        next.push(j);
+                    }
+                }
+            }
+            frontier = next;
+        }
+        self.boosted.clear();
+        let touching = |(id, c): (ConstraintId, &Constraint)| Some(id).filter(|_| region[c.p1] || region[c.p2]);
+        self.boosted.extend(self.constraints.iter_with_ids().filter_map(touching));
+
+        let (dt, k, omega) = (self.dt(), &self.config.stiffness, self.config.over_relaxation);
+        for _ in 0..boost.iterations {
+            for &id in &self.boosted {
+                if let Some(c) = self.constraints.get_mut(id) {
+                    gauss_seidel_update(&mut self.particles, c, dt, k, omega);
+                }
+            }
+        }
+        self.stats.boosted = self.boosted.len();
+    }
+
+    fn heat_up(&mut self, i: I) {
+        self.heat.resize(self.particles.len(), 0.);
+        self.heat[i] = 1.;
+    }
+
+    /* DECAY THE HEAT OF THE LAST STEP AND HEAT UP THE ENDS OF THE SPRINGS STRAINED PAST HOT_STRAIN */
+    fn cool_down(&mut self) {
+        self.heat.resize(self.particles.len(), 0.);
+        self.heat.iter_mut().for_each(|h| *h *= HEAT_DECAY);
+        for &(strain, p1, p2) in &self.strains {
+            if strain > HOT_STRAIN {
+                self.heat[p1] = 1.;
+                self.heat[p2] = 1.;
+            }
+        }
+    }
+
+    /* PRE-APPLY warm_start OF THE CORRECTIONS EACH CONSTRAINT ENDED THE LAST STEP WITH, SINCE THEY */
+    /* CHANGE LITTLE FROM STEP TO STEP; THE NEW ACCUMULATION STARTS FROM WHAT WAS PRE-APPLIED */
+    /* ONLY THE GAUSS-SEIDEL ITERATIONS ON THE CPU ACCUMULATE, ANYTHING ELSE STARTS COLD */
+    fn warm_start(&mut self, on_gpu: bool) {
+        let fraction = match self.config.solver {
+            SolverMode::GaussSeidel | SolverMode::Hierarchical { .. } if !on_gpu => self.config.warm_start,
+            _ => 0.,
+        };
+        if fraction == 0. {
+            self.clear_warm_start();
+            return;
+        }
+        let particles = &mut self.particles;
+        for c in self.constraints.iter_mut() {
+            if particles.asleep(c.p1) {
+                c.accumulated = V::zeros();
+                continue;
+            }
+            c.accumulated *= fraction;
+            particles.offset(c.p1, c.accumulated * c.share);
+            particles.offset(c.p2, -c.accumulated * (2. - c.share));
+        }
+    }
+
+    /* THE LAST CORRECTIONS NO LONGER FIT AFTER A TEAR, A ROLLBACK OR A RESET */
+    fn clear_warm_start(&mut self) {
+        self.constraints.iter_mut().for_each(|c| c.accumulated = V::zeros());
+    }
+
+    /* SAME CORRECTIONS, BUT ALL COMPUTED FROM THE POSITIONS AT THE START OF THE PASS */
+    /* AVERAGED PER PARTICLE AND OVER-RELAXED SINCE NEIGHBORING CONSTRAINTS PULL AGAINST EACH OTHER */
+    fn solve_jacobi(&mut self) -> F {
+        let dt = self.dt();
+        self.corrections.clear();
+        self.corrections.resize(self.particles.len(), (V::zeros(), 0));
+        let mut max_correction: F = 0.;
+        for c in self.constraints.iter().filter(|c| !self.particles.asleep(c.p1)) {
+            let f_c = c.correction(&self.particles, dt, &self.config.stiffness);
+            let (i1, i2) = (c.p1, c.p2);
+            self.corrections[i1].0 += f_c * c.share;
+            self.corrections[i1].1 += 1;
+            self.corrections[i2].0 -= f_c * (2. - c.share);
+            self.corrections[i2].1 += 1;
+            max_correction = max_correction.max(f_c.norm());
+        }
+
+        let relaxation = self.config.relaxation;
+        for (i, &(sum, count)) in self.corrections.iter().enumerate() {
+            if count > 0 {
+                self.particles.offset(i, sum * (relaxation / count as F));
+            }
+        }
+        max_correction
+    }
+
+    /* MULTI-SOURCE DIJKSTRA FROM ALL FIXED PARTICLES OVER THE STRUCTURAL SPRINGS */
+    fn compute_long_range_attachments(&mut self) {
+        self.lra.clear();
+        self.lra.resize(self.particles.len(), None);
+        let mut heap = BinaryHeap::new();
+        let particles = &self.particles;
+        for i in (0..particles.len()).filter(|&i| particles.fixed(i)) {
+            self.lra[i] = Some((i, 0.));
+            heap.push(Frontier(0., i));
+        }
+        while let Some(Frontier(d, i)) = heap.pop() {
+            let (anchor, best) = self.lra[i].unwrap();
+            if d > best {
+                continue;
+            }
+            for &(j, rest) in &self.neighbors[i] {
+                let nd = d + rest;
+                if self.lra[j].map_or(true, |(_, dj)| nd < dj) {
+                    self.lra[j] = Some((anchor, nd));
+                    heap.push(Frontier(nd, j));
+                }
+            }
+        }
+        self.lra_dirty = false;
+    }
+
+    /* A SINGLE NON-ITERATIVE PROJECTION OF EACH PARTICLE INTO THE SPHERE AROUND ITS PIN */
+    fn apply_long_range_attachments(&mut self) {
+        if self.lra_dirty {
+            self.compute_long_range_attachments();
+        }
+        for i in 0..self.particles.len() {
+            if let Some((anchor, max_d)) = self.lra[i] {
+                let a = self.particles.data[anchor];
+                let diff = self.particles.data[i] - a;
+                let d = diff.norm();
+                if d > max_d {
+                    self.particles.offset(i, diff * (max_d / d - 1.));
+                }
+            }
+        }
+    }
+
+    /* AERODYNAMIC FORCE ON EACH FACE FROM THE AIR VELOCITY RELATIVE TO IT, SEE wind_force */
+    /* PROPORTIONAL TO THE FACE AREA AND SPLIT EVENLY BETWEEN ITS CORNERS */
+    fn apply_wind(&mut self) {
+        let wind = self.config.wind;
+        if wind.strength == 0. && wind.turbulence == 0. {
+            return;
+        }
+        let dir = wind.direction.try_normalize(F::EPSILON).unwrap_or_else(V::zeros);
+        /* THE SHELTER IS RECAST EVERY interval STEPS, AND RIGHT AWAY ONCE A TEAR CHANGED THE FACES */
+        match self.config.wind_occlusion {
+            Some(occlusion) if dir != V::zeros() => {
+                if self.steps % occlusion.interval == 0 || self.wind_shelter.len() != self.triangles.len() {
+                    let upwind = -self.frame.inverse_transform_vector(&dir);
+                    self.cast_wind_shelter(occlusion, upwind);
+                }
+            }
+            _ => self.wind_shelter.clear(),
+        }
+        let (porosity, air_drag) = (self.config.porosity, self.config.air_drag);
+        /* THE GUSTS DRIFT DOWNWIND AS THEY EVOLVE */
+        let drift = (dir + V::new(0., 0.1, 0.)) * (self.time * wind.frequency);
+        /* THE GUSTS ARE SAMPLED IN THE WORLD SO THEY STAY PUT WHEN THE ENTITY MOVES */
+        let (frame, noise) = (self.frame, &self.noise);
+        let air_at = |p: P| {
+            let gust = noise.sample_vector(frame * p * wind.scale - drift);
+            frame.inverse_transform_vector(&(dir * wind.strength + gust * wind.turbulence))
+        };
+        for (f, &Face { v: [a, b, c], .. }) in self.triangles.iter().enumerate() {
+            let particles = &self.particles;
+            let (pa, pb, pc) = (particles.data[a], particles.data[b], particles.data[c]);
+            let shelter = self.wind_shelter.get(f).copied().unwrap_or(1.);
+            let air = air_at(P::from((pa.coords + pb.coords + pc.coords) / 3.));
+            let velocity = (particles.velocity(a) + particles.velocity(b) + particles.velocity(c)) / 3.;
+
+            /* |n| IS TWICE THE AREA OF THE FACE */
+            let n = (pb - pa).cross(&(pc - pa));
+            let area = n.norm() / 2.;
+            if area < F::EPSILON {
+                continue;
+            }
+            let n = n / (2. * area);
+            /* EACH FACE TAKES THE AVERAGE DRAG COEFFICIENT OF ITS CORNERS */
+            let c_drag = (self.drag_at(a) + self.drag_at(b) + self.drag_at(c)) / 3.;
+            let force = wind_force(air - velocity, n, area, porosity, air_drag) * (shelter * c_drag / 3.);
+            for &i in &[a, b, c] {
+                self.particles.add_force(i, force);
+            }
+        }
+
+        /* A ROPE HAS NO FACES, EACH SEGMENT CATCHES THE AIR FLOWING ACROSS IT OVER ITS LENGTH TIMES ITS THICKNESS */
+        if !self.is_rope() {
+            return;
+        }
+        let thickness = self.config.thickness;
+        for c in self.constraints.iter().filter(|c| c.kind == ConstraintKind::Structural) {
+            let particles = &self.particles;
+            let (a, b) = (c.p1, c.p2);
+            let segment = particles.data[b] - particles.data[a];
+            let length = segment.norm();
+            if length < F::EPSILON {
+                continue;
+            }
+            let air = air_at(nalgebra::center(&particles.data[a], &particles.data[b]));
+            let relative = air - (particles.velocity(a) + particles.velocity(b)) / 2.;
+            let t = segment / length;
+            let n = match (relative - t * t.dot(&relative)).try_normalize(F::EPSILON) {
+                Some(n) => n,
+                None => continue,
+            };
+            let c_drag = (self.drag_at(a) + self.drag_at(b)) / 2.;
+            let force = wind_force(relative, n, length * thickness, porosity, air_drag) * (c_drag / 2.);
+            for &i in &[a, b] {
+                self.particles.add_force(i, force);
+            }
+        }
+    }
+
+    /* CAST A RAY reach THICKNESSES UPWIND FROM THE CENTER OF EVERY FACE THROUGH THE TRIANGLE BVH OF */
+    /* SELF-COLLISION, REBUILT HERE FROM THE CURRENT POSITIONS. A FACE THE RAY HITS, OTHER THAN ONE */
+    /* SHARING A CORNER WITH THE CASTING FACE, SHELTERS IT DOWN TO attenuation OF THE WIND */
+    fn cast_wind_shelter(&mut self, occlusion: WindOcclusion, upwind: V) {
+        let particles = &self.particles.data;
+        self.triangle_boxes.clear();
+        self.triangle_boxes.extend(self.triangles.iter().map(|&Face { v: [a, b, c], .. }| {
+            Aabb::from_points(&[particles[a], particles[b], particles[c]])
+        }));
+        self.bvh.build(&self.triangle_boxes);
+
+        let ray = upwind * (occlusion.reach * self.config.thickness);
+        let (triangles, bvh) = (&self.triangles, &self.bvh);
+        let corners = |v: [I; 3]| [particles[v[0]], particles[v[1]], particles[v[2]]];
+        self.wind_shelter = triangles
+            .iter()
+            .map(|&Face { v, .. }| {
+                let [a, b, c] = corners(v);
+                let origin = P::from((a.coords + b.coords + c.coords) / 3.);
+                let mut blocked = false;
+                bvh.query(&Aabb::from_points(&[origin, origin + ray]), &mut |t| {
+                    let other = triangles[t].v;
+                    if blocked || other.iter().any(|i| v.contains(i)) {
+                        return;
+                    }
+                    blocked = ray_triangle(origin, ray, corners(other)).map_or(false, |(t, _)| t <= 1.);
+                });
+                if blocked { occlusion.attenuation } else { 1. }
+            })
+            .collect();
+    }
+
+    /* REMOVE PART OF THE RELATIVE VELOCITY OF EACH SPRING'S END POINTS ALONG THE SPRING */
+    /* WITH THE FRACTION CLAMPED TO [0, 1] THE RELATIVE VELOCITY SHRINKS BUT NEVER FLIPS SIGN */
+    fn apply_forces(&mut self) {
+        self.apply_wind();
+        self.apply_attractors();
+        self.apply_force_fields();
+        /* GRAVITY ACCELERATES ALL PARTICLES EQUALLY REGARDLESS OF MASS */
+        self.particles.accelerate(self.vector_to_local(self.config.gravity));
+    }
+
+    /* EACH PARTICLE IS PULLED TOWARDS THE ATTRACTORS FROM ITS WORLD POSITION, THE SUM GOES BACK THROUGH frame */
+    fn apply_attractors(&mut self) {
+        if self.attractors.is_empty() {
+            return;
+        }
+        let frame = self.frame;
+        for i in 0..self.particles.len() {
+            let p = frame * self.particles.data[i];
+            let force: V = self.attractors.iter().map(|a| a.force(p)).sum();
+            self.particles.add_force(i, frame.inverse_transform_vector(&force));
+        }
+    }
+
+    /* EVERY FIELD IS SAMPLED AT EACH PARTICLE'S WORLD POSITION, THE SUM GOES BACK THROUGH frame */
+    fn apply_force_fields(&mut self) {
+        if self.fields.is_empty() {
+            return;
+        }
+        let frame = self.frame;
+        for i in 0..self.particles.len() {
+            let p = frame * self.particles.data[i];
+            let force: V = self.fields.iter().map(|f| f.sample(p)).sum();
+            self.particles.add_force(i, frame.inverse_transform_vector(&force));
+        }
+    }
+
+    /* OPEN THE GPU SOLVER IF THE CONFIG ASKS FOR IT, FALLING BACK TO THE CPU WITHOUT ONE */
+    #[cfg(feature = "gpu")]
+    fn init_gpu(&mut self) {
+        if !self.config.gpu {
+            return;
+        }
+        if self.config.deterministic {
+            warn!("gpu results depend on the device, solving on the cpu for determinism");
+            return;
+        }
+        match GpuSolver::new() {
+            Ok(gpu) => self.gpu = Some(gpu),
+            Err(err) => warn!("gpu solver unavailable, solving on the cpu: {}", err),
+        }
+    }
+
+    #[cfg(not(feature = "gpu"))]
+    fn init_gpu(&mut self) {}
+
+    /* JACOBI ITERATIONS AND INTEGRATION ON THE GPU, FALSE IF THE CPU HAS TO DO THEM */
+    /* AREA AND PRESSURE CONSTRAINTS ARE NOT PORTED AND ARE SKIPPED ON THIS PATH */
+    #[cfg(feature = "gpu")]
+    fn step_on_gpu(&mut self) -> bool {
+        let gpu = match self.gpu.as_mut() {
+            Some(gpu) => gpu,
+            None => return false,
+        };
+        if gpu.needs_constraints(self.particles.len()) {
+            let links = self.constraints.iter().map(|c| (c.p1, c.p2, c.d, c.kind, c.weave));
+            gpu.set_constraints(self.particles.len(), links);
+        }
+        self.apply_forces();
+        let dt = self.dt();
+        let (particles, config) = (&mut self.particles, &self.config);
+        if let Err(err) = self.gpu.as_mut().unwrap().step(particles, config, dt) {
+            /* THE FORCES ARE STILL ACCUMULATED, SO THIS STEP ONLY LOSES ITS CONSTRAINT ITERATIONS */
+            bevy::log::error!("gpu step failed, solving on the cpu from now on: {}", err);
+            self.gpu = None;
+            self.particles.integrate(self.config.integrator, self.config.damping, dt);
+        }
+        true
+    }
+
+    #[cfg(not(feature = "gpu"))]
+    fn step_on_gpu(&mut self) -> bool {
+        false
+    }
+
+    /* WHETHER THE CONSTRAINTS AND INTEGRATION CURRENTLY RUN ON THE GPU, FALSE ONCE A FAILED STEP FELL BACK */
+    #[cfg(feature = "gpu")]
+    pub fn on_gpu(&self) -> bool {
+        self.gpu.is_some()
+    }
+
+    #[cfg(not(feature = "gpu"))]
+    pub fn on_gpu(&self) -> bool {
+        false
+    }
+
+    fn damp_springs(&mut self) {
+        let (damping, dt) = (self.config.spring_damping, self.dt());
+        for c in self.constraints.iter() {
+            let k = damping.get(c.kind);
+            if k == 0. {
+                continue;
+            }
+            let particles = &self.particles;
+            let (i1, i2) = (c.p1, c.p2);
+            let (w1, w2) = (particles.inv_mass(i1), particles.inv_mass(i2));
+            let p12 = particles.data[i2] - particles.data[i1];
+            let d = p12.norm();
+            if d == 0. || w1 + w2 == 0. {
+                continue;
+            }
+            let n = p12 / d;
+            let v_rel = (particles.velocity(i2) - particles.velocity(i1)).dot(&n) * dt;
+            let delta = n * (k * v_rel / (w1 + w2));
+            self.particles.offset(i1, delta * w1);
+            self.particles.offset(i2, -delta * w2);
+        }
+    }
+
+    /* PROVOT-STYLE FIXUP: PULL OVERSTRETCHED STRUCTURAL EDGES BACK TO EXACTLY THE LIMIT */
+    fn limit_strain(&mut self, (max_warp, max_weft): (F, F)) {
+        for _ in 0..STRAIN_LIMIT_ITER {
+            for c in self.constraints.iter().filter(|c| c.kind == ConstraintKind::Structural) {
+                let particles = &self.particles;
+                let (i1, i2) = (c.p1, c.p2);
+                let (w1, w2) = (particles.inv_mass(i1), particles.inv_mass(i2));
+                let p12 = particles.data[i2] - particles.data[i1];
+                let d = p12.norm();
+                let max_strain = if c.weave == Weave::Warp { max_warp } else { max_weft };
+                let limit = max_strain * c.d;
+                if d <= limit || w1 + w2 == 0. {
+                    continue;
+                }
+                let delta = p12 * ((d - limit) / d / (w1 + w2));
+                self.particles.offset(i1, delta * w1);
+                self.particles.offset(i2, -delta * w2);
+            }
+        }
+    }
+
+    /* CHEAP POINT-POINT COLLISION CHECKING, PARTICLES ARE KEPT A FULL THICKNESS APART */
+    fn collide_points(&mut self) {
+        let (separation, mu) = (self.config.thickness, self.config.friction);
+        let particles = &self.particles;
+        /* THE CELLS ARE AT LEAST separation WIDE, SO EVERY PAIR IN CONTACT IS IN THE SAME OR ADJACENT CELLS */
+        for (i1, i2) in self.broad_phase.iter_cell_pairs() {
+            let (p1, p2) = (particles.data[i1], particles.data[i2]);
+            let diff = p2 - p1;
+            let d = diff.norm();
+            let fixed = (particles.inv_mass(i1) == 0., particles.inv_mass(i2) == 0.);
+            if d >= separation || d == 0. || (fixed.0 && fixed.1) {
+                continue;
+            }
+            /* SPLIT THE SEPARATION BETWEEN THE PAIR, A FIXED PARTICLE LEAVES ALL OF IT TO THE OTHER */
+            let delta = diff * (1. - separation / d);
+            let share = match fixed {
+                (true, _) => 0.,
+                (_, true) => 1.,
+                _ => 0.5,
+            };
+            self.corrections[i1].0 += delta * share;
+            self.corrections[i1].1 += 1;
+            self.corrections[i2].0 -= delta * (1. - share);
+            self.corrections[i2].1 += 1;
+            /* FRICTION DRAGS EACH ALONG WITH THE OTHER, SHARED LIKE THE SEPARATION */
+            let slip = particles.displacement(i2) - particles.displacement(i1);
+            let f = particles::friction(slip, diff / d, delta.norm(), mu);
+            self.frictions[i1] += f * share;
+            self.frictions[i2] -= f * (1. - share);
+        }
+    }
+
+    /* POINT-FACE COLLISION CHECKING, ONLY AGAINST TRIANGLES NEARBY IN THE BVH */
+    /* A BALL OF HALF THE THICKNESS KEPT HALF THE THICKNESS FROM THE TRIANGLE, */
+    /* SO PARTICLES END UP A FULL THICKNESS FROM THE OTHER LAYER */
+    fn collide_triangles(&mut self) {
+        let (radius, mu) = (self.config.thickness / 2., self.config.friction);
+        let particles = &self.particles.data;
+
+        self.triangle_boxes.clear();
+        self.triangle_boxes.extend(self.triangles.iter().map(|&Face { v: [a, b, c], .. }| {
+            Aabb::from_points(&[particles[a], particles[b], particles[c]])
+        }));
+        self.bvh.build(&self.triangle_boxes);
+
+        let (triangles, corrections, frictions) = (&self.triangles, &mut self.corrections, &mut self.frictions);
+        let moving = &self.particles;
+        let displacement = |i: I| moving.displacement(i);
+        let ball = Ball::new(radius);
+        for (i, p) in particles.iter().enumerate() {
+            let m = Isometry3::translation(p.x, p.y, p.z);
+            self.bvh.query(&Aabb::around(*p, 2. * radius), &mut |t| {
+                let [a, b, c] = triangles[t].v;
+                /* SKIP TRIANGLES THE PARTICLE ITSELF BELONGS TO */
+                if i == a || i == b || i == c {
+                    return;
+                }
+                let t = Triangle::new(particles[a], particles[b], particles[c]);
+                let res = closest_points(&m, &ball, &Isometry3::identity(), &t, radius);
+                if let ClosestPoints::WithinMargin(pa, pb) = res {
+                    let diff = pb - pa;
+                    let d = diff.norm();
+                    let ratio = radius / d;
+                    let delta = diff * (1. - ratio);
+                    corrections[i].0 += delta;
+                    corrections[i].1 += 1;
+                    /* THE TRIANGLE MOVES WITH THE AVERAGE OF ITS CORNERS */
+                    let slip = (displacement(a) + displacement(b) + displacement(c)) / 3. - displacement(i);
+                    frictions[i] += particles::friction(slip, diff / d, delta.norm(), mu);
+                }
+            });
+        }
+    }
+
+    /* EDGE-EDGE PROXIMITY OF THE STRUCTURAL EDGES, AGAINST EDGES SCISSORING THROUGH EACH OTHER IN */
+    /* TIGHT FOLDS WHERE NO PARTICLE COMES CLOSE TO ANOTHER. EDGES CLOSER THAN THE THICKNESS ARE PUSHED */
+    /* APART AT THEIR CLOSEST POINTS, SPREAD OVER THE FOUR ENDS BY THE CLOSEST-POINT WEIGHTS. ONLY EDGES */
+    /* WITH AN AWAKE END THAT TOUCHED SOMETHING IN THE LAST EDGE_ACTIVE_STEPS STEPS ARE TESTED, AND AT */
+    /* MOST max_pairs PAIRS PER STEP. RUNS BEFORE THE CORRECTIONS ARE APPLIED, SO THE GRID IS EXACT */
+    fn collide_edges(&mut self, max_pairs: I) {
+        let n = self.particles.len();
+        self.edge_active.resize(n, 0);
+        for i in 0..n {
+            let touched = self.corrections[i].1 > 0 || self.in_contact.get(i).copied().unwrap_or(false);
+            let steps = &mut self.edge_active[i];
+            *steps = if touched { EDGE_ACTIVE_STEPS } else { steps.saturating_sub(1) };
+        }
+
+        let separation = self.config.thickness;
+        let (particles, neighbors, grid, constraints) =
+            (&self.particles, &self.neighbors, &self.broad_phase, &self.constraints);
+        let (candidates, hits, corrections) = (&mut self.edge_candidates, &mut self.edge_hits, &mut self.corrections);
+        let edge_active = &self.edge_active;
+        let active = |i: I| edge_active[i] > 0 && !particles.asleep(i);
+        let edge = |i: I, j: I| (i.min(j), i.max(j));
+        let structural = || constraints.iter().filter(|c| c.kind == ConstraintKind::Structural);
+        let length = |i: I, j: I| (particles.data[j] - particles.data[i]).norm();
+        let longest = structural().map(|c| length(c.p1, c.p2)).fold(0., F::max);
+
+        hits.clear();
+        let mut pairs = 0;
+        'edges: for spring in structural() {
+            let (a, b) = edge(spring.p1, spring.p2);
+            if !active(a) && !active(b) {
+                continue;
+            }
+            /* AN EDGE WITHIN separation HAS AN END WITHIN THIS RADIUS OF THE MIDPOINT */
+            let (pa, pb) = (particles.data[a], particles.data[b]);
+            let radius = length(a, b) / 2. + separation + longest / 2.;
+            candidates.clear();
+            grid.neighbors_of(&nalgebra::center(&pa, &pb), radius, &mut |j| {
+                candidates.extend(neighbors[j].iter().map(|&(k, _)| edge(j, k)));
+            });
+            candidates.sort_unstable();
+            candidates.dedup();
+            for &(c, d) in candidates.iter() {
+                /* EDGES SHARING A PARTICLE, AND PAIRS OF ACTIVE EDGES FROM THE LARGER ONE */
+                if c == a || c == b || d == a || d == b || ((c, d) < (a, b) && (active(c) || active(d))) {
+                    continue;
+                }
+                if pairs == max_pairs {
+                    break 'edges;
+                }
+                pairs += 1;
+                let (pc, pd) = (particles.data[c], particles.data[d]);
+                let (s, t) = segment_closest(pa, pb, pc, pd);
+                let diff = (pc + (pd - pc) * t) - (pa + (pb - pa) * s);
+                let dist = diff.norm();
+                if dist >= separation || dist == 0. {
+                    continue;
+                }
+                /* ONE POSITION-BASED CONSTRAINT dist >= separation OVER THE FOUR ENDS, n POINTING FROM ab TO cd */
+                let ends = [(a, -(1. - s)), (b, -s), (c, 1. - t), (d, t)];
+                let weight: F = ends.iter().map(|&(i, g)| particles.inv_mass(i) * g * g).sum();
+                if weight == 0. {
+                    continue;
+                }
+                let (normal, lambda) = (diff / dist, (separation - dist) / weight);
+                for &(i, g) in &ends {
+                    corrections[i].0 += normal * (particles.inv_mass(i) * g * lambda);
+                    corrections[i].1 += 1;
+                    hits.push(i);
+                }
+            }
+        }
+        self.stats.edge_pairs = pairs;
+        for &i in &self.edge_hits {
+            self.edge_active[i] = EDGE_ACTIVE_STEPS;
+        }
+    }
+
+    /* BUILDER FOR A CLOTH WITH A DRAG COEFFICIENT PER PARTICLE, SEE set_drag_with */
+    pub fn with_drag(mut self, f: impl Fn(GridIdx) -> F) -> Self {
+        self.set_drag_with(f);
+        self
+    }
+
+    /* DRAG COEFFICIENT OF EVERY PARTICLE BY GRID INDEX, PARTICLES SPLIT OFF A CUT TAKE THEIR ORIGIN'S */
+    pub fn set_drag_with(&mut self, f: impl Fn(GridIdx) -> F) {
+        for i in 0..self.particles.len() {
+            let c = f(self.grid_idx(i));
+            self.particles.set_drag(i, c);
+        }
+    }
+
+    /* SET THE DRAG COEFFICIENT OF EVERY PARTICLE IN THE INCLUSIVE RECTANGLE BETWEEN TWO CORNERS */
+    pub fn set_drag_region(&mut self, ((x0, y0), (x1, y1)): (GridIdx, GridIdx), c: F) {
+        let (xs, ys) = (x0.min(x1)..=x0.max(x1), y0.min(y1)..=y0.max(y1));
+        for i in 0..self.particles.len() {
+            let (x, y) = self.grid_idx(i);
+            if xs.contains(&x) && ys.contains(&y) {
+                self.particles.set_drag(i, c);
+            }
+        }
+    }
+
+    pub fn drag(&self, idx: GridIdx) -> F {
+        self.particles.drag(self.particles.flatten(idx))
+    }
+
+    /* DRAG COEFFICIENT OF A PARTICLE WITH THE FACTOR OF THE WEIGHT MAP ON TOP */
+    fn drag_at(&self, i: I) -> F {
+        self.particles.drag(i) * self.weight_factor(WeightTarget::Drag, i)
+    }
+
+    pub fn weights(&self) -> &WeightMap {
+        &self.weights
+    }
+
+    /* MOVE THE WEIGHTS WITHIN radius OF p TOWARDS value BY amount AT p, FALLING OFF SMOOTHLY TO NOTHING */
+    /* AT radius, LIKE A BRUSH. MASSES AND STIFFNESSES BOUND TO THE MAP FOLLOW RIGHT AWAY */
+    pub fn paint_weights(&mut self, p: P, radius: F, value: F, amount: F) {
+        let p = self.to_local(p);
+        for i in 0..self.particles.len() {
+            let d = (self.particles.data[i] - p).norm();
+            if d >= radius {
+                continue;
+            }
+            let falloff = 1. - (d / radius).powi(2);
+            let weight = &mut self.weights.data[self.particles.origin(i)];
+            *weight += (value - *weight) * (amount * falloff * falloff).min(1.);
+        }
+        self.apply_weights();
+    }
+
+    /* REPLACE THE WEIGHT MAP, E.G. WITH ONE LOADED FROM A RECORDING, ONE WEIGHT PER GRID PARTICLE */
+    /* A MAP OF THE WRONG LENGTH, E.G. RECORDED AT ANOTHER RESOLUTION, IS REJECTED WITH GridError::LengthMismatch */
+    pub fn set_weights(&mut self, weights: &[F]) -> Result<(), ClothError> {
+        self.weights = Grid::with_dimensions(weights.to_vec(), self.weights.dimensions())?;
+        self.apply_weights();
+        Ok(())
+    }
+
+    /* MULTIPLY THE STIFFNESS OF EVERY CONSTRAINT WITH ITS MIDPOINT WITHIN radius OF p BY factor, KEPT */
+    /* BETWEEN MIN_PAINTED_STIFFNESS AND MAX_PAINTED_STIFFNESS. THE MULTIPLIERS SURVIVE reset */
+    pub fn scale_stiffness_near(&mut self, p: P, radius: F, factor: F) {
+        let p = self.to_local(p);
+        let particles = &self.particles.data;
+        let mut changed = vec![];
+        for c in self.constraints.iter_mut() {
+            if (nalgebra::center(&particles[c.p1], &particles[c.p2]) - p).norm() >= radius {
+                continue;
+            }
+            c.painted = (c.painted * factor).max(MIN_PAINTED_STIFFNESS).min(MAX_PAINTED_STIFFNESS);
+            changed.push((c.p1, c.p2, c.painted));
+        }
+        for (p1, p2, painted) in changed {
+            let key = self.paint_key(p1, p2);
+            self.painted.insert(key, painted);
+        }
+        /* THE CORRECTIONS OF THE LAST STEP WERE FOR THE OLD STIFFNESS */
+        self.clear_warm_start();
+    }
+
+    pub fn painted_stiffness(&self) -> &PaintedStiffness {
+        &self.painted
+    }
+
+    /* REPLACE THE PAINTED STIFFNESS, E.G. WITH THAT OF A RECORDING. KEYS WITHOUT A CONSTRAINT ARE KEPT */
+    /* IN CASE ONE IS ADDED BACK, BUT DO NOTHING */
+    pub fn set_painted_stiffness(&mut self, painted: PaintedStiffness) {
+        self.painted = painted;
+        self.apply_painted();
+        self.clear_warm_start();
+    }
+
+    fn paint_key(&self, p1: I, p2: I) -> (GridIdx, GridIdx) {
+        let (a, b) = (self.grid_idx(p1), self.grid_idx(p2));
+        (a.min(b), a.max(b))
+    }
+
+    fn apply_painted(&mut self) {
+        let keys: Vec<_> = self.constraints.iter().map(|c| self.paint_key(c.p1, c.p2)).collect();
+        for (c, key) in self.constraints.iter_mut().zip(keys) {
+            c.painted = self.painted.get(&key).copied().unwrap_or(1.);
+        }
+    }
+
+    /* FACTOR OF THE WEIGHT MAP ON target AT PARTICLE i, 1 UNLESS THE MAP IS BOUND TO target */
+    fn weight_factor(&self, target: WeightTarget, i: I) -> F {
+        match self.config.weight_binding.filter(|binding| binding.target == target) {
+            Some(binding) => binding.factor(self.weights.data[self.particles.origin(i)]),
+            None => 1.,
+        }
+    }
+
+    /* MASSES FROM particle_mass AND SPRING STIFFNESSES AND SHARES FROM THE WEIGHT MAP, AFTER THE MAP OR */
+    /* ITS BINDING CHANGED. DRAG IS SCALED WHERE IT IS USED */
+    fn apply_weights(&mut self) {
+        let factors = |target| (0..self.particles.len()).map(|i| self.weight_factor(target, i)).collect::<Vec<_>>();
+        let (mass, stiffness) = (factors(WeightTarget::Mass), factors(WeightTarget::Stiffness));
+        for (i, factor) in mass.iter().enumerate() {
+            self.particles.set_mass(i, self.config.particle_mass * factor);
+        }
+        self.constraints.iter_mut().for_each(|c| c.weigh(&mass, &stiffness));
+    }
+
+    pub fn set_row_fixed(&mut self, y: I, fixed: bool) {
+        let w = self.particles.width();
+        let anchor = if fixed { Anchor::Fixed } else { Anchor::Free };
+        (y * w..(y + 1) * w).for_each(|i| self.pin(i, anchor));
+        self.lra_dirty = true;
+    }
+
+    pub fn row_fixed(&self, y: I) -> bool {
+        let w = self.particles.width();
+        (y * w..(y + 1) * w).all(|i| self.particles.fixed(i))
+    }
+
+    /* GRID INDICES OF ALL FIXED PARTICLES, IN ROW-MAJOR ORDER */
+    pub fn pin_layout(&self) -> Vec<GridIdx> {
+        let w = self.particles.width();
+        (0..self.particles.grid_len())
+            .filter(|&i| self.particles.fixed(i))
+            .map(|i| (i % w, i / w))
+            .collect()
+    }
+
+    /* HOLD EVERY PARTICLE THE PREDICATE PICKS EXACTLY WHERE IT IS, ON TOP OF THE PINS, WHICH STAY AS THEY ARE */
+    /* PARTICLES SPLIT OFF ALONG A CUT ARE PICKED BY THE GRID INDEX THEY CAME FROM */
+    pub fn freeze_region(&mut self, predicate: impl Fn(GridIdx, &P) -> bool) {
+        let w = self.particles.width();
+        for i in 0..self.particles.len() {
+            let origin = self.particles.origin(i);
+            if predicate((origin % w, origin / w), &self.particles.data[i]) {
+                self.particles.set_frozen(i, true);
+            }
+        }
+    }
+
+    /* RELEASE ALL FROZEN PARTICLES AT REST, SO THEY DO NOT POP WITH THE MOVEMENT THEY HAD WHEN FROZEN */
+    pub fn unfreeze_all(&mut self) {
+        (0..self.particles.len()).for_each(|i| self.particles.set_frozen(i, false));
+    }
+
+    pub fn frozen(&self) -> bool {
+        (0..self.particles.len()).any(|i| self.particles.frozen(i))
+    }
+
+    /* REPLACE ALL PINS, RELEASING PREVIOUSLY PINNED PARTICLES WITHOUT A VELOCITY SPIKE */
+    pub fn apply_pin_layout(&mut self, pins: &[GridIdx]) {
+        for i in 0..self.particles.len() {
+            if self.particles.fixed(i) {
+                self.pin(i, Anchor::Free);
+                self.particles.reset_velocity(i);
+            }
+        }
+        let (w, h) = self.subdivisions();
+        for &(x, y) in pins.iter().filter(|(x, y)| *x < w && *y < h) {
+            self.pin(y * w + x, Anchor::Fixed);
+        }
+        self.lra_dirty = true;
+    }
+
+    /* ATTACHED PARTICLES ARE FIXED FOR THE SOLVE AND MOVED BY set_position */
+    pub fn attach(&mut self, entity: Entity, idx: GridIdx, local_offset: V) {
+        let i = self.particles.flatten(idx);
+        self.attachments.push(Attachment {
+            entity,
+            idx,
+            local_offset,
+            was: self.particles.anchor(i),
+        });
+        self.pin(i, Anchor::Fixed);
+        self.lra_dirty = true;
+    }
+
+    /* BIND A WHOLE ROW TO AN ENTITY, LIKE A CAPE ACROSS THE SHOULDERS, WITH ONE OFFSET PER PARTICLE */
+    pub fn bind_row(&mut self, y: I, entity: Entity, local_offsets: Vec<V>) {
+        let w = self.particles.width();
+        for (x, offset) in local_offsets.into_iter().enumerate().take(w) {
+            self.attach(entity, (x, y), offset);
+        }
+    }
+
+    /* RELEASED PARTICLES KEEP THE VELOCITY OF THE ENTITY, SEE set_position */
+    pub fn detach(&mut self, entity: Entity) {
+        for a in self.attachments.iter().filter(|a| a.entity == entity) {
+            let i = self.particles.flatten(a.idx);
+            self.pin(i, a.was);
+        }
+        self.attachments.retain(|a| a.entity != entity);
+        self.lra_dirty = true;
+    }
+
+    pub fn attachments(&self) -> &[Attachment] {
+        &self.attachments
+    }
+
+    /* KINEMATIC MOVE TO A LOCAL POSITION: old_p TRAILS ONE STEP BEHIND SO THE PARTICLE CARRIES THE */
+    /* VELOCITY OF ITS DRIVER */
+    pub fn set_position(&mut self, idx: GridIdx, p: P) -> Result<(), ClothError> {
+        let i = self.index_of(idx)?;
+        let displacement = p - self.particles.data[i];
+        self.particles.place(i, p, displacement);
+        self.moved(i);
+        self.heat_up(i);
+        self.mesh_dirty = true;
+        Ok(())
+    }
+
+    /* MOVE A PARTICLE BY HAND, CONTINUING WITH displacement PER STEP ONCE THE SOLVER HAS IT BACK */
+    pub fn place_particle(&mut self, idx: GridIdx, p: P, displacement: V) -> Result<(), ClothError> {
+        let i = self.index_of(idx)?;
+        self.particles.place(i, p, displacement);
+        self.moved(i);
+        self.heat_up(i);
+        self.mesh_dirty = true;
+        Ok(())
+    }
+
+    /* FLATTENED INDEX OF A GRID POSITION, WHICH THE Grid INDEXING WOULD WRAP INTO THE NEXT ROW OR PANIC ON */
+    fn index_of(&self, idx: GridIdx) -> Result<I, ClothError> {
+        match self.particles.contains(idx) {
+            true => Ok(self.particles.flatten(idx)),
+            false => Err(ClothError::IndexOutOfBounds { idx, subdivisions: self.subdivisions() }),
+        }
+    }
+
+    /* DISPLACE A PARTICLE BY FLATTENED INDEX, LEAVING FIXED PARTICLES IN PLACE */
+    pub fn offset_particle(&mut self, i: I, delta: V) {
+        self.particles.offset(i, delta);
+        self.moved(i);
+        self.mesh_dirty = true;
+    }
+
+    /* CHANGE THE MOVEMENT OVER THE LAST STEP BY FLATTENED INDEX, E.G. FOR FRICTION */
+    pub fn offset_displacement(&mut self, i: I, delta: V) {
+        self.particles.offset_displacement(i, delta);
+    }
+
+    /* OVERWRITE ALL POSITIONS, E.G. FROM A RECORDED FRAME */
+    /* A PAUSED REPLAY SETS THE SAME FRAME EVERY FRAME, WHICH LEAVES THE MESH CLEAN */
+    /* A FRAME WITH THE WRONG NUMBER OF POSITIONS, E.G. FROM ANOTHER RESOLUTION, IS NOT APPLIED AT ALL */
+    pub fn set_positions(&mut self, positions: &[P]) -> Result<(), ClothError> {
+        if positions.len() != self.particles.len() {
+            return Err(ClothError::SizeMismatch { expected: self.particles.len(), found: positions.len() });
+        }
+        self.broad_phase_slack = None;
+        self.quiet = true;
+        for (i, &p) in positions.iter().enumerate() {
+            self.mesh_dirty |= self.particles.data[i] != p;
+            self.particles.set_position(i, p);
+        }
+        Ok(())
+    }
+
+    pub fn reset_velocities(&mut self) {
+        (0..self.particles.len()).for_each(|i| self.particles.reset_velocity(i));
+        self.clear_warm_start();
+        self.contact_cache.clear();
+    }
+
+    /* PRE-ROLL BEFORE THE FIRST FRAME: steps ROUNDS OF max_iterations SOLVER ITERATIONS WITH NO FORCES AND */
+    /* NOTHING INTEGRATED, SO THE CLOTH STARTS OUT SELF-CONSISTENT INSTEAD OF SNAPPING INTO SHAPE ON SCREEN */
+    /* STOPS EARLY ONCE CONVERGED. NO VELOCITY IS LEFT OVER, EVERY old_p ENDS UP AT ITS p */
+    pub fn relax(&mut self, steps: I) {
+        let iterations = self.config.max_iterations.max(1);
+        for iter in 0..steps * iterations {
+            let max_correction = match self.config.solver {
+                SolverMode::GaussSeidel | SolverMode::Hierarchical { .. } => self.solve_gauss_seidel(iter % iterations),
+                SolverMode::Jacobi => self.solve_jacobi(),
+            };
+            if max_correction < self.config.tolerance {
+                break;
+            }
+        }
+        if let Some(max_strain) = self.config.max_strain {
+            self.limit_strain(max_strain);
+        }
+        self.reset_velocities();
+        self.mesh_dirty = true;
+    }
+
+    /* PRE-ROLL THAT ALSO LETS THE CLOTH FALL ONTO THE colliders, E.G. SO A TABLECLOTH STARTS OUT DRAPED */
+    /* steps FULL STEPS WITH NO WIND AND AT MOST SETTLE_DAMPING. AFTERWARDS THE VELOCITIES, EVENTS, IMPULSES */
+    /* ON COLLIDERS, STATS AND SIMULATED TIME START OVER AS IF THE CLOTH HAD BEEN BUILT LIKE THAT */
+    /* THE COLLIDERS ARE IN WORLD SPACE, SO SET THE FRAME FIRST */
+    pub fn settle_under_gravity(&mut self, steps: I, colliders: &[LayeredCollider]) {
+        let settling = ClothConfig {
+            wind: Wind { strength: 0., turbulence: 0., ..self.config.wind },
+            damping: self.config.damping.min(SETTLE_DAMPING),
+            auto_damping: None,
+            ..self.config.clone()
+        };
+        let config = std::mem::replace(&mut self.config, settling);
+        (0..steps).for_each(|_| self.step(colliders));
+        self.config = config;
+
+        self.reset_velocities();
+        self.events.clear();
+        self.collider_impulses.clear();
+        self.good_states.clear();
+        self.damping_tuner = DampingTuner::default();
+        self.stats = Stats::default();
+        self.time = 0.;
+        self.steps = 0;
+        self.mesh_dirty = true;
+    }
+
+    /* BUILDERS FOR A CLOTH THAT STARTS OUT RELAXED OR DRAPED, SEE relax AND settle_under_gravity */
+    pub fn relaxed(mut self, steps: I) -> Self {
+        self.relax(steps);
+        self
+    }
+
+    pub fn settled(mut self, steps: I, colliders: &[LayeredCollider]) -> Self {
+        self.settle_under_gravity(steps, colliders);
+        self
+    }
+
+    /* PUSH ALONG dir EVERY PARTICLE AHEAD OF origin AND WITHIN radius OF THE RAY, */
+    /* FALLING OFF SMOOTHLY TO ZERO AT radius FROM THE AXIS; BLOWN PIECES WAKE UP */
+    pub fn apply_force_along_ray(&mut self, origin: P, dir: V, radius: F, strength: F) {
+        self.disturbed = true;
+        let (origin, dir) = (self.to_local(origin), self.vector_to_local(dir));
+        let dir = match dir.try_normalize(F::EPSILON) {
+            Some(dir) => dir,
+            None => return,
+        };
+        let mut woken = vec![];
+        for i in 0..self.particles.len() {
+            let offset = self.particles.data[i] - origin;
+            let along = offset.dot(&dir);
+            let off_axis = (offset - dir * along).norm();
+            if along <= 0. || off_axis >= radius || self.particles.fixed(i) {
+                continue;
+            }
+            let falloff = 1. - (off_axis / radius).powi(2);
+            self.particles.add_force(i, dir * (strength * falloff * falloff));
+            if self.particles.asleep(i) {
+                woken.push(self.piece[i]);
+            }
+        }
+        woken.into_iter().for_each(|piece| self.wake(piece));
+    }
+
+    /* PARTICLE QUERIES TAKE WORLD POINTS LIKE THE REST OF THE INTERACTION API AND RETURN INDICES */
+    /* THAT flatten BACK TO THE SAME PARTICLE, ALSO FOR PARTICLES SPLIT OFF BY A CUT */
+    /* THEY SEARCH THE BROAD PHASE OF THE LAST STEP WIDENED BY ITS SLACK, OR SCAN EVERY PARTICLE */
+    /* PARTICLES THAT ARE NOT FINITE ARE SKIPPED, THEIR DISTANCE NEVER COMPARES AS NEARER */
+    pub fn nearest_particle(&self, p: P) -> Result<(GridIdx, F), ClothError> {
+        let local = self.to_local(p);
+        if !local.coords.iter().all(|c| c.is_finite()) {
+            return Err(ClothError::NonFinitePosition);
+        }
+        let distance = |i: I| (self.particles.data[i] - local).norm();
+        let closest = |best: (I, F), i: I| match distance(i) {
+            d if d < best.1 => (i, d),
+            _ => best,
+        };
+        if let Some(slack) = self.broad_phase_slack() {
+            /* ANY PARTICLE WITHIN r IS A CANDIDATE, SO A CANDIDATE WITHIN r IS THE NEAREST OF ALL */
+            let mut r = self.broad_phase.cell_size();
+            for _ in 0..NEAREST_ROUNDS {
+                let mut best = (0, F::INFINITY);
+                self.broad_phase.neighbors_of(&local, r + slack, &mut |i| best = closest(best, i));
+                if best.1 <= r {
+                    return Ok((self.particles.unflatten(best.0), best.1));
+                }
+                r *= 2.;
+            }
+        }
+        match (0..self.particles.len()).fold((0, F::INFINITY), closest) {
+            (i, d) if d.is_finite() => Ok((self.particles.unflatten(i), d)),
+            _ => Err(ClothError::NonFinitePosition),
+        }
+    }
+
+    pub fn particles_within_sphere(&self, center: P, radius: F) -> Vec<GridIdx> {
+        self.indices_within_sphere(center, radius).into_iter().map(|i| self.particles.unflatten(i)).collect()
+    }
+
+    /* THE BOX IS AXIS-ALIGNED IN THE WORLD, SO IT IS TESTED AGAINST THE WORLD POSITIONS */
+    pub fn particles_in_aabb(&self, min: P, max: P) -> Vec<GridIdx> {
+        let aabb = Aabb { min, max };
+        let inside = |i: I| aabb.intersects(&Aabb::around(self.to_world(self.particles.data[i]), 0.));
+        let mut found: Vec<I> = match self.broad_phase_slack() {
+            Some(slack) => {
+                let local = aabb.transformed(&self.frame.inverse()).inflated(slack);
+                let mut found = vec![];
+                self.broad_phase.in_box(&local, &mut |i| found.push(i));
+                found.retain(|&i| inside(i));
+                found
+            }
+            None => (0..self.particles.len()).filter(|&i| inside(i)).collect(),
+        };
+        found.sort_unstable();
+        found.into_iter().map(|i| self.particles.unflatten(i)).collect()
+    }
+
+    fn indices_within_sphere(&self, center: P, radius: F) -> Vec<I> {
+        let (center, r2) = (self.to_local(center), radius * radius);
+        let inside = |i: I| (self.particles.data[i] - center).norm_squared() <= r2;
+        match self.broad_phase_slack() {
+            Some(slack) => {
+                let mut found = vec![];
+                self.broad_phase.neighbors_of(&center, radius + slack, &mut |i| found.push(i));
+                found.retain(|&i| inside(i));
+                found.sort_unstable();
+                found
+            }
+            None => (0..self.particles.len()).filter(|&i| inside(i)).collect(),
+        }
+    }
+
+    /* THE BROAD PHASE IS ONLY USABLE WHILE IT STILL COVERS EVERY PARTICLE, E.G. NOT AFTER A CUT */
+    fn broad_phase_slack(&self) -> Option<F> {
+        self.broad_phase_slack.filter(|_| self.broad_phase.len() == self.particles.len())
+    }
+
+    /* WIDEN THE SLACK BY A PARTICLE MOVED OUTSIDE A STEP, SO QUERIES STILL FIND IT */
+    fn moved(&mut self, i: I) {
+        self.disturbed = true;
+        self.broad_phase_slack = match (self.broad_phase_slack, self.integrated.get(i)) {
+            (Some(slack), Some(q)) => Some(slack.max((self.particles.data[i] - q).norm())),
+            _ => None,
+        };
+    }
+
+    /* SET ALL POINTS WITHIN radius OF THE SELECTED POINT TO BE FIXED IN SPACE */
+    /* THE NEAREST POINT IS ALWAYS INCLUDED SO A TINY RADIUS PINS EXACTLY ONE */
+    pub fn set_fixed(&mut self, p: P, radius: F, fixed: bool) -> Result<(), ClothError> {
+        let nearest = self.particles.flatten(self.nearest_particle(p)?.0);
+        let mut chosen = self.indices_within_sphere(p, radius);
+        if !chosen.contains(&nearest) {
+            chosen.push(nearest);
+        }
+        match fixed {
+            true => chosen.into_iter().for_each(|i| self.pin(i, Anchor::Fixed)),
+            false => chosen.into_iter().for_each(|i| self.release(i)),
+        }
+        self.lra_dirty = true;
+        Ok(())
+    }
+
+    /* RELEASE EVERY FIXED PARTICLE, EXCEPT THOSE AN ENTITY DRIVES, FROM REST */
+    pub fn clear_fixed(&mut self) {
+        for i in (0..self.particles.len()).filter(|&i| self.particles.fixed(i) && !self.attached(i)) {
+            self.release(i);
+        }
+        self.lra_dirty = true;
+    }
+
+    /* PIN OR RELEASE ONE PARTICLE BY ITS GRID POSITION */
+    pub fn set_pinned(&mut self, idx: GridIdx, fixed: bool) -> Result<(), ClothError> {
+        let i = self.index_of(idx)?;
+        match fixed {
+            true => self.pin(i, Anchor::Fixed),
+            false => self.release(i),
+        }
+        self.lra_dirty = true;
+        Ok(())
+    }
+
+    pub fn fix_all(&mut self) {
+        (0..self.particles.len()).for_each(|i| self.pin(i, Anchor::Fixed));
+        self.lra_dirty = true;
+    }
+
+    /* A PINNED PARTICLE'S old_p IS WHEREVER IT WAS PINNED OR LAST PUT, SO WITHOUT RESETTING IT */
+    /* A PARTICLE RELEASED AFTER BEING DRAGGED AWAY WOULD BE FLUNG BACK WITH A HUGE VELOCITY */
+    fn release(&mut self, i: I) {
+        if self.particles.pinned(i) {
+            self.pin(i, Anchor::Free);
+            self.particles.reset_velocity(i);
+        }
+    }
+
+    fn attached(&self, i: I) -> bool {
+        self.attachments.iter().any(|a| self.particles.flatten(a.idx) == i)
+    }
+
+    /* FIXED PARTICLES WITHIN radius OF p THAT NO ENTITY DRIVES, WHICH A DRAG CAN PICK UP */
+    pub fn fixed_near(&self, p: P, radius: F) -> Vec<GridIdx> {
+        self.indices_within_sphere(p, radius)
+            .into_iter()
+            .filter(|&i| self.particles.fixed(i) && !self.attached(i))
+            .map(|i| self.particles.unflatten(i))
+            .collect()
+    }
+
+    /* THE PARTICLES THE SOLVER MOVES, E.G. FOR A SOFT DRAG TO TAKE ALONG WITHOUT PINNING THEM */
+    pub fn free_near(&self, p: P, radius: F) -> Vec<GridIdx> {
+        self.indices_within_sphere(p, radius)
+            .into_iter()
+            .filter(|&i| !self.particles.fixed(i))
+            .map(|i| self.particles.unflatten(i))
+            .collect()
+    }
+
+    pub fn update_mesh(&mut self, mesh: &mut Mesh) {
+        match self.mesh_stale {
+            true => self.fill_mesh(mesh),
+            false => self.render.update(&self.particles, mesh, self.config.normal_weighting),
+        }
+        self.mesh_dirty = false;
+    }
+
+    /* COPY THE VERTICES WORKED OUT BY THE LAST update_mesh INTO THE SUB-MESH OF REGION k, ONLY THE ONES */
+    /* ITS TRIANGLES USE */
+    fn write_region(&mut self, k: I, mesh: &mut Mesh) {
+        if let Some(region) = self.regions.get_mut(k) {
+            self.render.write_region(k, mesh, region.stale);
+            region.stale = false;
+        }
+    }
+
+    /* LOCAL-SPACE BOUNDS OF THE RENDER MESH AS OF THE LAST update_mesh, CONTAINING EVERY PARTICLE AT */
+    /* THAT TIME. MOVES BELOW MESH_EPSILON DO NOT UPDATE THE MESH, SO USE bounding_box FOR EXACT TESTS */
+    pub fn aabb(&self) -> &Aabb {
+        self.render.bounds()
+    }
+
+    /* WHETHER ANY PARTICLE MOVED SINCE THE LAST update_mesh, A PAUSED OR SETTLED CLOTH STAYS CLEAN */
+    pub fn mesh_dirty(&self) -> bool {
+        self.mesh_dirty
+    }
+
+    /* ADD A CUSTOM SPRING, A REST LENGTH OF None KEEPS THE CURRENT DISTANCE BETWEEN THE PARTICLES */
+    pub fn add_constraint(&mut self, p1: GridIdx, p2: GridIdx, rest: Option<F>, kind: ConstraintKind) -> ConstraintId {
+        let mut c = Constraint::new(p1, p2, kind, &self.particles);
+        if let Some(d) = rest {
+            c.d = d;
+        }
+        let id = self.constraints.insert(c);
+        self.shuffled.push(id);
+        self.by_kind[kind as I].push(id);
+        if kind == ConstraintKind::Structural {
+            self.constraints_changed();
+        }
+        if self.config.weight_binding.is_some() {
+            self.apply_weights();
+        }
+        id
+    }
+
+    /* REMOVING AN ALREADY REMOVED CONSTRAINT IS A NO-OP */
+    pub fn remove_constraint(&mut self, id: ConstraintId) {
+        if let Some(c) = self.constraints.remove(id) {
+            self.shuffled.retain(|&s| s != id);
+            self.by_kind[c.kind as I].retain(|&s| s != id);
+            if c.kind == ConstraintKind::Structural {
+                self.constraints_changed();
+            }
+        }
+    }
+
+    /* ALL CONSTRAINTS WITH THE GIVEN PARTICLE AS ONE OF THEIR END POINTS */
+    pub fn constraints_at(&self, idx: GridIdx) -> Result<impl Iterator<Item = ConstraintId> + '_, ClothError> {
+        let i = self.index_of(idx)?;
+        Ok(self.constraints.iter_with_ids().filter(move |(_, c)| c.p1 == i || c.p2 == i).map(|(id, _)| id))
+    }
+
+    /* GRID POSITION OF A PARTICLE, OR OF THE ONE IT WAS SPLIT OFF FROM */
+    fn grid_idx(&self, i: I) -> GridIdx {
+        let (o, w) = (self.particles.origin(i), self.particles.width());
+        (o % w, o / w)
+    }
+
+    /* FIX A PARTICLE, LET IT SLIDE ALONG A LINE OR PLANE, OR RELEASE IT */
+    pub fn set_anchor(&mut self, idx: GridIdx, anchor: Anchor) -> Result<(), ClothError> {
+        let i = self.index_of(idx)?;
+        self.pin(i, anchor);
+        self.lra_dirty = true;
+        Ok(())
+    }
+
+    /* EVERY CHANGE OF A PIN FROM THE PINNING API GOES THROUGH HERE TO BE REPORTED */
+    /* SLIDING COUNTS AS PINNED, MOVING BETWEEN RAILS OR TO A FULL PIN IS NOT REPORTED AGAIN */
+    fn pin(&mut self, i: I, anchor: Anchor) {
+        let was = self.particles.anchor(i);
+        if was != anchor {
+            if let Some(&piece) = self.piece.get(i) {
+                self.wake(piece);
+            }
+            /* A SLIDING ANCHOR PROJECTS THE PARTICLE ONTO ITS LINE OR PLANE */
+            self.particles.set_anchor(i, anchor);
+            self.moved(i);
+            self.mesh_dirty = true;
+            let idx = self.grid_idx(i);
+            match (was == Anchor::Free, anchor == Anchor::Free) {
+                (true, false) => self.events.push(ClothEvent::Pinned { idx }),
+                (false, true) => self.events.push(ClothEvent::Unpinned { idx }),
+                _ => {}
+            }
+        }
+    }
+
+    /* EVENTS SINCE THE LAST CALL, OLDEST FIRST */
+    pub fn drain_events(&mut self) -> impl Iterator<Item = ClothEvent> + '_ {
+        self.events.drain(..)
+    }
+
+    /* IMPULSES OF THE LAST STEP ON THE DYNAMIC COLLIDERS, WITH THEIR INDICES */
+    pub fn drain_collider_impulses(&mut self) -> impl Iterator<Item = (ColliderId, V)> + '_ {
+        self.collider_impulses.drain(..).enumerate().filter(|(_, j)| *j != V::zeros())
+    }
+
+    /* TEAR THE CLOTH ALONG A LINE: side CLASSIFIES GRID POSITIONS AS BEFORE, ON OR PAST THE CUT */
+    /* PARTICLES ON THE CUT ARE SPLIT IN TWO SO EACH SIDE HAS ITS OWN EDGE: CONSTRAINTS AND */
+    /* TRIANGLES PAST THE CUT ARE REWIRED TO THE COPIES, THOSE ALONG IT ARE DUPLICATED FOR BOTH */
+    /* SIDES AND THOSE ACROSS IT ARE REMOVED. COPIES ARE CLASSIFIED BY THE PARTICLE THEY CAME FROM, */
+    /* SO CUTTING THROUGH THE SAME PARTICLES TWICE SPLITS THEM AGAIN. THE NEXT update_mesh REFILLS THE MESH */
+    pub fn cut(&mut self, side: impl Fn(GridIdx) -> Ordering) {
+        use Ordering::*;
+        if self.welded.is_some() {
+            warn!("a cloth built from a mesh cannot be cut, its render vertices would not follow the split particles");
+            return;
+        }
+        let w = self.particles.width();
+        let sides: Vec<Ordering> = (0..self.particles.len())
+            .map(|i| self.particles.origin(i))
+            .map(|o| side((o % w, o / w)))
+            .collect();
+
+        /* COPIES ARE ONLY MADE FOR PARTICLES THAT SOMETHING PAST THE CUT IS ATTACHED TO */
+        let mut copies: Vec<Option<I>> = vec![None; sides.len()];
+        let particles = &mut self.particles;
+        let mut copy = |i: I| *copies[i].get_or_insert_with(|| particles.split(i));
+
+        let across: Vec<ConstraintId> = self
+            .constraints
+            .iter_with_ids()
+            .filter(|(_, c)| matches!((sides[c.p1], sides[c.p2]), (Less, Greater) | (Greater, Less)))
+            .map(|(id, _)| id)
+            .collect();
+        let mut along = vec![];
+        for c in self.constraints.iter_mut() {
+            match (sides[c.p1], sides[c.p2]) {
+                (Equal, Equal) => along.push(Constraint {
+                    p1: copy(c.p1),
+                    p2: copy(c.p2),
+                    d: c.d,
+                    kind: c.kind,
+                    weave: c.weave,
+                    resistance: c.resistance,
+                    accumulated: V::zeros(),
+                    plastic: c.plastic,
+                    yielding: c.yielding,
+                    scale: c.scale,
+                    share: c.share,
+                    painted: c.painted,
+                }),
+                (Equal, Greater) => c.p1 = copy(c.p1),
+                (Greater, Equal) => c.p2 = copy(c.p2),
+                _ => {}
+            }
+        }
+
+        /* A TRIANGLE BELONGS TO THE SIDE OF ITS VERTICES OFF THE CUT */
+        self.triangles.retain(|t| !(t.v.iter().any(|&i| sides[i] == Less) && t.v.iter().any(|&i| sides[i] == Greater)));
+        for t in self.triangles.iter_mut().filter(|t| t.v.iter().any(|&i| sides[i] == Greater)) {
+            for i in t.v.iter_mut().filter(|i| sides[**i] == Equal) {
+                *i = copy(*i);
+            }
+        }
+
+        for id in across {
+            if let Some(c) = self.constraints.get(id) {
+                let (p1, p2) = (self.grid_idx(c.p1), self.grid_idx(c.p2));
+                self.events.push(ClothEvent::Torn { p1, p2 });
+            }
+            self.remove_constraint(id);
+        }
+        for c in along {
+            let kind = c.kind;
+            let id = self.constraints.insert(c);
+            self.shuffled.push(id);
+            self.by_kind[kind as I].push(id);
+        }
+        self.constraints_changed();
+
+        self.torn = true;
+        self.render = self.render_grid(1);
+        self.mesh_stale = true;
+    }
+
+    /* RIP THE CLOTH AROUND p: EVERY CONSTRAINT WITH ITS MIDPOINT WITHIN radius LOSES UP TO amount */
+    /* OF ITS RESISTANCE, FALLING OFF LINEARLY TO NOTHING AT radius, AND TEARS ONCE IT IS USED UP */
+    /* SO KEEPING AT IT IN ONE SPOT OPENS A HOLE THAT GROWS OUT TOWARDS radius */
+    /* TRIANGLES ACROSS A TORN EDGE ARE REMOVED, PARTICLES ARE NEVER SPLIT AND PINS ARE LEFT ALONE */
+    pub fn weaken_near(&mut self, p: P, radius: F, amount: F) {
+        let p = self.to_local(p);
+        let particles = &self.particles.data;
+        let mut torn = vec![];
+        for (id, c) in self.constraints.iter_mut_with_ids() {
+            let d = (nalgebra::center(&particles[c.p1], &particles[c.p2]) - p).norm();
+            if d >= radius {
+                continue;
+            }
+            c.resistance -= amount * (1. - d / radius);
+            if c.resistance < 0. {
+                torn.push(id);
+            }
+        }
+        if torn.is_empty() {
+            return;
+        }
+
+        /* CONNECTIVITY IS UPDATED ONCE FOR THE WHOLE BATCH RATHER THAN PER CONSTRAINT */
+        let mut edges = vec![];
+        for &id in &torn {
+            if let Some(c) = self.constraints.remove(id) {
+                let (p1, p2) = (self.grid_idx(c.p1), self.grid_idx(c.p2));
+                self.events.push(ClothEvent::Torn { p1, p2 });
+                edges.push((c.p1, c.p2));
+            }
+        }
+        self.shuffled.retain(|id| !torn.contains(id));
+        self.by_kind.iter_mut().for_each(|ids| ids.retain(|id| !torn.contains(id)));
+        self.triangles.retain(|t| !edges.iter().any(|(a, b)| t.v.contains(a) && t.v.contains(b)));
+        self.constraints_changed();
+
+        self.torn = true;
+        self.closed = false;
+        self.render = self.render_grid(1);
+        self.mesh_stale = true;
+    }
+
+    pub fn torn(&self) -> bool {
+        self.torn
+    }
+
+    pub fn closed(&self) -> bool {
+        self.closed
+    }
+
+    /* PARTICLE INDICES OF EVERY TRIANGLE OF THE SURFACE, FOLLOWING TEARS */
+    pub fn faces(&self) -> impl Iterator<Item = [I; 3]> + '_ {
+        self.triangles.iter().map(|f| f.v)
+    }
+
+    /* CURRENT ENCLOSED VOLUME, ONLY MEANINGFUL FOR A CLOSED CLOTH */
+    pub fn volume(&self) -> F {
+        volume::signed_volume(&self.particles.data, self.triangles.iter().map(|f| f.v))
+    }
+
+    /* BOX IN THE WORLD AROUND ALL PARTICLES, INCLUDING THOSE SPLIT OFF BY CUTS */
+    /* NEAREST HIT OF A WORLD-SPACE RAY ON THE TRIANGLES AT THE CURRENT PARTICLE POSITIONS, UNLIKE THE */
+    /* PICKING PLUGIN WHICH SEES THE MESH OF AN EARLIER FRAME. RETURNS THE CORNER OF THE HIT TRIANGLE */
+    /* NEAREST THE HIT IN THE GRID, THE HIT POINT IN THE WORLD AND ITS DISTANCE ALONG dir IN UNITS OF ITS */
+    /* LENGTH. THE BARYCENTRIC WEIGHTS OF THE HIT PLACE IT IN THE GRID, SO A STRETCHED OR SHEARED TRIANGLE */
+    /* PICKS THE SAME CORNER AS A FLAT ONE, AND THE CORNER IS NEVER A PARTICLE THAT IS ONLY CLOSER IN SPACE */
+    /* ON A FOLD BEHIND THE HIT. A TIE ON AN EDGE GOES TO THE LOWEST GRID INDEX */
+    pub fn raycast(&self, origin: P, dir: V) -> Option<(GridIdx, P, F)> {
+        let (origin, dir) = (self.to_local(origin), self.vector_to_local(dir));
+        let particles = &self.particles.data;
+        /* ONE BOX AROUND THE WHOLE CLOTH SKIPS THE TRIANGLES FOR RAYS THAT MISS IT */
+        let radius = self.config.thickness / 2.;
+        if !Aabb::from_points(self.particles.as_slice()).inflated(radius).hits_ray(origin, dir) {
+            return None;
+        }
+        let faces = self.triangles.iter().filter_map(|&Face { v: [a, b, c], .. }| {
+            let hit = ray_triangle(origin, dir, [particles[a], particles[b], particles[c]]);
+            hit.map(|(t, [wa, wb, wc])| (t, vec![(a, wa), (b, wb), (c, wc)]))
+        });
+        /* A ROPE HAS NO FACES, ITS SEGMENTS ARE HIT AS TUBES AS THICK AS THE ROPE */
+        let rope = self.is_rope();
+        let segments = self
+            .constraints
+            .iter()
+            .filter(|c| rope && c.kind == ConstraintKind::Structural)
+            .filter_map(|c| {
+                let hit = ray_segment(origin, dir, particles[c.p1], particles[c.p2], radius);
+                hit.map(|(t, u)| (t, vec![(c.p1, 1. - u), (c.p2, u)]))
+            });
+        let (t, v) = faces.chain(segments).min_by(|(t1, _), (t2, _)| t1.partial_cmp(t2).unwrap_or(Equal))?;
+        let hit = origin + dir * t;
+        let corners: Vec<_> = v.iter().map(|&(i, w)| (self.grid_idx(i), w)).collect();
+        let (gx, gy) = corners.iter().fold((0., 0.), |(gx, gy), &((x, y), w)| (gx + x as F * w, gy + y as F * w));
+        let distance = |(x, y): GridIdx| (x as F - gx).powi(2) + (y as F - gy).powi(2);
+        let nearest = corners.iter().map(|&(idx, _)| distance(idx)).fold(F::INFINITY, F::min);
+        let corner = corners
+            .iter()
+            .map(|&(idx, _)| idx)
+            .filter(|&idx| distance(idx) <= nearest + PICK_TIE)
+            .min_by_key(|&(x, y)| (y, x))?;
+        Some((corner, self.to_world(hit), t))
+    }
+
+    pub fn bounding_box(&self) -> Aabb {
+        let points: Vec<_> = self.particles.iter().map(|&p| self.to_world(p)).collect();
+        Aabb::from_points(&points)
+    }
+
+    /* STRUCTURAL CONNECTIVITY CHANGED SO GEODESIC DISTANCES HAVE TO BE RECOMPUTED */
+    /* AND THE CLOTH MAY HAVE COME APART INTO SEPARATE PIECES */
+    fn constraints_changed(&mut self) {
+        #[cfg(feature = "gpu")]
+        self.gpu.iter_mut().for_each(GpuSolver::invalidate);
+        self.neighbors = structural_neighbors(&self.constraints, &self.particles);
+        self.lra_dirty = true;
+        self.clear_warm_start();
+        self.find_pieces();
+    }
+
+    /* CONNECTED COMPONENTS OF THE CONSTRAINT GRAPH BY UNION-FIND, EVERY PIECE STARTS AWAKE */
+    fn find_pieces(&mut self) {
+        fn root(parent: &mut [I], mut i: I) -> I {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+        let n = self.particles.len();
+        let mut parent: Vec<I> = (0..n).collect();
+        for c in self.constraints.iter() {
+            let (a, b) = (root(&mut parent, c.p1), root(&mut parent, c.p2));
+            parent[a] = b;
+        }
+
+        let mut labels = vec![None; n];
+        self.pieces.clear();
+        self.piece.clear();
+        for i in 0..n {
+            let r = root(&mut parent, i);
+            let label = *labels[r].get_or_insert(self.pieces.len());
+            if label == self.pieces.len() {
+                self.pieces.push(Piece::default());
+            }
+            self.piece.push(label);
+            self.particles.set_asleep(i, false);
+        }
+    }
+
+    fn wake(&mut self, piece: I) {
+        if let Some(p) = self.pieces.get_mut(piece).filter(|p| p.asleep) {
+            p.asleep = false;
+            p.still_steps = 0;
+            for (i, &k) in self.piece.iter().enumerate() {
+                if k == piece {
+                    self.particles.set_asleep(i, false);
+                }
+            }
+        }
+    }
+
+    /* ONCE TORN, UNPINNED PIECES AT REST OR FALLEN BELOW THE KILL HEIGHT STOP BEING SIMULATED */
+    /* THEY ONLY WAKE UP WHEN PINNED OR CUT AGAIN */
+    fn update_pieces(&mut self) {
+        if self.pieces.len() < 2 {
+            return;
+        }
+        let kill_height = self.config.kill_height;
+        let mut states = vec![(0., false, false); self.pieces.len()];
+        for i in 0..self.particles.len() {
+            let (speed, pinned, above) = &mut states[self.piece[i]];
+            *speed = self.particles.velocity(i).norm().max(*speed);
+            *pinned |= self.particles.fixed(i);
+            *above |= self.to_world(self.particles.data[i]).y >= kill_height;
+        }
+
+        let mut sleepers = vec![];
+        for (k, (piece, &(speed, pinned, above))) in self.pieces.iter_mut().zip(&states).enumerate() {
+            if piece.asleep || pinned {
+                piece.still_steps = 0;
+                continue;
+            }
+            piece.still_steps = if speed < SLEEP_SPEED { piece.still_steps + 1 } else { 0 };
+            if piece.still_steps >= SLEEP_STEPS || !above {
+                piece.asleep = true;
+                sleepers.push(k);
+            }
+        }
+        for (i, k) in self.piece.iter().enumerate() {
+            if sleepers.contains(k) {
+                self.particles.set_asleep(i, true);
+            }
+        }
+    }
+
+    /* NUMBER OF SEPARATE PIECES THE CLOTH HAS BEEN TORN INTO, AND HOW MANY OF THEM ARE ASLEEP */
+    pub fn pieces(&self) -> (I, I) {
+        (self.pieces.len(), self.pieces.iter().filter(|p| p.asleep).count())
+    }
+
+    pub fn constraint_count(&self) -> I {
+        self.constraints.iter().count()
+    }
+
+    /* LARGEST RELATIVE DEVIATION OF ANY CONSTRAINT FROM ITS REST LENGTH */
+    pub fn max_strain(&self) -> F {
+        self.constraints
+            .iter()
+            .map(|c| c.strain(&self.particles))
+            .fold(0., F::max)
+    }
+
+    /* AVERAGE STRAIN OVER ALL CONSTRAINTS AFTER THE LAST SOLVE */
+    pub fn mean_strain(&self) -> F {
+        let total: F = self.strains.iter().map(|&(strain, _, _)| strain).sum();
+        total / self.strains.len().max(1) as F
+    }
+
+    /* config.damping, OR WHAT THE TUNER MAKES OF IT WITH auto_damping. THE WIND, AN ATTRACTOR, A FORCE FIELD, */
+    /* OR A FORCE, DRAG OR PIN SINCE THE LAST STEP HOLD THE TUNER AT config.damping */
+    fn tuned_damping(&mut self) -> F {
+        let disturbed = std::mem::take(&mut self.disturbed);
+        let settings = match self.config.auto_damping {
+            Some(settings) => settings,
+            None => return self.config.damping,
+        };
+        let pushed = self.config.wind.strength != 0. || !self.attractors.is_empty() || !self.fields.is_empty();
+        let energy = self.mean_energy();
+        self.damping_tuner.update(&settings, self.config.damping, energy, disturbed || pushed)
+    }
+
+    /* MEAN KINETIC ENERGY, AND KINETIC PLUS POTENTIAL ENERGY IN THE GRAVITY, PER MOVING PARTICLE */
+    pub fn mean_energy(&self) -> (F, F) {
+        let gravity = self.vector_to_local(self.config.gravity);
+        let moving: Vec<_> = (0..self.particles.len()).filter(|&i| self.particles.inv_mass(i) > 0.).collect();
+        let potential: F =
+            moving.iter().map(|&i| -gravity.dot(&self.particles.data[i].coords) / self.particles.inv_mass(i)).sum();
+        let count = moving.len().max(1) as F;
+        let kinetic = self.kinetic_energy() / count;
+        (kinetic, kinetic + potential / count)
+    }
+
+    /* SUM OF HALF m v^2 OVER THE MOVING PARTICLES, WITH THE VELOCITY TAKEN FROM THE LAST STEP */
+    pub fn kinetic_energy(&self) -> F {
+        (0..self.particles.len())
+            .filter(|&i| self.particles.inv_mass(i) > 0.)
+            .map(|i| 0.5 * self.particles.velocity(i).norm_squared() / self.particles.inv_mass(i))
+            .sum()
+    }
+
+    pub fn in_contact(&self, i: I) -> bool {
+        self.in_contact.get(i).copied().unwrap_or(false)
+    }
+
+    /* NUMBER OF PARTICLES TOUCHING A COLLIDER AFTER THE LAST STEP */
+    pub fn contacts(&self) -> I {
+        self.in_contact.iter().filter(|&&c| c).count()
+    }
+
+    /* LARGEST STRAIN OF THE CONSTRAINTS AT EACH GRID POSITION AFTER THE LAST SOLVE */
+    /* TORN COPIES OF A PARTICLE COUNT FOR ITS ORIGINAL POSITION */
+    pub fn grid_strains(&self, out: &mut Vec<F>) {
+        out.clear();
+        out.resize(self.particles.grid_len(), 0.);
+        for &(strain, p1, p2) in &self.strains {
+            for &i in &[p1, p2] {
+                let o = self.particles.origin(i);
+                out[o] = out[o].max(strain);
+            }
+        }
+    }
+
+    /* END POINTS OF THE k MOST STRAINED CONSTRAINTS AFTER THE LAST SOLVE, AT LEAST threshold, */
+    /* MOST STRAINED FIRST, USING LAZYSORT SO ONLY THE TOP k ARE FULLY SORTED */
+    pub fn most_strained(&self, k: I, threshold: F) -> Vec<(F, P, P)> {
+        let p = &self.particles.data;
+        self.strains
+            .iter()
+            .filter(|(strain, _, _)| *strain >= threshold)
+            .map(|&(strain, p1, p2)| (strain, self.to_world(p[p1]), self.to_world(p[p2])))
+            .sorted_by(|(a, _, _), (b, _, _)| b.partial_cmp(a).unwrap_or(Equal))
+            .take(k)
+            .collect()
+    }
+
+    /* WORLD END POINTS OF THE CONSTRAINTS PAINTED STIFFER THAN THEY WERE BUILT, OR SOFTER */
+    pub fn painted_segments(&self, stiffer: bool) -> impl Iterator<Item = (P, P)> + '_ {
+        self.constraints
+            .iter()
+            .filter(move |c| c.painted != 1. && (c.painted > 1.) == stiffer)
+            .map(move |c| (self.to_world(self.particles.data[c.p1]), self.to_world(self.particles.data[c.p2])))
+    }
+
+    /* WORLD END POINTS OF ALL CONSTRAINTS OF THE GIVEN KIND, FOR DEBUG WIREFRAME RENDERING */
+    pub fn constraint_segments(&self, kind: ConstraintKind) -> impl Iterator<Item = (P, P)> + '_ {
+        self.constraints
+            .iter()
+            .filter(move |c| c.kind == kind)
+            .map(move |c| (self.to_world(self.particles.data[c.p1]), self.to_world(self.particles.data[c.p2])))
+    }
+}
+
+/* A ClothSim AS A BEVY COMPONENT, WITH THE MESH ASSETS IT IS DRAWN WITH. IT DEREFERENCES TO THE ClothSim */
+/* SO ANYTHING THAT ONLY READS OR STEPS THE SIMULATION TAKES EITHER */
+pub struct Cloth {
+    sim: ClothSim,
+    pub mesh_handle: Handle<Mesh>,
+    /* THE SUB-MESH OF EACH TEXTURE REGION, BY INDEX */
+    region_meshes: Vec<Handle<Mesh>>,
+    /* MATERIALS MADE FOR THIS CLOTH ALONE, UNLIKE THE SHARED ONES OF ClothMaterials */
+    materials: Vec<Handle<StandardMaterial>>,
+}
+
+impl From<ClothSim> for Cloth {
+    fn from(sim: ClothSim) -> Self {
+        Cloth { sim, mesh_handle: Handle::default(), region_meshes: Vec::new(), materials: Vec::new() }
+    }
+}
+
+impl Deref for Cloth {
+    type Target = ClothSim;
+
+    fn deref(&self) -> &ClothSim {
+        &self.sim
+    }
+}
+
+impl DerefMut for Cloth {
+    fn deref_mut(&mut self) -> &mut ClothSim {
+        &mut self.sim
+    }
+}
+
+impl Cloth {
+    /* THE SIMULATION ALONE WITH THE DEFAULT CONFIG, FOR TESTS */
+    #[cfg(test)]
+    pub fn build_sim(width: F, height: F, subdivisions: GridIdx) -> Result<ClothSim, ClothError> {
+        ClothSim::new(width, height, subdivisions, ClothConfig::default())
+    }
+
+    pub fn new(width: F, height: F, subdivisions: GridIdx, config: ClothConfig) -> Result<Self, ClothError> {
+        ClothSim::new(width, height, subdivisions, config).map(Cloth::from)
+    }
+
+    pub fn new_rope(length: F, segments: I, config: ClothConfig) -> Result<Self, ClothError> {
+        ClothSim::new_rope(length, segments, config).map(Cloth::from)
+    }
+
+    pub fn from_mesh(mesh: &Mesh, config: ClothConfig) -> Result<Self, ClothError> {
+        ClothSim::from_mesh(mesh, config).map(Cloth::from)
+    }
+
+    /* THE MESH ASSET KEPT IN SYNC BY update_meshes, rebuild AND set_render_subdivision */
+    pub fn attach_mesh(&mut self, handle: Handle<Mesh>) {
+        self.mesh_handle = handle;
+    }
+
+    /* THE MESH ASSET OF REGION k, KEPT IN SYNC BY update_meshes */
+    pub fn attach_region_mesh(&mut self, k: I, handle: Handle<Mesh>) {
+        if k < self.sim.regions.len() {
+            self.region_meshes.resize(self.sim.regions.len(), Handle::default());
+            self.region_meshes[k] = handle;
+            self.sim.regions[k].stale = true;
+        }
+    }
+
+    /* A MATERIAL ONLY THIS CLOTH USES, REMOVED ALONG WITH IT BY despawn */
+    pub fn own_material(&mut self, handle: Handle<StandardMaterial>) {
+        self.materials.push(handle);
+    }
+
+    /* REMOVE THE CLOTH ENTITY AND THE ENTITIES OF ITS REGIONS TOGETHER WITH THEIR MESH ASSETS AND THE */
+    /* MATERIALS IT OWNS, WHICH WOULD OTHERWISE LEAK */
+    pub fn despawn(
+        &self,
+        entity: Entity,
+        commands: &mut Commands,
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<StandardMaterial>,
+    ) {
+        meshes.remove(&self.mesh_handle);
+        self.region_meshes.iter().for_each(|handle| {
+            meshes.remove(handle);
+        });
+        self.materials.iter().for_each(|handle| {
+            materials.remove(handle);
+        });
+        commands.despawn_recursive(entity);
+    }
+
+    /* regenerate, REFILLING THE MESH ASSET RIGHT AWAY */
+    pub fn rebuild(
+        &mut self,
+        subdivisions_x: I,
+        subdivisions_y: I,
+        meshes: &mut Assets<Mesh>,
+    ) -> Result<(), ClothError> {
+        self.sim.regenerate(subdivisions_x, subdivisions_y)?;
+        let mesh = meshes.get_mut(&self.mesh_handle).ok_or(ClothError::MeshMissing)?;
+        self.sim.fill_mesh(mesh);
+        Ok(())
+    }
+
+    /* START OVER FROM THE POSE AND PIN PRESET AT THE CURRENT RESOLUTION */
+    pub fn reset(&mut self, meshes: &mut Assets<Mesh>) -> Result<(), ClothError> {
+        let (w, h) = self.subdivisions();
+        self.rebuild(w, h, meshes)
+    }
+
+    /* SWITCH THE RENDER MESH TO A FINER GRID, SPLINE-INTERPOLATED FROM THE PARTICLES */
+    pub fn set_render_subdivision(&mut self, factor: I, meshes: &mut Assets<Mesh>) {
+        self.sim.config.render_subdivision = factor;
+        self.sim.render = self.sim.render_grid(factor);
+        if let Some(mesh) = meshes.get_mut(&self.mesh_handle) {
+            self.sim.fill_mesh(mesh);
+        }
+    }
+
+    /* update_mesh FOR THE MESH ASSET AND THE SUB-MESH OF EVERY REGION. THE VERTICES ARE WORKED OUT ONCE */
+    /* AND COPIED INTO EACH SUB-MESH */
+    pub fn update_meshes(&mut self, meshes: &mut Assets<Mesh>) -> Result<(), ClothError> {
+        let mesh = meshes.get_mut(&self.mesh_handle).ok_or(ClothError::MeshMissing)?;
+        self.sim.update_mesh(mesh);
+        for k in 0..self.sim.regions.len() {
+            let handle = self.region_meshes.get(k).ok_or(ClothError::MeshMissing)?;
+            let mesh = meshes.get_mut(handle).ok_or(ClothError::MeshMissing)?;
+            self.sim.write_region(k, mesh);
+        }
+        Ok(())
+    }
+
+    /* ClothSim::cut, REFILLING THE MESH ASSET RIGHT AWAY */
+    pub fn cut(&mut self, side: impl Fn(GridIdx) -> Ordering, meshes: &mut Assets<Mesh>) {
+        self.sim.cut(side);
+        if let Some(mesh) = meshes.get_mut(&self.mesh_handle) {
+            self.sim.fill_mesh(mesh);
+        }
+    }
+
+    /* ClothSim::weaken_near, REFILLING THE MESH ASSET RIGHT AWAY */
+    pub fn weaken_near(&mut self, p: P, radius: F, amount: F, meshes: &mut Assets<Mesh>) {
+        self.sim.weaken_near(p, radius, amount);
+        if let Some(mesh) = meshes.get_mut(&self.mesh_handle) {
+            self.sim.fill_mesh(mesh);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::mesh::{Indices, VertexAttributeValues};
+
+    use super::*;
+    use crate::{
+        collider::{Collider, Colliders},
+        config::{AutoDamping, KindIterations, SpringDamping},
+        grid::GridError,
+    };
+
+    // RADIUS OF THE SPHERE THE CONTACT TESTS DROP A CLOTH ONTO, AND THE SIZE, PARTICLES ALONG EACH SIDE AND DROP
+    // HEIGHT OF THAT CLOTH
+    const CONTACT_RADIUS: F = 2.;
+    const CONTACT_SIZE: F = 6.;
+    const CONTACT_SUBDIVISIONS: I = 20;
+    const CONTACT_DROP: F = 3.;
+
+    fn free_cloth(subdivisions: GridIdx) -> ClothSim {
+        let config = ClothConfig { pin_preset: PinPreset::Free, ..ClothConfig::default() };
+        ClothSim::new(2., 2., subdivisions, config).unwrap()
+    }
+
+    /* THE DEFAULT CLOTH FALLS UNDER GRAVITY WITHOUT BLOWING UP, WHILE ITS PINS HOLD */
+    #[test]
+    fn steps_without_bevy() {
+        let mut cloth = Cloth::build_sim(3., 3., (30, 30)).unwrap();
+        let start = cloth.particles.data.clone();
+        (0..100).for_each(|_| cloth.step(&[]));
+        let positions = &cloth.particles.data;
+        assert!(positions.iter().all(|p| p.coords.iter().all(|x| x.is_finite())));
+        let pinned: Vec<_> = (0..positions.len()).filter(|&i| cloth.particles.fixed(i)).collect();
+        assert!(!pinned.is_empty());
+        assert!(pinned.iter().all(|&i| positions[i] == start[i]));
+        assert!((0..positions.len()).any(|i| positions[i].y < start[i].y));
+    }
+
+    /* ONCE WARMED UP, STEPPING ONTO A FLOOR ALLOCATES NOTHING: THE SCRATCH BUFFERS KEEP THEIR CAPACITY */
+    #[test]
+    fn scratch_buffers_stop_growing() {
+        let capacities = |cloth: &ClothSim| {
+            vec![
+                cloth.strains.capacity(),
+                cloth.corrections.capacity(),
+                cloth.frictions.capacity(),
+                cloth.integrated.capacity(),
+                cloth.step_start.capacity(),
+                cloth.volume_gradients.capacity(),
+                cloth.triangle_boxes.capacity(),
+                cloth.wind_shelter.capacity(),
+                cloth.candidates.capacity(),
+                cloth.edge_candidates.capacity(),
+                cloth.edge_hits.capacity(),
+                cloth.boost_region.capacity(),
+                cloth.boosted.capacity(),
+            ]
+        };
+        let mut cloth = Cloth::build_sim(4., 4., (40, 40)).unwrap();
+        let mut colliders = Colliders::default();
+        let floor = cloth.bounding_box().min.y - 0.5;
+        colliders.add(Collider::Plane { point: P::new(0., floor, 0.), normal: V::y() });
+        (0..50).for_each(|_| cloth.step(&colliders.0));
+        let warm = capacities(&cloth);
+        (0..200).for_each(|_| cloth.step(&colliders.0));
+        assert_eq!(capacities(&cloth), warm);
+    }
+
+    /* A WEIGHT MAP OF THE WRONG LENGTH IS REJECTED WITH THE SHAPE IT WAS MEANT FOR */
+    #[test]
+    fn weight_map_restore() {
+        let mut cloth = free_cloth((4, 3));
+        let expected = ClothError::Grid(GridError::LengthMismatch { dimensions: (4, 3), len: 11 });
+        assert_eq!(cloth.set_weights(&[0.5; 11]), Err(expected));
+        assert_eq!(cloth.set_weights(&[0.5; 12]), Ok(()));
+    }
+
+    /* WHETHER despawn_on_request DESPAWNS EVERY CLOTH ON THE NEXT UPDATE */
+    #[derive(Default)]
+    struct DespawnRequested(bool);
+
+    fn spawn_owning(
+        mut commands: Commands,
+        mut meshes: ResMut<Assets<Mesh>>,
+        mut materials: ResMut<Assets<StandardMaterial>>,
+    ) {
+        let mut cloth = Cloth::from(free_cloth((3, 3)));
+        let mesh = meshes.add(cloth.build_mesh());
+        cloth.attach_mesh(mesh.clone());
+        cloth.own_material(materials.add(Color::WHITE.into()));
+        commands.spawn((mesh, cloth));
+    }
+
+    fn despawn_on_request(
+        mut commands: Commands,
+        requested: Res<DespawnRequested>,
+        mut meshes: ResMut<Assets<Mesh>>,
+        mut materials: ResMut<Assets<StandardMaterial>>,
+        cloths: Query<(Entity, &Cloth)>,
+    ) {
+        if requested.0 {
+            for (entity, cloth) in cloths.iter() {
+                cloth.despawn(entity, &mut commands, &mut meshes, &mut materials);
+            }
+        }
+    }
+
+    /* A DESPAWNED CLOTH TAKES ITS MESH AND THE MATERIALS IT OWNS ALONG, SO RESPAWNING DOES NOT LEAK */
+    #[test]
+    fn despawn_removes_the_assets() {
+        let mut builder = App::build();
+        builder
+            .add_plugin(bevy::core::CorePlugin::default())
+            .add_plugin(bevy::asset::AssetPlugin::default())
+            .add_asset::<Mesh>()
+            .add_asset::<StandardMaterial>()
+            .init_resource::<DespawnRequested>()
+            .add_startup_system(spawn_owning.system())
+            .add_system(despawn_on_request.system());
+        let mut app = builder.app;
+        let counts = |app: &App| {
+            let meshes = app.resources.get::<Assets<Mesh>>().unwrap().len();
+            let materials = app.resources.get::<Assets<StandardMaterial>>().unwrap().len();
+            let cloths = app.world.query::<&Cloth>().count();
+            (meshes, materials, cloths)
+        };
+
+        app.update();
+        assert_eq!(counts(&app), (1, 1, 1));
+        app.resources.get_mut::<DespawnRequested>().unwrap().0 = true;
+        app.update();
+        assert_eq!(counts(&app), (0, 0, 0));
+    }
+
+    #[test]
+    fn set_anchor_checks_the_index() {
+        let mut cloth = free_cloth((3, 3));
+        let outside = ClothError::IndexOutOfBounds { idx: (3, 0), subdivisions: (3, 3) };
+        assert_eq!(cloth.set_anchor((3, 0), Anchor::Fixed), Err(outside));
+        assert_eq!(cloth.set_anchor((2, 2), Anchor::Fixed), Ok(()));
+        assert!(cloth.particles.fixed(cloth.particles.flatten((2, 2))));
+    }
+
+    /* A FREE CLOTH FLAT ABOVE THE SPHERE, AT height ABOVE ITS CENTER, WITH THE SPHERE IN colliders */
+    fn over_sphere(height: F, contact_cache: bool) -> (ClothSim, Colliders) {
+        let mut colliders = Colliders::default();
+        colliders.add(Collider::Sphere { center: P::origin(), radius: CONTACT_RADIUS });
+        let config = ClothConfig {
+            pose: Pose::FlatHorizontal { height },
+            pin_preset: PinPreset::Free,
+            origin: V::new(-CONTACT_SIZE / 2., 0., -CONTACT_SIZE / 2.),
+            contact_cache,
+            ..ClothConfig::default()
+        };
+        let subdivisions = (CONTACT_SUBDIVISIONS, CONTACT_SUBDIVISIONS);
+        (ClothSim::new(CONTACT_SIZE, CONTACT_SIZE, subdivisions, config).unwrap(), colliders)
+    }
+
+    /* LARGEST DISTANCE BETWEEN THE SAME PARTICLE OF TWO CLOTHS */
+    fn deviation(a: &ClothSim, b: &ClothSim) -> F {
+        a.particles.iter().zip(b.particles.iter()).map(|(p, q)| (p - q).norm()).fold(0., F::max)
+    }
+
+    /* PARAMETER a OF THE CATENARY y = a cosh(x / a) OF THE GIVEN LENGTH BETWEEN TWO POINTS span APART AT THE */
+    /* SAME HEIGHT, SOLVING 2 a sinh(span / 2a) = length BY BISECTION ON A LOG SCALE, THE LEFT SIDE FALLS WITH a */
+    fn catenary(span: F, length: F) -> F {
+        let (mut lo, mut hi): (F, F) = (1e-3 * span, 1e3 * span);
+        for _ in 0..100 {
+            let a = (lo * hi).sqrt();
+            if 2. * a * (span / (2. * a)).sinh() > length {
+                lo = a;
+            } else {
+                hi = a;
+            }
+        }
+        (lo * hi).sqrt()
+    }
+
+    /* A ROPE HUNG BETWEEN TWO PINS AT THE SAME HEIGHT, CLOSER THAN ITS LENGTH, SAGS INTO THE CATENARY OF THE */
+    /* SAME LENGTH WITH BOUNDED STRAIN */
+    #[test]
+    fn rope_sags_into_a_catenary() {
+        // LENGTH AND SEGMENTS OF THE ROPE, AND THE DISTANCE BETWEEN ITS PINS AS A FRACTION OF ITS LENGTH
+        const LENGTH: F = 10.;
+        const SEGMENTS: I = 30;
+        const SPAN: F = 0.8;
+        // LARGEST STRAIN, AND LARGEST DEVIATION FROM THE CATENARY AS A FRACTION OF ITS SAG
+        const MAX_STRAIN: F = 0.1;
+        const SHAPE_TOLERANCE: F = 0.1;
+
+        let config = ClothConfig {
+            pose: Pose::FlatHorizontal { height: 0. },
+            pin_preset: PinPreset::Custom(vec![(0, 0), (0, SEGMENTS)]),
+            ..ClothConfig::default()
+        };
+        let mut rope = ClothSim::new_rope(LENGTH, SEGMENTS, config).unwrap();
+        /* START OUT STRAIGHT AND EVENLY COMPRESSED BETWEEN THE PINS */
+        let start = rope.particles[(0, 0)];
+        let span = SPAN * LENGTH;
+        let compressed: Vec<_> = rope.particles.iter().map(|p| start + (p - start) * SPAN).collect();
+        rope.set_positions(&compressed).unwrap();
+        rope.reset_velocities();
+        (0..2000).for_each(|_| rope.step(&[]));
+
+        /* THE CATENARY THROUGH BOTH PINS, WITH x FROM THE MIDDLE BETWEEN THEM */
+        let a = catenary(span, LENGTH);
+        let lowest = a * (span / (2. * a)).cosh();
+        let expected = |x: F| a * (x / a).cosh() - lowest;
+        let deviation = rope
+            .particles
+            .iter()
+            .map(|p| (p - start).y - expected((p - start).z - span / 2.))
+            .fold(0., |max: F, d| max.max(d.abs()));
+        let sag = lowest - a;
+        assert!(rope.max_strain() <= MAX_STRAIN, "max strain {}", rope.max_strain());
+        assert!(deviation <= SHAPE_TOLERANCE * sag, "deviation {} from a catenary with sag {}", deviation, sag);
+    }
+
+    /* A FREE CLOTH SETTLED ON A SPHERE KEEPS ITS CACHED CONTACTS STILL */
+    #[test]
+    fn cached_contacts_stay_still() {
+        // LARGEST MOVE OF A RESTING CONTACT PARTICLE IN ONE STEP, AND THE STEPS IT IS MEASURED OVER
+        const EPSILON: F = 1e-4;
+        const STEPS: I = 100;
+
+        let (mut cloth, colliders) = over_sphere(CONTACT_DROP, true);
+        (0..600).for_each(|_| cloth.step(&colliders.0));
+        let mut jitter: F = 0.;
+        for _ in 0..STEPS {
+            let before = cloth.particles.data.clone();
+            cloth.step(&colliders.0);
+            for (i, p) in before.iter().enumerate().filter(|&(i, _)| cloth.in_contact(i)) {
+                jitter = jitter.max((cloth.particles.data[i] - p).norm());
+            }
+        }
+        assert!(cloth.contacts() > 0);
+        assert!(jitter <= EPSILON, "a contact particle moved {:.2e} in one step", jitter);
+    }
+
+    /* A CLOTH SPAWNED HALFWAY DOWN THE SPHERE, WITH ITS TOP POKING THROUGH, IS PUSHED OUT WITHOUT ANY */
+    /* PARTICLE MOVING MORE THAN THE CAP IN ONE STEP */
+    #[test]
+    fn untangles_gently() {
+        // STEPS THE CLOTH GETS TO GET OUT OF THE SPHERE
+        const STEPS: I = 300;
+
+        let (mut cloth, colliders) = over_sphere(CONTACT_RADIUS / 2., true);
+        let thickness = cloth.config.thickness;
+        let cap = UNTANGLE_PUSH * CONTACT_SIZE / (CONTACT_SUBDIVISIONS - 1) as F;
+        let (mut untangled, mut fastest) = (false, 0.);
+        while cloth.steps() < STEPS && !untangled {
+            let (before, was_untangling) = (cloth.particles.data.clone(), cloth.untangling());
+            cloth.step(&colliders.0);
+            if was_untangling || cloth.untangling() {
+                let moved = before.iter().zip(&cloth.particles.data).map(|(p, q)| (q - p).norm());
+                fastest = moved.fold(fastest, F::max);
+            }
+            untangled = was_untangling && !cloth.untangling();
+        }
+        /* HOW FAR INSIDE THE SPHERE, AND PAST THE HALF THICKNESS KEPT FROM IT, THE DEEPEST PARTICLE IS */
+        let closest = cloth.particles.data.iter().map(|p| p.coords.norm()).fold(F::INFINITY, F::min);
+        let depth = CONTACT_RADIUS + thickness / 2. - closest;
+        assert!(untangled, "still untangling after {} steps", STEPS);
+        assert!(fastest <= cap * (1. + F::EPSILON.sqrt()), "moved {} in a step, past the cap {}", fastest, cap);
+        assert!(depth <= thickness, "deepest particle {} inside", depth);
+    }
+
+    /* THE FLEXION SPRING OF A 3 PARTICLE ROPE HELD COMPRESSED PAST YIELD KEEPS ITS REST LENGTH FOR */
+    /* yield_steps STEPS AND THEN CREEPS EXACTLY creep OF THE WAY TOWARDS THE HELD LENGTH */
+    #[test]
+    fn plastic_flexion_creeps_after_yield() {
+        // COMPRESSION OF THE FLEXION SPRING, AND THE LARGEST ERROR OF ITS CREPT REST LENGTH
+        const COMPRESSION: F = 0.4;
+        const TOLERANCE: F = 1e-5;
+
+        let plasticity = Plasticity::default();
+        let config = ClothConfig {
+            pose: Pose::FlatHorizontal { height: 0. },
+            pin_preset: PinPreset::Free,
+            gravity: V::zeros(),
+            plasticity: Some(plasticity),
+            ..ClothConfig::default()
+        };
+        let mut rope = ClothSim::new_rope(2., 2, config).unwrap();
+        rope.fix_all();
+        /* THE FLEXION SPRING IS THE LONGEST CONSTRAINT AT THE FIRST END */
+        let rest = |rope: &ClothSim, id| rope.rest_length(id).unwrap_or(0.);
+        let longest = |a, b| rest(&rope, a).partial_cmp(&rest(&rope, b)).unwrap_or(Equal);
+        let flexion = rope.constraints_at((0, 0)).unwrap().max_by(|&a, &b| longest(a, b)).unwrap();
+        let d0 = rest(&rope, flexion);
+        let start = rope.particles[(0, 0)];
+        let held = (1. - COMPRESSION) * d0;
+        rope.set_position((0, 2), start + (rope.particles[(0, 2)] - start).normalize() * held).unwrap();
+        rope.reset_velocities();
+
+        (0..plasticity.yield_steps).for_each(|_| rope.step(&[]));
+        let before = rest(&rope, flexion);
+        rope.step(&[]);
+        let after = rest(&rope, flexion);
+        assert!((before - d0).abs() <= TOLERANCE, "rest length {} moved from {} before yielding", before, d0);
+        let expected = d0 + (held - d0) * plasticity.creep;
+        assert!((after - expected).abs() <= TOLERANCE, "rest length {} crept to instead of {}", after, expected);
+    }
+
+    /* BAD INPUT TO THE FALLIBLE APIS: A 1x1 CLOTH, A FRAME OF THE WRONG SIZE, A PARTICLE OUTSIDE THE GRID */
+    /* AND PICKING ON A CLOTH WITH A NaN PARTICLE, WHICH HAS TO SKIP IT, OR WITH NOTHING BUT NaN PARTICLES */
+    #[test]
+    fn bad_input_is_reported() {
+        let invalid = ClothError::InvalidDimensions { size: (1., 1.), subdivisions: (1, 1) };
+        assert_eq!(ClothSim::new(1., 1., (1, 1), ClothConfig::default()).err(), Some(invalid));
+
+        let mut cloth = free_cloth((3, 3));
+        let mut positions = cloth.particles.data.clone();
+        let corner = cloth.world_position((2, 2));
+        positions[0] = P::new(F::NAN, F::NAN, F::NAN);
+        let mismatch = ClothError::SizeMismatch { expected: 9, found: 4 };
+        assert_eq!(cloth.set_positions(&positions[..4]), Err(mismatch));
+        let outside = ClothError::IndexOutOfBounds { idx: (3, 0), subdivisions: (3, 3) };
+        assert_eq!(cloth.set_position((3, 0), P::origin()), Err(outside));
+        assert_eq!(cloth.set_positions(&positions), Ok(()));
+
+        let nan = P::new(F::NAN, 0., 0.);
+        assert_eq!(cloth.set_fixed(nan, 0.1, true), Err(ClothError::NonFinitePosition));
+        assert_eq!(cloth.set_fixed(corner, 0.1, true), Ok(()));
+        assert!(cloth.particles.fixed(cloth.particles.flatten((2, 2))));
+        cloth.set_positions(&[P::new(F::NAN, F::NAN, F::NAN); 9]).unwrap();
+        assert_eq!(cloth.set_fixed(corner, 0.1, true), Err(ClothError::NonFinitePosition));
+    }
+
+    /* A CLOTH IN WIND, PULLED BY AN ATTRACTOR, TAKES THE SAME STEPS IN SLOW MOTION OVER AS MANY MORE FRAMES AS */
+    /* THAT TAKES, AND ENDS UP WHERE IT DOES AT FULL SPEED */
+    #[test]
+    fn slow_motion_follows_full_speed() {
+        // SLOW MOTION, THE STEPS AT FULL SPEED, AND THE LARGEST DEVIATION FROM FULL SPEED
+        const SLOW_MOTION: F = 0.5;
+        const STEPS: I = 100;
+        const TOLERANCE: F = 1e-4;
+
+        let attractor = Attractor { point: P::new(0., -8., 6.), strength: 0.2, radius: 20., core: 0.3 };
+        let run = |time_scale: F| {
+            let config = ClothConfig { time_scale, ..ClothConfig::default() };
+            let (w, h) = SIZE;
+            let mut cloth = ClothSim::new(w, h, (CONTACT_SUBDIVISIONS, CONTACT_SUBDIVISIONS), config).unwrap();
+            cloth.set_attractors(&[attractor]);
+            for _ in 0..(STEPS as F / time_scale).ceil() as I {
+                if cloth.tick() {
+                    cloth.step(&[]);
+                }
+            }
+            cloth
+        };
+        let (full, slow) = (run(1.), run(SLOW_MOTION));
+        assert_eq!(slow.steps(), full.steps());
+        assert!(deviation(&full, &slow) <= TOLERANCE, "slow motion is {:.2e} off", deviation(&full, &slow));
+    }
+
+    /* A PATCH TILED 3x1, STITCHED AND NOT, SIMULATES LIKE A CLOTH BUILT DIRECTLY AT THE SAME SIZE AND SPACING, */
+    /* SO THE SEAMS ADD NO TENSION AND THE PINS LINE UP. MIRRORING TWICE GIVES THE PATCH BACK */
+    #[test]
+    fn tiled_and_mirrored_match() {
+        // PARTICLES ALONG EACH SIDE OF THE PATCH, HOW MANY TIMES ACROSS IT IS TILED, THE STEPS BOTH CLOTHS RUN
+        // AND THE LARGEST DEVIATION BETWEEN THEM
+        const N: I = 20;
+        const TILES: I = 3;
+        const STEPS: I = 100;
+        const TOLERANCE: F = 1e-3;
+
+        let config = ClothConfig { jitter: 0., pin_preset: PinPreset::TopRow, ..ClothConfig::default() };
+        let (w, h) = SIZE;
+        let patch = ClothSim::new(w, h, (N, N), config.clone()).unwrap();
+        for &stitch in &[true, false] {
+            let mut tiled = patch.tiled(TILES, 1, stitch, TileUvs::Repeat).unwrap();
+            let (tw, th) = tiled.subdivisions();
+            assert_eq!((tw, th), (TILES * N - (TILES - 1) * stitch as I, N));
+            let mut direct = ClothSim::new(w * tw as F / N as F, h, (tw, th), config.clone()).unwrap();
+            for _ in 0..STEPS {
+                tiled.step(&[]);
+                direct.step(&[]);
+            }
+            let largest = deviation(&tiled, &direct);
+            assert!(largest <= TOLERANCE, "stitched {}: {:.2e} off the direct cloth", stitch, largest);
+        }
+
+        /* A CLOTH PINNED ALONG ITS LEFT COLUMN IS PINNED ALONG ITS RIGHT ONE MIRRORED, AND BACK TO ITSELF TWICE */
+        let pinned_left = ClothConfig { pin_preset: PinPreset::LeftColumn, ..config };
+        let patch = ClothSim::new(w, h, (N, N), pinned_left).unwrap();
+        for &axis in &[Axis::X, Axis::Y] {
+            let twice = patch.mirrored(axis).unwrap().mirrored(axis).unwrap();
+            assert!(deviation(&patch, &twice) <= TOLERANCE, "mirrored twice along {:?}", axis);
+            assert_eq!(twice.pin_layout(), patch.pin_layout());
+        }
+        let right: Vec<_> = (0..N).map(|y| (N - 1, y)).collect();
+        assert_eq!(patch.mirrored(Axis::X).unwrap().pin_layout(), right);
+    }
+
+    /* A CLOTH THAT WOULD SWING FOREVER AT damping 1 IS PUSHED SIDEWAYS AND LET GO WITH auto_damping ON. IT HAS TO */
+    /* SETTLE, WITH THE DAMPING LEFT AT 1 WHILE IT IS PUSHED AND NEVER BELOW THE FLOOR. A FREE CLOTH WITH THE SAME */
+    /* SETTINGS HAS TO KEEP FALLING NEARLY AS FAST AS IT WOULD AT THE FLOOR */
+    #[test]
+    fn auto_damping_settles_without_freezing() {
+        // PARTICLES ALONG EACH SIDE, THE SIDEWAYS FORCE OF THE PUSH AND FOR HOW MANY STEPS, THE STEPS IT GETS TO
+        // SETTLE AFTERWARDS, AND THE STEPS A FREE CLOTH FALLS FOR
+        const N: I = 20;
+        const PUSH: F = 0.5;
+        const PUSH_STEPS: I = 40;
+        const SETTLE_STEPS: I = 2000;
+        const FALL_STEPS: I = 300;
+        // FRACTION OF THE TERMINAL SPEED AT THE DAMPING FLOOR THAT THE FREE CLOTH MUST FALL AT
+        const FALL_SPEED: F = 0.9;
+
+        let settings = AutoDamping::default();
+        let config = ClothConfig {
+            damping: 1.,
+            auto_damping: Some(settings),
+            wind: Wind { strength: 0., ..Wind::default() },
+            air_drag: 0.,
+            pose: Pose::HangingVertical,
+            pin_preset: PinPreset::TopRow,
+            ..ClothConfig::default()
+        };
+        let (w, h) = SIZE;
+        let mut cloth = ClothSim::new(w, h, (N, N), config.clone()).unwrap();
+        for _ in 0..PUSH_STEPS {
+            cloth.add_force(V::new(PUSH, 0., PUSH));
+            cloth.step(&[]);
+            assert_eq!(cloth.stats.damping, 1., "the damping was tuned while the cloth was pushed");
+        }
+        let mut lowest = F::INFINITY;
+        let settled = (1..=SETTLE_STEPS).any(|_| {
+            cloth.step(&[]);
+            lowest = lowest.min(cloth.stats.damping);
+            cloth.mean_energy().0 < settings.target
+        });
+        assert!(settled, "not settled after {} steps", SETTLE_STEPS);
+        assert!(lowest >= settings.floor, "damping {} below the floor", lowest);
+
+        let free = ClothConfig { pin_preset: PinPreset::Free, ..config.clone() };
+        let mut falling = ClothSim::new(w, h, (N, N), free).unwrap();
+        (0..FALL_STEPS).for_each(|_| falling.step(&[]));
+        let count = falling.particles.len();
+        let speed = (0..count).map(|i| falling.particles.velocity(i).norm()).sum::<F>() / count as F;
+        let terminal = config.gravity.norm() * falling.dt() / (1. - settings.floor);
+        assert!(speed >= FALL_SPEED * terminal, "falling at {}, {} at the floor", speed, terminal);
+    }
+
+    /* A HANGING CLOTH WITH EVERY FREE PARTICLE KNOCKED OUT OF PLACE IS RELAXED, WHICH HAS TO LEAVE IT UNDER */
+    /* MAX_STRAIN WITH ITS PINS WHERE THEY WERE. A TABLECLOTH SETTLED ONTO THE SPHERE HAS TO END UP DRAPED OVER */
+    /* IT. NEITHER MAY CARRY ANY VELOCITY INTO ITS FIRST STEP */
+    #[test]
+    fn relaxed_and_settled_start_at_rest() {
+        // PARTICLES ALONG EACH SIDE, HOW FAR EACH OF THEM IS KNOCKED OUT OF PLACE AS A FRACTION OF THE REST EDGE,
+        // AND THE SEED OF THE KNOCKS
+        const N: I = 20;
+        const KNOCK: F = 0.2;
+        const SEED: u64 = 11;
+        // STEPS OF relax AND settle_under_gravity, AND THE LARGEST STRAIN relax MAY LEAVE
+        const RELAX_STEPS: I = 50;
+        const SETTLE_STEPS: I = 400;
+        const MAX_STRAIN: F = 0.01;
+
+        let still = |c: &ClothSim| (0..c.particles.len()).all(|i| c.particles.displacement(i) == V::zeros());
+        let mut rng = StdRng::seed_from_u64(SEED);
+        let config = ClothConfig { pose: Pose::HangingVertical, pin_preset: PinPreset::TopRow, ..Default::default() };
+        let (w, h) = SIZE;
+        let mut cloth = ClothSim::new(w, h, (N, N), config).unwrap();
+        let knock = KNOCK * w / N as F;
+        let start = cloth.particles.data.clone();
+        let knocked: Vec<_> = (0..start.len())
+            .map(|i| match cloth.particles.fixed(i) {
+                true => start[i],
+                false => start[i] + V::new(rng.gen_range(-knock, knock), rng.gen_range(-knock, knock), 0.),
+            })
+            .collect();
+        cloth.set_positions(&knocked).unwrap();
+        assert!(cloth.max_strain() >= MAX_STRAIN);
+        cloth.relax(RELAX_STEPS);
+        assert!(cloth.max_strain() < MAX_STRAIN, "max strain {} after relaxing", cloth.max_strain());
+        assert!((0..start.len()).filter(|&i| cloth.particles.fixed(i)).all(|i| cloth.particles.data[i] == start[i]));
+        assert!(still(&cloth));
+
+        let (tablecloth, colliders) = over_sphere(CONTACT_DROP, true);
+        let tablecloth = tablecloth.settled(SETTLE_STEPS, &colliders.0);
+        let lowest = tablecloth.particles.iter().map(|p| p.y).fold(F::INFINITY, F::min);
+        assert!(lowest < 0., "lowest point {} above the center of the sphere", lowest);
+        assert!(still(&tablecloth));
+        assert_eq!(tablecloth.steps(), 0);
+    }
+
+    /* TWO DETERMINISTIC RUNS OF THE SAME SEEDED SCENE, A FREE CLOTH IN GUSTY WIND FOLDING ONTO A SPHERE WITH */
+    /* TRIANGLE SELF-COLLISION, END WITH BIT-IDENTICAL POSITIONS */
+    #[test]
+    fn deterministic_runs_match_bitwise() {
+        let run = || {
+            let mut colliders = Colliders::default();
+            colliders.add(Collider::Sphere { center: P::origin(), radius: CONTACT_RADIUS });
+            let config = ClothConfig {
+                pose: Pose::FlatHorizontal { height: CONTACT_DROP },
+                pin_preset: PinPreset::Free,
+                origin: V::new(-CONTACT_SIZE / 2., 0., -CONTACT_SIZE / 2.),
+                self_collision: SelfCollision::Triangles,
+                seed: 7,
+                deterministic: true,
+                ..ClothConfig::default()
+            };
+            let subdivisions = (CONTACT_SUBDIVISIONS, CONTACT_SUBDIVISIONS);
+            let mut cloth = ClothSim::new(CONTACT_SIZE, CONTACT_SIZE, subdivisions, config).unwrap();
+            (0..200).for_each(|_| cloth.step(&colliders.0));
+            cloth.particles.iter().map(|p| [p.x.to_bits(), p.y.to_bits(), p.z.to_bits()]).collect::<Vec<_>>()
+        };
+        assert!(run() == run(), "two deterministic runs ended apart");
+    }
+
+    /* A CYLINDER WITH A UV SEAM, ITS FIRST COLUMN OF VERTICES REPEATED AT u = 1, WELDS INTO ONE PARTICLE PER */
+    /* POINT AND HANGS FROM ITS TOP RING IN THE WIND AS ONE CLOSED TUBE: THE VERTICES ON EITHER SIDE OF THE */
+    /* SEAM STAY TOGETHER WITH ONE NORMAL AND THE UVS ARE THOSE OF THE MESH. A RESET REBUILDS IT FROM THE */
+    /* MESH, WHILE A NEW RESOLUTION, TILING AND CUTS ARE REFUSED */
+    #[test]
+    fn seamed_cylinder_holds_together() {
+        // VERTICES AROUND THE CYLINDER AND RINGS DOWN IT, ITS RADIUS AND HEIGHT, THE WIND AND THE STEPS IT HANGS IN IT
+        const AROUND: I = 16;
+        const RINGS: I = 10;
+        const RADIUS: f32 = 1.;
+        const HEIGHT: f32 = 3.;
+        const WIND: F = 5.;
+        const STEPS: I = 150;
+
+        let (mut positions, mut uvs, mut indices) = (vec![], vec![], vec![]);
+        for ring in 0..RINGS {
+            for k in 0..=AROUND {
+                let (u, v) = (k as f32 / AROUND as f32, ring as f32 / (RINGS - 1) as f32);
+                let angle = u * std::f32::consts::PI * 2.;
+                positions.push([RADIUS * angle.cos(), HEIGHT * (1. - v), RADIUS * angle.sin()]);
+                uvs.push([u, v]);
+            }
+        }
+        let vertex = |ring: I, k: I| ring * (AROUND + 1) + k;
+        for ring in 0..RINGS - 1 {
+            for k in 0..AROUND {
+                let (a, b) = (vertex(ring, k), vertex(ring, k + 1));
+                let (c, d) = (vertex(ring + 1, k + 1), vertex(ring + 1, k));
+                indices.extend([a, b, d, b, c, d].iter().map(|&i| i as u32));
+            }
+        }
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions.into());
+        mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs.clone().into());
+        mesh.set_indices(Some(Indices::U32(indices)));
+
+        let mut cloth = ClothSim::from_mesh(&mesh, ClothConfig::default()).unwrap();
+        assert_eq!(cloth.subdivisions(), (AROUND * RINGS, 1));
+        let start = cloth.particles.data.clone();
+        (0..AROUND).for_each(|k| cloth.set_pinned((k, 0), true).unwrap());
+        cloth.set_wind(V::x(), WIND);
+        (0..STEPS).for_each(|_| cloth.step(&[]));
+        assert_eq!(cloth.pieces().0, 1);
+        assert!(cloth.particles.iter().zip(&start).any(|(p, s)| (p - s).norm() > 1e-3));
+
+        let mesh = cloth.build_mesh();
+        let attribute = |name: &'static str| match mesh.attribute(name) {
+            Some(VertexAttributeValues::Float3(values)) => values.clone(),
+            _ => panic!("the cloth mesh has no {}", name),
+        };
+        let (positions, normals) = (attribute(Mesh::ATTRIBUTE_POSITION), attribute(Mesh::ATTRIBUTE_NORMAL));
+        for ring in 0..RINGS {
+            let (first, last) = (vertex(ring, 0), vertex(ring, AROUND));
+            assert_eq!(positions[first], positions[last]);
+            assert_eq!(normals[first], normals[last]);
+        }
+        match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+            Some(VertexAttributeValues::Float2(rendered)) => assert_eq!(rendered[..uvs.len()], uvs[..]),
+            _ => panic!("the cloth mesh has no uvs"),
+        }
+
+        cloth.regenerate(AROUND * RINGS, 1).unwrap();
+        assert_eq!(cloth.particles.data, start);
+        assert_eq!(cloth.regenerate(AROUND, RINGS), Err(ClothError::NotAGrid));
+        assert_eq!(cloth.tiled(2, 1, true, TileUvs::Repeat).err(), Some(ClothError::NotAGrid));
+        cloth.cut(|(x, _)| x.cmp(&AROUND));
+        assert!(!cloth.torn());
+    }
+
+    /* WITHOUT JITTER THE FIRST PARTICLE SITS ON THE ORIGIN. WITH IT EVERY COORDINATE MOVES BY LESS THAN THE */
+    /* AMPLITUDE, THE SAME WAY FOR THE SAME SEED AND ANOTHER WAY FOR ANOTHER */
+    #[test]
+    fn jitter_is_bounded_and_seeded() {
+        const JITTER: F = 0.1;
+
+        let origin = V::new(1., 2., 3.);
+        let hanging = |jitter, seed| {
+            let config = ClothConfig { pose: Pose::HangingVertical, origin, jitter, seed, ..ClothConfig::default() };
+            ClothSim::new(2., 2., (8, 8), config).unwrap()
+        };
+        let still = hanging(0., 0);
+        assert_eq!(still.particles[(0, 0)], P::from(origin));
+        let (a, b, other) = (hanging(JITTER, 1), hanging(JITTER, 1), hanging(JITTER, 2));
+        assert_eq!(a.particles.data, b.particles.data);
+        assert_ne!(a.particles.data, other.particles.data);
+        for (p, q) in a.particles.iter().zip(still.particles.iter()) {
+            assert!((p - q).iter().all(|d| d.abs() < JITTER), "{} is too far from {}", p, q);
+        }
+    }
+
+    /* A CLOTH HUNG UNDER HEAVY GRAVITY AT ONE ITERATION STRETCHES LESS WITH ITS STRUCTURAL SPRINGS CAPPED, */
+    /* AND REPEATED PASSES OF THE CAP BRING EVERY ONE OF THEM WITHIN IT */
+    #[test]
+    fn strain_limit_caps_structural_stretch() {
+        const STEPS: I = 60;
+        const LIMIT: F = 1.05;
+
+        let hung = |max_strain| {
+            let config = ClothConfig {
+                pose: Pose::HangingVertical,
+                pin_preset: PinPreset::TopRow,
+                gravity: V::new(0., -2., 0.),
+                min_iterations: 1,
+                max_iterations: 1,
+                max_strain,
+                ..ClothConfig::default()
+            };
+            let mut cloth = ClothSim::new(3., 3., (16, 16), config).unwrap();
+            (0..STEPS).for_each(|_| cloth.step(&[]));
+            cloth
+        };
+        let stretch = |cloth: &ClothSim| {
+            let structural = cloth.constraints.iter().filter(|c| c.kind == ConstraintKind::Structural);
+            structural.map(|c| (cloth.particles.data[c.p2] - cloth.particles.data[c.p1]).norm() / c.d).fold(0., F::max)
+        };
+        let (loose, mut capped) = (hung(None), hung(Some((LIMIT, LIMIT))));
+        assert!(stretch(&capped) < stretch(&loose), "capped {} loose {}", stretch(&capped), stretch(&loose));
+        (0..STEPS).for_each(|_| capped.limit_strain((LIMIT, LIMIT)));
+        assert!(stretch(&capped) <= LIMIT + 1e-3, "still stretched to {}", stretch(&capped));
+    }
+
+    /* A CLOTH AT REST STOPS AFTER min_iterations, ONE WITH A PARTICLE PULLED OUT OF IT RUNS UP TO */
+    /* max_iterations TO PULL IT BACK */
+    #[test]
+    fn iterations_follow_the_residual() {
+        let config = ClothConfig {
+            pose: Pose::FlatHorizontal { height: 0. },
+            pin_preset: PinPreset::Free,
+            gravity: V::zeros(),
+            wind: Wind { strength: 0., ..Wind::default() },
+            jitter: 0.,
+            min_iterations: 2,
+            max_iterations: 12,
+            ..ClothConfig::default()
+        };
+        let mut cloth = ClothSim::new(4., 4., (10, 10), config).unwrap();
+        cloth.step(&[]);
+        assert_eq!(cloth.stats.iterations, 2);
+        let pulled = cloth.particles[(5, 5)] + V::y();
+        cloth.set_position((5, 5), pulled).unwrap();
+        cloth.step(&[]);
+        assert_eq!(cloth.stats.iterations, 12);
+    }
+
+    /* A CLOTH SPREADING OUT EVENLY KEEPS SPREADING, ONLY SLOWER, ONCE ITS STRUCTURAL SPRINGS ARE DAMPED, */
+    /* WHILE ONE SLIDING ALONG AS A WHOLE IS LEFT ALONE. THE FRACTION OF EACH KIND IS CLAMPED TO [0, 1] */
+    #[test]
+    fn spring_damping_slows_stretching() {
+        // OUTWARD SPEED PER UNIT OF DISTANCE FROM THE CENTER
+        const SPREAD: F = 0.5;
+
+        let moving = |spring_damping, velocity: &dyn Fn(V) -> V| {
+            let config = ClothConfig { pin_preset: PinPreset::Free, spring_damping, ..ClothConfig::default() };
+            let mut cloth = ClothSim::new(2., 2., (6, 6), config).unwrap();
+            let dt = cloth.dt();
+            let n = cloth.particles.data.len();
+            let center = cloth.particles.data.iter().fold(V::zeros(), |sum, p| sum + p.coords) / n as F;
+            cloth.particles.set_last_dt(dt);
+            for i in 0..n {
+                let p = cloth.particles.data[i];
+                cloth.particles.place(i, p, velocity(p.coords - center) * dt);
+            }
+            cloth
+        };
+        let spread = |cloth: &ClothSim| {
+            let rates: Vec<F> = (cloth.constraints.iter())
+                .filter(|c| c.kind == ConstraintKind::Structural)
+                .map(|c| {
+                    let n = (cloth.particles.data[c.p2] - cloth.particles.data[c.p1]).normalize();
+                    (cloth.particles.velocity(c.p2) - cloth.particles.velocity(c.p1)).dot(&n) / c.d
+                })
+                .collect();
+            rates.iter().sum::<F>() / rates.len() as F
+        };
+
+        let mut cloth = moving(SpringDamping { structural: 0.5, ..SpringDamping::default() }, &|r| r * SPREAD);
+        let undamped = spread(&cloth);
+        cloth.damp_springs();
+        let slowed = spread(&cloth);
+        assert!(0. < slowed && slowed < undamped, "spread {} went to {}", undamped, slowed);
+
+        let mut sliding = moving(SpringDamping::uniform(1.), &|_| V::new(1., 0., -2.));
+        let before = sliding.particles.data.clone();
+        sliding.damp_springs();
+        assert_eq!(sliding.particles.data, before);
+
+        let out_of_range = SpringDamping { structural: 0.2, shear: 3., flexion: -1. };
+        let fractions = [ConstraintKind::Structural, ConstraintKind::Shear, ConstraintKind::Flexion];
+        assert_eq!(fractions.iter().map(|&kind| out_of_range.get(kind)).collect::<Vec<_>>(), vec![0.2, 1., 0.]);
+    }
+
+    /* A CLOTH CRUMPLED INTO A BALL SMALLER THAN ITS THICKNESS GIVES EVERY PARTICLE MANY CONTACTS AT ONCE, BUT THEIR */
+    /* AVERAGE MOVES IT NO MORE THAN ONE CONTACT WOULD, AND NEVER MORE THAN max_collision_correction */
+    #[test]
+    fn crumpled_self_contacts_are_averaged_and_capped() {
+        // SHRINK OF THE CLOTH TOWARDS ITS CENTER, AND THE CAP OF THE SECOND RUN
+        const CRUMPLE: F = 0.05;
+        const CAP: F = 0.01;
+
+        let pushes = |max_collision_correction| {
+            let config = ClothConfig {
+                pin_preset: PinPreset::Free,
+                gravity: V::zeros(),
+                wind: Wind { strength: 0., ..Wind::default() },
+                self_collision: SelfCollision::Points,
+                max_collision_correction,
+                ..ClothConfig::default()
+            };
+            let mut cloth = ClothSim::new(2., 2., (8, 8), config).unwrap();
+            let center = cloth.particles[(4, 4)];
+            for i in 0..cloth.particles.data.len() {
+                let p = cloth.particles.data[i];
+                cloth.particles.place(i, center + (p - center) * CRUMPLE, V::zeros());
+            }
+            cloth.step(&[]);
+            let pushed = |(p, q): (&P, &P)| (p - q).norm();
+            cloth.particles.data.iter().zip(&cloth.integrated).map(pushed).collect::<Vec<F>>()
+        };
+        let thickness = ClothConfig::default().thickness;
+        let averaged = pushes(F::INFINITY);
+        let most = averaged.iter().copied().fold(0., F::max);
+        assert!(0. < most && most <= thickness / 2. + 1e-5, "pushed {} apart", most);
+        assert!(pushes(CAP).iter().all(|&d| d <= CAP + 1e-5));
+    }
+
+    /* A CLOTH PULLED UP AT ITS CENTER IS SYMMETRIC UNDER A HALF TURN ABOUT IT. JACOBI SOLVES EVERY CONSTRAINT FROM */
+    /* THE SAME POSITIONS SO THE SYMMETRY SURVIVES A STEP, WHILE GAUSS-SEIDEL LEAVES A TRACE OF ITS ORDER */
+    #[test]
+    fn jacobi_is_order_independent() {
+        // PARTICLES ALONG EACH SIDE, ODD SO ONE SITS IN THE CENTER
+        const N: I = 9;
+
+        let asymmetry = |solver| {
+            let config = ClothConfig {
+                pose: Pose::FlatHorizontal { height: 0. },
+                pin_preset: PinPreset::Free,
+                gravity: V::zeros(),
+                wind: Wind { strength: 0., ..Wind::default() },
+                jitter: 0.,
+                solver,
+                ..ClothConfig::default()
+            };
+            let mut cloth = ClothSim::new(4., 4., (N, N), config).unwrap();
+            let center = (N / 2, N / 2);
+            cloth.set_position(center, cloth.particles[center] + V::y()).unwrap();
+            cloth.step(&[]);
+            let mut most: F = 0.;
+            for (x, y) in (0..N).flat_map(|x| (0..N).map(move |y| (x, y))) {
+                most = most.max((cloth.particles[(x, y)].y - cloth.particles[(N - 1 - x, N - 1 - y)].y).abs());
+            }
+            most
+        };
+        assert!(asymmetry(SolverMode::Jacobi) < 1e-5);
+        assert!(asymmetry(SolverMode::GaussSeidel) > 1e-4);
+    }
+
+    /* THE SHUFFLED ORDER VISITS EVERY CONSTRAINT ONCE, IS DRAWN FROM THE SEED AND FORGETS REMOVED ONES. */
+    /* ALTERNATING SWEEPS MATCH THE GENERATED ORDER ON EVEN ITERATIONS AND RUN BACKWARDS ON ODD ONES */
+    #[test]
+    fn solve_orders() {
+        let stretched = |solve_order, seed| {
+            let config = ClothConfig { pin_preset: PinPreset::TopCorners, solve_order, seed, ..ClothConfig::default() };
+            let mut cloth = ClothSim::new(3., 2., (7, 5), config).unwrap();
+            cloth.set_position((3, 2), cloth.particles[(3, 2)] + V::new(0.5, 0.5, -1.)).unwrap();
+            cloth
+        };
+
+        let mut cloth = stretched(SolveOrder::Shuffled, 3);
+        let mut ids: Vec<_> = cloth.constraints.iter_with_ids().map(|(id, _)| id).collect();
+        let mut order = cloth.shuffled.clone();
+        assert_ne!(order, ids);
+        order.sort_by_key(|id| ids.iter().position(|i| i == id));
+        assert_eq!(order, ids);
+        assert_eq!(stretched(SolveOrder::Shuffled, 3).shuffled, cloth.shuffled);
+        assert_ne!(stretched(SolveOrder::Shuffled, 4).shuffled, cloth.shuffled);
+        let removed = ids.swap_remove(10);
+        cloth.remove_constraint(removed);
+        assert!(!cloth.shuffled.contains(&removed));
+
+        let sweep = |solve_order, iter| {
+            let mut cloth = stretched(solve_order, 0);
+            cloth.solve_gauss_seidel(iter);
+            cloth.particles.data.clone()
+        };
+        assert_eq!(sweep(SolveOrder::AlternateSweeps, 0), sweep(SolveOrder::AsGenerated, 0));
+        assert_eq!(sweep(SolveOrder::AlternateSweeps, 2), sweep(SolveOrder::AsGenerated, 2));
+        assert_ne!(sweep(SolveOrder::AlternateSweeps, 1), sweep(SolveOrder::AsGenerated, 1));
+    }
+
+    /* A TRIANGLE BLOWN UP TO TWICE ITS SIZE IS PULLED BACK TO ITS REST AREA BY REPEATED AREA PASSES, WITH A */
+    /* PINNED CORNER LEFT WHERE IT IS AND THE PARTICLES OF OTHER FACES UNTOUCHED. ONE AT REST IS NOT MOVED */
+    #[test]
+    fn area_passes_restore_the_rest_area() {
+        // PASSES GIVEN TO THE STRETCHED TRIANGLE
+        const PASSES: I = 20;
+
+        let mut particles = Particles::from_fn(2, 2, |(x, y)| P::new(x as F, 0., y as F));
+        let faces = [Face::new([0, 1, 2], &particles), Face::new([1, 3, 2], &particles)];
+        assert_eq!(area_pass(&mut particles, &faces, 1.), 0.);
+
+        let area = |particles: &Particles| {
+            let ([a, b, c], p) = (faces[0].v, &particles.data);
+            (p[b] - p[a]).cross(&(p[c] - p[a])).norm() / 2.
+        };
+        (0..4).for_each(|i| particles.place(i, particles.data[i] * 2., V::zeros()));
+        particles.set_fixed(1, true);
+        let (pinned, other) = (particles.data[1], particles.data[3]);
+        assert!(area_pass(&mut particles, &faces[..1], 1.) > 0.);
+        (1..PASSES).for_each(|_| {
+            area_pass(&mut particles, &faces[..1], 1.);
+        });
+        assert!((area(&particles) - faces[0].rest_area).abs() < 1e-4, "area {}", area(&particles));
+        assert_eq!((particles.data[1], particles.data[3]), (pinned, other));
+    }
+
+    /* A CLOTH BLOWN UP EVENLY SHRINKS BACK ONLY ALONG THE THREAD DIRECTION THAT HAS ANY STIFFNESS: ONE SWEEP */
+    /* WITH ONLY THE WARP STIFF PULLS THE ROWS IN AND LEAVES THE COLUMNS STRETCHED, AND THE OTHER WAY AROUND */
+    #[test]
+    fn warp_and_weft_stiffen_their_own_springs() {
+        // HOW FAR THE CLOTH IS BLOWN UP BEFORE THE SWEEP
+        const BLOW_UP: F = 1.5;
+
+        let swept = |stiffness| {
+            let config = ClothConfig {
+                pose: Pose::FlatHorizontal { height: 0. },
+                pin_preset: PinPreset::Free,
+                origin: V::zeros(),
+                jitter: 0.,
+                stiffness,
+                ..ClothConfig::default()
+            };
+            let mut cloth = ClothSim::new(3., 3., (7, 7), config).unwrap();
+            for i in 0..cloth.particles.data.len() {
+                let p = cloth.particles.data[i];
+                cloth.particles.place(i, P::new(p.x * BLOW_UP, p.y, p.z * BLOW_UP), V::zeros());
+            }
+            cloth.solve_gauss_seidel(0);
+            let length = |c: &Constraint| (cloth.particles.data[c.p2] - cloth.particles.data[c.p1]).norm();
+            let stretch = |weave| {
+                let structural = cloth.constraints.iter().filter(|c| c.kind == ConstraintKind::Structural);
+                let stretches: Vec<F> = structural.filter(|c| c.weave == weave).map(|c| length(c) / c.d).collect();
+                stretches.iter().sum::<F>() / stretches.len() as F
+            };
+            (stretch(Weave::Warp), stretch(Weave::Weft))
+        };
+        let only = |warp, weft| Stiffness { warp, weft, shear: 0., flexion: 0. };
+        let (warp, weft) = swept(only(1., 0.));
+        assert!(warp < BLOW_UP - 0.2 && (weft - BLOW_UP).abs() < 1e-4, "warp {} weft {}", warp, weft);
+        let (warp, weft) = swept(only(0., 1.));
+        assert!(weft < BLOW_UP - 0.2 && (warp - BLOW_UP).abs() < 1e-4, "warp {} weft {}", warp, weft);
+    }
+
+    /* THE TIME STEP IS CLAMPED TO max_dt. A PARTICLE GONE NaN ROLLS THE CLOTH BACK TO THE OLDEST GOOD STATE */
+    /* AT HALF THE TIME STEP, WHICH RETURNS TO FULL ONCE THE CLOTH STAYED STABLE FOR RECOVERY_STEPS CHECKS */
+    #[test]
+    fn blow_ups_roll_back_at_a_smaller_step() {
+        // TIME STEP ASKED FOR, AND THE LARGEST ONE ALLOWED
+        const DT: F = 1.;
+        const MAX_DT: F = 0.02;
+
+        let config = ClothConfig { dt: DT, max_dt: MAX_DT, pin_preset: PinPreset::TopRow, ..ClothConfig::default() };
+        let mut cloth = ClothSim::new(2., 2., (6, 6), config).unwrap();
+        assert_eq!(cloth.dt(), MAX_DT);
+        let oldest = cloth.particles.data.clone();
+        (0..RECOVERY_HISTORY).for_each(|_| cloth.step(&[]));
+        assert_ne!(cloth.particles.data, oldest);
+
+        let good = cloth.good_states.front().unwrap().0.clone();
+        cloth.particles.set_position(20, P::new(F::NAN, 0., 0.));
+        cloth.check_stability();
+        assert_eq!(cloth.stats.recoveries, 1);
+        assert_eq!(cloth.particles.data, good);
+        assert_eq!(cloth.dt(), MAX_DT / 2.);
+        (0..RECOVERY_STEPS).for_each(|_| cloth.check_stability());
+        assert_eq!(cloth.dt(), MAX_DT);
+    }
+
+    /* A CLOTH LYING STILL NEEDS ITS MESH REFILLED ONCE, THEN STAYS CLEAN THROUGH STEPS AND REPLAYS OF THE SAME */
+    /* FRAME UNTIL SOMETHING ACTUALLY MOVES IT */
+    #[test]
+    fn still_cloths_skip_mesh_updates() {
+        let config = ClothConfig {
+            pose: Pose::FlatHorizontal { height: 0. },
+            pin_preset: PinPreset::Free,
+            gravity: V::zeros(),
+            wind: Wind { strength: 0., ..Wind::default() },
+            jitter: 0.,
+            ..ClothConfig::default()
+        };
+        let mut cloth = ClothSim::new(2., 1., (5, 3), config).unwrap();
+        let mut mesh = cloth.build_mesh();
+        assert!(cloth.mesh_dirty());
+        cloth.update_mesh(&mut mesh);
+        assert!(!cloth.mesh_dirty());
+
+        (0..5).for_each(|_| cloth.step(&[]));
+        let mut frame = cloth.particles.data.clone();
+        cloth.set_positions(&frame).unwrap();
+        assert!(!cloth.mesh_dirty());
+
+        frame[7].y += 0.5;
+        cloth.set_positions(&frame).unwrap();
+        assert!(cloth.mesh_dirty());
+        cloth.update_mesh(&mut mesh);
+        cloth.step(&[]);
+        assert!(cloth.mesh_dirty(), "the lifted particle springs back");
+    }
+
+    /* A CLOTH THROWN FLAT ONTO A BOUNCY FLOOR LEAVES IT AGAIN WITH MOST OF ITS SPEED, ONE THROWN ONTO A DEAD */
+    /* FLOOR STAYS ON IT. A SLOW ENOUGH LANDING DOES NOT BOUNCE AT ALL */
+    #[test]
+    fn bouncy_floors_throw_cloths_back() {
+        // RESTITUTION OF THE BOUNCY FLOOR, AND THE DOWNWARD SPEED THE CLOTH IS THROWN AT
+        const RESTITUTION: F = 0.8;
+        const THROW: F = 4.;
+
+        let rebound = |restitution| {
+            let mut colliders = Colliders::default();
+            let floor = colliders.add(Collider::Plane { point: P::origin(), normal: V::y() });
+            colliders.set_restitution(floor, restitution);
+            let config = ClothConfig {
+                pose: Pose::FlatHorizontal { height: 0.5 },
+                pin_preset: PinPreset::Free,
+                origin: V::zeros(),
+                gravity: V::zeros(),
+                wind: Wind { strength: 0., ..Wind::default() },
+                contact_cache: false,
+                ..ClothConfig::default()
+            };
+            let mut cloth = ClothSim::new(1., 1., (4, 4), config).unwrap();
+            let dt = cloth.dt();
+            for i in 0..cloth.particles.len() {
+                let p = cloth.particles.data[i];
+                cloth.particles.place(i, p, V::y() * -THROW * dt);
+            }
+            (0..10).for_each(|_| cloth.step(&colliders.0));
+            let n = cloth.particles.len();
+            (0..n).map(|i| cloth.particles.velocity(i).y).sum::<F>() / n as F
+        };
+        let bounced = rebound(RESTITUTION);
+        assert!(bounced > THROW * RESTITUTION / 2., "left the floor at {}", bounced);
+        assert!(rebound(0.).abs() < 1e-3);
+        assert_eq!(particles::bounce(-THROW, RESTITUTION, THROW), 0.);
+    }
+
+    /* WIND INTO A FACE PUSHES IT BY (1 - porosity) OF THE SOLID FORCE, NOTHING FOR A NET, WHILE THE WIND */
+    /* ALONG IT DRAGS EVEN A NET ALONG BY air_drag. BOTH GROW WITH THE AREA OF THE FACE */
+    #[test]
+    fn porosity_lets_the_wind_through() {
+        // AREA OF THE FACE, AND THE DRAG OF THE AIR ALONG IT
+        const AREA: F = 0.5;
+        const AIR_DRAG: F = 0.1;
+
+        let (normal, gust) = (V::z(), V::new(3., 0., 2.));
+        let solid = wind_force(gust, normal, AREA, 0., 0.);
+        assert_eq!(solid, V::new(0., 0., 1.));
+        assert!((wind_force(gust, normal, AREA, 0.25, 0.) - solid * 0.75).norm() < 1e-6);
+        assert_eq!(wind_force(gust, normal, AREA, 1., 0.), V::zeros());
+        let net = wind_force(gust, normal, 2. * AREA, 1., AIR_DRAG);
+        assert!((net - V::x() * (3. * AIR_DRAG * 2. * AREA)).norm() < 1e-6, "net pulled by {}", net);
+        assert_eq!(wind_force(gust, -normal, AREA, 0., 0.), solid);
+    }
+
+    /* fix_all HOLDS EVERY PARTICLE THROUGH A STEP. clear_fixed LETS THEM ALL GO FROM REST, EVEN ONE DRAGGED */
+    /* FAR WHILE PINNED, SO IT IS NOT FLUNG BY ITS LAST JUMP */
+    #[test]
+    fn pins_are_released_from_rest() {
+        let mut cloth = ClothSim::new(2., 2., (5, 5), ClothConfig::default()).unwrap();
+        cloth.fix_all();
+        let held = cloth.particles.data.clone();
+        (0..3).for_each(|_| cloth.step(&[]));
+        assert_eq!(cloth.particles.data, held);
+
+        let dragged = cloth.particles[(4, 4)] + V::new(2., 0., 0.);
+        cloth.set_position((4, 4), dragged).unwrap();
+        let i = cloth.particles.flatten((4, 4));
+        assert!(cloth.particles.displacement(i).norm() > 1.);
+        cloth.clear_fixed();
+        assert!((0..cloth.particles.len()).all(|i| !cloth.particles.pinned(i)));
+        assert_eq!((cloth.particles.data[i], cloth.particles.displacement(i)), (dragged, V::zeros()));
+    }
+
+    /* A RIGID MOVE ABOUT THE UP AXIS COMMUTES WITH STEPPING: MOVING A SWINGING CLOTH, PINS AND VELOCITY */
+    /* INCLUDED, ENDS WHERE MOVING IT BEFORE THE SWING WOULD HAVE TAKEN IT, WITH ITS REST LENGTHS UNTOUCHED */
+    #[test]
+    fn rigid_moves_commute_with_steps() {
+        // STEPS BEFORE AND AFTER THE MOVE
+        const STEPS: I = 15;
+
+        let iso = Isometry3::new(V::new(3., -1., 2.), V::y() * 1.2);
+        let swinging = || {
+            let config = ClothConfig {
+                pose: Pose::Slanted { slope: 1. },
+                pin_preset: PinPreset::TopCorners,
+                wind: Wind { strength: 0., ..Wind::default() },
+                ..ClothConfig::default()
+            };
+            ClothSim::new(2., 2., (6, 6), config).unwrap()
+        };
+        let (mut early, mut late) = (swinging(), swinging());
+        early.transform(&iso);
+        (0..STEPS).for_each(|_| early.step(&[]));
+        (0..STEPS).for_each(|_| late.step(&[]));
+        let rest: Vec<F> = late.constraints.iter().map(|c| c.d).collect();
+        late.transform(&iso);
+        assert_eq!(late.constraints.iter().map(|c| c.d).collect::<Vec<F>>(), rest);
+        for _ in 0..STEPS {
+            early.step(&[]);
+            late.step(&[]);
+        }
+        for (p, q) in early.particles.iter().zip(late.particles.iter()) {
+            assert!((p - q).norm() < 1e-3, "{} moved to {}", p, q);
+        }
+    }
+
+    /* OVER-RELAXATION SCALES EVERY GAUSS-SEIDEL CORRECTION, AND WITH PER-KIND COUNTS AN ITERATION ONLY SWEEPS */
+    /* THE KINDS WHOSE COUNT IT HAS NOT RUN PAST */
+    #[test]
+    fn over_relaxation_and_kind_counts() {
+        // FRACTION OF THE GAP EACH END OF THE SPRING CLOSES WITHOUT OVER-RELAXATION
+        const FRACTION: F = 0.125;
+
+        let gap = |omega| {
+            let mut particles = Particles::from_fn(2, 1, |(x, _)| P::new(x as F, 0., 0.));
+            let mut spring = Constraint::new((0, 0), (1, 0), ConstraintKind::Structural, &particles);
+            particles.place(1, P::new(3., 0., 0.), V::zeros());
+            gauss_seidel_update(&mut particles, &mut spring, FRACTION, &Stiffness::default(), omega);
+            (particles.data[1] - particles.data[0]).norm() - spring.d
+        };
+        assert!((gap(1.) - 2. * (1. - 2. * FRACTION)).abs() < 1e-6);
+        assert!((gap(2.) - 2. * (1. - 4. * FRACTION)).abs() < 1e-6);
+
+        let counts = KindIterations { structural: 3, shear: 1, flexion: 0 };
+        let config = ClothConfig { kind_iterations: Some(counts), ..ClothConfig::default() };
+        let mut cloth = ClothSim::new(2., 2., (5, 5), config).unwrap();
+        for i in 0..cloth.particles.len() {
+            let p = cloth.particles.data[i];
+            cloth.particles.place(i, p * 1.3, V::zeros());
+        }
+        let swept = |cloth: &mut ClothSim, iter| {
+            cloth.constraints.iter_mut().for_each(|c| c.accumulated = V::zeros());
+            cloth.solve_gauss_seidel(iter);
+            let moved = |kind| cloth.constraints.iter().any(|c| c.kind == kind && c.accumulated != V::zeros());
+            [moved(ConstraintKind::Structural), moved(ConstraintKind::Shear), moved(ConstraintKind::Flexion)]
+        };
+        assert_eq!(swept(&mut cloth, 0), [true, true, false]);
+        assert_eq!(swept(&mut cloth, 2), [true, false, false]);
+        assert_eq!(swept(&mut cloth, 3), [false, false, false]);
+    }
+
+    /* A FLICKED PARTICLE MOVES AT MOST max PER STEP IN THE DIRECTION IT WAS FLICKED, AND A COLLISION PUSH IS */
+    /* PULLED BACK TO max FROM WHERE THE PARTICLE WAS INTEGRATED TO, KEEPING THE VELOCITY IT LEFT. */
+    /* ONLY THE PARTICLES PAST THE LIMIT ARE COUNTED AS CLAMPED */
+    #[test]
+    fn speeds_and_corrections_are_clamped() {
+        // LIMIT OF BOTH CLAMPS, AND THE FLICK AND PUSH PAST IT
+        const MAX: F = 0.1;
+        const FLICK: F = 2.;
+
+        let mut rope = ClothSim::new(4., 0.5, (8, 2), ClothConfig::default()).unwrap();
+        let (flicked, pushed) = (3, 12);
+        let flick = V::new(1., 2., -2.) / 3. * FLICK;
+        let p = rope.particles.data[flicked];
+        rope.particles.place(flicked, p, flick);
+        rope.clamp_speeds(MAX);
+        assert_eq!(rope.stats.clamped, 1);
+        assert!((rope.particles.displacement(flicked) - flick * (MAX / FLICK)).norm() < 1e-6);
+
+        rope.integrated = rope.particles.data.clone();
+        let (p, v) = (rope.particles.data[pushed], rope.particles.displacement(pushed));
+        rope.particles.place(pushed, p + V::y() * FLICK, v);
+        rope.particles.place(flicked, rope.particles.data[flicked] + V::y() * MAX / 2., V::zeros());
+        rope.clamp_corrections(MAX);
+        assert_eq!(rope.stats.clamped, 2);
+        assert!((rope.particles.data[pushed] - (p + V::y() * MAX)).norm() < 1e-6);
+        assert!((rope.particles.displacement(pushed) - v).norm() < 1e-6);
+    }
+
+    /* THE NEAREST-PARTICLE, SPHERE AND BOX QUERIES AGREE WITH A SCAN OF EVERY PARTICLE, BOTH THROUGH THE */
+    /* BROAD PHASE OF THE LAST STEP AND ONCE A RIGID MOVE LEFT THEM TO SCAN THEMSELVES */
+    #[test]
+    fn queries_match_a_full_scan() {
+        // RADIUS OF THE SPHERE QUERIES, AND HALF THE SIZE OF THE BOX QUERIES
+        const RADIUS: F = 0.6;
+        const HALF: F = 0.4;
+
+        let config = ClothConfig { pose: Pose::Slanted { slope: 0.5 }, ..ClothConfig::default() };
+        let mut cloth = ClothSim::new(3., 2., (12, 8), config).unwrap();
+        (0..8).for_each(|_| cloth.step(&[]));
+        let probes = [V::new(0.5, -0.5, 0.2), V::new(2.5, -1.8, 1.), V::new(-4., 3., 0.)];
+        let check = |cloth: &ClothSim| {
+            let world: Vec<P> = cloth.particles.iter().map(|&p| cloth.to_world(p)).collect();
+            let idx = |i: I| cloth.particles.unflatten(i);
+            for &probe in &probes {
+                let q = P::from(probe) + cloth.world_position((0, 0)).coords;
+                let nearest = world.iter().map(|p| (p - q).norm()).fold(F::INFINITY, F::min);
+                let (found, d) = cloth.nearest_particle(q).unwrap();
+                assert!((d - nearest).abs() < 1e-6 && (cloth.world_position(found) - q).norm() - d < 1e-6);
+
+                let within: Vec<_> = (0..world.len()).filter(|&i| (world[i] - q).norm() <= RADIUS).map(idx).collect();
+                assert_eq!(cloth.particles_within_sphere(q, RADIUS), within);
+                let (min, max) = (q - V::repeat(HALF), q + V::repeat(HALF));
+                let inside = |p: &P| (0..3).all(|k| min[k] <= p[k] && p[k] <= max[k]);
+                let boxed: Vec<_> = (0..world.len()).filter(|&i| inside(&world[i])).map(idx).collect();
+                assert_eq!(cloth.particles_in_aabb(min, max), boxed);
+            }
+        };
+        assert!(cloth.broad_phase_slack().is_some());
+        check(&cloth);
+        cloth.translate(V::new(1., 0., -3.));
+        assert!(cloth.broad_phase_slack().is_none());
+        check(&cloth);
+    }
+
+    /* A CLOTH DROPPED THROUGH THE KILL PLANE IS CAUGHT ON IT, EACH PARTICLE REPORTED AS IT CROSSES. UNDER */
+    /* Respawn IT IS RESET TO WHERE IT STARTED ONCE MORE THAN THE FRACTION IS DOWN */
+    #[test]
+    fn kill_plane_catches_and_respawns() {
+        // HEIGHT OF THE KILL PLANE, AND THE STEPS GIVEN TO THE CLOTH TO FALL THROUGH IT
+        const FLOOR: F = -1.;
+        const STEPS: I = 40;
+
+        let dropped = |kill_mode| {
+            let config = ClothConfig {
+                pose: Pose::FlatHorizontal { height: 0. },
+                pin_preset: PinPreset::Free,
+                origin: V::zeros(),
+                gravity: V::new(0., -5., 0.),
+                kill_plane: Some(FLOOR),
+                kill_mode,
+                ..ClothConfig::default()
+            };
+            ClothSim::new(1.5, 1., (6, 4), config).unwrap()
+        };
+
+        let mut caught = dropped(KillMode::Clamp);
+        let mut crossings = 0;
+        for _ in 0..STEPS {
+            caught.step(&[]);
+            for event in caught.drain_events() {
+                match event {
+                    ClothEvent::KillPlane { crossed } => crossings += crossed,
+                    ClothEvent::Respawned => panic!("clamped cloths are not respawned"),
+                    _ => {}
+                }
+            }
+        }
+        assert!(crossings >= caught.particles.len());
+        assert!(caught.particles.iter().all(|p| (p.y - FLOOR).abs() < 1e-4));
+
+        let mut respawning = dropped(KillMode::Respawn { fraction: 0.5 });
+        let start = respawning.particles.data.clone();
+        let respawned = (0..STEPS).any(|_| {
+            respawning.step(&[]);
+            respawning.drain_events().any(|event| matches!(event, ClothEvent::Respawned))
+        });
+        assert!(respawned);
+        assert_eq!(respawning.particles.data, start);
+    }
+
+    /* A FROZEN REGION OF A SWINGING CLOTH KEEPS ITS POSITIONS BIT FOR BIT WHILE THE REST MOVES ON, AND IS */
+    /* RELEASED AT REST. THE PINS INSIDE IT ARE STILL PINS AFTERWARDS, THE FREE PARTICLES STILL FREE */
+    #[test]
+    fn frozen_regions_hold_apart_from_the_pins() {
+        // PARTICLES ALONG EACH SIDE OF THE CLOTH
+        const N: I = 8;
+
+        let config = ClothConfig { pin_preset: PinPreset::LeftColumn, ..ClothConfig::default() };
+        let mut cloth = ClothSim::new(2., 2., (N, N), config).unwrap();
+        (0..5).for_each(|_| cloth.step(&[]));
+        cloth.freeze_region(|(x, y), _| y >= N / 2 || x == 0);
+        assert!(cloth.frozen());
+        let frozen: Vec<I> = (0..cloth.particles.len()).filter(|&i| cloth.particles.frozen(i)).collect();
+        assert_eq!(frozen.len(), N * N / 2 + N / 2);
+        let positions = |cloth: &ClothSim| frozen.iter().map(|&i| cloth.particles.data[i]).collect::<Vec<P>>();
+        let (held, before) = (positions(&cloth), cloth.particles.data.clone());
+
+        (0..5).for_each(|_| cloth.step(&[]));
+        assert_eq!(positions(&cloth), held);
+        assert_ne!(cloth.particles.data, before);
+
+        cloth.unfreeze_all();
+        assert!(!cloth.frozen());
+        for i in 0..cloth.particles.len() {
+            assert_eq!(cloth.particles.fixed(i), i % N == 0, "particle {}", i);
+            assert!(!frozen.contains(&i) || cloth.particles.displacement(i) == V::zeros());
+        }
+    }
+
+    /* EASING THE REST LENGTHS TO HALF OVER FOUR STEPS TAKES EVEN STEPS THERE AND STOPS, WHILE rest_factor */
+    /* ALREADY REPORTS THE TARGET. A FILTERED SCALING ONLY TOUCHES THE CONSTRAINTS IT PICKS */
+    #[test]
+    fn rest_lengths_ease_to_their_target() {
+        // LENGTH OF THE CUSTOM SPRING THE SCALING IS READ OFF
+        const REST: F = 2.;
+
+        let mut cloth = free_cloth((6, 6));
+        let spring = cloth.add_constraint((0, 0), (5, 5), Some(REST), ConstraintKind::Flexion);
+        cloth.animate_rest_scale(0.5, 4);
+        assert_eq!(cloth.rest_factor(), 0.5);
+        let mut eased = vec![];
+        for _ in 0..6 {
+            cloth.animate_rest_lengths();
+            eased.push(cloth.rest_length(spring).unwrap() / REST);
+        }
+        let expected = [0.875, 0.75, 0.625, 0.5, 0.5, 0.5];
+        assert!(eased.iter().zip(&expected).all(|(a, b)| (a - b).abs() < 1e-6), "eased through {:?}", eased);
+        assert_eq!(cloth.rest_factor, cloth.rest_factor());
+
+        let others = |cloth: &ClothSim| {
+            let springs = cloth.constraints.iter().filter(|c| c.kind != ConstraintKind::Flexion);
+            springs.map(|c| c.d).collect::<Vec<F>>()
+        };
+        let before = others(&cloth);
+        cloth.scale_rest_lengths_where(3., |kind, (x, _), _| kind == ConstraintKind::Flexion && x == 0);
+        assert!((cloth.rest_length(spring).unwrap() - 1.5 * REST).abs() < 1e-6);
+        assert_eq!(others(&cloth), before);
     }
 }
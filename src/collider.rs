@@ -0,0 +1,562 @@
+use std::{cmp::Ordering::Equal, collections::HashMap, fs, io, path::Path};
+
+use bevy::render::{
+    mesh::{Indices, Mesh},
+    pipeline::PrimitiveTopology,
+};
+use nalgebra::Isometry3;
+
+use crate::{
+    bvh::{Aabb, Bvh},
+    to_array, F, I, P, V,
+};
+
+/* OBSTACLES THE CLOTH IS PUSHED OUT OF, STATIC UNLESS A SPHERE IS GIVEN A Body */
+
+#[derive(Clone)]
+pub enum Collider {
+    Plane { point: P, normal: V },
+    Sphere { center: P, radius: F },
+    /* EVERY POINT WITHIN radius OF THE SEGMENT FROM a TO b, E.G. A LIMB OR A TORSO */
+    Capsule { a: P, b: P, radius: F },
+    Box { min: P, max: P },
+    TriMesh(TriMesh),
+}
+
+// LAYER OF COLLIDERS AND CLOTHS THAT DO NOT PICK ONE, AND A MASK THAT MEETS EVERY LAYER
+pub const DEFAULT_LAYER: u32 = 1;
+pub const ALL_LAYERS: u32 = !0;
+
+/* WHETHER SOMETHING WITH THIS MASK COLLIDES WITH SOMETHING ON THIS LAYER */
+pub fn meets(mask: u32, layer: u32) -> bool { mask & layer != 0 }
+
+/* A COLLIDER ONLY PUSHES CLOTHS WHOSE collision_mask SHARES A BIT WITH ITS LAYER */
+/* ITS FRICTION COEFFICIENT OVERRIDES THE friction OF THE CLOTHS TOUCHING IT */
+/* ITS RESTITUTION IS THE FRACTION OF THE NORMAL SPEED KEPT ON IMPACT: 0 DOES NOT BOUNCE, 1 IS ELASTIC */
+#[derive(Clone)]
+pub struct LayeredCollider {
+    pub collider: Collider,
+    pub layer: u32,
+    pub friction: Option<F>,
+    pub restitution: F,
+    pub body: Option<Body>,
+}
+
+/* MASS AND VELOCITY OF A DYNAMIC SPHERE, WHICH props::advance MOVES BY GRAVITY AND BY THE IMPULSES */
+/* OF THE CLOTHS PUSHED OUT OF IT. impact IS THE FASTEST APPROACH OF THE CURRENT CONTACT, NO REBOUND */
+/* FROM IT MAY BE FASTER, AND 0 WHILE NOTHING TOUCHES THE SPHERE. A KINEMATIC BODY IS MOVED BY ITS */
+/* OWNER INSTEAD, WHICH KEEPS ITS velocity UP TO DATE */
+#[derive(Clone, Copy, Debug)]
+pub struct Body {
+    pub mass: F,
+    pub velocity: V,
+    pub impact: F,
+    pub kinematic: bool,
+}
+
+impl LayeredCollider {
+    /* THE ONE PLACE THE VELOCITY OF A COLLIDER COMES FROM, ZERO WITHOUT A BODY */
+    pub fn velocity(&self) -> V { self.body.map_or_else(V::zeros, |b| b.velocity) }
+}
+
+/* INDEX OF A COLLIDER IN Colliders */
+pub type ColliderId = I;
+
+/* ALL COLLIDERS IN THE SCENE, SHARED BY EVERY CLOTH */
+#[derive(Default)]
+pub struct Colliders(pub Vec<LayeredCollider>);
+
+impl Colliders {
+    pub fn add(&mut self, collider: Collider) -> ColliderId { self.add_on_layer(collider, DEFAULT_LAYER) }
+
+    pub fn add_on_layer(&mut self, collider: Collider, layer: u32) -> ColliderId {
+        self.0.push(LayeredCollider { collider, layer, friction: None, restitution: 0., body: None });
+        self.0.len() - 1
+    }
+
+    pub fn set_friction(&mut self, id: ColliderId, friction: F) {
+        if let Some(layered) = self.0.get_mut(id) {
+            layered.friction = Some(friction);
+        }
+    }
+
+    pub fn set_restitution(&mut self, id: ColliderId, restitution: F) {
+        if let Some(layered) = self.0.get_mut(id) {
+            layered.restitution = restitution.max(0.).min(1.);
+        }
+    }
+
+    /* MAKE A SPHERE DYNAMIC, STARTING AT REST. OTHER SHAPES STAY STATIC */
+    pub fn set_mass(&mut self, id: ColliderId, mass: F) {
+        if let Some(layered) = self.0.get_mut(id).filter(|l| matches!(l.collider, Collider::Sphere { .. })) {
+            layered.body = Some(Body { mass, velocity: V::zeros(), impact: 0., kinematic: false });
+        }
+    }
+
+    /* GIVE A COLLIDER MOVED BY ITS OWNER THE VELOCITY IT IS MOVED AT, SO FRICTION DRAGS THE CLOTHS ALONG */
+    pub fn set_kinematic(&mut self, id: ColliderId, velocity: V) {
+        if let Some(layered) = self.0.get_mut(id) {
+            layered.body = Some(Body { mass: F::INFINITY, velocity, impact: 0., kinematic: true });
+        }
+    }
+
+    /* FIRST UPWARD-FACING PLANE, THE FLOOR THAT floor_height MEASURES */
+    pub fn floor(&self) -> Option<ColliderId> {
+        self.0.iter().position(|c| match c.collider {
+            Collider::Plane { normal, .. } => normal.y > 1. - F::EPSILON.sqrt(),
+            _ => false,
+        })
+    }
+
+    /* HEIGHT OF THE FIRST UPWARD-FACING PLANE, OTHERWISE THE LOWEST POINT OF ANY BOUNDED COLLIDER */
+    pub fn floor_height(&self) -> Option<F> {
+        let plane = self.0.iter().find_map(|c| match c.collider {
+            Collider::Plane { point, normal } if normal.y > 1. - F::EPSILON.sqrt() => Some(point.y),
+            _ => None,
+        });
+        plane.or_else(|| {
+            self.0
+                .iter()
+                .filter_map(|c| c.collider.bounds())
+                .map(|b| b.min.y)
+                .min_by(|a, b| a.partial_cmp(b).unwrap_or(Equal))
+        })
+    }
+}
+
+impl Collider {
+    /* PLANES ARE UNBOUNDED */
+    pub fn bounds(&self) -> Option<Aabb> {
+        match self {
+            Collider::Plane { .. } => None,
+            Collider::Sphere { center, radius } => Some(Aabb::around(*center, *radius)),
+            Collider::Capsule { a, b, radius } => Some(Aabb::around(*a, *radius).merge(&Aabb::around(*b, *radius))),
+            Collider::Box { min, max } => Some(Aabb { min: *min, max: *max }),
+            Collider::TriMesh(mesh) => Some(Aabb::from_points(&mesh.vertices)),
+        }
+    }
+
+    /* TELEPORT THE COLLIDER, A TRIANGLE MESH REBUILDS ITS BVH */
+    pub fn translate(&mut self, v: V) {
+        match self {
+            Collider::Plane { point, .. } => *point += v,
+            Collider::Sphere { center, .. } => *center += v,
+            Collider::Capsule { a, b, .. } => {
+                *a += v;
+                *b += v;
+            }
+            Collider::Box { min, max } => {
+                *min += v;
+                *max += v;
+            }
+            Collider::TriMesh(mesh) => *mesh = mesh.clone().translated(v),
+        }
+    }
+
+    /* THE SAME SHAPE AFTER A RIGID MOVE. A BOX STAYS ALIGNED WITH THE AXES AND A TRIANGLE MESH */
+    /* IS NOT REBUILT, SO BOTH ONLY TAKE THE TRANSLATION */
+    pub fn transformed(&self, iso: &Isometry3<F>) -> Collider {
+        match self {
+            Collider::Plane { point, normal } => Collider::Plane { point: iso * point, normal: iso * normal },
+            Collider::Sphere { center, radius } => Collider::Sphere { center: iso * center, radius: *radius },
+            Collider::Capsule { a, b, radius } => Collider::Capsule { a: iso * a, b: iso * b, radius: *radius },
+            Collider::Box { .. } | Collider::TriMesh(_) => {
+                let mut moved = self.clone();
+                moved.translate(iso.translation.vector);
+                moved
+            }
+        }
+    }
+
+    /* OFFSET NEEDED TO MOVE p OUTSIDE THE COLLIDER BY AT LEAST margin, IF ANY */
+    pub fn push_out(&self, p: P, margin: F) -> Option<V> {
+        match self {
+            Collider::Plane { point, normal } => {
+                let h = (p - point).dot(normal);
+                if h < margin {
+                    Some(normal * (margin - h))
+                } else {
+                    None
+                }
+            }
+            Collider::Sphere { center, radius } => {
+                let diff = p - center;
+                let d = diff.norm();
+                if d < radius + margin && d > 0. {
+                    Some(diff * ((radius + margin) / d - 1.))
+                } else {
+                    None
+                }
+            }
+            Collider::Capsule { a, b, radius } => {
+                Collider::Sphere { center: closest_on_segment(p, *a, *b), radius: *radius }.push_out(p, margin)
+            }
+            Collider::Box { min, max } => {
+                let inside = (0..3).all(|i| p[i] > min[i] - margin && p[i] < max[i] + margin);
+                if !inside {
+                    return None;
+                }
+                /* PUSH OUT ALONG THE AXIS OF LEAST PENETRATION */
+                (0..3)
+                    .flat_map(|i| {
+                        vec![
+                            (i, min[i] - margin - p[i]),
+                            (i, max[i] + margin - p[i]),
+                        ]
+                    })
+                    .min_by(|(_, a), (_, b)| a.abs().partial_cmp(&b.abs()).unwrap_or(Equal))
+                    .map(|(i, d)| {
+                        let mut v = V::zeros();
+                        v[i] = d;
+                        v
+                    })
+            }
+            Collider::TriMesh(mesh) => mesh.push_out(p, margin),
+        }
+    }
+
+    /* CONTINUOUS TEST OF THE SEGMENT MOVED ALONG DURING A STEP AGAINST THE MARGIN SHELL */
+    /* RETURNS THE FIRST POINT OF CONTACT AND THE SURFACE NORMAL THERE */
+    pub fn sweep(&self, from: P, to: P, margin: F) -> Option<(P, V)> {
+        let d = to - from;
+        match self {
+            Collider::Plane { point, normal } => {
+                let (h0, h1) = ((from - point).dot(normal), (to - point).dot(normal));
+                if h0 >= margin && h1 < margin {
+                    let t = (h0 - margin) / (h0 - h1);
+                    Some((from + d * t, *normal))
+                } else {
+                    None
+                }
+            }
+            Collider::Sphere { center, radius } => {
+                let r = radius + margin;
+                let m = from - center;
+                let (a, b, c) = (d.dot(&d), m.dot(&d), m.dot(&m) - r * r);
+                /* STARTING INSIDE IS LEFT TO THE DISCRETE TEST */
+                if c < 0. || a == 0. {
+                    return None;
+                }
+                let disc = b * b - a * c;
+                if disc < 0. {
+                    return None;
+                }
+                let t = (-b - disc.sqrt()) / a;
+                if t < 0. || t > 1. {
+                    return None;
+                }
+                let hit = from + d * t;
+                Some((hit, (hit - center) / r))
+            }
+            /* AGAINST THE SPHERE AROUND THE POINT OF THE AXIS NEAREST THE END OF THE MOVE, WHICH IS EXACT */
+            /* FOR MOVES ACROSS THE AXIS AND CLOSE ENOUGH FOR THE SHORT MOVES OF A STEP ALONG IT */
+            Collider::Capsule { a, b, radius } => {
+                Collider::Sphere { center: closest_on_segment(to, *a, *b), radius: *radius }.sweep(from, to, margin)
+            }
+            Collider::Box { min, max } => {
+                /* SLAB TEST AGAINST THE BOX EXPANDED BY THE MARGIN */
+                let (mut t_enter, mut t_exit, mut axis) = (0., 1., None);
+                for i in 0..3 {
+                    let (lo, hi) = (min[i] - margin, max[i] + margin);
+                    if d[i].abs() < F::EPSILON {
+                        if from[i] < lo || from[i] > hi {
+                            return None;
+                        }
+                        continue;
+                    }
+                    let (t0, t1) = ((lo - from[i]) / d[i], (hi - from[i]) / d[i]);
+                    let (t0, t1, sign) = if t0 < t1 { (t0, t1, -1.) } else { (t1, t0, 1.) };
+                    if t0 > t_enter {
+                        t_enter = t0;
+                        axis = Some((i, sign));
+                    }
+                    t_exit = t1.min(t_exit);
+                    if t_enter > t_exit {
+                        return None;
+                    }
+                }
+                /* NO ENTERING AXIS MEANS THE SEGMENT STARTED INSIDE */
+                axis.map(|(i, sign)| {
+                    let mut n = V::zeros();
+                    n[i] = sign;
+                    (from + d * t_enter, n)
+                })
+            }
+            Collider::TriMesh(_) => None,
+        }
+    }
+}
+
+/* POINT OF THE SEGMENT FROM a TO b NEAREST p */
+fn closest_on_segment(p: P, a: P, b: P) -> P {
+    let ab = b - a;
+    let len2 = ab.norm_squared();
+    if len2 <= F::EPSILON {
+        return a;
+    }
+    a + ab * ((p - a).dot(&ab) / len2).max(0.).min(1.)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Feature {
+    Face,
+    /* EDGES ARE NUMBERED AB, BC, CA */
+    Edge(I),
+    Vertex(I),
+}
+
+/* ARBITRARY TRIANGLE MESH, WITH A BVH AND PSEUDO-NORMALS PRECOMPUTED AT LOAD TIME */
+#[derive(Clone)]
+pub struct TriMesh {
+    vertices: Vec<P>,
+    triangles: Vec<[I; 3]>,
+    face_normals: Vec<V>,
+    edge_normals: Vec<[V; 3]>,
+    vertex_normals: Vec<V>,
+    bvh: Bvh,
+}
+
+impl TriMesh {
+    pub fn new(vertices: Vec<P>, triangles: Vec<[I; 3]>) -> Self {
+        let face_normals: Vec<V> = triangles
+            .iter()
+            .map(|&[a, b, c]| {
+                (vertices[b] - vertices[a])
+                    .cross(&(vertices[c] - vertices[a]))
+                    .try_normalize(F::EPSILON)
+                    .unwrap_or_else(V::zeros)
+            })
+            .collect();
+
+        /* ANGLE-WEIGHTED VERTEX NORMALS SO THAT CONTACTS AT VERTICES DO NOT POP */
+        let mut vertex_normals = vec![V::zeros(); vertices.len()];
+        for (t, n) in triangles.iter().zip(&face_normals) {
+            for k in 0..3 {
+                let (v0, v1, v2) = (vertices[t[k]], vertices[t[(k + 1) % 3]], vertices[t[(k + 2) % 3]]);
+                vertex_normals[t[k]] += n * (v1 - v0).angle(&(v2 - v0));
+            }
+        }
+        vertex_normals.iter_mut().for_each(|n| *n = n.try_normalize(F::EPSILON).unwrap_or_else(V::zeros));
+
+        /* EDGE NORMALS AVERAGE THE NORMALS OF THE FACES SHARING THE EDGE */
+        let key = |a: I, b: I| (a.min(b), a.max(b));
+        let mut edges: HashMap<(I, I), V> = HashMap::new();
+        for (t, n) in triangles.iter().zip(&face_normals) {
+            for k in 0..3 {
+                *edges.entry(key(t[k], t[(k + 1) % 3])).or_insert_with(V::zeros) += n;
+            }
+        }
+        let edge_normals = triangles
+            .iter()
+            .map(|t| {
+                let normal = |k: I| {
+                    edges[&key(t[k], t[(k + 1) % 3])]
+                        .try_normalize(F::EPSILON)
+                        .unwrap_or_else(V::zeros)
+                };
+                [normal(0), normal(1), normal(2)]
+            })
+            .collect();
+
+        let boxes: Vec<Aabb> = triangles
+            .iter()
+            .map(|&[a, b, c]| Aabb::from_points(&[vertices[a], vertices[b], vertices[c]]))
+            .collect();
+        let mut bvh = Bvh::default();
+        bvh.build(&boxes);
+
+        Self {
+            vertices,
+            triangles,
+            face_normals,
+            edge_normals,
+            vertex_normals,
+            bvh,
+        }
+    }
+
+    /* THE SAME MESH MOVED BY v, ITS BVH AND NORMALS ARE REBUILT */
+    pub fn translated(self, v: V) -> Self {
+        TriMesh::new(self.vertices.into_iter().map(|p| p + v).collect(), self.triangles)
+    }
+
+    /* MINIMAL OBJ SUPPORT: VERTEX POSITIONS AND POLYGONAL FACES, TRIANGULATED AS FANS */
+    pub fn load_obj(path: impl AsRef<Path>) -> io::Result<Self> {
+        let invalid = |line: &str| io::Error::new(io::ErrorKind::InvalidData, format!("bad obj line: {}", line));
+        let mut vertices = vec![];
+        let mut triangles = vec![];
+
+        for line in fs::read_to_string(path)?.lines() {
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("v") => {
+                    let c: Vec<F> = tokens.take(3).filter_map(|t| t.parse().ok()).collect();
+                    if c.len() != 3 {
+                        return Err(invalid(line));
+                    }
+                    vertices.push(P::new(c[0], c[1], c[2]));
+                }
+                Some("f") => {
+                    let face = tokens
+                        .map(|t| t.split('/').next().and_then(|i| i.parse::<I>().ok()))
+                        .map(|i| i.filter(|&i| i >= 1 && i <= vertices.len()).map(|i| i - 1))
+                        .collect::<Option<Vec<I>>>()
+                        .filter(|f| f.len() >= 3)
+                        .ok_or_else(|| invalid(line))?;
+                    for k in 1..face.len() - 1 {
+                        triangles.push([face[0], face[k], face[k + 1]]);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self::new(vertices, triangles))
+    }
+
+    fn push_out(&self, p: P, margin: F) -> Option<V> {
+        /* CLOSEST SURFACE POINT WITHIN THE MARGIN, WITH THE PSEUDO-NORMAL OF ITS FEATURE */
+        let mut best: Option<(F, P, V)> = None;
+        self.bvh.query(&Aabb::around(p, margin), &mut |t| {
+            let [a, b, c] = self.triangles[t];
+            let (q, feature) = closest_point(p, self.vertices[a], self.vertices[b], self.vertices[c]);
+            let d = (p - q).norm_squared();
+            if best.map_or(true, |(bd, _, _)| d < bd) {
+                let n = match feature {
+                    Feature::Face => self.face_normals[t],
+                    Feature::Edge(k) => self.edge_normals[t][k],
+                    Feature::Vertex(k) => self.vertex_normals[self.triangles[t][k]],
+                };
+                best = Some((d, q, n));
+            }
+        });
+
+        let (_, q, n) = best?;
+        let h = (p - q).dot(&n);
+        if h < margin {
+            Some(n * (margin - h))
+        } else {
+            None
+        }
+    }
+
+    /* RENDER MESH WITH FLAT SHADED FACES */
+    pub fn to_mesh(&self) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        let positions: Vec<[f32; 3]> = self
+            .triangles
+            .iter()
+            .flat_map(|t| t.iter().map(|&i| to_array(self.vertices[i])).collect::<Vec<_>>())
+            .collect();
+        let normals: Vec<[f32; 3]> = self
+            .face_normals
+            .iter()
+            .flat_map(|n| vec![to_array(P::from(*n)); 3])
+            .collect();
+        let n = positions.len();
+        mesh.set_indices(Some(Indices::U32((0..n as u32).collect())));
+        mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions.into());
+        mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals.into());
+        mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0., 0.]; n].into());
+        mesh
+    }
+}
+
+/* CLOSEST POINT ON TRIANGLE abc TO p, FROM REAL-TIME COLLISION DETECTION (ERICSON) */
+fn closest_point(p: P, a: P, b: P, c: P) -> (P, Feature) {
+    let (ab, ac, ap) = (b - a, c - a, p - a);
+    let (d1, d2) = (ab.dot(&ap), ac.dot(&ap));
+    if d1 <= 0. && d2 <= 0. {
+        return (a, Feature::Vertex(0));
+    }
+
+    let bp = p - b;
+    let (d3, d4) = (ab.dot(&bp), ac.dot(&bp));
+    if d3 >= 0. && d4 <= d3 {
+        return (b, Feature::Vertex(1));
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0. && d1 >= 0. && d3 <= 0. {
+        return (a + ab * (d1 / (d1 - d3)), Feature::Edge(0));
+    }
+
+    let cp = p - c;
+    let (d5, d6) = (ab.dot(&cp), ac.dot(&cp));
+    if d6 >= 0. && d5 <= d6 {
+        return (c, Feature::Vertex(2));
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0. && d2 >= 0. && d6 <= 0. {
+        return (a + ac * (d2 / (d2 - d6)), Feature::Edge(2));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0. && d4 - d3 >= 0. && d5 - d6 >= 0. {
+        let w = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+        return (b + (c - b) * w, Feature::Edge(1));
+    }
+
+    let denom = 1. / (va + vb + vc);
+    (a + ab * (vb * denom) + ac * (vc * denom), Feature::Face)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cloth::ClothSim,
+        config::{ClothConfig, PinPreset, Pose},
+    };
+
+    // MARGIN OF THE SWEPT TESTS, AND HOW FAR A PARTICLE TUNNELING THROUGH A COLLIDER MOVES IN ONE STEP
+    const MARGIN: F = 0.1;
+    const LEAP: F = 10.;
+
+    /* A PARTICLE LEAPING THROUGH A COLLIDER IN ONE STEP IS STOPPED ON THE MARGIN SHELL WHERE IT FIRST */
+    /* TOUCHES, FACING OUT. ONE STARTING INSIDE OR PASSING BY IS LEFT TO THE DISCRETE TEST */
+    #[test]
+    fn sweeps_stop_tunneling() {
+        let from = P::new(0., 5., 0.);
+        let colliders = [
+            (Collider::Plane { point: P::origin(), normal: V::y() }, 0.),
+            (Collider::Sphere { center: P::origin(), radius: 1. }, 1.),
+            (Collider::Box { min: P::new(-1., -2., -1.), max: P::new(1., 0.5, 1.) }, 0.5),
+        ];
+        for &(ref collider, top) in &colliders {
+            let (hit, normal) = collider.sweep(from, from - V::y() * LEAP, MARGIN).expect("the leap passes through");
+            assert!((hit - P::new(0., top + MARGIN, 0.)).norm() < 1e-4, "hit at {}", hit);
+            assert!((normal - V::y()).norm() < 1e-4, "normal {}", normal);
+            assert_eq!(collider.sweep(P::new(0., top, 0.), P::new(0., -LEAP, 0.), MARGIN), None);
+            assert_eq!(collider.sweep(from, from + V::x() * LEAP, MARGIN), None);
+        }
+    }
+
+    /* A FLOOR ONLY HOLDS UP THE CLOTHS WHOSE MASK SHARES A BIT WITH ITS LAYER, THE OTHERS FALL THROUGH IT */
+    #[test]
+    fn layers_pick_what_collides() {
+        // LAYER OF THE FLOOR, AND THE STEPS GIVEN TO THE CLOTHS TO LAND ON IT
+        const FLOOR: u32 = 0b100;
+        const STEPS: I = 60;
+
+        assert!(meets(ALL_LAYERS, FLOOR) && meets(FLOOR | DEFAULT_LAYER, FLOOR) && !meets(DEFAULT_LAYER, FLOOR));
+        let mut colliders = Colliders::default();
+        colliders.add_on_layer(Collider::Plane { point: P::origin(), normal: V::y() }, FLOOR);
+        let lowest = |collision_mask| {
+            let config = ClothConfig {
+                pose: Pose::FlatHorizontal { height: 1. },
+                pin_preset: PinPreset::Free,
+                gravity: V::new(0., -10., 0.),
+                origin: V::zeros(),
+                collision_mask,
+                ..ClothConfig::default()
+            };
+            let mut cloth = ClothSim::new(2., 2., (6, 6), config).unwrap();
+            (0..STEPS).for_each(|_| cloth.step(&colliders.0));
+            cloth.bounding_box().min.y
+        };
+        assert!(lowest(ALL_LAYERS) > -MARGIN, "fell through the floor it meets");
+        assert!(lowest(DEFAULT_LAYER) < -1., "held up by a floor it does not meet");
+    }
+}
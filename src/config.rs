@@ -0,0 +1,841 @@
+use std::{cmp::Ordering, str::FromStr};
+
+use serde::Deserialize;
+
+/* LIVES WITH THE PARTICLES SO THE BENCHMARKS CAN PULL THEM IN WITHOUT THE REST OF THE CONFIG */
+pub use crate::particles::Integrator;
+
+use crate::{
+    cloth::{ConstraintKind, Weave},
+    collider::{ALL_LAYERS, DEFAULT_LAYER},
+    fabric::FabricPreset,
+    grid::GridIdx,
+    F, I, P, V,
+};
+
+/* TUNABLE PARAMETERS OF THE SIMULATION */
+
+// SEPARATION OF THE TWO SHEETS OF A PILLOW BEFORE IT INFLATES
+pub const PILLOW_GAP: F = 0.5;
+
+// HEIGHT BELOW WHICH A LOOSE PIECE OF CLOTH IS FROZEN FOR GOOD, WELL UNDER THE STAIRS
+const KILL_HEIGHT: F = -100.;
+
+// TIME-STEP SIZE
+const DT: F = 0.05;
+
+// LARGEST TIME STEP ACTUALLY TAKEN, WHATEVER dt IS SET TO, SINCE VERLET BLOWS UP WELL BEFORE dt = 1
+const MAX_DT: F = 0.1;
+
+// ENERGY DAMPING TO APPLY TO SYSTEM WHEN PERFORMING VERLET POSITION INTEGRATION
+const DAMPING: F = 0.995;
+
+// BOUNDS ON THE NUMBER OF STEPS IN ITERATIVE CONSTRAINT SOLVING
+const MIN_ITERATIONS: I = 1;
+const MAX_ITERATIONS: I = 10;
+
+// PASSES OVER THE COARSE LEVEL OF THE HIERARCHICAL SOLVER BEFORE THE FINE ITERATIONS
+pub const COARSE_ITERATIONS: I = 4;
+
+// STOP ITERATING ONCE THE LARGEST CORRECTION OF A PASS FALLS BELOW THIS
+const TOLERANCE: F = 1e-4;
+
+// OVER-RELAXATION OF THE AVERAGED JACOBI CORRECTIONS, RECOVERING SOME OF THE LOST CONVERGENCE
+const RELAXATION: F = 1.5;
+
+// SCALE OF EACH GAUSS-SEIDEL CORRECTION, 1 IS PLAIN GAUSS-SEIDEL
+const OVER_RELAXATION: F = 1.;
+
+// BASE WIND: DIRECTION, SPEED AND AMPLITUDE OF THE TURBULENT GUSTS ON TOP OF IT
+const WIND_DIRECTION: [F; 3] = [0., 0., 1.];
+const WIND_STRENGTH: F = 0.3;
+const WIND_TURBULENCE: F = 0.6;
+
+// SIZE OF THE GUSTS IN NOISE LATTICE CELLS PER UNIT LENGTH, AND HOW FAST THEY EVOLVE
+const WIND_SCALE: F = 0.15;
+const WIND_FREQUENCY: F = 0.5;
+
+// STANDARD GRAVITY, SCALED DOWN TO THE UNITS OF THE SIMULATION
+const GRAVITY: F = 9.81;
+const GRAVITY_SCALE: F = 0.02;
+
+// WHERE THE FIRST PARTICLE OF THE CLOTH IS PLACED
+const ORIGIN: [F; 3] = [0., 0., 20.];
+
+// AMPLITUDE OF THE RANDOM PER-AXIS OFFSET APPLIED TO INITIAL POSITIONS TO BREAK SYMMETRY
+const JITTER: F = 0.05;
+
+// COULOMB FRICTION COEFFICIENT OF ALL CONTACTS, UNLESS A COLLIDER HAS ITS OWN
+const FRICTION: F = 0.5;
+
+// FRACTION OF THE AIR THAT PASSES THROUGH THE CLOTH, AND THE DRAG OF THE AIR ALONG IT
+const POROSITY: F = 0.;
+const AIR_DRAG: F = 0.;
+
+// THICKNESS OF THE CLOTH: SEPARATION KEPT BETWEEN LAYERS OF THE SAME OR DIFFERENT CLOTHS,
+// HALF OF IT IS KEPT BETWEEN PARTICLES AND THE SURFACE OF COLLIDERS
+const THICKNESS: F = 0.2;
+
+// LARGEST DISTANCE A PARTICLE IS MOVED BY SELF-COLLISION RESPONSE IN A SINGLE STEP
+const MAX_COLLISION_CORRECTION: F = 0.2;
+
+// LARGEST MOVE OF A PARTICLE IN ONE STEP, AND LARGEST CORRECTION OF ITS INTEGRATED POSITION BY THE
+// COLLISION PASS, BOTH IN MEAN STRUCTURAL REST LENGTHS
+const MAX_SPEED: F = 2.;
+const MAX_STEP_CORRECTION: F = 1.;
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SelfCollision {
+    /* CHEAP, WORKS WELL IN REALTIME EVEN UP TO 50+ BY 50+ GRID */
+    Points,
+    /* MORE ACCURATE, ACCELERATED WITH A BVH OVER THE CLOTH TRIANGLES */
+    Triangles,
+    Off,
+}
+
+impl FromStr for SelfCollision {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "points" => Ok(SelfCollision::Points),
+            "triangles" => Ok(SelfCollision::Triangles),
+            "off" => Ok(SelfCollision::Off),
+            _ => Err(format!("unknown self-collision mode '{}'", s)),
+        }
+    }
+}
+
+/* HOW THE CORRECTIONS OF THE CONSTRAINTS WITHIN ONE ITERATION ARE APPLIED */
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SolverMode {
+    /* IMMEDIATELY, SO LATER CONSTRAINTS SEE EARLIER ONES: FAST TO CONVERGE BUT ORDER-DEPENDENT */
+    GaussSeidel,
+    /* SUMMED PER PARTICLE AND APPLIED TOGETHER: ORDER-INDEPENDENT, NEEDS ABOUT TWICE THE ITERATIONS */
+    Jacobi,
+    /* coarse_iters PASSES OVER A COARSE GRID OF LONG SPRINGS, INTERPOLATED DOWN TO THE PARTICLES, */
+    /* THEN AT MOST fine_iters GAUSS-SEIDEL ITERATIONS, WHICH REPLACES max_iterations. SEE hierarchy */
+    Hierarchical { coarse_iters: I, fine_iters: I },
+}
+
+impl FromStr for SolverMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gauss-seidel" => Ok(SolverMode::GaussSeidel),
+            "jacobi" => Ok(SolverMode::Jacobi),
+            "hierarchical" => Ok(SolverMode::Hierarchical {
+                coarse_iters: COARSE_ITERATIONS,
+                fine_iters: MAX_ITERATIONS,
+            }),
+            /* hierarchical:<COARSE>x<FINE> */
+            _ => match s.strip_prefix("hierarchical:").and_then(|n| n.split_once('x')) {
+                Some((coarse, fine)) => match (coarse.parse(), fine.parse()) {
+                    (Ok(coarse_iters), Ok(fine_iters)) => Ok(SolverMode::Hierarchical { coarse_iters, fine_iters }),
+                    _ => Err(format!("invalid iteration counts in '{}'", s)),
+                },
+                None => Err(format!("unknown solver '{}'", s)),
+            },
+        }
+    }
+}
+
+/* HOW MUCH EACH FACE AROUND A VERTEX CONTRIBUTES TO ITS SMOOTHED RENDER NORMAL */
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NormalWeighting {
+    /* BY FACE AREA: CHEAP, BUT LARGE STRETCHED FACES DOMINATE AND FOLDS LOOK FACETED */
+    Area,
+    /* BY THE ANGLE OF THE FACE AT THE VERTEX: SMOOTHER ACROSS UNEVENLY SIZED TRIANGLES */
+    Angle,
+}
+
+impl FromStr for NormalWeighting {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "area" => Ok(NormalWeighting::Area),
+            "angle" => Ok(NormalWeighting::Angle),
+            _ => Err(format!("unknown normal weighting '{}'", s)),
+        }
+    }
+}
+
+/* WHICH DIAGONAL SPLITS EACH GRID QUAD INTO ITS TWO TRIANGLES, CHOSEN ONCE AT CONSTRUCTION */
+/* A UNIFORM SPLIT GIVES THE CLOTH A PREFERRED FOLDING DIRECTION ALONG THE DIAGONALS */
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Diagonal {
+    /* EVERY QUAD ALONG (x + 1, y)-(x, y + 1) */
+    Uniform,
+    /* FLIPPED ON EVERY OTHER QUAD IN A CHECKERBOARD */
+    Alternating,
+    /* THE SHORTER OF THE TWO DIAGONALS IN THE REST POSITIONS */
+    Shortest,
+}
+
+impl FromStr for Diagonal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "uniform" => Ok(Diagonal::Uniform),
+            "alternating" => Ok(Diagonal::Alternating),
+            "shortest" => Ok(Diagonal::Shortest),
+            _ => Err(format!("unknown diagonal '{}'", s)),
+        }
+    }
+}
+
+/* ORDER IN WHICH GAUSS-SEIDEL VISITS THE CONSTRAINTS, A FIXED ORDER BIASES THE PROPAGATION */
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SolveOrder {
+    /* ROW-MAJOR, AS THE CONSTRAINTS WERE CREATED */
+    AsGenerated,
+    /* FORWARD ON EVEN ITERATIONS, BACKWARD ON ODD ONES */
+    AlternateSweeps,
+    /* A FIXED RANDOM PERMUTATION DRAWN FROM THE SEED AT CONSTRUCTION */
+    Shuffled,
+}
+
+impl FromStr for SolveOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "as-generated" => Ok(SolveOrder::AsGenerated),
+            "alternate" => Ok(SolveOrder::AlternateSweeps),
+            "shuffled" => Ok(SolveOrder::Shuffled),
+            _ => Err(format!("unknown solve order '{}'", s)),
+        }
+    }
+}
+
+/* INITIAL SHAPE OF THE CLOTH, u AND v GO FROM 0 TO 1 ACROSS ITS WIDTH AND HEIGHT */
+#[derive(Clone, Copy, Debug)]
+pub enum Pose {
+    HangingVertical,
+    FlatHorizontal { height: F },
+    Slanted { slope: F },
+    /* FOLDED IN HALF ALONG ITS MIDDLE ROW INTO TWO FLAT SHEETS gap APART, SEE pillow::seams */
+    Pillow { gap: F },
+    Custom(fn(GridIdx, F, F) -> P),
+}
+
+impl Pose {
+    /* rows IS THE NUMBER OF ROWS OF PARTICLES, ONLY THE PILLOW NEEDS IT TO FIND ITS FOLD */
+    pub fn position(self, idx: GridIdx, u: F, v: F, width: F, height: F, rows: I) -> P {
+        match self {
+            Pose::HangingVertical => P::new(width * u, -height * v, 0.),
+            Pose::FlatHorizontal { height: y } => P::new(width * u, y, height * v),
+            Pose::Slanted { slope } => P::new(width * u, -height * v, slope * height * v),
+            Pose::Pillow { gap } => {
+                /* ROW y LIES OVER ROW rows - 1 - y, THE TOP SHEET ABOVE THE BOTTOM ONE */
+                let y = idx.1;
+                let t = y.min(rows - 1 - y) as F / (rows as F / 2.);
+                let side = match (2 * y + 1).cmp(&rows) {
+                    Ordering::Less => 0.5,
+                    Ordering::Equal => 0.,
+                    Ordering::Greater => -0.5,
+                };
+                P::new(width * u, side * gap, height * t)
+            }
+            Pose::Custom(f) => f(idx, u, v),
+        }
+    }
+}
+
+impl FromStr for Pose {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "curtain" => Ok(Pose::HangingVertical),
+            "tablecloth" => Ok(Pose::FlatHorizontal { height: 0. }),
+            "slanted" => Ok(Pose::Slanted { slope: 0.5 }),
+            "pillow" => Ok(Pose::Pillow { gap: PILLOW_GAP }),
+            _ => Err(format!("unknown pose '{}'", s)),
+        }
+    }
+}
+
+/* WHAT HAPPENS TO PARTICLES THAT FALL THROUGH THE KILL PLANE */
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KillMode {
+    /* HOLD THEM ON THE PLANE WITH ZERO VELOCITY */
+    Clamp,
+    /* CLAMP THEM, AND RESET THE WHOLE CLOTH ONCE MORE THAN THIS FRACTION OF ITS PARTICLES IS DOWN */
+    Respawn { fraction: F },
+}
+
+/* GLOBAL VOLUME CONSTRAINT OF A CLOSED CLOTH, PUSHING IT OUT ALONG ITS NORMALS TOWARDS volume */
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Pressure {
+    pub stiffness: F,
+    pub volume: F,
+}
+
+/* PERMANENT CREASES: A FLEXION SPRING HELD PAST yield_strain FOR MORE THAN yield_steps STEPS IN A ROW */
+/* MOVES ITS REST LENGTH creep OF THE WAY TOWARDS ITS CURRENT LENGTH EVERY STEP AFTER THAT, UP TO */
+/* max_strain AWAY FROM THE REST LENGTH IT WAS BUILT WITH */
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Plasticity {
+    pub yield_strain: F,
+    pub yield_steps: I,
+    pub creep: F,
+    pub max_strain: F,
+}
+
+impl Default for Plasticity {
+    fn default() -> Self {
+        Self { yield_strain: 0.2, yield_steps: 30, creep: 0.02, max_strain: 0.5 }
+    }
+}
+
+/* LET THE SOLVER TUNE damping FROM THE ENERGY OF THE CLOTH WHILE ONLY GRAVITY ACTS ON IT. KINETIC ENERGY */
+/* THAT STAYS ABOVE target, A MEAN PER MOVING PARTICLE OVER window STEPS, LOWERS THE DAMPING BY rate A STEP */
+/* DOWN TO floor. MECHANICAL ENERGY GROWING BY MORE THAN growth TIMES THE KINETIC ENERGY OVER THE WINDOW */
+/* DROPS IT TO floor AT ONCE. BELOW THE TARGET IT CLIMBS BACK BY rate TO config.damping. floor KEEPS A */
+/* FALLING CLOTH FROM FREEZING IN MID-AIR, SEE damping::DampingTuner */
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AutoDamping {
+    pub target: F,
+    pub floor: F,
+    pub window: I,
+    pub rate: F,
+    pub growth: F,
+}
+
+impl Default for AutoDamping {
+    fn default() -> Self {
+        Self { target: 1e-4, floor: 0.98, window: 30, rate: 5e-4, growth: 1. }
+    }
+}
+
+/* WHAT THE PAINTED WEIGHT MAP OF A CLOTH SCALES: A WEIGHT OF 0 LEAVES IT AS IT IS AND A WEIGHT OF 1 */
+/* MULTIPLIES IT BY scale, WITH THE FACTOR LINEAR IN BETWEEN. STIFFNESS IS TAKEN PER SPRING AS THE */
+/* MEAN OF ITS END POINTS AND CAPPED AT 1 */
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WeightBinding {
+    pub target: WeightTarget,
+    pub scale: F,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WeightTarget {
+    Mass,
+    Drag,
+    Stiffness,
+}
+
+impl WeightBinding {
+    pub fn factor(self, weight: F) -> F {
+        1. + (self.scale - 1.) * weight
+    }
+}
+
+/* <TARGET>:<SCALE> */
+impl FromStr for WeightBinding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (target, scale) = s.split_once(':').ok_or_else(|| format!("expected <target>:<scale>, got '{}'", s))?;
+        let target = match target {
+            "mass" => WeightTarget::Mass,
+            "drag" => WeightTarget::Drag,
+            "stiffness" => WeightTarget::Stiffness,
+            _ => return Err(format!("unknown weight target '{}'", target)),
+        };
+        let scale = scale.parse().map_err(|_| format!("invalid weight scale '{}'", scale))?;
+        Ok(Self { target, scale })
+    }
+}
+
+/* WHICH PARTICLES ARE FIXED AT SIMULATION START, RE-APPLIED WHENEVER THE CLOTH IS REGENERATED */
+#[derive(Clone, Debug, PartialEq)]
+pub enum PinPreset {
+    /* 2 PARTICLES AT EACH OF THE 4 CORNERS */
+    Corners,
+    TopRow,
+    /* 2 PARTICLES AT EACH OF THE TOP CORNERS, HANGING THE CLOTH LIKE A FLAG ON A LINE */
+    TopCorners,
+    LeftColumn,
+    /* THE TOP ROW SLIDES ALONG A HORIZONTAL LINE LIKE A CURTAIN ON A RAIL */
+    Rail,
+    Free,
+    /* EXACTLY THESE GRID POSITIONS, SEE validate */
+    Custom(Vec<GridIdx>),
+}
+
+impl PinPreset {
+    /* GRID POSITIONS THE PRESET FIXES ON A w x h GRID, THE RAIL SLIDES RATHER THAN FIXES */
+    /* OUT-OF-RANGE CUSTOM POSITIONS ARE LEFT OUT, validate REPORTS THEM */
+    pub fn fixed(&self, w: I, h: I) -> Vec<GridIdx> {
+        let xs = [0, 1, w.saturating_sub(2), w.saturating_sub(1)];
+        let corners = |rows: &[I]| {
+            let mut pins: Vec<_> = rows.iter().flat_map(|&y| xs.iter().map(move |&x| (x, y))).collect();
+            /* NARROW GRIDS WOULD PIN THE SAME PARTICLES TWICE, A ROPE HAS NO SECOND COLUMN */
+            pins.retain(|&(x, _)| x < w);
+            pins.sort_unstable();
+            pins.dedup();
+            pins
+        };
+        match self {
+            PinPreset::Corners => corners(&[0, h - 1]),
+            PinPreset::TopRow => (0..w).map(|x| (x, 0)).collect(),
+            PinPreset::TopCorners => corners(&[0]),
+            PinPreset::LeftColumn => (0..h).map(|y| (0, y)).collect(),
+            PinPreset::Rail | PinPreset::Free => vec![],
+            PinPreset::Custom(pins) => pins.iter().copied().filter(|&(x, y)| x < w && y < h).collect(),
+        }
+    }
+
+    /* THE CORNER PRESETS NEED 2 PARTICLES ALONG EACH SIDE, CUSTOM POSITIONS MUST LIE ON THE GRID */
+    pub fn validate(&self, w: I, h: I) -> Result<(), String> {
+        let corners = *self == PinPreset::Corners || *self == PinPreset::TopCorners;
+        if corners && (w < 2 || h < 2) {
+            return Err(format!("corner pins need a grid of at least 2x2 particles, got {}x{}", w, h));
+        }
+        match self {
+            PinPreset::Custom(pins) => match pins.iter().find(|&&(x, y)| x >= w || y >= h) {
+                Some((x, y)) => Err(format!("pin {},{} is outside the {}x{} grid", x, y, w, h)),
+                None => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+impl FromStr for PinPreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "corners" => Ok(PinPreset::Corners),
+            "top-row" => Ok(PinPreset::TopRow),
+            "top-corners" => Ok(PinPreset::TopCorners),
+            "left-column" => Ok(PinPreset::LeftColumn),
+            "rail" => Ok(PinPreset::Rail),
+            "free" => Ok(PinPreset::Free),
+            /* x,y;x,y;... */
+            _ => match s.strip_prefix("custom:") {
+                Some(list) => list
+                    .split(';')
+                    .map(|pin| {
+                        let (x, y) = pin.split_once(',').ok_or_else(|| format!("pin '{}' is not x,y", pin))?;
+                        match (x.trim().parse(), y.trim().parse()) {
+                            (Ok(x), Ok(y)) => Ok((x, y)),
+                            _ => Err(format!("pin '{}' is not x,y", pin)),
+                        }
+                    })
+                    .collect::<Result<_, _>>()
+                    .map(PinPreset::Custom),
+                None => Err(format!("unknown pin preset '{}'", s)),
+            },
+        }
+    }
+}
+
+/* HOW THE TEXTURE IS ORIENTED ON THE CLOTH, APPLIED BEFORE TILING AND OFFSETTING */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UvOrientation {
+    Identity,
+    FlipU,
+    FlipV,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+impl UvOrientation {
+    pub fn apply(self, u: F, v: F) -> (F, F) {
+        match self {
+            UvOrientation::Identity => (u, v),
+            UvOrientation::FlipU => (1. - u, v),
+            UvOrientation::FlipV => (u, 1. - v),
+            UvOrientation::Rotate90 => (1. - v, u),
+            UvOrientation::Rotate180 => (1. - u, 1. - v),
+            UvOrientation::Rotate270 => (v, 1. - u),
+        }
+    }
+}
+
+/* AIR FLOW ACTING ON THE FACES OF THE CLOTH, TURBULENCE IS COHERENT IN SPACE AND TIME */
+#[derive(Clone, Copy, Debug)]
+pub struct Wind {
+    pub direction: V,
+    pub strength: F,
+    pub turbulence: F,
+    pub scale: F,
+    pub frequency: F,
+}
+
+impl Default for Wind {
+    fn default() -> Self {
+        Self {
+            direction: V::from(WIND_DIRECTION),
+            strength: WIND_STRENGTH,
+            turbulence: WIND_TURBULENCE,
+            scale: WIND_SCALE,
+            frequency: WIND_FREQUENCY,
+        }
+    }
+}
+
+/* FRACTION OF EACH SPRING'S CORRECTION APPLIED PER ITERATION, IN [0, 1] */
+/* THE STRUCTURAL SPRINGS ARE SPLIT BY WEAVE SO THE TWO THREAD DIRECTIONS CAN STRETCH DIFFERENTLY */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stiffness {
+    pub warp: F,
+    pub weft: F,
+    pub shear: F,
+    pub flexion: F,
+}
+
+impl Default for Stiffness {
+    fn default() -> Self { Self { warp: 1., weft: 1., shear: 1., flexion: 1. } }
+}
+
+impl Stiffness {
+    pub fn get(&self, kind: ConstraintKind, weave: Weave) -> F {
+        match (kind, weave) {
+            (ConstraintKind::Structural, Weave::Warp) => self.warp,
+            (ConstraintKind::Structural, Weave::Weft) => self.weft,
+            (ConstraintKind::Shear, _) => self.shear,
+            (ConstraintKind::Flexion, _) => self.flexion,
+        }
+    }
+}
+
+/* FRACTION OF THE RELATIVE VELOCITY ALONG EACH SPRING REMOVED PER STEP, CLAMPED TO [0, 1] */
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SpringDamping {
+    pub structural: F,
+    pub shear: F,
+    pub flexion: F,
+}
+
+impl SpringDamping {
+    pub fn uniform(k: F) -> Self { Self { structural: k, shear: k, flexion: k } }
+
+    pub fn get(&self, kind: ConstraintKind) -> F {
+        let k = match kind {
+            ConstraintKind::Structural => self.structural,
+            ConstraintKind::Shear => self.shear,
+            ConstraintKind::Flexion => self.flexion,
+        };
+        k.max(0.).min(1.)
+    }
+}
+
+/* GAUSS-SEIDEL SWEEPS OF EACH KIND OF CONSTRAINT PER STEP, TAKING THE PLACE OF max_iterations */
+/* SHEAR AND FLEXION SPRINGS RARELY NEED AS MANY AS THE STRUCTURAL ONES */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KindIterations {
+    pub structural: I,
+    pub shear: I,
+    pub flexion: I,
+}
+
+impl KindIterations {
+    pub fn get(&self, kind: ConstraintKind) -> I {
+        match kind {
+            ConstraintKind::Structural => self.structural,
+            ConstraintKind::Shear => self.shear,
+            ConstraintKind::Flexion => self.flexion,
+        }
+    }
+
+    /* SWEEPS OF THE WHOLE STEP, THE KINDS WITH FEWER DROP OUT OF THE LATER ONES */
+    pub fn max(&self) -> I { self.structural.max(self.shear).max(self.flexion) }
+}
+
+/* "STRUCTURAL,SHEAR,FLEXION", E.G. "10,6,3" */
+impl FromStr for KindIterations {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let counts = s
+            .split(',')
+            .map(|c| c.trim().parse().map_err(|_| format!("bad count '{}'", c)))
+            .collect::<Result<Vec<I>, _>>()?;
+        match counts[..] {
+            [structural, shear, flexion] => Ok(KindIterations { structural, shear, flexion }),
+            _ => Err(format!("expected 3 counts, got {}", counts.len())),
+        }
+    }
+}
+
+/* EXTRA GAUSS-SEIDEL SWEEPS AFTER THE GLOBAL ONES, ONLY OVER THE CONSTRAINTS TOUCHING PARTICLES WITHIN */
+/* radius STRUCTURAL SPRINGS OF A HOT ONE: RECENTLY GRABBED, NEWLY IN CONTACT WITH A COLLIDER OR NEXT TO */
+/* A STRONGLY STRAINED SPRING, SO THE WORK GOES WHERE THE CLOTH IS DISTURBED */
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LocalBoost {
+    pub radius: I,
+    pub iterations: I,
+}
+
+/* <RADIUS>:<ITERATIONS>, E.G. "3:12" */
+impl FromStr for LocalBoost {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (radius, iterations) =
+            s.split_once(':').ok_or_else(|| format!("expected <radius>:<iterations>, got '{}'", s))?;
+        let radius = radius.parse().map_err(|_| format!("bad radius '{}'", radius))?;
+        let iterations = iterations.parse().map_err(|_| format!("bad count '{}'", iterations))?;
+        Ok(Self { radius, iterations })
+    }
+}
+
+/* ATTENUATE THE WIND ON FACES SHELTERED BY ANOTHER PART OF THE SAME CLOTH: EVERY interval STEPS A RAY */
+/* IS CAST UPWIND FROM EACH FACE FOR reach CLOTH THICKNESSES, AND A FACE IT HITS ON THE WAY CUTS THE WIND */
+/* ON THE CASTING FACE TO attenuation OF ITSELF UNTIL THE NEXT CAST */
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WindOcclusion {
+    pub attenuation: F,
+    pub reach: F,
+    pub interval: I,
+}
+
+/* <ATTENUATION>:<REACH>:<INTERVAL>, E.G. "0.2:5:4" */
+impl FromStr for WindOcclusion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').collect();
+        let (attenuation, reach, interval) = match parts.as_slice() {
+            [attenuation, reach, interval] => (attenuation, reach, interval),
+            _ => return Err(format!("expected <attenuation>:<reach>:<interval>, got '{}'", s)),
+        };
+        let attenuation = attenuation.parse().map_err(|_| format!("bad attenuation '{}'", attenuation))?;
+        let reach = reach.parse().map_err(|_| format!("bad reach '{}'", reach))?;
+        let interval = interval.parse().map_err(|_| format!("bad interval '{}'", interval))?;
+        Ok(Self { attenuation, reach, interval })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ClothConfig {
+    pub dt: F,
+    /* FRACTION OF THE FRAMES THE CLOTH STEPS ON, FROM 1 AT FULL SPEED DOWN TO 0 FROZEN. THE STEPS KEEP */
+    /* THEIR SIZE SO SLOW MOTION IS AS STABLE AS FULL SPEED, SEE Cloth::tick */
+    pub time_scale: F,
+    /* dt IS CLAMPED TO THIS, AGAINST HITCHES AND TYPOS IN THE CONFIG */
+    pub max_dt: F,
+    pub damping: F,
+    /* TUNE damping FROM THE ENERGY OF THE CLOTH, None KEEPS IT FIXED */
+    pub auto_damping: Option<AutoDamping>,
+    /* INTERNAL DAMPING OF THE SPRINGS, AGAINST HIGH-FREQUENCY RINGING ALONG THEM */
+    pub spring_damping: SpringDamping,
+    pub stiffness: Stiffness,
+    pub min_iterations: I,
+    pub max_iterations: I,
+    pub tolerance: F,
+    pub solver: SolverMode,
+    /* THE GPU SOLVER ALWAYS INTEGRATES WITH VERLET */
+    pub integrator: Integrator,
+    pub solve_order: SolveOrder,
+    /* SCALE OF THE AVERAGED JACOBI CORRECTIONS, ABOVE 1 OVER-RELAXES, UNUSED BY GAUSS-SEIDEL */
+    pub relaxation: F,
+    /* SOR FACTOR OF EVERY GAUSS-SEIDEL CORRECTION, ALSO IN THE HIERARCHICAL FINE PASSES */
+    /* ABOVE 1 CONVERGES FASTER, STABLE UP TO ABOUT 1.8, AT 2 AND BEYOND IT DIVERGES */
+    pub over_relaxation: F,
+    /* PER-KIND SWEEP COUNTS OF THE GAUSS-SEIDEL SOLVERS, None SWEEPS ALL KINDS max_iterations TIMES */
+    pub kind_iterations: Option<KindIterations>,
+    /* EXTRA SWEEPS AROUND HOT PARTICLES, None LEAVES EVERY CONSTRAINT AT THE GLOBAL COUNT */
+    pub local_boost: Option<LocalBoost>,
+    pub gravity: V,
+    pub wind: Wind,
+    /* SHELTER FACES BEHIND THE CLOTH'S OWN FOLDS FROM THE WIND, None LETS EVERY FACE CATCH ALL OF IT */
+    pub wind_occlusion: Option<WindOcclusion>,
+    /* 0 CATCHES ALL THE WIND FLOWING INTO THE FACES LIKE A SAIL, 1 LETS IT ALL THROUGH LIKE A NET */
+    pub porosity: F,
+    /* FRICTION OF THE AIR FLOWING ALONG THE FACES, WHICH STILL PULLS ON A FULLY POROUS CLOTH */
+    pub air_drag: F,
+    /* MASS OF EVERY PARTICLE, WHICH ONLY MATTERS AGAINST FORCES LIKE THE WIND, NOT GRAVITY */
+    pub particle_mass: F,
+    pub pose: Pose,
+    pub origin: V,
+    pub jitter: F,
+    pub seed: u64,
+    pub self_collision: SelfCollision,
+    /* EDGE-EDGE PASS ON TOP OF THE SELF-COLLISION ABOVE, TESTING AT MOST THIS MANY EDGE PAIRS PER */
+    /* STEP NEAR RECENT CONTACTS, None LEAVES IT OFF. EXPENSIVE, FOR CLOTHS FOLDED INTO TIGHT CREASES */
+    pub edge_collision: Option<I>,
+    /* SAFETY VALVE ON THE AVERAGED SELF-COLLISION CORRECTION OF EACH PARTICLE PER STEP */
+    pub max_collision_correction: F,
+    /* SAFETY LIMITS IN MEAN STRUCTURAL REST LENGTHS, SO THEY SCALE WITH THE RESOLUTION OF THE CLOTH */
+    /* max_speed CLAMPS p - old_p AFTER INTEGRATION, max_step_correction HOW FAR THE COLLISION PASS */
+    /* MOVES A PARTICLE FROM WHERE IT WAS INTEGRATED TO. None LEAVES THE MOTION UNCLAMPED */
+    pub max_speed: Option<F>,
+    pub max_step_correction: Option<F>,
+    /* LOOSE PIECES THAT FALL ENTIRELY BELOW THIS HEIGHT ARE FROZEN INSTEAD OF FALLING FOREVER */
+    pub kill_height: F,
+    /* WORLD HEIGHT NO PARTICLE MAY FALL BELOW, AGAINST CLOTHS FALLING FOREVER IN SCENES WITHOUT A */
+    /* FLOOR, WHERE THE HUGE COORDINATES WOULD EVENTUALLY COST THE SOLVE ITS PRECISION */
+    pub kill_plane: Option<F>,
+    pub kill_mode: KillMode,
+    /* LAYER BITS OF THIS CLOTH, AND THE LAYERS OF COLLIDERS AND CLOTHS IT COLLIDES WITH */
+    /* SELF-COLLISION NEEDS THE MASK TO INCLUDE THE CLOTH'S OWN LAYER */
+    pub layer: u32,
+    pub collision_mask: u32,
+    /* TWO-SIDED SHELL AROUND THE CLOTH: FOLDED AND STACKED LAYERS STAY thickness APART, */
+    /* COLLIDERS ARE KEPT thickness / 2 FROM THE MIDDLE OF THE CLOTH */
+    pub thickness: F,
+    /* FRICTION OF SELF-COLLISION, CLOTH-CLOTH AND COLLIDER CONTACTS, 0 SLIDES LIKE ON ICE */
+    pub friction: F,
+    /* SWEPT TESTS AGAINST PLANES, SPHERES AND BOXES TO STOP FAST PARTICLES TUNNELING */
+    pub continuous_collision: bool,
+    /* KEEP COLLIDER CONTACTS ACROSS STEPS AND HOLD THE PARTICLES IN THEM BEFORE THE COLLISION PASS */
+    pub contact_cache: bool,
+    /* PUSH A CLOTH THAT STARTS INSIDE A COLLIDER OUT GENTLY RATHER THAN EJECTING IT */
+    pub untangle: bool,
+    pub pin_preset: PinPreset,
+    /* TEXTURE COORDINATES: REPEATS ACROSS THE CLOTH, SHIFT AND ORIENTATION */
+    pub uv_scale: (F, F),
+    pub uv_offset: (F, F),
+    pub uv_orientation: UvOrientation,
+    /* RENDER VERTICES PER SIMULATED EDGE, 1 RENDERS THE PARTICLES DIRECTLY */
+    pub render_subdivision: I,
+    pub normal_weighting: NormalWeighting,
+    /* TRIANGULATION OF THE GRID, USED BY WIND, NORMALS, AREA CONSTRAINTS AND COLLISIONS */
+    pub diagonal: Diagonal,
+    /* CLAMP EACH PARTICLE TO ITS GEODESIC DISTANCE FROM THE NEAREST PIN AFTER SOLVING */
+    pub long_range_attachments: bool,
+    /* HARD LIMIT ON STRUCTURAL EDGE LENGTH RELATIVE TO REST, E.G. 1.1 FOR 110%, FOR WARP AND WEFT */
+    pub max_strain: Option<(F, F)>,
+    /* STIFFNESS OF THE PER-TRIANGLE AREA CONSTRAINTS BETWEEN 0 AND 1, AGAINST SHEARING INTO SLIVERS */
+    pub area_stiffness: Option<F>,
+    /* ONLY APPLIED TO CLOTHS WHOSE TRIANGLES FORM A CLOSED SURFACE, LIKE THE PILLOW */
+    pub pressure: Option<Pressure>,
+    /* ONLY AFFECTS FLEXION SPRINGS, None KEEPS THE CLOTH PERFECTLY ELASTIC */
+    pub plasticity: Option<Plasticity>,
+    /* WHAT THE PAINTED WEIGHT MAP SCALES, None LEAVES IT WITHOUT EFFECT */
+    pub weight_binding: Option<WeightBinding>,
+    /* GRADUALLY LENGTHEN ALL REST LENGTHS ONCE THE TOTAL STRUCTURAL LENGTH HAS CONTRACTED */
+    /* BY MORE THAN THIS FRACTION OF ITS REST LENGTH, E.G. 0.01, AGAINST SLOW SHRINKING OVER LONG RUNS */
+    pub retension: Option<F>,
+    /* FRACTION OF THE LAST STEP'S CONSTRAINT CORRECTIONS PRE-APPLIED BEFORE SOLVING, 0 STARTS COLD */
+    pub warm_start: F,
+    /* RUN THE CONSTRAINTS AND INTEGRATION ON THE GPU, ONLY WITH THE gpu FEATURE */
+    pub gpu: bool,
+    /* BITWISE REPRODUCIBLE RESULTS FOR A SEED ON ANY MACHINE: ACCUMULATIONS ARE REDUCED IN PARTICLE */
+    /* INDEX ORDER, THE GPU SOLVER, WHOSE ROUNDING DEPENDS ON THE DEVICE, IS NOT USED AND AN async_sim WORKER */
+    /* STEPS IN LOCKSTEP WITH THE FRAMES */
+    pub deterministic: bool,
+    /* STEP THE MAIN CLOTH ON A BACKGROUND THREAD AND PRESENT ITS LATEST STEP, ONLY WITH THE async_sim FEATURE */
+    pub async_sim: bool,
+}
+
+impl Default for ClothConfig {
+    fn default() -> Self {
+        Self {
+            dt: DT,
+            time_scale: 1.,
+            max_dt: MAX_DT,
+            damping: DAMPING,
+            auto_damping: None,
+            spring_damping: SpringDamping::default(),
+            stiffness: Stiffness::default(),
+            min_iterations: MIN_ITERATIONS,
+            max_iterations: MAX_ITERATIONS,
+            tolerance: TOLERANCE,
+            solver: SolverMode::GaussSeidel,
+            integrator: Integrator::Verlet,
+            solve_order: SolveOrder::AsGenerated,
+            relaxation: RELAXATION,
+            over_relaxation: OVER_RELAXATION,
+            kind_iterations: None,
+            local_boost: None,
+            gravity: V::new(0., -GRAVITY * GRAVITY_SCALE, 0.),
+            wind: Wind::default(),
+            wind_occlusion: None,
+            porosity: POROSITY,
+            air_drag: AIR_DRAG,
+            particle_mass: 1.,
+            pose: Pose::Slanted { slope: 0.5 },
+            origin: V::from(ORIGIN),
+            jitter: JITTER,
+            seed: 0,
+            self_collision: SelfCollision::Points,
+            edge_collision: None,
+            max_collision_correction: MAX_COLLISION_CORRECTION,
+            max_speed: Some(MAX_SPEED),
+            max_step_correction: Some(MAX_STEP_CORRECTION),
+            kill_height: KILL_HEIGHT,
+            kill_plane: None,
+            kill_mode: KillMode::Clamp,
+            layer: DEFAULT_LAYER,
+            collision_mask: ALL_LAYERS,
+            thickness: THICKNESS,
+            friction: FRICTION,
+            continuous_collision: true,
+            contact_cache: true,
+            untangle: true,
+            pin_preset: PinPreset::Corners,
+            uv_scale: (1., 1.),
+            uv_offset: (0., 0.),
+            uv_orientation: UvOrientation::Identity,
+            render_subdivision: 1,
+            normal_weighting: NormalWeighting::Area,
+            diagonal: Diagonal::Uniform,
+            long_range_attachments: false,
+            max_strain: None,
+            area_stiffness: None,
+            pressure: None,
+            plasticity: None,
+            weight_binding: None,
+            retension: None,
+            warm_start: 0.,
+            gpu: false,
+            deterministic: false,
+            async_sim: false,
+        }
+    }
+}
+
+impl ClothConfig {
+    /* THE DEFAULTS WITH THE MATERIAL PARAMETERS OF A BUILT-IN FABRIC */
+    #[cfg(test)]
+    pub fn from_preset(preset: FabricPreset) -> Self {
+        let mut config = Self::default();
+        preset.fabric().apply(&mut config);
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* THE LEFT COLUMN PINS ONE PARTICLE PER ROW, A CUSTOM LIST PARSES FROM x,y;x,y AND IS ONLY ACCEPTED */
+    /* WHEN EVERY PIN LIES ON THE GRID, WHILE THE CORNER PRESETS NEED TWO PARTICLES ALONG EACH SIDE */
+    #[test]
+    fn pin_presets_fit_the_grid() {
+        assert_eq!(PinPreset::LeftColumn.fixed(4, 3), vec![(0, 0), (0, 1), (0, 2)]);
+
+        let custom: PinPreset = "custom:0,0; 3,1;5,2".parse().unwrap();
+        assert_eq!(custom, PinPreset::Custom(vec![(0, 0), (3, 1), (5, 2)]));
+        assert_eq!(custom.fixed(4, 3), vec![(0, 0), (3, 1)]);
+        assert_eq!(custom.validate(4, 3), Err("pin 5,2 is outside the 4x3 grid".to_string()));
+        assert_eq!(custom.validate(6, 3), Ok(()));
+        assert!("custom:1;2".parse::<PinPreset>().is_err());
+
+        assert!(PinPreset::TopCorners.validate(1, 8).is_err());
+        assert_eq!(PinPreset::Corners.fixed(3, 2).len(), 6);
+    }
+}
@@ -0,0 +1,108 @@
+use bevy::prelude::*;
+
+use crate::{cloth::Cloth, collider, particles, spatial::SpatialGrid, F, I, P, V};
+
+/* COLLISIONS BETWEEN SEPARATE CLOTH ENTITIES, RESOLVED AFTER EVERY CLOTH HAS STEPPED */
+/* EACH CLOTH ONLY SEES ITS OWN PARTICLES IN ITS STEP, SO OVERLAPPING CLOTHS NEED THIS PASS */
+/* TWO CLOTHS ONLY COLLIDE IF EACH ONE'S MASK INCLUDES THE OTHER'S LAYER */
+
+struct Point {
+    cloth: I,
+    p: P,
+    /* MOVEMENT OVER THE LAST STEP, FOR FRICTION */
+    displacement: V,
+    fixed: bool,
+}
+
+/* SCRATCH STATE KEPT BETWEEN FRAMES TO AVOID PER-FRAME ALLOCATIONS */
+#[derive(Default)]
+pub struct ClothContacts {
+    /* OVER THE WORLD POSITIONS OF ALL CLOTHS, WHOSE OWN BROAD PHASES ARE EACH IN THEIR LOCAL SPACE */
+    grid: SpatialGrid,
+    points: Vec<Point>,
+    /* LAYER, COLLISION MASK AND FRICTION OF EACH CLOTH */
+    layers: Vec<(u32, u32, F)>,
+    corrections: Vec<V>,
+    frictions: Vec<V>,
+}
+
+pub fn collide(mut contacts: ResMut<ClothContacts>, mut cloths: Query<&mut Cloth>) {
+    let contacts = &mut *contacts;
+    contacts.points.clear();
+    contacts.layers.clear();
+    let mut count = 0;
+    let mut thickness: F = 0.;
+    for (c, cloth) in cloths.iter_mut().enumerate() {
+        count += 1;
+        thickness = thickness.max(cloth.config.thickness);
+        contacts.layers.push((cloth.config.layer, cloth.config.collision_mask, cloth.config.friction));
+        let particles = &cloth.particles;
+        /* CLOTHS MAY LIVE IN DIFFERENT LOCAL SPACES, SO THEY MEET IN THE WORLD */
+        contacts.points.extend(particles.iter().enumerate().map(|(i, &p)| Point {
+            cloth: c,
+            p: cloth.to_world(p),
+            displacement: cloth.vector_to_world(particles.displacement(i)),
+            fixed: particles.fixed(i),
+        }));
+    }
+    if count < 2 || thickness <= 0. {
+        return;
+    }
+
+    let (grid, points, layers) = (&mut contacts.grid, &contacts.points, &contacts.layers);
+    let (corrections, frictions) = (&mut contacts.corrections, &mut contacts.frictions);
+    grid.build(points.iter().map(|p| p.p), thickness);
+    corrections.clear();
+    corrections.resize(points.len(), V::zeros());
+    frictions.clear();
+    frictions.resize(points.len(), V::zeros());
+
+    for (i, a) in points.iter().enumerate() {
+        grid.neighbors_of(&a.p, thickness, &mut |j| {
+            let b = &points[j];
+            if j <= i || a.cloth == b.cloth || (a.fixed && b.fixed) {
+                return;
+            }
+            let ((layer_a, mask_a, mu_a), (layer_b, mask_b, mu_b)) = (layers[a.cloth], layers[b.cloth]);
+            if !collider::meets(mask_a, layer_b) || !collider::meets(mask_b, layer_a) {
+                return;
+            }
+            let diff = b.p - a.p;
+            let d = diff.norm();
+            if d >= thickness || d == 0. {
+                return;
+            }
+            /* SPLIT THE SEPARATION EVENLY, UNLESS ONE SIDE IS PINNED AND CANNOT MOVE */
+            let delta = diff * (1. - thickness / d);
+            let share = match (a.fixed, b.fixed) {
+                (true, _) => 0.,
+                (_, true) => 1.,
+                _ => 0.5,
+            };
+            corrections[i] += delta * share;
+            corrections[j] -= delta * (1. - share);
+            /* THE COEFFICIENTS OF THE TWO CLOTHS COMBINE BY THEIR GEOMETRIC MEAN */
+            let mu = (mu_a * mu_b).sqrt();
+            let f = particles::friction(b.displacement - a.displacement, diff / d, delta.norm(), mu);
+            frictions[i] += f * share;
+            frictions[j] -= f * (1. - share);
+        });
+    }
+
+    /* QUERY ITERATION ORDER IS STABLE WITHIN THE SYSTEM, SO INDICES LINE UP WITH THE GATHER */
+    let mut offset = 0;
+    for mut cloth in cloths.iter_mut() {
+        let n = cloth.particles.len();
+        if corrections[offset..offset + n].iter().any(|d| *d != V::zeros()) {
+            for (i, delta) in corrections[offset..offset + n].iter().enumerate() {
+                let delta = cloth.vector_to_local(*delta);
+                cloth.offset_particle(i, delta);
+            }
+            for (i, f) in frictions[offset..offset + n].iter().enumerate() {
+                let f = cloth.vector_to_local(*f);
+                cloth.offset_displacement(i, f);
+            }
+        }
+        offset += n;
+    }
+}
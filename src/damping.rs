@@ -0,0 +1,54 @@
+use std::collections::VecDeque;
+
+use bevy::log::warn;
+
+use crate::{config::AutoDamping, F};
+
+/* ENERGY-DRIVEN DAMPING: THE ENERGIES OF THE LAST window STEPS DECIDE HOW MUCH OF ITS VELOCITY A CLOTH KEEPS */
+/* EACH STEP. config.damping IS WHERE IT STARTS AND WHAT IT RETURNS TO, floor THE LOWEST IT EVER GOES */
+
+#[derive(Clone, Debug, Default)]
+pub struct DampingTuner {
+    /* MEAN KINETIC AND MECHANICAL ENERGY PER MOVING PARTICLE BEFORE EACH OF THE LAST window STEPS */
+    energies: VecDeque<(F, F)>,
+    /* DAMPING OF THE LAST STEP, None WHILE HELD */
+    current: Option<F>,
+}
+
+impl DampingTuner {
+    /* DAMPING FOR THE NEXT STEP. WHILE held, E.G. WHILE THE CLOTH IS DRAGGED OR BLOWN, THE CONFIGURED */
+    /* DAMPING IS USED AS IT IS AND THE WINDOW STARTS OVER, SO THE TUNER NEVER FIGHTS THE USER */
+    pub fn update(&mut self, settings: &AutoDamping, base: F, energy: (F, F), held: bool) -> F {
+        if held || !(energy.0.is_finite() && energy.1.is_finite()) {
+            self.energies.clear();
+            self.current = None;
+            return base;
+        }
+        let window = settings.window.max(2);
+        if self.energies.len() == window {
+            self.energies.pop_front();
+        }
+        self.energies.push_back(energy);
+        let (current, floor) = (self.current.unwrap_or(base), settings.floor.min(base));
+        if self.energies.len() < window {
+            self.current = Some(current);
+            return current;
+        }
+
+        let (first, last) = (self.energies[0], self.energies[window - 1]);
+        let kinetic = self.energies.iter().map(|e| e.0).sum::<F>() / window as F;
+        /* GRAVITY ALONE ONLY TRADES POTENTIAL ENERGY FOR KINETIC, SO A RISE IN THEIR SUM IS AN INSTABILITY */
+        let next = if last.1 - first.1 > settings.growth * first.0.max(settings.target) {
+            if current > floor {
+                warn!("energy of a cloth grew with no force to explain it, damping at {} until it settles", floor);
+            }
+            floor
+        } else if kinetic > settings.target {
+            (current - settings.rate).max(floor)
+        } else {
+            (current + settings.rate).min(base)
+        };
+        self.current = Some(next);
+        next
+    }
+}
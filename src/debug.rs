@@ -0,0 +1,168 @@
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, pipeline::PrimitiveTopology},
+};
+
+use crate::{
+    bindings::Action,
+    cloth::{Cloth, ConstraintKind},
+    to_array,
+};
+
+/* DEBUG VISUALIZATION OF THE SOLVER STATE */
+/* TAB CYCLES THROUGH THE MODES, F1/F2/F3 SELECT ONE DIRECTLY */
+/* THE WIREFRAME DRAWS CONSTRAINTS PAINTED STIFFER IN ORANGE AND SOFTER IN PURPLE OVER THEIR KIND'S COLOR */
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DebugMode {
+    Textured,
+    Wireframe,
+    Points,
+}
+
+impl DebugMode {
+    fn next(self) -> Self {
+        match self {
+            DebugMode::Textured => DebugMode::Wireframe,
+            DebugMode::Wireframe => DebugMode::Points,
+            DebugMode::Points => DebugMode::Textured,
+        }
+    }
+}
+
+/* TAGS AN ENTITY AS ONLY BEING VISIBLE IN THE GIVEN DEBUG MODE */
+pub struct DebugLayer(pub DebugMode);
+
+pub struct DebugRender {
+    pub mode: DebugMode,
+    structural: Handle<Mesh>,
+    shear: Handle<Mesh>,
+    flexion: Handle<Mesh>,
+    stiffer: Handle<Mesh>,
+    softer: Handle<Mesh>,
+    free: Handle<Mesh>,
+    fixed: Handle<Mesh>,
+}
+
+pub fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let render = DebugRender {
+        mode: DebugMode::Textured,
+        structural: meshes.add(Mesh::new(PrimitiveTopology::LineList)),
+        shear: meshes.add(Mesh::new(PrimitiveTopology::LineList)),
+        flexion: meshes.add(Mesh::new(PrimitiveTopology::LineList)),
+        stiffer: meshes.add(Mesh::new(PrimitiveTopology::LineList)),
+        softer: meshes.add(Mesh::new(PrimitiveTopology::LineList)),
+        free: meshes.add(Mesh::new(PrimitiveTopology::PointList)),
+        fixed: meshes.add(Mesh::new(PrimitiveTopology::PointList)),
+    };
+
+    let layers = [
+        (&render.structural, Color::rgb(0.9, 0.9, 0.9), DebugMode::Wireframe),
+        (&render.shear, Color::rgb(0.2, 0.8, 0.2), DebugMode::Wireframe),
+        (&render.flexion, Color::rgb(0.2, 0.4, 1.), DebugMode::Wireframe),
+        (&render.stiffer, Color::rgb(1., 0.5, 0.1), DebugMode::Wireframe),
+        (&render.softer, Color::rgb(0.8, 0.2, 0.9), DebugMode::Wireframe),
+        (&render.free, Color::rgb(0.9, 0.9, 0.9), DebugMode::Points),
+        (&render.fixed, Color::rgb(1., 0.1, 0.1), DebugMode::Points),
+    ];
+    for (mesh, color, mode) in layers.iter() {
+        commands
+            .spawn(PbrComponents {
+                mesh: (*mesh).clone(),
+                material: materials.add(StandardMaterial {
+                    albedo: *color,
+                    shaded: false,
+                    ..Default::default()
+                }),
+                draw: Draw {
+                    is_visible: false,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with(DebugLayer(*mode));
+    }
+
+    commands.insert_resource(render);
+}
+
+pub fn toggle(
+    actions: Res<Input<Action>>,
+    mut render: ResMut<DebugRender>,
+    mut query: Query<(&DebugLayer, &mut Draw)>,
+) {
+    let mode = if actions.just_pressed(Action::CycleDebug) {
+        render.mode.next()
+    } else if actions.just_pressed(Action::Textured) {
+        DebugMode::Textured
+    } else if actions.just_pressed(Action::Wireframe) {
+        DebugMode::Wireframe
+    } else if actions.just_pressed(Action::Points) {
+        DebugMode::Points
+    } else {
+        return;
+    };
+
+    render.mode = mode;
+    for (layer, mut draw) in query.iter_mut() {
+        draw.is_visible = layer.0 == mode;
+    }
+}
+
+/* REBUILD THE AUXILIARY MESHES FROM THE CURRENT SIMULATION STATE, ONLY WHILE VISIBLE */
+pub fn update(render: Res<DebugRender>, mut meshes: ResMut<Assets<Mesh>>, cloth: &Cloth) {
+    match render.mode {
+        DebugMode::Textured => {}
+        DebugMode::Wireframe => {
+            for (handle, kind) in &[
+                (&render.structural, ConstraintKind::Structural),
+                (&render.shear, ConstraintKind::Shear),
+                (&render.flexion, ConstraintKind::Flexion),
+            ] {
+                let positions = cloth
+                    .constraint_segments(*kind)
+                    .flat_map(|(p1, p2)| vec![to_array(p1), to_array(p2)])
+                    .collect();
+                if let Some(mesh) = meshes.get_mut(*handle) {
+                    write_vertices(mesh, positions);
+                }
+            }
+            for (handle, stiffer) in &[(&render.stiffer, true), (&render.softer, false)] {
+                let positions = cloth
+                    .painted_segments(*stiffer)
+                    .flat_map(|(p1, p2)| vec![to_array(p1), to_array(p2)])
+                    .collect();
+                if let Some(mesh) = meshes.get_mut(*handle) {
+                    write_vertices(mesh, positions);
+                }
+            }
+        }
+        DebugMode::Points => {
+            for (handle, fixed) in &[(&render.free, false), (&render.fixed, true)] {
+                let particles = &cloth.particles;
+                let positions = particles
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| particles.pinned(*i) == *fixed)
+                    .map(|(_, p)| to_array(cloth.to_world(*p)))
+                    .collect();
+                if let Some(mesh) = meshes.get_mut(*handle) {
+                    write_vertices(mesh, positions);
+                }
+            }
+        }
+    }
+}
+
+/* UNLIT DEBUG MESHES STILL NEED NORMALS AND UVS TO SATISFY THE PBR PIPELINE */
+pub fn write_vertices(mesh: &mut Mesh, positions: Vec<[f32; 3]>) {
+    let n = positions.len();
+    mesh.set_indices(Some(Indices::U32((0..n as u32).collect())));
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions.into());
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0., 0., 1.]; n].into());
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0., 0.]; n].into());
+}
@@ -0,0 +1,57 @@
+use std::fmt;
+
+use crate::{
+    grid::{GridError, GridIdx},
+    F, I,
+};
+
+/* WHAT THE FALLIBLE CLOTH APIS RETURN INSTEAD OF PANICKING ON BAD INPUT */
+/* THE BEVY SYSTEMS LOG THESE AND CARRY ON, THE HEADLESS RUNS PRINT THEM AND EXIT */
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClothError {
+    /* A CLOTH NEEDS A FINITE POSITIVE SIZE, AT LEAST 2 ROWS AND AT LEAST 1 COLUMN */
+    InvalidDimensions { size: (F, F), subdivisions: GridIdx },
+    IndexOutOfBounds { idx: GridIdx, subdivisions: GridIdx },
+    /* A QUERY POINT THAT IS NOT FINITE, OR NO FINITE PARTICLE TO ANSWER IT WITH */
+    NonFinitePosition,
+    /* A FRAME OF POSITIONS THAT DOES NOT HAVE ONE PER PARTICLE, E.G. RECORDED AT ANOTHER RESOLUTION */
+    SizeMismatch { expected: I, found: I },
+    MeshMissing,
+    /* from_mesh NEEDS A TRIANGLE LIST WITH Float3 POSITIONS AND Float2 UVS, IF ANY, FOR EVERY VERTEX */
+    UnsupportedMesh,
+    /* A CLOTH BUILT FROM A MESH HAS A SINGLE ROW OF PARTICLES, NOT A GRID TO CHANGE THE RESOLUTION OF */
+    NotAGrid,
+    /* TILING AND MIRRORING NEED AN UNTORN OPEN SHEET, NOT A ROPE, A PILLOW OR A CLOTH TORN APART */
+    NotASheet,
+    Grid(GridError),
+}
+
+impl fmt::Display for ClothError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClothError::InvalidDimensions { size: (w, h), subdivisions: (x, y) } => {
+                write!(f, "invalid cloth of size {}x{} with {}x{} particles", w, h, x, y)
+            }
+            ClothError::IndexOutOfBounds { idx: (x, y), subdivisions: (w, h) } => {
+                write!(f, "particle ({}, {}) is outside the {}x{} grid", x, y, w, h)
+            }
+            ClothError::NonFinitePosition => write!(f, "no finite position to work with"),
+            ClothError::SizeMismatch { expected, found } => {
+                write!(f, "expected {} positions, one per particle, got {}", expected, found)
+            }
+            ClothError::MeshMissing => write!(f, "the mesh of the cloth is missing"),
+            ClothError::UnsupportedMesh => write!(f, "only a triangle mesh with float positions can become a cloth"),
+            ClothError::NotAGrid => write!(f, "a cloth built from a mesh has no grid to change"),
+            ClothError::NotASheet => write!(f, "only an untorn open sheet of cloth can be tiled or mirrored"),
+            ClothError::Grid(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ClothError {}
+
+impl From<GridError> for ClothError {
+    fn from(err: GridError) -> Self {
+        ClothError::Grid(err)
+    }
+}
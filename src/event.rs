@@ -0,0 +1,69 @@
+use bevy::prelude::*;
+
+use crate::{cloth::Cloth, collider::ColliderId, grid::GridIdx, F, I};
+
+/* NOTABLE CHANGES TO A CLOTH, FOR GAME LOGIC TO REACT TO WITHOUT POLLING ITS STATE */
+/* THE CLOTH BUFFERS THEM DURING step AND THE OTHER MUTATIONS, emit FORWARDS THEM EVERY FRAME */
+
+// HOW LONG THE LIGHT STAYS BRIGHTENED AFTER THE CLOTH FIRST LANDS ON SOMETHING
+const FLASH_SECONDS: f32 = 0.3;
+
+#[derive(Clone, Copy, Debug)]
+pub enum ClothEvent {
+    /* A CUT REMOVED THE CONSTRAINT BETWEEN THESE PARTICLES */
+    Torn { p1: GridIdx, p2: GridIdx },
+    /* A PARTICLE STARTED TOUCHING A COLLIDER, REPORTED ONCE PER CONTACT RATHER THAN EVERY STEP */
+    CollisionContact { idx: GridIdx, collider: ColliderId, depth: F },
+    Pinned { idx: GridIdx },
+    Unpinned { idx: GridIdx },
+    /* THIS MANY PARTICLES FELL THROUGH THE KILL PLANE DURING A STEP AND WERE CAUGHT ON IT */
+    KillPlane { crossed: I },
+    /* KillMode::Respawn RESET THE CLOTH AFTER TOO MUCH OF IT FELL THROUGH THE KILL PLANE */
+    Respawned,
+}
+
+pub fn emit(mut events: ResMut<Events<ClothEvent>>, mut cloths: Query<&mut Cloth>) {
+    for mut cloth in cloths.iter_mut() {
+        cloth.drain_events().for_each(|e| events.send(e));
+    }
+}
+
+/* EXAMPLE CONSUMERS: LOG TEARS, AND FLASH THE LIGHT WHEN THE CLOTH FIRST HITS A COLLIDER */
+
+pub fn log_tears(mut reader: Local<EventReader<ClothEvent>>, events: Res<Events<ClothEvent>>) {
+    for event in reader.iter(&events) {
+        if let ClothEvent::Torn { p1, p2 } = event {
+            info!("cloth torn between {:?} and {:?}", p1, p2);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Flash {
+    landed: bool,
+    remaining: f32,
+    color: Option<Color>,
+}
+
+pub fn flash_on_contact(
+    time: Res<Time>,
+    mut flash: Local<Flash>,
+    mut reader: Local<EventReader<ClothEvent>>,
+    events: Res<Events<ClothEvent>>,
+    mut lights: Query<&mut Light>,
+) {
+    let contact = reader.iter(&events).any(|e| matches!(e, ClothEvent::CollisionContact { .. }));
+    if contact && !flash.landed {
+        flash.landed = true;
+        flash.remaining = FLASH_SECONDS;
+    }
+    if flash.remaining <= 0. {
+        return;
+    }
+
+    flash.remaining -= time.delta_seconds;
+    for mut light in lights.iter_mut() {
+        let color = *flash.color.get_or_insert(light.color);
+        light.color = if flash.remaining > 0. { color * 2. } else { color };
+    }
+}
@@ -0,0 +1,268 @@
+use std::{fs, io::ErrorKind, str::FromStr};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    bindings::{Action, FABRIC_SLOTS},
+    cloth::Cloth,
+    config::{ClothConfig, SpringDamping, Stiffness},
+    F,
+};
+
+/* NAMED BUNDLES OF THE MATERIAL PARAMETERS OF A CLOTH, SWAPPED ON THE LIVE CLOTHS WITH THE NUMBER KEYS */
+/* 1-4 PICK THE BUILT-IN FABRICS, 5-9 THE EXTRA ONES FROM assets/fabrics.ron IN FILE ORDER */
+
+pub const FABRICS_PATH: &str = "assets/fabrics.ron";
+
+#[derive(Clone, Copy, Debug)]
+pub struct Fabric {
+    pub stiffness: Stiffness,
+    pub damping: F,
+    pub spring_damping: SpringDamping,
+    pub air_drag: F,
+    pub particle_mass: F,
+    pub max_strain: Option<(F, F)>,
+}
+
+impl Fabric {
+    /* ONLY THE MATERIAL FIELDS ARE OVERWRITTEN, THE POSE, PINS AND SOLVER SETTINGS ARE KEPT */
+    pub fn apply(&self, config: &mut ClothConfig) {
+        config.stiffness = self.stiffness;
+        config.damping = self.damping;
+        config.spring_damping = self.spring_damping;
+        config.air_drag = self.air_drag;
+        config.particle_mass = self.particle_mass;
+        config.max_strain = self.max_strain;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FabricPreset {
+    /* LIGHT AND FLOWING, CATCHES THE AIR AND FOLDS INTO FINE WRINKLES */
+    Silk,
+    /* HEAVY AND STIFF AGAINST BENDING, BARELY STRETCHES */
+    Denim,
+    /* STRETCHES FAR AND SPRINGS BACK WITHOUT LOSING MUCH ENERGY */
+    RubberSheet,
+    /* VERY HEAVY, CANNOT STRETCH BUT SHEARS AND BENDS FREELY LIKE LINKED RINGS */
+    Chainmail,
+}
+
+impl FabricPreset {
+    pub const ALL: [FabricPreset; 4] =
+        [FabricPreset::Silk, FabricPreset::Denim, FabricPreset::RubberSheet, FabricPreset::Chainmail];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FabricPreset::Silk => "silk",
+            FabricPreset::Denim => "denim",
+            FabricPreset::RubberSheet => "rubber-sheet",
+            FabricPreset::Chainmail => "chainmail",
+        }
+    }
+
+    pub fn fabric(self) -> Fabric {
+        let stiffness = |warp, weft, shear, flexion| Stiffness { warp, weft, shear, flexion };
+        let spring_damping = |structural, shear, flexion| SpringDamping { structural, shear, flexion };
+        match self {
+            FabricPreset::Silk => Fabric {
+                stiffness: stiffness(0.9, 0.9, 0.3, 0.05),
+                damping: 0.998,
+                spring_damping: spring_damping(0.05, 0.05, 0.05),
+                air_drag: 0.3,
+                particle_mass: 0.3,
+                max_strain: Some((1.05, 1.05)),
+            },
+            FabricPreset::Denim => Fabric {
+                stiffness: stiffness(1., 1., 0.9, 0.6),
+                damping: 0.99,
+                spring_damping: spring_damping(0.2, 0.1, 0.1),
+                air_drag: 0.05,
+                particle_mass: 2.,
+                max_strain: Some((1.02, 1.01)),
+            },
+            FabricPreset::RubberSheet => Fabric {
+                stiffness: stiffness(0.3, 0.3, 0.3, 0.2),
+                damping: 0.995,
+                spring_damping: SpringDamping::default(),
+                air_drag: 0.,
+                particle_mass: 1.5,
+                max_strain: None,
+            },
+            FabricPreset::Chainmail => Fabric {
+                stiffness: stiffness(1., 1., 0.1, 0.),
+                damping: 0.995,
+                spring_damping: spring_damping(0.3, 0.3, 0.3),
+                air_drag: 0.,
+                particle_mass: 6.,
+                max_strain: Some((1., 1.)),
+            },
+        }
+    }
+}
+
+impl FromStr for FabricPreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FabricPreset::ALL
+            .iter()
+            .copied()
+            .find(|preset| preset.name() == s)
+            .ok_or_else(|| format!("unknown fabric '{}'", s))
+    }
+}
+
+/* ONE ENTRY OF assets/fabrics.ron, WITH THE ARRAYS IN THE SAME ORDER AS IN assets/cloth.ron */
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FabricFile {
+    name: String,
+    /* WARP, WEFT, SHEAR, FLEXION */
+    stiffness: [F; 4],
+    damping: F,
+    /* STRUCTURAL, SHEAR, FLEXION */
+    spring_damping: [F; 3],
+    air_drag: F,
+    particle_mass: F,
+    max_strain: Option<(F, F)>,
+}
+
+impl FabricFile {
+    fn fabric(&self) -> Result<Fabric, String> {
+        let unit = |x: F| (0. ..=1.).contains(&x);
+        let [warp, weft, shear, flexion] = self.stiffness;
+        let [structural, spring_shear, spring_flexion] = self.spring_damping;
+        if !self.stiffness.iter().all(|&k| unit(k)) {
+            return Err(format!("{}: stiffness must be between 0 and 1", self.name));
+        }
+        if !unit(self.damping) {
+            return Err(format!("{}: damping must be between 0 and 1", self.name));
+        }
+        if !(self.particle_mass > 0.) {
+            return Err(format!("{}: particle_mass must be positive", self.name));
+        }
+        if self.air_drag < 0. {
+            return Err(format!("{}: air_drag must not be negative", self.name));
+        }
+        if self.max_strain.map_or(false, |(warp, weft)| warp < 1. || weft < 1.) {
+            return Err(format!("{}: max_strain must be at least 1", self.name));
+        }
+        Ok(Fabric {
+            stiffness: Stiffness { warp, weft, shear, flexion },
+            damping: self.damping,
+            spring_damping: SpringDamping { structural, shear: spring_shear, flexion: spring_flexion },
+            air_drag: self.air_drag,
+            particle_mass: self.particle_mass,
+            max_strain: self.max_strain,
+        })
+    }
+}
+
+/* THE EXTRA FABRICS OF A RON FILE, NONE IF IT DOES NOT EXIST */
+pub fn load(path: &str) -> Result<Vec<(String, Fabric)>, String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err.to_string()),
+    };
+    let files: Vec<FabricFile> = ron::de::from_str(&text).map_err(|err| err.to_string())?;
+    files.iter().map(|file| file.fabric().map(|fabric| (file.name.clone(), fabric))).collect()
+}
+
+/* THE BUILT-IN FABRICS FOLLOWED BY THOSE OF FABRICS_PATH, ONE PER NUMBER KEY */
+pub struct Fabrics(pub Vec<(String, Fabric)>);
+
+impl Default for Fabrics {
+    fn default() -> Self {
+        let mut fabrics: Vec<_> = FabricPreset::ALL.iter().map(|p| (p.name().to_string(), p.fabric())).collect();
+        match load(FABRICS_PATH) {
+            Ok(extra) => fabrics.extend(extra),
+            Err(err) => error!("failed to read {}, only the built-in fabrics are available: {}", FABRICS_PATH, err),
+        }
+        if fabrics.len() > FABRIC_SLOTS {
+            warn!("only the first {} fabrics get a number key", FABRIC_SLOTS);
+        }
+        Self(fabrics)
+    }
+}
+
+/* SWAP THE FABRIC OF EVERY CLOTH IN PLACE, AND OF THE CLOTHS SPAWNED FROM NOW ON */
+pub fn swap(
+    actions: Res<Input<Action>>,
+    fabrics: Res<Fabrics>,
+    mut config: ResMut<ClothConfig>,
+    mut cloths: Query<&mut Cloth>,
+) {
+    let chosen = fabrics.0.iter().enumerate().find(|(i, _)| actions.just_pressed(Action::Fabric(i + 1)));
+    let (name, fabric) = match chosen {
+        Some((_, entry)) => entry,
+        None => return,
+    };
+    info!("fabric: {}", name);
+    fabric.apply(&mut config);
+    for mut cloth in cloths.iter_mut() {
+        let mut own = cloth.config.clone();
+        fabric.apply(&mut own);
+        cloth.apply_config(own);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cloth::{ClothSim, SIZE, SUBDIVISIONS},
+        I,
+    };
+
+    /* A PRESET SETS THE MATERIAL FIELDS OF ITS FABRIC AND LEAVES THE REST AT THE DEFAULTS, AND THE PRESETS */
+    /* KEEP THE CHARACTER THEIR NAMES PROMISE */
+    #[test]
+    fn presets_set_only_their_material() {
+        let defaults = ClothConfig::default();
+        for &preset in &FabricPreset::ALL {
+            let (fabric, config) = (preset.fabric(), ClothConfig::from_preset(preset));
+            assert_eq!(config.stiffness, fabric.stiffness, "{}", preset.name());
+            assert_eq!(config.damping, fabric.damping, "{}", preset.name());
+            assert_eq!(config.spring_damping.flexion, fabric.spring_damping.flexion, "{}", preset.name());
+            assert_eq!(config.air_drag, fabric.air_drag, "{}", preset.name());
+            assert_eq!(config.particle_mass, fabric.particle_mass, "{}", preset.name());
+            assert_eq!(config.max_strain, fabric.max_strain, "{}", preset.name());
+            assert_eq!((config.gravity, config.max_iterations), (defaults.gravity, defaults.max_iterations));
+            assert_eq!(preset.name().parse(), Ok(preset));
+        }
+
+        let mass = |preset: FabricPreset| preset.fabric().particle_mass;
+        let [silk, denim, rubber, chainmail] = FabricPreset::ALL;
+        assert!(mass(silk) < mass(rubber) && mass(rubber) < mass(denim) && mass(denim) < mass(chainmail));
+        assert!(silk.fabric().air_drag > denim.fabric().air_drag);
+        assert_eq!(rubber.fabric().max_strain, None);
+        assert_eq!(chainmail.fabric().max_strain, Some((1., 1.)));
+        assert!(chainmail.fabric().stiffness.shear < denim.fabric().stiffness.shear);
+    }
+
+    /* EVERY BUILT-IN FABRIC AND THOSE OF FABRICS_PATH HANGS FROM ITS CORNERS IN THE WIND AND ENDS WITH FINITE */
+    /* POSITIONS, NO RECOVERIES AND BOUNDED STRAIN */
+    #[test]
+    fn fabrics_stay_stable() {
+        // STEPS EACH FABRIC HANGS FOR, AND THE LARGEST STRAIN IT MAY END WITH, WELL PAST ANY OF THEIR STRAIN LIMITS
+        const STEPS: I = 200;
+        const MAX_STRAIN: F = 1.;
+
+        let built_in = FabricPreset::ALL.iter().map(|&p| (p.name().to_string(), ClothConfig::from_preset(p)));
+        let extra = load(FABRICS_PATH).unwrap().into_iter().map(|(name, fabric)| {
+            let mut config = ClothConfig::default();
+            fabric.apply(&mut config);
+            (name, config)
+        });
+        for (name, config) in built_in.chain(extra) {
+            let mut cloth = ClothSim::new(SIZE.0, SIZE.1, (SUBDIVISIONS, SUBDIVISIONS), config).unwrap();
+            (0..STEPS).for_each(|_| cloth.step(&[]));
+            assert!(cloth.particles.iter().all(|p| p.coords.iter().all(|c| c.is_finite())), "{} is not finite", name);
+            assert_eq!(cloth.stats.recoveries, 0, "{} needed recovering", name);
+            assert!(cloth.max_strain() <= MAX_STRAIN, "{} ended with a max strain of {}", name, cloth.max_strain());
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::str::FromStr;
+
+use bevy::prelude::*;
+
+use crate::{bvh::Aabb, F, P, V};
+
+/* SCENE-LEVEL FORCE FIELDS: ENTITIES WITH A ForceField ARE GATHERED BEFORE EVERY FRAME, AND EACH */
+/* CLOTH SAMPLES ALL OF THEM AT THE WORLD POSITION OF EVERY PARTICLE BEFORE IT STEPS */
+/* THE FIELDS ARE FORCES LIKE THE WIND AND THE ATTRACTORS, SO HEAVIER PARTICLES RESPOND LESS */
+
+#[derive(Clone, Copy, Debug)]
+pub enum FieldKind {
+    /* THE SAME FORCE EVERYWHERE */
+    Uniform(V),
+    /* AWAY FROM center, OR TOWARDS IT FOR A NEGATIVE strength. strength AT THE CENTER, FALLING OFF */
+    /* AS 1 / (1 + (d / falloff)^2) WITH THE DISTANCE d, A falloff OF 0 DOES NOT FALL OFF */
+    Radial { center: P, strength: F, falloff: F },
+    /* strength AROUND THE LINE THROUGH axis_point ALONG axis_dir, WHATEVER THE DISTANCE FROM IT */
+    /* COUNTER-CLOCKWISE SEEN FROM THE TIP OF axis_dir, ZERO ON THE AXIS ITSELF */
+    Vortex { axis_point: P, axis_dir: V, strength: F },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ForceField {
+    pub kind: FieldKind,
+    /* WORLD BOX OUTSIDE WHICH THE FIELD IS ZERO, None REACHES EVERYWHERE */
+    pub bounds: Option<Aabb>,
+}
+
+impl ForceField {
+    pub fn new(kind: FieldKind) -> Self {
+        Self { kind, bounds: None }
+    }
+
+    pub fn within(self, bounds: Aabb) -> Self {
+        Self { bounds: Some(bounds), ..self }
+    }
+
+    /* FORCE ON A PARTICLE AT THE WORLD POSITION p */
+    pub fn sample(&self, p: P) -> V {
+        if self.bounds.map_or(false, |b| !b.intersects(&Aabb::around(p, 0.))) {
+            return V::zeros();
+        }
+        match self.kind {
+            FieldKind::Uniform(force) => force,
+            FieldKind::Radial { center, strength, falloff } => {
+                let d = p - center;
+                let dist = d.norm();
+                if dist <= F::EPSILON {
+                    return V::zeros();
+                }
+                let scale = if falloff > 0. { 1. + (dist / falloff).powi(2) } else { 1. };
+                d * (strength / (dist * scale))
+            }
+            FieldKind::Vortex { axis_point, axis_dir, strength } => {
+                let axis = match axis_dir.try_normalize(F::EPSILON) {
+                    Some(axis) => axis,
+                    None => return V::zeros(),
+                };
+                let r = p - axis_point;
+                let tangent = axis.cross(&(r - axis * axis.dot(&r)));
+                tangent.try_normalize(F::EPSILON).map_or(V::zeros(), |t| t * strength)
+            }
+        }
+    }
+}
+
+// FORCE OF THE EXAMPLE FIELDS, ABOUT THAT OF THE DEFAULT WIND
+const EXAMPLE_STRENGTH: F = 0.3;
+
+/* ONE FIELD OF EACH KIND TO TRY ON THE MAIN CLOTH WITH --field */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldExample {
+    /* A STEADY SIDEWAYS PUSH */
+    Uniform,
+    /* BLOWING OUTWARDS FROM THE MIDDLE OF THE CLOTH */
+    Radial,
+    /* SWIRLING THE CLOTH AROUND A VERTICAL AXIS THROUGH ITS MIDDLE */
+    Vortex,
+}
+
+impl FromStr for FieldExample {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "uniform" => Ok(FieldExample::Uniform),
+            "radial" => Ok(FieldExample::Radial),
+            "vortex" => Ok(FieldExample::Vortex),
+            _ => Err(format!("unknown force field '{}'", s)),
+        }
+    }
+}
+
+impl FieldExample {
+    /* THE FIELD CENTERED ON A CLOTH'S BOX, REACHING HALF THE BOX'S DIAGONAL PAST IT */
+    pub fn around(self, bounds: &Aabb) -> ForceField {
+        let (center, size) = (nalgebra::center(&bounds.min, &bounds.max), (bounds.max - bounds.min).norm());
+        let kind = match self {
+            FieldExample::Uniform => FieldKind::Uniform(V::x() * EXAMPLE_STRENGTH),
+            FieldExample::Radial => FieldKind::Radial { center, strength: EXAMPLE_STRENGTH, falloff: size / 4. },
+            FieldExample::Vortex => {
+                FieldKind::Vortex { axis_point: center, axis_dir: V::y(), strength: EXAMPLE_STRENGTH }
+            }
+        };
+        ForceField::new(kind).within(bounds.inflated(size / 2.))
+    }
+}
+
+/* THE FIELDS OF THE CURRENT FRAME, HANDED TO EVERY CLOTH BY THE STEP SYSTEM */
+#[derive(Default)]
+pub struct ForceFields(pub Vec<ForceField>);
+
+pub fn collect(mut fields: ResMut<ForceFields>, query: Query<&ForceField>) {
+    fields.0.clear();
+    fields.0.extend(query.iter().copied());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* A RADIAL FIELD PUSHES AWAY AT ITS STRENGTH AT THE CENTER AND AT HALF OF IT ONE falloff OUT, A */
+    /* VORTEX TURNS COUNTER-CLOCKWISE ABOUT ITS AXIS AT ANY DISTANCE, AND NEITHER REACHES PAST ITS BOX */
+    #[test]
+    fn fields_sample_their_shape() {
+        // STRENGTH OF THE FIELDS, AND THE DISTANCE OVER WHICH THE RADIAL ONE FALLS TO HALF
+        const STRENGTH: F = 2.;
+        const FALLOFF: F = 3.;
+
+        let close = |a: V, b: V| (a - b).norm() < 1e-5;
+        let center = P::new(1., 0., 0.);
+        let radial = ForceField::new(FieldKind::Radial { center, strength: STRENGTH, falloff: FALLOFF });
+        assert!(close(radial.sample(center + V::y() * 1e-3), V::y() * STRENGTH));
+        assert!(close(radial.sample(center - V::z() * FALLOFF), -V::z() * STRENGTH / 2.));
+        assert_eq!(radial.sample(center), V::zeros());
+
+        let vortex = FieldKind::Vortex { axis_point: P::origin(), axis_dir: V::y() * 5., strength: STRENGTH };
+        let vortex = ForceField::new(vortex);
+        assert!(close(vortex.sample(P::new(1., 7., 0.)), -V::z() * STRENGTH));
+        assert!(close(vortex.sample(P::new(0., -2., 40.)), V::x() * STRENGTH));
+        assert_eq!(vortex.sample(P::new(0., 3., 0.)), V::zeros());
+
+        let boxed = ForceField::new(FieldKind::Uniform(V::x())).within(Aabb::around(P::origin(), 1.));
+        assert_eq!((boxed.sample(P::new(0.5, 0.5, -0.5)), boxed.sample(P::new(0., 1.5, 0.))), (V::x(), V::zeros()));
+    }
+}
@@ -0,0 +1,300 @@
+use bevy::prelude::*;
+
+use crate::{
+    bindings::Action,
+    bvh::Aabb,
+    cloth::Cloth,
+    collider::{Collider, ColliderId, Colliders, DEFAULT_LAYER},
+    config::{ClothConfig, PinPreset, Pose},
+    fabric::FabricPreset,
+    grid::GridIdx,
+    spawn_cloth_at,
+    stitch::Stitches,
+    to_isometry, to_vec3, F, P, V,
+};
+
+/* EXAMPLE: A CAPE WITH A LINING DRAPED OVER A MANNEQUIN OF CAPSULES AND A SPHERE THAT TURNS AND BOBS */
+/* THE TOP OF THE CAPE IS ATTACHED TO THE RIG AND THE TOP OF THE LINING IS SEWN TO THE CAPE, Y TAKES */
+/* THE GARMENT OFF THE MANNEQUIN AND PUTS A FRESH ONE BACK ON */
+
+// COLLISION LAYERS, THE RIG PUSHES BOTH CLOTHS AND EACH CLOTH PUSHES THE OTHER BUT NOT ITSELF
+pub const RIG_LAYER: u32 = 1 << 8;
+pub const OUTER_LAYER: u32 = 1 << 9;
+pub const INNER_LAYER: u32 = 1 << 10;
+
+// WHERE THE BASE OF THE NECK STANDS, AWAY FROM THE OTHER EXAMPLES
+const ORIGIN: [F; 3] = [30., 0., -20.];
+const TURN_AMPLITUDE: f32 = 0.6;
+const TURN_FREQUENCY: f32 = 0.2;
+const BOB_AMPLITUDE: f32 = 0.15;
+
+const HEAD_RADIUS: F = 0.45;
+const SHOULDER_RADIUS: F = 0.3;
+const TORSO_RADIUS: F = 0.55;
+const ARM_RADIUS: F = 0.18;
+const TORSO_LENGTH: F = 2.6;
+
+const OUTER_FRICTION: F = 0.5;
+// SILK SLIDES OVER THE BODY INSTEAD OF STICKING TO IT
+const INNER_FRICTION: F = 0.15;
+
+/* COLLIDERS OF THE RIG IN ITS OWN FRAME, MOVED TO ITS GlobalTransform BY follow EVERY FRAME */
+pub struct Mannequin {
+    parts: Vec<(ColliderId, Collider)>,
+    rest: Vec3,
+    garment: Option<Garment>,
+}
+
+/* THE TWO CLOTHS SPAWNED BY A GarmentBundle */
+#[derive(Clone, Copy, Debug)]
+pub struct Garment {
+    pub outer: Entity,
+    pub inner: Entity,
+}
+
+impl Garment {
+    /* REMOVES THE SEAM BETWEEN THE LAYERS ALONG WITH THE CLOTHS, THEIR MESHES AND THEIR MATERIALS */
+    pub fn despawn(
+        &self,
+        commands: &mut Commands,
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<StandardMaterial>,
+        stitches: &mut Stitches,
+        cloths: &Query<&Cloth>,
+    ) {
+        for &entity in &[self.outer, self.inner] {
+            stitches.remove_cloth(entity);
+            if let Ok(cloth) = cloths.get(entity) {
+                cloth.despawn(entity, commands, meshes, materials);
+            }
+        }
+    }
+}
+
+/* TWO LAYERS OF THE SAME SIZE HANGING BEHIND A RIG, THE INNER ONE gap CLOSER TO THE BODY */
+/* anchor IS THE TOP LEFT CORNER OF THE OUTER LAYER IN THE FRAME OF THE RIG */
+#[derive(Clone, Debug)]
+pub struct GarmentBundle {
+    pub size: (F, F),
+    pub subdivisions: GridIdx,
+    pub anchor: V,
+    pub gap: F,
+    pub outer: FabricPreset,
+    pub inner: FabricPreset,
+    pub config: ClothConfig,
+}
+
+impl GarmentBundle {
+    /* A CAPE ACROSS THE BACK OF THE EXAMPLE MANNEQUIN */
+    pub fn cape(config: &ClothConfig) -> Self {
+        let (width, gap) = (2.6, 1.25 * config.thickness);
+        Self {
+            size: (width, 3.4),
+            subdivisions: (16, 20),
+            anchor: V::new(-width / 2., 0.05, -(TORSO_RADIUS + 2. * gap)),
+            gap,
+            outer: FabricPreset::Denim,
+            inner: FabricPreset::Silk,
+            config: config.clone(),
+        }
+    }
+
+    fn layer(&self, preset: FabricPreset, origin: V, layer: u32, other: u32, friction: F) -> Option<Cloth> {
+        let mut config = ClothConfig {
+            pose: Pose::HangingVertical,
+            origin,
+            pin_preset: PinPreset::Free,
+            layer,
+            collision_mask: DEFAULT_LAYER | RIG_LAYER | other,
+            friction,
+            ..self.config.clone()
+        };
+        preset.fabric().apply(&mut config);
+        let (width, height) = self.size;
+        match Cloth::new(width, height, self.subdivisions, config) {
+            Ok(cloth) => Some(cloth),
+            Err(err) => {
+                error!("failed to build a {} layer of a garment: {}", preset.name(), err);
+                None
+            }
+        }
+    }
+
+    /* BOTH CLOTHS ARE BUILT IN THE FRAME OF THE RIG AND SPAWNED AT ITS CURRENT TRANSFORM, SO THE */
+    /* ATTACHMENT OFFSETS ARE JUST THE REST POSITIONS OF THE TOP ROW */
+    pub fn spawn(
+        self,
+        commands: &mut Commands,
+        meshes: &mut Assets<Mesh>,
+        stitches: &mut Stitches,
+        rig: (Entity, Transform),
+        materials: (Handle<StandardMaterial>, Handle<StandardMaterial>),
+    ) -> Option<Garment> {
+        let (entity, transform) = rig;
+        let inner_origin = self.anchor + V::z() * self.gap;
+        let mut outer = self.layer(self.outer, self.anchor, OUTER_LAYER, INNER_LAYER, OUTER_FRICTION)?;
+        let mut inner = self.layer(self.inner, inner_origin, INNER_LAYER, OUTER_LAYER, INNER_FRICTION)?;
+        outer.own_material(materials.0.clone());
+        inner.own_material(materials.1.clone());
+
+        let offsets = outer.particles.row(0).map(|p| p.coords).collect();
+        outer.bind_row(0, entity, offsets);
+        let top: Vec<_> = (0..outer.subdivisions().0).map(|x| (x, 0)).collect();
+
+        spawn_cloth_at(commands, meshes, outer, materials.0, transform);
+        let outer = commands.current_entity()?;
+        spawn_cloth_at(commands, meshes, inner, materials.1, transform);
+        let inner = commands.current_entity()?;
+        /* REST LENGTH IS THE GAP SO THE SEAM HOLDS THE LAYERS APART INSTEAD OF FIGHTING THE CONTACTS */
+        stitches.sew(outer, &top, inner, &top, self.gap);
+        Some(Garment { outer, inner })
+    }
+}
+
+/* Y ROTATED ONTO THE SEGMENT, SO A UNIT SPHERE SCALED ALONG Y COVERS A CAPSULE */
+fn along(a: P, b: P) -> Quat {
+    let dir = to_vec3(b - a).normalize();
+    let axis = Vec3::unit_y().cross(dir);
+    if axis.length() < 1e-6 {
+        return Quat::identity();
+    }
+    Quat::from_axis_angle(axis.normalize(), Vec3::unit_y().dot(dir).acos())
+}
+
+fn part_transform(part: &Collider) -> Transform {
+    match *part {
+        Collider::Sphere { center, radius } => Transform {
+            translation: to_vec3(center.coords),
+            rotation: Quat::identity(),
+            scale: Vec3::splat(radius as f32),
+        },
+        Collider::Capsule { a, b, radius } => Transform {
+            translation: to_vec3(nalgebra::center(&a, &b).coords),
+            rotation: along(a, b),
+            scale: Vec3::new(radius as f32, ((b - a).norm() / 2. + radius) as f32, radius as f32),
+        },
+        _ => Transform::default(),
+    }
+}
+
+fn rig_parts() -> Vec<Collider> {
+    let arm = |side: F| Collider::Capsule {
+        a: P::new(side * 0.95, 0., 0.),
+        b: P::new(side * 1.1, -2.2, 0.),
+        radius: ARM_RADIUS,
+    };
+    vec![
+        Collider::Sphere { center: P::new(0., 0.35 + HEAD_RADIUS, 0.), radius: HEAD_RADIUS },
+        Collider::Capsule { a: P::new(-0.9, 0., 0.), b: P::new(0.9, 0., 0.), radius: SHOULDER_RADIUS },
+        Collider::Capsule { a: P::new(0., -0.3, 0.), b: P::new(0., -TORSO_LENGTH, 0.), radius: TORSO_RADIUS },
+        arm(-1.),
+        arm(1.),
+    ]
+}
+
+/* THE WORLD BOX AROUND THE MANNEQUIN AND THE GARMENT HANGING OFF IT, FOR FRAMING THE CAMERA */
+pub fn bounds() -> Aabb {
+    let center = P::from(V::from(ORIGIN)) - V::y() * TORSO_LENGTH / 2.;
+    Aabb::around(center, 2.5)
+}
+
+pub fn spawn(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    colliders: &mut Colliders,
+    stitches: &mut Stitches,
+    config: &ClothConfig,
+) {
+    let rest = to_vec3(V::from(ORIGIN));
+    let iso = to_isometry(rest, Quat::identity());
+    let parts: Vec<_> = rig_parts()
+        .into_iter()
+        .map(|part| (colliders.add_on_layer(part.transformed(&iso), RIG_LAYER), part))
+        .collect();
+
+    let sphere = meshes.add(Mesh::from(shape::Icosphere { radius: 1., subdivisions: 3 }));
+    let skin = materials.add(Color::rgb(0.8, 0.75, 0.7).into());
+    let shapes: Vec<_> = parts.iter().map(|(_, part)| part_transform(part)).collect();
+    commands.spawn((Transform::from_translation(rest), GlobalTransform::default()));
+    let rig = match commands.current_entity() {
+        Some(rig) => rig,
+        None => return,
+    };
+    commands.with_children(|parent| {
+        for transform in shapes {
+            parent.spawn(PbrComponents {
+                mesh: sphere.clone(),
+                material: skin.clone(),
+                transform,
+                ..Default::default()
+            });
+        }
+    });
+
+    let dress = garment_materials(materials);
+    let garment = GarmentBundle::cape(config).spawn(
+        commands,
+        meshes,
+        stitches,
+        (rig, Transform::from_translation(rest)),
+        dress,
+    );
+    commands.insert_one(rig, Mannequin { parts, rest, garment });
+}
+
+/* OXBLOOD OUTSIDE, GOLD INSIDE */
+fn garment_materials(materials: &mut Assets<StandardMaterial>) -> (Handle<StandardMaterial>, Handle<StandardMaterial>) {
+    (materials.add(Color::rgb(0.5, 0.08, 0.1).into()), materials.add(Color::rgb(0.85, 0.7, 0.3).into()))
+}
+
+pub fn animate(time: Res<Time>, mut rigs: Query<(&Mannequin, &mut Transform)>) {
+    let t = 2. * std::f32::consts::PI * TURN_FREQUENCY * time.seconds_since_startup as f32;
+    for (rig, mut transform) in rigs.iter_mut() {
+        transform.rotation = Quat::from_rotation_y(TURN_AMPLITUDE * t.sin());
+        transform.translation = rig.rest + Vec3::new(0., BOB_AMPLITUDE * (2. * t).sin(), 0.);
+    }
+}
+
+/* RUNS BEFORE THE SIMULATION STEP LIKE attach::follow, SO THE CLOTHS SEE THE BODY WHERE IT IS NOW */
+pub fn follow(mut colliders: ResMut<Colliders>, rigs: Query<(&Mannequin, &GlobalTransform)>) {
+    for (rig, transform) in rigs.iter() {
+        let iso = to_isometry(transform.translation, transform.rotation);
+        for (id, part) in &rig.parts {
+            if let Some(layered) = colliders.0.get_mut(*id) {
+                layered.collider = part.transformed(&iso);
+            }
+        }
+    }
+}
+
+pub fn toggle(
+    mut commands: Commands,
+    actions: Res<Input<Action>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut stitches: ResMut<Stitches>,
+    config: Res<ClothConfig>,
+    cloths: Query<&Cloth>,
+    mut rigs: Query<(Entity, &mut Mannequin, &GlobalTransform)>,
+) {
+    if !actions.just_pressed(Action::Garment) {
+        return;
+    }
+
+    for (entity, mut rig, transform) in rigs.iter_mut() {
+        match rig.garment.take() {
+            Some(garment) => garment.despawn(&mut commands, &mut meshes, &mut materials, &mut stitches, &cloths),
+            None => {
+                let at = Transform {
+                    translation: transform.translation,
+                    rotation: transform.rotation,
+                    scale: Vec3::one(),
+                };
+                let dress = garment_materials(&mut materials);
+                rig.garment =
+                    GarmentBundle::cape(&config).spawn(&mut commands, &mut meshes, &mut stitches, (entity, at), dress);
+            }
+        }
+    }
+}
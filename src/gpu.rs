@@ -0,0 +1,345 @@
+use bevy::render::shader::{glsl_to_spirv, ShaderStage};
+use futures_lite::future;
+
+use crate::{
+    cloth::{ConstraintKind, Weave},
+    config::ClothConfig,
+    particles::Particles,
+    F, I, P, V,
+};
+
+/* EXPERIMENTAL GPU SOLVER: JACOBI CONSTRAINT ITERATIONS AND VERLET INTEGRATION IN COMPUTE SHADERS */
+/* BEVY DOES NOT EXPOSE COMPUTE PASSES IN THIS VERSION, SO THIS RUNS ON ITS OWN wgpu DEVICE AND */
+/* THE POSITIONS MAKE A ROUND TRIP EVERY STEP: UPLOAD, ALL ITERATIONS IN ONE SUBMISSION, READBACK */
+/* THE READBACK STALLS THE CPU UNTIL THE GPU IS DONE, SO THIS ONLY PAYS OFF FOR LARGE CLOTHS */
+
+const JACOBI_SHADER: &str = include_str!("../assets/shaders/jacobi.comp");
+const INTEGRATE_SHADER: &str = include_str!("../assets/shaders/integrate.comp");
+
+// INVOCATIONS PER WORKGROUP, MUST MATCH local_size_x IN THE SHADERS
+const WORKGROUP_SIZE: I = 64;
+
+// BYTES PER PARTICLE IN THE vec4 BUFFERS, AND OF THE UNIFORM PARAMETERS
+const VEC4_SIZE: u64 = 16;
+const PARAMS_SIZE: u64 = 32;
+
+// BYTES PER LINK: OTHER PARTICLE, REST LENGTH AND STIFFNESS SLOT
+const LINK_SIZE: usize = 12;
+
+/* BUFFERS SIZED FOR THE CURRENT PARTICLES AND CONSTRAINTS, REBUILT WHEN EITHER CHANGES */
+struct Buffers {
+    count: I,
+    params: wgpu::Buffer,
+    /* PING-PONG POSITIONS: ITERATIONS READ ONE AND WRITE THE OTHER, INTEGRATION RUNS ON THE FIRST */
+    positions: [wgpu::Buffer; 2],
+    old_positions: wgpu::Buffer,
+    accelerations: wgpu::Buffer,
+    staging: wgpu::Buffer,
+    /* ITERATION FROM EACH POSITION BUFFER INTO THE OTHER, AND INTEGRATION */
+    jacobi: [wgpu::BindGroup; 2],
+    integrate: wgpu::BindGroup,
+    /* KEEPS THE CONSTRAINT BUFFERS ALIVE FOR THE BIND GROUPS */
+    _links: [wgpu::Buffer; 2],
+}
+
+pub struct GpuSolver {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    jacobi: (wgpu::ComputePipeline, wgpu::BindGroupLayout),
+    integrate: (wgpu::ComputePipeline, wgpu::BindGroupLayout),
+    buffers: Option<Buffers>,
+}
+
+fn f32_bytes(values: impl Iterator<Item = f32>) -> Vec<u8> {
+    values.flat_map(|v| v.to_le_bytes().to_vec()).collect()
+}
+
+fn u32_bytes(values: impl Iterator<Item = u32>) -> Vec<u8> {
+    values.flat_map(|v| v.to_le_bytes().to_vec()).collect()
+}
+
+fn storage_entry(binding: u32, readonly: bool) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStage::COMPUTE,
+        ty: wgpu::BindingType::StorageBuffer {
+            dynamic: false,
+            min_binding_size: None,
+            readonly,
+        },
+        count: None,
+    }
+}
+
+fn uniform_entry() -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStage::COMPUTE,
+        ty: wgpu::BindingType::UniformBuffer {
+            dynamic: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+impl GpuSolver {
+    /* FAILS WITHOUT A SUITABLE ADAPTER, IN WHICH CASE THE CALLER STAYS ON THE CPU */
+    pub fn new() -> Result<Self, String> {
+        let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY);
+        let adapter = future::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: None,
+        }))
+        .ok_or_else(|| "no gpu adapter found".to_string())?;
+        let (device, queue) = future::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                features: wgpu::Features::empty(),
+                limits: wgpu::Limits::default(),
+                shader_validation: true,
+            },
+            None,
+        ))
+        .map_err(|err| format!("failed to open the gpu: {:?}", err))?;
+
+        let jacobi_layout = [
+            uniform_entry(),
+            storage_entry(1, true),
+            storage_entry(2, false),
+            storage_entry(3, true),
+            storage_entry(4, true),
+        ];
+        let integrate_layout =
+            [uniform_entry(), storage_entry(1, false), storage_entry(2, false), storage_entry(3, true)];
+        let jacobi = Self::pipeline(&device, JACOBI_SHADER, &jacobi_layout)?;
+        let integrate = Self::pipeline(&device, INTEGRATE_SHADER, &integrate_layout)?;
+        Ok(Self { device, queue, jacobi, integrate, buffers: None })
+    }
+
+    fn pipeline(
+        device: &wgpu::Device,
+        source: &str,
+        entries: &[wgpu::BindGroupLayoutEntry],
+    ) -> Result<(wgpu::ComputePipeline, wgpu::BindGroupLayout), String> {
+        let spirv = glsl_to_spirv(source, ShaderStage::Compute, None).map_err(|err| format!("{:?}", err))?;
+        let module = device.create_shader_module(wgpu::ShaderModuleSource::SpirV(spirv.into()));
+        let bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor { label: None, entries });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            layout: Some(&layout),
+            compute_stage: wgpu::ProgrammableStageDescriptor { module: &module, entry_point: "main" },
+        });
+        Ok((pipeline, bind_group_layout))
+    }
+
+    fn buffer(&self, size: u64, usage: wgpu::BufferUsage) -> wgpu::Buffer {
+        self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: size.max(VEC4_SIZE),
+            usage,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn bind_group(&self, layout: &wgpu::BindGroupLayout, buffers: &[&wgpu::Buffer]) -> wgpu::BindGroup {
+        let entries: Vec<_> = buffers
+            .iter()
+            .enumerate()
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: wgpu::BindingResource::Buffer(buffer.slice(..)),
+            })
+            .collect();
+        self.device.create_bind_group(&wgpu::BindGroupDescriptor { label: None, layout, entries: &entries })
+    }
+
+    /* UPLOAD THE CONSTRAINTS OF count PARTICLES AS (p1, p2, REST LENGTH, KIND, WEAVE), GROUPED BY PARTICLE */
+    /* EVERY CONSTRAINT IS LISTED UNDER BOTH OF ITS ENDS SINCE EACH INVOCATION ONLY WRITES ITS OWN PARTICLE */
+    /* THE STIFFNESS ITSELF IS A PARAMETER OF EVERY step, SO RETUNING IT NEEDS NO NEW UPLOAD */
+    pub fn set_constraints(&mut self, count: I, constraints: impl Iterator<Item = (I, I, F, ConstraintKind, Weave)>) {
+        let mut links = vec![vec![]; count];
+        for (p1, p2, rest, kind, weave) in constraints {
+            /* INDEX INTO THE (WARP, WEFT, SHEAR, FLEXION) STIFFNESS VECTOR OF THE SHADER */
+            let slot: u32 = match (kind, weave) {
+                (ConstraintKind::Structural, Weave::Warp) => 0,
+                (ConstraintKind::Structural, Weave::Weft) => 1,
+                (ConstraintKind::Shear, _) => 2,
+                (ConstraintKind::Flexion, _) => 3,
+            };
+            links[p1].push((p2, rest, slot));
+            links[p2].push((p1, rest, slot));
+        }
+        let mut offsets = vec![0u32];
+        let mut flat = Vec::with_capacity(LINK_SIZE * links.iter().map(Vec::len).sum::<I>());
+        for particle in &links {
+            for &(other, rest, slot) in particle {
+                flat.extend_from_slice(&(other as u32).to_le_bytes());
+                flat.extend_from_slice(&(rest as f32).to_le_bytes());
+                flat.extend_from_slice(&slot.to_le_bytes());
+            }
+            offsets.push(offsets[offsets.len() - 1] + particle.len() as u32);
+        }
+
+        use wgpu::BufferUsage as U;
+        let vec4s = count as u64 * VEC4_SIZE;
+        let params = self.buffer(PARAMS_SIZE, U::UNIFORM | U::COPY_DST);
+        let positions = [
+            self.buffer(vec4s, U::STORAGE | U::COPY_DST | U::COPY_SRC),
+            self.buffer(vec4s, U::STORAGE | U::COPY_DST | U::COPY_SRC),
+        ];
+        let old_positions = self.buffer(vec4s, U::STORAGE | U::COPY_DST | U::COPY_SRC);
+        let accelerations = self.buffer(vec4s, U::STORAGE | U::COPY_DST);
+        let staging = self.buffer(2 * vec4s, U::MAP_READ | U::COPY_DST);
+        let offsets_buffer = self.buffer(offsets.len() as u64 * 4, U::STORAGE | U::COPY_DST);
+        let links_buffer = self.buffer(flat.len() as u64, U::STORAGE | U::COPY_DST);
+        self.queue.write_buffer(&offsets_buffer, 0, &u32_bytes(offsets.into_iter()));
+        if !flat.is_empty() {
+            self.queue.write_buffer(&links_buffer, 0, &flat);
+        }
+
+        let jacobi = [
+            self.bind_group(&self.jacobi.1, &[&params, &positions[0], &positions[1], &offsets_buffer, &links_buffer]),
+            self.bind_group(&self.jacobi.1, &[&params, &positions[1], &positions[0], &offsets_buffer, &links_buffer]),
+        ];
+        let integrate = self.bind_group(&self.integrate.1, &[&params, &positions[0], &old_positions, &accelerations]);
+        self.buffers = Some(Buffers {
+            count,
+            params,
+            positions,
+            old_positions,
+            accelerations,
+            staging,
+            jacobi,
+            integrate,
+            _links: [offsets_buffer, links_buffer],
+        });
+    }
+
+    /* WHETHER set_constraints HAS TO BE CALLED BEFORE THE NEXT step */
+    pub fn needs_constraints(&self, count: I) -> bool {
+        self.buffers.as_ref().map_or(true, |b| b.count != count)
+    }
+
+    /* DROP THE UPLOADED CONSTRAINTS AFTER THE TOPOLOGY CHANGED */
+    pub fn invalidate(&mut self) {
+        self.buffers = None;
+    }
+
+    /* max_iterations JACOBI PASSES, THEN INTEGRATION OF THE ACCUMULATED FORCES, WHICH ARE CLEARED */
+    /* dt IS THE STEP ACTUALLY TAKEN, WHICH MAY BE SMALLER THAN THE ONE CONFIGURED */
+    pub fn step(&mut self, particles: &mut Particles, config: &ClothConfig, dt: F) -> Result<(), String> {
+        let buffers = self.buffers.as_ref().ok_or_else(|| "no constraints uploaded".to_string())?;
+        let (count, iterations) = (buffers.count, config.max_iterations);
+        let vec4s = count as u64 * VEC4_SIZE;
+
+        let k = config.stiffness;
+        let mut params = u32_bytes(std::iter::once(count as u32));
+        let values = [dt, config.damping, config.relaxation, k.warp, k.weft, k.shear, k.flexion];
+        params.extend(f32_bytes(values.iter().map(|&v| v as f32)));
+        let vec4 = |v: V, w: F| vec![v.x as f32, v.y as f32, v.z as f32, w as f32];
+        let positions = (0..count).flat_map(|i| vec4(particles.data[i].coords, particles.inv_mass(i)));
+        let old_positions = (0..count).flat_map(|i| vec4(particles.old_position(i).coords, 0.));
+        let accelerations = (0..count).flat_map(|i| vec4(particles.acceleration(i), 0.));
+        self.queue.write_buffer(&buffers.params, 0, &params);
+        self.queue.write_buffer(&buffers.positions[0], 0, &f32_bytes(positions));
+        self.queue.write_buffer(&buffers.old_positions, 0, &f32_bytes(old_positions));
+        self.queue.write_buffer(&buffers.accelerations, 0, &f32_bytes(accelerations));
+
+        let groups = ((count + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE) as u32;
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_compute_pass();
+            pass.set_pipeline(&self.jacobi.0);
+            for iter in 0..iterations {
+                pass.set_bind_group(0, &buffers.jacobi[iter % 2], &[]);
+                pass.dispatch(groups, 1, 1);
+            }
+        }
+        /* AN ODD NUMBER OF ITERATIONS LEAVES THE RESULT IN THE SECOND BUFFER */
+        if iterations % 2 == 1 {
+            encoder.copy_buffer_to_buffer(&buffers.positions[1], 0, &buffers.positions[0], 0, vec4s);
+        }
+        {
+            let mut pass = encoder.begin_compute_pass();
+            pass.set_pipeline(&self.integrate.0);
+            pass.set_bind_group(0, &buffers.integrate, &[]);
+            pass.dispatch(groups, 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&buffers.positions[0], 0, &buffers.staging, 0, vec4s);
+        encoder.copy_buffer_to_buffer(&buffers.old_positions, 0, &buffers.staging, vec4s, vec4s);
+        self.queue.submit(Some(encoder.finish()));
+
+        /* THE SYNC POINT: WAIT FOR THE GPU AND COPY BOTH POSITION BUFFERS BACK */
+        let slice = buffers.staging.slice(..2 * vec4s);
+        let mapped = slice.map_async(wgpu::MapMode::Read);
+        self.device.poll(wgpu::Maintain::Wait);
+        future::block_on(mapped).map_err(|err| format!("gpu readback failed: {:?}", err))?;
+        {
+            let data = slice.get_mapped_range();
+            let value = |k: I| {
+                let mut bytes = [0; 4];
+                bytes.copy_from_slice(&data[4 * k..4 * k + 4]);
+                f32::from_le_bytes(bytes) as F
+            };
+            let read = |base: I, i: I| P::new(value(base + 4 * i), value(base + 4 * i + 1), value(base + 4 * i + 2));
+            /* THE ROUND TRIP THROUGH f32 WOULD NUDGE FROZEN PARTICLES, WHICH THE SHADER LEFT ALONE ANYWAY */
+            for i in 0..count {
+                if !particles.frozen(i) {
+                    let (p, old_p) = (read(0, i), read(4 * count, i));
+                    particles.place(i, p, p - old_p);
+                }
+            }
+        }
+        buffers.staging.unmap();
+        particles.clear_forces();
+        particles.set_last_dt(dt);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cloth::ClothSim,
+        config::{ClothConfig, Integrator, SelfCollision, SolverMode},
+        F, I,
+    };
+
+    /* THE SAME CLOTH STEPPED WITH CPU JACOBI AND ON THE GPU STAYS WITHIN TOLERANCE OF ITSELF. WHAT THE GPU DOES */
+    /* NOT COVER OR ORDERS DIFFERENTLY IS TURNED OFF, AND EVERY STEP RUNS ALL ITERATIONS. NEEDS A GPU ADAPTER, */
+    /* SO IT ONLY RUNS WITH --ignored */
+    #[test]
+    #[ignore]
+    fn matches_cpu_jacobi() {
+        // STEPS, PARTICLES ALONG EACH SIDE, AND THE LARGEST POSITION DIFFERENCE BETWEEN THE SOLVERS
+        const STEPS: I = 20;
+        const N: I = 20;
+        const TOLERANCE: F = 1e-3;
+
+        let mut config = ClothConfig::default();
+        config.solver = SolverMode::Jacobi;
+        config.integrator = Integrator::Verlet;
+        config.min_iterations = config.max_iterations;
+        config.wind.strength = 0.;
+        config.wind.turbulence = 0.;
+        config.self_collision = SelfCollision::Off;
+        config.area_stiffness = None;
+        config.pressure = None;
+        config.retension = None;
+        let new = |gpu| ClothSim::new(4., 4., (N, N), ClothConfig { gpu, ..config.clone() }).unwrap();
+        let (mut cpu, mut gpu) = (new(false), new(true));
+        assert!(gpu.on_gpu(), "no gpu solver available");
+        for step in 0..STEPS {
+            cpu.step(&[]);
+            gpu.step(&[]);
+            let diff = cpu.particles.iter().zip(gpu.particles.iter()).map(|(a, b)| (a - b).norm()).fold(0., F::max);
+            assert!(diff <= TOLERANCE, "step {}: {:.2e} apart", step, diff);
+        }
+    }
+}
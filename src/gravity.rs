@@ -0,0 +1,142 @@
+use bevy::{prelude::*, render::pipeline::PrimitiveTopology};
+use nalgebra::{Rotation3, Vector3};
+
+use crate::{bindings::Action, cloth::Cloth, config::ClothConfig, debug, record::Recorder, to_array, F, P, V};
+
+/* TILT THE WORLD: ROTATE THE GRAVITY OF ALL CLOTHS AT RUNTIME */
+/* UP/DOWN TILT AROUND THE x AXIS, LEFT/RIGHT AROUND THE z AXIS, G FLIPS, 0 RESETS TO THE CONFIG */
+/* Z SWITCHES GRAVITY OFF AND BACK ON TO WHAT IT WAS, 0 ALSO LEAVES ZERO GRAVITY */
+
+// ROTATION PER KEY PRESS, IN DEGREES
+const TILT_STEP: F = 5.;
+
+// WHERE THE ARROW SHOWING THE DIRECTION OF GRAVITY IS DRAWN
+const ARROW_ORIGIN: [F; 3] = [0., 0., 0.];
+const ARROW_LENGTH: F = 2.;
+const ARROW_HEAD: F = 0.4;
+
+pub struct GravityArrow;
+
+/* GRAVITY OF EACH CLOTH BEFORE Z SWITCHED IT OFF, None WHILE GRAVITY IS ON */
+/* CLOTHS SPAWNED IN ZERO GRAVITY GET THE CONFIG GRAVITY BACK */
+#[derive(Default)]
+pub struct ZeroGravity {
+    saved: Option<Vec<(Entity, V)>>,
+}
+
+pub fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands
+        .spawn(PbrComponents {
+            mesh: meshes.add(Mesh::new(PrimitiveTopology::LineList)),
+            material: materials.add(StandardMaterial {
+                albedo: Color::rgb(1., 0.8, 0.),
+                shaded: false,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .with(GravityArrow);
+}
+
+pub fn tilt(
+    actions: Res<Input<Action>>,
+    config: Res<ClothConfig>,
+    recorder: Res<Recorder>,
+    mut zero_g: Local<ZeroGravity>,
+    mut cloths: Query<(Entity, &mut Cloth)>,
+) {
+    /* THE TILTS SHARE THE ARROW KEYS WITH STEPPING THROUGH FRAMES, SO THEY ARE OFF DURING REPLAY */
+    let arrows = !recorder.replaying;
+    let step = TILT_STEP.to_radians();
+    let rotation = if arrows && actions.just_pressed(Action::TiltUp) {
+        Rotation3::from_axis_angle(&Vector3::x_axis(), -step)
+    } else if arrows && actions.just_pressed(Action::TiltDown) {
+        Rotation3::from_axis_angle(&Vector3::x_axis(), step)
+    } else if arrows && actions.just_pressed(Action::TiltLeft) {
+        Rotation3::from_axis_angle(&Vector3::z_axis(), -step)
+    } else if arrows && actions.just_pressed(Action::TiltRight) {
+        Rotation3::from_axis_angle(&Vector3::z_axis(), step)
+    } else if actions.just_pressed(Action::ZeroGravity) {
+        match zero_g.saved.take() {
+            Some(saved) => {
+                for (entity, mut cloth) in cloths.iter_mut() {
+                    let gravity = saved.iter().find(|(e, _)| *e == entity).map_or(config.gravity, |&(_, g)| g);
+                    cloth.set_gravity(gravity);
+                }
+            }
+            None => {
+                zero_g.saved = Some(cloths.iter_mut().map(|(entity, cloth)| (entity, cloth.config.gravity)).collect());
+                for (_, mut cloth) in cloths.iter_mut() {
+                    cloth.set_gravity(V::zeros());
+                }
+            }
+        }
+        return;
+    } else if actions.just_pressed(Action::FlipGravity) {
+        for (_, mut cloth) in cloths.iter_mut() {
+            let gravity = -cloth.config.gravity;
+            cloth.set_gravity(gravity);
+        }
+        return;
+    } else if actions.just_pressed(Action::ResetGravity) {
+        zero_g.saved = None;
+        for (_, mut cloth) in cloths.iter_mut() {
+            cloth.set_gravity(config.gravity);
+        }
+        return;
+    } else {
+        return;
+    };
+
+    for (_, mut cloth) in cloths.iter_mut() {
+        let gravity = rotation * cloth.config.gravity;
+        cloth.set_gravity(gravity);
+    }
+}
+
+/* ARROW FROM ARROW_ORIGIN ALONG THE GRAVITY OF THE FIRST CLOTH, WITH A TWO-LINE HEAD */
+pub fn update_arrow(
+    mut meshes: ResMut<Assets<Mesh>>,
+    cloths: Query<&Cloth>,
+    arrows: Query<(&GravityArrow, &Handle<Mesh>)>,
+) {
+    let gravity = match cloths.iter().next() {
+        Some(cloth) => cloth.config.gravity,
+        None => return,
+    };
+    /* NOTHING TO POINT AT IN ZERO GRAVITY */
+    let dir = match gravity.try_normalize(F::EPSILON) {
+        Some(dir) => dir,
+        None => {
+            for (_, handle) in arrows.iter() {
+                if let Some(mesh) = meshes.get_mut(handle) {
+                    debug::write_vertices(mesh, vec![]);
+                }
+            }
+            return;
+        }
+    };
+    let side = dir.cross(&V::x()).try_normalize(F::EPSILON).unwrap_or_else(|| dir.cross(&V::z()).normalize());
+
+    let start = P::from(ARROW_ORIGIN);
+    let tip = start + dir * ARROW_LENGTH;
+    let back = tip - dir * ARROW_HEAD;
+    let positions = vec![
+        start,
+        tip,
+        tip,
+        back + side * ARROW_HEAD,
+        tip,
+        back - side * ARROW_HEAD,
+    ];
+
+    for (_, handle) in arrows.iter() {
+        if let Some(mesh) = meshes.get_mut(handle) {
+            debug::write_vertices(mesh, positions.iter().map(|p| to_array(*p)).collect());
+        }
+    }
+}
@@ -1,4 +1,9 @@
-use std::ops::{Deref, DerefMut, Index, IndexMut};
+use std::{
+    fmt,
+    iter::FromIterator,
+    ops::{Index, IndexMut},
+    slice, vec,
+};
 
 use crate::I;
 
@@ -6,38 +11,347 @@ use crate::I;
 
 pub type GridIdx = (I, I);
 
+/* FLAT DATA THAT DOES NOT MAKE UP THE GRID IT IS MEANT TO FILL */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GridError {
+    /* CELLS WITH NO COLUMNS TO PUT THEM IN */
+    ZeroWidth { len: I },
+    /* THE LAST ROW IS CUT SHORT */
+    PartialRow { len: I, width: I },
+    /* NOT ONE CELL PER POSITION OF A GRID OF KNOWN SIZE */
+    LengthMismatch { dimensions: GridIdx, len: I },
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GridError::ZeroWidth { len } => write!(f, "{} cells do not fit in a grid with no columns", len),
+            GridError::PartialRow { len, width } => write!(f, "{} cells do not fill whole rows of {}", len, width),
+            GridError::LengthMismatch { dimensions: (w, h), len } => {
+                write!(f, "expected {} cells for a {}x{} grid, got {}", w * h, w, h, len)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GridError {}
+
+/* NO Deref TO THE STORAGE, THE FLAT VIEW IS EXPLICIT THROUGH iter, as_slice AND len */
+/* THE HEIGHT IS STORED, SO CELLS PUSHED PAST THE LAST ROW, LIKE SPLIT PARTICLES, ARE NOT A NEW ROW */
+/* A DESERIALIZED GRID GOES THROUGH with_dimensions LIKE ANY OTHER DATA FROM OUTSIDE */
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serialize", serde(try_from = "RawGrid<T>"))]
 pub struct Grid<T> {
     pub data: Vec<T>,
     width: I,
+    height: I,
+}
+
+#[cfg(feature = "serialize")]
+#[derive(serde::Deserialize)]
+struct RawGrid<T> {
+    data: Vec<T>,
+    width: I,
+    height: I,
+}
+
+#[cfg(feature = "serialize")]
+impl<T> std::convert::TryFrom<RawGrid<T>> for Grid<T> {
+    type Error = GridError;
+
+    fn try_from(raw: RawGrid<T>) -> Result<Self, GridError> { Grid::with_dimensions(raw.data, (raw.width, raw.height)) }
 }
 
 impl<T> Grid<T> {
-    pub fn new(data: Vec<T>, width: I) -> Self { Self { data, width } }
+    /* THE HEIGHT FOLLOWS FROM THE LENGTH, WHICH MUST BE WHOLE ROWS. ONLY AN EMPTY GRID MAY HAVE NO WIDTH */
+    pub fn new(data: Vec<T>, width: I) -> Result<Self, GridError> {
+        match (data.len(), width) {
+            (0, 0) => Ok(Self { data, width, height: 0 }),
+            (len, 0) => Err(GridError::ZeroWidth { len }),
+            (len, width) if len % width != 0 => Err(GridError::PartialRow { len, width }),
+            (len, width) => Ok(Self { data, width, height: len / width }),
+        }
+    }
+
+    /* FOR DATA THAT CLAIMS A SHAPE, E.G. A SNAPSHOT BEING RESTORED INTO A GRID OF KNOWN SIZE */
+    pub fn with_dimensions(data: Vec<T>, dimensions: GridIdx) -> Result<Self, GridError> {
+        let (width, height) = dimensions;
+        if data.len() != width * height {
+            return Err(GridError::LengthMismatch { dimensions, len: data.len() });
+        }
+        Ok(Self { data, width, height })
+    }
+
+    /* FOR CALLERS THAT BUILT THE DATA THEMSELVES, A MISMATCH IS A BUG AND PANICS RIGHT HERE */
+    pub fn new_unchecked(data: Vec<T>, width: I) -> Self {
+        Self::new(data, width).unwrap_or_else(|err| panic!("invalid grid: {}", err))
+    }
+
+    /* FILLS IN ROW-MAJOR ORDER: (0, 0), (1, 0), ..., (width - 1, 0), (0, 1), ... */
+    /* WHICH MATCHES THE FLATTENED VERTEX LAYOUT OF THE CLOTH MESH */
+    pub fn from_fn(width: I, height: I, mut f: impl FnMut(GridIdx) -> T) -> Self {
+        let data = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|idx| f(idx))
+            .collect();
+        Self { data, width, height }
+    }
+
+    pub fn filled(width: I, height: I, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self { data: vec![value; width * height], width, height }
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Grid<U> {
+        Grid { data: self.data.into_iter().map(f).collect(), width: self.width, height: self.height }
+    }
+
+    /* EVERY CELL, INCLUDING ANY PUSHED PAST THE LAST ROW */
+    pub fn fill(&mut self, value: T)
+    where
+        T: Clone,
+    {
+        self.data.iter_mut().for_each(|cell| *cell = value.clone());
+    }
+
+    pub fn width(&self) -> I { self.width }
+
+    pub fn height(&self) -> I { self.height }
+
+    pub fn dimensions(&self) -> GridIdx { (self.width, self.height) }
+
+    pub fn contains(&self, idx: GridIdx) -> bool { idx.0 < self.width && idx.1 < self.height }
+
+    pub fn flatten(&self, idx: GridIdx) -> I { idx.1 * self.width + idx.0 }
+
+    /* A GRID WITH NO COLUMNS HAS NO CELLS, SO EVERY INDEX MAPS TO A POSITION IT DOES NOT contain */
+    pub fn unflatten(&self, i: I) -> GridIdx {
+        match self.width {
+            0 => (i, 0),
+            width => (i % width, i / width),
+        }
+    }
+
+    pub fn len(&self) -> I { self.data.len() }
+
+    pub fn is_empty(&self) -> bool { self.data.is_empty() }
+
+    pub fn iter(&self) -> slice::Iter<T> { self.data.iter() }
+
+    pub fn iter_mut(&mut self) -> slice::IterMut<T> { self.data.iter_mut() }
+
+    pub fn as_slice(&self) -> &[T] { &self.data }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] { &mut self.data }
+
+    pub fn row(&self, y: I) -> impl Iterator<Item = &T> {
+        self.data[y * self.width..(y + 1) * self.width].iter()
+    }
+
+    pub fn row_mut(&mut self, y: I) -> impl Iterator<Item = &mut T> {
+        self.data[y * self.width..(y + 1) * self.width].iter_mut()
+    }
+
+    /* COLUMNS ARE STRIDED BY THE WIDTH IN THE FLATTENED STORAGE */
+    /* WITHOUT COLUMNS THERE ARE NO CELLS, THE STRIDE OF 1 ONLY KEEPS step_by FROM PANICKING ON NOTHING */
+    pub fn col(&self, x: I) -> impl Iterator<Item = &T> {
+        self.data.iter().skip(x).step_by(self.width.max(1))
+    }
+
+    pub fn col_mut(&mut self, x: I) -> impl Iterator<Item = &mut T> {
+        self.data.iter_mut().skip(x).step_by(self.width.max(1))
+    }
+
+    /* LIKEWISE A GRID WITH NO COLUMNS YIELDS NO ROWS, EVEN IF IT CLAIMS A HEIGHT. CELLS PUSHED ONTO THE */
+    /* DATA PAST THE LAST ROW, LIKE THE PARTICLES SPLIT OFF A TORN CLOTH, ARE NOT PART OF ANY ROW */
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.data[..self.width * self.height].chunks(self.width.max(1))
+    }
+}
+
+/* ROW-MAJOR ITERATION OVER THE CELLS, LIKE from_fn FILLS THEM */
+impl<T> IntoIterator for Grid<T> {
+    type Item = T;
+    type IntoIter = vec::IntoIter<T>;
+    fn into_iter(self) -> Self::IntoIter { self.data.into_iter() }
+}
+
+impl<'a, T> IntoIterator for &'a Grid<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter { self.data.iter() }
+}
+
+impl<'a, T> IntoIterator for &'a mut Grid<T> {
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+    fn into_iter(self) -> Self::IntoIter { self.data.iter_mut() }
 }
 
-impl<T> Deref for Grid<T> {
-    type Target = Vec<T>;
-    fn deref(&self) -> &Self::Target { &self.data }
+/* WITHOUT A WIDTH EVERYTHING GOES INTO A SINGLE ROW, SEE collect_grid FOR OTHER SHAPES */
+/* AN EMPTY GRID COLLECTED FROM NOTHING HAS NO WIDTH AND NO ROWS */
+impl<T> FromIterator<T> for Grid<T> {
+    fn from_iter<It: IntoIterator<Item = T>>(iter: It) -> Self {
+        let data: Vec<T> = iter.into_iter().collect();
+        Self { width: data.len(), height: (!data.is_empty()) as I, data }
+    }
+}
 
+/* iter.collect_grid(width) FILLS ROW BY ROW, THE ITEMS MUST MAKE UP WHOLE ROWS */
+pub trait CollectGrid: Iterator + Sized {
+    fn collect_grid(self, width: I) -> Grid<Self::Item> { Grid::new_unchecked(self.collect(), width) }
 }
 
-impl<T> DerefMut for Grid<T> {
-    fn deref_mut(&mut self) -> &mut Self::Target { &mut self.data }
+impl<It: Iterator> CollectGrid for It {}
 
+/* FLATTENED INDEX, AS RETURNED BY flatten */
+impl<T> Index<I> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, i: I) -> &Self::Output { &self.data[i] }
+}
+
+impl<T> IndexMut<I> for Grid<T> {
+    fn index_mut(&mut self, i: I) -> &mut Self::Output { &mut self.data[i] }
 }
 
 impl<T> Index<GridIdx> for Grid<T> {
     type Output = T;
 
     fn index(&self, idx: GridIdx) -> &Self::Output {
-        let pos = idx.1 * self.width + idx.0;
+        debug_assert!(idx.0 < self.width, "column {} is outside a grid {} wide", idx.0, self.width);
+        let pos = self.flatten(idx);
         &self.data[pos]
     }
 }
 
 impl<T> IndexMut<GridIdx> for Grid<T> {
     fn index_mut(&mut self, idx: GridIdx) -> &mut Self::Output {
-        let pos = idx.1 * self.width + idx.0;
+        debug_assert!(idx.0 < self.width, "column {} is outside a grid {} wide", idx.0, self.width);
+        let pos = self.flatten(idx);
         &mut self.data[pos]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+
+    // RANDOM GRIDS BUILT, THE LONGEST SIDE ANY OF THEM HAS, AND THE SEED THEIR SIZES COME FROM
+    const CHECKS: I = 200;
+    const MAX_SIDE: I = 24;
+    const SEED: u64 = 7;
+
+    /* EVERY CELL OF A GRID OF RANDOM SIZE IS FOUND AGAIN BY ITS INDEX, AND ONLY THE CELLS INSIDE ARE CONTAINED */
+    #[test]
+    fn random_grids_index_round_trip() {
+        let mut rng = StdRng::seed_from_u64(SEED);
+        for _ in 0..CHECKS {
+            let (w, h) = (rng.gen_range(1, MAX_SIDE + 1), rng.gen_range(0, MAX_SIDE + 1));
+            let mut grid = Grid::from_fn(w, h, |idx| idx);
+            for idx in (0..h).flat_map(|y| (0..w).map(move |x| (x, y))) {
+                assert_eq!(grid[idx], idx);
+                assert_eq!(grid.unflatten(grid.flatten(idx)), idx);
+                assert!(grid.contains(idx), "{}x{} grid should contain {:?}", w, h, idx);
+            }
+            for &idx in &[(w, 0), (0, h), (w + rng.gen_range(0, w + 1), h)] {
+                assert!(!grid.contains(idx), "{}x{} grid should not contain {:?}", w, h, idx);
+            }
+            assert_eq!(grid.dimensions(), (w, h));
+            assert_eq!(Grid::new(grid.data.clone(), w).map(|g| g.dimensions()), Ok((w, h)));
+            assert_eq!(grid.rows().count(), h);
+            assert!(grid.col(w - 1).eq(grid.rows().map(|row| &row[w - 1])));
+
+            grid.fill((0, 0));
+            assert!(grid.iter().all(|&cell| cell == (0, 0)));
+        }
+    }
+
+    /* FLAT DATA THAT DOES NOT MAKE WHOLE ROWS IS REJECTED WITH ITS ERROR */
+    #[test]
+    fn partial_rows_are_rejected() {
+        let mut rng = StdRng::seed_from_u64(SEED);
+        for _ in 0..CHECKS {
+            let (w, h) = (rng.gen_range(1, MAX_SIDE + 1), rng.gen_range(0, MAX_SIDE + 1));
+            let len = w * h + 1;
+            let expected = match len % (w + 1) {
+                0 => Ok((w + 1, len / (w + 1))),
+                _ => Err(GridError::PartialRow { len, width: w + 1 }),
+            };
+            assert_eq!(Grid::new((0..len).collect(), w + 1).map(|g| g.dimensions()), expected);
+        }
+        assert_eq!(
+            Grid::with_dimensions(vec![0; 11], (4, 3)),
+            Err(GridError::LengthMismatch { dimensions: (4, 3), len: 11 })
+        );
+    }
+
+    #[test]
+    fn zero_width() {
+        assert_eq!(Grid::new(vec![0; 3], 0), Err(GridError::ZeroWidth { len: 3 }));
+        assert_eq!(Grid::<I>::new(vec![], 0).map(|g| g.dimensions()), Ok((0, 0)));
+
+        /* NO COLUMNS BUT A HEIGHT: NO CELLS, NO ROWS AND NO COLUMNS, AND NOTHING IS CONTAINED */
+        let mut grid = Grid::from_fn(0, 3, |idx| idx);
+        assert!(grid.is_empty());
+        assert_eq!(grid.rows().count(), 0);
+        assert_eq!(grid.col(0).count(), 0);
+        assert_eq!(grid.col_mut(0).count(), 0);
+        assert_eq!(grid.row(2).count(), 0);
+        assert!(!grid.contains(grid.unflatten(5)));
+
+        let empty: Grid<I> = std::iter::empty().collect();
+        assert_eq!(empty.dimensions(), (0, 0));
+        assert_eq!(empty.rows().count(), 0);
+        assert!(!empty.contains(empty.unflatten(0)));
+    }
+
+    /* filled AND from_fn BUILD THE SAME GRID FROM THE SAME VALUES, AND map CHANGES THE CELLS IN PLACE, KEEPING */
+    /* THE SHAPE EVEN WHEN THE CELL TYPE CHANGES */
+    #[test]
+    fn constructors_and_map_agree() {
+        assert_eq!(Grid::filled(3, 2, 'x'), Grid::from_fn(3, 2, |_| 'x'));
+        let product = |(x, y): GridIdx| x * y;
+        assert_eq!(Grid::from_fn(5, 4, |idx| idx).map(product), Grid::from_fn(5, 4, product));
+        let labels = Grid::from_fn(3, 2, |(x, y)| x + 3 * y).map(|i| i.to_string());
+        assert_eq!(labels.dimensions(), (3, 2));
+        assert_eq!(labels[(2, 1)], "5");
+        assert!(Grid::filled(0, 4, 1.).map(|c: f32| c * 2.).is_empty());
+    }
+
+    /* WRITING THROUGH A ROW OR A COLUMN CHANGES THAT LINE OF THE GRID AND NOTHING ELSE */
+    #[test]
+    fn row_and_column_writes_stay_on_their_line() {
+        let mut grid = Grid::filled(4, 3, 0);
+        grid.row_mut(1).for_each(|cell| *cell += 1);
+        grid.col_mut(2).for_each(|cell| *cell += 10);
+        for (x, y) in (0..3).flat_map(|y| (0..4).map(move |x| (x, y))) {
+            assert_eq!(grid[(x, y)], (y == 1) as I + 10 * (x == 2) as I, "cell {:?}", (x, y));
+        }
+        assert_eq!(grid.row(1).copied().collect::<Vec<_>>(), vec![1, 1, 11, 1]);
+        assert_eq!(grid.col(2).copied().collect::<Vec<_>>(), vec![10, 11, 10]);
+        assert_eq!(grid.col(3).count(), grid.height());
+    }
+
+    #[test]
+    fn cells_past_the_last_row_are_in_no_row() {
+        let mut grid = Grid::from_fn(3, 2, |(x, y)| x + 3 * y);
+        grid.data.extend(6..10);
+        assert_eq!(grid.rows().collect::<Vec<_>>(), vec![&[0, 1, 2][..], &[3, 4, 5][..]]);
+        assert_eq!(grid.rows().count(), grid.height());
+    }
+
+    #[test]
+    fn collect_grid_fills_row_by_row() {
+        let grid = (0..6).collect_grid(3);
+        assert_eq!(grid.dimensions(), (3, 2));
+        assert_eq!(grid[(2, 1)], 5);
+        assert_eq!(grid, (0..6).collect_grid(3));
+        assert_ne!(grid, (0..6).collect_grid(2));
+    }
+}
@@ -0,0 +1,226 @@
+use std::{
+    fs,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    args::Dimensions,
+    cloth::ClothSim,
+    collider::{Collider, Colliders},
+    config::{ClothConfig, PinPreset, Pose},
+    error::ClothError,
+    lod::{self, ClothLod},
+    metrics::Metrics,
+    scenario::{Scenario, ScenarioRunner},
+    stats::Phase,
+    *,
+};
+
+/* RUN THE SIMULATION WITHOUT ANY RENDERING, FOR BENCHMARKING AND CI */
+
+// DEFAULT NUMBER OF STEPS TO SIMULATE
+pub const STEPS: I = 100;
+
+// PARTICLES ALONG EACH SIDE, SIZE AND SPACING OF THE CLOTHS OF --bench-scene
+const BENCH_SUBDIVISIONS: I = 20;
+const BENCH_SIZE: F = 4.;
+const BENCH_SPACING: F = 6.;
+// HEIGHT ABOVE THE SHARED FLOOR THE BENCH CLOTHS ARE DROPPED FROM
+const BENCH_DROP: F = 3.;
+
+/* A HEADLESS RUN HAS NOTHING TO FALL BACK ON, SO AN ERROR ENDS IT */
+fn or_exit<T>(result: Result<T, ClothError>) -> T {
+    result.unwrap_or_else(|err| {
+        println!("FAILED: {}", err);
+        std::process::exit(1)
+    })
+}
+
+pub fn run(steps: I, dimensions: Dimensions, config: ClothConfig, metrics_path: Option<String>) {
+    let (width, height) = dimensions.size;
+    let colliders = Colliders::default();
+    let cloth = or_exit(ClothSim::new(width, height, dimensions.subdivisions, config));
+    let mut cloth = cloth.relaxed(dimensions.relax).settled(dimensions.settle, &colliders.0);
+
+    let mut metrics = metrics_path.map(|path| {
+        Metrics::create(&path).unwrap_or_else(|err| {
+            eprintln!("failed to create {}: {}", path, err);
+            std::process::exit(1);
+        })
+    });
+
+    let start = Instant::now();
+    for step in 0..steps {
+        let step_start = Instant::now();
+        simulate(&mut cloth, &colliders);
+        let step_time = step_start.elapsed();
+        /* A FAILED WRITE STOPS THE METRICS BUT NOT THE RUN */
+        if let Some(err) = metrics.as_mut().and_then(|m| m.record(step, step_time, &cloth).err()) {
+            eprintln!("failed to write metrics: {}", err);
+            metrics = None;
+        }
+    }
+    let elapsed = start.elapsed();
+    if let Some(err) = metrics.as_mut().and_then(|m| m.flush().err()) {
+        eprintln!("failed to flush metrics: {}", err);
+    }
+
+    let finite = cloth.particles.iter().all(|p| p.coords.iter().all(|c| c.is_finite()));
+    println!("particles:      {}", cloth.particles.len());
+    println!("steps:          {}", steps);
+    println!("total time:     {:.3?}", elapsed);
+    println!("time per step:  {:.3?}", elapsed / steps.max(1) as u32);
+    println!("max strain:     {:.4}", cloth.max_strain());
+    println!("iterations:     {}", cloth.stats.iterations);
+    println!("solver:         {}", if cloth.on_gpu() { "gpu" } else { "cpu" });
+    println!("all finite:     {}", finite);
+    println!("recoveries:     {}", cloth.stats.recoveries);
+    println!("clamped:        {} in the last step, {} in total", cloth.stats.clamped, cloth.stats.clamped_total);
+    println!("broad phase:    built {}x in the last step", cloth.stats.broad_phase_builds);
+    println!("edge pairs:     {} in the last step", cloth.stats.edge_pairs);
+    println!("contraction:    {:.4} (rest lengths x{:.4})", cloth.stats.contraction, cloth.stats.rest_scale);
+    let (pieces, asleep) = cloth.pieces();
+    println!("pieces:         {} ({} asleep)", pieces, asleep);
+    let bounds = cloth.bounding_box();
+    let extent = bounds.max - bounds.min;
+    println!("extent:         {:.3} wide, {:.3} tall (aspect {:.3})", extent.x, extent.y, extent.y / extent.x);
+    if cloth.closed() {
+        println!("volume:         {:.3}", cloth.volume());
+    }
+    /* ROLLING AVERAGES OVER THE LAST stats::WINDOW STEPS */
+    for &phase in Phase::ALL.iter() {
+        println!("{:<15} {:.3?}", format!("{}:", phase.name()), cloth.stats.average(phase));
+    }
+}
+
+/* PLAY A SCENARIO FILE TO ITS LAST STEP, PRINTING EVERY CHECK AND FAILING IF ANY OF THEM DID */
+pub fn run_scenario(path: &str, config: ClothConfig) {
+    let scenario = Scenario::load(path).unwrap_or_else(|err| {
+        println!("FAILED: cannot load {}: {}", path, err);
+        std::process::exit(1)
+    });
+    let mut colliders = Colliders::default();
+    let mut runner = ScenarioRunner::new(scenario, &mut colliders);
+    let mut cloth = or_exit(runner.scenario().cloth(&config));
+    let mut outcomes = runner.advance(&mut cloth, &mut colliders);
+    while cloth.steps() < runner.scenario().steps {
+        simulate(&mut cloth, &colliders);
+        outcomes.extend(runner.advance(&mut cloth, &mut colliders));
+    }
+    outcomes.iter().for_each(|outcome| println!("{}", outcome));
+    let failed = outcomes.iter().filter(|outcome| !outcome.passed).count();
+    println!("{} of {} checks passed in {} steps", outcomes.len() - failed, outcomes.len(), cloth.steps());
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+/* STRESS TEST: count FREE CLOTHS IN A SQUARE GRID DROPPED ONTO ONE SHARED FLOOR PLANE, ALL STEPPED */
+/* EVERY FRAME FOR frames FRAMES. PRINTS THE MEAN AND PERCENTILES OF THE FRAME TIME, THE SIZE OF THE */
+/* SCENE AND THE PHASE TIMES SUMMED OVER THE CLOTHS, AND WRITES THEM TO json_path IF GIVEN */
+pub fn bench_scene(count: I, frames: I, config: ClothConfig, json_path: Option<String>, auto_lod: bool) {
+    let columns = (count as F).sqrt().ceil() as I;
+    let mut colliders = Colliders::default();
+    colliders.add(Collider::Plane { point: P::origin(), normal: V::y() });
+    let mut cloths: Vec<_> = (0..count)
+        .map(|k| {
+            let origin = V::new((k % columns) as F, 0., (k / columns) as F) * BENCH_SPACING;
+            let config = ClothConfig {
+                pose: Pose::FlatHorizontal { height: BENCH_DROP },
+                pin_preset: PinPreset::Free,
+                origin,
+                ..config.clone()
+            };
+            or_exit(ClothSim::new(BENCH_SIZE, BENCH_SIZE, (BENCH_SUBDIVISIONS, BENCH_SUBDIVISIONS), config))
+        })
+        .collect();
+
+    /* WITH --lod A CAMERA ABOVE THE CORNER OF THE FIRST CLOTH LOOKS ACROSS THE GRID TOWARDS ITS MIDDLE */
+    let eye = P::new(-BENCH_SPACING, BENCH_DROP + BENCH_SPACING, -BENCH_SPACING);
+    let middle = P::new(columns as F, 0., (count / columns) as F) * (BENCH_SPACING / 2.);
+    let forward = (middle - eye).normalize();
+    let mut lods: Vec<ClothLod> = cloths.iter().map(|_| ClothLod::default()).collect();
+
+    let mut frame_times = Vec::with_capacity(frames);
+    let start = Instant::now();
+    for _ in 0..frames {
+        let frame_start = Instant::now();
+        for (cloth, lod) in cloths.iter_mut().zip(&mut lods) {
+            if auto_lod {
+                lod.level = lod::level_for(eye, forward, &cloth.bounding_box());
+                if !lod.advance(cloth) {
+                    continue;
+                }
+            }
+            simulate(cloth, &colliders);
+        }
+        frame_times.push(frame_start.elapsed());
+    }
+    let elapsed = start.elapsed();
+
+    frame_times.sort();
+    let percentile = |q: F| {
+        let k = ((frame_times.len().max(1) - 1) as F * q).round() as I;
+        frame_times.get(k).copied().unwrap_or_default()
+    };
+    let mean = elapsed / frames.max(1) as u32;
+    let (p50, p95, p99) = (percentile(0.5), percentile(0.95), percentile(0.99));
+    let max = frame_times.last().copied().unwrap_or_default();
+    let particles: I = cloths.iter().map(|c| c.particles.len()).sum();
+    let constraints: I = cloths.iter().map(ClothSim::constraint_count).sum();
+    let contacts: I = cloths.iter().map(ClothSim::contacts).sum();
+    let finite = cloths.iter().all(|c| c.particles.iter().all(|p| p.coords.iter().all(|x| x.is_finite())));
+    let phases: Vec<(Phase, Duration)> =
+        Phase::ALL.iter().map(|&phase| (phase, cloths.iter().map(|c| c.stats.average(phase)).sum())).collect();
+
+    println!("cloths:         {} of {}x{}", count, BENCH_SUBDIVISIONS, BENCH_SUBDIVISIONS);
+    println!("particles:      {}", particles);
+    println!("constraints:    {}", constraints);
+    println!("frames:         {} in {:.3?}", frames, elapsed);
+    println!("frame time:     mean {:.3?}, p50 {:.3?}, p95 {:.3?}, p99 {:.3?}, max {:.3?}", mean, p50, p95, p99, max);
+    println!("contacts:       {}", contacts);
+    println!("all finite:     {}", finite);
+    if auto_lod {
+        let at = |level: I| lods.iter().filter(|lod| lod.level == level).count();
+        let levels: Vec<_> = (0..lod::LEVELS.len()).map(|level| at(level).to_string()).collect();
+        println!("lod levels:     {} cloths at each level from full down", levels.join(" / "));
+    }
+    /* ROLLING AVERAGES OVER THE LAST stats::WINDOW STEPS, SUMMED OVER THE CLOTHS */
+    for &(phase, time) in &phases {
+        println!("{:<15} {:.3?}", format!("{}:", phase.name()), time);
+    }
+
+    if let Some(path) = json_path {
+        let seconds = |d: Duration| d.as_secs_f64();
+        let phases: Vec<_> =
+            phases.iter().map(|&(phase, time)| format!("\"{}\": {:.9}", phase.name(), seconds(time))).collect();
+        let json = format!(
+            "{{\"cloths\": {}, \"subdivisions\": {}, \"particles\": {}, \"constraints\": {}, \"frames\": {}, \
+             \"total_seconds\": {:.6}, \"frame_seconds\": {{\"mean\": {:.9}, \"p50\": {:.9}, \"p95\": {:.9}, \
+             \"p99\": {:.9}, \"max\": {:.9}}}, \"contacts\": {}, \"finite\": {}, \"lod\": {}, \
+             \"phase_seconds\": {{{}}}}}\n",
+            count,
+            BENCH_SUBDIVISIONS,
+            particles,
+            constraints,
+            frames,
+            seconds(elapsed),
+            seconds(mean),
+            seconds(p50),
+            seconds(p95),
+            seconds(p99),
+            seconds(max),
+            contacts,
+            finite,
+            auto_lod,
+            phases.join(", "),
+        );
+        if let Err(err) = fs::write(&path, json) {
+            eprintln!("failed to write {}: {}", path, err);
+            std::process::exit(1);
+        }
+    }
+    if !finite {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{particles::Particles, F, I, P};
+
+/* COARSE LEVEL OF THE HIERARCHICAL SOLVER: EVERY STRIDE-TH PARTICLE OF THE GRID ALONG EACH SIDE */
+/* THE FINE SPRINGS MOVE A CORRECTION ONE EDGE PER ITERATION, THE COARSE ONES STRIDE EDGES AT A TIME, */
+/* SO A FEW COARSE ITERATIONS CARRY THE LOAD OF THE PINS ACROSS A LARGE CLOTH BEFORE THE FINE ONES */
+
+// FINE PARTICLES BETWEEN NEIGHBORING COARSE NODES ALONG EACH SIDE
+const STRIDE: I = 4;
+
+pub struct Hierarchy {
+    /* GRID COLUMNS AND ROWS OF THE NODES, ALWAYS INCLUDING THE LAST ONES */
+    xs: Vec<I>,
+    ys: Vec<I>,
+    /* FOR EVERY FINE COLUMN OR ROW, THE NODE BEFORE IT AND HOW FAR IT IS TOWARDS THE NEXT ONE */
+    cells_x: Vec<(I, F)>,
+    cells_y: Vec<(I, F)>,
+    /* NODES AND REST LENGTH OF THE LONG STRUCTURAL AND SHEAR SPRINGS BETWEEN NEIGHBORING NODES */
+    links: Vec<(I, I, F)>,
+    /* SCRATCH BUFFERS REUSED ACROSS STEPS */
+    start: Vec<P>,
+    positions: Vec<P>,
+    pinned: Vec<bool>,
+}
+
+fn axis(n: I) -> Vec<I> {
+    let mut nodes: Vec<_> = (0..n).step_by(STRIDE).collect();
+    if nodes.last() != Some(&(n - 1)) {
+        nodes.push(n - 1);
+    }
+    nodes
+}
+
+fn cells(nodes: &[I], n: I) -> Vec<(I, F)> {
+    let mut k = 0;
+    (0..n)
+        .map(|i| {
+            while k + 2 < nodes.len() && nodes[k + 1] <= i {
+                k += 1;
+            }
+            match nodes.get(k + 1) {
+                Some(&next) => (k, (i - nodes[k]) as F / (next - nodes[k]) as F),
+                None => (k, 0.),
+            }
+        })
+        .collect()
+}
+
+/* NODE NEAREST TO A FINE COLUMN OR ROW */
+fn nearest((k, t): (I, F)) -> I {
+    if t > 0.5 {
+        k + 1
+    } else {
+        k
+    }
+}
+
+impl Hierarchy {
+    /* REST LENGTHS ARE TAKEN FROM THE POSE AT CONSTRUCTION, LIKE THOSE OF THE FINE SPRINGS */
+    pub fn new(particles: &Particles) -> Self {
+        let (w, h) = (particles.width(), particles.height());
+        let (xs, ys) = (axis(w), axis(h));
+        let (cw, ch) = (xs.len(), ys.len());
+        let node = |xi: I, yi: I| particles.data[particles.flatten((xs[xi], ys[yi]))];
+
+        let mut links = vec![];
+        let mut link = |a: (I, I), b: (I, I)| {
+            let rest = (node(b.0, b.1) - node(a.0, a.1)).norm();
+            links.push((a.1 * cw + a.0, b.1 * cw + b.0, rest));
+        };
+        for yi in 0..ch {
+            for xi in 0..cw {
+                if xi + 1 < cw {
+                    link((xi, yi), (xi + 1, yi));
+                }
+                if yi + 1 < ch {
+                    link((xi, yi), (xi, yi + 1));
+                }
+                if xi + 1 < cw && yi + 1 < ch {
+                    link((xi, yi), (xi + 1, yi + 1));
+                    link((xi + 1, yi), (xi, yi + 1));
+                }
+            }
+        }
+
+        Hierarchy {
+            cells_x: cells(&xs, w),
+            cells_y: cells(&ys, h),
+            xs,
+            ys,
+            links,
+            start: vec![],
+            positions: vec![],
+            pinned: vec![],
+        }
+    }
+
+    /* RUN iterations PASSES OVER THE COARSE SPRINGS, THEN MOVE THE GRID PARTICLES BY THE BILINEAR */
+    /* INTERPOLATION OF HOW FAR THE SURROUNDING NODES MOVED. A NODE IS PINNED WHEN ANY GRID PARTICLE */
+    /* NEAREST TO IT IS PINNED OR ASLEEP, SO THE COARSE LEVEL NEVER DRAGS A PIN ALONG */
+    /* THE COARSE SPRINGS ONLY RESIST STRETCHING: A FOLDED OR DRAPED CLOTH IS LEGITIMATELY SHORTER */
+    /* BETWEEN DISTANT NODES, AND THE FINE SPRINGS HANDLE COMPRESSION AND BENDING */
+    pub fn solve(&mut self, particles: &mut Particles, iterations: I, rest_scale: F) {
+        let (w, h) = (particles.width(), particles.height());
+        let cw = self.xs.len();
+        let n = cw * self.ys.len();
+
+        self.pinned.clear();
+        self.pinned.resize(n, false);
+        for y in 0..h {
+            for x in 0..w {
+                let i = particles.flatten((x, y));
+                if particles.pinned(i) || particles.asleep(i) {
+                    self.pinned[nearest(self.cells_y[y]) * cw + nearest(self.cells_x[x])] = true;
+                }
+            }
+        }
+        self.start.clear();
+        for &y in &self.ys {
+            for &x in &self.xs {
+                self.start.push(particles.data[particles.flatten((x, y))]);
+            }
+        }
+        self.positions.clone_from(&self.start);
+
+        let (positions, pinned) = (&mut self.positions, &self.pinned);
+        for _ in 0..iterations {
+            for &(a, b, rest) in &self.links {
+                let diff = positions[b] - positions[a];
+                let (d, rest) = (diff.norm(), rest * rest_scale);
+                let free = (!pinned[a]) as I + (!pinned[b]) as I;
+                if d <= rest || free == 0 {
+                    continue;
+                }
+                let correction = diff * ((d - rest) / d / free as F);
+                if !pinned[a] {
+                    positions[a] += correction;
+                }
+                if !pinned[b] {
+                    positions[b] -= correction;
+                }
+            }
+        }
+
+        let delta = |k: I| self.positions[k] - self.start[k];
+        for y in 0..h {
+            let (ky, ty) = self.cells_y[y];
+            let ky1 = (ky + 1).min(self.ys.len() - 1);
+            for x in 0..w {
+                let (kx, tx) = self.cells_x[x];
+                let kx1 = (kx + 1).min(cw - 1);
+                let top = delta(ky * cw + kx) * (1. - tx) + delta(ky * cw + kx1) * tx;
+                let bottom = delta(ky1 * cw + kx) * (1. - tx) + delta(ky1 * cw + kx1) * tx;
+                let i = particles.flatten((x, y));
+                particles.offset(i, top * (1. - ty) + bottom * ty);
+            }
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use bevy::prelude::*;
+
+use crate::{bvh::Aabb, camera::OrbitCamera, cloth::{Cloth, ClothSim}, to_v, F, I, P, V};
+
+/* LEVEL OF DETAIL: CLOTHS FAR FROM THE CAMERA OR OUT OF VIEW STEP LESS OFTEN, WITH FEWER ITERATIONS */
+/* AND NO SELF-COLLISION, AND UPDATE THEIR MESH LESS OFTEN. LEVEL 0 IS THE FULL SIMULATION */
+
+// DISTANCES FROM THE CAMERA TO THE BOX OF A CLOTH WHERE THE NEXT LEVEL STARTS
+const LOD_NEAR: F = 25.;
+const LOD_FAR: F = 60.;
+
+// HALF THE OPENING ANGLE OF THE CONE STANDING IN FOR THE VIEW FRUSTUM, IN DEGREES
+const VIEW_HALF_ANGLE: F = 50.;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LodLevel {
+    /* FRAMES PER STEP, THE STEP COVERS ALL OF THEM UP TO THE max_dt OF THE CLOTH */
+    pub interval: I,
+    /* CAP ON THE CONSTRAINT ITERATIONS PER STEP, None KEEPS THE CONFIG */
+    pub iterations: Option<I>,
+    pub self_collision: bool,
+    /* FRAMES PER MESH UPDATE */
+    pub mesh_interval: I,
+}
+
+pub const FULL: LodLevel = LodLevel { interval: 1, iterations: None, self_collision: true, mesh_interval: 1 };
+
+pub const LEVELS: [LodLevel; 3] = [
+    FULL,
+    LodLevel { interval: 2, iterations: Some(8), self_collision: false, mesh_interval: 2 },
+    LodLevel { interval: 4, iterations: Some(4), self_collision: false, mesh_interval: 4 },
+];
+
+/* THE LEVEL OF A CLOTH WITH THE WORLD BOX bounds SEEN FROM eye LOOKING ALONG forward */
+/* THE BOX COUNTS AS IN VIEW IF ITS BOUNDING SPHERE TOUCHES THE VIEW CONE, OUT OF VIEW IS THE LAST LEVEL */
+pub fn level_for(eye: P, forward: V, bounds: &Aabb) -> I {
+    let center = nalgebra::center(&bounds.min, &bounds.max);
+    let radius = (bounds.max - bounds.min).norm() / 2.;
+    let to = center - eye;
+    let distance = to.norm();
+    if distance <= radius {
+        return 0;
+    }
+    let slack = (radius / distance).asin();
+    if forward.angle(&to) > VIEW_HALF_ANGLE.to_radians() + slack {
+        return LEVELS.len() - 1;
+    }
+    match distance - radius {
+        d if d < LOD_NEAR => 0,
+        d if d < LOD_FAR => 1,
+        _ => 2,
+    }
+}
+
+/* WHETHER auto_lod PICKS THE LEVELS, --lod */
+pub struct AutoLod(pub bool);
+
+/* THE LEVEL OF DETAIL OF A CLOTH ENTITY, SET BY auto_lod OR BY HAND */
+#[derive(Debug, Default)]
+pub struct ClothLod {
+    pub level: I,
+    /* FRAMES SINCE THE LAST STEP AND SINCE THE LAST MESH UPDATE */
+    frames: I,
+    mesh_frames: I,
+}
+
+impl ClothLod {
+    pub fn current(&self) -> LodLevel {
+        LEVELS[self.level.min(LEVELS.len() - 1)]
+    }
+
+    /* COUNT A FRAME AND SET THE CLOTH UP FOR A STEP IF ONE IS DUE, RETURNING WHETHER IT IS */
+    /* THE STEP COVERS THE FRAMES SINCE THE LAST ONE, SO RETURNING TO FULL RATE ONLY EVER TAKES A SINGLE */
+    /* CATCH-UP STEP OF THE FRAMES ALREADY SKIPPED INSTEAD OF INTEGRATING A LONG GAP */
+    pub fn advance(&mut self, cloth: &mut ClothSim) -> bool {
+        let level = self.current();
+        self.frames += 1;
+        self.mesh_frames += 1;
+        if self.frames < level.interval {
+            return false;
+        }
+        cloth.set_lod(self.frames as F, &level);
+        self.frames = 0;
+        true
+    }
+
+    /* WHETHER THE MESH SHOULD BE UPDATED THIS FRAME, RESTARTING THE COUNT IF SO */
+    pub fn mesh_due(&mut self) -> bool {
+        if self.mesh_frames < self.current().mesh_interval {
+            return false;
+        }
+        self.mesh_frames = 0;
+        true
+    }
+}
+
+/* PICK THE LEVEL OF EVERY CLOTH FROM THE ORBIT CAMERA */
+pub fn auto_lod(
+    enabled: Res<AutoLod>,
+    cameras: Query<(&OrbitCamera, &GlobalTransform)>,
+    mut cloths: Query<(&Cloth, &mut ClothLod)>,
+) {
+    if !enabled.0 {
+        return;
+    }
+    let (eye, forward) = match cameras.iter().next() {
+        Some((_, transform)) => (P::from(to_v(transform.translation)), to_v(transform.rotation * -Vec3::unit_z())),
+        None => return,
+    };
+    for (cloth, mut lod) in cloths.iter_mut() {
+        /* THE MESH BOUNDS ARE EMPTY UNTIL THE FIRST MESH UPDATE */
+        let bounds = cloth.aabb();
+        lod.level = if bounds.min.x > bounds.max.x {
+            0
+        } else {
+            level_for(eye, forward, &bounds.transformed(&cloth.frame()))
+        };
+    }
+}
@@ -1,98 +1,1218 @@
+mod args;
+mod attach;
+mod bake;
+mod bindings;
+mod bvh;
+mod camera;
+mod capture;
 mod cloth;
+mod collider;
+mod config;
+mod contact;
+mod damping;
+mod debug;
+mod error;
+mod event;
+mod fabric;
+mod field;
+mod garment;
+#[cfg(feature = "gpu")]
+mod gpu;
+mod gravity;
 mod grid;
+mod headless;
+mod hierarchy;
+mod lod;
+mod markers;
+mod material;
+mod metrics;
+mod noise;
+mod particles;
+mod pick;
+mod pillow;
+mod pins;
+mod probe;
+mod props;
+mod record;
+mod regression;
+mod reload;
+mod render;
+mod scenario;
+mod scene;
+mod skin;
+mod slots;
+mod spatial;
+mod stats;
+mod stitch;
+mod strain;
+mod volume;
+mod weld;
+#[cfg(feature = "async_sim")]
+mod worker;
 
-use bevy::{
-    prelude::*,
-    render::{mesh::Indices, pipeline::PrimitiveTopology},
+use bevy::{prelude::*, render::pipeline::PrimitiveTopology};
+use nalgebra::{
+    geometry::{Isometry3, Point3, Quaternion, Translation3, UnitQuaternion},
+    Vector3,
 };
-use bevy_mod_picking::*;
-use nalgebra::{geometry::Point3, Vector3};
-use rand::Rng;
 
-use cloth::Cloth;
+use args::{Args, Dimensions};
+use bake::{Bake, BakeMaterial};
+use bindings::{Action, InputMap};
+use bvh::Aabb;
+use camera::OrbitCamera;
+use capture::Capture;
+use cloth::{Axis, Cloth, ClothSim, ConstraintId, ConstraintKind, TileUvs};
+use collider::{Collider, Colliders, TriMesh};
+use config::{ClothConfig, NormalWeighting, PinPreset, Pose};
+use contact::ClothContacts;
+use debug::{DebugLayer, DebugMode};
+use error::ClothError;
+use event::ClothEvent;
+use fabric::Fabrics;
+use lod::{AutoLod, ClothLod};
+use field::{FieldExample, ForceFields};
+use markers::PinMarkers;
+use pick::{Attractors, CursorRay, Drag, Grab, Hovered, InteractionSettings, Press};
+use material::ClothMaterials;
+use probe::ProbeSpeed;
+use record::Recorder;
+use scenario::{Scenario, ScenarioRunner};
+use skin::SkinOptions;
+use stats::{Phase, StatsHistory, Stopwatch};
+use stitch::Stitches;
+use strain::StrainOverlay;
 
 type I = usize;
+#[cfg(not(feature = "f64"))]
 type F = f32;
+#[cfg(feature = "f64")]
+type F = f64;
 type P = Point3<F>;
 type V = Vector3<F>;
 
+/* CONVERSIONS AT THE BOUNDARY WITH BEVY, WHICH ALWAYS WORKS IN f32 */
+fn to_array(p: P) -> [f32; 3] {
+    [p.x as f32, p.y as f32, p.z as f32]
+}
+
+fn to_vec3(v: V) -> Vec3 {
+    Vec3::new(v.x as f32, v.y as f32, v.z as f32)
+}
+
+fn to_v(v: Vec3) -> V {
+    V::new(v.x() as F, v.y() as F, v.z() as F)
+}
+
+/* RIGID PART OF A TRANSFORM, THE SCALE IS LEFT OUT */
+fn to_isometry(translation: Vec3, rotation: Quat) -> Isometry3<F> {
+    let q = Vec4::from(rotation);
+    let rotation = UnitQuaternion::from_quaternion(Quaternion::new(q.w() as F, q.x() as F, q.y() as F, q.z() as F));
+    Isometry3::from_parts(Translation3::from(to_v(translation)), rotation)
+}
+
+struct TexturePath(String);
+
+/* WHETHER THE CAMERA USES A PARALLEL PROJECTION INSTEAD OF A PERSPECTIVE ONE */
+struct Orthographic(bool);
+
+/* WHETHER TO ADD THE EXAMPLE OF TWO CLOTHS STACKED OVER A SPHERE */
+struct Stack(bool);
+
+/* WHETHER THE MAIN CLOTH IS AN INFLATED PILLOW WITH A SPHERE POKING IT */
+struct Balloon(bool);
+
+/* WHETHER TO ADD THE EXAMPLE OF TWO PANELS THAT CAN BE SEWN TOGETHER */
+struct Panels(bool);
+
+/* WHETHER THE MAIN CLOTH FALLS ONTO A FLOOR PLANE WHOSE RESTITUTION R TOGGLES */
+struct Bounce(bool);
+
+// HEIGHT OF THE FLOOR PLANE BELOW THE CLOTH, AND THE RESTITUTION R SWITCHES IT TO
+const BOUNCE_DROP: F = 8.;
+const BOUNCE_RESTITUTION: F = 0.5;
+
+/* MASS OF THE BALL DROPPED ONTO THE MAIN CLOTH, IF ANY */
+struct Trampoline(Option<F>);
+
+/* WHETHER TO ADD THE EXAMPLE OF A SOLID SAIL NEXT TO A POROUS NET */
+struct Sails(bool);
+
+/* WHETHER TO HANG THE EXAMPLE ROPES NEXT TO THE MAIN CLOTH */
+struct Ropes(bool);
+
+/* WHETHER TO DROP A CLOTH BUILT FROM THE STAIRS MESH ONTO THE STAIRS */
+struct SoftStairs(bool);
+
+// HEIGHT ABOVE THE STAIRS THE CLOTH COPY OF THEM IS DROPPED FROM
+const SOFT_STAIRS_DROP: F = 4.;
+
+/* WHETHER TO HANG THE EXAMPLE BANNER TILED FROM A PATCH, AND ITS MIRROR IMAGE */
+struct Banner(bool);
+
+// CORNER OF THE BANNER, SIZE AND SUBDIVISIONS OF THE PATCH IT IS TILED FROM, THE NUMBER OF TILES AND THE GAP
+// TO ITS MIRROR IMAGE
+const BANNER_ORIGIN: [F; 3] = [30., 0., -20.];
+const BANNER_PATCH: (F, F) = (3., 5.);
+const BANNER_SUBDIVISIONS: I = 12;
+const BANNER_TILES: I = 3;
+const BANNER_GAP: F = 2.;
+
+/* WHETHER TO ADD THE EXAMPLE OF A LINED CAPE ON A MANNEQUIN, SEE garment */
+struct ExampleGarment(bool);
+
+/* WHETHER TO ADD THE EXAMPLE OF A FLAG WITH AN EMBLEM OF ANOTHER MATERIAL */
+struct EmblemFlag(bool);
 
-/* TIME-STEP SIZE */
-const DT: F = 0.05;
-const DT_SQ: F = DT * DT;
+// NUMBER OF EXAMPLE ROPES, THEIR SEGMENTS AND THICKNESS, AND THE GAP TO THE CLOTH AND BETWEEN THEM
+const ROPES: I = 3;
+const ROPE_SEGMENTS: I = 24;
+const ROPE_THICKNESS: F = 0.15;
+const ROPE_GAP: F = 1.5;
 
-const IMAGE_PATH: &str = "/home/phaqlow/projects/cloth_sim/assets/texture.png";
+/* THE FORCE FIELD TO PLACE AROUND THE MAIN CLOTH, IF ANY */
+struct ExampleField(Option<FieldExample>);
+
+/* THE SCENARIO TO PLAY NEXT TO THE MAIN CLOTH, IF ANY */
+struct ScenarioFile(Option<Scenario>);
+
+// CORNER OF THE SAIL, SIZE OF BOTH CLOTHS, THE GAP BETWEEN THEM AND THE POROSITY OF THE NET
+const SAILS_ORIGIN: [F; 3] = [-30., 0., -20.];
+const SAIL_SIZE: (F, F) = (6., 8.);
+const SAIL_GAP: F = 2.;
+const NET_POROSITY: F = 0.8;
+
+// CORNER OF THE FLAG BY ITS POLE, ITS SIZE AND THE COLOR OF ITS EMBLEM
+const FLAG_ORIGIN: [F; 3] = [-30., 0., 20.];
+const FLAG_SIZE: (F, F) = (9., 6.);
+const EMBLEM_COLOR: [f32; 3] = [0.75, 0.1, 0.1];
+
+// ROWS AT THE BOTTOM OF THE MAIN CLOTH THAT --wet-hem AFFECTS, AND THEIR DRAG COEFFICIENT
+const WET_HEM_ROWS: I = 3;
+const WET_HEM_DRAG: F = 4.;
+
+// REST LENGTH FACTOR W SHRINKS THE CLOTH TO, AND THE SIMULATED SECONDS IT TAKES
+const SHRINK_FACTOR: F = 0.8;
+const SHRINK_SECONDS: F = 2.;
+
+// SLOWEST TIME SCALE COMMA GOES DOWN TO BEFORE FREEZING THE CLOTH, AND PERIOD STARTS FROM WHEN FROZEN
+const MIN_TIME_SCALE: F = 1. / 32.;
+
+const STAIRS_PATH: &str = "assets/stairs.obj";
+
+// SPHERE THE STACKED CLOTHS ARE DROPPED ONTO, AWAY FROM THE MAIN CLOTH AND STAIRS
+const STACK_CENTER: [F; 3] = [30., -6., 25.];
+const STACK_RADIUS: F = 3.;
 
 fn main() {
-    App::build()
+    let args = match args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            if !err.is_empty() {
+                eprintln!("{}\n", err);
+            }
+            eprintln!("{}", args::USAGE);
+            std::process::exit(1);
+        }
+    };
+    let Args {
+        config,
+        dimensions,
+        texture,
+        orthographic,
+        stack,
+        probe_speed,
+        pillow,
+        panels,
+        bounce,
+        trampoline,
+        sails,
+        ropes,
+        soft_stairs,
+        banner,
+        garment,
+        emblem_flag,
+        field,
+        lod,
+        bake,
+        pin_markers,
+        strain_overlay,
+        headless,
+        metrics,
+        update_goldens,
+        scenario,
+        bench_scene,
+        bench_json,
+        skin,
+    } = args;
+
+    /* RUN THE SOLVER WITHOUT A WINDOW */
+    if let Some(steps) = headless {
+        if let Some(count) = bench_scene {
+            headless::bench_scene(count, steps, config, bench_json, lod);
+        } else if update_goldens {
+            regression::update_goldens();
+        } else if let Some(path) = scenario {
+            headless::run_scenario(&path, config);
+        } else {
+            headless::run(steps, dimensions, config, metrics);
+        }
+        return;
+    }
+
+    /* A SCENARIO THAT DOES NOT LOAD IS A TYPO ON THE COMMAND LINE, NOT SOMETHING TO CARRY ON WITHOUT */
+    let scenario = scenario.map(|path| {
+        Scenario::load(&path).unwrap_or_else(|err| {
+            eprintln!("failed to load {}: {}", path, err);
+            std::process::exit(1)
+        })
+    });
+
+    let mut app = App::build();
+    app.add_resource(TexturePath(texture))
+        .add_resource(ProbeSpeed(probe_speed))
+        .add_resource(Orthographic(orthographic))
+        .add_resource(Stack(stack))
+        .add_resource(Balloon(pillow))
+        .add_resource(Panels(panels))
+        .add_resource(Bounce(bounce))
+        .add_resource(Trampoline(trampoline))
+        .add_resource(Sails(sails))
+        .add_resource(Ropes(ropes))
+        .add_resource(SoftStairs(soft_stairs))
+        .add_resource(Banner(banner))
+        .add_resource(ExampleGarment(garment))
+        .add_resource(EmblemFlag(emblem_flag))
+        .add_resource(ExampleField(field))
+        .add_resource(ScenarioFile(scenario))
+        .add_resource(AutoLod(lod))
+        .add_resource(Bake::new(bake))
+        .add_resource(PinMarkers { visible: pin_markers })
+        .add_resource(StrainOverlay::new(strain_overlay.0, strain_overlay.1))
+        .add_resource(skin)
+        .add_resource(InputMap::load_or_default())
+        .add_resource(dimensions)
+        .add_resource(config)
+        .init_resource::<Colliders>()
+        .init_resource::<Recorder>()
+        .init_resource::<Capture>()
+        .init_resource::<ClothContacts>()
+        .init_resource::<InteractionSettings>()
+        .init_resource::<Attractors>()
+        .init_resource::<Grab>()
+        .init_resource::<CursorRay>()
+        .init_resource::<Hovered>()
+        .init_resource::<Stitches>()
+        .init_resource::<Fabrics>()
+        .init_resource::<ForceFields>()
+        .init_resource::<Input<Action>>()
+        .init_resource::<StatsHistory>()
+        .add_event::<ClothEvent>()
         .add_plugins(DefaultPlugins)
-        .add_plugin(PickingPlugin)
+        .add_asset::<BakeMaterial>()
         .add_startup_system(setup.system())
+        .add_startup_system(debug::setup.system())
+        .add_startup_system(probe::setup.system())
+        .add_startup_system(pins::setup.system())
+        .add_startup_system(gravity::setup.system())
+        .add_startup_system(markers::setup.system())
+        .add_startup_system(pick::setup.system())
+        .add_startup_system(strain::setup.system())
+        .add_startup_system(bake::setup.system())
+        .add_startup_system(stats::setup.system())
+        .add_system_to_stage(stage::PRE_UPDATE, bindings::resolve.system())
+        .add_system_to_stage(stage::PRE_UPDATE, attach::follow.system())
+        .add_system_to_stage(stage::PRE_UPDATE, garment::follow.system())
+        .add_system_to_stage(stage::PRE_UPDATE, pick::cast.system())
+        .add_system_to_stage(stage::PRE_UPDATE, field::collect.system())
+        .add_system(lod::auto_lod.system())
         .add_system(step.system())
+        .add_system(scenario::run.system())
+        .add_system(contact::collide.system())
+        .add_system(props::advance.system())
+        .add_system(stitch::solve.system())
+        .add_system(stats::readout.system())
+        .add_system(stats::sample.system())
+        .add_system(stats::controls.system())
+        .add_system(record::input.system())
+        .add_system(skin::export_recording.system())
+        .add_system(pick::adjust.system())
         .add_system(interact.system())
-        .run();
+        .add_system(pick::indicator.system())
+        .add_system(pick::toggle_soft.system())
+        .add_system(pick::soft_overlay.system())
+        .add_system(pick::blow.system())
+        .add_system(pick::attract.system())
+        .add_system(camera::orbit.system())
+        .add_system(toggle_top_row.system())
+        .add_system(clear_pins.system())
+        .add_system(toggle_freeze.system())
+        .add_system(toggle_shrink.system())
+        .add_system(reset_cloth.system())
+        .add_system(toggle_restitution.system())
+        .add_system(scale_time.system())
+        .add_system(toggle_normal_weighting.system())
+        .add_system(change_resolution.system())
+        .add_system(gravity::tilt.system())
+        .add_system(gravity::update_arrow.system())
+        .add_system(toggle_brace.system())
+        .add_system(cut_in_half.system())
+        .add_system(toggle_upsampling.system())
+        .add_system(despawn_respawn.system())
+        .add_system(pins::input.system())
+        .add_system(reload::watch.system())
+        .add_system(bindings::watch.system())
+        .add_system(attach::sway.system())
+        .add_system(probe::drive.system())
+        .add_system(pillow::poke.system())
+        .add_system(attach::toggle.system())
+        .add_system(garment::animate.system())
+        .add_system(garment::toggle.system())
+        .add_system(stitch::toggle.system())
+        .add_system(debug::toggle.system())
+        .add_system(debug::update.system())
+        .add_system(markers::update.system())
+        .add_system(strain::update.system())
+        .add_system(bake::toggle.system())
+        .add_system(bake::bake.system())
+        .add_system(material::fallback.system())
+        .add_system(material::cycle.system())
+        .add_system(fabric::swap.system())
+        .add_system(material::wrap.system())
+        .add_system(event::emit.system())
+        .add_system(event::log_tears.system())
+        .add_system(event::flash_on_contact.system())
+        .add_system(capture::capture.system())
+        /* AFTER THE CAMERA HAS MOVED, SO THE GRAPH STAYS PUT ON SCREEN WHILE ORBITING */
+        .add_system_to_stage(stage::POST_UPDATE, stats::graph.system());
+    #[cfg(feature = "async_sim")]
+    app.add_system(worker::exchange.system());
+    #[cfg(feature = "mod_picking")]
+    app.add_plugin(bevy_mod_picking::PickingPlugin);
+    app.run();
+}
+
+/* SIMULATE SINGLE STEP, SHARED BETWEEN THE WINDOWED AND HEADLESS MODES */
+fn simulate(cloth: &mut ClothSim, colliders: &Colliders) {
+    // simulate single step, wind included
+    cloth.step(&colliders.0);
 }
 
-/* SIMULATE SINGLE STEP */
-fn step(mut meshes: ResMut<Assets<Mesh>>, mut cloth: Mut<Cloth>) {
-    // add gravity (negative y direction)
-    cloth.add_force(V::new(0., -0.2, 0.));
+fn step(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut recorder: ResMut<Recorder>,
+    colliders: Res<Colliders>,
+    attractors: Res<Attractors>,
+    fields: Res<ForceFields>,
+    mut cloth: Mut<Cloth>,
+    mut lod: Mut<ClothLod>,
+    transform: &GlobalTransform,
+    #[cfg(feature = "async_sim")] worker: Option<&worker::SimWorker>,
+) {
+    /* THE PARTICLES LIVE IN THE LOCAL SPACE OF THE ENTITY, WHICH THE RENDERER TRANSFORMS AS USUAL */
+    cloth.set_frame(to_isometry(transform.translation, transform.rotation));
+    cloth.set_attractors(&attractors.0);
+    cloth.set_force_fields(&fields.0);
 
-    // add some random crosswind
-    cloth.add_force(V::new(0., 0., rand::thread_rng().gen_range(-0.1, 0.1)));
+    /* A BACKGROUND WORKER STEPS THE CLOTH INSTEAD, worker::exchange PRESENTS ITS STEPS */
+    #[cfg(feature = "async_sim")]
+    let remote = worker.is_some();
+    #[cfg(not(feature = "async_sim"))]
+    let remote = false;
 
-    // simulate single step
-    cloth.step();
+    /* DURING REPLAY THE POSITIONS COME FROM THE RECORDING INSTEAD. FRAMES THE TIME SCALE HOLDS BACK */
+    /* ARE NOT RECORDED, SO A RECORDING REPLAYS AT FULL SPEED WHATEVER SCALE IT WAS RECORDED AT */
+    if !recorder.replaying {
+        let mut held = false;
+        if !remote && lod.advance(&mut cloth) {
+            held = !cloth.tick();
+            if !held {
+                simulate(&mut cloth, &colliders);
+            }
+        }
+        if !held {
+            recorder.record(&cloth);
+        }
+    }
 
     // update mesh for displaying based on the simulated step
-    let mesh = meshes.get_mut(&cloth.mesh_handle).unwrap();
-    cloth.update_mesh(mesh);
+    /* FETCHING THE MESH MUTABLY ALONE MARKS IT CHANGED AND RE-UPLOADS IT, SO SKIP IT ENTIRELY */
+    if !cloth.mesh_dirty() || !lod.mesh_due() {
+        return;
+    }
+    let mut stopwatch = Stopwatch::start();
+    match cloth.update_meshes(&mut meshes) {
+        Ok(()) => cloth.stats.record(Phase::Mesh, stopwatch.lap()),
+        Err(err) => error!("{}", err),
+    }
 }
 
 /* BOILERPLATE CODE FOR UI INITIALIZATION AND INTERACTION */
 
-fn interact(mbi: Res<Input<MouseButton>>, (mut cloth, entity): (Mut<Cloth>, &PickableMesh)) {
-    let lmb = mbi.pressed(MouseButton::Left);
-    let rmb = mbi.pressed(MouseButton::Right);
-    if !lmb && !rmb {
+/* A LEFT CLICK TOGGLES THE PINS UNDER THE MOUSE, HOLDING IT ON PINS DRAGS THEM ALONG, WITH SOFT SELECTION */
+/* HOLDING IT ANYWHERE DRAGS THE CLOTH THERE ALONG WITH THE FALLOFF AROUND IT */
+/* RIGHT UNPINS AND MIDDLE RIPS THE CLOTH UNDER THE MOUSE FOR AS LONG AS THEY ARE HELD */
+fn interact(
+    time: Res<Time>,
+    actions: Res<Input<Action>>,
+    settings: Res<InteractionSettings>,
+    ray: Res<CursorRay>,
+    mut grab: ResMut<Grab>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    cameras: Query<(&OrbitCamera, &GlobalTransform)>,
+    mut cloths: Query<(Entity, &mut Cloth)>,
+) {
+    let (now, radius) = (time.seconds_since_startup, settings.pick_radius);
+
+    /* SWITCHING MODES DROPS A PRESS OR DRAG IN PROGRESS, SO THE RELEASE DOES NOT TOGGLE ANY PINS */
+    if actions.just_pressed(Action::PaintStiffness) {
+        grab.painting_stiffness = !grab.painting_stiffness;
+        grab.press = None;
+        grab.drag = None;
+        info!("left button {}", if grab.painting_stiffness { "paints stiffness" } else { "pins" });
+    }
+
+    /* A RELEASE ENDS THE GESTURE, AS DOES THE CAMERA TAKING THE BUTTON OVER */
+    if actions.just_released(Action::TogglePin) {
+        if let Some(press) = grab.press.take() {
+            for (entity, mut cloth) in cloths.iter_mut() {
+                if entity != press.cloth {
+                    continue;
+                }
+                if let Err(err) = cloth.set_fixed(press.point, radius, !press.pinned) {
+                    warn!("failed to toggle pins: {}", err);
+                }
+            }
+        }
+        grab.drag = None;
+    }
+
+    /* THE MOUSE IS DRIVING THE CAMERA */
+    if actions.pressed(Action::Orbit) || actions.pressed(Action::Pan) {
         return;
     }
 
-    if let Some(it) = entity.intersection(&Group::default()).unwrap() {
-        let p = it.position();
-        let p = P::new(p.x(), p.y(), p.z());
-        cloth.set_fixed(p, lmb);
+    let lmb = actions.just_pressed(Action::TogglePin) && !grab.painting_stiffness;
+    let stiffen = (grab.painting_stiffness && actions.pressed(Action::TogglePin)).then(|| {
+        let factor = settings.stiffness_rate.powf(time.delta_seconds as F);
+        if actions.pressed(Action::Modify) { 1. / factor } else { factor }
+    });
+    let rmb = actions.pressed(Action::Unpin);
+    let mmb = actions.pressed(Action::Tear);
+    let paint = actions.pressed(Action::PaintWeights).then(|| if actions.pressed(Action::Modify) { 0. } else { 1. });
+    for (entity, mut cloth) in cloths.iter_mut() {
+        let (idx, p) = match pick::cursor_pick(&ray, &cloth, &settings) {
+            Some(pick) => pick,
+            None => continue,
+        };
+        /* PINS ARE TOGGLED AROUND THE PICKED PARTICLE, SO THE ONE THE CROSS MARKS IS ALWAYS AMONG THEM */
+        let vertex = cloth.world_position(idx);
+        if lmb && grab.press.is_none() {
+            let pinned = !cloth.fixed_near(vertex, radius).is_empty();
+            grab.press = Some(Press { cloth: entity, point: vertex, since: now, pinned });
+        }
+        if rmb {
+            if let Err(err) = cloth.set_fixed(vertex, radius, false) {
+                warn!("failed to unpin: {}", err);
+            }
+        }
+        if mmb {
+            let amount = settings.tear_rate * time.delta_seconds as F;
+            cloth.weaken_near(p, radius, amount, &mut meshes);
+        }
+        if let Some(value) = paint {
+            cloth.paint_weights(p, radius, value, settings.brush_strength * time.delta_seconds as F);
+        }
+        if let Some(factor) = stiffen {
+            cloth.scale_stiffness_near(p, radius, factor);
+        }
+    }
+
+    /* HELD LONG ENOUGH ON PINS, OR ANYWHERE WITH SOFT SELECTION: PICK THEM UP, KEEPING THEIR OFFSETS FROM */
+    /* THE GRABBED POINT */
+    if let Some(press) = grab.press.filter(|p| (p.pinned || settings.soft) && now - p.since >= pick::HOLD_SECONDS) {
+        grab.press = None;
+        let normal = match cameras.iter().next() {
+            Some((_, transform)) => to_v(transform.rotation.mul_vec3(-Vec3::unit_z())),
+            None => return,
+        };
+        for (entity, cloth) in cloths.iter_mut().filter(|(e, _)| *e == press.cloth) {
+            grab.drag = Some(Drag::new(entity, &cloth, press.point, normal, &settings));
+        }
+    }
+
+    let drag = match grab.drag.as_mut() {
+        Some(drag) => drag,
+        None => return,
+    };
+    let target = match ray.0.and_then(|ray| drag.target(ray)) {
+        Some(target) => target,
+        None => return,
+    };
+    let entity = drag.cloth;
+    for (_, mut cloth) in cloths.iter_mut().filter(|(e, _)| *e == entity) {
+        drag.apply(&mut cloth, target, &settings);
     }
 }
 
-fn setup(
+/* T PINS OR UNPINS THE ENTIRE TOP ROW */
+fn toggle_top_row(actions: Res<Input<Action>>, mut cloth: Mut<Cloth>) {
+    if actions.just_pressed(Action::TopRow) {
+        let fixed = !cloth.row_fixed(0);
+        cloth.set_row_fixed(0, fixed);
+    }
+}
+
+/* C RELEASES EVERY PIN OF EVERY CLOTH AT ONCE, SHIFT+C PINS EVERY PARTICLE */
+fn clear_pins(actions: Res<Input<Action>>, mut cloth: Mut<Cloth>) {
+    if actions.just_pressed(Action::PinAll) {
+        cloth.fix_all();
+    } else if actions.just_pressed(Action::ClearPins) {
+        cloth.clear_fixed();
+    }
+}
+
+/* Q FREEZES THE TOP HALF OF THE ROWS SO THE BOTTOM CAN SETTLE ON ITS OWN, AND PRESSED AGAIN UNFREEZES IT */
+fn toggle_freeze(actions: Res<Input<Action>>, mut cloth: Mut<Cloth>) {
+    if !actions.just_pressed(Action::Freeze) {
+        return;
+    }
+    if cloth.frozen() {
+        cloth.unfreeze_all();
+    } else {
+        let (_, h) = cloth.subdivisions();
+        cloth.freeze_region(|(_, y), _| y < h / 2);
+    }
+}
+
+/* W SHRINKS THE REST LENGTHS LIKE WET WOOL SO THE CLOTH PULLS TAUT BETWEEN ITS PINS, AND BACK AGAIN */
+fn toggle_shrink(actions: Res<Input<Action>>, mut cloth: Mut<Cloth>) {
+    if actions.just_pressed(Action::Shrink) {
+        let target = if cloth.rest_factor() < 1. { 1. } else { SHRINK_FACTOR };
+        let steps = (SHRINK_SECONDS / cloth.config.dt).round() as I;
+        cloth.animate_rest_scale(target, steps);
+    }
+}
+
+/* N STARTS THE CLOTH OVER FROM ITS POSE AND PIN PRESET */
+fn reset_cloth(actions: Res<Input<Action>>, mut meshes: ResMut<Assets<Mesh>>, mut cloth: Mut<Cloth>) {
+    if actions.just_pressed(Action::Reset) {
+        if let Err(err) = cloth.reset(&mut meshes) {
+            error!("failed to reset the cloth: {}", err);
+        }
+    }
+}
+
+/* COMMA HALVES AND PERIOD DOUBLES THE TIME SCALE OF THE CLOTH UNDER THE MOUSE, OR OF EVERY CLOTH */
+/* WITH THE MOUSE OVER NONE. HALVING BELOW MIN_TIME_SCALE FREEZES IT, DOUBLING STOPS AT FULL SPEED */
+fn scale_time(actions: Res<Input<Action>>, ray: Res<CursorRay>, mut cloths: Query<&mut Cloth>) {
+    let slower = actions.just_pressed(Action::Slower);
+    if !slower && !actions.just_pressed(Action::Faster) {
+        return;
+    }
+    let hovered = cloths.iter_mut().position(|cloth| pick::cursor_hit(&ray, &cloth).is_some());
+    for (_, mut cloth) in cloths.iter_mut().enumerate().filter(|(i, _)| hovered.map_or(true, |h| h == *i)) {
+        let scale = cloth.config.time_scale;
+        let scale = match slower {
+            true if scale / 2. < MIN_TIME_SCALE => 0.,
+            true => scale / 2.,
+            false => (scale * 2.).max(MIN_TIME_SCALE),
+        };
+        cloth.set_time_scale(scale);
+        info!("time scale {}", cloth.config.time_scale);
+    }
+}
+
+/* R SWITCHES THE FLOOR BETWEEN NOT BOUNCING AND BOUNCING THE CLOTH BACK AT HALF ITS SPEED */
+fn toggle_restitution(actions: Res<Input<Action>>, mut colliders: ResMut<Colliders>) {
+    if !actions.just_pressed(Action::Restitution) {
+        return;
+    }
+    if let Some(floor) = colliders.floor() {
+        let restitution = if colliders.0[floor].restitution > 0. { 0. } else { BOUNCE_RESTITUTION };
+        colliders.set_restitution(floor, restitution);
+        info!("floor restitution {}", restitution);
+    }
+}
+
+/* B ADDS OR REMOVES A SHORTENED DIAGONAL BRACE BETWEEN OPPOSITE CORNERS OF THE CLOTH */
+fn toggle_brace(actions: Res<Input<Action>>, mut brace: Local<Option<ConstraintId>>, mut cloth: Mut<Cloth>) {
+    if !actions.just_pressed(Action::Brace) {
+        return;
+    }
+
+    let (w, h) = cloth.subdivisions();
+    let (a, b) = ((0, 0), (w - 1, h - 1));
+    /* A RESOLUTION CHANGE REBUILDS THE CLOTH AND DROPS THE BRACE ALONG WITH IT */
+    match brace.take().filter(|id| cloth.constraints_at(a).map_or(false, |mut cs| cs.any(|c| c == *id))) {
+        Some(id) => cloth.remove_constraint(id),
+        None => {
+            let rest = 0.8 * (cloth.particles[a] - cloth.particles[b]).norm();
+            *brace = Some(cloth.add_constraint(a, b, Some(rest), ConstraintKind::Structural));
+        }
+    }
+}
+
+/* K CUTS THE CLOTH IN HALF THROUGH ITS MIDDLE COLUMN OF PARTICLES */
+fn cut_in_half(actions: Res<Input<Action>>, mut meshes: ResMut<Assets<Mesh>>, mut cloth: Mut<Cloth>) {
+    if actions.just_pressed(Action::Cut) && !cloth.torn() {
+        let (w, _) = cloth.subdivisions();
+        cloth.cut(|(x, _)| x.cmp(&(w / 2)), &mut meshes);
+    }
+}
+
+/* U SWITCHES THE RENDER MESH BETWEEN THE SIMULATION GRID AND A 3x UPSAMPLED ONE */
+fn toggle_upsampling(
+    actions: Res<Input<Action>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut cloth: Mut<Cloth>,
+) {
+    if actions.just_pressed(Action::Upsample) {
+        let factor = if cloth.render_subdivision() == 1 { 3 } else { 1 };
+        cloth.set_render_subdivision(factor, &mut meshes);
+    }
+}
+
+/* H SWITCHES THE RENDER NORMALS BETWEEN AREA- AND ANGLE-WEIGHTED SMOOTHING */
+fn toggle_normal_weighting(actions: Res<Input<Action>>, mut cloth: Mut<Cloth>) {
+    if actions.just_pressed(Action::NormalWeighting) {
+        let weighting = match cloth.config.normal_weighting {
+            NormalWeighting::Area => NormalWeighting::Angle,
+            NormalWeighting::Angle => NormalWeighting::Area,
+        };
+        cloth.set_normal_weighting(weighting);
+    }
+}
+
+/* +/- STEP THE GRID RESOLUTION UP AND DOWN */
+fn change_resolution(
+    actions: Res<Input<Action>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut cloth: Mut<Cloth>,
+) {
+    let delta: isize = if actions.just_pressed(Action::Finer) {
+        5
+    } else if actions.just_pressed(Action::Coarser) {
+        -5
+    } else {
+        return;
+    };
+
+    let (x, y) = cloth.subdivisions();
+    let resize = |s: I| (s as isize + delta).max(5).min(100) as I;
+    /* A ROPE STAYS ONE PARTICLE WIDE */
+    let x = if cloth.is_rope() { x } else { resize(x) };
+    if let Err(err) = cloth.rebuild(x, resize(y), &mut meshes) {
+        error!("failed to change the resolution: {}", err);
+    }
+}
+
+/* DELETE REMOVES ALL CLOTHS ALONG WITH THEIR MESHES AND OWN MATERIALS, INSERT BRINGS BACK THE MAIN ONE */
+fn despawn_respawn(
     mut commands: Commands,
-    meshes: ResMut<Assets<Mesh>>,
+    actions: Res<Input<Action>>,
+    mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    asset_server: Res<AssetServer>,
+    dimensions: Res<Dimensions>,
+    config: Res<ClothConfig>,
+    colliders: Res<Colliders>,
+    cloth_materials: Res<ClothMaterials>,
+    cloths: Query<(Entity, &Cloth)>,
+) {
+    if actions.just_pressed(Action::Despawn) {
+        for (entity, cloth) in cloths.iter() {
+            cloth.despawn(entity, &mut commands, &mut meshes, &mut materials);
+        }
+    } else if actions.just_pressed(Action::Respawn) && cloths.iter().next().is_none() {
+        let (mut cloth, _) = match main_cloth(&dimensions, &config) {
+            Ok(cloth) => cloth,
+            Err(err) => {
+                error!("failed to build the main cloth: {}", err);
+                return;
+            }
+        };
+        settle_main_cloth(&mut cloth, &dimensions, &colliders);
+        let cloth = Cloth::from(cloth);
+        spawn_cloth_at(&mut commands, &mut meshes, cloth, cloth_materials.current(), main_transform(&dimensions));
+        #[cfg(feature = "async_sim")]
+        attach_worker(&mut commands, &dimensions, &config, &colliders);
+    }
+}
+
+/* THE POSE PLACES THE CLOTH AT config.origin, IT IS MOVED TO BE CENTERED OVER THE WORLD ORIGIN */
+/* ALSO RETURNS THE TRANSLATION SO THE SCENERY AROUND IT CAN BE MOVED ALONG */
+fn main_cloth(dimensions: &Dimensions, config: &ClothConfig) -> Result<(ClothSim, V), ClothError> {
+    let (width, height) = dimensions.size;
+    let mut cloth = ClothSim::new(width, height, dimensions.subdivisions, config.clone())?;
+    if dimensions.wet_hem {
+        let h = dimensions.subdivisions.1;
+        cloth = cloth.with_drag(|(_, y)| if y + WET_HEM_ROWS >= h { WET_HEM_DRAG } else { 1. });
+    }
+    cloth.relax(dimensions.relax);
+    let bounds = cloth.bounding_box();
+    let center = nalgebra::center(&bounds.min, &bounds.max);
+    let recenter = V::new(-center.x, 0., -center.z);
+    cloth.translate(recenter);
+    let transform = main_transform(dimensions);
+    cloth.set_frame(to_isometry(transform.translation, transform.rotation));
+    Ok((cloth, recenter))
+}
+
+/* --settle DRAPES THE MAIN CLOTH BEFORE IT IS SPAWNED, SO THE COLLIDERS OF THE SCENE HAVE TO BE IN PLACE */
+fn settle_main_cloth(cloth: &mut ClothSim, dimensions: &Dimensions, colliders: &Colliders) {
+    if dimensions.settle > 0 {
+        cloth.settle_under_gravity(dimensions.settle, &colliders.0);
+    }
+}
+
+/* THE MAIN CLOTH ENTITY IS ROLLED ABOUT THE VIEWING AXIS BY --rotate, GRAVITY STAYS WORLD-SPACE */
+fn main_transform(dimensions: &Dimensions) -> Transform {
+    Transform::from_rotation(Quat::from_rotation_z(dimensions.rotation.to_radians() as f32))
+}
+
+/* WITH --async-sim A BACKGROUND WORKER STEPS A TWIN OF THE MAIN CLOTH JUST SPAWNED, BUILT THE SAME WAY */
+#[cfg(feature = "async_sim")]
+fn attach_worker(commands: &mut Commands, dimensions: &Dimensions, config: &ClothConfig, colliders: &Colliders) {
+    if config.async_sim {
+        match main_cloth(dimensions, config) {
+            Ok((mut cloth, _)) => {
+                settle_main_cloth(&mut cloth, dimensions, colliders);
+                commands.with(worker::SimWorker::start(cloth));
+            }
+            Err(err) => error!("failed to build the cloth of the background worker: {}", err),
+        }
+    }
+}
+
+fn spawn_cloth(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    cloth: Cloth,
+    material: Handle<StandardMaterial>,
+) {
+    spawn_cloth_at(commands, meshes, cloth, material, Transform::default());
+}
+
+/* SCALE IS REJECTED RATHER THAN BAKED INTO THE REST LENGTHS: THE COLLISION MARGINS, FORCES AND */
+/* PICK RADII WOULD ALL HAVE TO BE SCALED ALONG, SO THE SIZE IS SET WITH --size INSTEAD */
+fn spawn_cloth_at(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    cloth: Cloth,
+    material: Handle<StandardMaterial>,
+    transform: Transform,
 ) {
-    let (cloth, mesh_handle) = Cloth::new(10., 12., meshes);
+    spawn_cloth_with_regions(commands, meshes, cloth, material, &[], transform);
+}
 
+/* spawn_cloth_at WITH A CHILD ENTITY PER REGION OF THE CLOTH, SEE Cloth::assign_region, DRAWING ITS */
+/* SUB-MESH WITH THE MATERIAL AT THE SAME INDEX OF region_materials, OR material PAST THE END OF IT */
+fn spawn_cloth_with_regions(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    mut cloth: Cloth,
+    material: Handle<StandardMaterial>,
+    region_materials: &[Handle<StandardMaterial>],
+    mut transform: Transform,
+) {
+    if transform.scale != Vec3::one() {
+        warn!("ignoring the scale {:?} of a cloth, only rotation and translation are supported", transform.scale);
+        transform.scale = Vec3::one();
+    }
+    cloth.set_frame(to_isometry(transform.translation, transform.rotation));
+    let mesh = meshes.add(cloth.build_mesh());
+    cloth.attach_mesh(mesh.clone());
+    let regions: Vec<_> = (0..cloth.regions().len())
+        .map(|k| {
+            let region = meshes.add(Mesh::new(PrimitiveTopology::TriangleList));
+            cloth.attach_region_mesh(k, region.clone());
+            (region, region_materials.get(k).unwrap_or(&material).clone())
+        })
+        .collect();
+    if !regions.is_empty() {
+        if let Err(err) = cloth.update_meshes(meshes) {
+            error!("failed to fill the regions of a cloth: {}", err);
+        }
+    }
     commands
         .spawn(PbrComponents {
-            mesh: mesh_handle,
-            material: materials.add(asset_server.load(IMAGE_PATH).into()),
+            mesh,
+            material,
+            transform,
             ..Default::default()
         })
         .with(cloth)
-        .with(PickableMesh::default())
-        .spawn(LightComponents {
-            transform: Transform::from_translation(Vec3::new(6., -6., 15.)),
-            light: Light {
-                color: Color::rgb(2., 2., 2.),
+        .with(ClothLod::default())
+        .with(DebugLayer(DebugMode::Textured));
+    #[cfg(feature = "mod_picking")]
+    commands.with(bevy_mod_picking::PickableMesh::default());
+    /* THE REGIONS ARE IN THE LOCAL SPACE OF THE CLOTH LIKE ITS OWN MESH, SO THEY TAKE NO TRANSFORM OF THEIR OWN */
+    if regions.is_empty() {
+        return;
+    }
+    commands.with_children(|parent| {
+        for (mesh, material) in regions {
+            parent
+                .spawn(PbrComponents {
+                    mesh,
+                    material,
+                    ..Default::default()
+                })
+                .with(DebugLayer(DebugMode::Textured));
+        }
+    });
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut textures: ResMut<Assets<Texture>>,
+    asset_server: Res<AssetServer>,
+    texture_path: Res<TexturePath>,
+    orthographic: Res<Orthographic>,
+    dimensions: Res<Dimensions>,
+    config: Res<ClothConfig>,
+    stack: Res<Stack>,
+    balloon: Res<Balloon>,
+    panels: Res<Panels>,
+    bounce: Res<Bounce>,
+    trampoline: Res<Trampoline>,
+    sails: Res<Sails>,
+    ropes: Res<Ropes>,
+    soft_stairs: Res<SoftStairs>,
+    banner: Res<Banner>,
+    garment: Res<ExampleGarment>,
+    emblem_flag: Res<EmblemFlag>,
+    field: Res<ExampleField>,
+    scenario: Res<ScenarioFile>,
+    mut colliders: ResMut<Colliders>,
+    mut stitches: ResMut<Stitches>,
+) {
+    /* THE EFFECTIVE CONFIGURATION AFTER THE FLAGS, LOGGED ONCE */
+    info!("{:?}\n{:#?}", *dimensions, *config);
+
+    /* --size AND --subdivisions ARE VALIDATED ALREADY, SO THIS ONLY FAILS ON A BUG */
+    let (mut cloth, recenter) = match main_cloth(&dimensions, &config) {
+        Ok(cloth) => cloth,
+        Err(err) => {
+            error!("failed to build the main cloth: {}", err);
+            return;
+        }
+    };
+    attach::spawn_pole(&mut commands, &mut meshes, &mut materials, &cloth);
+
+    /* STAIRS FOR THE CLOTH TO DRAPE OVER ONCE UNPINNED, MOVED ALONG WITH IT */
+    let mut soft_stairs_mesh = None;
+    match TriMesh::load_obj(STAIRS_PATH) {
+        Ok(stairs) => {
+            let stairs = stairs.translated(recenter);
+            if soft_stairs.0 {
+                soft_stairs_mesh = Some(stairs.to_mesh());
+            }
+            commands.spawn(PbrComponents {
+                mesh: meshes.add(stairs.to_mesh()),
+                material: materials.add(Color::rgb(0.6, 0.6, 0.6).into()),
                 ..Default::default()
-            },
-            ..Default::default()
-        })
-        .spawn(Camera3dComponents {
-            transform: Transform::from_translation(Vec3::new(-5., 0., 0.))
-                .looking_at(Vec3::new(2., -6., 20.), Vec3::unit_y()),
-            ..Default::default()
-        })
-        .with(PickSource::default());
+            });
+            colliders.add(Collider::TriMesh(stairs));
+        }
+        Err(err) => warn!("failed to load {}: {}", STAIRS_PATH, err),
+    }
+    let bounds = cloth.bounding_box();
+    if bounce.0 {
+        let point = P::new(0., bounds.min.y - BOUNCE_DROP, 0.);
+        colliders.add(Collider::Plane { point, normal: V::y() });
+    }
+    if let Some(mass) = trampoline.0 {
+        let center = nalgebra::center(&bounds.min, &bounds.max) + V::y() * (props::BALL_DROP + props::BALL_RADIUS);
+        props::spawn_ball(&mut commands, &mut meshes, &mut materials, &mut colliders, center, mass);
+    }
+    if let Some(field) = field.0 {
+        commands.spawn((field.around(&bounds),));
+    }
+    settle_main_cloth(&mut cloth, &dimensions, &colliders);
+    scene::spawn_ground(&mut commands, &mut meshes, &mut materials, colliders.floor_height(), &bounds);
+    scene::spawn_lights(&mut commands, &scene::Lighting::load_or_default(), &bounds);
+    let mut orbit = scene::framing(&bounds);
+    let cloth_materials =
+        ClothMaterials::new(&texture_path.0, &asset_server, &mut materials, &mut textures);
+
+    if balloon.0 {
+        pillow::spawn_poker(&mut commands, &mut meshes, &mut materials, &mut colliders);
+    }
+    if panels.0 {
+        let panel_bounds = stitch::spawn_panels(&mut commands, &mut meshes, &config, &cloth_materials);
+        orbit = scene::framing(&panel_bounds);
+    }
+    if sails.0 {
+        let sails_bounds = spawn_sails(&mut commands, &mut meshes, &config, &cloth_materials);
+        orbit = scene::framing(&sails_bounds);
+    }
+    if ropes.0 {
+        let rope_bounds = spawn_ropes(&mut commands, &mut meshes, &config, &cloth_materials, &bounds);
+        orbit = scene::framing(&bounds.merge(&rope_bounds));
+    }
+    if let Some(mesh) = soft_stairs_mesh {
+        let soft_bounds = spawn_soft_stairs(&mut commands, &mut meshes, &mesh, &config, &cloth_materials);
+        orbit = scene::framing(&bounds.merge(&soft_bounds));
+    }
+    if banner.0 {
+        let banner_bounds = spawn_banner(&mut commands, &mut meshes, &config, &cloth_materials);
+        orbit = scene::framing(&banner_bounds);
+    }
+    if garment.0 {
+        garment::spawn(&mut commands, &mut meshes, &mut materials, &mut colliders, &mut stitches, &config);
+        orbit = scene::framing(&garment::bounds());
+    }
+    if emblem_flag.0 {
+        let emblem = materials.add(Color::rgb(EMBLEM_COLOR[0], EMBLEM_COLOR[1], EMBLEM_COLOR[2]).into());
+        let flag_bounds = spawn_emblem_flag(&mut commands, &mut meshes, &config, cloth_materials.current(), emblem);
+        orbit = scene::framing(&flag_bounds);
+    }
+    if stack.0 {
+        let stack_bounds =
+            spawn_stack(&mut commands, &mut meshes, &mut materials, &mut colliders, &config, &cloth_materials);
+        orbit = scene::framing(&stack_bounds);
+    }
+    if let Some(scenario) = scenario.0.clone() {
+        let runner = ScenarioRunner::new(scenario, &mut colliders);
+        match runner.scenario().cloth(&config) {
+            Ok(cloth) => {
+                orbit = scene::framing(&cloth.bounding_box());
+                spawn_cloth(&mut commands, &mut meshes, Cloth::from(cloth), cloth_materials.current());
+                commands.with(runner);
+            }
+            Err(err) => error!("failed to build the cloth of the scenario: {}", err),
+        }
+    }
+
+    let cloth = Cloth::from(cloth);
+    spawn_cloth_at(&mut commands, &mut meshes, cloth, cloth_materials.current(), main_transform(&dimensions));
+    #[cfg(feature = "async_sim")]
+    attach_worker(&mut commands, &dimensions, &config, &colliders);
+    orbit.orthographic = orthographic.0;
+    orbit.spawn(&mut commands).insert_resource(cloth_materials);
+}
+
+/* A SAIL AND A NET OF THE SAME SIZE HANGING SIDE BY SIDE, FACING THE WIND */
+/* THEY SHARE THE SEED SO THE SAME GUSTS DRIFT THROUGH BOTH, ONLY THE POROSITY DIFFERS */
+fn spawn_sails(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    config: &ClothConfig,
+    cloth_materials: &ClothMaterials,
+) -> Aabb {
+    let (width, height) = SAIL_SIZE;
+    let mut bounds = Aabb::empty();
+    for (k, &porosity) in [0., NET_POROSITY].iter().enumerate() {
+        let config = ClothConfig {
+            pose: Pose::HangingVertical,
+            origin: V::from(SAILS_ORIGIN) + V::new(k as F * (width + SAIL_GAP), 0., 0.),
+            pin_preset: PinPreset::TopRow,
+            porosity,
+            ..config.clone()
+        };
+        let cloth = match Cloth::new(width, height, (cloth::SUBDIVISIONS, cloth::SUBDIVISIONS), config) {
+            Ok(cloth) => cloth,
+            Err(err) => {
+                error!("failed to build a sail: {}", err);
+                continue;
+            }
+        };
+        bounds = bounds.merge(&cloth.bounding_box());
+        spawn_cloth(commands, meshes, cloth, cloth_materials.current());
+    }
+    bounds
+}
+
+/* A FLAG ON A POLE WITH AN EMBLEM IN ITS MIDDLE THIRD, DRAWN AS A REGION WITH A MATERIAL OF ITS OWN */
+/* BOTH MATERIALS COVER ONE CLOTH, SO THE EMBLEM RIPPLES WITH THE REST OF THE FLAG WITHOUT A SEAM */
+fn spawn_emblem_flag(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    config: &ClothConfig,
+    fabric: Handle<StandardMaterial>,
+    emblem: Handle<StandardMaterial>,
+) -> Aabb {
+    let (width, height) = FLAG_SIZE;
+    let config = ClothConfig {
+        pose: Pose::HangingVertical,
+        origin: V::from(FLAG_ORIGIN),
+        pin_preset: PinPreset::LeftColumn,
+        ..config.clone()
+    };
+    let (w, h) = (cloth::SUBDIVISIONS, cloth::SUBDIVISIONS);
+    let mut cloth = match Cloth::new(width, height, (w, h), config) {
+        Ok(cloth) => cloth,
+        Err(err) => {
+            error!("failed to build the flag: {}", err);
+            return Aabb::empty();
+        }
+    };
+    if let Err(err) = cloth.assign_region("emblem", (((w - 1) / 3, (h - 1) / 3), (2 * (w - 1) / 3, 2 * (h - 1) / 3))) {
+        error!("failed to place the emblem: {}", err);
+    }
+    let bounds = cloth.bounding_box();
+    cloth.own_material(emblem.clone());
+    spawn_cloth_with_regions(commands, meshes, cloth, fabric, &[emblem], Transform::default());
+    bounds
+}
+
+/* ROPES HANGING FROM THE HEIGHT OF THE TOP OF THE MAIN CLOTH, WHOSE WORLD BOX IS cloth_bounds, IN A ROW */
+/* TO ITS RIGHT. THEY ARE AS LONG AS THE CLOTH IS TALL AND SHARE ITS CONFIG, SO THE SAME WIND SWINGS THEM */
+fn spawn_ropes(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    config: &ClothConfig,
+    cloth_materials: &ClothMaterials,
+    cloth_bounds: &Aabb,
+) -> Aabb {
+    let length = cloth_bounds.max.y - cloth_bounds.min.y;
+    let z = (cloth_bounds.min.z + cloth_bounds.max.z) / 2.;
+    let mut bounds = Aabb::empty();
+    for k in 1..=ROPES {
+        let config = ClothConfig {
+            pose: Pose::HangingVertical,
+            origin: V::new(cloth_bounds.max.x + k as F * ROPE_GAP, cloth_bounds.max.y, z),
+            pin_preset: PinPreset::TopRow,
+            thickness: ROPE_THICKNESS,
+            ..config.clone()
+        };
+        let rope = match Cloth::new_rope(length, ROPE_SEGMENTS, config) {
+            Ok(rope) => rope,
+            Err(err) => {
+                error!("failed to build a rope: {}", err);
+                continue;
+            }
+        };
+        bounds = bounds.merge(&rope.bounding_box());
+        spawn_cloth(commands, meshes, rope, cloth_materials.current());
+    }
+    bounds
+}
+
+/* A CLOTH BUILT FROM THE MESH OF THE STAIRS, DROPPED ONTO THEM FROM SOFT_STAIRS_DROP ABOVE. THE FACES OF */
+/* THE STAIRS SHARE NO VERTICES, SO ONLY THE WELD HOLDS THE CLOTH TOGETHER, AND THEIR UVS ARE LEFT OUT */
+/* FOR THE FALLBACK UNWRAP TO TEXTURE IT */
+fn spawn_soft_stairs(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    stairs: &Mesh,
+    config: &ClothConfig,
+    cloth_materials: &ClothMaterials,
+) -> Aabb {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    if let Some(positions) = stairs.attribute(Mesh::ATTRIBUTE_POSITION) {
+        mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions.clone());
+    }
+    mesh.set_indices(stairs.indices().cloned());
+    let config = ClothConfig { origin: V::y() * SOFT_STAIRS_DROP, ..config.clone() };
+    let cloth = match Cloth::from_mesh(&mesh, config) {
+        Ok(cloth) => cloth,
+        Err(err) => {
+            error!("failed to build the soft stairs: {}", err);
+            return Aabb::empty();
+        }
+    };
+    let bounds = cloth.bounding_box();
+    spawn_cloth(commands, meshes, cloth, cloth_materials.current());
+    bounds
+}
+
+/* A PATCH HUNG FROM A POLE AT ITS LEFT EDGE, TILED BANNER_TILES TIMES WITH STITCHED BORDERS SO THE */
+/* BANNER HANGS FROM A POLE AT EVERY SEAM, AND ITS MIRROR IMAGE HANGING FROM THE RIGHT NEXT TO IT */
+fn spawn_banner(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    config: &ClothConfig,
+    cloth_materials: &ClothMaterials,
+) -> Aabb {
+    let (width, height) = BANNER_PATCH;
+    let config = ClothConfig {
+        pose: Pose::HangingVertical,
+        origin: V::from(BANNER_ORIGIN),
+        pin_preset: PinPreset::LeftColumn,
+        ..config.clone()
+    };
+    let banners = ClothSim::new(width, height, (BANNER_SUBDIVISIONS, BANNER_SUBDIVISIONS), config)
+        .and_then(|patch| patch.tiled(BANNER_TILES, 1, true, TileUvs::Stretch))
+        .and_then(|banner| {
+            let (extent, mut mirror) = (banner.bounding_box(), banner.mirrored(Axis::X)?);
+            mirror.translate(V::x() * (extent.max.x - extent.min.x + BANNER_GAP));
+            Ok(vec![banner, mirror])
+        });
+    let mut bounds = Aabb::empty();
+    match banners {
+        Ok(banners) => {
+            for banner in banners.into_iter().map(Cloth::from) {
+                bounds = bounds.merge(&banner.bounding_box());
+                spawn_cloth(commands, meshes, banner, cloth_materials.current());
+            }
+        }
+        Err(err) => error!("failed to build the banner: {}", err),
+    }
+    bounds
+}
+
+/* TWO FREE CLOTHS DROPPED ONTO A SPHERE, THE UPPER ONE LANDING ON THE LOWER ONE */
+/* RETURNS THE BOX AROUND THE SPHERE AND BOTH CLOTHS FOR FRAMING THE CAMERA */
+fn spawn_stack(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    colliders: &mut Colliders,
+    config: &ClothConfig,
+    cloth_materials: &ClothMaterials,
+) -> Aabb {
+    let center = P::from(STACK_CENTER);
+    let sphere = Collider::Sphere { center, radius: STACK_RADIUS };
+    let mut bounds = sphere.bounds().unwrap();
+    colliders.add(sphere);
+    commands.spawn(PbrComponents {
+        mesh: meshes.add(Mesh::from(shape::Icosphere {
+            radius: STACK_RADIUS as f32,
+            subdivisions: 4,
+        })),
+        material: materials.add(Color::rgb(0.6, 0.6, 0.6).into()),
+        transform: Transform::from_translation(to_vec3(center.coords)),
+        ..Default::default()
+    });
+
+    let size = 10.;
+    for (layer, height) in [4., 7.].iter().enumerate() {
+        let config = ClothConfig {
+            pose: Pose::FlatHorizontal { height: *height },
+            origin: center.coords - V::new(size / 2., 0., size / 2.),
+            pin_preset: PinPreset::Free,
+            seed: config.seed + layer as u64 + 1,
+            ..config.clone()
+        };
+        let cloth = match Cloth::new(size, size, (cloth::SUBDIVISIONS, cloth::SUBDIVISIONS), config) {
+            Ok(cloth) => cloth,
+            Err(err) => {
+                error!("failed to build a stacked cloth: {}", err);
+                continue;
+            }
+        };
+        bounds = bounds.merge(&cloth.bounding_box());
+        spawn_cloth(commands, meshes, cloth, cloth_materials.current());
+    }
+    bounds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* THE SIMULATION FLOAT FOLLOWS THE f64 FEATURE AND SURVIVES THE TRIP THROUGH BEVY'S f32 WITHIN f32 PRECISION */
+    #[test]
+    fn float_type_follows_the_feature() {
+        assert_eq!(std::mem::size_of::<F>(), if cfg!(feature = "f64") { 8 } else { 4 });
+
+        let v = V::new(1. / 3., -2.5e3, 7e-4);
+        let back = to_v(to_vec3(v));
+        assert!((back - v).norm() <= v.norm() * f32::EPSILON as F);
+        assert_eq!(to_array(P::from(v)), [v.x as f32, v.y as f32, v.z as f32]);
+    }
 }
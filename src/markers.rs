@@ -0,0 +1,82 @@
+use bevy::{prelude::*, render::pipeline::PrimitiveTopology};
+
+use crate::{bindings::Action, cloth::Cloth, debug, to_array, F, V};
+
+/* SMALL OCTAHEDRA AT EVERY FIXED PARTICLE SO THE CURRENT PINS ARE ALWAYS VISIBLE */
+/* P TOGGLES THEM; REBUILT EVERY FRAME SINCE ATTACHMENTS MOVE PINNED PARTICLES TOO */
+
+// DISTANCE FROM THE CENTER OF A MARKER TO ITS TIPS
+const MARKER_SIZE: F = 0.12;
+
+pub struct PinMarkers {
+    pub visible: bool,
+}
+
+pub struct PinMarker;
+
+pub fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands
+        .spawn(PbrComponents {
+            mesh: meshes.add(Mesh::new(PrimitiveTopology::TriangleList)),
+            material: materials.add(StandardMaterial {
+                albedo: Color::rgb(1., 0.1, 0.1),
+                shaded: false,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .with(PinMarker);
+}
+
+/* ONE TRIANGLE PER OCTANT, WOUND SO THAT EVERY FACE POINTS OUTWARDS */
+pub fn octahedron() -> Vec<V> {
+    let mut vertices = vec![];
+    for &sx in &[1., -1.] {
+        for &sy in &[1., -1.] {
+            for &sz in &[1., -1.] {
+                let (x, y, z) = (V::x() * sx, V::y() * sy, V::z() * sz);
+                if sx * sy * sz > 0. {
+                    vertices.extend(&[x, y, z]);
+                } else {
+                    vertices.extend(&[x, z, y]);
+                }
+            }
+        }
+    }
+    vertices
+}
+
+pub fn update(
+    actions: Res<Input<Action>>,
+    mut markers: ResMut<PinMarkers>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    cloths: Query<&Cloth>,
+    mut query: Query<(&PinMarker, &Handle<Mesh>, &mut Draw)>,
+) {
+    if actions.just_pressed(Action::PinMarkers) {
+        markers.visible = !markers.visible;
+    }
+
+    let shape: Vec<V> = octahedron().into_iter().map(|v| v * MARKER_SIZE).collect();
+    for (_, handle, mut draw) in query.iter_mut() {
+        draw.is_visible = markers.visible;
+        if !markers.visible {
+            continue;
+        }
+
+        let mut positions = vec![];
+        for cloth in cloths.iter() {
+            let particles = &cloth.particles;
+            for (_, &p) in particles.iter().enumerate().filter(|(i, _)| particles.pinned(*i)) {
+                positions.extend(shape.iter().map(|&v| to_array(cloth.to_world(p) + v)));
+            }
+        }
+        if let Some(mesh) = meshes.get_mut(handle) {
+            debug::write_vertices(mesh, positions);
+        }
+    }
+}
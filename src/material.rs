@@ -0,0 +1,125 @@
+use bevy::{
+    asset::LoadState,
+    prelude::*,
+    render::texture::{AddressMode, TextureFormat},
+};
+
+use crate::{bindings::Action, cloth::Cloth};
+
+/* MATERIALS THE CLOTH CAN BE RENDERED WITH, CYCLED AT RUNTIME WITH THE M KEY */
+
+// DEFAULT TEXTURE, RELATIVE TO THE assets/ DIRECTORY
+pub const TEXTURE_PATH: &str = "texture.png";
+
+const FLAT_COLOR: Color = Color::rgb(0.8, 0.3, 0.3);
+
+// NUMBER OF SQUARES ALONG EACH SIDE OF THE PROCEDURAL CHECKERBOARD
+const CHECKER_SQUARES: usize = 8;
+const CHECKER_RESOLUTION: usize = 256;
+
+pub struct ClothMaterials {
+    texture: Handle<Texture>,
+    materials: Vec<Handle<StandardMaterial>>,
+    current: usize,
+}
+
+impl ClothMaterials {
+    pub fn new(
+        texture_path: &str,
+        asset_server: &AssetServer,
+        materials: &mut Assets<StandardMaterial>,
+        textures: &mut Assets<Texture>,
+    ) -> Self {
+        let texture = asset_server.load(texture_path);
+        Self {
+            materials: vec![
+                materials.add(texture.clone().into()),
+                materials.add(FLAT_COLOR.into()),
+                materials.add(textures.add(checkerboard()).into()),
+            ],
+            texture,
+            current: 0,
+        }
+    }
+
+    pub fn current(&self) -> Handle<StandardMaterial> {
+        self.materials[self.current].clone()
+    }
+}
+
+/* TILED UVS GO OUTSIDE 0..1 SO THE TEXTURES HAVE TO WRAP AROUND INSTEAD OF CLAMPING */
+fn repeat(texture: &mut Texture) {
+    texture.sampler.address_mode_u = AddressMode::Repeat;
+    texture.sampler.address_mode_v = AddressMode::Repeat;
+}
+
+fn checkerboard() -> Texture {
+    let square = CHECKER_RESOLUTION / CHECKER_SQUARES;
+    let data = (0..CHECKER_RESOLUTION)
+        .flat_map(|y| {
+            (0..CHECKER_RESOLUTION).flat_map(move |x| {
+                let v = if (x / square + y / square) % 2 == 0 { 230 } else { 40 };
+                vec![v, v, v, 255]
+            })
+        })
+        .collect();
+    let mut texture = Texture::new(
+        Vec2::new(CHECKER_RESOLUTION as f32, CHECKER_RESOLUTION as f32),
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    repeat(&mut texture);
+    texture
+}
+
+/* THE LOADED TEXTURE ONLY EXISTS ONCE LOADING FINISHES, SO ITS SAMPLER IS PATCHED THEN */
+pub fn wrap(
+    mut reader: Local<EventReader<AssetEvent<Texture>>>,
+    events: Res<Events<AssetEvent<Texture>>>,
+    cloth_materials: Res<ClothMaterials>,
+    mut textures: ResMut<Assets<Texture>>,
+) {
+    for event in reader.iter(&events) {
+        if let AssetEvent::Created { handle } = event {
+            if *handle == cloth_materials.texture {
+                if let Some(texture) = textures.get_mut(handle) {
+                    repeat(texture);
+                }
+            }
+        }
+    }
+}
+
+/* FALL BACK TO THE FLAT COLORED MATERIAL IF THE TEXTURE CANNOT BE LOADED */
+pub fn fallback(
+    asset_server: Res<AssetServer>,
+    mut cloth_materials: ResMut<ClothMaterials>,
+    mut query: Query<(&Cloth, &mut Handle<StandardMaterial>)>,
+) {
+    if cloth_materials.current != 0
+        || asset_server.get_load_state(&cloth_materials.texture) != LoadState::Failed
+    {
+        return;
+    }
+
+    warn!("failed to load cloth texture, falling back to a flat colored material");
+    cloth_materials.current = 1;
+    for (_, mut material) in query.iter_mut() {
+        *material = cloth_materials.current();
+    }
+}
+
+pub fn cycle(
+    actions: Res<Input<Action>>,
+    mut cloth_materials: ResMut<ClothMaterials>,
+    mut query: Query<(&Cloth, &mut Handle<StandardMaterial>)>,
+) {
+    if !actions.just_pressed(Action::CycleMaterial) {
+        return;
+    }
+
+    cloth_materials.current = (cloth_materials.current + 1) % cloth_materials.materials.len();
+    for (_, mut material) in query.iter_mut() {
+        *material = cloth_materials.current();
+    }
+}
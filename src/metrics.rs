@@ -0,0 +1,80 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::Duration,
+};
+
+use crate::{
+    cloth::ClothSim,
+    stats::{Series, StatsHistory},
+    I,
+};
+
+/* PER-STEP METRICS WRITTEN AS CSV, ONE ROW PER SIMULATED STEP, FOR PLOTTING STABILITY OVER A RUN */
+
+// COLUMNS OF THE FILE, IN ORDER
+const HEADER: &str = "step,step_seconds,kinetic_energy,max_strain,mean_strain,contacts,iterations,clamped";
+
+// ROWS BUFFERED BEFORE THEY ARE FLUSHED TO DISK, SO A CRASHED RUN STILL LEAVES MOST OF ITS ROWS
+const FLUSH_EVERY: I = 64;
+
+pub struct Metrics {
+    writer: BufWriter<File>,
+    rows: I,
+}
+
+impl Metrics {
+    /* TRUNCATES ANY EXISTING FILE AND WRITES THE HEADER */
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", HEADER)?;
+        Ok(Self { writer, rows: 0 })
+    }
+
+    /* APPEND THE STATE OF THE CLOTH AFTER step, WHICH TOOK elapsed OF WALL-CLOCK TIME */
+    pub fn record(&mut self, step: I, elapsed: Duration, cloth: &ClothSim) -> io::Result<()> {
+        writeln!(
+            self.writer,
+            "{},{:.6},{:.6},{:.6},{:.6},{},{},{}",
+            step,
+            elapsed.as_secs_f64(),
+            cloth.kinetic_energy(),
+            cloth.max_strain(),
+            cloth.mean_strain(),
+            cloth.contacts(),
+            cloth.stats.iterations,
+            cloth.stats.clamped,
+        )?;
+        self.rows += 1;
+        if self.rows % FLUSH_EVERY == 0 {
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/* THE BUFFERED TAIL IS WRITTEN OUT EVEN IF THE RUN ENDS WITHOUT AN EXPLICIT flush */
+impl Drop for Metrics {
+    fn drop(&mut self) {
+        if let Err(err) = self.writer.flush() {
+            eprintln!("failed to flush metrics: {}", err);
+        }
+    }
+}
+
+/* WRITE THE FRAMES OF A STATS HISTORY, OLDEST FIRST, WITH EACH SERIES NAMED AS ITS COLUMN ABOVE */
+pub fn write_history(path: impl AsRef<Path>, history: &StatsHistory) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let columns: Vec<_> = Series::ALL.iter().map(|series| series.column()).collect();
+    writeln!(writer, "frame,{}", columns.join(","))?;
+    for k in 0..history.frames() {
+        let row: Vec<_> = history.frame(k).iter().map(|v| format!("{:.6}", v)).collect();
+        writeln!(writer, "{},{}", k, row.join(","))?;
+    }
+    writer.flush()
+}
@@ -0,0 +1,52 @@
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{F, I, P, V};
+
+/* SMOOTH 3-D VALUE NOISE: RANDOM VALUES ON AN INTEGER LATTICE, BLENDED WITH A SMOOTHSTEP */
+/* DETERMINISTIC FOR A GIVEN SEED, WITH VALUES IN [-1, 1] */
+
+const SIZE: I = 256;
+
+pub struct Noise {
+    perm: Vec<I>,
+    values: Vec<F>,
+}
+
+impl Noise {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut perm: Vec<I> = (0..SIZE).collect();
+        perm.shuffle(&mut rng);
+        let values = (0..SIZE).map(|_| rng.gen_range(-1., 1.)).collect();
+        Self { perm, values }
+    }
+
+    fn lattice(&self, x: i64, y: i64, z: i64) -> F {
+        let wrap = |i: i64| i.rem_euclid(SIZE as i64) as I;
+        let h = self.perm[(self.perm[(self.perm[wrap(x)] + wrap(y)) % SIZE] + wrap(z)) % SIZE];
+        self.values[h]
+    }
+
+    pub fn sample(&self, p: P) -> F {
+        let (fx, fy, fz) = (p.x.floor(), p.y.floor(), p.z.floor());
+        let (x, y, z) = (fx as i64, fy as i64, fz as i64);
+        let smooth = |t: F| t * t * (3. - 2. * t);
+        let (tx, ty, tz) = (smooth(p.x - fx), smooth(p.y - fy), smooth(p.z - fz));
+        let lerp = |a: F, b: F, t: F| a + (b - a) * t;
+
+        let plane = |z: i64| {
+            let row = |y: i64| lerp(self.lattice(x, y, z), self.lattice(x + 1, y, z), tx);
+            lerp(row(y), row(y + 1), ty)
+        };
+        lerp(plane(z), plane(z + 1), tz)
+    }
+
+    /* THREE DECORRELATED SAMPLES, ONE PER AXIS, FAR APART IN THE NOISE FIELD */
+    pub fn sample_vector(&self, p: P) -> V {
+        V::new(
+            self.sample(p),
+            self.sample(p + V::new(31.7, 0., 0.)),
+            self.sample(p + V::new(0., 57.3, 0.)),
+        )
+    }
+}
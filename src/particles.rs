@@ -0,0 +1,498 @@
+use std::{ops::Deref, str::FromStr};
+
+use nalgebra::{geometry::Isometry3, zero, Affine3};
+use serde::Deserialize;
+
+use crate::{
+    grid::{Grid, GridIdx},
+    F, I, P, V,
+};
+
+/* HOW THE PARTICLES ARE ADVANCED IN TIME AFTER THE CONSTRAINTS ARE SOLVED */
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Integrator {
+    /* POSITION VERLET, THE VELOCITY IS IMPLIED BY THE PREVIOUS POSITION */
+    Verlet,
+    /* EXPLICIT VELOCITY, BETTER SUITED TO A VARYING dt AND TO SETTING VELOCITIES DIRECTLY */
+    SemiImplicitEuler,
+}
+
+impl FromStr for Integrator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "verlet" => Ok(Integrator::Verlet),
+            "euler" => Ok(Integrator::SemiImplicitEuler),
+            _ => Err(format!("unknown integrator '{}'", s)),
+        }
+    }
+}
+
+/* HOW FREELY A PARTICLE MAY MOVE: NOT AT ALL, ALONG A LINE OR WITHIN A PLANE, E.G. A CURTAIN RAIL */
+/* SLIDING PARTICLES KEEP THEIR FULL MASS, ONLY THE PART OF EVERY MOVE THE ANCHOR ALLOWS IS TAKEN */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Anchor {
+    Free,
+    Fixed,
+    Line { point: P, dir: V },
+    Plane { point: P, normal: V },
+}
+
+impl Anchor {
+    pub fn line(point: P, dir: V) -> Self {
+        Anchor::Line { point, dir: dir.normalize() }
+    }
+
+    pub fn plane(point: P, normal: V) -> Self {
+        Anchor::Plane { point, normal: normal.normalize() }
+    }
+
+    /* THE PART OF A DISPLACEMENT THE ANCHOR ALLOWS */
+    pub fn restrict(&self, v: V) -> V {
+        match *self {
+            Anchor::Free => v,
+            Anchor::Fixed => zero(),
+            Anchor::Line { dir, .. } => dir * dir.dot(&v),
+            Anchor::Plane { normal, .. } => v - normal * normal.dot(&v),
+        }
+    }
+
+    /* CLOSEST POSITION ON THE LINE OR PLANE, FREE AND FIXED PARTICLES STAY WHERE THEY ARE */
+    pub fn project(&self, p: P) -> P {
+        match *self {
+            Anchor::Free | Anchor::Fixed => p,
+            Anchor::Line { point, .. } | Anchor::Plane { point, .. } => point + self.restrict(p - point),
+        }
+    }
+
+    /* THE SAME LINE OR PLANE AFTER A RIGID MOVE */
+    pub fn transform(&self, iso: &Isometry3<F>) -> Self {
+        match *self {
+            Anchor::Free | Anchor::Fixed => *self,
+            Anchor::Line { point, dir } => Anchor::Line { point: iso * point, dir: iso * dir },
+            Anchor::Plane { point, normal } => Anchor::Plane { point: iso * point, normal: iso * normal },
+        }
+    }
+
+    /* transform FOR A RIGID MOVE THAT MAY ALSO MIRROR, WHICH AN Isometry3 CANNOT */
+    pub fn map(&self, t: &Affine3<F>) -> Self {
+        match *self {
+            Anchor::Free | Anchor::Fixed => *self,
+            Anchor::Line { point, dir } => Anchor::line(t * point, t * dir),
+            Anchor::Plane { point, normal } => Anchor::plane(t * point, t * normal),
+        }
+    }
+}
+
+/* COULOMB FRICTION OF A CONTACT THAT PUSHED ITS PARTICLES APART BY pushed ALONG normal: */
+/* THE PART OF THE RELATIVE MOVE slip TO CANCEL, ALL OF ITS TANGENTIAL PART WHEN THAT IS WITHIN */
+/* mu * pushed (STATIC FRICTION), OTHERWISE mu * pushed OF IT (KINETIC FRICTION) */
+pub fn friction(slip: V, normal: V, pushed: F, mu: F) -> V {
+    let tangential = slip - normal * normal.dot(&slip);
+    let (t, limit) = (tangential.norm(), mu * pushed);
+    if t <= limit {
+        tangential
+    } else {
+        tangential * (limit / t)
+    }
+}
+
+/* OUTGOING SPEED ALONG THE CONTACT NORMAL FOR A PARTICLE THAT CAME IN AT approach (NEGATIVE WHEN */
+/* MOVING INTO THE SURFACE), REFLECTED AND SCALED BY restitution. SLOWER IMPACTS THAN min_speed DO */
+/* NOT BOUNCE, SO A RESTING CLOTH DOES NOT JITTER UNDER GRAVITY */
+pub fn bounce(approach: F, restitution: F, min_speed: F) -> F {
+    if -approach > min_speed {
+        -approach * restitution
+    } else {
+        0.
+    }
+}
+
+/* STRUCTURE-OF-ARRAYS STORAGE FOR THE PARTICLES OF A CLOTH */
+/* EVERY ATTRIBUTE IS CONTIGUOUS SO THE HOT LOOPS STREAM THROUGH MEMORY AND CAN BE AUTO-VECTORIZED */
+/* DEREFS TO THE GRID OF POSITIONS FOR READING: particles[(x, y)] IS THE POSITION OF A PARTICLE */
+/* THE OTHER ATTRIBUTES ARE ACCESSED BY FLATTENED INDEX, AND EVERY WRITE GOES THROUGH A METHOD */
+/* PARTICLES SPLIT OFF ALONG A CUT ARE APPENDED PAST THE LAST ROW OF THE GRID */
+pub struct Particles {
+    p: Grid<P>,
+    old_p: Vec<P>,
+    /* ONLY KEPT UP TO DATE BY THE EULER INTEGRATOR, USE velocity TO READ IT */
+    v: Vec<V>,
+    a: Vec<V>,
+    inv_m: Vec<F>,
+    anchors: Vec<Anchor>,
+    /* FROZEN IN PLACE TO SAVE WORK, E.G. A TORN-OFF PIECE AT REST, UNLIKE A PIN THIS IS NOT USER-FACING */
+    asleep: Vec<bool>,
+    /* HELD STILL WHILE AUTHORING A SCENE, APART FROM THE ANCHORS SO THE PINS OUTLIVE IT */
+    frozen: Vec<bool>,
+    /* MULTIPLIER OF THE AIR FORCES ON THE FACES AROUND A PARTICLE, E.G. HIGHER FOR A HEAVY WET HEM */
+    drag: Vec<F>,
+    /* GRID INDEX EACH APPENDED PARTICLE WAS SPLIT OFF FROM */
+    origins: Vec<I>,
+    /* TIME STEP OF THE LAST INTEGRATION, 0 BEFORE THE FIRST */
+    dt: F,
+}
+
+impl Particles {
+    /* PARTICLES AT REST WITH UNIT MASS, NONE OF THEM FIXED */
+    pub fn from_fn(width: I, height: I, f: impl FnMut(GridIdx) -> P) -> Self {
+        let p = Grid::from_fn(width, height, f);
+        let n = p.len();
+        Self {
+            old_p: p.data.clone(),
+            p,
+            v: vec![zero(); n],
+            a: vec![zero(); n],
+            inv_m: vec![1.; n],
+            anchors: vec![Anchor::Free; n],
+            asleep: vec![false; n],
+            frozen: vec![false; n],
+            drag: vec![1.; n],
+            origins: vec![],
+            dt: 0.,
+        }
+    }
+
+    /* ROWS OF THE GRID, NOT COUNTING THE PARTICLES APPENDED BY split */
+    pub fn height(&self) -> I {
+        self.p.height()
+    }
+
+    pub fn grid_len(&self) -> I {
+        self.p.width() * self.p.height()
+    }
+
+    /* THE GRID PARTICLE THAT i IS OR WAS SPLIT OFF FROM */
+    pub fn origin(&self, i: I) -> I {
+        match i.checked_sub(self.grid_len()) {
+            Some(k) => self.origins[k],
+            None => i,
+        }
+    }
+
+    /* APPEND A FREE PARTICLE AT REST AT p WITH UNIT MASS, STANDING FOR THE GRID PARTICLE origin, RETURNING */
+    /* ITS INDEX. THE ONLY PLACE THE ATTRIBUTE ARRAYS GROW, SO THEY ALWAYS HAVE ONE ENTRY PER PARTICLE */
+    pub fn push(&mut self, p: P, origin: I) -> I {
+        self.p.data.push(p);
+        self.old_p.push(p);
+        self.v.push(zero());
+        self.a.push(zero());
+        self.inv_m.push(1.);
+        self.anchors.push(Anchor::Free);
+        self.asleep.push(false);
+        self.frozen.push(false);
+        self.drag.push(1.);
+        self.origins.push(origin);
+        self.p.len() - 1
+    }
+
+    /* APPEND AN IDENTICAL COPY OF A PARTICLE, RETURNING ITS INDEX */
+    pub fn split(&mut self, i: I) -> I {
+        let j = self.push(self.p.data[i], self.origin(i));
+        self.old_p[j] = self.old_p[i];
+        self.v[j] = self.v[i];
+        self.a[j] = self.a[i];
+        self.inv_m[j] = self.inv_m[i];
+        self.anchors[j] = self.anchors[i];
+        self.asleep[j] = self.asleep[i];
+        self.frozen[j] = self.frozen[i];
+        self.drag[j] = self.drag[i];
+        j
+    }
+
+    pub fn origins(&self) -> &[I] {
+        &self.origins
+    }
+
+    pub fn fixed(&self, i: I) -> bool {
+        self.anchors[i] == Anchor::Fixed
+    }
+
+    /* FIXED OR SLIDING */
+    pub fn pinned(&self, i: I) -> bool {
+        self.anchors[i] != Anchor::Free
+    }
+
+    pub fn set_fixed(&mut self, i: I, fixed: bool) {
+        self.set_anchor(i, if fixed { Anchor::Fixed } else { Anchor::Free });
+    }
+
+    pub fn anchor(&self, i: I) -> Anchor {
+        self.anchors[i]
+    }
+
+    /* A SLIDING PARTICLE IS MOVED ONTO ITS LINE OR PLANE, KEEPING THE ALLOWED PART OF ITS VELOCITY */
+    pub fn set_anchor(&mut self, i: I, anchor: Anchor) {
+        self.anchors[i] = anchor;
+        let (p, displacement) = (self.p.data[i], self.displacement(i));
+        self.place(i, p, displacement);
+    }
+
+    pub fn asleep(&self, i: I) -> bool {
+        self.asleep[i]
+    }
+
+    /* A PARTICLE FALLS ASLEEP AT REST AND WAKES UP WITHOUT ANY VELOCITY */
+    pub fn set_asleep(&mut self, i: I, asleep: bool) {
+        self.asleep[i] = asleep;
+        self.old_p[i] = self.p.data[i];
+    }
+
+    pub fn frozen(&self, i: I) -> bool {
+        self.frozen[i]
+    }
+
+    /* A FROZEN PARTICLE KEEPS ITS POSITION BIT FOR BIT, AND IS RELEASED WITHOUT ANY VELOCITY */
+    pub fn set_frozen(&mut self, i: I, frozen: bool) {
+        if self.frozen[i] && !frozen {
+            self.v[i] = zero();
+            self.old_p[i] = self.p.data[i];
+        }
+        self.frozen[i] = frozen;
+    }
+
+    /* FIXED, ASLEEP OR FROZEN, SO NOTHING BUT A KINEMATIC MOVE MAY DISPLACE IT */
+    pub fn held(&self, i: I) -> bool {
+        self.anchors[i] == Anchor::Fixed || self.asleep[i] || self.frozen[i]
+    }
+
+    pub fn drag(&self, i: I) -> F {
+        self.drag[i]
+    }
+
+    pub fn set_drag(&mut self, i: I, c: F) {
+        self.drag[i] = c;
+    }
+
+    pub fn set_mass(&mut self, i: I, m: F) {
+        self.inv_m[i] = 1. / m;
+    }
+
+    /* FIXED, SLEEPING AND FROZEN PARTICLES BEHAVE AS IF INFINITELY HEAVY */
+    pub fn inv_mass(&self, i: I) -> F {
+        if self.held(i) {
+            0.
+        } else {
+            self.inv_m[i]
+        }
+    }
+
+    /* MOVEMENT OVER THE LAST STEP, THE VERLET STAND-IN FOR THE VELOCITY */
+    pub fn displacement(&self, i: I) -> V {
+        self.p.data[i] - self.old_p[i]
+    }
+
+    pub fn old_position(&self, i: I) -> P {
+        self.old_p[i]
+    }
+
+    /* VELOCITY OVER THE LAST STEP, THE SAME FOR BOTH INTEGRATORS: CORRECTIONS MADE SINCE THE LAST */
+    /* INTEGRATION BY CONSTRAINTS AND COLLISIONS ARE INCLUDED, AS THE NEXT INTEGRATION WILL SEE THEM */
+    pub fn velocity(&self, i: I) -> V {
+        if self.dt > 0. {
+            self.displacement(i) / self.dt
+        } else {
+            self.v[i]
+        }
+    }
+
+    /* FOR VERLET THIS REWRITES old_p, BEFORE THE FIRST STEP ONLY THE EULER INTEGRATOR PICKS IT UP */
+    pub fn set_velocity(&mut self, i: I, v: V) {
+        self.v[i] = v;
+        if self.dt > 0. {
+            self.old_p[i] = self.p.data[i] - v * self.dt;
+        }
+    }
+
+    /* FOR INTEGRATION DONE ELSEWHERE, E.G. ON THE GPU, SO velocity KNOWS THE STEP IT WAS OVER */
+    pub fn set_last_dt(&mut self, dt: F) {
+        self.dt = dt;
+    }
+
+    /* MOVE A PARTICLE TO p AS IS, LEAVING ITS OLD POSITION, E.G. TO SHOW A RECORDED FRAME */
+    pub fn set_position(&mut self, i: I, p: P) {
+        self.p.data[i] = p;
+    }
+
+    /* MOVE A PARTICLE TO p, CONTINUING WITH THE GIVEN DISPLACEMENT PER STEP */
+    /* A SLIDING PARTICLE ENDS UP ON ITS LINE OR PLANE, A FIXED ONE WHEREVER IT IS PUT */
+    pub fn place(&mut self, i: I, p: P, displacement: V) {
+        let (p, displacement) = match self.anchors[i] {
+            Anchor::Fixed => (p, displacement),
+            anchor => (anchor.project(p), anchor.restrict(displacement)),
+        };
+        self.p.data[i] = p;
+        self.old_p[i] = p - displacement;
+    }
+
+    /* CHANGE THE MOVEMENT OVER THE LAST STEP WITHOUT MOVING THE PARTICLE, E.G. FOR FRICTION */
+    pub fn offset_displacement(&mut self, i: I, delta: V) {
+        if !self.held(i) {
+            self.old_p[i] -= self.anchors[i].restrict(delta);
+        }
+    }
+
+    /* SHORTEN THE MOVEMENT OVER THE LAST STEP TO AT MOST max, KEEPING ITS DIRECTION, BY PULLING p BACK */
+    /* TOWARDS old_p. THE EULER VELOCITY IS SCALED ALONG. RETURNS WHETHER THE PARTICLE WAS CLAMPED */
+    pub fn clamp_displacement(&mut self, i: I, max: F) -> bool {
+        let norm = self.displacement(i).norm();
+        if norm <= max || self.held(i) {
+            return false;
+        }
+        let scale = max / norm;
+        self.p.data[i] = self.old_p[i] + self.displacement(i) * scale;
+        self.v[i] *= scale;
+        true
+    }
+
+    /* FORGET THE VELOCITY, E.G. WHEN RELEASING A PIN OR AFTER TELEPORTING */
+    pub fn reset_velocity(&mut self, i: I) {
+        self.old_p[i] = self.p.data[i];
+    }
+
+    /* RIGID MOVE OF EVERY PARTICLE, INCLUDING FIXED AND SLEEPING ONES. OLD POSITIONS MOVE ALONG AND */
+    /* VELOCITIES AND FORCES ARE ROTATED, SO NO VELOCITY IS INJECTED */
+    pub fn transform(&mut self, iso: &Isometry3<F>) {
+        self.p.data.iter_mut().chain(&mut self.old_p).for_each(|p| *p = iso * *p);
+        self.v.iter_mut().chain(&mut self.a).for_each(|v| *v = iso * *v);
+        self.anchors.iter_mut().for_each(|anchor| *anchor = anchor.transform(iso));
+    }
+
+    pub fn add_force(&mut self, i: I, f: V) {
+        self.a[i] += f * self.inv_m[i];
+    }
+
+    pub fn offset(&mut self, i: I, v: V) {
+        if !self.asleep[i] && !self.frozen[i] {
+            self.p.data[i] += self.anchors[i].restrict(v);
+        }
+    }
+
+    /* EXTERNAL ACCELERATION ACCUMULATED SINCE THE LAST INTEGRATION */
+    pub fn acceleration(&self, i: I) -> V {
+        self.a[i]
+    }
+
+    /* DROP THE ACCUMULATED FORCES, FOR INTEGRATION DONE ELSEWHERE, E.G. ON THE GPU */
+    pub fn clear_forces(&mut self) {
+        self.a.iter_mut().for_each(|acc| *acc = zero());
+    }
+
+    /* SAME ACCELERATION FOR ALL PARTICLES REGARDLESS OF MASS, E.G. GRAVITY */
+    pub fn accelerate(&mut self, a: V) {
+        self.a.iter_mut().for_each(|acc| *acc += a);
+    }
+
+    /* FIXED, SLEEPING AND FROZEN PARTICLES ALSO DROP THEIR ACCELERATION SO NONE BUILDS UP UNTIL THEY MOVE AGAIN */
+    /* SLIDING PARTICLES ARE PROJECTED BACK ONTO THEIR LINE OR PLANE */
+    pub fn integrate(&mut self, integrator: Integrator, damping: F, dt: F) {
+        match integrator {
+            Integrator::Verlet => self.integrate_verlet(damping, dt),
+            Integrator::SemiImplicitEuler => self.integrate_euler(damping, dt),
+        }
+        self.dt = dt;
+    }
+
+    /* SEMI-IMPLICIT EULER: v += a dt, THEN p += v dt, WITH THE DAMPING APPLIED TO v */
+    /* THE VELOCITY IS TAKEN PER UNIT TIME, SO IT CARRIES OVER CORRECTLY WHEN dt CHANGES */
+    fn integrate_euler(&mut self, damping: F, dt: F) {
+        for i in 0..self.p.len() {
+            let anchor = self.anchors[i];
+            if !self.held(i) {
+                let v = damping * (self.velocity(i) + self.a[i] * dt);
+                self.v[i] = v;
+                self.old_p[i] = self.p.data[i];
+                self.p.data[i] = anchor.project(self.p.data[i] + v * dt);
+            }
+            self.a[i] = zero();
+        }
+    }
+
+    /* VERLET POSITION INTEGRATION AS ONE PASS OVER THE CONTIGUOUS ATTRIBUTE ARRAYS */
+    /* THE VELOCITY IS IMPLIED BY old_p, SO IT IS EFFECTIVELY RESCALED WHEN dt CHANGES */
+    fn integrate_verlet(&mut self, damping: F, dt: F) {
+        let held = self.anchors.iter().zip(&self.asleep).zip(&self.frozen);
+        let attributes = self.p.data.iter_mut().zip(&mut self.old_p).zip(&mut self.a).zip(held);
+        for (((p, old_p), a), ((anchor, &asleep), &frozen)) in attributes {
+            if *anchor != Anchor::Fixed && !asleep && !frozen {
+                let tmp = *p;
+                *p = anchor.project(*p + damping * (*p - *old_p) + *a * dt * dt);
+                *old_p = tmp;
+            }
+            *a = zero();
+        }
+    }
+}
+
+impl Deref for Particles {
+    type Target = Grid<P>;
+    fn deref(&self) -> &Self::Target { &self.p }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* ONE VERLET PASS OVER THE ARRAYS MOVES A FREE PARTICLE BY ITS DAMPED DISPLACEMENT PLUS a dt^2, LEAVES THE */
+    /* HELD ONES WHERE THEY ARE AND DROPS EVERY ACCELERATION. A SPLIT COPY CARRIES EVERY ATTRIBUTE ALONG */
+    #[test]
+    fn attribute_arrays_stay_in_step() {
+        // TIME STEP AND DAMPING OF THE PASS
+        const DT: F = 0.1;
+        const DAMPING: F = 0.5;
+
+        let mut particles = Particles::from_fn(3, 2, |(x, y)| P::new(x as F, y as F, 0.));
+        (0..particles.len()).for_each(|i| particles.place(i, particles.data[i], V::new(0.2, 0., 0.)));
+        particles.set_fixed(1, true);
+        particles.set_asleep(2, true);
+        particles.set_frozen(3, true);
+        particles.set_drag(5, 3.);
+        particles.accelerate(V::new(0., -10., 0.));
+        let before = particles.data.clone();
+
+        let copy = particles.split(5);
+        assert_eq!((copy, particles.grid_len(), particles.origin(copy)), (6, 6, 5));
+        assert_eq!(particles.data[copy], particles.data[5]);
+        assert_eq!(particles.displacement(copy), particles.displacement(5));
+        assert_eq!((particles.drag(copy), particles.acceleration(copy)), (3., particles.acceleration(5)));
+
+        particles.integrate(Integrator::Verlet, DAMPING, DT);
+        let moved = V::new(0.2 * DAMPING, -10. * DT * DT, 0.);
+        for i in [0, 4, 5, copy].iter().copied() {
+            assert!((particles.data[i] - before[i.min(5)] - moved).norm() < 1e-6, "particle {}", i);
+        }
+        for i in 1..4 {
+            assert_eq!(particles.data[i], before[i]);
+        }
+        assert!((0..particles.len()).all(|i| particles.acceleration(i) == V::zeros()));
+    }
+
+    /* SEMI-IMPLICIT EULER KEEPS ITS VELOCITY PER UNIT TIME, SO A SET VELOCITY SURVIVES A CHANGE OF TIME STEP, */
+    /* WHILE VERLET CARRIES THE DISPLACEMENT OVER AND SO SPEEDS UP WHEN THE STEP IS HALVED */
+    #[test]
+    fn euler_velocity_survives_a_smaller_step() {
+        // VELOCITY SET ON THE PARTICLE, AND THE TWO TIME STEPS TAKEN ONE AFTER THE OTHER
+        const SPEED: F = 2.;
+        const DTS: [F; 2] = [0.1, 0.05];
+
+        let coasting = |integrator| {
+            let mut particles = Particles::from_fn(1, 1, |_| P::origin());
+            particles.set_velocity(0, V::x() * SPEED);
+            particles.integrate(integrator, 1., DTS[0]);
+            particles.set_velocity(0, V::x() * SPEED);
+            particles.integrate(integrator, 1., DTS[1]);
+            particles.velocity(0).x
+        };
+        assert!((coasting(Integrator::SemiImplicitEuler) - SPEED).abs() < 1e-5);
+        assert!((coasting(Integrator::Verlet) - SPEED * DTS[0] / DTS[1]).abs() < 1e-5);
+
+        let mut falling = Particles::from_fn(1, 1, |_| P::origin());
+        for &dt in &DTS {
+            falling.accelerate(-V::y());
+            falling.integrate(Integrator::SemiImplicitEuler, 1., dt);
+        }
+        assert!((falling.velocity(0) + V::y() * (DTS[0] + DTS[1])).norm() < 1e-5);
+    }
+}
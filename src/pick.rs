@@ -0,0 +1,688 @@
+use std::collections::HashMap;
+
+use bevy::{
+    input::mouse::MouseWheel,
+    prelude::*,
+    render::{camera::Camera, pipeline::PrimitiveTopology},
+    window::{CursorLeft, WindowId},
+};
+
+use crate::{
+    bindings::Action,
+    camera::OrbitCamera,
+    cloth::{Attractor, Cloth, ClothSim},
+    debug,
+    grid::GridIdx,
+    markers, to_array, to_v, F, P, V,
+};
+
+/* HOW MUCH OF THE CLOTH A CLICK PINS OR RELEASES */
+/* HOLDING THE LEFT BUTTON ON PINNED PARTICLES DRAGS THEM AROUND INSTEAD */
+/* HOLD LEFT SHIFT AND SCROLL TO RESIZE, A WIRE SPHERE UNDER THE MOUSE SHOWS THE REACH */
+/* HOLDING V OVER THE CLOTH BLOWS AIR ALONG THE CAMERA RAY THROUGH THE MOUSE */
+/* HOLDING A OVER THE CLOTH PULLS IT TOWARDS THE POINT WHERE A WAS PRESSED, UNTIL IT IS RELEASED */
+/* HOLDING THE MIDDLE BUTTON RIPS THE CLOTH WITHIN THE SAME RADIUS AS PINNING */
+/* HOLDING D OVER THE CLOTH PAINTS ITS WEIGHT MAP UP TO 1 WITHIN THAT RADIUS, WITH SHIFT DOWN TO 0 */
+/* SCROLLING WHILE HOLDING D ADJUSTS THE STRENGTH OF THE BRUSH */
+/* I SWITCHES THE LEFT BUTTON FROM PINNING TO PAINTING STIFFNESS: HOLDING IT OVER THE CLOTH STIFFENS THE */
+/* CONSTRAINTS WITHIN THE RADIUS, WITH SHIFT IT SOFTENS THEM. THE WIREFRAME SHOWS WHAT WAS PAINTED */
+/* X SWITCHES SOFT SELECTION ON: A HOLD ON ANY PART OF THE CLOTH PICKS IT UP, AND THE FREE PARTICLES AROUND IT */
+/* FOLLOW WITH A FALLOFF THAT SHIFT+X SWITCHES BETWEEN LINEAR AND SMOOTHSTEP. SCROLLING WHILE DRAGGING */
+/* RESIZES THE FALLOFF, AND MARKERS FROM DARK TO LIGHT BLUE SHOW HOW CLOSELY EACH PARTICLE FOLLOWS */
+/* A CROSS INSIDE THE WIRE SPHERE MARKS THE PARTICLE A CLICK WOULD PIN, AND THE WINDOW TITLE NAMES IT. S CASTS */
+/* FOUR MORE RAYS AROUND THE CURSOR THAT VOTE ON IT, SO A CLICK NEAR AN EDGE DOES NOT FLICKER BETWEEN SIDES */
+/* THESE ARE THE DEFAULT BINDINGS, assets/input.ron CHANGES THEM */
+
+// BOUNDS OF THE PICK RADIUS AND THE RELATIVE CHANGE PER SCROLL STEP
+const MIN_PICK_RADIUS: F = 0.05;
+const MAX_PICK_RADIUS: F = 5.;
+const PICK_RADIUS_SPEED: F = 0.1;
+
+// NUMBER OF SEGMENTS IN EACH OF THE THREE CIRCLES OF THE INDICATOR, AND THE SIZE OF THE CROSS ON THE PARTICLE
+const INDICATOR_SEGMENTS: usize = 32;
+const VERTEX_CROSS_SIZE: F = 0.05;
+
+// RAYS CAST AROUND THE CURSOR FOR A MULTI-SAMPLED PICK, AND HOW MANY PIXELS OFF IT ALONG EACH DIAGONAL
+pub const SUB_RAYS: usize = 4;
+const SUB_RAY_OFFSET: f32 = 3.;
+
+// BOUNDS OF THE BRUSH STRENGTH
+const MIN_BRUSH_STRENGTH: F = 0.05;
+const MAX_BRUSH_STRENGTH: F = 10.;
+
+// DISTANCE WITHIN WHICH THE PULL OF AN ATTRACTOR STOPS GROWING
+const ATTRACTOR_CORE: F = 0.3;
+
+// HOLDING THE LEFT BUTTON THIS LONG ON PINNED PARTICLES PICKS THEM UP RATHER THAN RELEASING THEM
+pub const HOLD_SECONDS: f64 = 0.2;
+
+// BOUNDS OF THE SOFT SELECTION FALLOFF BEYOND THE PICK RADIUS. A DRAG TAKES ALONG EVERY FREE PARTICLE WITHIN
+// THE LARGEST, SO SCROLLING CAN GROW THE FALLOFF WHILE DRAGGING
+const MIN_SOFT_RADIUS: F = 0.2;
+pub const MAX_SOFT_RADIUS: F = 10.;
+
+// MARKER COLORS FROM THE LOOSEST TO THE FIRMEST FOLLOWING PARTICLES, ONE MESH EACH, AND THE MARKER SIZE
+const SOFT_COLORS: [(f32, f32, f32); 4] = [(0.1, 0.2, 0.5), (0.1, 0.4, 0.8), (0.3, 0.7, 1.), (0.8, 1., 1.)];
+const SOFT_MARKER_SIZE: F = 0.06;
+
+/* HOW THE PULL OF A SOFT DRAG FADES OUT, FROM 1 AT THE PICK RADIUS TO 0 AT THE FALLOFF RADIUS BEYOND IT */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Falloff {
+    Linear,
+    /* EASES IN AND OUT, SO THERE IS NO CREASE AT EITHER END */
+    Smoothstep,
+}
+
+impl Falloff {
+    pub fn weight(self, distance: F, core: F, radius: F) -> F {
+        let t = (1. - (distance - core).max(0.) / radius.max(F::EPSILON)).max(0.).min(1.);
+        match self {
+            Falloff::Linear => t,
+            Falloff::Smoothstep => t * t * (3. - 2. * t),
+        }
+    }
+}
+
+pub struct InteractionSettings {
+    pub pick_radius: F,
+    /* DISTANCE FROM THE RAY AT WHICH THE BLAST FADES OUT, AND ITS FORCE ON THE AXIS */
+    pub blast_radius: F,
+    pub blast_strength: F,
+    /* TEAR RESISTANCE WORN OFF PER SECOND AT THE CENTER OF A RIP, EVERY CONSTRAINT STARTS AT 1 */
+    pub tear_rate: F,
+    /* FORCE OF THE ATTRACTOR AT UNIT DISTANCE, AND THE DISTANCE BEYOND WHICH IT HAS NO PULL */
+    pub attractor_strength: F,
+    pub attractor_radius: F,
+    /* HOW FAR PER SECOND THE BRUSH MOVES THE WEIGHTS AT ITS CENTER TOWARDS THE PAINTED VALUE */
+    pub brush_strength: F,
+    /* FACTOR PER SECOND THAT PAINTING MULTIPLIES THE STIFFNESS UNDER THE CURSOR BY, OR DIVIDES IT BY */
+    pub stiffness_rate: F,
+    /* WHETHER A DRAG TAKES THE FREE CLOTH AROUND IT ALONG, HOW FAR BEYOND THE PICK RADIUS AND WITH WHICH CURVE */
+    pub soft: bool,
+    pub soft_radius: F,
+    pub falloff: Falloff,
+    /* WHETHER A PICK LETS THE RAYS AROUND THE CURSOR VOTE ON THE PARTICLE WITH THE ONE THROUGH IT */
+    pub multisample: bool,
+}
+
+impl Default for InteractionSettings {
+    fn default() -> Self {
+        Self {
+            pick_radius: 0.5,
+            blast_radius: 1.5,
+            blast_strength: 0.6,
+            tear_rate: 2.,
+            attractor_strength: 0.2,
+            attractor_radius: 4.,
+            brush_strength: 1.,
+            stiffness_rate: 2.,
+            soft: false,
+            soft_radius: 2.,
+            falloff: Falloff::Smoothstep,
+            multisample: false,
+        }
+    }
+}
+
+/* ATTRACTORS PASSED TO EVERY CLOTH BEFORE IT STEPS */
+#[derive(Default)]
+pub struct Attractors(pub Vec<Attractor>);
+
+/* THE RAY FROM THE CAMERA THROUGH THE CURSOR, REBUILT BY cast EVERY FRAME FROM WHERE THE CAMERA IS */
+/* NOW, SO PICKING KEEPS UP WITH AN ORBITING CAMERA. NONE WHILE THE CURSOR IS OUTSIDE THE WINDOW THE */
+/* CAMERA RENDERS TO. PINNING, DRAGGING, TEARING, PAINTING, THE BLAST AND THE ATTRACTOR ALL USE IT */
+/* THE SECOND FIELD ARE THE RAYS A FEW PIXELS OFF THE CURSOR ALONG THE DIAGONALS, FOR A MULTI-SAMPLED PICK */
+#[derive(Default)]
+pub struct CursorRay(pub Option<(P, V)>, pub [Option<(P, V)>; SUB_RAYS]);
+
+/* THE PARTICLE A CLICK WOULD PIN RIGHT NOW, FOR THE READOUT */
+#[derive(Default)]
+pub struct Hovered(pub Option<GridIdx>);
+
+#[derive(Default)]
+pub struct CursorState {
+    moved: EventReader<CursorMoved>,
+    left: EventReader<CursorLeft>,
+    /* WINDOW THE CURSOR IS IN AND ITS POSITION THERE, IN PIXELS FROM THE BOTTOM LEFT */
+    cursor: Option<(WindowId, Vec2)>,
+}
+
+/* STATE OF THE LEFT BUTTON ACROSS FRAMES: A QUICK CLICK TOGGLES THE PINS AT THE POINT ONCE, ON */
+/* RELEASE, WHILE A PRESS HELD ON PINNED PARTICLES TURNS INTO A DRAG OF THOSE PARTICLES */
+#[derive(Default)]
+pub struct Grab {
+    pub press: Option<Press>,
+    pub drag: Option<Drag>,
+    /* WHETHER THE LEFT BUTTON PAINTS STIFFNESS INSTEAD, SO IT NEVER PINS OR DRAGS */
+    pub painting_stiffness: bool,
+}
+
+impl Grab {
+    /* WHILE A SOFT DRAG IS UNDER WAY THE SCROLL WHEEL RESIZES ITS FALLOFF INSTEAD OF ZOOMING */
+    pub fn soft_dragging(&self) -> bool {
+        self.drag.as_ref().map_or(false, |drag| !drag.soft.is_empty())
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Press {
+    pub cloth: Entity,
+    pub point: P,
+    pub since: f64,
+    /* WHETHER THERE WERE PINNED PARTICLES AT THE POINT, WHICH A CLICK RELEASES AND A HOLD PICKS UP */
+    pub pinned: bool,
+}
+
+/* A FREE PARTICLE TAKEN ALONG BY A SOFT DRAG. IT STAYS SIMULATED, EACH FRAME IT IS MOVED ITS FALLOFF WEIGHT */
+/* OF THE WAY FROM WHERE THE SOLVER LEFT IT TO WHERE ITS OFFSET FROM THE CURSOR PUTS IT */
+#[derive(Clone, Copy, Debug)]
+pub struct SoftParticle {
+    pub idx: GridIdx,
+    /* OFFSET FROM THE GRABBED POINT AND DISTANCE TO IT WHEN PICKED UP */
+    pub offset: V,
+    pub distance: F,
+    /* WHERE THE LAST FRAME PUT IT, WHICH THE VELOCITY IT IS HANDED BACK WITH IS MEASURED FROM */
+    pub last: P,
+}
+
+/* PINNED PARTICLES MOVING WITH THE CURSOR ACROSS THE PLANE FACING THE CAMERA THROUGH THE GRABBED POINT */
+pub struct Drag {
+    pub cloth: Entity,
+    /* EACH HELD PARTICLE AND ITS OFFSET FROM THE GRABBED POINT */
+    pub particles: Vec<(GridIdx, V)>,
+    /* EMPTY WITHOUT SOFT SELECTION */
+    pub soft: Vec<SoftParticle>,
+    pub point: P,
+    pub normal: V,
+}
+
+impl Drag {
+    /* PICK UP THE PINS AT point, AND WITH SOFT SELECTION THE FREE PARTICLES WITHIN REACH OF THE LARGEST FALLOFF */
+    pub fn new(entity: Entity, cloth: &ClothSim, point: P, normal: V, settings: &InteractionSettings) -> Self {
+        let radius = settings.pick_radius;
+        let particles = cloth.fixed_near(point, radius);
+        let particles = particles.into_iter().map(|idx| (idx, cloth.world_position(idx) - point)).collect();
+        let soft = match settings.soft {
+            true => cloth.free_near(point, radius + MAX_SOFT_RADIUS),
+            false => vec![],
+        };
+        let soft = soft
+            .into_iter()
+            .map(|idx| {
+                let p = cloth.world_position(idx);
+                SoftParticle { idx, offset: p - point, distance: (p - point).norm(), last: p }
+            })
+            .collect();
+        Self { cloth: entity, particles, soft, point, normal }
+    }
+
+    pub fn weight(&self, particle: &SoftParticle, settings: &InteractionSettings) -> F {
+        settings.falloff.weight(particle.distance, settings.pick_radius, settings.soft_radius)
+    }
+
+    /* PUT THE GRABBED POINT AT target. FIXED PARTICLES ARE LEFT ALONE BY THE SOLVER, SO THE PINS GO EXACTLY */
+    /* WHERE THEY ARE PUT. A SOFT PARTICLE CONTINUES WITH ITS MOVEMENT OVER THE FRAME, SO ON RELEASE THE CLOTH */
+    /* KEEPS SWINGING THE WAY IT WAS DRAGGED. A RESOLUTION CHANGE MAY HAVE DROPPED SOME OF THE PARTICLES, */
+    /* WHICH ARE LEFT OUT QUIETLY */
+    pub fn apply(&mut self, cloth: &mut ClothSim, target: P, settings: &InteractionSettings) {
+        for &(idx, offset) in &self.particles {
+            let p = cloth.to_local(target + offset);
+            let _ = cloth.set_position(idx, p);
+        }
+        let weights: Vec<F> = self.soft.iter().map(|s| self.weight(s, settings)).collect();
+        for (s, w) in self.soft.iter_mut().zip(weights) {
+            if !cloth.particles.contains(s.idx) {
+                continue;
+            }
+            let current = cloth.world_position(s.idx);
+            if w <= 0. {
+                s.last = current;
+                continue;
+            }
+            let p = current + (target + s.offset - current) * w;
+            let displacement = cloth.vector_to_local(p - s.last);
+            let _ = cloth.place_particle(s.idx, cloth.to_local(p), displacement);
+            s.last = p;
+        }
+    }
+
+    /* WHERE THE GRABBED POINT IS UNDER THE CURSOR, NONE WHILE THE RAY RUNS ALONG THE PLANE */
+    pub fn target(&self, (origin, dir): (P, V)) -> Option<P> {
+        let along = dir.dot(&self.normal);
+        if along.abs() < F::EPSILON {
+            return None;
+        }
+        let t = (self.point - origin).dot(&self.normal) / along;
+        Some(origin + dir * t).filter(|_| t > 0.)
+    }
+}
+
+/* RAY FROM THE CAMERA THROUGH THE CURSOR, STARTING ON THE NEAR PLANE. UNPROJECTING THE NEAR AND FAR */
+/* PLANES RATHER THAN STARTING AT THE EYE WORKS FOR AN ORTHOGRAPHIC CAMERA TOO, WHOSE RAYS ARE PARALLEL */
+pub fn cursor_ray(cursor: Vec2, window: &Window, camera: &Camera, transform: &GlobalTransform) -> Option<(P, V)> {
+    let size = Vec2::new(window.width() as f32, window.height() as f32);
+    let inside = cursor.x() >= 0. && cursor.y() >= 0. && cursor.x() <= size.x() && cursor.y() <= size.y();
+    if size.x() <= 0. || size.y() <= 0. || !inside {
+        return None;
+    }
+    let ndc = cursor / size * 2. - Vec2::one();
+    let unproject = transform.compute_matrix() * camera.projection_matrix.inverse();
+    let at = |depth: f32| {
+        let p = unproject * Vec4::new(ndc.x(), ndc.y(), depth, 1.);
+        p.truncate() / p.w()
+    };
+    let (near, far) = (at(0.), at(1.));
+    Some((P::from(to_v(near)), to_v(far - near))).filter(|(_, dir)| dir.norm() > 0.)
+}
+
+/* TRACK THE CURSOR ACROSS WINDOWS AND CAST THE RAY OF THE ORBIT CAMERA THROUGH IT, BEFORE ANY */
+/* INTERACTION RUNS. THE TRANSFORM IS THE ONE THE LAST FRAME WAS RENDERED WITH, WHICH IS WHAT THE */
+/* CURSOR WAS POINTING AT. WITH THE mod_picking FEATURE A CURSOR THE EVENTS HAVE NOT PLACED YET FALLS */
+/* BACK TO THE HIT OF THE PICKING PLUGIN, WHICH IS A FRAME BEHIND AND HAS NO SUB-RAYS */
+pub fn cast(
+    mut state: Local<CursorState>,
+    moved: Res<Events<CursorMoved>>,
+    left: Res<Events<CursorLeft>>,
+    windows: Res<Windows>,
+    cameras: Query<(&OrbitCamera, &Camera, &GlobalTransform)>,
+    #[cfg(feature = "mod_picking")] pickables: Query<&bevy_mod_picking::PickableMesh>,
+    mut ray: ResMut<CursorRay>,
+) {
+    if let Some(e) = state.moved.iter(&moved).last() {
+        state.cursor = Some((e.id, e.position));
+    }
+    let cursor = state.cursor;
+    if state.left.iter(&left).any(|e| cursor.map_or(false, |(id, _)| id == e.id)) {
+        state.cursor = None;
+    }
+    *ray = CursorRay::default();
+    if let (Some((id, cursor)), Some((_, camera, transform))) = (state.cursor, cameras.iter().next()) {
+        if let Some(window) = windows.get(id).filter(|_| id == camera.window) {
+            ray.0 = cursor_ray(cursor, window, camera, transform);
+            let offsets = [(-1., -1.), (1., -1.), (-1., 1.), (1., 1.)];
+            for (sub, &(x, y)) in ray.1.iter_mut().zip(offsets.iter()) {
+                let offset = Vec2::new(x, y) * SUB_RAY_OFFSET;
+                *sub = cursor_ray(cursor + offset, window, camera, transform);
+            }
+        }
+    }
+    #[cfg(feature = "mod_picking")]
+    if let (None, Some((_, _, transform))) = (ray.0, cameras.iter().next()) {
+        let eye = P::from(to_v(transform.translation));
+        let hit = pickables.iter().find_map(|pickable| {
+            pickable.intersection(&bevy_mod_picking::Group::default()).ok().flatten().map(|it| *it.position())
+        });
+        ray.0 = hit.map(|p| (eye, P::from(to_v(p)) - eye)).filter(|(_, dir)| dir.norm() > 0.);
+    }
+}
+
+/* POINT ON THE CLOTH UNDER THE CURSOR, RAYCAST AGAINST THE CURRENT PARTICLE POSITIONS SO IT DOES */
+/* NOT LAG BEHIND A FAST-MOVING CLOTH */
+pub fn cursor_hit(ray: &CursorRay, cloth: &ClothSim) -> Option<P> {
+    let (origin, dir) = ray.0?;
+    cloth.raycast(origin, dir).map(|(_, p, _)| p)
+}
+
+/* THE PARTICLE UNDER THE CURSOR A CLICK PINS OR RELEASES, AND THE POINT HIT ON THE CLOTH. WITH A */
+/* MULTI-SAMPLED PICK THE PARTICLE IS THE ONE MOST RAYS HIT, THE ONE THROUGH THE CURSOR ON A TIE */
+pub fn cursor_pick(ray: &CursorRay, cloth: &ClothSim, settings: &InteractionSettings) -> Option<(GridIdx, P)> {
+    let (origin, dir) = ray.0?;
+    let (idx, p, _) = cloth.raycast(origin, dir)?;
+    if !settings.multisample {
+        return Some((idx, p));
+    }
+    let mut picks = vec![Some(idx)];
+    picks.extend(ray.1.iter().map(|sub| sub.and_then(|(origin, dir)| cloth.raycast(origin, dir)).map(|hit| hit.0)));
+    Some((vote(&picks).unwrap_or(idx), p))
+}
+
+/* THE PARTICLE PICKED MOST OFTEN, THE EARLIEST OF THOSE ON A TIE */
+/* THE VOTES ARE TALLIED IN ONE PASS, THE SECOND ONLY FINDS THE EARLIEST WINNER IN THE ORDER OF picks */
+pub fn vote(picks: &[Option<GridIdx>]) -> Option<GridIdx> {
+    let mut votes = HashMap::new();
+    for &idx in picks.iter().flatten() {
+        *votes.entry(idx).or_insert(0) += 1;
+    }
+    let most = votes.values().copied().max()?;
+    picks.iter().flatten().copied().find(|idx| votes[idx] == most)
+}
+
+pub struct PickIndicator;
+
+/* ONE PER COLOR OF SOFT_COLORS, BY INDEX */
+pub struct SoftMarkers(usize);
+
+pub fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands
+        .spawn(PbrComponents {
+            mesh: meshes.add(Mesh::new(PrimitiveTopology::LineList)),
+            material: materials.add(StandardMaterial {
+                albedo: Color::rgb(0.3, 0.9, 1.),
+                shaded: false,
+                ..Default::default()
+            }),
+            draw: Draw {
+                is_visible: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with(PickIndicator);
+
+    for (k, &(r, g, b)) in SOFT_COLORS.iter().enumerate() {
+        commands
+            .spawn(PbrComponents {
+                mesh: meshes.add(Mesh::new(PrimitiveTopology::TriangleList)),
+                material: materials.add(StandardMaterial {
+                    albedo: Color::rgb(r, g, b),
+                    shaded: false,
+                    ..Default::default()
+                }),
+                draw: Draw {
+                    is_visible: false,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with(SoftMarkers(k));
+    }
+}
+
+/* X SWITCHES SOFT SELECTION, SHIFT+X ITS FALLOFF CURVE AND S THE MULTI-SAMPLED PICK. A DRAG UNDER WAY */
+/* KEEPS WHAT IT PICKED UP */
+pub fn toggle_soft(actions: Res<Input<Action>>, mut settings: ResMut<InteractionSettings>) {
+    if actions.just_pressed(Action::SoftSelection) {
+        settings.soft = !settings.soft;
+        info!("soft selection {}", if settings.soft { "on" } else { "off" });
+    }
+    if actions.just_pressed(Action::CycleFalloff) {
+        settings.falloff = match settings.falloff {
+            Falloff::Linear => Falloff::Smoothstep,
+            Falloff::Smoothstep => Falloff::Linear,
+        };
+        info!("soft selection falloff {:?}", settings.falloff);
+    }
+    if actions.just_pressed(Action::PickSamples) {
+        settings.multisample = !settings.multisample;
+        info!("{} rays per pick", if settings.multisample { SUB_RAYS + 1 } else { 1 });
+    }
+}
+
+/* SCROLLING ADJUSTS THE RADIUS, THE BRUSH STRENGTH WHILE PAINTING OR THE FALLOFF DURING A SOFT DRAG, */
+/* WHEREVER THE MOUSE IS */
+pub fn adjust(
+    mut wheel: Local<EventReader<MouseWheel>>,
+    wheel_events: Res<Events<MouseWheel>>,
+    actions: Res<Input<Action>>,
+    grab: Res<Grab>,
+    mut settings: ResMut<InteractionSettings>,
+) {
+    let scroll: f32 = wheel.iter(&wheel_events).map(|e| e.y).sum();
+    if scroll != 0. && grab.soft_dragging() {
+        let radius = settings.soft_radius * (1. + scroll as F * PICK_RADIUS_SPEED);
+        settings.soft_radius = radius.max(MIN_SOFT_RADIUS).min(MAX_SOFT_RADIUS);
+        return;
+    }
+    if scroll != 0. && actions.pressed(Action::PaintWeights) {
+        let strength = settings.brush_strength * (1. + scroll as F * PICK_RADIUS_SPEED);
+        settings.brush_strength = strength.max(MIN_BRUSH_STRENGTH).min(MAX_BRUSH_STRENGTH);
+        return;
+    }
+    if scroll == 0. || !actions.pressed(Action::Modify) {
+        return;
+    }
+    let radius = settings.pick_radius * (1. + scroll as F * PICK_RADIUS_SPEED);
+    settings.pick_radius = radius.max(MIN_PICK_RADIUS).min(MAX_PICK_RADIUS);
+}
+
+/* THREE GREAT CIRCLES OF THE PICK SPHERE AT THE POINT UNDER THE MOUSE AND A CROSS ON THE PARTICLE A */
+/* CLICK WOULD PIN, HIDDEN OFF THE CLOTH */
+pub fn indicator(
+    settings: Res<InteractionSettings>,
+    ray: Res<CursorRay>,
+    mut hovered: ResMut<Hovered>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    cloths: Query<&Cloth>,
+    mut query: Query<(&PickIndicator, &Handle<Mesh>, &mut Draw)>,
+) {
+    let pick = cloths.iter().find_map(|cloth| {
+        cursor_pick(&ray, cloth, &settings).map(|(idx, p)| (idx, p, cloth.world_position(idx)))
+    });
+    hovered.0 = pick.map(|(idx, _, _)| idx);
+
+    for (_, handle, mut draw) in query.iter_mut() {
+        draw.is_visible = pick.is_some();
+        let (center, vertex) = match pick {
+            Some((_, center, vertex)) => (center, vertex),
+            None => continue,
+        };
+
+        let r = settings.pick_radius;
+        let mut positions = vec![];
+        for &(a, b) in &[(V::x(), V::y()), (V::y(), V::z()), (V::z(), V::x())] {
+            let point = |k: usize| {
+                let t = k as F / INDICATOR_SEGMENTS as F * std::f64::consts::TAU as F;
+                to_array(center + (a * t.cos() + b * t.sin()) * r)
+            };
+            for k in 0..INDICATOR_SEGMENTS {
+                positions.push(point(k));
+                positions.push(point(k + 1));
+            }
+        }
+        for axis in &[V::x(), V::y(), V::z()] {
+            positions.push(to_array(vertex - axis * VERTEX_CROSS_SIZE));
+            positions.push(to_array(vertex + axis * VERTEX_CROSS_SIZE));
+        }
+        if let Some(mesh) = meshes.get_mut(handle) {
+            debug::write_vertices(mesh, positions);
+        }
+    }
+}
+
+/* MARKERS ON THE PARTICLES A SOFT DRAG TAKES ALONG, LIGHTER THE MORE CLOSELY THEY FOLLOW THE CURSOR */
+pub fn soft_overlay(
+    grab: Res<Grab>,
+    settings: Res<InteractionSettings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    cloths: Query<(Entity, &Cloth)>,
+    mut query: Query<(&SoftMarkers, &Handle<Mesh>, &mut Draw)>,
+) {
+    let drag = grab.drag.as_ref().filter(|drag| !drag.soft.is_empty());
+    let n = SOFT_COLORS.len();
+    let mut buckets = vec![vec![]; n];
+    if let Some(drag) = drag {
+        let shape: Vec<V> = markers::octahedron().into_iter().map(|v| v * SOFT_MARKER_SIZE).collect();
+        for (_, cloth) in cloths.iter().filter(|(e, _)| *e == drag.cloth) {
+            for s in drag.soft.iter().filter(|s| cloth.particles.contains(s.idx)) {
+                let w = drag.weight(s, &settings);
+                if w <= 0. {
+                    continue;
+                }
+                let p = cloth.world_position(s.idx);
+                buckets[((w * n as F) as usize).min(n - 1)].extend(shape.iter().map(|&v| to_array(p + v)));
+            }
+        }
+    }
+
+    for (markers, handle, mut draw) in query.iter_mut() {
+        draw.is_visible = drag.is_some();
+        if drag.is_none() {
+            continue;
+        }
+        if let Some(mesh) = meshes.get_mut(handle) {
+            debug::write_vertices(mesh, std::mem::take(&mut buckets[markers.0]));
+        }
+    }
+}
+
+/* THE BLAST FOLLOWS THE RAY FROM THE CAMERA THROUGH THE MOUSE WHILE IT IS OVER A CLOTH */
+/* AND REACHES EVERY CLOTH ALONG IT, NOT ONLY THE ONE THAT WAS HIT */
+pub fn blow(
+    actions: Res<Input<Action>>,
+    settings: Res<InteractionSettings>,
+    ray: Res<CursorRay>,
+    mut cloths: Query<&mut Cloth>,
+) {
+    if !actions.pressed(Action::WindGun) {
+        return;
+    }
+    let (origin, dir) = match ray.0 {
+        Some(ray) => ray,
+        None => return,
+    };
+    if cloths.iter_mut().any(|cloth| cursor_hit(&ray, &cloth).is_some()) {
+        for mut cloth in cloths.iter_mut() {
+            cloth.apply_force_along_ray(origin, dir, settings.blast_radius, settings.blast_strength);
+        }
+    }
+}
+
+/* PRESSING A OVER A CLOTH PLACES AN ATTRACTOR AT THE POINT UNDER THE MOUSE, RELEASING IT REMOVES IT */
+/* THE POINT STAYS WHERE IT WAS PLACED, SO THE CLOTH IS PULLED INTO A BULGE THAT OUTLASTS THE RELEASE */
+pub fn attract(
+    actions: Res<Input<Action>>,
+    settings: Res<InteractionSettings>,
+    ray: Res<CursorRay>,
+    mut attractors: ResMut<Attractors>,
+    cloths: Query<&Cloth>,
+) {
+    if actions.just_released(Action::Attract) {
+        attractors.0.clear();
+    }
+    if !actions.just_pressed(Action::Attract) {
+        return;
+    }
+    let hit = cloths.iter().filter_map(|cloth| cursor_hit(&ray, cloth)).next();
+    if let Some(point) = hit {
+        attractors.0.push(Attractor {
+            point,
+            strength: settings.attractor_strength,
+            radius: settings.attractor_radius,
+            core: ATTRACTOR_CORE,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Entity;
+
+    use super::{vote, Drag, InteractionSettings};
+    use crate::{
+        cloth::{ClothSim, SIZE, SUBDIVISIONS},
+        config::{ClothConfig, PinPreset, Pose},
+        grid::GridIdx,
+        F, I, V,
+    };
+
+    const A: Option<(usize, usize)> = Some((3, 4));
+    const B: Option<(usize, usize)> = Some((4, 4));
+
+    #[test]
+    fn majority_wins() {
+        assert_eq!(vote(&[A, B, B, None, None]), B);
+        assert_eq!(vote(&[None, A, None, None, None]), A);
+    }
+
+    #[test]
+    fn tie_goes_to_the_ray_through_the_cursor() {
+        assert_eq!(vote(&[A, B, B, None, A]), A);
+        assert_eq!(vote(&[B, A, A, B, None]), B);
+    }
+
+    #[test]
+    fn all_misses_pick_nothing() {
+        assert_eq!(vote(&[None; 5]), None);
+    }
+
+    /* RAYS STRAIGHT AT A FLAT CURTAIN, AT POINTS GIVEN AS FRACTIONS OF ONE OF ITS QUADS, PICK THE CORNER OF THE */
+    /* HIT TRIANGLE NEAREST IN THE GRID: INSIDE A TRIANGLE, EXACTLY ON ITS CORNERS, ON THE EDGES BETWEEN QUADS, */
+    /* WHERE A TIE GOES TO THE LOWEST GRID INDEX, AND ON THE DIAGONAL */
+    #[test]
+    fn rays_pick_the_nearest_corner() {
+        // QUAD THE RAYS AIM AT, AND HOW FAR IN FRONT OF THE CLOTH THEY START
+        const QUAD: GridIdx = (10, 12);
+        const HEIGHT: F = 1.;
+
+        let config = ClothConfig { pose: Pose::HangingVertical, pin_preset: PinPreset::Free, ..Default::default() };
+        let cloth = ClothSim::new(SIZE.0, SIZE.1, (SUBDIVISIONS, SUBDIVISIONS), config).unwrap();
+        let (x, y) = QUAD;
+        let corner = |dx: I, dy: I| cloth.world_position((x + dx, y + dy));
+        let (across, down) = (corner(1, 0) - corner(0, 0), corner(0, 1) - corner(0, 0));
+        let cases: &[((F, F), GridIdx)] = &[
+            ((0., 0.), (x, y)),
+            ((1., 1.), (x + 1, y + 1)),
+            ((1., 0.), (x + 1, y)),
+            ((0.2, 0.1), (x, y)),
+            ((0.45, 0.3), (x, y)),
+            ((0.8, 0.1), (x + 1, y)),
+            ((0.1, 0.8), (x, y + 1)),
+            ((0.9, 0.8), (x + 1, y + 1)),
+            ((0.5, 0.), (x, y)),
+            ((0.5, 1.), (x, y + 1)),
+            ((0., 0.5), (x, y)),
+            ((1., 0.5), (x + 1, y)),
+            ((0.3, 0.7), (x, y + 1)),
+            ((0.7, 0.3), (x + 1, y)),
+        ];
+        for &((u, v), expected) in cases {
+            let point = corner(0, 0) + across * u + down * v;
+            let picked = cloth.raycast(point + V::z() * HEIGHT, -V::z()).map(|hit| hit.0);
+            assert_eq!(picked, Some(expected), "hit at ({}, {}) of quad {:?}", u, v, QUAD);
+        }
+    }
+
+    /* THE MIDDLE OF A HANGING CLOTH IS PINNED AND PULLED STRAIGHT OUT OF THE SHEET, ONCE ALONE AND ONCE WITH SOFT */
+    /* SELECTION. ONLY THE SOFT DRAG TAKES FREE PARTICLES ALONG, IT SPREADS THE STRETCH, LEAVING THE MOST STRAINED */
+    /* SPRING LESS STRAINED, AND THE CLOTH IT TOOK ALONG KEEPS MOVING OUT ONCE LET GO */
+    #[test]
+    fn soft_drag_spreads_the_stretch() {
+        // PARTICLES ALONG EACH SIDE, HOW FAR THE MIDDLE IS PULLED OVER HOW MANY STEPS, AND THE PICK AND FALLOFF RADII
+        const N: I = 20;
+        const REACH: F = 3.;
+        const STEPS: I = 30;
+        const PICK_RADIUS: F = 0.1;
+        const SOFT_RADIUS: F = 3.;
+
+        let config = ClothConfig { pose: Pose::HangingVertical, pin_preset: PinPreset::TopRow, ..Default::default() };
+        let (w, h) = SIZE;
+        let pull = |soft: bool| {
+            let mut cloth = ClothSim::new(w, h, (N, N), config.clone()).unwrap();
+            let point = cloth.world_position((N / 2, N / 2));
+            cloth.set_fixed(point, PICK_RADIUS, true).unwrap();
+
+            let settings =
+                InteractionSettings { pick_radius: PICK_RADIUS, soft, soft_radius: SOFT_RADIUS, ..Default::default() };
+            let normal = V::z();
+            let mut drag = Drag::new(Entity::new(0), &cloth, point, normal, &settings);
+            let mut strain: F = 0.;
+            for k in 1..=STEPS {
+                drag.apply(&mut cloth, point + normal * REACH * k as F / STEPS as F, &settings);
+                cloth.step(&[]);
+                strain = strain.max(cloth.max_strain());
+            }
+
+            /* LET GO MID-MOVE: THE FREE PARTICLES THAT WERE FOLLOWING CARRY ON ALONG THE DRAG */
+            let taken: Vec<I> = drag
+                .soft
+                .iter()
+                .filter(|s| drag.weight(s, &settings) > 0.)
+                .map(|s| cloth.particles.flatten(s.idx))
+                .collect();
+            cloth.step(&[]);
+            let onwards = taken.iter().map(|&i| cloth.particles.displacement(i).dot(&normal)).sum::<F>();
+            (strain, taken.len(), onwards / taken.len().max(1) as F)
+        };
+
+        let (hard_strain, hard_taken, _) = pull(false);
+        let (soft_strain, soft_taken, onwards) = pull(true);
+        assert_eq!(hard_taken, 0);
+        assert!(soft_taken > 0);
+        assert!(soft_strain < hard_strain, "max strain {} soft, {} alone", soft_strain, hard_strain);
+        assert!(onwards > 0., "moved {} out of the sheet after letting go", onwards);
+    }
+}
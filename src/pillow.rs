@@ -0,0 +1,102 @@
+use bevy::prelude::*;
+
+use crate::{
+    cloth::Cloth,
+    collider::{Collider, ColliderId, Colliders},
+    grid::GridIdx,
+    to_v, to_vec3, F, I, P, V,
+};
+
+/* BALLOON MODE: A CLOTH FOLDED INTO TWO SHEETS, SEWN SHUT ALONG THE OPEN EDGES AND INFLATED */
+/* --pillow DROPS ONE ONTO THE STAIRS AND BOBS A KINEMATIC SPHERE INTO IT FROM ABOVE */
+
+// STIFFNESS OF THE VOLUME CONSTRAINT AND TARGET VOLUME AS A FRACTION OF width * height * min(width, height)
+pub const PRESSURE: F = 0.2;
+pub const INFLATION: F = 0.15;
+
+// RADIUS OF THE POKING SPHERE, AND ITS HEIGHT ABOVE THE PILLOW CENTER AT THE TOP OF ITS SWING
+const POKER_RADIUS: F = 2.;
+const POKER_LIFT: f32 = 6.;
+const POKER_FREQUENCY: f32 = 0.25;
+
+/* PAIRS OF PARTICLES ACROSS THE SEAMS, AND THE TRIANGLES CLOSING THEM */
+/* ROWS y AND rows - 1 - y LIE OVER EACH OTHER, SO THE SIDE COLUMNS AND THE FIRST AND LAST ROWS ARE SEWN */
+/* THE TRIANGLES ARE WOUND ARBITRARILY, volume::orient MAKES THEM CONSISTENT */
+pub fn seams(w: I, rows: I) -> (Vec<(GridIdx, GridIdx)>, Vec<[GridIdx; 3]>) {
+    let (mut pairs, mut triangles) = (vec![], vec![]);
+    let quad = |triangles: &mut Vec<[GridIdx; 3]>, [a, b, c, d]: [GridIdx; 4]| {
+        triangles.push([a, b, d]);
+        triangles.push([b, c, d]);
+    };
+    for &x in &[0, w - 1] {
+        pairs.extend((0..(rows - 1) / 2).map(|y| ((x, y), (x, rows - 1 - y))));
+        for y in 0..rows / 2 - 1 {
+            quad(&mut triangles, [(x, y), (x, y + 1), (x, rows - 2 - y), (x, rows - 1 - y)]);
+        }
+        /* WITH AN ODD NUMBER OF ROWS THE FOLD IS A SINGLE ROW, LEAVING ONE TRIANGLE AT EACH END */
+        if rows % 2 == 1 {
+            let n = rows / 2;
+            triangles.push([(x, n - 1), (x, n), (x, n + 1)]);
+        }
+    }
+    pairs.extend((1..w - 1).map(|x| ((x, 0), (x, rows - 1))));
+    for x in 0..w - 1 {
+        quad(&mut triangles, [(x, 0), (x + 1, 0), (x + 1, rows - 1), (x, rows - 1)]);
+    }
+    (pairs, triangles)
+}
+
+pub struct Poker {
+    collider: ColliderId,
+}
+
+pub fn spawn_poker(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    colliders: &mut Colliders,
+) {
+    /* PLACED BY poke ON THE FIRST FRAME */
+    let collider = colliders.add(Collider::Sphere { center: P::origin(), radius: POKER_RADIUS });
+    commands
+        .spawn(PbrComponents {
+            mesh: meshes.add(Mesh::from(shape::Icosphere {
+                radius: POKER_RADIUS as f32,
+                subdivisions: 4,
+            })),
+            material: materials.add(Color::rgb(0.8, 0.5, 0.3).into()),
+            ..Default::default()
+        })
+        .with(Poker { collider });
+}
+
+/* FOLLOWS THE PILLOW AROUND, DIPPING UNTIL ITS LOWEST POINT REACHES THE CENTER OF THE PILLOW. ITS MOVE OVER */
+/* THE STEP OF THE PILLOW IS KEPT AS ITS VELOCITY, SO FRICTION DRAGS THE PILLOW ALONG WITH IT */
+pub fn poke(
+    time: Res<Time>,
+    mut colliders: ResMut<Colliders>,
+    cloths: Query<&Cloth>,
+    mut pokers: Query<(&Poker, &mut Transform)>,
+) {
+    let (bounds, dt) = match cloths.iter().find(|c| c.closed()) {
+        Some(cloth) => (cloth.bounding_box(), cloth.dt()),
+        None => return,
+    };
+    let phase = (2. * std::f32::consts::PI * POKER_FREQUENCY * time.seconds_since_startup as f32).cos();
+    let center = to_vec3(nalgebra::center(&bounds.min, &bounds.max).coords);
+    let lift = POKER_RADIUS as f32 + POKER_LIFT * (0.5 + 0.5 * phase);
+
+    for (poker, mut transform) in pokers.iter_mut() {
+        transform.translation = center + Vec3::new(0., lift, 0.);
+        let center = P::from(to_v(transform.translation));
+        if let Some(c) = colliders.0.get_mut(poker.collider) {
+            let velocity = match c.collider {
+                /* NO BODY YET ON THE FIRST FRAME, WHEN IT JUMPS FROM THE ORIGIN */
+                Collider::Sphere { center: old, .. } if dt > 0. && c.body.is_some() => (center - old) / dt,
+                _ => V::zeros(),
+            };
+            c.collider = Collider::Sphere { center, radius: POKER_RADIUS };
+            colliders.set_kinematic(poker.collider, velocity);
+        }
+    }
+}
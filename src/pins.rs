@@ -0,0 +1,133 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    ops::Bound::{Excluded, Unbounded},
+    path::Path,
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{bindings::Action, cloth::{Cloth, ClothSim}, grid::GridIdx, F, I};
+
+/* NAMED PIN LAYOUTS PERSISTED ACROSS RUNS */
+/* CTRL+S SAVES THE CURRENT PINS UNDER A NEW NAME, CTRL+L CYCLES THROUGH THE SAVED LAYOUTS */
+
+pub const PIN_LAYOUTS_PATH: &str = "assets/pin_layouts.ron";
+
+/* PINS TOGETHER WITH THE GRID RESOLUTION THEY WERE SAVED AT */
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PinLayout {
+    pub subdivisions: GridIdx,
+    pub pins: Vec<GridIdx>,
+}
+
+impl PinLayout {
+    pub fn new(cloth: &ClothSim) -> Self {
+        Self {
+            subdivisions: cloth.subdivisions(),
+            pins: cloth.pin_layout(),
+        }
+    }
+
+    /* MAP THE PINS PROPORTIONALLY ONTO A GRID OF ANOTHER RESOLUTION, KEEPING THE EDGES ON THE EDGES */
+    pub fn scaled(&self, (w, h): GridIdx) -> Vec<GridIdx> {
+        let (sw, sh) = self.subdivisions;
+        let scale = |i: I, from: I, to: I| {
+            if from <= 1 {
+                0
+            } else {
+                ((i * (to - 1)) as F / (from - 1) as F).round() as I
+            }
+        };
+        let mut pins: Vec<_> = self.pins.iter().map(|&(x, y)| (scale(x, sw, w), scale(y, sh, h))).collect();
+        pins.sort_unstable();
+        pins.dedup();
+        pins
+    }
+}
+
+#[derive(Default)]
+pub struct PinLayouts {
+    layouts: BTreeMap<String, PinLayout>,
+    current: Option<String>,
+}
+
+impl PinLayouts {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let layouts =
+            ron::de::from_str(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+        Ok(Self { layouts, current: None })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let text = ron::ser::to_string_pretty(&self.layouts, Default::default())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+        fs::write(path, text)
+    }
+
+    /* FIRST UNUSED NAME OF THE FORM layout_N */
+    pub fn insert(&mut self, layout: PinLayout) -> String {
+        let name = (1..)
+            .map(|n| format!("layout_{}", n))
+            .find(|name| !self.layouts.contains_key(name))
+            .unwrap();
+        self.layouts.insert(name.clone(), layout);
+        name
+    }
+
+    /* THE LAYOUT AFTER THE LAST APPLIED ONE IN NAME ORDER, WRAPPING AROUND */
+    pub fn next(&mut self) -> Option<(&str, &PinLayout)> {
+        let next = match &self.current {
+            Some(current) => self
+                .layouts
+                .range::<String, _>((Excluded(current), Unbounded))
+                .next()
+                .or_else(|| self.layouts.iter().next()),
+            None => self.layouts.iter().next(),
+        };
+        self.current = next.map(|(name, _)| name.clone());
+        next.map(|(name, layout)| (name.as_str(), layout))
+    }
+}
+
+/* LOAD THE SAVED LAYOUTS AT STARTUP, A MISSING FILE JUST MEANS THERE ARE NONE YET */
+pub fn setup(mut commands: Commands) {
+    let layouts = match PinLayouts::load(PIN_LAYOUTS_PATH) {
+        Ok(layouts) => layouts,
+        Err(err) => {
+            if err.kind() != io::ErrorKind::NotFound {
+                warn!("failed to load {}: {}", PIN_LAYOUTS_PATH, err);
+            }
+            PinLayouts::default()
+        }
+    };
+    commands.insert_resource(layouts);
+}
+
+/* SAVES THE PINS OF THE FIRST CLOTH, APPLIES LAYOUTS TO ALL OF THEM */
+pub fn input(actions: Res<Input<Action>>, mut layouts: ResMut<PinLayouts>, mut cloths: Query<&mut Cloth>) {
+    if actions.just_pressed(Action::SaveLayout) {
+        if let Some(cloth) = cloths.iter_mut().next() {
+            let name = layouts.insert(PinLayout::new(&cloth));
+            match layouts.save(PIN_LAYOUTS_PATH) {
+                Ok(()) => info!("saved pin layout {} to {}", name, PIN_LAYOUTS_PATH),
+                Err(err) => error!("failed to save pin layouts: {}", err),
+            }
+        }
+    }
+
+    if actions.just_pressed(Action::CycleLayout) {
+        match layouts.next() {
+            Some((name, layout)) => {
+                info!("applying pin layout {}", name);
+                for mut cloth in cloths.iter_mut() {
+                    let pins = layout.scaled(cloth.subdivisions());
+                    cloth.apply_pin_layout(&pins);
+                }
+            }
+            None => warn!("no saved pin layouts in {}", PIN_LAYOUTS_PATH),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+
+use crate::{
+    bindings::Action,
+    camera::OrbitCamera,
+    cloth::Cloth,
+    collider::{Collider, ColliderId, Colliders, DEFAULT_LAYER},
+    to_v, to_vec3, F, P, V,
+};
+
+/* A SPHERE DRIVEN THROUGH THE SCENE FROM THE KEYBOARD TO POKE THE CLOTH AND DRAG IT ALONG */
+/* ALT+P SWITCHES IT ON IN FRONT OF THE CAMERA FOCUS AND OFF AGAIN, ALT+W AND ALT+S MOVE IT AWAY FROM AND */
+/* TOWARDS THE CAMERA, ALT+A AND ALT+D TO THE SIDES, ALT+E AND ALT+Q UP AND DOWN. WHILE OFF IT IS HIDDEN AND */
+/* ON NO LAYER. THESE ARE THE DEFAULT BINDINGS, assets/input.ron CHANGES THEM */
+
+// DEFAULT SPEED IN UNITS PER SECOND OF SIMULATED TIME, AND THE RADIUS
+pub const PROBE_SPEED: F = 4.;
+pub const PROBE_RADIUS: F = 1.;
+
+// FRICTION COEFFICIENT OF THE SPHERE, HIGH SO IT GRIPS THE CLOTH IT DRAGS
+const PROBE_FRICTION: F = 1.;
+
+// DISTANCE FROM THE CAMERA FOCUS TOWARDS THE CAMERA IT IS SWITCHED ON AT, IN RADII
+const PROBE_STANDOFF: F = 3.;
+
+// LAYER OF THE SPHERE WHILE OFF, WHICH NO CLOTH COLLIDES WITH
+const OFF_LAYER: u32 = 0;
+
+/* SPEED GIVEN BY --probe-speed */
+pub struct ProbeSpeed(pub F);
+
+pub struct Probe {
+    /* ADDED TO Colliders WHEN IT IS FIRST SWITCHED ON */
+    collider: Option<ColliderId>,
+    on: bool,
+    center: P,
+    speed: F,
+}
+
+pub fn setup(
+    mut commands: Commands,
+    speed: Res<ProbeSpeed>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands
+        .spawn(PbrComponents {
+            mesh: meshes.add(Mesh::from(shape::Icosphere {
+                radius: PROBE_RADIUS as f32,
+                subdivisions: 4,
+            })),
+            material: materials.add(Color::rgb(0.4, 0.8, 0.5).into()),
+            draw: Draw {
+                is_visible: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with(Probe { collider: None, on: false, center: P::origin(), speed: speed.0 });
+}
+
+/* MOVES RELATIVE TO THE CAMERA BUT LEVEL WITH THE GROUND, BY THE STEP OF THE FIRST CLOTH SO IT KEEPS PACE */
+/* WITH THE SIMULATION. ITS VELOCITY IS KEPT ON ITS COLLIDER, SO FRICTION DRAGS THE CLOTHS IT TOUCHES ALONG */
+pub fn drive(
+    actions: Res<Input<Action>>,
+    mut colliders: ResMut<Colliders>,
+    cameras: Query<(&OrbitCamera, &GlobalTransform)>,
+    cloths: Query<&Cloth>,
+    mut probes: Query<(&mut Probe, &mut Transform, &mut Draw)>,
+) {
+    let camera = cameras.iter().next().map(|(orbit, transform)| (orbit.focus, transform.rotation));
+    for (mut probe, mut transform, mut draw) in probes.iter_mut() {
+        if actions.just_pressed(Action::Probe) {
+            probe.on = !probe.on;
+            if let (true, Some((focus, rotation))) = (probe.on, camera) {
+                let towards_camera = to_v(rotation.mul_vec3(Vec3::unit_z())) * (PROBE_STANDOFF * PROBE_RADIUS);
+                probe.center = P::from(to_v(focus)) + towards_camera;
+            }
+            let sphere = Collider::Sphere { center: probe.center, radius: PROBE_RADIUS };
+            let i = match probe.collider {
+                Some(i) => i,
+                None => {
+                    let i = colliders.add(sphere);
+                    colliders.set_friction(i, PROBE_FRICTION);
+                    probe.collider = Some(i);
+                    i
+                }
+            };
+            colliders.0[i].layer = if probe.on { DEFAULT_LAYER } else { OFF_LAYER };
+            info!("probe sphere {}", if probe.on { "on" } else { "off" });
+        }
+        draw.is_visible = probe.on;
+
+        let (i, rotation) = match (probe.collider, camera) {
+            (Some(i), Some((_, rotation))) if probe.on => (i, rotation),
+            (Some(i), _) => {
+                colliders.set_kinematic(i, V::zeros());
+                continue;
+            }
+            _ => continue,
+        };
+        let level = |v: Vec3| to_v(Vec3::new(v.x(), 0., v.z())).try_normalize(F::EPSILON).unwrap_or_else(V::zeros);
+        let (forward, right) = (level(rotation.mul_vec3(-Vec3::unit_z())), level(rotation.mul_vec3(Vec3::unit_x())));
+        let mut direction = V::zeros();
+        for &(action, v) in &[
+            (Action::ProbeForward, forward),
+            (Action::ProbeBack, -forward),
+            (Action::ProbeLeft, -right),
+            (Action::ProbeRight, right),
+            (Action::ProbeUp, V::y()),
+            (Action::ProbeDown, -V::y()),
+        ] {
+            if actions.pressed(action) {
+                direction += v;
+            }
+        }
+        let velocity = direction.try_normalize(F::EPSILON).unwrap_or_else(V::zeros) * probe.speed;
+        let dt = match cloths.iter().next() {
+            Some(cloth) => cloth.dt(),
+            None => continue,
+        };
+        probe.center += velocity * dt;
+        colliders.0[i].collider = Collider::Sphere { center: probe.center, radius: PROBE_RADIUS };
+        colliders.set_kinematic(i, velocity);
+        transform.translation = to_vec3(probe.center.coords);
+    }
+}
@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+
+use crate::{
+    cloth::Cloth,
+    collider::{Collider, ColliderId, Colliders},
+    to_vec3, F, P, V,
+};
+
+/* TWO-WAY COUPLING: DYNAMIC SPHERES THE CLOTHS PUSH BACK ON. EVERY CLOTH STEP RECORDS THE MOMENTUM */
+/* ITS COLLISION PASS GAVE THE PARTICLES IT PUSHED OUT OF A DYNAMIC SPHERE, AND advance HANDS THE */
+/* OPPOSITE TO THE SPHERE BEFORE MOVING IT BY ITS VELOCITY AND GRAVITY. THERE IS NO ROTATION */
+/* --trampoline DROPS A BALL ONTO THE MAIN CLOTH, LAID FLAT AND HELD BY ITS FOUR CORNERS */
+
+// RADIUS OF THE BALL, ITS HEIGHT ABOVE THE CLOTH WHEN DROPPED AND ITS DEFAULT MASS, LIGHT NEXT TO THE CLOTH
+pub const BALL_RADIUS: F = 1.5;
+pub const BALL_DROP: F = 6.;
+pub const BALL_MASS: F = 20.;
+
+// FRACTION OF THE CLOTH'S IMPULSE PASSED ON TO A SPHERE, THE REST IS LOST LIKE IN A DAMPED CONTACT
+const COUPLING: F = 0.8;
+
+// DRAG ON A SPHERE'S VELOCITY, AS THE FRACTION LOST PER SECOND OF SIMULATED TIME
+const PROP_DAMPING: F = 0.1;
+
+/* LINKS THE MESH OF A PROP TO ITS COLLIDER */
+pub struct Prop {
+    collider: ColliderId,
+}
+
+pub fn spawn_ball(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    colliders: &mut Colliders,
+    center: P,
+    mass: F,
+) {
+    let collider = colliders.add(Collider::Sphere { center, radius: BALL_RADIUS });
+    colliders.set_mass(collider, mass);
+    commands
+        .spawn(PbrComponents {
+            mesh: meshes.add(Mesh::from(shape::Icosphere {
+                radius: BALL_RADIUS as f32,
+                subdivisions: 4,
+            })),
+            material: materials.add(Color::rgb(0.3, 0.5, 0.8).into()),
+            transform: Transform::from_translation(to_vec3(center.coords)),
+            ..Default::default()
+        })
+        .with(Prop { collider });
+}
+
+/* ONE RIGID STEP OF EVERY DYNAMIC SPHERE, AFTER THE CLOTHS HAVE STEPPED. ITS TIME STEP AND GRAVITY */
+/* ARE THOSE OF THE FIRST CLOTH. THE IMPULSE IS DAMPED BY COUPLING AND CLAMPED SO THE SPHERE NEVER */
+/* LEAVES A CONTACT FASTER ALONG THE IMPULSE THAN IT CAME IN: THE CLOTH GIVES BACK AT MOST THE ENERGY */
+/* THE IMPACT STORED IN IT, SO A BOUNCE DIES DOWN INSTEAD OF PUMPING ITSELF UP */
+pub fn advance(
+    mut colliders: ResMut<Colliders>,
+    mut cloths: Query<&mut Cloth>,
+    mut props: Query<(&Prop, &mut Transform)>,
+) {
+    let (dt, gravity) = match cloths.iter().next() {
+        Some(cloth) => (cloth.dt(), cloth.config.gravity),
+        None => return,
+    };
+    let mut impulses = vec![V::zeros(); colliders.0.len()];
+    for mut cloth in cloths.iter_mut() {
+        for (id, impulse) in cloth.drain_collider_impulses() {
+            if let Some(total) = impulses.get_mut(id) {
+                *total += impulse;
+            }
+        }
+    }
+
+    for (layered, impulse) in colliders.0.iter_mut().zip(impulses) {
+        let body = match &mut layered.body {
+            Some(body) if !body.kinematic => body,
+            _ => continue,
+        };
+        let kick = impulse * (COUPLING / body.mass);
+        match kick.try_normalize(F::EPSILON) {
+            Some(n) => {
+                body.impact = body.impact.max(-body.velocity.dot(&n));
+                let out = (body.velocity + kick).dot(&n);
+                body.velocity += kick - n * (out - body.impact).max(0.);
+            }
+            None => body.impact = 0.,
+        }
+        body.velocity += gravity * dt;
+        body.velocity *= (1. - PROP_DAMPING * dt).max(0.);
+        let step = body.velocity * dt;
+        layered.collider.translate(step);
+    }
+
+    for (prop, mut transform) in props.iter_mut() {
+        if let Some(Collider::Sphere { center, .. }) = colliders.0.get(prop.collider).map(|c| &c.collider) {
+            transform.translation = to_vec3(center.coords);
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use std::{
+    collections::VecDeque,
+    convert::TryInto,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    bindings::Action,
+    cloth::{Cloth, ClothSim, PaintedStiffness},
+    F, I, P,
+};
+
+/* RECORD SIMULATION RUNS AND REPLAY THEM FRAME BY FRAME */
+/* F6 STARTS/STOPS RECORDING (SAVED TO recording.bin ON STOP), F7 ENTERS/EXITS REPLAY */
+/* DURING REPLAY: SPACE PLAYS/PAUSES, RIGHT/LEFT STEP FORWARD/BACKWARD */
+
+pub const RECORDING_PATH: &str = "recording.bin";
+
+// FILE LAYOUT: MAGIC, VERSION, FRAME COUNT, PARTICLE COUNT, WEIGHT COUNT, THE f32 WEIGHTS, PAINTED COUNT,
+// THE PAINTED STIFFNESS AS FOUR u32 GRID COORDINATES AND AN f32 MULTIPLIER EACH, THEN f32 TRIPLES
+const MAGIC: &[u8; 8] = b"CLOTHREC";
+const VERSION: u32 = 3;
+
+// MAXIMUM NUMBER OF FRAMES KEPT IN MEMORY, OLDEST ARE DROPPED FIRST
+const CAPACITY: I = 3600;
+
+#[derive(Default)]
+pub struct Recorder {
+    pub recording: bool,
+    pub replaying: bool,
+    playing: bool,
+    cursor: I,
+    particle_count: I,
+    /* THE WEIGHT MAP OF THE CLOTH WHEN RECORDING STARTED, RESTORED WHEN THE REPLAY STARTS */
+    weights: Vec<F>,
+    /* LIKEWISE THE STIFFNESS PAINTED ONTO ITS CONSTRAINTS */
+    painted: PaintedStiffness,
+    frames: VecDeque<Vec<P>>,
+}
+
+impl Recorder {
+    pub fn start(&mut self, cloth: &ClothSim) {
+        self.frames.clear();
+        self.particle_count = cloth.particles.len();
+        self.weights = cloth.weights().data.clone();
+        self.painted = cloth.painted_stiffness().clone();
+        self.recording = true;
+    }
+
+    pub fn record(&mut self, cloth: &ClothSim) {
+        if !self.recording || cloth.particles.len() != self.particle_count {
+            return;
+        }
+        if self.frames.len() == CAPACITY {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(cloth.particles.iter().copied().collect());
+    }
+
+    pub fn frame(&self) -> Option<&[P]> {
+        self.frames.get(self.cursor).map(Vec::as_slice)
+    }
+
+    pub fn frames(&self) -> impl Iterator<Item = &[P]> {
+        self.frames.iter().map(Vec::as_slice)
+    }
+
+    pub fn len(&self) -> I {
+        self.frames.len()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        w.write_all(MAGIC)?;
+        w.write_all(&VERSION.to_le_bytes())?;
+        w.write_all(&(self.frames.len() as u32).to_le_bytes())?;
+        w.write_all(&(self.particle_count as u32).to_le_bytes())?;
+        w.write_all(&(self.weights.len() as u32).to_le_bytes())?;
+        for weight in &self.weights {
+            w.write_all(&(*weight as f32).to_le_bytes())?;
+        }
+        w.write_all(&(self.painted.len() as u32).to_le_bytes())?;
+        for (&((x1, y1), (x2, y2)), painted) in &self.painted {
+            for c in &[x1, y1, x2, y2] {
+                w.write_all(&(*c as u32).to_le_bytes())?;
+            }
+            w.write_all(&(*painted as f32).to_le_bytes())?;
+        }
+        for p in self.frames.iter().flatten() {
+            for c in &[p.x, p.y, p.z] {
+                w.write_all(&(*c as f32).to_le_bytes())?;
+            }
+        }
+        w.flush()
+    }
+
+    /* FAILS LOUDLY ON FILES WRITTEN IN ANOTHER FORMAT VERSION OR TRUNCATED FILES */
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let mut r = BufReader::new(File::open(path)?);
+
+        let mut magic = [0; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a cloth recording".to_string()));
+        }
+
+        let read_u32 = |r: &mut BufReader<File>| -> io::Result<u32> {
+            let mut buf = [0; 4];
+            r.read_exact(&mut buf)?;
+            Ok(u32::from_le_bytes(buf))
+        };
+        let version = read_u32(&mut r)?;
+        if version != VERSION {
+            return Err(invalid(format!(
+                "recording version {} is not supported (expected {})",
+                version, VERSION
+            )));
+        }
+        let frame_count = read_u32(&mut r)? as I;
+        let particle_count = read_u32(&mut r)? as I;
+        let weight_count = read_u32(&mut r)? as I;
+
+        let mut buf = vec![0; weight_count * 4];
+        r.read_exact(&mut buf)?;
+        let weights = buf.chunks_exact(4).map(|b| f32::from_le_bytes(b.try_into().unwrap()) as F).collect();
+
+        let painted_count = read_u32(&mut r)? as I;
+        let mut buf = vec![0; painted_count * 5 * 4];
+        r.read_exact(&mut buf)?;
+        let painted = buf
+            .chunks_exact(5 * 4)
+            .map(|b| {
+                let c = |i: I| u32::from_le_bytes(b[i * 4..i * 4 + 4].try_into().unwrap()) as I;
+                let painted = f32::from_le_bytes(b[16..20].try_into().unwrap()) as F;
+                (((c(0), c(1)), (c(2), c(3))), painted)
+            })
+            .collect();
+
+        let mut buf = vec![0; particle_count * 3 * 4];
+        let mut frames = VecDeque::with_capacity(frame_count);
+        for _ in 0..frame_count {
+            r.read_exact(&mut buf)?;
+            let c = |i: I| f32::from_le_bytes(buf[i * 4..i * 4 + 4].try_into().unwrap()) as F;
+            frames.push_back((0..particle_count).map(|i| P::new(c(3 * i), c(3 * i + 1), c(3 * i + 2))).collect());
+        }
+
+        Ok(Self {
+            particle_count,
+            weights,
+            painted,
+            frames,
+            ..Default::default()
+        })
+    }
+}
+
+pub fn input(actions: Res<Input<Action>>, mut recorder: ResMut<Recorder>, mut cloth: Mut<Cloth>) {
+    if actions.just_pressed(Action::Record) && !recorder.replaying {
+        if recorder.recording {
+            recorder.recording = false;
+            match recorder.save(RECORDING_PATH) {
+                Ok(()) => info!("saved {} frames to {}", recorder.len(), RECORDING_PATH),
+                Err(err) => error!("failed to save recording: {}", err),
+            }
+        } else {
+            recorder.start(&cloth);
+            info!("recording started");
+        }
+    }
+
+    if actions.just_pressed(Action::Replay) {
+        if recorder.replaying {
+            /* RESUME SIMULATING FROM THE DISPLAYED FRAME WITHOUT ANY VELOCITY */
+            recorder.replaying = false;
+            cloth.reset_velocities();
+        } else {
+            recorder.recording = false;
+            if recorder.len() == 0 {
+                match Recorder::load(RECORDING_PATH) {
+                    Ok(loaded) => *recorder = loaded,
+                    Err(err) => error!("failed to load recording: {}", err),
+                }
+            }
+            recorder.replaying = recorder.len() > 0 && recorder.particle_count == cloth.particles.len();
+            if recorder.replaying {
+                if let Err(err) = cloth.set_weights(&recorder.weights) {
+                    error!("failed to restore the weight map of the recording: {}", err);
+                }
+                cloth.set_painted_stiffness(recorder.painted.clone());
+            }
+            recorder.playing = false;
+            recorder.cursor = recorder.len().saturating_sub(1);
+        }
+    }
+
+    if !recorder.replaying {
+        return;
+    }
+
+    if actions.just_pressed(Action::Pause) {
+        recorder.playing = !recorder.playing;
+    }
+    let last = recorder.len() - 1;
+    if recorder.playing || actions.just_pressed(Action::StepOnce) {
+        recorder.cursor = (recorder.cursor + 1).min(last);
+    }
+    if actions.just_pressed(Action::StepBack) {
+        recorder.cursor = recorder.cursor.saturating_sub(1);
+    }
+    /* A TEAR WHILE REPLAYING CHANGES THE PARTICLE COUNT, WHICH ENDS THE REPLAY */
+    if let Some(frame) = recorder.frame() {
+        if let Err(err) = cloth.set_positions(frame) {
+            error!("failed to replay the recording: {}", err);
+            recorder.replaying = false;
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use std::{fs, io, path::PathBuf};
+
+use crate::{
+    cloth::ClothSim,
+    collider::{Collider, Colliders},
+    config::{ClothConfig, PinPreset, Pose, Wind},
+    error::ClothError,
+    simulate, F, I, P, V,
+};
+
+/* FIXED-SEED REGRESSION RUNS: A FEW SCENES STEPPED A FIXED NUMBER OF STEPS ON THE DETERMINISTIC CPU */
+/* SOLVER AND COMPARED PARTICLE BY PARTICLE AGAINST GOLDEN POSITIONS. THE UNIT TEST FAILS WITH A REPORT */
+/* OF THE WORST PARTICLES ONCE ANY OF THEM DRIFTED PAST THE TOLERANCE, --update-goldens REWRITES THE */
+/* GOLDEN FILES INSTEAD. THE SCENES IGNORE THE COMMAND LINE CONFIG SO THE GOLDENS STAY PUT */
+
+pub const GOLDEN_DIR: &str = "assets/golden";
+
+// FIRST LINE OF EVERY GOLDEN FILE, FOLLOWED BY THE PARTICLE COUNT
+const HEADER: &str = "cloth_sim golden v1";
+
+// SEED, SIZE AND PARTICLES ALONG EACH SIDE OF THE CLOTH OF EVERY SCENE
+const SEED: u64 = 1;
+const SIZE: F = 4.;
+const SUBDIVISIONS: I = 16;
+
+struct Scene {
+    name: &'static str,
+    steps: I,
+    config: ClothConfig,
+    colliders: Vec<Collider>,
+}
+
+fn scenes() -> Vec<Scene> {
+    let base = ClothConfig { seed: SEED, deterministic: true, ..ClothConfig::default() };
+    let calm = Wind { strength: 0., turbulence: 0., ..Wind::default() };
+    vec![
+        Scene {
+            name: "hanging_settle",
+            steps: 300,
+            config: ClothConfig { pin_preset: PinPreset::TopRow, wind: calm, ..base.clone() },
+            colliders: vec![],
+        },
+        Scene {
+            name: "sphere_drape",
+            steps: 300,
+            config: ClothConfig {
+                pose: Pose::FlatHorizontal { height: 2. },
+                pin_preset: PinPreset::Free,
+                origin: V::new(-SIZE / 2., 0., -SIZE / 2.),
+                wind: calm,
+                ..base.clone()
+            },
+            colliders: vec![Collider::Sphere { center: P::origin(), radius: 1. }],
+        },
+        Scene {
+            name: "wind_flutter",
+            steps: 200,
+            config: ClothConfig { pin_preset: PinPreset::TopRow, ..base },
+            colliders: vec![],
+        },
+    ]
+}
+
+/* GOLDENS DEPEND ON THE PRECISION THE SOLVER RUNS IN, SO THE f64 FEATURE HAS ITS OWN */
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(GOLDEN_DIR).join(format!("{}_{}.txt", name, std::any::type_name::<F>()))
+}
+
+/* ONE PARTICLE PER LINE, WRITTEN WITH THE SHORTEST TEXT THAT READS BACK TO THE SAME VALUE */
+fn write_golden(name: &str, positions: &[P]) -> io::Result<()> {
+    let lines = positions.iter().map(|p| format!("{} {} {}\n", p.x, p.y, p.z));
+    let text = std::iter::once(format!("{} {}\n", HEADER, positions.len())).chain(lines).collect::<String>();
+    fs::create_dir_all(GOLDEN_DIR)?;
+    fs::write(golden_path(name), text)
+}
+
+/* THE POSITIONS OF A SCENE AFTER ITS LAST STEP */
+fn run(scene: Scene) -> Result<Vec<P>, ClothError> {
+    let mut colliders = Colliders::default();
+    scene.colliders.into_iter().for_each(|collider| {
+        colliders.add(collider);
+    });
+    let mut cloth = ClothSim::new(SIZE, SIZE, (SUBDIVISIONS, SUBDIVISIONS), scene.config)?;
+    (0..scene.steps).for_each(|_| simulate(&mut cloth, &colliders));
+    Ok(cloth.particles.data.clone())
+}
+
+/* RUN EVERY SCENE AND REWRITE ITS GOLDEN */
+pub fn update_goldens() {
+    let mut failed = false;
+    for scene in scenes() {
+        let (name, label) = (scene.name, format!("{}:", scene.name));
+        let positions = match run(scene) {
+            Ok(positions) => positions,
+            Err(err) => {
+                println!("{:<15} FAILED: {}", label, err);
+                failed = true;
+                continue;
+            }
+        };
+        match write_golden(name, &positions) {
+            Ok(()) => println!("{:<15} wrote {}", label, golden_path(name).display()),
+            Err(err) => {
+                println!("{:<15} FAILED to write the golden: {}", label, err);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cmp::Ordering::Equal, io::ErrorKind};
+
+    use super::*;
+
+    // LARGEST DISTANCE OF ANY PARTICLE FROM ITS GOLDEN POSITION, AND THE PARTICLES LISTED FOR A SCENE THAT DRIFTED
+    const TOLERANCE: F = 1e-4;
+    const REPORT_WORST: I = 5;
+
+    fn read_golden(name: &str) -> io::Result<Vec<P>> {
+        let invalid = |msg: String| io::Error::new(ErrorKind::InvalidData, msg);
+        let text = fs::read_to_string(golden_path(name))?;
+        let mut lines = text.lines();
+        let count = lines
+            .next()
+            .and_then(|header| header.strip_prefix(HEADER))
+            .and_then(|count| count.trim().parse::<I>().ok())
+            .ok_or_else(|| invalid("not a golden file".to_string()))?;
+        let positions = lines
+            .map(|line| {
+                let c: Vec<F> = line.split_whitespace().filter_map(|c| c.parse().ok()).collect();
+                match c[..] {
+                    [x, y, z] => Ok(P::new(x, y, z)),
+                    _ => Err(invalid(format!("bad position '{}'", line))),
+                }
+            })
+            .collect::<io::Result<Vec<P>>>()?;
+        if positions.len() != count {
+            return Err(invalid(format!("expected {} positions, found {}", count, positions.len())));
+        }
+        Ok(positions)
+    }
+
+    /* EVERY SCENE HAS TO END WITH EVERY PARTICLE WITHIN TOLERANCE OF ITS GOLDEN, FAILING WITH THE WORST */
+    /* PARTICLES OF EACH SCENE THAT DRIFTED */
+    /* IGNORED BY DEFAULT: assets/golden IS NOT CHECKED IN, THE GOLDENS HAVE TO BE WRITTEN BY --update-goldens */
+    /* ON A TRUSTED BUILD OF EACH PRECISION FIRST. RUN IT WITH cargo test -- --ignored ONCE THEY ARE THERE */
+    #[test]
+    #[ignore]
+    fn scenes_match_their_goldens() {
+        let mut report = vec![];
+        for scene in scenes() {
+            let name = scene.name;
+            let positions = run(scene).unwrap();
+            let golden = read_golden(name).unwrap_or_else(|err| panic!("{}: {}", golden_path(name).display(), err));
+            assert_eq!(golden.len(), positions.len(), "{}: particle count differs from the golden", name);
+            /* A NaN IS AS FAR OFF AS IT GETS */
+            let deviation = |(g, p): (&P, &P)| Some((g - p).norm()).filter(|d| !d.is_nan()).unwrap_or(F::INFINITY);
+            let mut deviations: Vec<(F, I)> = golden.iter().zip(&positions).map(deviation).zip(0..).collect();
+            deviations.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Equal));
+            for &(d, i) in deviations.iter().take(REPORT_WORST).filter(|&&(d, _)| d > TOLERANCE) {
+                let (g, p) = (golden[i], positions[i]);
+                report.push(format!("{} particle {}: {:.3e} off, golden {} now {}", name, i, d, g, p));
+            }
+        }
+        assert!(report.is_empty(), "drifted past {:.0e}:\n{}", TOLERANCE, report.join("\n"));
+    }
+}
@@ -0,0 +1,304 @@
+use std::{fs, time::SystemTime};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    cloth::Cloth,
+    config::{
+        AutoDamping, ClothConfig, Diagonal, Integrator, KindIterations, LocalBoost, NormalWeighting, Plasticity,
+        Pressure, SelfCollision, SolveOrder, SolverMode, SpringDamping, Stiffness, WeightBinding, WindOcclusion,
+    },
+    F, I, V,
+};
+
+/* LIVE TUNING: assets/cloth.ron IS POLLED FOR CHANGES AND RE-APPLIED TO ALL CLOTHS */
+/* ONLY FIELDS PRESENT IN THE FILE ARE CHANGED, INVALID FILES KEEP THE PREVIOUS CONFIG */
+
+pub const CONFIG_PATH: &str = "assets/cloth.ron";
+
+// SECONDS BETWEEN CHECKS OF THE MODIFICATION TIME
+const POLL_INTERVAL: f32 = 0.5;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WindFile {
+    direction: Option<[F; 3]>,
+    strength: Option<F>,
+    turbulence: Option<F>,
+    scale: Option<F>,
+    frequency: Option<F>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    dt: Option<F>,
+    time_scale: Option<F>,
+    max_dt: Option<F>,
+    damping: Option<F>,
+    /* STRUCTURAL, SHEAR, FLEXION */
+    spring_damping: Option<[F; 3]>,
+    /* WARP, WEFT, SHEAR, FLEXION */
+    stiffness: Option<[F; 4]>,
+    min_iterations: Option<I>,
+    max_iterations: Option<I>,
+    tolerance: Option<F>,
+    solver: Option<SolverMode>,
+    integrator: Option<Integrator>,
+    solve_order: Option<SolveOrder>,
+    relaxation: Option<F>,
+    over_relaxation: Option<F>,
+    /* STRUCTURAL, SHEAR, FLEXION */
+    kind_iterations: Option<[I; 3]>,
+    local_boost: Option<LocalBoost>,
+    gravity: Option<[F; 3]>,
+    wind: Option<WindFile>,
+    wind_occlusion: Option<WindOcclusion>,
+    porosity: Option<F>,
+    air_drag: Option<F>,
+    particle_mass: Option<F>,
+    self_collision: Option<SelfCollision>,
+    max_collision_correction: Option<F>,
+    /* IN MEAN REST EDGE LENGTHS */
+    max_speed: Option<F>,
+    max_step_correction: Option<F>,
+    thickness: Option<F>,
+    friction: Option<F>,
+    continuous_collision: Option<bool>,
+    contact_cache: Option<bool>,
+    untangle: Option<bool>,
+    long_range_attachments: Option<bool>,
+    /* WARP, WEFT */
+    max_strain: Option<(F, F)>,
+    area_stiffness: Option<F>,
+    edge_collision: Option<I>,
+    /* ONLY AFFECTS CLOSED CLOTHS */
+    pressure: Option<Pressure>,
+    plasticity: Option<Plasticity>,
+    auto_damping: Option<AutoDamping>,
+    weight_binding: Option<WeightBinding>,
+    retension: Option<F>,
+    normal_weighting: Option<NormalWeighting>,
+    warm_start: Option<F>,
+    /* STRUCTURAL: CHANGING THESE REBUILDS THE CLOTHS */
+    subdivisions: Option<(I, I)>,
+    diagonal: Option<Diagonal>,
+}
+
+impl ConfigFile {
+    /* THE CONFIG WITH ALL FIELDS OF THE FILE APPLIED, OR WHY THEY ARE OUT OF RANGE */
+    fn apply(&self, config: &ClothConfig) -> Result<ClothConfig, String> {
+        let mut c = config.clone();
+        c.dt = self.dt.unwrap_or(c.dt);
+        c.time_scale = self.time_scale.unwrap_or(c.time_scale);
+        c.max_dt = self.max_dt.unwrap_or(c.max_dt);
+        c.damping = self.damping.unwrap_or(c.damping);
+        c.min_iterations = self.min_iterations.unwrap_or(c.min_iterations);
+        c.max_iterations = self.max_iterations.unwrap_or(c.max_iterations);
+        c.tolerance = self.tolerance.unwrap_or(c.tolerance);
+        c.solver = self.solver.unwrap_or(c.solver);
+        c.integrator = self.integrator.unwrap_or(c.integrator);
+        c.solve_order = self.solve_order.unwrap_or(c.solve_order);
+        c.relaxation = self.relaxation.unwrap_or(c.relaxation);
+        c.over_relaxation = self.over_relaxation.unwrap_or(c.over_relaxation);
+        if let Some([structural, shear, flexion]) = self.kind_iterations {
+            c.kind_iterations = Some(KindIterations { structural, shear, flexion });
+        }
+        if self.local_boost.is_some() {
+            c.local_boost = self.local_boost;
+        }
+        if self.wind_occlusion.is_some() {
+            c.wind_occlusion = self.wind_occlusion;
+        }
+        c.self_collision = self.self_collision.unwrap_or(c.self_collision);
+        c.max_collision_correction = self.max_collision_correction.unwrap_or(c.max_collision_correction);
+        c.thickness = self.thickness.unwrap_or(c.thickness);
+        c.friction = self.friction.unwrap_or(c.friction);
+        c.porosity = self.porosity.unwrap_or(c.porosity);
+        c.air_drag = self.air_drag.unwrap_or(c.air_drag);
+        c.particle_mass = self.particle_mass.unwrap_or(c.particle_mass);
+        c.continuous_collision = self.continuous_collision.unwrap_or(c.continuous_collision);
+        c.contact_cache = self.contact_cache.unwrap_or(c.contact_cache);
+        c.untangle = self.untangle.unwrap_or(c.untangle);
+        c.long_range_attachments = self.long_range_attachments.unwrap_or(c.long_range_attachments);
+        c.warm_start = self.warm_start.unwrap_or(c.warm_start);
+        c.normal_weighting = self.normal_weighting.unwrap_or(c.normal_weighting);
+        c.diagonal = self.diagonal.unwrap_or(c.diagonal);
+        if let Some([structural, shear, flexion]) = self.spring_damping {
+            c.spring_damping = SpringDamping { structural, shear, flexion };
+        }
+        if let Some([warp, weft, shear, flexion]) = self.stiffness {
+            c.stiffness = Stiffness { warp, weft, shear, flexion };
+        }
+        if let Some(g) = self.gravity {
+            c.gravity = V::from(g);
+        }
+        if let Some(w) = &self.wind {
+            c.wind.direction = w.direction.map(V::from).unwrap_or(c.wind.direction);
+            c.wind.strength = w.strength.unwrap_or(c.wind.strength);
+            c.wind.turbulence = w.turbulence.unwrap_or(c.wind.turbulence);
+            c.wind.scale = w.scale.unwrap_or(c.wind.scale);
+            c.wind.frequency = w.frequency.unwrap_or(c.wind.frequency);
+        }
+        if self.max_strain.is_some() {
+            c.max_strain = self.max_strain;
+        }
+        if self.edge_collision.is_some() {
+            c.edge_collision = self.edge_collision;
+        }
+        if self.area_stiffness.is_some() {
+            c.area_stiffness = self.area_stiffness;
+        }
+        if self.pressure.is_some() {
+            c.pressure = self.pressure;
+        }
+        if self.plasticity.is_some() {
+            c.plasticity = self.plasticity;
+        }
+        if self.auto_damping.is_some() {
+            c.auto_damping = self.auto_damping;
+        }
+        if self.weight_binding.is_some() {
+            c.weight_binding = self.weight_binding;
+        }
+        if self.retension.is_some() {
+            c.retension = self.retension;
+        }
+        if self.max_speed.is_some() {
+            c.max_speed = self.max_speed;
+        }
+        if self.max_step_correction.is_some() {
+            c.max_step_correction = self.max_step_correction;
+        }
+
+        let check = |ok: bool, msg: &str| if ok { Ok(()) } else { Err(msg.to_string()) };
+        let unit = |k: F| (0. ..=1.).contains(&k);
+        let SpringDamping { structural, shear, flexion } = c.spring_damping;
+        check(c.dt > 0. && c.max_dt > 0., "dt and max_dt must be positive")?;
+        check(unit(c.damping), "damping must be between 0 and 1")?;
+        check(unit(structural) && unit(shear) && unit(flexion), "spring_damping must be between 0 and 1")?;
+        check(
+            unit(c.stiffness.warp) && unit(c.stiffness.weft) && unit(c.stiffness.shear) && unit(c.stiffness.flexion),
+            "stiffness must be between 0 and 1",
+        )?;
+        check(c.min_iterations >= 1, "min_iterations must be at least 1")?;
+        check(c.max_iterations >= c.min_iterations, "max_iterations must be at least min_iterations")?;
+        check(
+            match c.solver {
+                SolverMode::Hierarchical { fine_iters, .. } => fine_iters >= 1,
+                _ => true,
+            },
+            "the hierarchical solver needs at least 1 fine iteration",
+        )?;
+        check(c.tolerance >= 0., "tolerance must not be negative")?;
+        check(c.relaxation > 0. && c.relaxation < 2., "relaxation must be between 0 and 2")?;
+        check(c.over_relaxation > 0. && c.over_relaxation < 2., "over_relaxation must be between 0 and 2")?;
+        check(c.kind_iterations.map_or(true, |k| k.structural >= 1), "kind_iterations needs at least 1 structural")?;
+        check(c.local_boost.map_or(true, |b| b.iterations >= 1), "local_boost needs at least 1 iteration")?;
+        check(
+            c.wind_occlusion.map_or(true, |o| (0. ..=1.).contains(&o.attenuation) && o.reach > 0. && o.interval >= 1),
+            "wind_occlusion needs an attenuation between 0 and 1, a positive reach and an interval of at least 1",
+        )?;
+        check(c.max_collision_correction > 0., "max_collision_correction must be positive")?;
+        check(c.max_speed.map_or(true, |e| e > 0.), "max_speed must be positive")?;
+        check(c.max_step_correction.map_or(true, |e| e > 0.), "max_step_correction must be positive")?;
+        check(c.thickness >= 0., "thickness must not be negative")?;
+        check(c.friction >= 0., "friction must not be negative")?;
+        check(unit(c.porosity), "porosity must be between 0 and 1")?;
+        check(unit(c.time_scale), "time_scale must be between 0 and 1")?;
+        check(c.air_drag >= 0., "air_drag must not be negative")?;
+        check(c.particle_mass > 0., "particle_mass must be positive")?;
+        check(c.wind.scale >= 0. && c.wind.frequency >= 0., "wind scale and frequency must not be negative")?;
+        check(c.max_strain.map_or(true, |(warp, weft)| warp >= 1. && weft >= 1.), "max_strain must be at least 1")?;
+        check(c.area_stiffness.map_or(true, unit), "area_stiffness must be between 0 and 1")?;
+        check(
+            c.pressure.map_or(true, |p| unit(p.stiffness) && p.volume > 0.),
+            "pressure needs a stiffness between 0 and 1 and a positive volume",
+        )?;
+        check(
+            c.plasticity.map_or(true, |p| p.yield_strain >= 0. && unit(p.creep) && p.max_strain >= 0.),
+            "plasticity needs a non-negative yield_strain and max_strain and a creep between 0 and 1",
+        )?;
+        check(
+            c.auto_damping.map_or(true, |a| {
+                let floor = unit(a.floor) && a.floor <= c.damping;
+                a.target >= 0. && floor && a.window >= 2 && a.rate >= 0. && a.growth > 0.
+            }),
+            "auto_damping needs a non-negative target and rate, a floor between 0 and damping, a window of at least 2 \
+             and a positive growth",
+        )?;
+        check(
+            c.weight_binding.map_or(true, |b| b.scale.is_finite() && b.scale > 0.),
+            "the scale of weight_binding must be positive",
+        )?;
+        check(unit(c.warm_start), "warm_start must be between 0 and 1")?;
+        check(c.retension.map_or(true, |t| t >= 0.), "retension must not be negative")?;
+        check(self.subdivisions.map_or(true, |(x, y)| x >= 2 && y >= 2), "subdivisions must be at least 2")?;
+        Ok(c)
+    }
+}
+
+#[derive(Default)]
+pub struct Watcher {
+    since_poll: f32,
+    modified: Option<SystemTime>,
+}
+
+/* THE FILE IS ONLY APPLIED ONCE IT CHANGES, SO COMMAND-LINE FLAGS HOLD UNTIL THE FIRST EDIT */
+pub fn watch(
+    time: Res<Time>,
+    mut watcher: Local<Watcher>,
+    mut config: ResMut<ClothConfig>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut cloths: Query<&mut Cloth>,
+) {
+    watcher.since_poll += time.delta_seconds;
+    if watcher.since_poll < POLL_INTERVAL {
+        return;
+    }
+    watcher.since_poll = 0.;
+
+    let modified = match fs::metadata(CONFIG_PATH).and_then(|m| m.modified()) {
+        Ok(modified) => modified,
+        Err(_) => return,
+    };
+    match watcher.modified.replace(modified) {
+        Some(previous) if previous != modified => {}
+        _ => return,
+    }
+
+    let file: ConfigFile = match fs::read_to_string(CONFIG_PATH)
+        .map_err(|err| err.to_string())
+        .and_then(|text| ron::de::from_str(&text).map_err(|err| err.to_string()))
+    {
+        Ok(file) => file,
+        Err(err) => {
+            error!("failed to read {}, keeping the previous config: {}", CONFIG_PATH, err);
+            return;
+        }
+    };
+    let new = match file.apply(&config) {
+        Ok(new) => new,
+        Err(err) => {
+            error!("invalid {}, keeping the previous config: {}", CONFIG_PATH, err);
+            return;
+        }
+    };
+
+    info!("reloaded {}", CONFIG_PATH);
+    *config = new;
+    for mut cloth in cloths.iter_mut() {
+        /* PER-CLOTH SETTINGS NOT IN THE FILE, LIKE THE POSE AND PINS, ARE KEPT */
+        let diagonal = cloth.config.diagonal;
+        if let Ok(own) = file.apply(&cloth.config) {
+            cloth.apply_config(own);
+        }
+        let (x, y) = file.subdivisions.unwrap_or_else(|| cloth.subdivisions());
+        if (x, y) != cloth.subdivisions() || cloth.config.diagonal != diagonal {
+            if let Err(err) = cloth.rebuild(x, y, &mut meshes) {
+                error!("failed to rebuild the cloth: {}", err);
+            }
+        }
+    }
+}
@@ -0,0 +1,527 @@
+use bevy::{
+    prelude::*,
+    render::mesh::{Indices, VertexAttributeValues},
+};
+
+use crate::{
+    bvh::Aabb,
+    config::{ClothConfig, Diagonal, NormalWeighting},
+    grid::{Grid, GridIdx},
+    to_array, F, I, P, V,
+};
+
+/* RENDER MESH OF A CLOTH, OPTIONALLY UPSAMPLED FROM THE SIMULATION GRID */
+/* ONLY READS THE PARTICLES, SO THE SIMULATION IS UNAFFECTED BY THE RENDER RESOLUTION */
+
+// VERTEX ATTRIBUTE NAME FOR THE [x, y, z, handedness] TANGENTS USED BY NORMAL MAPPING
+pub const ATTRIBUTE_TANGENT: &str = "Vertex_Tangent";
+
+/* PER QUAD OF THE REST GRID, WHETHER IT IS SPLIT ALONG (x, y)-(x + 1, y + 1) INSTEAD OF */
+/* THE UNIFORM (x + 1, y)-(x, y + 1). TIES IN Shortest KEEP THE UNIFORM DIAGONAL */
+pub fn diagonals(diagonal: Diagonal, rest: &Grid<P>) -> Grid<bool> {
+    let (w, h) = (rest.width(), rest.height());
+    Grid::from_fn(w.saturating_sub(1), h.saturating_sub(1), |(x, y)| match diagonal {
+        Diagonal::Uniform => false,
+        Diagonal::Alternating => (x + y) % 2 == 1,
+        Diagonal::Shortest => {
+            let main = (rest[(x + 1, y + 1)] - rest[(x, y)]).norm();
+            let anti = (rest[(x, y + 1)] - rest[(x + 1, y)]).norm();
+            main < anti
+        }
+    })
+}
+
+/* FRONT-FACING TRIANGLES OF THE GRID, TWO PER QUAD, AS FLATTENED PARTICLE INDICES */
+/* EACH QUAD TAKES THE DIAGONAL OF THE SIMULATED QUAD IT WAS UPSAMPLED FROM */
+pub fn triangles(w: I, h: I, factor: I, flipped: &Grid<bool>) -> Vec<[I; 3]> {
+    let flatten = |x, y| y * w + x;
+    (0..h - 1)
+        .flat_map(|y| {
+            (0..w - 1).flat_map(move |x| {
+                let (a, b, c, d) = (flatten(x, y), flatten(x + 1, y), flatten(x + 1, y + 1), flatten(x, y + 1));
+                match flipped[(x / factor, y / factor)] {
+                    false => vec![[a, b, d], [b, c, d]],
+                    true => vec![[a, b, c], [a, c, d]],
+                }
+            })
+        })
+        .collect()
+}
+
+/* TEXTURE COORDINATES PER VERTEX, VALUES OUTSIDE 0..1 RELY ON A REPEATING SAMPLER */
+pub fn uvs(w: I, h: I, config: &ClothConfig) -> Vec<[F; 2]> {
+    let ((su, sv), (ou, ov)) = (config.uv_scale, config.uv_offset);
+    Grid::from_fn(w, h, |(x, y)| {
+        let (u, v) = config.uv_orientation.apply(x as F / (w - 1) as F, y as F / (h - 1) as F);
+        [u * su + ou, v * sv + ov]
+    })
+    .data
+}
+
+/* CATMULL-ROM WEIGHTS OF THE 4 SURROUNDING SIMULATED POINTS FOR EACH RENDER POINT ALONG AN AXIS */
+/* THE CURVE PASSES THROUGH THE SIMULATED POINTS, ENDS ARE HANDLED BY CLAMPING THE INDICES */
+fn spline_weights(n: I, factor: I) -> Vec<([I; 4], [F; 4])> {
+    let clamp = |i: isize| i.max(0).min(n as isize - 1) as I;
+    (0..(n - 1) * factor + 1)
+        .map(|r| {
+            let c = (r / factor).min(n - 2);
+            let t = (r - c * factor) as F / factor as F;
+            let (t2, t3) = (t * t, t * t * t);
+            let c = c as isize;
+            (
+                [clamp(c - 1), clamp(c), clamp(c + 1), clamp(c + 2)],
+                [
+                    (-t3 + 2. * t2 - t) / 2.,
+                    (3. * t3 - 5. * t2 + 2.) / 2.,
+                    (-3. * t3 + 4. * t2 + t) / 2.,
+                    (t3 - t2) / 2.,
+                ],
+            )
+        })
+        .collect()
+}
+
+/* OVERWRITE A MESH ATTRIBUTE IN PLACE, REUSING ITS EXISTING STORAGE IF PRESENT */
+fn write_attribute(mesh: &mut Mesh, name: &'static str, values: impl Iterator<Item = [f32; 3]>) {
+    match mesh.attribute_mut(name) {
+        Some(VertexAttributeValues::Float3(buf)) => {
+            buf.clear();
+            buf.extend(values);
+        }
+        _ => mesh.set_attribute(name, values.collect::<Vec<_>>().into()),
+    }
+}
+
+fn write_tangents(mesh: &mut Mesh, values: impl Iterator<Item = [f32; 4]>) {
+    match mesh.attribute_mut(ATTRIBUTE_TANGENT) {
+        Some(VertexAttributeValues::Float4(buf)) => {
+            buf.clear();
+            buf.extend(values);
+        }
+        _ => mesh.set_attribute(ATTRIBUTE_TANGENT, values.collect::<Vec<_>>().into()),
+    }
+}
+
+/* ANGLE AT p BETWEEN THE EDGES TO q AND r, 0 RATHER THAN NAN WHEN AN EDGE IS DEGENERATE */
+fn corner_angle(p: P, q: P, r: P) -> F {
+    let (u, v) = (q - p, r - p);
+    u.cross(&v).norm().atan2(u.dot(&v))
+}
+
+/* UNIT VERTEX NORMALS OF A TRIANGLE MESH, EVERY FACE CONTRIBUTING ITS UNIT NORMAL TIMES THE WEIGHT */
+/* FACES WITHOUT A DIRECTION ARE SKIPPED, VERTICES LEFT WITHOUT ONE FACE -z */
+pub fn vertex_normals(positions: &[P], triangles: &[[I; 3]], weighting: NormalWeighting, normals: &mut Vec<V>) {
+    normals.clear();
+    normals.resize(positions.len(), V::zeros());
+    for &[a, b, c] in triangles {
+        let (pa, pb, pc) = (positions[a], positions[b], positions[c]);
+        let cross = (pb - pa).cross(&(pc - pa));
+        let n = match cross.try_normalize(F::EPSILON) {
+            Some(n) => n,
+            None => continue,
+        };
+        let weights = match weighting {
+            NormalWeighting::Area => [cross.norm(); 3],
+            NormalWeighting::Angle => [corner_angle(pa, pb, pc), corner_angle(pb, pc, pa), corner_angle(pc, pa, pb)],
+        };
+        for (&i, &w) in [a, b, c].iter().zip(weights.iter()) {
+            normals[i] += n * w;
+        }
+    }
+    for n in normals.iter_mut() {
+        *n = n.try_normalize(F::EPSILON).unwrap_or_else(|| -V::z());
+    }
+}
+
+/* TRIANGLES OF THE RENDER MESH MOVED INTO A SUB-MESH OF THEIR OWN, WITH A COPY OF THE VERTICES THEY USE */
+struct Region {
+    /* RENDER VERTEX OF EACH VERTEX OF THE SUB-MESH */
+    vertices: Vec<I>,
+    /* INDEXING vertices */
+    triangles: Vec<[I; 3]>,
+}
+
+pub struct RenderGrid {
+    factor: I,
+    /* HALF THE WIDTH OF THE RIBBON A ROPE, ONE PARTICLE WIDE, IS DRAWN AS */
+    ribbon: Option<F>,
+    /* WIDTH OF THE RENDER GRID, VERTICES PAST THE GRID ARE SPLIT OFF ALONG A CUT FROM origins */
+    width: I,
+    origins: Vec<I>,
+    /* ON A CLOTH BUILT FROM A MESH, THE PARTICLE EACH VERTEX FOLLOWS, SO A PARTICLE WELDED FROM SEVERAL */
+    /* VERTICES IS DRAWN BY ALL OF THEM, AND THE TRIANGLES OVER THE PARTICLES THE NORMALS ARE WORKED OUT ON */
+    /* EMPTY ON A GRID */
+    welds: Vec<I>,
+    welded: Vec<[I; 3]>,
+    triangles: Vec<[I; 3]>,
+    /* PER TRIANGLE, THE REGION THAT DRAWS IT INSTEAD OF THE MAIN MESH */
+    owners: Vec<Option<I>>,
+    regions: Vec<Region>,
+    uvs: Vec<[F; 2]>,
+    xs: Vec<([I; 4], [F; 4])>,
+    ys: Vec<([I; 4], [F; 4])>,
+
+    /* SCRATCH BUFFERS REUSED ACROSS FRAMES TO AVOID PER-FRAME ALLOCATIONS */
+    positions: Vec<P>,
+    normals: Vec<V>,
+    /* NORMAL PER PARTICLE OF A CLOTH BUILT FROM A MESH */
+    welded_normals: Vec<V>,
+    /* ACCUMULATED (TANGENT, BITANGENT) PER VERTEX */
+    tangents: Vec<(V, V)>,
+    /* ORTHONORMALIZED TANGENT AND HANDEDNESS PER VERTEX, KEPT FOR THE REGIONS */
+    frames: Vec<(V, F)>,
+    /* BOUNDS OF THE POSITIONS WRITTEN BY THE LAST update */
+    bounds: Aabb,
+}
+
+impl RenderGrid {
+    /* A FACTOR OF 1 RENDERS THE SIMULATED PARTICLES DIRECTLY */
+    /* A ROPE IS DRAWN AS A RIBBON TWO VERTICES WIDE AND AS WIDE AS ITS THICKNESS, NEVER UPSAMPLED */
+    pub fn new(w: I, h: I, factor: I, flipped: &Grid<bool>, config: &ClothConfig) -> Self {
+        let ribbon = Some(config.thickness / 2.).filter(|_| w == 1);
+        let straight = Grid::filled(1, h.saturating_sub(1), false);
+        let (w, factor, flipped) = match ribbon {
+            Some(_) => (2, 1, &straight),
+            None => (w, factor.max(1), flipped),
+        };
+        let (rw, rh) = ((w - 1) * factor + 1, (h - 1) * factor + 1);
+        let triangles = triangles(rw, rh, factor, flipped);
+        Self {
+            factor,
+            ribbon,
+            width: rw,
+            origins: vec![],
+            welds: vec![],
+            welded: vec![],
+            owners: vec![None; triangles.len()],
+            regions: vec![],
+            triangles,
+            uvs: uvs(rw, rh, config),
+            xs: if factor > 1 { spline_weights(w, factor) } else { vec![] },
+            ys: if factor > 1 { spline_weights(h, factor) } else { vec![] },
+            positions: Vec::with_capacity(rw * rh),
+            normals: Vec::with_capacity(rw * rh),
+            welded_normals: vec![],
+            tangents: Vec::with_capacity(rw * rh),
+            frames: Vec::with_capacity(rw * rh),
+            bounds: Aabb::empty(),
+        }
+    }
+
+    /* THE VERTICES OF A CLOTH BUILT FROM A MESH WITH THEIR OWN TRIANGLES AND UVS, EACH FOLLOWING THE PARTICLE */
+    /* IN welds. ITS n PARTICLES FORM A SINGLE ROW OF THE GRID, AND THE MESH IS NEVER UPSAMPLED */
+    pub fn welded(welds: &[I], triangles: &[[I; 3]], uvs: &[[F; 2]], n: I) -> Self {
+        let m = welds.len();
+        Self {
+            factor: 1,
+            ribbon: None,
+            width: n,
+            origins: vec![],
+            welds: welds.to_vec(),
+            welded: triangles.iter().map(|&[a, b, c]| [welds[a], welds[b], welds[c]]).collect(),
+            owners: vec![None; triangles.len()],
+            regions: vec![],
+            triangles: triangles.to_vec(),
+            uvs: uvs.to_vec(),
+            xs: vec![],
+            ys: vec![],
+            positions: Vec::with_capacity(m),
+            normals: Vec::with_capacity(m),
+            welded_normals: Vec::with_capacity(n),
+            tangents: Vec::with_capacity(m),
+            frames: Vec::with_capacity(m),
+            bounds: Aabb::empty(),
+        }
+    }
+
+    pub fn factor(&self) -> I {
+        self.factor
+    }
+
+    /* EMPTY UNTIL THE FIRST update, AN UPSAMPLED MESH CAN BULGE SLIGHTLY PAST THE PARTICLES */
+    pub fn bounds(&self) -> &Aabb {
+        &self.bounds
+    }
+
+    /* RENDER THE GIVEN TRIANGLES INSTEAD OF THE FULL GRID, WITH THE PARTICLES SPLIT OFF ALONG */
+    /* A CUT AS EXTRA VERTICES THAT COPY THE UVS OF THEIR ORIGIN SO THE TEXTURE DOES NOT SMEAR */
+    /* ONLY MEANINGFUL AT A FACTOR OF 1, THE SPLINE UPSAMPLING KNOWS NOTHING ABOUT CUTS */
+    /* REGIONS ARE DROPPED, SET THEM AGAIN AFTERWARDS */
+    pub fn tear(&mut self, triangles: &[[I; 3]], origins: &[I]) {
+        self.triangles = triangles.to_vec();
+        self.owners = vec![None; triangles.len()];
+        self.regions.clear();
+        self.origins = origins.to_vec();
+        let uvs: Vec<_> = origins.iter().map(|&o| self.uvs[o]).collect();
+        self.uvs.extend(uvs);
+    }
+
+    /* SIMULATED GRID POSITION OF A RENDER VERTEX, IN RENDER QUADS SO IT STAYS AN INTEGER WHEN UPSAMPLED */
+    fn grid_position(&self, i: I) -> GridIdx {
+        if self.ribbon.is_some() {
+            return (0, i / 2);
+        }
+        let grid_len = self.uvs.len() - self.origins.len();
+        let i = match self.welds.get(i) {
+            Some(&particle) => particle,
+            None if i < grid_len => i,
+            None => self.origins[i - grid_len],
+        };
+        (i % self.width, i / self.width)
+    }
+
+    /* MOVE THE TRIANGLES WITH EVERY CORNER IN THE INCLUSIVE RECTANGLE OF SIMULATED PARTICLES OF EACH */
+    /* REGION OUT OF THE MAIN MESH, A LATER REGION TAKING THE TRIANGLES IT SHARES WITH AN EARLIER ONE */
+    pub fn set_regions(&mut self, rects: &[(GridIdx, GridIdx)]) {
+        let factor = self.factor;
+        let inside = |((x0, y0), (x1, y1)): (GridIdx, GridIdx), (x, y): GridIdx| {
+            (x0 * factor..=x1 * factor).contains(&x) && (y0 * factor..=y1 * factor).contains(&y)
+        };
+        let owners: Vec<_> = self
+            .triangles
+            .iter()
+            .map(|t| rects.iter().rposition(|&rect| t.iter().all(|&i| inside(rect, self.grid_position(i)))))
+            .collect();
+
+        let mut local = vec![None; self.uvs.len()];
+        self.regions = (0..rects.len())
+            .map(|k| {
+                let mut region = Region { vertices: vec![], triangles: vec![] };
+                for (t, _) in self.triangles.iter().zip(&owners).filter(|(_, owner)| **owner == Some(k)) {
+                    let mut corner = |i: I| match local[i] {
+                        Some((owner, j)) if owner == k => j,
+                        _ => {
+                            local[i] = Some((k, region.vertices.len()));
+                            region.vertices.push(i);
+                            region.vertices.len() - 1
+                        }
+                    };
+                    let corners = [corner(t[0]), corner(t[1]), corner(t[2])];
+                    region.triangles.push(corners);
+                }
+                region
+            })
+            .collect();
+        self.owners = owners;
+    }
+
+    /* WRITE THE INDEX BUFFER, UVS, POSITIONS AND NORMALS, RESIZING ANY EXISTING ATTRIBUTES */
+    pub fn fill(&mut self, particles: &Grid<P>, mesh: &mut Mesh, weighting: NormalWeighting) {
+        let owners = &self.owners;
+        let main: Vec<_> = self.triangles.iter().zip(owners).filter(|(_, o)| o.is_none()).map(|(&t, _)| t).collect();
+        set_indices(mesh, &main, self.uvs.len());
+        set_uvs(mesh, self.uvs.iter().copied());
+
+        self.update(particles, mesh, weighting);
+    }
+
+    /* WRITE REGION k INTO ITS OWN MESH FROM THE VERTICES OF THE LAST update */
+    /* THE INDICES AND UVS ONLY CHANGE WITH THE REGIONS OR THE TRIANGLES, SO THEY ARE ONLY WRITTEN WHEN full */
+    pub fn write_region(&self, k: I, mesh: &mut Mesh, full: bool) {
+        let region = match self.regions.get(k) {
+            Some(region) => region,
+            None => return,
+        };
+        if full {
+            set_indices(mesh, &region.triangles, region.vertices.len());
+            set_uvs(mesh, region.vertices.iter().map(|&i| self.uvs[i]));
+        }
+        let positions = region.vertices.iter().map(|&i| to_array(self.positions[i]));
+        write_attribute(mesh, Mesh::ATTRIBUTE_POSITION, positions.clone().chain(positions));
+        let normals = |sign: F| region.vertices.iter().map(move |&i| to_array(P::from(self.normals[i] * sign)));
+        write_attribute(mesh, Mesh::ATTRIBUTE_NORMAL, normals(1.).chain(normals(-1.)));
+        let frames = region.vertices.iter().map(|&i| self.frames[i]);
+        write_tangents(mesh, tangents(frames.clone(), 1.).chain(tangents(frames, -1.)));
+    }
+
+    pub fn update(&mut self, particles: &Grid<P>, mesh: &mut Mesh, weighting: NormalWeighting) {
+        self.positions.clear();
+        if let Some(half_width) = self.ribbon {
+            self.extrude(particles, half_width);
+        } else if !self.welds.is_empty() {
+            self.positions.extend(self.welds.iter().map(|&i| particles.data[i]));
+        } else if self.factor == 1 {
+            self.positions.extend(particles.iter().copied());
+        } else {
+            /* TENSOR PRODUCT OF THE PER-AXIS SPLINE WEIGHTS OVER THE 4x4 SURROUNDING PARTICLES */
+            for (iy, wy) in &self.ys {
+                for (ix, wx) in &self.xs {
+                    let mut p = V::zeros();
+                    for b in 0..4 {
+                        for a in 0..4 {
+                            p += particles[(ix[a], iy[b])].coords * (wx[a] * wy[b]);
+                        }
+                    }
+                    self.positions.push(P::from(p));
+                }
+            }
+        }
+        self.bounds = Aabb::from_points(&self.positions);
+
+        let positions = self.positions.iter().map(|p| to_array(*p));
+        write_attribute(mesh, Mesh::ATTRIBUTE_POSITION, positions.clone().chain(positions));
+
+        /* SMOOTHED VERTEX NORMALS, NEGATED FOR THE BACK SIDE. THE REGIONS COUNT TOO SO THEIR SEAMS DO NOT SHOW */
+        /* THE VERTICES WELDED INTO A PARTICLE ALL TAKE ITS NORMAL, SO NEITHER DO THE UV SEAMS OF A MESH */
+        if self.welds.is_empty() {
+            vertex_normals(&self.positions, &self.triangles, weighting, &mut self.normals);
+        } else {
+            vertex_normals(particles.as_slice(), &self.welded, weighting, &mut self.welded_normals);
+            self.normals.clear();
+            let welded_normals = &self.welded_normals;
+            self.normals.extend(self.welds.iter().map(|&i| welded_normals[i]));
+        }
+        let front = self.normals.iter().map(|n| to_array(P::from(*n)));
+        let back = self.normals.iter().map(|n| to_array(P::from(-n)));
+        write_attribute(mesh, Mesh::ATTRIBUTE_NORMAL, front.chain(back));
+
+        self.update_tangents(mesh);
+    }
+
+    /* TWO VERTICES PER GRID PARTICLE OF A ROPE, half_width TO EITHER SIDE ALONG THE BINORMAL */
+    /* THE NORMAL IS PARALLEL TRANSPORTED DOWN THE ROPE SO THE RIBBON DOES NOT TWIST BETWEEN PARTICLES */
+    fn extrude(&mut self, particles: &Grid<P>, half_width: F) {
+        let n = self.uvs.len() / 2;
+        let mut normal = V::z();
+        for i in 0..n {
+            let (prev, next) = (particles.data[i.saturating_sub(1)], particles.data[(i + 1).min(n - 1)]);
+            let tangent = (next - prev).try_normalize(F::EPSILON).unwrap_or_else(|| -V::y());
+            let fallback = || tangent.cross(&V::x()).try_normalize(F::EPSILON).unwrap_or_else(V::z);
+            normal = (normal - tangent * tangent.dot(&normal)).try_normalize(F::EPSILON).unwrap_or_else(fallback);
+            let side = tangent.cross(&normal) * half_width;
+            let p = particles.data[i];
+            self.positions.push(p - side);
+            self.positions.push(p + side);
+        }
+    }
+
+    /* PER-FACE TANGENTS FROM THE UV GRADIENTS, ACCUMULATED AND ORTHONORMALIZED AGAINST THE NORMAL */
+    /* TRIANGLES WITH DEGENERATE UVS ARE SKIPPED, VERTICES LEFT WITHOUT ONE GET ANY PERPENDICULAR */
+    fn update_tangents(&mut self, mesh: &mut Mesh) {
+        self.tangents.clear();
+        self.tangents.resize(self.positions.len(), (V::zeros(), V::zeros()));
+        for &[a, b, c] in &self.triangles {
+            let (pa, pb, pc) = (self.positions[a], self.positions[b], self.positions[c]);
+            let ([ua, va], [ub, vb], [uc, vc]) = (self.uvs[a], self.uvs[b], self.uvs[c]);
+            let (e1, e2) = (pb - pa, pc - pa);
+            let (du1, dv1, du2, dv2) = (ub - ua, vb - va, uc - ua, vc - va);
+            let r = du1 * dv2 - du2 * dv1;
+            if r.abs() < F::EPSILON {
+                continue;
+            }
+            let tangent = (e1 * dv2 - e2 * dv1) / r;
+            let bitangent = (e2 * du1 - e1 * du2) / r;
+            for &i in &[a, b, c] {
+                self.tangents[i].0 += tangent;
+                self.tangents[i].1 += bitangent;
+            }
+        }
+
+        /* GRAM-SCHMIDT AGAINST THE NORMAL, WITH THE HANDEDNESS OF THE UV FRAME IN w */
+        /* THE BACK SIDE HAS A NEGATED NORMAL WITH THE SAME UVS, WHICH FLIPS THE HANDEDNESS */
+        let frames = self.normals.iter().zip(self.tangents.iter()).map(|(n, (t, b))| {
+            let fallback = || n.cross(&V::y()).try_normalize(F::EPSILON).unwrap_or_else(V::x);
+            let t = (t - n * n.dot(t)).try_normalize(F::EPSILON).unwrap_or_else(fallback);
+            let w: F = if n.cross(&t).dot(b) < 0. { -1. } else { 1. };
+            (t, w)
+        });
+        self.frames.clear();
+        self.frames.extend(frames);
+        let frames = self.frames.iter().copied();
+        write_tangents(mesh, tangents(frames.clone(), 1.).chain(tangents(frames, -1.)));
+    }
+}
+
+/* FRONT FACES USE THE FIRST COPY OF THE n VERTICES, BACK FACES THE SECOND COPY */
+/* WITH REVERSED WINDING SO THAT EACH SIDE CAN CARRY ITS OWN NORMAL */
+fn set_indices(mesh: &mut Mesh, triangles: &[[I; 3]], n: I) {
+    let front = triangles.iter().flat_map(|t| t.to_vec()).map(|i| i as u32);
+    let back = triangles.iter().flat_map(|&[a, b, c]| vec![c, b, a]).map(|i| (i + n) as u32);
+    mesh.set_indices(Some(Indices::U32(front.chain(back).collect())));
+}
+
+fn set_uvs(mesh: &mut Mesh, uvs: impl Iterator<Item = [F; 2]>) {
+    let uv: Vec<[f32; 2]> = uvs.map(|[u, v]| [u as f32, v as f32]).collect();
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, [uv.clone(), uv].concat().into());
+}
+
+/* TANGENTS OF ONE SIDE, THE BACK SIDE HAS sign -1 */
+fn tangents(frames: impl Iterator<Item = (V, F)>, sign: F) -> impl Iterator<Item = [f32; 4]> {
+    frames.map(move |(t, w)| [t.x as f32, t.y as f32, t.z as f32, (sign * w) as f32])
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::pipeline::PrimitiveTopology;
+
+    use super::*;
+
+    /* A CORNER SHARED BY A LARGE FACE AND A TINY ONE AT RIGHT ANGLES: WEIGHTED BY AREA THE LARGE FACE TAKES */
+    /* THE NORMAL OVER, WEIGHTED BY ANGLE BOTH COUNT THE SAME. A VERTEX IN NO FACE OR ONLY IN A DEGENERATE */
+    /* ONE FACES -z */
+    #[test]
+    fn normal_weightings_at_a_shared_corner() {
+        let positions = [
+            P::origin(),
+            P::new(10., 0., 0.),
+            P::new(0., 10., 0.),
+            P::new(0., 0.1, 0.),
+            P::new(0., 0., 0.1),
+            P::new(5., 5., 5.),
+        ];
+        let triangles = [[0, 1, 2], [0, 3, 4], [5, 5, 1]];
+        let mut normals = vec![];
+
+        vertex_normals(&positions, &triangles, NormalWeighting::Area, &mut normals);
+        assert!((normals[0] - V::z()).norm() < 1e-3, "area-weighted {}", normals[0]);
+        assert!((normals[1] - V::z()).norm() < 1e-6 && (normals[4] - V::x()).norm() < 1e-6);
+        assert_eq!(normals[5], -V::z());
+
+        vertex_normals(&positions, &triangles, NormalWeighting::Angle, &mut normals);
+        assert!((normals[0] - V::new(1., 0., 1.).normalize()).norm() < 1e-5, "angle-weighted {}", normals[0]);
+        assert_eq!(normals.len(), positions.len());
+    }
+
+    /* THE ALTERNATING LAYOUT FLIPS EVERY OTHER QUAD, LAST ONE INCLUDED, AND THE SHORTEST ONE FOLLOWS THE */
+    /* SHEAR OF THE REST GRID, KEEPING THE UNIFORM DIAGONAL ON SQUARE QUADS */
+    #[test]
+    fn diagonal_layouts() {
+        let sheared = |shear: F| Grid::from_fn(3, 3, |(x, y)| P::new(x as F + shear * y as F, y as F, 0.));
+        let square = sheared(0.);
+
+        let alternating = diagonals(Diagonal::Alternating, &square);
+        assert_eq!(alternating.data, vec![false, true, true, false]);
+        let expected = vec![[0, 1, 3], [1, 4, 3], [1, 2, 5], [1, 5, 4], [3, 4, 7], [3, 7, 6], [4, 5, 7], [5, 8, 7]];
+        assert_eq!(triangles(3, 3, 1, &alternating), expected);
+
+        assert_eq!(diagonals(Diagonal::Shortest, &square).data, vec![false; 4]);
+        assert_eq!(diagonals(Diagonal::Shortest, &sheared(0.8)).data, vec![false; 4]);
+        assert_eq!(diagonals(Diagonal::Shortest, &sheared(-0.8)).data, vec![true; 4]);
+        assert_eq!(diagonals(Diagonal::Uniform, &sheared(-0.8)).data, vec![false; 4]);
+    }
+
+    /* THE BOUNDS ARE EMPTY UNTIL THE FIRST UPDATE, THEN BOX THE VERTICES JUST WRITTEN: THE PARTICLES */
+    /* THEMSELVES AT A FACTOR OF 1, FOLLOWING ONE THAT MOVES, AND A ROPE WIDENED INTO ITS RIBBON */
+    #[test]
+    fn bounds_follow_the_written_vertices() {
+        let config = ClothConfig::default();
+        let mut particles = Grid::from_fn(4, 3, |(x, y)| P::new(x as F, -(y as F), 0.5));
+        let mut render = RenderGrid::new(4, 3, 1, &Grid::filled(3, 2, false), &config);
+        assert!(render.bounds().min.x > render.bounds().max.x);
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        render.fill(&particles, &mut mesh, config.normal_weighting);
+        let bounds = *render.bounds();
+        assert_eq!((bounds.min, bounds.max), (P::new(0., -2., 0.5), P::new(3., 0., 0.5)));
+        particles[(3, 2)].z = -4.;
+        render.update(&particles, &mut mesh, config.normal_weighting);
+        assert_eq!((render.bounds().min.z, render.bounds().max), (-4., bounds.max));
+
+        let rope = Grid::from_fn(1, 5, |(_, y)| P::new(0., -(y as F), 0.));
+        let mut ribbon = RenderGrid::new(1, 5, 1, &Grid::filled(0, 4, false), &config);
+        ribbon.fill(&rope, &mut mesh, config.normal_weighting);
+        let half_width = config.thickness / 2.;
+        let widened = ribbon.bounds().max - ribbon.bounds().min;
+        assert!((widened - V::new(2. * half_width, 4., 0.)).abs().max() < 1e-6, "ribbon spans {}", widened);
+    }
+}
@@ -0,0 +1,266 @@
+use std::{fmt, fs, io, path::Path};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    cloth::{Cloth, ClothSim},
+    collider::{Collider, ColliderId, Colliders},
+    config::{ClothConfig, PinPreset, Pose},
+    error::ClothError,
+    grid::GridIdx,
+    F, I, P, V,
+};
+
+/* SCRIPTED RUNS: ONE CLOTH, THE COLLIDERS AROUND IT AND ACTIONS AT FIXED STEP COUNTS, SOME OF THEM */
+/* CHECKS ON THE STATE OF THE CLOTH. --scenario <PATH> PLAYS ONE NEXT TO THE MAIN CLOTH AS A DEMO AND */
+/* LOGS THE CHECKS, WITH --headless IT RUNS ON ITS OWN AS A TEST THAT FAILS IF ANY CHECK DOES */
+/* ALL POSITIONS ARE IN THE WORLD, THE CLOTH IS SPAWNED WITHOUT A TRANSFORM */
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    pub size: (F, F),
+    pub subdivisions: GridIdx,
+    pub start: Start,
+    /* WHERE THE POSE PUTS THE FIRST PARTICLE */
+    pub origin: [F; 3],
+    pub pins: Pins,
+    #[serde(default)]
+    pub colliders: Vec<Shape>,
+    /* A HEADLESS RUN STOPS AFTER THIS MANY STEPS */
+    pub steps: I,
+    pub actions: Vec<Timed>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub enum Start {
+    Hanging,
+    /* LYING FLAT AT THIS HEIGHT ABOVE origin */
+    Flat(F),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub enum Pins {
+    Free,
+    TopRow,
+    Corners,
+    At(Vec<GridIdx>),
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub enum Shape {
+    Plane { point: [F; 3], normal: [F; 3] },
+    Sphere { center: [F; 3], radius: F },
+    Box { min: [F; 3], max: [F; 3] },
+}
+
+/* AN ACTION IS APPLIED ONCE THE CLOTH HAS TAKEN step STEPS, ACTIONS AT THE SAME STEP IN FILE ORDER */
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Timed {
+    pub step: I,
+    pub action: Action,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub enum Action {
+    Gust(Gust),
+    Pin(Vec<GridIdx>),
+    Unpin(Vec<GridIdx>),
+    Wind { direction: [F; 3], strength: F },
+    /* TELEPORT THE COLLIDER AT THIS INDEX OF colliders BY offset */
+    MoveCollider { index: I, offset: [F; 3] },
+    Check(Check),
+}
+
+/* AIR BLOWN ALONG A RAY FOR steps STEPS, LIKE HOLDING V, SEE Cloth::apply_force_along_ray */
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Gust {
+    pub origin: [F; 3],
+    pub direction: [F; 3],
+    pub radius: F,
+    pub strength: F,
+    pub steps: I,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub enum Check {
+    MaxStrainBelow(F),
+    MeanStrainBelow(F),
+    KineticEnergyBelow(F),
+    /* HEIGHT OF THE LOWEST PARTICLE */
+    LowestAbove(F),
+    LowestBelow(F),
+}
+
+impl Check {
+    /* THE MEASURED VALUE AND WHETHER IT PASSES */
+    fn measure(self, cloth: &ClothSim) -> (F, bool) {
+        let lowest = || cloth.bounding_box().min.y;
+        match self {
+            Check::MaxStrainBelow(limit) => below(cloth.max_strain(), limit),
+            Check::MeanStrainBelow(limit) => below(cloth.mean_strain(), limit),
+            Check::KineticEnergyBelow(limit) => below(cloth.kinetic_energy(), limit),
+            Check::LowestAbove(limit) => (lowest(), lowest() > limit),
+            Check::LowestBelow(limit) => below(lowest(), limit),
+        }
+    }
+}
+
+/* NaN NEVER PASSES */
+fn below(value: F, limit: F) -> (F, bool) {
+    (value, value < limit)
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Check::MaxStrainBelow(limit) => write!(f, "max strain below {}", limit),
+            Check::MeanStrainBelow(limit) => write!(f, "mean strain below {}", limit),
+            Check::KineticEnergyBelow(limit) => write!(f, "kinetic energy below {}", limit),
+            Check::LowestAbove(limit) => write!(f, "lowest point above {}", limit),
+            Check::LowestBelow(limit) => write!(f, "lowest point below {}", limit),
+        }
+    }
+}
+
+/* THE RESULT OF ONE CHECK, WITH THE STEP IT RAN AT AND WHAT IT MEASURED */
+#[derive(Clone, Copy, Debug)]
+pub struct Outcome {
+    pub step: I,
+    pub check: Check,
+    pub measured: F,
+    pub passed: bool,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let verdict = if self.passed { "passed" } else { "FAILED" };
+        write!(f, "step {}: {} {}, measured {:.4}", self.step, self.check, verdict, self.measured)
+    }
+}
+
+impl Scenario {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        ron::de::from_str(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
+    }
+
+    /* THE CLOTH OF THE SCENARIO, WITH THE MATERIAL AND SOLVER SETTINGS OF config */
+    pub fn cloth(&self, config: &ClothConfig) -> Result<ClothSim, ClothError> {
+        let pose = match self.start {
+            Start::Hanging => Pose::HangingVertical,
+            Start::Flat(height) => Pose::FlatHorizontal { height },
+        };
+        let pin_preset = match &self.pins {
+            Pins::Free => PinPreset::Free,
+            Pins::TopRow => PinPreset::TopRow,
+            Pins::Corners => PinPreset::Corners,
+            Pins::At(pins) => PinPreset::Custom(pins.clone()),
+        };
+        let config = ClothConfig { pose, pin_preset, origin: V::from(self.origin), ..config.clone() };
+        let (width, height) = self.size;
+        ClothSim::new(width, height, self.subdivisions, config)
+    }
+}
+
+impl Shape {
+    fn collider(self) -> Collider {
+        match self {
+            Shape::Plane { point, normal } => {
+                Collider::Plane { point: P::from(point), normal: V::from(normal).normalize() }
+            }
+            Shape::Sphere { center, radius } => Collider::Sphere { center: P::from(center), radius },
+            Shape::Box { min, max } => Collider::Box { min: P::from(min), max: P::from(max) },
+        }
+    }
+}
+
+/* PLAYS A SCENARIO ON THE CLOTH OF ITS ENTITY, FOLLOWING THE STEP COUNT OF THE CLOTH */
+pub struct ScenarioRunner {
+    scenario: Scenario,
+    /* INDEX OF THE FIRST COLLIDER OF THE SCENARIO IN Colliders, WHICH MAY HOLD OTHERS BEFORE IT */
+    first_collider: ColliderId,
+    /* NEXT ACTION TO APPLY, AND THE STEP COUNT OF THE CLOTH LAST TIME */
+    next: I,
+    steps: I,
+    /* GUSTS STILL BLOWING, WITH THE STEP THEY STOP AT */
+    gusts: Vec<(Gust, I)>,
+}
+
+impl ScenarioRunner {
+    /* ADDS THE COLLIDERS OF THE SCENARIO */
+    pub fn new(mut scenario: Scenario, colliders: &mut Colliders) -> Self {
+        scenario.actions.sort_by_key(|timed| timed.step);
+        let first_collider = colliders.0.len();
+        scenario.colliders.iter().for_each(|&shape| {
+            colliders.add(shape.collider());
+        });
+        Self { scenario, first_collider, next: 0, steps: 0, gusts: vec![] }
+    }
+
+    pub fn scenario(&self) -> &Scenario {
+        &self.scenario
+    }
+
+    /* APPLY EVERY ACTION DUE BY THE STEP COUNT OF THE CLOTH AND THE FORCES OF THE GUSTS STILL BLOWING */
+    /* FOR ITS NEXT STEP. A CLOTH THAT WAS RESET STARTS THE SCENARIO OVER, THE COLLIDERS STAY WHERE THEY ARE */
+    pub fn advance(&mut self, cloth: &mut ClothSim, colliders: &mut Colliders) -> Vec<Outcome> {
+        let step = cloth.steps();
+        if step < self.steps {
+            self.next = 0;
+            self.gusts.clear();
+        }
+        self.steps = step;
+
+        let mut outcomes = vec![];
+        while let Some(timed) = self.scenario.actions.get(self.next).filter(|timed| timed.step <= step) {
+            self.next += 1;
+            match &timed.action {
+                Action::Gust(gust) => self.gusts.push((*gust, step + gust.steps)),
+                Action::Pin(pins) => pin(cloth, pins, true),
+                Action::Unpin(pins) => pin(cloth, pins, false),
+                Action::Wind { direction, strength } => cloth.set_wind(V::from(*direction), *strength),
+                Action::MoveCollider { index, offset } => {
+                    let own = *index < self.scenario.colliders.len();
+                    match colliders.0.get_mut(self.first_collider + index).filter(|_| own) {
+                        Some(layered) => layered.collider.translate(V::from(*offset)),
+                        None => warn!("the scenario has no collider {}", index),
+                    }
+                }
+                Action::Check(check) => {
+                    let (measured, passed) = check.measure(cloth);
+                    outcomes.push(Outcome { step, check: *check, measured, passed });
+                }
+            }
+        }
+
+        self.gusts.retain(|(_, until)| *until > step);
+        for (gust, _) in &self.gusts {
+            cloth.apply_force_along_ray(P::from(gust.origin), V::from(gust.direction), gust.radius, gust.strength);
+        }
+        outcomes
+    }
+}
+
+fn pin(cloth: &mut ClothSim, pins: &[GridIdx], fixed: bool) {
+    for &idx in pins {
+        if let Err(err) = cloth.set_pinned(idx, fixed) {
+            warn!("failed to {} in the scenario: {}", if fixed { "pin" } else { "unpin" }, err);
+        }
+    }
+}
+
+/* PLAYS THE SCENARIOS IN THE WINDOW, THE CHECKS ARE ONLY LOGGED */
+pub fn run(mut colliders: ResMut<Colliders>, mut runners: Query<(&mut ScenarioRunner, &mut Cloth)>) {
+    for (mut runner, mut cloth) in runners.iter_mut() {
+        for outcome in runner.advance(&mut cloth, &mut colliders) {
+            match outcome.passed {
+                true => info!("scenario {}", outcome),
+                false => error!("scenario {}", outcome),
+            }
+        }
+    }
+}
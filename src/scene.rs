@@ -0,0 +1,140 @@
+use std::{fs, io, path::Path};
+
+use bevy::{prelude::*, render::pipeline::PrimitiveTopology};
+use serde::Deserialize;
+
+use crate::{bvh::Aabb, camera::OrbitCamera, debug, to_vec3, F};
+
+/* EXAMPLE SCENE AROUND THE CLOTH: LIGHTS, A GROUND GRID AND THE INITIAL CAMERA FRAMING */
+/* LIGHTING IS READ ONCE AT STARTUP, THE REST IS FITTED TO THE BOUNDING BOX OF THE CLOTH */
+
+pub const SCENE_PATH: &str = "assets/scene.ron";
+
+// HALF-WIDTH OF THE GROUND GRID AND THE SPACING OF ITS LINES
+const GROUND_EXTENT: F = 60.;
+const GROUND_SPACING: F = 2.;
+
+// HOW FAR BELOW THE CLOTH THE GROUND GOES WHEN NO COLLIDER DEFINES A FLOOR
+const GROUND_DROP: F = 10.;
+
+// DIRECTION FROM THE CLOTH TO THE CAMERA, THE VERTICAL FIELD OF VIEW AND THE ROOM LEFT AROUND THE CLOTH
+const VIEW_DIRECTION: [f32; 3] = [-0.45, 0.4, -0.8];
+const FOV: f32 = std::f32::consts::FRAC_PI_4;
+const FRAMING_MARGIN: f32 = 1.2;
+
+// DEFAULT LIGHT POSITIONS, IN MULTIPLES OF THE RADIUS OF THE CLOTH AWAY FROM ITS CENTER
+const KEY_OFFSET: [f32; 3] = [0.5, 1.5, -1.];
+const FILL_OFFSET: [f32; 3] = [-1.5, 0.5, 1.];
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LightSettings {
+    /* LEFT OUT, THE LIGHT IS PLACED RELATIVE TO THE CLOTH */
+    #[serde(default)]
+    pub position: Option<[F; 3]>,
+    pub color: [f32; 3],
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Lighting {
+    pub key: LightSettings,
+    /* OPTIONAL DIMMER LIGHT FROM THE OTHER SIDE, SO THE BACK OF THE CLOTH IS NOT BLACK */
+    #[serde(default)]
+    pub fill: Option<LightSettings>,
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        Self {
+            key: LightSettings { position: None, color: [2., 2., 2.] },
+            fill: None,
+        }
+    }
+}
+
+impl Lighting {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        ron::de::from_str(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
+    }
+
+    /* A MISSING OR INVALID FILE KEEPS THE DEFAULT SINGLE WHITE LIGHT */
+    pub fn load_or_default() -> Self {
+        Self::load(SCENE_PATH).unwrap_or_else(|err| {
+            if err.kind() != io::ErrorKind::NotFound {
+                warn!("failed to load {}: {}", SCENE_PATH, err);
+            }
+            Self::default()
+        })
+    }
+}
+
+/* CENTER AND RADIUS OF THE SPHERE AROUND A BOX */
+fn bounding_sphere(bounds: &Aabb) -> (Vec3, f32) {
+    let (min, max) = (to_vec3(bounds.min.coords), to_vec3(bounds.max.coords));
+    ((min + max) / 2., ((max - min).length() / 2.).max(1.))
+}
+
+pub fn spawn_lights(commands: &mut Commands, lighting: &Lighting, bounds: &Aabb) {
+    let (center, radius) = bounding_sphere(bounds);
+    let lights = std::iter::once((lighting.key, KEY_OFFSET)).chain(lighting.fill.map(|fill| (fill, FILL_OFFSET)));
+    for (settings, offset) in lights {
+        let position = match settings.position {
+            Some([x, y, z]) => Vec3::new(x as f32, y as f32, z as f32),
+            None => center + Vec3::from(offset) * radius,
+        };
+        let [r, g, b] = settings.color;
+        commands.spawn(LightComponents {
+            transform: Transform::from_translation(position),
+            light: Light {
+                color: Color::rgb(r, g, b),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+    }
+}
+
+/* FAR ENOUGH AWAY THAT THE WHOLE BOUNDING SPHERE FITS IN THE VERTICAL FIELD OF VIEW */
+pub fn framing(bounds: &Aabb) -> OrbitCamera {
+    let (center, radius) = bounding_sphere(bounds);
+    let distance = radius * FRAMING_MARGIN / (FOV / 2.).sin();
+    OrbitCamera::looking_at(center + Vec3::from(VIEW_DIRECTION).normalize() * distance, center)
+}
+
+/* SQUARE OF GRID LINES AT THE FLOOR, CENTERED UNDER THE CLOTH */
+/* floor IS THE HEIGHT OF THE FLOOR COLLIDER, IF THERE IS ONE */
+pub fn spawn_ground(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    floor: Option<F>,
+    bounds: &Aabb,
+) {
+    let y = floor.unwrap_or(bounds.min.y - GROUND_DROP);
+    let snap = |v: F| (v / GROUND_SPACING).round() * GROUND_SPACING;
+    let (cx, cz) = (snap((bounds.min.x + bounds.max.x) / 2.), snap((bounds.min.z + bounds.max.z) / 2.));
+
+    let (e, lines) = (GROUND_EXTENT, (2. * GROUND_EXTENT / GROUND_SPACING) as i32);
+    let mut positions = vec![];
+    for k in 0..=lines {
+        let t = -e + k as F * GROUND_SPACING;
+        for &(x0, z0, x1, z1) in &[(cx + t, cz - e, cx + t, cz + e), (cx - e, cz + t, cx + e, cz + t)] {
+            positions.push([x0 as f32, y as f32, z0 as f32]);
+            positions.push([x1 as f32, y as f32, z1 as f32]);
+        }
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::LineList);
+    debug::write_vertices(&mut mesh, positions);
+    commands.spawn(PbrComponents {
+        mesh: meshes.add(mesh),
+        material: materials.add(StandardMaterial {
+            albedo: Color::rgb(0.35, 0.35, 0.35),
+            shaded: false,
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+}
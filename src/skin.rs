@@ -0,0 +1,492 @@
+use std::{fs, io, path::Path};
+
+use bevy::prelude::*;
+use nalgebra::{Matrix3, Rotation3, UnitQuaternion};
+use serde_json::{json, Value};
+
+use crate::{
+    bindings::Action,
+    cloth::{Cloth, ClothSim},
+    config::NormalWeighting,
+    record::{Recorder, RECORDING_PATH},
+    render, to_array, F, I, P, V,
+};
+
+/* BAKE A RECORDED RUN INTO A BONE ANIMATION FOR ENGINES THAT CANNOT RUN THE SOLVER: THE GRID IS SPLIT */
+/* INTO M x N REGIONS WITH A BONE EACH, EVERY PARTICLE IS SKINNED TO THE 4 BONES AROUND IT BY BILINEAR */
+/* WEIGHTS, AND EVERY FRAME EACH BONE TAKES THE CENTROID OF ITS REGION AND OPTIONALLY THE ROTATION THAT */
+/* BEST MAPS THE REST REGION ONTO IT. F8 WRITES THE RECORDING AS A BINARY glTF WITH THE SKINNED REST */
+/* MESH AND ONE ANIMATION CLIP, ONLY THE SUBSET OF glTF NEEDED FOR THAT */
+
+pub const EXPORT_PATH: &str = "recording.glb";
+
+// DEFAULT REGIONS ACROSS AND DOWN THE GRID
+pub const REGIONS: (I, I) = (4, 4);
+
+// GLB CONTAINER: MAGIC, VERSION AND CHUNK TYPES, ALL LITTLE ENDIAN
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_VERSION: u32 = 2;
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+// glTF COMPONENT TYPES AND BUFFER VIEW TARGETS
+const FLOAT: u32 = 5126;
+const UNSIGNED_SHORT: u32 = 5123;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+#[derive(Clone, Copy, Debug)]
+pub struct SkinOptions {
+    pub regions: (I, I),
+    /* FIT A ROTATION PER BONE AND FRAME ON TOP OF THE TRANSLATION */
+    pub rotation: bool,
+}
+
+impl Default for SkinOptions {
+    fn default() -> Self {
+        Self { regions: REGIONS, rotation: true }
+    }
+}
+
+/* WHAT THE EXPORT NEEDS OF THE CLOTH THE FRAMES WERE RECORDED FROM */
+pub struct SkinSource {
+    subdivisions: (I, I),
+    /* GRID POSITION EACH PARTICLE IS OR WAS SPLIT OFF FROM */
+    grid: Vec<(I, I)>,
+    triangles: Vec<[I; 3]>,
+    uvs: Vec<[F; 2]>,
+    normal_weighting: NormalWeighting,
+    /* SIMULATED TIME BETWEEN RECORDED FRAMES */
+    dt: F,
+}
+
+impl SkinSource {
+    pub fn new(cloth: &ClothSim) -> Self {
+        let (w, h) = cloth.subdivisions();
+        let grid: Vec<(I, I)> = (0..cloth.particles.len())
+            .map(|i| cloth.particles.origin(i))
+            .map(|o| (o % w, o / w))
+            .collect();
+        let uvs = render::uvs(w, h, &cloth.config);
+        let uvs = grid.iter().map(|&(x, y)| uvs[y * w + x]).collect();
+        Self {
+            subdivisions: (w, h),
+            grid,
+            triangles: cloth.faces().collect(),
+            uvs,
+            normal_weighting: cloth.config.normal_weighting,
+            dt: cloth.config.dt,
+        }
+    }
+}
+
+/* HOW CLOSELY THE SKINNED MESH FOLLOWS THE RECORDING, OVER ALL FRAMES AND PARTICLES */
+#[derive(Debug)]
+pub struct SkinReport {
+    pub bones: I,
+    pub frames: I,
+    pub mean_error: F,
+    pub max_error: F,
+}
+
+/* FOR ONE AXIS OF THE GRID, THE TWO NEAREST BONES AND THE WEIGHT OF THE SECOND, BONES SITTING AT THE */
+/* CENTERS OF THEIR REGIONS. PAST THE OUTERMOST CENTERS THE OUTERMOST BONE TAKES EVERYTHING */
+fn axis_membership(i: I, len: I, bones: I) -> (I, I, F) {
+    let u = ((i as F + 0.5) / len as F * bones as F - 0.5).max(0.).min((bones - 1) as F);
+    let a = u.floor() as I;
+    (a, (a + 1).min(bones - 1), u - a as F)
+}
+
+/* THE 4 BONES A PARTICLE IS SKINNED TO WITH THEIR WEIGHTS, UNUSED SLOTS ARE BONE 0 WITH WEIGHT 0 */
+fn membership((x, y): (I, I), (w, h): (I, I), (m, n): (I, I)) -> [(I, F); 4] {
+    let ((x0, x1, fx), (y0, y1, fy)) = (axis_membership(x, w, m), axis_membership(y, h, n));
+    let slot = |bx: I, by: I, weight: F| if weight > 0. { (by * m + bx, weight) } else { (0, 0.) };
+    [
+        slot(x0, y0, (1. - fx) * (1. - fy)),
+        slot(x1, y0, fx * (1. - fy)),
+        slot(x0, y1, (1. - fx) * fy),
+        slot(x1, y1, fx * fy),
+    ]
+}
+
+/* ROTATION BEST MAPPING THE REST OFFSETS ONTO THE CURRENT ONES BY WEIGHTED LEAST SQUARES (KABSCH) */
+/* A FLAT REGION ONLY PINS DOWN TWO AXES, THE THIRD IS CHOSEN SO THE RESULT IS NO REFLECTION */
+fn best_rotation(covariance: Matrix3<F>) -> UnitQuaternion<F> {
+    let svd = covariance.svd(true, true);
+    let (u, v) = match (svd.u, svd.v_t) {
+        (Some(u), Some(v_t)) => (u, v_t.transpose()),
+        _ => return UnitQuaternion::identity(),
+    };
+    let d = (v * u.transpose()).determinant().signum();
+    let r = v * Matrix3::from_diagonal(&V::new(1., 1., d)) * u.transpose();
+    UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix_unchecked(r))
+}
+
+/* BINARY BUFFER WITH ITS VIEWS AND ACCESSORS, EVERY VIEW STARTING 4-BYTE ALIGNED */
+#[derive(Default)]
+struct Buffers {
+    bin: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl Buffers {
+    /* ADD AN ACCESSOR OVER NEW DATA OF count ELEMENTS, RETURNING ITS INDEX */
+    fn accessor(&mut self, bytes: &[u8], target: Option<u32>, component: u32, count: I, kind: &str) -> I {
+        self.bin.resize((self.bin.len() + 3) / 4 * 4, 0);
+        let mut view = json!({ "buffer": 0, "byteOffset": self.bin.len(), "byteLength": bytes.len() });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        self.bin.extend_from_slice(bytes);
+        self.views.push(view);
+        let view = self.views.len() - 1;
+        self.accessors.push(json!({ "bufferView": view, "componentType": component, "count": count, "type": kind }));
+        self.accessors.len() - 1
+    }
+
+    /* FLOAT ACCESSOR OVER values, width OF THEM PER ELEMENT */
+    fn floats(&mut self, values: &[f32], width: I, kind: &str, target: Option<u32>) -> I {
+        let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes().to_vec()).collect();
+        self.accessor(&bytes, target, FLOAT, values.len() / width, kind)
+    }
+
+    /* THE PER-COMPONENT BOUNDS glTF REQUIRES OF POSITIONS AND ANIMATION INPUTS */
+    fn bound(&mut self, index: I, values: &[f32], width: I) {
+        let column = |k: I| values.iter().skip(k).step_by(width).copied();
+        let min: Vec<f32> = (0..width).map(|k| column(k).fold(f32::INFINITY, f32::min)).collect();
+        let max: Vec<f32> = (0..width).map(|k| column(k).fold(f32::NEG_INFINITY, f32::max)).collect();
+        self.accessors[index]["min"] = json!(min);
+        self.accessors[index]["max"] = json!(max);
+    }
+}
+
+/* WRITE frames, ALL WITH THE PARTICLES OF source, AS A SKINNED AND ANIMATED BINARY glTF AT path */
+pub fn export(
+    path: impl AsRef<Path>,
+    source: &SkinSource,
+    frames: &[&[P]],
+    options: SkinOptions,
+) -> io::Result<SkinReport> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+    let n = source.grid.len();
+    let rest = match frames.first() {
+        Some(rest) => *rest,
+        None => return Err(invalid("nothing recorded".to_string())),
+    };
+    if let Some(frame) = frames.iter().find(|f| f.len() != n) {
+        return Err(invalid(format!("a frame has {} particles, the cloth {}", frame.len(), n)));
+    }
+    let (w, h) = source.subdivisions;
+    let (m, k) = (options.regions.0.max(1).min(w), options.regions.1.max(1).min(h));
+    let bones = m * k;
+    let members: Vec<[(I, F); 4]> = source.grid.iter().map(|&g| membership(g, (w, h), (m, k))).collect();
+
+    /* REST CENTROID OF EVERY BONE, THE ORIGIN ITS ROTATION IS ABOUT */
+    let centroids = |frame: &[P]| {
+        let mut sums = vec![(P::origin().coords, 0.); bones];
+        for (p, slots) in frame.iter().zip(&members) {
+            for &(bone, weight) in slots {
+                sums[bone].0 += p.coords * weight;
+                sums[bone].1 += weight;
+            }
+        }
+        sums.into_iter().map(|(sum, weight)| P::from(sum / weight.max(F::EPSILON))).collect::<Vec<P>>()
+    };
+    let rest_centers = centroids(rest);
+
+    /* TRANSLATION AND ROTATION OF EVERY BONE IN EVERY FRAME, ROTATIONS KEPT IN ONE HEMISPHERE SO THE */
+    /* INTERPOLATION BETWEEN FRAMES TAKES THE SHORT WAY */
+    let mut poses: Vec<Vec<(P, UnitQuaternion<F>)>> = Vec::with_capacity(frames.len());
+    for frame in frames {
+        let centers = centroids(frame);
+        let mut covariances = vec![Matrix3::zeros(); bones];
+        if options.rotation {
+            for ((&p, &p0), slots) in frame.iter().zip(rest).zip(&members) {
+                for &(bone, weight) in slots {
+                    covariances[bone] += (p0 - rest_centers[bone]) * (p - centers[bone]).transpose() * weight;
+                }
+            }
+        }
+        let pose: Vec<(P, UnitQuaternion<F>)> = (0..bones)
+            .map(|bone| {
+                let rotation =
+                    if options.rotation { best_rotation(covariances[bone]) } else { UnitQuaternion::identity() };
+                let rotation = match poses.last() {
+                    Some(last) if last[bone].1.coords.dot(&rotation.coords) < 0. => {
+                        UnitQuaternion::new_unchecked(-rotation.into_inner())
+                    }
+                    _ => rotation,
+                };
+                (centers[bone], rotation)
+            })
+            .collect();
+        poses.push(pose);
+    }
+
+    /* WHERE THE SKINNED MESH PUTS EVERY PARTICLE, AGAINST WHERE IT WAS RECORDED */
+    let (mut total, mut max_error): (F, F) = (0., 0.);
+    for (frame, pose) in frames.iter().zip(&poses) {
+        for ((&p, &p0), slots) in frame.iter().zip(rest).zip(&members) {
+            let skinned = slots.iter().fold(P::origin().coords, |sum, &(bone, weight)| {
+                let (center, rotation) = pose[bone];
+                sum + (center.coords + rotation * (p0 - rest_centers[bone])) * weight
+            });
+            let error = (skinned - p.coords).norm();
+            total += error;
+            max_error = max_error.max(error);
+        }
+    }
+
+    let mut buffers = Buffers::default();
+    let vertex = Some(ARRAY_BUFFER);
+    let positions: Vec<f32> = rest.iter().flat_map(|&p| to_array(p).to_vec()).collect();
+    let position = buffers.floats(&positions, 3, "VEC3", vertex);
+    buffers.bound(position, &positions, 3);
+    let mut normals = vec![];
+    render::vertex_normals(rest, &source.triangles, source.normal_weighting, &mut normals);
+    let normals: Vec<f32> = normals.iter().flat_map(|&n| to_array(P::from(n)).to_vec()).collect();
+    let normal = buffers.floats(&normals, 3, "VEC3", vertex);
+    let uvs: Vec<f32> = source.uvs.iter().flat_map(|&[u, v]| vec![u as f32, v as f32]).collect();
+    let uv = buffers.floats(&uvs, 2, "VEC2", vertex);
+    let joints: Vec<u8> =
+        members.iter().flatten().flat_map(|&(bone, _)| (bone as u16).to_le_bytes().to_vec()).collect();
+    let joints = buffers.accessor(&joints, vertex, UNSIGNED_SHORT, n, "VEC4");
+    let weights: Vec<f32> = members.iter().flatten().map(|&(_, weight)| weight as f32).collect();
+    let weights = buffers.floats(&weights, 4, "VEC4", vertex);
+    let indices: Vec<u8> = source.triangles.iter().flatten().flat_map(|&i| (i as u32).to_le_bytes().to_vec()).collect();
+    let count = source.triangles.len() * 3;
+    let indices = buffers.accessor(&indices, Some(ELEMENT_ARRAY_BUFFER), UNSIGNED_INT, count, "SCALAR");
+
+    /* THE BIND POSE OF EVERY BONE IS ITS REST CENTROID WITHOUT ROTATION, COLUMN MAJOR */
+    let inverse_binds: Vec<f32> = rest_centers
+        .iter()
+        .flat_map(|c| {
+            let [x, y, z] = to_array(*c);
+            vec![1., 0., 0., 0., 0., 1., 0., 0., 0., 0., 1., 0., -x, -y, -z, 1.]
+        })
+        .collect();
+    let inverse_binds = buffers.floats(&inverse_binds, 16, "MAT4", None);
+
+    let times: Vec<f32> = (0..frames.len()).map(|f| (f as F * source.dt) as f32).collect();
+    let input = buffers.floats(&times, 1, "SCALAR", None);
+    buffers.bound(input, &times, 1);
+    let (mut samplers, mut channels) = (vec![], vec![]);
+    for bone in 0..bones {
+        let translations: Vec<f32> = poses.iter().flat_map(|pose| to_array(pose[bone].0).to_vec()).collect();
+        let output = buffers.floats(&translations, 3, "VEC3", None);
+        samplers.push(json!({ "input": input, "output": output, "interpolation": "LINEAR" }));
+        channels.push(json!({ "sampler": samplers.len() - 1, "target": { "node": bone + 1, "path": "translation" } }));
+        if options.rotation {
+            /* glTF TAKES x, y, z, w, THE ORDER OF THE COORDINATES OF A QUATERNION */
+            let rotation =
+                |pose: &Vec<(P, UnitQuaternion<F>)>| pose[bone].1.coords.iter().map(|&c| c as f32).collect::<Vec<_>>();
+            let rotations: Vec<f32> = poses.iter().flat_map(rotation).collect();
+            let output = buffers.floats(&rotations, 4, "VEC4", None);
+            samplers.push(json!({ "input": input, "output": output, "interpolation": "LINEAR" }));
+            channels.push(json!({ "sampler": samplers.len() - 1, "target": { "node": bone + 1, "path": "rotation" } }));
+        }
+    }
+
+    let joint_nodes: Vec<I> = (1..=bones).collect();
+    let mut nodes = vec![json!({ "name": "cloth", "mesh": 0, "skin": 0 })];
+    nodes.extend(rest_centers.iter().enumerate().map(|(bone, c)| {
+        json!({ "name": format!("region_{}_{}", bone % m, bone / m), "translation": to_array(*c) })
+    }));
+    let attributes = json!({
+        "POSITION": position,
+        "NORMAL": normal,
+        "TEXCOORD_0": uv,
+        "JOINTS_0": joints,
+        "WEIGHTS_0": weights,
+    });
+    buffers.bin.resize((buffers.bin.len() + 3) / 4 * 4, 0);
+    let gltf = json!({
+        "asset": { "version": "2.0", "generator": "cloth_sim" },
+        "scene": 0,
+        "scenes": [{ "nodes": (0..=bones).collect::<Vec<I>>() }],
+        "nodes": nodes,
+        "meshes": [{ "primitives": [{ "attributes": attributes, "indices": indices }] }],
+        "skins": [{ "joints": joint_nodes, "inverseBindMatrices": inverse_binds }],
+        "animations": [{ "name": "recording", "samplers": samplers, "channels": channels }],
+        "buffers": [{ "byteLength": buffers.bin.len() }],
+        "bufferViews": buffers.views,
+        "accessors": buffers.accessors,
+    });
+
+    let mut json = serde_json::to_vec(&gltf).map_err(|err| invalid(err.to_string()))?;
+    json.resize((json.len() + 3) / 4 * 4, b' ');
+    let total_length = 12 + 8 + json.len() + 8 + buffers.bin.len();
+    let mut glb = Vec::with_capacity(total_length);
+    for word in &[GLB_MAGIC, GLB_VERSION, total_length as u32, json.len() as u32, CHUNK_JSON] {
+        glb.extend_from_slice(&word.to_le_bytes());
+    }
+    glb.extend_from_slice(&json);
+    glb.extend_from_slice(&(buffers.bin.len() as u32).to_le_bytes());
+    glb.extend_from_slice(&CHUNK_BIN.to_le_bytes());
+    glb.extend_from_slice(&buffers.bin);
+    fs::write(path, glb)?;
+
+    let samples = (frames.len() * n).max(1);
+    Ok(SkinReport { bones, frames: frames.len(), mean_error: total / samples as F, max_error })
+}
+
+/* F8 EXPORTS THE RECORDING IN MEMORY, OR THE ONE SAVED TO RECORDING_PATH, OF THE CLOTH IT FITS */
+pub fn export_recording(
+    actions: Res<Input<Action>>,
+    options: Res<SkinOptions>,
+    recorder: Res<Recorder>,
+    cloths: Query<&Cloth>,
+) {
+    if !actions.just_pressed(Action::ExportSkin) {
+        return;
+    }
+    let loaded;
+    let recording = match recorder.len() {
+        0 => match Recorder::load(RECORDING_PATH) {
+            Ok(recording) => {
+                loaded = recording;
+                &loaded
+            }
+            Err(err) => {
+                error!("nothing recorded and failed to load {}: {}", RECORDING_PATH, err);
+                return;
+            }
+        },
+        _ => &*recorder,
+    };
+    let frames: Vec<&[P]> = recording.frames().collect();
+    let cloth = match cloths.iter().find(|c| frames.first().map_or(false, |f| f.len() == c.particles.len())) {
+        Some(cloth) => cloth,
+        None => {
+            error!("no cloth has the particles of the recording, e.g. after a tear or a resolution change");
+            return;
+        }
+    };
+    match export(EXPORT_PATH, &SkinSource::new(cloth), &frames, *options) {
+        Ok(report) => info!(
+            "exported {} frames with {} bones to {}, skinned error mean {:.4} max {:.4}",
+            report.frames, report.bones, EXPORT_PATH, report.mean_error, report.max_error
+        ),
+        Err(err) => error!("failed to export {}: {}", EXPORT_PATH, err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use super::*;
+    use crate::cloth::SIZE;
+
+    /* READ BACK AN EXPORTED FILE AND CHECK ITS CONTAINER, THAT EVERY VIEW AND ACCESSOR FITS IN THE */
+    /* BUFFER, AND THE BONE AND FRAME COUNTS OF ITS SKIN AND ANIMATION */
+    fn validate(path: impl AsRef<Path>, bones: I, frames: I, rotation: bool) -> Result<(), String> {
+        let glb = fs::read(path).map_err(|err| err.to_string())?;
+        let word = |at: I| -> Result<u32, String> {
+            glb.get(at..at + 4)
+                .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+                .ok_or_else(|| format!("file ends at {} bytes", glb.len()))
+        };
+        if word(0)? != GLB_MAGIC || word(4)? != GLB_VERSION {
+            return Err("not a glTF 2.0 binary".to_string());
+        }
+        if word(8)? as I != glb.len() {
+            return Err(format!("header length {} but the file has {} bytes", word(8)?, glb.len()));
+        }
+        let json_length = word(12)? as I;
+        if word(16)? != CHUNK_JSON || json_length % 4 != 0 {
+            return Err("the first chunk is not an aligned json chunk".to_string());
+        }
+        let gltf: Value = glb
+            .get(20..20 + json_length)
+            .ok_or("the json chunk runs past the end")
+            .and_then(|json| serde_json::from_slice(json).map_err(|_| "the json chunk does not parse"))?;
+        let bin_at = 20 + json_length;
+        let bin_length = word(bin_at)? as I;
+        if word(bin_at + 4)? != CHUNK_BIN || bin_at + 8 + bin_length != glb.len() {
+            return Err("the second chunk is not a binary chunk ending the file".to_string());
+        }
+
+        let count = |v: &Value| v.as_u64().map(|c| c as I);
+        let list = |key: &str| gltf[key].as_array().map_or(0, Vec::len);
+        let buffer_length = count(&gltf["buffers"][0]["byteLength"]).ok_or("no buffer length")?;
+        if buffer_length > bin_length {
+            return Err(format!("the buffer has {} bytes but the binary chunk {}", buffer_length, bin_length));
+        }
+        for (i, view) in gltf["bufferViews"].as_array().ok_or("no buffer views")?.iter().enumerate() {
+            let (offset, length) = (count(&view["byteOffset"]).unwrap_or(0), count(&view["byteLength"]));
+            let end = offset.saturating_add(length.unwrap_or(I::MAX));
+            if end > buffer_length {
+                return Err(format!("buffer view {} ends at {}, past the buffer", i, end));
+            }
+        }
+        for (i, accessor) in gltf["accessors"].as_array().ok_or("no accessors")?.iter().enumerate() {
+            let width = match accessor["type"].as_str() {
+                Some("SCALAR") => 1,
+                Some("VEC2") => 2,
+                Some("VEC3") => 3,
+                Some("VEC4") => 4,
+                Some("MAT4") => 16,
+                _ => return Err(format!("accessor {} has an unknown type", i)),
+            };
+            let size = match accessor["componentType"].as_u64().map(|c| c as u32) {
+                Some(UNSIGNED_SHORT) => 2,
+                Some(FLOAT) | Some(UNSIGNED_INT) => 4,
+                _ => return Err(format!("accessor {} has an unknown component type", i)),
+            };
+            let view = &gltf["bufferViews"][accessor["bufferView"].as_u64().unwrap_or(u64::MAX) as usize];
+            let needed = count(&accessor["count"]).unwrap_or(I::MAX).saturating_mul(width * size);
+            if needed > count(&view["byteLength"]).unwrap_or(0) {
+                return Err(format!("accessor {} needs {} bytes, more than its view", i, needed));
+            }
+        }
+
+        let joints = gltf["skins"][0]["joints"].as_array().map_or(0, Vec::len);
+        if joints != bones || list("nodes") != bones + 1 {
+            return Err(format!("expected {} bones, the skin has {} of {} nodes", bones, joints, list("nodes")));
+        }
+        let animation = &gltf["animations"][0];
+        let channels = animation["channels"].as_array().map_or(0, Vec::len);
+        let expected = if rotation { 2 * bones } else { bones };
+        if channels != expected {
+            return Err(format!("expected {} animation channels, found {}", expected, channels));
+        }
+        for sampler in animation["samplers"].as_array().ok_or("no animation samplers")? {
+            for key in &["input", "output"] {
+                let accessor = &gltf["accessors"][sampler[*key].as_u64().unwrap_or(u64::MAX) as usize];
+                if count(&accessor["count"]) != Some(frames) {
+                    let keys = count(&accessor["count"]);
+                    return Err(format!("a sampler {} has {:?} keys, expected {} frames", key, keys, frames));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /* A RECORDED HANGING CLOTH EXPORTED AS A SKINNED glTF FILE IN THE TEMPORARY DIRECTORY READS BACK WITH THE */
+    /* BONES AND FRAMES IT WAS WRITTEN WITH */
+    #[test]
+    fn export_reads_back() {
+        // STEPS RECORDED, AND THE PARTICLES ALONG EACH SIDE OF THE CLOTH
+        const STEPS: I = 120;
+        const N: I = 16;
+
+        let mut cloth = Cloth::build_sim(SIZE.0, SIZE.1, (N, N)).unwrap();
+        let mut frames = vec![cloth.particles.data.clone()];
+        for _ in 0..STEPS {
+            cloth.step(&[]);
+            frames.push(cloth.particles.data.clone());
+        }
+        let frames: Vec<&[P]> = frames.iter().map(Vec::as_slice).collect();
+
+        let options = SkinOptions::default();
+        let path = std::env::temp_dir().join(EXPORT_PATH);
+        let report = export(&path, &SkinSource::new(&cloth), &frames, options).unwrap();
+        let checked = validate(&path, report.bones, report.frames, options.rotation);
+        let _ = fs::remove_file(&path);
+        checked.unwrap();
+        assert!(report.max_error.is_finite(), "the skinned mesh does not follow the recording");
+    }
+}
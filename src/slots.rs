@@ -0,0 +1,83 @@
+use crate::I;
+
+/* GENERATIONAL STORAGE: IDS STAY VALID ACROSS REMOVALS OF OTHER ENTRIES */
+/* AND A REMOVED ID NEVER ALIASES THE ENTRY THAT LATER REUSES ITS SLOT */
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SlotId {
+    index: I,
+    generation: u32,
+}
+
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+pub struct Slots<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<I>,
+}
+
+impl<T> Default for Slots<T> {
+    fn default() -> Self { Self { slots: vec![], free: vec![] } }
+}
+
+impl<T> Slots<T> {
+    pub fn insert(&mut self, value: T) -> SlotId {
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.value = Some(value);
+                SlotId { index, generation: slot.generation }
+            }
+            None => {
+                self.slots.push(Slot { generation: 0, value: Some(value) });
+                SlotId { index: self.slots.len() - 1, generation: 0 }
+            }
+        }
+    }
+
+    /* BUMPING THE GENERATION INVALIDATES ALL OUTSTANDING IDS FOR THE SLOT */
+    pub fn remove(&mut self, id: SlotId) -> Option<T> {
+        let slot = self.slots.get_mut(id.index).filter(|s| s.generation == id.generation)?;
+        let value = slot.value.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(id.index);
+        Some(value)
+    }
+
+    pub fn get(&self, id: SlotId) -> Option<&T> {
+        self.slots.get(id.index).filter(|s| s.generation == id.generation)?.value.as_ref()
+    }
+
+    pub fn get_mut(&mut self, id: SlotId) -> Option<&mut T> {
+        self.slots.get_mut(id.index).filter(|s| s.generation == id.generation)?.value.as_mut()
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> { self.slots.iter().filter_map(|s| s.value.as_ref()) }
+
+    pub fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut T> {
+        self.slots.iter_mut().filter_map(|s| s.value.as_mut())
+    }
+
+    pub fn iter_with_ids(&self) -> impl Iterator<Item = (SlotId, &T)> {
+        self.slots.iter().enumerate().filter_map(|(index, s)| {
+            s.value.as_ref().map(|v| (SlotId { index, generation: s.generation }, v))
+        })
+    }
+
+    pub fn iter_mut_with_ids(&mut self) -> impl Iterator<Item = (SlotId, &mut T)> {
+        self.slots.iter_mut().enumerate().filter_map(|(index, s)| {
+            let generation = s.generation;
+            s.value.as_mut().map(|v| (SlotId { index, generation }, v))
+        })
+    }
+}
+
+impl<T> std::iter::FromIterator<T> for Slots<T> {
+    fn from_iter<It: IntoIterator<Item = T>>(iter: It) -> Self {
+        let slots = iter.into_iter().map(|value| Slot { generation: 0, value: Some(value) }).collect();
+        Self { slots, free: vec![] }
+    }
+}
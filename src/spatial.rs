@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use crate::{bvh::Aabb, F, I, P};
+
+/* UNIFORM GRID OVER A SET OF POINTS, THE BROAD PHASE OF EVERY PARTICLE PROXIMITY TEST */
+/* BUILT ONCE PER STEP AND SHARED BY SELF-COLLISION, COLLIDER CANDIDATES AND PARTICLE QUERIES */
+/* CELLS ARE VISITED IN THE ORDER THEY WERE FIRST FILLED, SO RESULTS DO NOT DEPEND ON HASHING */
+
+type Cell = (i32, i32, i32);
+
+// THE 13 NEIGHBORING CELLS AFTER A CELL IN LEXICOGRAPHIC ORDER, SO EACH ADJACENT PAIR IS VISITED ONCE
+const FORWARD: [Cell; 13] = [
+    (0, 0, 1),
+    (0, 1, -1),
+    (0, 1, 0),
+    (0, 1, 1),
+    (1, -1, -1),
+    (1, -1, 0),
+    (1, -1, 1),
+    (1, 0, -1),
+    (1, 0, 0),
+    (1, 0, 1),
+    (1, 1, -1),
+    (1, 1, 0),
+    (1, 1, 1),
+];
+
+#[derive(Default)]
+pub struct SpatialGrid {
+    cell_size: F,
+    cells: HashMap<Cell, Vec<I>>,
+    /* OCCUPIED CELLS IN THE ORDER THEY WERE FIRST FILLED */
+    order: Vec<Cell>,
+    len: I,
+}
+
+impl SpatialGrid {
+    fn cell(&self, p: &P) -> Cell {
+        let c = |x: F| (x / self.cell_size).floor() as i32;
+        (c(p.x), c(p.y), c(p.z))
+    }
+
+    /* REUSES THE BUCKET ALLOCATIONS FROM THE PREVIOUS BUILD */
+    pub fn build(&mut self, points: impl Iterator<Item = P>, cell_size: F) {
+        self.cell_size = cell_size;
+        self.cells.values_mut().for_each(Vec::clear);
+        self.order.clear();
+        self.len = 0;
+        for (i, p) in points.enumerate() {
+            let cell = self.cell(&p);
+            let bucket = self.cells.entry(cell).or_default();
+            if bucket.is_empty() {
+                self.order.push(cell);
+            }
+            bucket.push(i);
+            self.len = i + 1;
+        }
+    }
+
+    /* NUMBER OF POINTS IN THE LAST BUILD */
+    pub fn len(&self) -> I {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn cell_size(&self) -> F {
+        self.cell_size
+    }
+
+    /* CANDIDATES WITHIN radius OF p, WHICH STILL NEED AN EXACT DISTANCE CHECK */
+    pub fn neighbors_of(&self, p: &P, radius: F, f: &mut impl FnMut(I)) {
+        self.in_box(&Aabb::around(*p, radius), f);
+    }
+
+    /* CANDIDATES IN THE CELLS OVERLAPPING A BOX. A BOX SPANNING MORE CELLS THAN ARE OCCUPIED */
+    /* WALKS THE OCCUPIED CELLS INSTEAD OF THE EMPTY SPACE */
+    pub fn in_box(&self, aabb: &Aabb, f: &mut impl FnMut(I)) {
+        if self.cell_size <= 0. || self.len == 0 {
+            return;
+        }
+        let (lo, hi) = (self.cell(&aabb.min), self.cell(&aabb.max));
+        let span = |a: i32, b: i32| (b as i64 - a as i64 + 1).max(0) as u64;
+        let count = span(lo.0, hi.0).saturating_mul(span(lo.1, hi.1)).saturating_mul(span(lo.2, hi.2));
+        if count > self.order.len() as u64 {
+            let inside = |(x, y, z): Cell| {
+                (lo.0..=hi.0).contains(&x) && (lo.1..=hi.1).contains(&y) && (lo.2..=hi.2).contains(&z)
+            };
+            for &cell in self.order.iter().filter(|&&cell| inside(cell)) {
+                self.cells[&cell].iter().for_each(|&i| f(i));
+            }
+            return;
+        }
+        for x in lo.0..=hi.0 {
+            for y in lo.1..=hi.1 {
+                for z in lo.2..=hi.2 {
+                    if let Some(bucket) = self.cells.get(&(x, y, z)) {
+                        bucket.iter().for_each(|&i| f(i));
+                    }
+                }
+            }
+        }
+    }
+
+    /* EVERY UNORDERED PAIR OF POINTS IN THE SAME OR ADJACENT CELLS, EACH ONCE, WHICH COVERS ALL */
+    /* PAIRS CLOSER THAN THE CELL SIZE */
+    pub fn iter_cell_pairs(&self) -> impl Iterator<Item = (I, I)> + '_ {
+        self.order.iter().flat_map(move |&(x, y, z)| {
+            let bucket = &self.cells[&(x, y, z)];
+            let own = bucket.iter().enumerate().flat_map(move |(k, &i)| bucket[k + 1..].iter().map(move |&j| (i, j)));
+            let adjacent = FORWARD
+                .iter()
+                .filter_map(move |&(dx, dy, dz)| self.cells.get(&(x + dx, y + dy, z + dz)))
+                .flat_map(move |other| bucket.iter().flat_map(move |&i| other.iter().map(move |&j| (i, j))));
+            own.chain(adjacent)
+        })
+    }
+}
@@ -0,0 +1,434 @@
+use std::{collections::VecDeque, time::Duration};
+
+#[cfg(feature = "profiling")]
+use std::time::Instant;
+
+use bevy::{prelude::*, render::pipeline::PrimitiveTopology};
+
+use crate::{
+    bindings::Action,
+    camera::OrbitCamera,
+    cloth::{Cloth, ClothSim},
+    debug,
+    grid::GridIdx,
+    metrics,
+    pick::Hovered,
+    F, I,
+};
+
+// NUMBER OF FRAMES THE PHASE TIMINGS ARE AVERAGED OVER
+pub const WINDOW: I = 60;
+
+// SECONDS BETWEEN REFRESHES OF THE TIMING READOUT
+const READOUT_INTERVAL: f32 = 0.5;
+
+// FRAMES KEPT BY THE STATS HISTORY, THE WIDTH OF THE GRAPH
+pub const HISTORY: I = 300;
+
+// FILE THE HISTORY IS EXPORTED TO
+pub const HISTORY_PATH: &str = "stats_history.csv";
+
+// SIZE OF THE GRAPH AND ITS DISTANCE FROM THE BOTTOM LEFT CORNER OF THE WINDOW, IN PIXELS
+const GRAPH_WIDTH: f32 = 360.;
+const GRAPH_HEIGHT: f32 = 120.;
+const GRAPH_MARGIN: f32 = 16.;
+
+// DEPTH IN NORMALIZED DEVICE COORDINATES THE GRAPH IS DRAWN AT, JUST PAST THE NEAR PLANE SO IT IS IN FRONT
+const GRAPH_DEPTH: f32 = 1e-3;
+
+// COLOR OF EACH SERIES AND OF THE FRAME AROUND THEM
+const SERIES_COLORS: [(f32, f32, f32); SERIES] = [(0.9, 0.9, 0.9), (0.3, 0.9, 0.3), (1., 0.2, 0.2), (1., 0.85, 0.2)];
+const FRAME_COLOR: (f32, f32, f32) = (0.4, 0.4, 0.45);
+
+/* THE PARTS OF A FRAME THAT ARE TIMED SEPARATELY */
+#[derive(Clone, Copy, Debug)]
+pub enum Phase {
+    Constraints,
+    Integration,
+    BroadPhase,
+    Collision,
+    Mesh,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 5] =
+        [Phase::Constraints, Phase::Integration, Phase::BroadPhase, Phase::Collision, Phase::Mesh];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Constraints => "constraints",
+            Phase::Integration => "integration",
+            Phase::BroadPhase => "broadphase",
+            Phase::Collision => "collision",
+            Phase::Mesh => "mesh",
+        }
+    }
+}
+
+/* MEASURES THE TIME BETWEEN LAPS; A NO-OP WITHOUT THE profiling FEATURE */
+pub struct Stopwatch {
+    #[cfg(feature = "profiling")]
+    last: Instant,
+}
+
+impl Stopwatch {
+    pub fn start() -> Self {
+        Self {
+            #[cfg(feature = "profiling")]
+            last: Instant::now(),
+        }
+    }
+
+    /* TIME SINCE THE START OR THE PREVIOUS LAP */
+    pub fn lap(&mut self) -> Duration {
+        #[cfg(feature = "profiling")]
+        {
+            let now = Instant::now();
+            let elapsed = now - self.last;
+            self.last = now;
+            elapsed
+        }
+        #[cfg(not(feature = "profiling"))]
+        Duration::default()
+    }
+}
+
+/* MEASUREMENTS OF THE MOST RECENT SIMULATION STEP */
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+    /* CONSTRAINT ITERATIONS ACTUALLY USED, AFTER EARLY TERMINATION */
+    pub iterations: I,
+    /* TIMES THE CLOTH WAS ROLLED BACK AFTER GOING UNSTABLE, OVER ITS WHOLE LIFETIME */
+    pub recoveries: I,
+    /* HOW MUCH SHORTER THE STRUCTURAL SPRINGS ARE IN TOTAL THAN THEIR ORIGINAL REST LENGTHS, */
+    /* AS A FRACTION, NEGATIVE WHEN STRETCHED; REFRESHED EVERY FEW STEPS */
+    pub contraction: F,
+    /* FACTOR ALL REST LENGTHS ARE CURRENTLY SCALED BY TO COUNTER THE CONTRACTION */
+    pub rest_scale: F,
+    /* PARTICLES WHOSE SPEED OR CORRECTION HIT A SAFETY LIMIT IN THE LAST STEP, AND OVER THE LIFETIME */
+    pub clamped: I,
+    pub clamped_total: I,
+    /* TIMES THE BROAD PHASE WAS BUILT IN THE LAST STEP, SHARED BY ALL PROXIMITY TESTS SO ALWAYS 1 */
+    pub broad_phase_builds: I,
+    /* EDGE PAIRS THE EDGE-EDGE PASS TESTED IN THE LAST STEP, AT MOST config.edge_collision */
+    pub edge_pairs: I,
+    /* CONSTRAINTS THE LOCAL BOOST SWEPT AGAIN IN THE LAST STEP, EACH local_boost.iterations TIMES */
+    pub boosted: I,
+    /* DAMPING THE LAST STEP INTEGRATED WITH, config.damping UNLESS auto_damping TUNED IT */
+    pub damping: F,
+    /* WALL-CLOCK TIME OF THE LAST STEP, MEASURED WITH OR WITHOUT THE profiling FEATURE */
+    pub step_time: Duration,
+    /* PHASE TIMINGS OF THE FRAME IN PROGRESS AND OF THE LAST WINDOW FRAMES */
+    current: [Duration; 5],
+    history: VecDeque<[Duration; 5]>,
+}
+
+impl Stats {
+    pub fn record(&mut self, phase: Phase, time: Duration) {
+        self.current[phase as I] += time;
+    }
+
+    /* CLOSE THE FRAME IN PROGRESS, FORGETTING FRAMES OLDER THAN THE WINDOW */
+    pub fn end_frame(&mut self) {
+        if self.history.len() == WINDOW {
+            self.history.pop_front();
+        }
+        self.history.push_back(std::mem::take(&mut self.current));
+    }
+
+    /* ROLLING AVERAGE OF A PHASE OVER THE COMPLETED FRAMES IN THE WINDOW */
+    pub fn average(&self, phase: Phase) -> Duration {
+        let total: Duration = self.history.iter().map(|frame| frame[phase as I]).sum();
+        total / self.history.len().max(1) as u32
+    }
+
+    /* ONE LINE SUMMARY, e.g. "constraints 1.2ms | integration 0.3ms | ..." */
+    pub fn summary(&self) -> String {
+        Phase::ALL
+            .iter()
+            .map(|&phase| format!("{} {:.2?}", phase.name(), self.average(phase)))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
+
+/* SHOW THE ROLLING PHASE TIMINGS OF THE FIRST CLOTH IN THE WINDOW TITLE, AND THE PARTICLE UNDER THE */
+/* CURSOR A CLICK WOULD PIN AS SOON AS IT CHANGES */
+pub fn readout(
+    time: Res<Time>,
+    hovered: Res<Hovered>,
+    mut elapsed: Local<f32>,
+    mut last_hovered: Local<Option<GridIdx>>,
+    mut windows: ResMut<Windows>,
+    cloths: Query<&Cloth>,
+) {
+    *elapsed += time.delta_seconds;
+    if *elapsed < READOUT_INTERVAL && *last_hovered == hovered.0 {
+        return;
+    }
+    *elapsed = 0.;
+    *last_hovered = hovered.0;
+    let pick = hovered.0.map_or_else(String::new, |(x, y)| format!(" | pick ({}, {})", x, y));
+
+    if let (Some(cloth), Some(window)) = (cloths.iter().next(), windows.get_primary_mut()) {
+        let (iterations, builds) = (cloth.stats.iterations, cloth.stats.broad_phase_builds);
+        let size = cloth.aabb().max - cloth.aabb().min;
+        window.set_title(format!(
+            "cloth_sim | time x{} | damping {:.4} | {} iterations, {} boosted | broad phase built {}x | \
+             bounds {:.1}x{:.1}x{:.1} | {}{}",
+            cloth.config.time_scale,
+            cloth.stats.damping,
+            iterations,
+            cloth.stats.boosted,
+            builds,
+            size.x.max(0.),
+            size.y.max(0.),
+            size.z.max(0.),
+            cloth.stats.summary(),
+            pick
+        ));
+    }
+}
+
+// NUMBER OF SERIES IN THE STATS HISTORY
+pub const SERIES: I = 4;
+
+/* THE MEASUREMENTS THE STATS HISTORY KEEPS FOR EVERY FRAME */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Series {
+    StepTime,
+    KineticEnergy,
+    MaxStrain,
+    Contacts,
+}
+
+impl Series {
+    pub const ALL: [Series; SERIES] = [Series::StepTime, Series::KineticEnergy, Series::MaxStrain, Series::Contacts];
+
+    /* NAMED AS THE SAME COLUMN OF THE PER-STEP METRICS FILE */
+    pub fn column(self) -> &'static str {
+        match self {
+            Series::StepTime => "step_seconds",
+            Series::KineticEnergy => "kinetic_energy",
+            Series::MaxStrain => "max_strain",
+            Series::Contacts => "contacts",
+        }
+    }
+}
+
+/* THE LAST HISTORY FRAMES OF THE FIRST CLOTH, OLDEST OVERWRITTEN FIRST. THE RING IS ALLOCATED ONCE, */
+/* SO RECORDING NEVER ALLOCATES; L SHOWS IT AS A GRAPH, SHIFT + 1-4 PICK THE SERIES, SHIFT + L EXPORTS IT */
+pub struct StatsHistory {
+    samples: Vec<[F; SERIES]>,
+    /* SLOT THE NEXT FRAME GOES IN AND FRAMES HELD, AT MOST HISTORY */
+    next: I,
+    frames: I,
+    pub visible: bool,
+    pub shown: [bool; SERIES],
+}
+
+impl Default for StatsHistory {
+    fn default() -> Self {
+        Self { samples: vec![[0.; SERIES]; HISTORY], next: 0, frames: 0, visible: false, shown: [true; SERIES] }
+    }
+}
+
+impl StatsHistory {
+    pub fn push(&mut self, sample: [F; SERIES]) {
+        self.samples[self.next] = sample;
+        self.next = (self.next + 1) % HISTORY;
+        self.frames = (self.frames + 1).min(HISTORY);
+    }
+
+    /* RECORD THE STATE OF cloth AFTER ITS LAST STEP */
+    pub fn sample(&mut self, cloth: &ClothSim) {
+        self.push([
+            cloth.stats.step_time.as_secs_f64() as F,
+            cloth.kinetic_energy(),
+            cloth.max_strain(),
+            cloth.contacts() as F,
+        ]);
+    }
+
+    pub fn frames(&self) -> I {
+        self.frames
+    }
+
+    /* THE k-TH FRAME HELD, OLDEST FIRST */
+    pub fn frame(&self, k: I) -> [F; SERIES] {
+        self.samples[(self.next + HISTORY - self.frames + k) % HISTORY]
+    }
+
+    pub fn values(&self, series: Series) -> impl Iterator<Item = F> + '_ {
+        (0..self.frames).map(move |k| self.frame(k)[series as I])
+    }
+
+    /* LARGEST VALUE OF A SERIES HELD, WHICH THE GRAPH SCALES IT BY */
+    pub fn peak(&self, series: Series) -> F {
+        self.values(series).fold(0., F::max)
+    }
+}
+
+/* A LINE MESH OF THE GRAPH: ONE PER SERIES, AND None FOR THE FRAME AROUND THEM */
+pub struct GraphLines(Option<Series>);
+
+pub fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let lines = Series::ALL.iter().map(|&series| Some(series)).chain(std::iter::once(None));
+    for series in lines {
+        let (r, g, b) = series.map_or(FRAME_COLOR, |series| SERIES_COLORS[series as I]);
+        commands
+            .spawn(PbrComponents {
+                mesh: meshes.add(Mesh::new(PrimitiveTopology::LineList)),
+                material: materials.add(StandardMaterial {
+                    albedo: Color::rgb(r, g, b),
+                    shaded: false,
+                    ..Default::default()
+                }),
+                draw: Draw {
+                    is_visible: false,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with(GraphLines(series));
+    }
+}
+
+/* KEEP A FRAME OF THE FIRST CLOTH EVERY FRAME, WHETHER OR NOT THE GRAPH IS SHOWN */
+pub fn sample(mut history: ResMut<StatsHistory>, cloths: Query<&Cloth>) {
+    if let Some(cloth) = cloths.iter().next() {
+        history.sample(cloth);
+    }
+}
+
+pub fn controls(actions: Res<Input<Action>>, mut history: ResMut<StatsHistory>) {
+    if actions.just_pressed(Action::StatsGraph) {
+        history.visible = !history.visible;
+    }
+    for (s, shown) in history.shown.iter_mut().enumerate() {
+        if actions.just_pressed(Action::GraphSeries(s + 1)) {
+            *shown = !*shown;
+        }
+    }
+    if actions.just_pressed(Action::ExportStats) {
+        match metrics::write_history(HISTORY_PATH, &history) {
+            Ok(()) => info!("exported {} frames of stats to {}", history.frames(), HISTORY_PATH),
+            Err(err) => error!("failed to export stats to {}: {}", HISTORY_PATH, err),
+        }
+    }
+}
+
+/* DRAW THE SHOWN SERIES IN THE BOTTOM LEFT CORNER, EACH SCALED TO ITS OWN PEAK. THE LINES ARE PLACED IN */
+/* THE WORLD JUST IN FRONT OF THE ORBIT CAMERA, SO THIS RUNS AFTER THE CAMERA HAS MOVED FOR THE FRAME */
+pub fn graph(
+    history: Res<StatsHistory>,
+    windows: Res<Windows>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    cameras: Query<(&OrbitCamera, &Camera, &Transform)>,
+    mut lines: Query<(&GraphLines, &Handle<Mesh>, &mut Draw)>,
+) {
+    let view = cameras.iter().next().and_then(|(_, camera, transform)| {
+        let window = windows.get(camera.window)?;
+        let size = Vec2::new(window.width() as f32, window.height() as f32);
+        let unproject = transform.compute_matrix() * camera.projection_matrix.inverse();
+        Some((size, unproject)).filter(|_| size.x() > 0. && size.y() > 0.)
+    });
+    for (graph, handle, mut draw) in lines.iter_mut() {
+        let shown = graph.0.map_or(true, |series| history.shown[series as I]);
+        draw.is_visible = history.visible && shown && view.is_some();
+        let (size, unproject) = match view {
+            Some(view) if draw.is_visible => view,
+            _ => continue,
+        };
+        let at = |x: f32, y: f32| {
+            let pixel = Vec2::new(GRAPH_MARGIN + x * GRAPH_WIDTH, GRAPH_MARGIN + y * GRAPH_HEIGHT);
+            let ndc = pixel / size * 2. - Vec2::one();
+            let p = unproject * Vec4::new(ndc.x(), ndc.y(), GRAPH_DEPTH, 1.);
+            let p = p.truncate() / p.w();
+            [p.x(), p.y(), p.z()]
+        };
+
+        let positions = match graph.0 {
+            None => {
+                let corners = [at(0., 0.), at(1., 0.), at(1., 1.), at(0., 1.)];
+                (0..4).flat_map(|c| vec![corners[c], corners[(c + 1) % 4]]).collect()
+            }
+            Some(series) => {
+                let peak = history.peak(series);
+                let scale = if peak > 0. { 1. / peak } else { 0. };
+                let points: Vec<_> = history
+                    .values(series)
+                    .enumerate()
+                    .map(|(k, v)| at(k as f32 / (HISTORY - 1) as f32, (v * scale) as f32))
+                    .collect();
+                points.windows(2).flat_map(|pair| pair.to_vec()).collect()
+            }
+        };
+        if let Some(mesh) = meshes.get_mut(handle) {
+            debug::write_vertices(mesh, positions);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        cloth::{SIZE, SUBDIVISIONS},
+        config::{ClothConfig, PinPreset, Pose},
+        V,
+    };
+
+    /* A HANGING CLOTH IS SAMPLED EVERY STEP AND ITS BOTTOM ROW YANKED OUT OF THE SHEET ONCE. THE STRAIN SERIES */
+    /* SPIKES RIGHT AT THE YANK AND DECAYS AFTER IT, THE RING KEEPS ONLY THE LAST HISTORY FRAMES, AND THE EXPORT */
+    /* HOLDS ONE ROW PER FRAME UNDER THE COLUMNS OF THE METRICS FILE */
+    #[test]
+    fn strain_history_spikes_at_a_yank() {
+        // FRAMES BEFORE AND AFTER THE YANK, WHICH TOGETHER OVERFILL THE HISTORY, HOW FAR THE BOTTOM ROW IS YANKED,
+        // AND HOW MUCH THE STRAIN HAS TO SPIKE AND THEN DECAY
+        const BEFORE: I = 250;
+        const AFTER: I = 100;
+        const DISTANCE: F = 1.;
+        const SPIKE: F = 3.;
+        const DECAY: F = 0.25;
+
+        let config = ClothConfig { pose: Pose::HangingVertical, pin_preset: PinPreset::TopRow, ..Default::default() };
+        let mut cloth = ClothSim::new(SIZE.0, SIZE.1, (SUBDIVISIONS, SUBDIVISIONS), config).unwrap();
+        let mut history = StatsHistory::default();
+        for frame in 0..BEFORE + AFTER {
+            if frame == BEFORE {
+                let (n, m) = cloth.subdivisions();
+                for x in 0..n {
+                    let p = cloth.world_position((x, m - 1)) + V::z() * DISTANCE;
+                    cloth.place_particle((x, m - 1), p, V::zeros()).unwrap();
+                }
+            }
+            cloth.step(&[]);
+            history.sample(&cloth);
+        }
+        assert_eq!(history.frames(), HISTORY);
+
+        let strain: Vec<F> = history.values(Series::MaxStrain).collect();
+        let yank = BEFORE - (BEFORE + AFTER - HISTORY);
+        let before = strain[..yank].iter().cloned().fold(0., F::max);
+        let highest = |best: (I, F), (k, &s): (I, &F)| if s > best.1 { (k, s) } else { best };
+        let (peak_at, peak) = strain.iter().enumerate().fold((0, 0.), highest);
+        assert!((yank..yank + 3).contains(&peak_at), "peak at frame {} instead of {}", peak_at, yank);
+        assert!(peak >= SPIKE * before, "peak {} over {} before the yank", peak, before);
+        assert!(strain[strain.len() - 1] <= DECAY * peak, "the spike did not decay");
+
+        let path = std::env::temp_dir().join(HISTORY_PATH);
+        metrics::write_history(&path, &history).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        let columns: Vec<_> = Series::ALL.iter().map(|series| series.column()).collect();
+        assert_eq!(text.lines().next(), Some(format!("frame,{}", columns.join(",")).as_str()));
+        assert_eq!(text.lines().count(), HISTORY + 1);
+    }
+}
@@ -0,0 +1,185 @@
+use bevy::prelude::*;
+
+use crate::{
+    bindings::Action,
+    bvh::Aabb,
+    cloth::Cloth,
+    config::{ClothConfig, PinPreset, Pose},
+    grid::GridIdx,
+    material::ClothMaterials,
+    slots::{SlotId, Slots},
+    spawn_cloth, F, I, V,
+};
+
+/* SEWING: DISTANCE CONSTRAINTS BETWEEN PARTICLES OF DIFFERENT CLOTHS, FOR ASSEMBLING GARMENTS FROM PANELS */
+/* EACH CLOTH ONLY SOLVES ITS OWN CONSTRAINTS, SO STITCHES ARE SOLVED BY solve AFTER EVERY CLOTH HAS STEPPED */
+
+// STIFFNESS OF A STITCH ONCE FULLY TIGHTENED, AND THE SECONDS IT TAKES TO RAMP UP TO IT
+// SO THAT EDGES FAR APART ARE DRAWN TOGETHER OVER A FEW FRAMES INSTEAD OF SNAPPING
+const STIFFNESS: F = 1.;
+const RAMP_SECONDS: F = 1.;
+
+/* STABLE HANDLE TO A STITCH FOR REMOVING IT LATER */
+pub type StitchId = SlotId;
+
+pub struct Stitch {
+    pub a: (Entity, GridIdx),
+    pub b: (Entity, GridIdx),
+    pub rest: F,
+    age: F,
+}
+
+#[derive(Default)]
+pub struct Stitches {
+    stitches: Slots<Stitch>,
+}
+
+impl Stitches {
+    pub fn add(&mut self, a: (Entity, GridIdx), b: (Entity, GridIdx), rest: F) -> StitchId {
+        self.stitches.insert(Stitch { a, b, rest, age: 0. })
+    }
+
+    /* JOIN TWO EDGES PAIRWISE, THE SHORTER EDGE DECIDES HOW MANY STITCHES ARE MADE */
+    pub fn sew(&mut self, a: Entity, a_edge: &[GridIdx], b: Entity, b_edge: &[GridIdx], rest: F) -> Vec<StitchId> {
+        a_edge.iter().zip(b_edge).map(|(&i, &j)| self.add((a, i), (b, j), rest)).collect()
+    }
+
+    pub fn remove(&mut self, id: StitchId) -> Option<Stitch> {
+        self.stitches.remove(id)
+    }
+
+    /* REMOVE EVERY STITCH TOUCHING THE GIVEN CLOTH, RETURNING HOW MANY THERE WERE */
+    pub fn remove_cloth(&mut self, cloth: Entity) -> I {
+        let ids: Vec<_> = self
+            .stitches
+            .iter_with_ids()
+            .filter(|(_, s)| s.a.0 == cloth || s.b.0 == cloth)
+            .map(|(id, _)| id)
+            .collect();
+        for &id in &ids {
+            self.stitches.remove(id);
+        }
+        ids.len()
+    }
+
+    pub fn between(&self, a: Entity, b: Entity) -> I {
+        self.stitches.iter().filter(|s| (s.a.0, s.b.0) == (a, b) || (s.a.0, s.b.0) == (b, a)).count()
+    }
+}
+
+/* ONE PASS OVER ALL STITCHES, SPLITTING EACH CORRECTION BY INVERSE MASS LIKE THE IN-CLOTH CONSTRAINTS */
+/* POSITIONS ARE GATHERED FIRST SINCE TWO CLOTHS CANNOT BE BORROWED MUTABLY AT ONCE */
+/* THE TWO CLOTHS MAY LIVE IN DIFFERENT LOCAL SPACES, SO STITCHES ARE SOLVED IN THE WORLD */
+pub fn solve(time: Res<Time>, mut stitches: ResMut<Stitches>, mut cloths: Query<&mut Cloth>) {
+    let dt = time.delta_seconds as F;
+    let mut corrections: Vec<(Entity, I, V)> = vec![];
+    let mut dangling = vec![];
+    for (id, stitch) in stitches.stitches.iter_with_ids() {
+        let mut end = |(entity, idx): (Entity, GridIdx)| {
+            let cloth = cloths.get_mut(entity).ok()?;
+            let i = cloth.particles.flatten(idx);
+            Some((cloth.to_world(cloth.particles.data[i]), cloth.particles.inv_mass(i), i))
+        };
+        let ((p1, w1, i1), (p2, w2, i2)) = match (end(stitch.a), end(stitch.b)) {
+            (Some(a), Some(b)) => (a, b),
+            /* ONE OF THE CLOTHS WAS DESPAWNED */
+            _ => {
+                dangling.push(id);
+                continue;
+            }
+        };
+
+        let diff = p2 - p1;
+        let d = diff.norm();
+        if d < F::EPSILON || w1 + w2 == 0. {
+            continue;
+        }
+        let k = STIFFNESS * (stitch.age / RAMP_SECONDS).min(1.);
+        let delta = diff * ((d - stitch.rest) / d * k / (w1 + w2));
+        corrections.push((stitch.a.0, i1, delta * w1));
+        corrections.push((stitch.b.0, i2, -delta * w2));
+    }
+
+    for id in dangling {
+        stitches.remove(id);
+    }
+    stitches.stitches.iter_mut().for_each(|s| s.age += dt);
+    for (entity, i, delta) in corrections {
+        if let Ok(mut cloth) = cloths.get_mut(entity) {
+            let delta = cloth.vector_to_local(delta);
+            cloth.offset_particle(i, delta);
+        }
+    }
+}
+
+/* EXAMPLE: TWO CURTAINS HANGING SIDE BY SIDE, J SEWS THEIR FACING EDGES TOGETHER OR UNPICKS THEM */
+
+// WHERE THE PANELS HANG, AWAY FROM THE MAIN CLOTH, THEIR SIZE AND THE GAP BETWEEN THEM
+const PANEL_ORIGIN: [F; 3] = [-30., 0., 20.];
+const PANEL_SIZE: (F, F) = (6., 8.);
+const PANEL_GAP: F = 2.;
+const PANEL_SUBDIVISIONS: I = 16;
+
+#[derive(Clone, Copy)]
+pub enum Panel {
+    Left,
+    Right,
+}
+
+pub fn spawn_panels(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    config: &ClothConfig,
+    cloth_materials: &ClothMaterials,
+) -> Aabb {
+    let (width, height) = PANEL_SIZE;
+    let mut bounds = Aabb::empty();
+    for (k, panel) in [Panel::Left, Panel::Right].iter().enumerate() {
+        let config = ClothConfig {
+            pose: Pose::HangingVertical,
+            origin: V::from(PANEL_ORIGIN) + V::new(k as F * (width + PANEL_GAP), 0., 0.),
+            pin_preset: PinPreset::TopRow,
+            seed: config.seed + k as u64 + 1,
+            ..config.clone()
+        };
+        let cloth = match Cloth::new(width, height, (PANEL_SUBDIVISIONS, PANEL_SUBDIVISIONS), config) {
+            Ok(cloth) => cloth,
+            Err(err) => {
+                error!("failed to build a panel: {}", err);
+                continue;
+            }
+        };
+        bounds = bounds.merge(&cloth.bounding_box());
+        spawn_cloth(commands, meshes, cloth, cloth_materials.current());
+        commands.with(*panel);
+    }
+    bounds
+}
+
+/* THE RIGHT EDGE OF THE LEFT PANEL MEETS THE LEFT EDGE OF THE RIGHT PANEL */
+/* REST LENGTH IS THE CLOTH THICKNESS SO THE SEAM DOES NOT FIGHT THE CLOTH-CLOTH CONTACTS */
+pub fn toggle(actions: Res<Input<Action>>, mut stitches: ResMut<Stitches>, panels: Query<(Entity, &Cloth, &Panel)>) {
+    if !actions.just_pressed(Action::Stitch) {
+        return;
+    }
+    let (mut left, mut right) = (None, None);
+    for (entity, cloth, panel) in panels.iter() {
+        match panel {
+            Panel::Left => left = Some((entity, cloth)),
+            Panel::Right => right = Some((entity, cloth)),
+        }
+    }
+    let ((a, left), (b, right)) = match (left, right) {
+        (Some(l), Some(r)) => (l, r),
+        _ => return,
+    };
+
+    if stitches.between(a, b) > 0 {
+        stitches.remove_cloth(a);
+        return;
+    }
+    let ((w, h), (_, rh)) = (left.subdivisions(), right.subdivisions());
+    let a_edge: Vec<_> = (0..h).map(|y| (w - 1, y)).collect();
+    let b_edge: Vec<_> = (0..rh).map(|y| (0, y)).collect();
+    stitches.sew(a, &a_edge, b, &b_edge, left.config.thickness);
+}
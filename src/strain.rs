@@ -0,0 +1,92 @@
+use bevy::{prelude::*, render::pipeline::PrimitiveTopology};
+
+use crate::{bindings::Action, cloth::Cloth, debug, to_array, F, I};
+
+/* OVERLAY OF THE MOST STRAINED CONSTRAINTS, TO SEE WHERE THE SOLVER IS STRUGGLING */
+/* O TOGGLES IT; LINES GO FROM YELLOW TO RED WITH INCREASING STRAIN */
+
+// DEFAULT NUMBER OF LINES DRAWN AND STRAIN BELOW WHICH A CONSTRAINT IS NOT SHOWN
+pub const TOP_K: I = 64;
+pub const THRESHOLD: F = 0.02;
+
+// STRAIN AT WHICH A LINE IS DRAWN FULLY RED
+const RED_STRAIN: F = 0.3;
+
+// LINE COLORS FROM LEAST TO MOST STRAINED, ONE MESH EACH SINCE MATERIALS ARE PER MESH
+const COLORS: [(f32, f32, f32); 4] = [(1., 1., 0.2), (1., 0.7, 0.1), (1., 0.4, 0.1), (1., 0.05, 0.05)];
+
+pub struct StrainOverlay {
+    pub visible: bool,
+    pub top_k: I,
+    pub threshold: F,
+    meshes: Vec<Handle<Mesh>>,
+}
+
+impl StrainOverlay {
+    /* HIDDEN UNTIL TOGGLED, THE LINE MESHES ARE CREATED BY setup */
+    pub fn new(top_k: I, threshold: F) -> Self {
+        Self { visible: false, top_k, threshold, meshes: vec![] }
+    }
+}
+
+pub struct StrainLines;
+
+pub fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut overlay: ResMut<StrainOverlay>,
+) {
+    for &(r, g, b) in COLORS.iter() {
+        let mesh = meshes.add(Mesh::new(PrimitiveTopology::LineList));
+        commands
+            .spawn(PbrComponents {
+                mesh: mesh.clone(),
+                material: materials.add(StandardMaterial {
+                    albedo: Color::rgb(r, g, b),
+                    shaded: false,
+                    ..Default::default()
+                }),
+                draw: Draw {
+                    is_visible: false,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with(StrainLines);
+        overlay.meshes.push(mesh);
+    }
+}
+
+pub fn update(
+    actions: Res<Input<Action>>,
+    mut overlay: ResMut<StrainOverlay>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    cloths: Query<&Cloth>,
+    mut lines: Query<(&StrainLines, &mut Draw)>,
+) {
+    if actions.just_pressed(Action::StrainOverlay) {
+        overlay.visible = !overlay.visible;
+        for (_, mut draw) in lines.iter_mut() {
+            draw.is_visible = overlay.visible;
+        }
+    }
+    if !overlay.visible {
+        return;
+    }
+
+    let mut buckets = vec![vec![]; COLORS.len()];
+    for cloth in cloths.iter() {
+        for (strain, p1, p2) in cloth.most_strained(overlay.top_k, overlay.threshold) {
+            let t = ((strain - overlay.threshold) / (RED_STRAIN - overlay.threshold)).max(0.).min(1.);
+            let bucket = ((t * COLORS.len() as F) as usize).min(COLORS.len() - 1);
+            buckets[bucket].push(to_array(p1));
+            buckets[bucket].push(to_array(p2));
+        }
+    }
+    for (handle, positions) in overlay.meshes.iter().zip(buckets) {
+        if let Some(mesh) = meshes.get_mut(handle) {
+            debug::write_vertices(mesh, positions);
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+use crate::{F, I, P};
+
+/* ENCLOSED VOLUME OF A TRIANGULATED SURFACE, FOR PRESSURE ON CLOSED CLOTHS */
+
+/* SUM OF THE SIGNED TETRAHEDRA FROM THE ORIGIN TO EACH TRIANGLE */
+/* ONLY MEANINGFUL FOR A CLOSED SURFACE, POSITIVE WHEN THE TRIANGLES WIND OUTWARDS */
+pub fn signed_volume(particles: &[P], triangles: impl Iterator<Item = [I; 3]>) -> F {
+    triangles
+        .map(|[a, b, c]| particles[a].coords.dot(&particles[b].coords.cross(&particles[c].coords)))
+        .sum::<F>()
+        / 6.
+}
+
+fn has_edge(t: &[I; 3], a: I, b: I) -> bool {
+    (0..3).any(|k| t[k] == a && t[(k + 1) % 3] == b)
+}
+
+/* FLIP TRIANGLES SO THAT NEIGHBOURS TRAVERSE THEIR SHARED EDGE IN OPPOSITE DIRECTIONS */
+/* FLOOD-FILLS FROM THE FIRST TRIANGLE OF EACH CONNECTED PIECE, WHICH KEEPS ITS WINDING */
+/* RETURNS WHETHER THE SURFACE IS CLOSED, I.E. EVERY EDGE IS SHARED BY EXACTLY TWO TRIANGLES */
+pub fn orient(triangles: &mut [[I; 3]]) -> bool {
+    let mut edges: HashMap<(I, I), Vec<I>> = HashMap::new();
+    for (t, tri) in triangles.iter().enumerate() {
+        for k in 0..3 {
+            let (a, b) = (tri[k], tri[(k + 1) % 3]);
+            edges.entry((a.min(b), a.max(b))).or_default().push(t);
+        }
+    }
+
+    let mut visited = vec![false; triangles.len()];
+    let mut stack = vec![];
+    for start in 0..triangles.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        stack.push(start);
+        while let Some(t) = stack.pop() {
+            let tri = triangles[t];
+            for k in 0..3 {
+                let (a, b) = (tri[k], tri[(k + 1) % 3]);
+                for &u in &edges[&(a.min(b), a.max(b))] {
+                    if visited[u] {
+                        continue;
+                    }
+                    /* A CONSISTENT NEIGHBOUR GOES FROM b TO a */
+                    if has_edge(&triangles[u], a, b) {
+                        triangles[u].swap(1, 2);
+                    }
+                    visited[u] = true;
+                    stack.push(u);
+                }
+            }
+        }
+    }
+
+    !edges.is_empty() && edges.values().all(|ts| ts.len() == 2)
+}
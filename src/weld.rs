@@ -0,0 +1,284 @@
+use std::collections::BTreeMap;
+
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, VertexAttributeValues},
+        pipeline::PrimitiveTopology,
+    },
+};
+
+use crate::{bvh::Aabb, error::ClothError, spatial::SpatialGrid, F, I, P};
+
+/* CLOTHS BUILT FROM A TRIANGLE MESH INSTEAD OF A GRID. THE SIMULATION WANTS ONE PARTICLE PER POINT OF THE */
+/* SURFACE, BUT A MESH SPLITS A VERTEX WHEREVER ITS UVS JUMP, E.G. DOWN THE SEAM OF A CYLINDER, WHICH WOULD */
+/* TEAR THE CLOTH OPEN THERE. VERTICES AT THE SAME SPOT ARE WELDED INTO ONE PARTICLE, AND THE RENDER MESH */
+/* KEEPS THE ORIGINAL VERTICES WITH THEIR OWN UVS, EACH FOLLOWING THE PARTICLE IT WAS WELDED INTO */
+
+// VERTICES CLOSER THAN THIS FRACTION OF THE DIAGONAL OF THE MESH BOUNDS BECOME ONE PARTICLE
+const WELD_TOLERANCE: F = 1e-5;
+
+// SPACE LEFT AROUND AN ISLAND OF THE FALLBACK UNWRAP IN ITS CELL, AS A FRACTION OF THE CELL, SO TEXTURE
+// FILTERING DOES NOT BLEED FROM ONE ISLAND INTO THE NEXT
+const ISLAND_MARGIN: F = 0.02;
+
+#[derive(Clone)]
+pub struct Welded {
+    /* ONE PER PARTICLE, IN THE ORDER THEIR FIRST VERTEX COMES IN THE MESH */
+    pub positions: Vec<P>,
+    /* THE TRIANGLES OVER THE PARTICLES, WITHOUT THOSE THE WELD COLLAPSED */
+    pub faces: Vec<[I; 3]>,
+    /* THE PARTICLE EACH RENDER VERTEX FOLLOWS */
+    pub vertices: Vec<I>,
+    /* THE TRIANGLES OVER THE RENDER VERTICES, ONE PER FACE AND IN THE SAME ORDER */
+    pub triangles: Vec<[I; 3]>,
+    pub uvs: Vec<[F; 2]>,
+}
+
+impl Welded {
+    /* A MESH WITHOUT UVS GETS THE RENDER VERTICES AND UVS OF unwrap INSTEAD OF ITS OWN */
+    pub fn new(mesh: &Mesh) -> Result<Self, ClothError> {
+        let (vertices, triangles, uvs) = read(mesh)?;
+        let (vertices, positions) = weld(&vertices);
+        let face = |[a, b, c]: [I; 3]| [vertices[a], vertices[b], vertices[c]];
+        let triangles: Vec<_> = triangles
+            .into_iter()
+            .filter(|&t| {
+                let [a, b, c] = face(t);
+                a != b && b != c && c != a
+            })
+            .collect();
+        if triangles.is_empty() {
+            return Err(ClothError::UnsupportedMesh);
+        }
+        let faces: Vec<_> = triangles.iter().map(|&t| face(t)).collect();
+        Ok(match uvs {
+            Some(uvs) => Welded { positions, faces, vertices, triangles, uvs },
+            None => {
+                let (vertices, triangles, uvs) = unwrap(&positions, &faces);
+                Welded { positions, faces, vertices, triangles, uvs }
+            }
+        })
+    }
+
+    /* END POINTS OF THE STRUCTURAL SPRINGS, ONE ALONG EVERY EDGE, AND OF THE FLEXION SPRINGS, ONE BETWEEN */
+    /* THE CORNERS ACROSS EVERY EDGE TWO FACES SHARE. EACH PAIR ONCE, SMALLER INDEX FIRST, IN INDEX ORDER */
+    pub fn springs(&self) -> (Vec<(I, I)>, Vec<(I, I)>) {
+        let mut across: BTreeMap<(I, I), Vec<I>> = BTreeMap::new();
+        for &[a, b, c] in &self.faces {
+            for &(p, q, o) in &[(a, b, c), (b, c, a), (c, a, b)] {
+                across.entry((p.min(q), p.max(q))).or_default().push(o);
+            }
+        }
+        let mut flexion: Vec<_> = across
+            .values()
+            .filter_map(|corners| match corners[..] {
+                [o1, o2] if o1 != o2 => Some((o1.min(o2), o1.max(o2))),
+                _ => None,
+            })
+            .collect();
+        flexion.sort_unstable();
+        flexion.dedup();
+        (across.keys().copied().collect(), flexion)
+    }
+}
+
+/* POSITIONS, TRIANGLES AND THE UVS IF THERE ARE ANY OF A TRIANGLE LIST, INDEXED OR NOT */
+fn read(mesh: &Mesh) -> Result<(Vec<P>, Vec<[I; 3]>, Option<Vec<[F; 2]>>), ClothError> {
+    let positions: Vec<P> = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+        Some(VertexAttributeValues::Float3(ps)) => ps.iter().map(|&[x, y, z]| P::new(x as F, y as F, z as F)).collect(),
+        _ => return Err(ClothError::UnsupportedMesh),
+    };
+    let uvs = match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+        Some(VertexAttributeValues::Float2(uvs)) if uvs.len() == positions.len() => {
+            Some(uvs.iter().map(|&[u, v]| [u as F, v as F]).collect())
+        }
+        None => None,
+        _ => return Err(ClothError::UnsupportedMesh),
+    };
+    let indices: Vec<I> = match mesh.indices() {
+        Some(Indices::U16(indices)) => indices.iter().map(|&i| i as I).collect(),
+        Some(Indices::U32(indices)) => indices.iter().map(|&i| i as I).collect(),
+        None => (0..positions.len()).collect(),
+    };
+    let valid = mesh.primitive_topology() == PrimitiveTopology::TriangleList
+        && indices.len() % 3 == 0
+        && indices.iter().all(|&i| i < positions.len())
+        && positions.iter().all(|p| p.coords.iter().all(|c| c.is_finite()));
+    match valid {
+        true => Ok((positions, indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect(), uvs)),
+        false => Err(ClothError::UnsupportedMesh),
+    }
+}
+
+/* THE PARTICLE OF EACH VERTEX AND THE POSITIONS OF THE PARTICLES. A VERTEX JOINS THE PARTICLE OF THE FIRST */
+/* EARLIER VERTEX WITHIN THE TOLERANCE, SO THE RESULT DOES NOT DEPEND ON HOW THE BROAD PHASE HASHES */
+fn weld(positions: &[P]) -> (Vec<I>, Vec<P>) {
+    let bounds = Aabb::from_points(positions);
+    let tolerance = ((bounds.max - bounds.min).norm() * WELD_TOLERANCE).max(F::EPSILON);
+    let mut grid = SpatialGrid::default();
+    grid.build(positions.iter().copied(), tolerance);
+
+    let (mut particle_of, mut particles) = (Vec::with_capacity(positions.len()), vec![]);
+    for (v, p) in positions.iter().enumerate() {
+        let mut first: Option<I> = None;
+        grid.neighbors_of(p, tolerance, &mut |u| {
+            if u < v && (positions[u] - p).norm() <= tolerance {
+                first = Some(first.map_or(u, |f| f.min(u)));
+            }
+        });
+        let particle = match first {
+            Some(u) => particle_of[u],
+            None => {
+                particles.push(*p);
+                particles.len() - 1
+            }
+        };
+        particle_of.push(particle);
+    }
+    (particle_of, particles)
+}
+
+/* FALLBACK UVS FOR A MESH WITHOUT ANY, AS (PARTICLE OF EACH RENDER VERTEX, TRIANGLES OVER THEM, UVS) */
+/* EVERY FACE GOES TO THE AXIS ITS NORMAL POINTS ALONG THE MOST, THE TWO WAYS ALONG IT COUNTED APART, AND */
+/* FACES OF THE SAME AXIS SHARING AN EDGE FORM AN ISLAND THAT IS PROJECTED ALONG IT WITHOUT MIRRORING. */
+/* THE ISLANDS FILL THE CELLS OF A SQUARE AT ONE SCALE, SO THE TEXTURE IS EQUALLY DENSE ON ALL OF THEM. */
+/* A PARTICLE ON THE BORDER OF TWO ISLANDS GETS A RENDER VERTEX IN EACH */
+pub fn unwrap(positions: &[P], faces: &[[I; 3]]) -> (Vec<I>, Vec<[I; 3]>, Vec<[F; 2]>) {
+    /* 2 * AXIS, PLUS 1 FACING THE NEGATIVE WAY */
+    let sides: Vec<I> = faces
+        .iter()
+        .map(|&[a, b, c]| {
+            let n = (positions[b] - positions[a]).cross(&(positions[c] - positions[a]));
+            let k = n.iamax();
+            2 * k + (n[k] < 0.) as I
+        })
+        .collect();
+
+    let mut edges: BTreeMap<(I, I), Vec<I>> = BTreeMap::new();
+    for (f, &[a, b, c]) in faces.iter().enumerate() {
+        for &(p, q) in &[(a, b), (b, c), (c, a)] {
+            edges.entry((p.min(q), p.max(q))).or_default().push(f);
+        }
+    }
+    let (mut island, mut islands) = (vec![None; faces.len()], 0);
+    for start in 0..faces.len() {
+        if island[start].is_some() {
+            continue;
+        }
+        island[start] = Some(islands);
+        let mut stack = vec![start];
+        while let Some(f) = stack.pop() {
+            let [a, b, c] = faces[f];
+            for &(p, q) in &[(a, b), (b, c), (c, a)] {
+                for &g in &edges[&(p.min(q), p.max(q))] {
+                    if island[g].is_none() && sides[g] == sides[f] {
+                        island[g] = Some(islands);
+                        stack.push(g);
+                    }
+                }
+            }
+        }
+        islands += 1;
+    }
+    let island: Vec<I> = island.into_iter().flatten().collect();
+
+    /* ONE RENDER VERTEX PER ISLAND AND PARTICLE, WITH ITS POSITION IN THE PLANE OF THE ISLAND */
+    let mut render: BTreeMap<(I, I), I> = BTreeMap::new();
+    let (mut vertices, mut planar, mut triangles) = (vec![], vec![], vec![]);
+    for (f, face) in faces.iter().enumerate() {
+        let (k, negative) = (sides[f] / 2, sides[f] % 2 == 1);
+        let (u, v) = if negative { ((k + 2) % 3, (k + 1) % 3) } else { ((k + 1) % 3, (k + 2) % 3) };
+        let mut corner = |p: I| {
+            *render.entry((island[f], p)).or_insert_with(|| {
+                vertices.push(p);
+                planar.push((island[f], [positions[p][u], positions[p][v]]));
+                vertices.len() - 1
+            })
+        };
+        triangles.push([corner(face[0]), corner(face[1]), corner(face[2])]);
+    }
+
+    let mut bounds = vec![([F::MAX; 2], [F::MIN; 2]); islands];
+    for &(i, [x, y]) in &planar {
+        let (lo, hi) = &mut bounds[i];
+        *lo = [lo[0].min(x), lo[1].min(y)];
+        *hi = [hi[0].max(x), hi[1].max(y)];
+    }
+    let scale = bounds.iter().map(|(lo, hi)| (hi[0] - lo[0]).max(hi[1] - lo[1])).fold(F::EPSILON, F::max);
+    let cols = (islands as F).sqrt().ceil() as I;
+    let uvs = planar
+        .iter()
+        .map(|&(i, [x, y])| {
+            let (lo, _) = bounds[i];
+            let cell = |c: I, t: F| (c as F + ISLAND_MARGIN + t / scale * (1. - 2. * ISLAND_MARGIN)) / cols as F;
+            [cell(i % cols, x - lo[0]), cell(i / cols, y - lo[1])]
+        })
+        .collect();
+    (vertices, triangles, uvs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mesh(positions: Vec<[f32; 3]>, indices: Vec<u32>) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions.into());
+        mesh.set_indices(Some(Indices::U32(indices)));
+        mesh
+    }
+
+    /* A QUAD AS TWO TRIANGLES THAT SHARE NO VERTICES, ONE CORNER OFF BY LESS THAN THE TOLERANCE, WELDS INTO */
+    /* 4 PARTICLES WITH ONE STRUCTURAL SPRING PER EDGE AND ONE FLEXION SPRING ACROSS THE DIAGONAL. A CORNER */
+    /* FURTHER OFF STAYS A PARTICLE OF ITS OWN */
+    #[test]
+    fn coincident_vertices_weld() {
+        // OFFSET BELOW AND ABOVE THE TOLERANCE ON A QUAD WITH A DIAGONAL OF ABOUT 1.4
+        const CLOSE: f32 = 1e-7;
+        const FAR: f32 = 1e-3;
+
+        let quad = |offset: f32| {
+            let first = vec![[0., 0., 0.], [1., 0., 0.], [0., 1., 0.]];
+            let second = vec![[1., offset, 0.], [1., 1., 0.], [0., 1., 0.]];
+            mesh([first, second].concat(), vec![0, 1, 2, 3, 4, 5])
+        };
+        let welded = Welded::new(&quad(CLOSE)).unwrap();
+        assert_eq!(welded.positions.len(), 4);
+        assert_eq!(welded.vertices, vec![0, 1, 2, 1, 3, 2]);
+        let (structural, flexion) = welded.springs();
+        assert_eq!(structural, vec![(0, 1), (0, 2), (1, 2), (1, 3), (2, 3)]);
+        assert_eq!(flexion, vec![(0, 3)]);
+        assert_eq!(Welded::new(&quad(FAR)).unwrap().positions.len(), 5);
+    }
+
+    /* A CUBE WITHOUT UVS UNWRAPS INTO ONE ISLAND PER SIDE: EVERY CORNER IS DRAWN BY THE 3 SIDES IT IS ON, */
+    /* EVERY UV IS INSIDE THE TEXTURE AND NO TWO SIDES OVERLAP IN IT */
+    #[test]
+    fn cube_unwraps_per_side() {
+        let corners = (0..8).map(|i| [(i & 1) as f32, ((i >> 1) & 1) as f32, ((i >> 2) & 1) as f32]).collect();
+        let quads = [[0, 2, 3, 1], [4, 5, 7, 6], [0, 1, 5, 4], [2, 6, 7, 3], [0, 4, 6, 2], [1, 3, 7, 5]];
+        let indices = quads.iter().flat_map(|&[a, b, c, d]| vec![a, b, c, a, c, d]).collect();
+        let welded = Welded::new(&mesh(corners, indices)).unwrap();
+
+        assert_eq!(welded.positions.len(), 8);
+        assert_eq!(welded.vertices.len(), 24);
+        assert!(welded.uvs.iter().flatten().all(|&c| c > 0. && c < 1.));
+        let boxes: Vec<_> = welded
+            .triangles
+            .chunks(2)
+            .map(|side| {
+                let uvs = side.iter().flatten().map(|&i| welded.uvs[i]);
+                let lo = uvs.clone().fold([F::MAX; 2], |lo, [u, v]| [lo[0].min(u), lo[1].min(v)]);
+                let hi = uvs.fold([F::MIN; 2], |hi, [u, v]| [hi[0].max(u), hi[1].max(v)]);
+                (lo, hi)
+            })
+            .collect();
+        for (i, (lo, hi)) in boxes.iter().enumerate() {
+            assert!(hi[0] > lo[0] && hi[1] > lo[1]);
+            for (other_lo, other_hi) in &boxes[i + 1..] {
+                assert!(hi[0] <= other_lo[0] || other_hi[0] <= lo[0] || hi[1] <= other_lo[1] || other_hi[1] <= lo[1]);
+            }
+        }
+    }
+}
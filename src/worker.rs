@@ -0,0 +1,269 @@
+use std::{
+    sync::mpsc::{self, Receiver, Sender, SyncSender, TryRecvError},
+    thread,
+};
+
+use bevy::prelude::*;
+use nalgebra::Isometry3;
+
+use crate::{
+    cloth::{Attractor, Cloth, ClothSim, PaintedStiffness},
+    collider::{Colliders, LayeredCollider},
+    field::{ForceField, ForceFields},
+    grid::GridIdx,
+    pick::Attractors,
+    record::Recorder,
+    to_isometry, F, P, V,
+};
+
+/* ASYNCHRONOUS SIMULATION: A BACKGROUND THREAD OWNS A TWIN OF THE MAIN CLOTH AND STEPS IT AT ITS OWN */
+/* PACE, WHILE THE CLOTH IN THE ECS ONLY PRESENTS THE LATEST FINISHED STEP SO RENDERING NEVER WAITS */
+/* EVERYTHING THAT DRIVES THE SIMULATION IS SENT OVER AS A COMMAND AND APPLIED IN ORDER BEFORE THE */
+/* NEXT STEP, INTERACTION KEEPS WORKING ON THE PRESENTED CLOTH AND ITS CHANGES ARE FORWARDED */
+/* A DETERMINISTIC CLOTH RUNS IN LOCKSTEP INSTEAD: THE TWIN TAKES ONE STEP PER FRAME, ON THE INPUTS OF THAT */
+/* FRAME, AND THE NEXT FRAME WAITS FOR IT, SO HOW FAR IT GETS NEVER DEPENDS ON THREAD TIMING */
+
+enum Command {
+    Frame(Isometry3<F>),
+    Gravity(V),
+    Attractors(Vec<Attractor>),
+    Fields(Vec<ForceField>),
+    Colliders(Vec<LayeredCollider>),
+    /* THE FIXED PARTICLES OF THE PRESENTED CLOTH AND WHERE THEY ARE, MOVED KINEMATICALLY */
+    Pins(Vec<(GridIdx, P)>),
+    /* THE WEIGHT MAP OF THE PRESENTED CLOTH AFTER IT WAS PAINTED */
+    Weights(Vec<F>),
+    /* THE STIFFNESS PAINTED ONTO THE PRESENTED CLOTH */
+    Painted(PaintedStiffness),
+    /* THE PRESENTED CLOTH WAS RESET OR MOVED BY SOMETHING ELSE, E.G. A REPLAY, CONTINUE FROM IT AT REST */
+    /* THE SNAPSHOTS FROM THEN ON ARE TAGGED WITH THE NEW EPOCH, SO THE OLDER ONES STILL IN FLIGHT ARE DROPPED */
+    Restart { subdivisions: GridIdx, positions: Vec<P>, epoch: u64 },
+    Pause(bool),
+    /* IN LOCKSTEP, TAKE THE ONE STEP OF THIS FRAME */
+    Step,
+}
+
+/* THE RENDER SIDE OF THE WORKER, ON THE MAIN CLOTH ENTITY */
+pub struct SimWorker {
+    commands: Sender<Command>,
+    /* HOLDS ONE SNAPSHOT: THE SOLVER WAITS ONCE IT IS A FULL STEP AHEAD OF WHAT IS PRESENTED */
+    snapshots: Receiver<(u64, Vec<P>)>,
+    epoch: u64,
+    /* WHAT THE PRESENTED CLOTH WAS LAST LEFT AT, TO NOTICE ANYTHING ELSE MOVING IT */
+    presented: Vec<P>,
+    pins: Vec<(GridIdx, P)>,
+    weights: Vec<F>,
+    painted: PaintedStiffness,
+    paused: bool,
+    /* WHETHER THE TWIN RUNS IN LOCKSTEP, AND WHETHER THE STEP OF THE LAST FRAME IS STILL TO BE PRESENTED */
+    lockstep: bool,
+    pending: bool,
+}
+
+impl SimWorker {
+    /* THE TWIN HAS TO START OUT IDENTICAL TO THE PRESENTED CLOTH */
+    pub fn start(twin: ClothSim) -> Self {
+        let (commands, inbox) = mpsc::channel();
+        let (outbox, snapshots) = mpsc::sync_channel(1);
+        let (presented, weights) = (twin.particles.data.clone(), twin.weights().data.clone());
+        let (painted, lockstep) = (twin.painted_stiffness().clone(), twin.config.deterministic);
+        thread::spawn(move || run(twin, inbox, outbox));
+        Self {
+            commands,
+            snapshots,
+            epoch: 0,
+            presented,
+            pins: vec![],
+            weights,
+            painted,
+            paused: false,
+            lockstep,
+            pending: false,
+        }
+    }
+
+    fn send(&self, command: Command) {
+        /* THE THREAD ONLY STOPS ONCE THIS SIDE IS DROPPED, UNLESS IT PANICKED, WHICH IS ALREADY REPORTED */
+        let _ = self.commands.send(command);
+    }
+}
+
+fn run(mut cloth: ClothSim, inbox: Receiver<Command>, outbox: SyncSender<(u64, Vec<P>)>) {
+    let lockstep = cloth.config.deterministic;
+    let mut colliders = vec![];
+    let (mut paused, mut epoch) = (false, 0);
+    loop {
+        /* WHILE PAUSED, OR IN LOCKSTEP, THERE IS NOTHING TO DO BUT WAIT FOR THE NEXT COMMAND */
+        let next = match paused || lockstep {
+            true => inbox.recv().map_err(|_| TryRecvError::Disconnected),
+            false => inbox.try_recv(),
+        };
+        match next {
+            Ok(Command::Step) => {}
+            Ok(command) => {
+                match command {
+                    Command::Frame(frame) => cloth.set_frame(frame),
+                    Command::Gravity(gravity) => cloth.set_gravity(gravity),
+                    Command::Attractors(attractors) => cloth.set_attractors(&attractors),
+                    Command::Fields(fields) => cloth.set_force_fields(&fields),
+                    Command::Colliders(latest) => colliders = latest,
+                    Command::Pins(pins) => {
+                        let layout: Vec<_> = pins.iter().map(|&(idx, _)| idx).collect();
+                        if layout != cloth.pin_layout() {
+                            cloth.apply_pin_layout(&layout);
+                        }
+                        for (idx, p) in pins {
+                            if let Err(err) = cloth.set_position(idx, p) {
+                                warn!("worker failed to move a pin: {}", err);
+                            }
+                        }
+                    }
+                    Command::Weights(weights) => {
+                        if let Err(err) = cloth.set_weights(&weights) {
+                            warn!("worker failed to take the weight map: {}", err);
+                        }
+                    }
+                    Command::Painted(painted) => cloth.set_painted_stiffness(painted),
+                    Command::Restart { subdivisions, positions, epoch: restarted } => {
+                        epoch = restarted;
+                        let regenerated = match subdivisions != cloth.subdivisions() {
+                            true => cloth.regenerate(subdivisions.0, subdivisions.1),
+                            false => Ok(()),
+                        };
+                        if let Err(err) = regenerated.and_then(|_| cloth.set_positions(&positions)) {
+                            warn!("worker failed to restart: {}", err);
+                        }
+                        cloth.reset_velocities();
+                    }
+                    Command::Pause(pause) => paused = pause,
+                    Command::Step => {}
+                }
+                continue;
+            }
+            Err(TryRecvError::Disconnected) => return,
+            Err(TryRecvError::Empty) => {}
+        }
+        cloth.step(&colliders);
+        if outbox.send((epoch, cloth.particles.data.clone())).is_err() {
+            return;
+        }
+    }
+}
+
+/* SYNC THE INPUTS OF THE FRAME TO THE WORKER AND PRESENT ITS MOST RECENT STEP */
+pub fn exchange(
+    attractors: Res<Attractors>,
+    fields: Res<ForceFields>,
+    colliders: Res<Colliders>,
+    recorder: Res<Recorder>,
+    mut cloth: Mut<Cloth>,
+    mut worker: Mut<SimWorker>,
+    transform: &GlobalTransform,
+) {
+    /* A FROZEN CLOTH PAUSES THE WORKER TOO, OTHER TIME SCALES DO NOTHING AS IT STEPS AT ITS OWN PACE */
+    let paused = recorder.replaying || cloth.config.time_scale == 0.;
+    if paused != worker.paused {
+        worker.paused = paused;
+        worker.send(Command::Pause(worker.paused));
+    }
+    /* A REPLAY OWNS THE POSITIONS, WHATEVER IT OR A DRAG WHILE FROZEN LEAVES BEHIND IS PICKED UP BELOW */
+    if worker.paused {
+        return;
+    }
+
+    let n = cloth.particles.len();
+    let moved = worker.presented.len() != n
+        || (0..n).any(|i| !cloth.particles.fixed(i) && cloth.particles.data[i] != worker.presented[i]);
+    if moved {
+        let (subdivisions, positions) = (cloth.subdivisions(), cloth.particles.data.clone());
+        worker.epoch += 1;
+        worker.send(Command::Restart { subdivisions, positions: positions.clone(), epoch: worker.epoch });
+        worker.presented = positions;
+    }
+
+    worker.send(Command::Frame(to_isometry(transform.translation, transform.rotation)));
+    worker.send(Command::Gravity(cloth.config.gravity));
+    worker.send(Command::Attractors(attractors.0.clone()));
+    worker.send(Command::Fields(fields.0.clone()));
+    worker.send(Command::Colliders(colliders.0.clone()));
+    let w = cloth.particles.width();
+    let pins: Vec<_> = (0..cloth.particles.grid_len())
+        .filter(|&i| cloth.particles.fixed(i))
+        .map(|i| ((i % w, i / w), cloth.particles.data[i]))
+        .collect();
+    if pins != worker.pins {
+        worker.pins = pins.clone();
+        worker.send(Command::Pins(pins));
+    }
+    if cloth.weights().as_slice() != worker.weights.as_slice() {
+        worker.weights = cloth.weights().data.clone();
+        worker.send(Command::Weights(worker.weights.clone()));
+    }
+    if *cloth.painted_stiffness() != worker.painted {
+        worker.painted = cloth.painted_stiffness().clone();
+        worker.send(Command::Painted(worker.painted.clone()));
+    }
+
+    /* ONLY THE LATEST FINISHED STEP MATTERS, THE PINS STAY WHERE THE PRESENTED CLOTH HAS THEM. IN LOCKSTEP IT */
+    /* IS THE STEP OF THE LAST FRAME, WAITED FOR, AND THE STEP OF THIS ONE IS ASKED FOR ONCE ITS INPUTS ARE SENT */
+    let epoch = worker.epoch;
+    let latest = match worker.lockstep {
+        true if worker.pending => worker.snapshots.recv().ok().filter(|(e, _)| *e == epoch),
+        true => None,
+        false => worker.snapshots.try_iter().filter(|(e, _)| *e == epoch).last(),
+    };
+    if worker.lockstep {
+        worker.send(Command::Step);
+        worker.pending = true;
+    }
+    let snapshot = match latest {
+        Some((_, snapshot)) => snapshot,
+        None => return,
+    };
+    let positions: Vec<P> = (0..n)
+        .map(|i| match snapshot.get(i) {
+            Some(&p) if !cloth.particles.fixed(i) => p,
+            _ => cloth.particles.data[i],
+        })
+        .collect();
+    if let Err(err) = cloth.set_positions(&positions) {
+        error!("failed to present the worker's step: {}", err);
+    }
+    worker.presented = positions;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{config::ClothConfig, I};
+
+    /* IN LOCKSTEP THE TWIN TAKES EXACTLY ONE STEP PER Step, ON THE INPUTS SENT BEFORE IT, HOWEVER LATE ITS */
+    /* SNAPSHOTS ARE COLLECTED, SO IT ENDS BIT FOR BIT WHERE THE SAME STEPS TAKEN DIRECTLY DO */
+    #[test]
+    fn lockstep_matches_direct_steps() {
+        const STEPS: I = 60;
+
+        let build = || {
+            let config = ClothConfig { seed: 3, deterministic: true, ..ClothConfig::default() };
+            ClothSim::new(4., 4., (12, 12), config).unwrap()
+        };
+        let worker = SimWorker::start(build());
+        let mut direct = build();
+        let mut last = vec![];
+        for k in 0..STEPS {
+            let gravity = V::new(k as F * 0.1, -9.8, 0.);
+            worker.send(Command::Gravity(gravity));
+            worker.send(Command::Step);
+            direct.set_gravity(gravity);
+            direct.step(&[]);
+            if k % 7 == 0 {
+                thread::sleep(Duration::from_millis(5));
+            }
+            last = worker.snapshots.recv().unwrap().1;
+        }
+        let bits = |ps: &[P]| ps.iter().map(|p| [p.x.to_bits(), p.y.to_bits(), p.z.to_bits()]).collect::<Vec<_>>();
+        assert!(bits(&last) == bits(&direct.particles.data), "the worker ended apart from the direct steps");
+    }
+}